@@ -0,0 +1,239 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements `dy analyze` subcommands, which inspect items in a table to answer operational questions,
+// e.g. "are there items which have expired by TTL but still remain?"
+use std::collections::HashMap;
+
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, TimeToLiveStatus},
+    Client as DynamoDbSdkClient,
+};
+use futures::stream::{self, StreamExt};
+use log::{debug, error};
+
+use super::app;
+use super::data;
+use super::output;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Number of DeleteItem requests in flight on `dy analyze expired --delete`.
+const DELETE_CONCURRENCY: usize = 8;
+
+/// DynamoDB TTL doesn't delete items whose expiration time is more than five years in the past.
+/// https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/howitworks-ttl.html
+pub const TTL_MAX_AGE_SECONDS: i64 = 5 * 365 * 24 * 60 * 60;
+
+const TTL_NAME_REF: &str = "#DYNEIN_TTL";
+const NOW_VALUE_REF: &str = ":DYNEIN_NOW";
+
+pub struct ExpiredParams {
+    pub ttl_attr: Option<String>,
+    pub delete: bool,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Executed when you call `$ dy analyze expired`. Scans items whose TTL attribute is a number of epoch seconds in the past,
+/// i.e. items which have expired but DynamoDB hasn't deleted yet, as TTL deletes expired items in the background.
+/// With `delete`, found items are deleted only if they're still expired, so that items extended in the meantime are kept.
+pub async fn expired(cx: &app::Context, params: ExpiredParams) {
+    let ts: app::TableSchema = app::table_schema(cx).await;
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    let configured_ttl_attr = ddb
+        .describe_time_to_live()
+        .table_name(&ts.name)
+        .send()
+        .await
+        .unwrap_or_else(|e| {
+            debug!("DescribeTimeToLive API call got an error -- {:?}", e);
+            app::bye_with_sdk_error(1, e);
+        })
+        .time_to_live_description
+        .filter(|desc| desc.time_to_live_status == Some(TimeToLiveStatus::Enabled))
+        .and_then(|desc| desc.attribute_name);
+    let ttl_attr = match (params.ttl_attr, &configured_ttl_attr) {
+        (Some(attr), _) => attr,
+        (None, Some(attr)) => attr.to_owned(),
+        (None, None) => {
+            error!(
+                "TTL is not enabled on the table '{}'. Specify the attribute which stores expiration time with --ttl-attr.",
+                &ts.name
+            );
+            std::process::exit(1);
+        }
+    };
+    match &configured_ttl_attr {
+        Some(attr) if attr == &ttl_attr => (),
+        Some(attr) => eprintln!(
+            "WARN: TTL of the table '{}' is enabled on '{}', so DynamoDB doesn't delete items by '{}'.",
+            &ts.name, attr, &ttl_attr
+        ),
+        None => eprintln!(
+            "WARN: TTL is not enabled on the table '{}', so DynamoDB doesn't delete expired items.",
+            &ts.name
+        ),
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let items = scan_expired_items(&ddb, &ts, &ttl_attr, now).await;
+    let view = output::ItemsView {
+        ts: &ts,
+        attributes: &Some(ttl_attr.to_owned()),
+        keys_only: false,
+        expand: &[],
+    };
+    output::print_items(cx.output.as_deref(), "table", &items, &view);
+
+    eprintln!(
+        "{} items in the table '{}' have expired by '{}' but are not deleted yet.",
+        items.len(),
+        &ts.name,
+        &ttl_attr
+    );
+    let too_old = items
+        .iter()
+        .filter(|item| {
+            expiration_of(item, &ttl_attr).map_or(false, |t| t < now - TTL_MAX_AGE_SECONDS)
+        })
+        .count();
+    if too_old > 0 {
+        eprintln!(
+            "WARN: {} of them expired more than five years ago, which DynamoDB TTL never deletes.",
+            too_old
+        );
+    }
+
+    if params.delete {
+        let deleted = delete_expired_items(&ddb, &ts, &ttl_attr, now, &items).await;
+        println!(
+            "Deleted {} expired items from the table '{}'.",
+            deleted, &ts.name
+        );
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Scans all items whose TTL attribute is a number less than `now`. Items with a non-number TTL are ignored by DynamoDB TTL.
+async fn scan_expired_items(
+    ddb: &DynamoDbSdkClient,
+    ts: &app::TableSchema,
+    ttl_attr: &str,
+    now: i64,
+) -> Vec<HashMap<String, AttributeValue>> {
+    let mut items = vec![];
+    let mut last_evaluated_key = None;
+    loop {
+        let res = ddb
+            .scan()
+            .table_name(&ts.name)
+            .filter_expression(format!("{} < {}", TTL_NAME_REF, NOW_VALUE_REF))
+            .expression_attribute_names(TTL_NAME_REF, ttl_attr)
+            .expression_attribute_values(NOW_VALUE_REF, AttributeValue::N(now.to_string()))
+            .set_exclusive_start_key(last_evaluated_key)
+            .send()
+            .await
+            .unwrap_or_else(|e| {
+                debug!("Scan API call got an error -- {:?}", e);
+                app::bye_with_sdk_error(1, e);
+            });
+        items.extend(res.items.unwrap_or_default());
+        match res.last_evaluated_key {
+            None => return items,
+            lek => last_evaluated_key = lek,
+        }
+    }
+}
+
+/// Deletes the items if they're still expired, and returns the number of deleted items.
+async fn delete_expired_items(
+    ddb: &DynamoDbSdkClient,
+    ts: &app::TableSchema,
+    ttl_attr: &str,
+    now: i64,
+    items: &[HashMap<String, AttributeValue>],
+) -> usize {
+    let mut deleted = 0;
+    let mut results = stream::iter(items)
+        .map(|item| {
+            ddb.delete_item()
+                .table_name(&ts.name)
+                .set_key(Some(data::primary_key_of(ts, item)))
+                .condition_expression(format!("{} < {}", TTL_NAME_REF, NOW_VALUE_REF))
+                .expression_attribute_names(TTL_NAME_REF, ttl_attr)
+                .expression_attribute_values(NOW_VALUE_REF, AttributeValue::N(now.to_string()))
+                .send()
+        })
+        .buffer_unordered(DELETE_CONCURRENCY);
+    while let Some(result) = results.next().await {
+        match result {
+            Ok(_) => deleted += 1,
+            // The item has been deleted by TTL, or its expiration time has been extended, in the meantime.
+            Err(e)
+                if e.as_service_error()
+                    .map_or(false, |se| se.is_conditional_check_failed_exception()) => {}
+            Err(e) => {
+                debug!("DeleteItem API call got an error -- {:?}", e);
+                app::bye_with_sdk_error(1, e);
+            }
+        }
+    }
+    deleted
+}
+
+/// Returns the expiration time of the item in epoch seconds. Fractional seconds are truncated.
+fn expiration_of(item: &HashMap<String, AttributeValue>, ttl_attr: &str) -> Option<i64> {
+    match item.get(ttl_attr)? {
+        AttributeValue::N(n) => n.parse::<f64>().ok().map(|t| t as i64),
+        _ => None,
+    }
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiration_of() {
+        let item = HashMap::from([
+            (
+                String::from("exp"),
+                AttributeValue::N(String::from("1700000000.5")),
+            ),
+            (
+                String::from("str"),
+                AttributeValue::S(String::from("1700000000")),
+            ),
+        ]);
+        assert_eq!(expiration_of(&item, "exp"), Some(1700000000));
+        assert_eq!(expiration_of(&item, "str"), None);
+        assert_eq!(expiration_of(&item, "missing"), None);
+    }
+}
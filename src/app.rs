@@ -16,28 +16,39 @@
 
 use ::serde::{Deserialize, Serialize};
 use aws_config::{
-    meta::region::RegionProviderChain, retry::RetryConfig, BehaviorVersion, Region, SdkConfig,
+    meta::region::RegionProviderChain, retry::RetryConfig, timeout::TimeoutConfig, BehaviorVersion,
+    Region, SdkConfig,
 };
-use aws_sdk_dynamodb::types::{AttributeDefinition, TableDescription};
+use aws_sdk_dynamodb::{
+    config::Credentials,
+    types::{AttributeDefinition, TableDescription},
+};
+use aws_sdk_sts::Client as StsSdkClient;
 use aws_smithy_runtime_api::client::result::SdkError;
-use aws_smithy_types::error::metadata::ProvideErrorMetadata;
+use aws_smithy_types::error::{display::DisplayErrorContext, metadata::ProvideErrorMetadata};
+use aws_types::service_config::{LoadServiceConfig, ServiceConfigKey};
+use dialoguer::Confirm;
 use log::{debug, error, info};
 use serde_yaml::Error as SerdeYAMLError;
 use std::convert::{TryFrom, TryInto};
 use std::time::Duration;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env, error,
     fmt::{self, Formatter},
     fs,
-    io::Error as IOError,
+    io::{Error as IOError, IsTerminal, Write},
     path,
+    sync::Mutex,
 };
+use tabwriter::TabWriter;
 use tempfile::NamedTempFile;
 use thiserror::Error;
 
 use super::control;
 use super::ddb::{key, table};
+use super::network;
+use super::recorder;
 
 /* =================================================
 struct / enum / const
@@ -47,11 +58,62 @@ const CONFIG_DIR: &str = ".dynein";
 const CONFIG_PATH_ENV_VAR_NAME: &str = "DYNEIN_CONFIG_DIR";
 const CONFIG_FILE_NAME: &str = "config.yml";
 const CACHE_FILE_NAME: &str = "cache.yml";
+const HISTORY_FILE_NAME: &str = "history.jsonl";
 const LOCAL_REGION: &str = "local";
-
+const LOCAL_REGION_PREFIX: &str = "local:";
+const DEFAULT_PORT: u32 = 8000;
+/// Keys which can be changed by `dy config set` and shown by `dy config get`.
+const CONFIG_KEYS: [&str; 19] = [
+    "using_region",
+    "using_table",
+    "using_port",
+    "query.strict_mode",
+    "query.scan_warning_threshold",
+    "retry.default.max_attempts",
+    "retry.default.initial_backoff",
+    "retry.default.max_backoff",
+    "retry.batch_write_item.max_attempts",
+    "retry.batch_write_item.initial_backoff",
+    "retry.batch_write_item.max_backoff",
+    "timeout.connect_timeout",
+    "timeout.read_timeout",
+    "history.enabled",
+    "history.max_entries",
+    "safety.require_confirmation",
+    "display.ellipsis",
+    "display.redact",
+    "cache.schema_max_age",
+];
+/// Values to unset an optional key by `dy config set`.
+const UNSET_VALUES: [&str; 2] = ["~", "null"];
+const STRICT_MODE_ENV_VAR_NAME: &str = "DYNEIN_STRICT";
+const DEFAULT_SCAN_WARNING_THRESHOLD: u64 = 100_000;
+const DEFAULT_HISTORY_MAX_ENTRIES: usize = 1000;
+/// Appended to values truncated to fit in a column of table output.
+pub const DEFAULT_ELLIPSIS: &str = "...";
+/// Cached table schemas older than this are refreshed by DescribeTable before they're used.
+const DEFAULT_SCHEMA_MAX_AGE: Duration = Duration::from_secs(3600);
+/// Messages of ValidationException returned when primary keys in a request don't match the key schema of the table.
+const KEY_SCHEMA_MISMATCH_MESSAGES: [&str; 5] = [
+    "The provided key element does not match the schema",
+    "Missing the key",
+    "Type mismatch for key",
+    "Query condition missed key schema element",
+    "Condition parameter type does not match schema type",
+];
+/// Same as the default connect timeout of the SDK, which is kept when only read_timeout is configured.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_millis(3100);
+/// Table schemas cached by DescribeTable in this process, with Unix timestamps when they were cached.
+/// They take precedence over the cache loaded into the context, which isn't updated until the cache file is loaded again.
+static REFRESHED_SCHEMAS: Mutex<BTreeMap<String, (TableSchema, i64)>> = Mutex::new(BTreeMap::new());
+const DYNAMODB_ENDPOINT_URL_ENV_VAR_NAME: &str = "AWS_ENDPOINT_URL_DYNAMODB";
+const IGNORE_CONFIGURED_ENDPOINT_URLS_ENV_VAR_NAME: &str = "AWS_IGNORE_CONFIGURED_ENDPOINT_URLS";
+
+#[allow(clippy::enum_variant_names)]
 pub enum DyneinFileType {
     ConfigFile,
     CacheFile,
+    HistoryFile,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -62,6 +124,16 @@ pub struct TableSchema {
     pub sk: Option<key::Key>,
     pub indexes: Option<Vec<IndexSchema>>,
     pub mode: table::Mode,
+    /// TTL setting of the table, or None if it hasn't been looked up. DescribeTable doesn't return it,
+    /// so it's retrieved by DescribeTimeToLive only when needed, see `ttl_attribute`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<TtlSchema>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TtlSchema {
+    /// The attribute which stores expiration time of items, or None if TTL is not enabled.
+    pub attr: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -108,6 +180,129 @@ pub struct Config {
     pub query: QueryConfig,
     // pub cache_expiration_time: Option<i64>, // in second. default 300 (= 5 minutes)
     pub retry: Option<RetrySettingGlobal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<TimeoutSetting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<HistorySetting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safety: Option<SafetySetting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<DisplaySetting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheSetting>,
+    /// Default options per table name, applied when the options are not given on the command line.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub table_defaults: BTreeMap<String, TableDefaults>,
+    /// Named local endpoints, which you can select by `--region local:<name>`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub locals: BTreeMap<String, LocalEndpoint>,
+    /// Shell commands run before or after mutating commands.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<Hook>,
+    /// Entities of single-table designs keyed by their names, whose keys are built from templates by `dy entity`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub entities: BTreeMap<String, Entity>,
+}
+
+/// Default options for a table, defined in `table_defaults` of config.yml:
+/// ```yaml
+/// table_defaults:
+///   Movie:
+///     output: json
+///     consistent_read: true
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TableDefaults {
+    /// Default of --output option of scan, query and get.
+    pub output: Option<String>,
+    /// Default of --consistent-read option of scan, query and get.
+    pub consistent_read: Option<bool>,
+}
+
+/// A local endpoint defined in `locals` of config.yml, e.g. DynamoDB Local on another port or LocalStack.
+/// It can be written either as an URL (`lstack: http://localhost:4566`) or with a region used to sign requests:
+/// ```yaml
+/// lstack:
+///   endpoint: http://localhost:4566
+///   region: us-east-1
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum LocalEndpoint {
+    Url(String),
+    Detail {
+        endpoint: String,
+        region: Option<String>,
+    },
+}
+
+/// A shell command run before or after mutating commands, defined in `hooks` of config.yml:
+/// ```yaml
+/// hooks:
+///   - when: before
+///     commands: [put, del, bwrite, import]
+///     run: ./check-freeze-window.sh
+///   - when: after
+///     commands: [import]
+///     run: ./notify-slack.sh
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Hook {
+    pub when: HookTiming,
+    /// Commands the hook applies to, named as in `dy history`, e.g. "put" or "admin delete". All mutating commands if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<String>,
+    /// Shell command to run by `sh -c`, or `cmd /C` on Windows.
+    pub run: String,
+}
+
+/// An entity of a single-table design defined in `entities` of config.yml. Keys of its items are built from templates,
+/// whose `{placeholders}` are given as options of `dy entity`, e.g. `dy entity get user --id 42`:
+/// ```yaml
+/// entities:
+///   user:
+///     table: App
+///     pk: "USER#{id}"
+///     sk: PROFILE
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Entity {
+    /// Table of the entity. Defaults to the target table, i.e. --table or the one chosen by `dy use`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    /// Template of the partition key value.
+    pub pk: String,
+    /// Template of the sort key value, for tables with sort key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sk: Option<String>,
+}
+
+/// When a hook runs. A before hook which fails blocks the command, while an after hook runs only when the command succeeds.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HookTiming {
+    Before,
+    After,
+}
+
+impl LocalEndpoint {
+    fn endpoint(&self) -> &str {
+        match self {
+            LocalEndpoint::Url(endpoint) => endpoint,
+            LocalEndpoint::Detail { endpoint, .. } => endpoint,
+        }
+    }
+
+    /// Region to sign requests. Defaults to "local", the same as `--region local`.
+    fn signing_region(&self) -> &str {
+        match self {
+            LocalEndpoint::Detail {
+                region: Some(region),
+                ..
+            } => region,
+            _ => LOCAL_REGION,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -181,21 +376,99 @@ impl TryFrom<RetrySetting> for RetryConfig {
     }
 }
 
+/// Timeouts of API calls. The defaults of the SDK are used for missing values.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TimeoutSetting {
+    pub connect_timeout: Option<Duration>,
+    pub read_timeout: Option<Duration>,
+}
+
+/// History of mutating commands, which is recorded in history.jsonl only when enabled.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HistorySetting {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Older entries are removed when the history exceeds this number.
+    pub max_entries: Option<usize>,
+}
+
+/// Safety settings for operations against DynamoDB tables other than DynamoDB Local.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SafetySetting {
+    /// Operations which ask for confirmation before execution, unless `--yes` is given.
+    #[serde(default)]
+    pub require_confirmation: Vec<SafetyOperation>,
+}
+
+/// Settings of how items are rendered in output of read commands.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DisplaySetting {
+    /// Appended to values truncated to fit in a column of table output. Defaults to "...".
+    pub ellipsis: Option<String>,
+    /// Attributes whose values are hidden in output, e.g. `[password, ssn]`, unless `--no-redact` is given.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact: Vec<String>,
+}
+
+/// Settings of table schemas cached in cache.yml.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CacheSetting {
+    /// Cached table schemas older than this are refreshed before they're used. Defaults to 1 hour.
+    pub schema_max_age: Option<Duration>,
+}
+
+/// Destructive operations which can be listed in `safety.require_confirmation`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SafetyOperation {
+    /// `dy del`, and `dy bwrite` with delete requests.
+    Delete,
+    /// `dy import`.
+    Import,
+}
+
+impl SafetyOperation {
+    const ALL: [SafetyOperation; 2] = [SafetyOperation::Delete, SafetyOperation::Import];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SafetyOperation::Delete => "delete",
+            SafetyOperation::Import => "import",
+        }
+    }
+}
+
+impl From<&TimeoutSetting> for TimeoutConfig {
+    fn from(value: &TimeoutSetting) -> Self {
+        let mut builder = Self::builder()
+            .connect_timeout(value.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT));
+        if let Some(read_timeout) = value.read_timeout {
+            builder = builder.read_timeout(read_timeout);
+        }
+        builder.build()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct QueryConfig {
     #[serde(default)]
     pub strict_mode: bool,
+    /// `dy scan` asks for confirmation when it's estimated to read more items than this value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_warning_threshold: Option<u64>,
 }
 
 /// Cache is saved at `~/.dynein/cache.yml`
-/// Cache contains retrieved info of tables, and how fresh they are (cache_created_at).
-/// Currently Cache struct doesn't manage freshness of each table.
-/// i.e. Entire cache will be removed after cache_expiration_time in Config has passed.
+/// Cache contains retrieved info of tables, and how fresh they are (cached_at).
+/// A table schema older than `cache.schema_max_age` in Config is refreshed by DescribeTable before it's used.
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Cache {
     /// cached table schema information.
     /// table schemas are stored in keys to identify the target table "<Region>/<TableName>" -- e.g. "ap-northeast-1/Employee"
     pub tables: Option<HashMap<String, TableSchema>>,
+    /// Unix timestamps when the table schemas were cached, with the same keys as `tables`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub cached_at: HashMap<String, i64>,
     // pub cache_updated_at: String,
     // pub cache_created_at: String,
 }
@@ -206,6 +479,16 @@ pub struct Retry {
     pub batch_write_item: Option<RetryConfig>,
 }
 
+/// Service-specific configuration used for DynamoDB Local, which is configured only by dynein options.
+#[derive(Debug)]
+struct LocalServiceConfig;
+
+impl LoadServiceConfig for LocalServiceConfig {
+    fn load_config(&self, _: ServiceConfigKey<'_>) -> Option<String> {
+        None
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Context {
     pub config: Option<Config>,
@@ -214,8 +497,11 @@ pub struct Context {
     pub overwritten_table_name: Option<String>, // --table option
     pub overwritten_port: Option<u32>,      // --port option
     pub output: Option<String>,
-    pub should_strict_for_query: Option<bool>,
+    pub shell_output: Option<String>, // `output` builtin in the shell mode
+    pub should_strict: Option<bool>,
     pub retry: Option<Retry>,
+    pub ca_bundle: Option<String>, // --ca-bundle option
+    pub timeout: Option<TimeoutSetting>,
 }
 
 /*
@@ -236,18 +522,55 @@ impl Context {
             })?),
             None => None,
         };
-        Ok(Context {
+        let timeout = config.timeout.clone();
+        let cx = Context {
             config: Some(config),
             cache: Some(load_or_touch_cache_file(true)?),
             overwritten_region: region_from_str(region),
-            overwritten_table_name: table,
+            overwritten_table_name: None,
             overwritten_port: port,
             output: None,
-            should_strict_for_query: None,
+            shell_output: None,
+            should_strict: None,
             retry,
+            ca_bundle: None,
+            timeout,
+        };
+        Ok(match table {
+            Some(table) => cx.with_table(&table),
+            None => cx,
         })
     }
 
+    /// Applies --connect-timeout, --read-timeout and --max-retries options on top of config.yml.
+    pub fn apply_connection_options(
+        &mut self,
+        connect_timeout: Option<Duration>,
+        read_timeout: Option<Duration>,
+        max_retries: Option<u32>,
+    ) {
+        if connect_timeout.is_some() || read_timeout.is_some() {
+            let timeout = self.timeout.get_or_insert_with(Default::default);
+            timeout.connect_timeout = connect_timeout.or(timeout.connect_timeout);
+            timeout.read_timeout = read_timeout.or(timeout.read_timeout);
+        }
+        if let Some(max_retries) = max_retries {
+            let max_attempts = max_retries.saturating_add(1);
+            self.retry = Some(match self.retry.take() {
+                Some(retry) => Retry {
+                    default: retry.default.with_max_attempts(max_attempts),
+                    batch_write_item: retry
+                        .batch_write_item
+                        .map(|r| r.with_max_attempts(max_attempts)),
+                },
+                None => Retry {
+                    default: RetryConfig::standard().with_max_attempts(max_attempts),
+                    batch_write_item: None,
+                },
+            });
+        }
+    }
+
     pub async fn effective_sdk_config(&self) -> SdkConfig {
         let region = self.effective_region().await;
         let region_name = region.as_ref();
@@ -274,19 +597,96 @@ impl Context {
         region_name: &str,
         retry_config: Option<RetryConfig>,
     ) -> SdkConfig {
-        let sdk_region = Region::new(region_name.to_owned());
+        // Decide with the given region rather than is_local(), as the region may be overridden, e.g. by `dy list --all-regions`.
+        let local_target = self.local_target(region_name);
+        let is_local = local_target.is_some();
+        let sdk_region = match &local_target {
+            Some((_, signing_region)) => Region::new(signing_region.to_owned()),
+            None => Region::new(region_name.to_owned()),
+        };
 
         let provider = RegionProviderChain::first_try(sdk_region);
         let mut config = aws_config::defaults(BehaviorVersion::v2024_03_28()).region(provider);
-        if self.is_local().await {
-            config = config.endpoint_url(format!("http://localhost:{}", self.effective_port()));
+        if let Some((endpoint, _)) = local_target {
+            config = config.endpoint_url(endpoint);
         }
 
-        if let Some(retry_config) = retry_config {
+        // The retry config given by the caller, e.g. for BatchWriteItem, takes precedence over the default one.
+        if let Some(retry_config) =
+            retry_config.or_else(|| self.retry.as_ref().map(|r| r.default.clone()))
+        {
             config = config.retry_config(retry_config);
         }
+        if let Some(timeout) = &self.timeout {
+            config = config.timeout_config(timeout.into());
+        }
+
+        if let Some(http_client) = network::http_client(self.ca_bundle.as_deref()) {
+            config = config.http_client(http_client);
+        }
 
-        config.load().await
+        // DYNEIN_RECORD/DYNEIN_REPLAY wrap or replace the HTTP client, e.g. for integration tests.
+        let sdk_config = match recorder::Mode::from_env() {
+            None => config.load().await,
+            Some(recorder::Mode::Replay(dir)) => {
+                // Replayed requests are never sent, so dummy credentials are enough to sign them.
+                config
+                    .http_client(recorder::ReplayingClient::new(dir))
+                    .credentials_provider(Credentials::new(
+                        "replay",
+                        "replay",
+                        None,
+                        None,
+                        "dynein-replay",
+                    ))
+                    .load()
+                    .await
+            }
+            Some(recorder::Mode::Record(dir)) => {
+                let sdk_config = config.load().await;
+                let inner = sdk_config
+                    .http_client()
+                    .expect("default HTTP client should be configured");
+                sdk_config
+                    .to_builder()
+                    .http_client(recorder::RecordingClient::new(dir, inner))
+                    .build()
+            }
+        };
+
+        // Other than the endpoint of DynamoDB Local, configuration is resolved by the standard provider chain of the SDK,
+        // e.g. AWS_ENDPOINT_URL_DYNAMODB or `services` section of a profile.
+        // As these service-specific settings take precedence over the endpoint given above, drop them for `--region local`.
+        if is_local {
+            sdk_config
+                .to_builder()
+                .service_config(LocalServiceConfig)
+                .build()
+        } else {
+            sdk_config
+        }
+    }
+
+    /// Returns the endpoint URL of DynamoDB if it's not the default one of the region,
+    /// i.e. DynamoDB Local or an endpoint given by AWS_ENDPOINT_URL_DYNAMODB, AWS_ENDPOINT_URL or a profile.
+    pub async fn effective_endpoint_url(&self) -> Option<String> {
+        if let Some((endpoint, _)) = self.local_target(self.effective_region().await.as_ref()) {
+            return Some(endpoint);
+        }
+        if env::var(IGNORE_CONFIGURED_ENDPOINT_URLS_ENV_VAR_NAME)
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+        {
+            return None;
+        }
+        if let Ok(url) = env::var(DYNAMODB_ENDPOINT_URL_ENV_VAR_NAME) {
+            return Some(url);
+        }
+        // AWS_ENDPOINT_URL and `endpoint_url` in the profile are loaded into SdkConfig by the SDK.
+        self.effective_sdk_config()
+            .await
+            .endpoint_url()
+            .map(String::from)
     }
 
     pub async fn effective_region(&self) -> Region {
@@ -314,19 +714,41 @@ impl Context {
     }
 
     pub fn effective_table_name(&self) -> String {
+        self.table_name_if_specified().unwrap_or_else(|| {
+            // if both --option nor config file are not available, raise error and exit the command.
+            error!("{}", Messages::NoEffectiveTable);
+            std::process::exit(1)
+        })
+    }
+
+    /// Same as effective_table_name, but returns None instead of exiting when no table is specified.
+    pub fn table_name_if_specified(&self) -> Option<String> {
         // if table is overwritten by --table option, use it.
         if let Some(ow_table_name) = &self.overwritten_table_name {
-            return ow_table_name.to_owned();
+            return Some(ow_table_name.to_owned());
         };
         // otherwise, retrieve an `using_table` from config file.
-        self.to_owned()
-            .config
-            .and_then(|x| x.using_table)
-            .unwrap_or_else(|| {
-                // if both --option nor config file are not available, raise error and exit the command.
-                error!("{}", Messages::NoEffectiveTable);
-                std::process::exit(1)
-            })
+        self.config.as_ref().and_then(|x| x.using_table.to_owned())
+    }
+
+    /// Returns hooks defined in config.yml, which run before or after mutating commands.
+    pub fn configured_hooks(&self) -> &[Hook] {
+        self.config.as_ref().map_or(&[], |c| c.hooks.as_slice())
+    }
+
+    /// Returns entities defined in config.yml, keyed by their names.
+    pub fn configured_entities(&self) -> Option<&BTreeMap<String, Entity>> {
+        self.config.as_ref().map(|c| &c.entities)
+    }
+
+    /// Returns the maximum number of history entries if recording history of mutating commands is enabled.
+    pub fn history_max_entries(&self) -> Option<usize> {
+        let history = self.config.as_ref()?.history.as_ref()?;
+        if history.enabled {
+            Some(history.max_entries.unwrap_or(DEFAULT_HISTORY_MAX_ENTRIES))
+        } else {
+            None
+        }
     }
 
     pub fn effective_port(&self) -> u32 {
@@ -338,7 +760,7 @@ impl Context {
             return using_port_in_config.to_owned();
         };
 
-        8000
+        DEFAULT_PORT
     }
 
     pub async fn effective_cache_key(&self) -> String {
@@ -374,26 +796,167 @@ impl Context {
         self
     }
 
+    /// Overwrites the target table. A table ARN, e.g. `arn:aws:dynamodb:eu-west-1:123456789012:table/Foo`, is accepted as well.
     pub fn with_table(mut self, table: &str) -> Self {
-        self.overwritten_table_name = Some(table.to_owned());
+        self.overwritten_table_name = Some(self.resolve_table_arn(table));
         self
     }
 
-    pub fn should_strict_for_query(&self) -> bool {
-        self.should_strict_for_query
+    /// Returns the table name in a table ARN, overwriting the region with the one in the ARN, which takes precedence over --region.
+    /// Other table names are returned as they are.
+    fn resolve_table_arn(&mut self, table: &str) -> String {
+        let (region, table_name) = match parse_table_arn(table) {
+            Some(parsed) => parsed,
+            None => return table.to_owned(),
+        };
+        if let Some(ow_region) = self
+            .overwritten_region
+            .as_ref()
+            .filter(|r| r.as_ref() != region)
+        {
+            eprintln!(
+                "WARN: The table ARN is in the region '{}', which is used instead of '{}'.",
+                region,
+                ow_region.as_ref()
+            );
+        }
+        self.overwritten_region = Some(Region::new(region.to_owned()));
+        table_name.to_owned()
+    }
+
+    /// Whether dynein parses sort key conditions and item literals in the strict mode.
+    /// The priority is: --strict/--non-strict option > DYNEIN_STRICT env var > `query.strict_mode` in config.yml.
+    pub fn should_strict(&self) -> bool {
+        self.should_strict
+            .or_else(strict_mode_from_env)
             .unwrap_or_else(|| self.config.as_ref().map_or(false, |c| c.query.strict_mode))
     }
 
+    /// Returns the given --output option, the format set by `output` builtin in the shell mode, or the default output format of the target table in config.yml.
+    pub fn output_or_table_default(&self, output: Option<String>) -> Option<String> {
+        output
+            .or_else(|| self.shell_output.to_owned())
+            .or_else(|| self.table_defaults()?.output.to_owned())
+    }
+
+    /// Returns true if --consistent-read option is given or it's enabled for the target table in config.yml.
+    pub fn consistent_read_or_table_default(&self, consistent_read: bool) -> bool {
+        consistent_read
+            || self
+                .table_defaults()
+                .and_then(|d| d.consistent_read)
+                .unwrap_or(false)
+    }
+
+    fn table_defaults(&self) -> Option<&TableDefaults> {
+        let table_name = self.table_name_if_specified()?;
+        self.config.as_ref()?.table_defaults.get(&table_name)
+    }
+
+    /// The number of items to read above which `dy scan` warns before reading a table.
+    /// It's configured by `query.scan_warning_threshold` in config.yml.
+    pub fn scan_warning_threshold(&self) -> u64 {
+        self.config
+            .as_ref()
+            .and_then(|c| c.query.scan_warning_threshold)
+            .unwrap_or(DEFAULT_SCAN_WARNING_THRESHOLD)
+    }
+
+    /// Returns the ellipsis which is appended to truncated values in table output, if it's configured.
+    pub fn configured_ellipsis(&self) -> Option<String> {
+        self.config.as_ref()?.display.as_ref()?.ellipsis.clone()
+    }
+
+    /// Returns names of attributes whose values are hidden in output, if any is configured by `display.redact`.
+    pub fn configured_redaction(&self) -> Option<Vec<String>> {
+        let redact = &self.config.as_ref()?.display.as_ref()?.redact;
+        (!redact.is_empty()).then(|| redact.clone())
+    }
+
+    /// Returns how long a cached table schema is used before it's refreshed, i.e. `cache.schema_max_age`.
+    pub fn schema_max_age(&self) -> Duration {
+        self.config
+            .as_ref()
+            .and_then(|c| c.cache.as_ref())
+            .and_then(|c| c.schema_max_age)
+            .unwrap_or(DEFAULT_SCHEMA_MAX_AGE)
+    }
+
+    /// Returns true if the operation should be confirmed, i.e. it's listed in `safety.require_confirmation`
+    /// and the target is not DynamoDB Local.
+    pub async fn requires_confirmation(&self, operation: SafetyOperation) -> bool {
+        let required = self
+            .config
+            .as_ref()
+            .and_then(|c| c.safety.as_ref())
+            .map_or(false, |s| s.require_confirmation.contains(&operation));
+        required && !self.is_local().await
+    }
+
     pub async fn is_local(&self) -> bool {
-        let region = self.effective_region();
-        region.await.as_ref() == LOCAL_REGION
+        let region = self.effective_region().await;
+        is_local_region(region.as_ref())
+    }
+
+    /// Returns the endpoint URL and the region to sign requests if the region is "local" or "local:<name>".
+    /// Exits when the named local endpoint isn't defined in config.yml.
+    fn local_target(&self, region_name: &str) -> Option<(String, String)> {
+        if region_name == LOCAL_REGION {
+            return Some((
+                local_endpoint_url(self.effective_port()),
+                String::from(LOCAL_REGION),
+            ));
+        }
+        let name = region_name.strip_prefix(LOCAL_REGION_PREFIX)?;
+        match self.config.as_ref().and_then(|c| c.locals.get(name)) {
+            Some(local) => Some((
+                local.endpoint().to_owned(),
+                local.signing_region().to_owned(),
+            )),
+            None => {
+                error!(
+                    "No local endpoint named '{}' is defined in `locals` of config.yml.",
+                    name
+                );
+                std::process::exit(1);
+            }
+        }
     }
 }
 
 #[derive(Error, Debug)]
 pub enum DyneinConfigContentError {
-    #[error("retry config error ")]
+    #[error("retry config error: {0}")]
     RetryConfig(#[from] RetryConfigError),
+    #[error("unknown config key '{0}'. Available keys are: {keys}", keys = CONFIG_KEYS.join(", "))]
+    UnknownKey(String),
+    #[error("invalid value '{value}' for {key}: {reason}")]
+    InvalidValue {
+        key: String,
+        value: String,
+        reason: String,
+    },
+}
+
+/// Where an effective configuration value comes from, shown by `dy config get`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConfigSource {
+    Default,
+    Config,
+    Env,
+    Flag,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let s = match self {
+            ConfigSource::Default => "default",
+            ConfigSource::Config => "config",
+            ConfigSource::Env => "env",
+            ConfigSource::Flag => "flag",
+        };
+        write!(f, "{}", s)
+    }
 }
 
 // FYI: https://doc.rust-lang.org/rust-by-example/error/multiple_error_types/wrap_error.html
@@ -455,6 +1018,23 @@ pub fn region_from_str(s: Option<String>) -> Option<Region> {
     }
 }
 
+/// Splits a table ARN, e.g. `arn:aws:dynamodb:eu-west-1:123456789012:table/Foo`, into the region and the table name.
+/// Returns None for anything else, including ARNs of indexes and streams, so that it's taken as a table name.
+pub fn parse_table_arn(arn: &str) -> Option<(&str, &str)> {
+    let parts: Vec<&str> = arn.splitn(6, ':').collect();
+    match parts[..] {
+        ["arn", _partition, "dynamodb", region, _account, resource] if !region.is_empty() => {
+            match resource.strip_prefix("table/") {
+                Some(table_name) if !table_name.is_empty() && !table_name.contains('/') => {
+                    Some((region, table_name))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 /// Loads dynein config file (YAML format) and return config struct as a result.
 /// Creates the file with default if the file couldn't be found.
 pub fn load_or_touch_config_file(first_try: bool) -> Result<Config, DyneinConfigError> {
@@ -526,14 +1106,16 @@ pub async fn use_table(
 ) -> Result<(), DyneinConfigError> {
     // When context has "overwritten_table_name". i.e. you passed --table (-t) option.
     // When you didn't pass --table option, check if you specified target table name directly, instead of --table option.
-    let target_table: Option<&String> = cx
-        .overwritten_table_name
-        .as_ref()
-        .or(positional_arg_table_name.as_ref());
+    // The positional table name can be a table ARN as well as --table option.
+    let target_table: Option<String> = match (&cx.overwritten_table_name, positional_arg_table_name)
+    {
+        (Some(tbl), _) => Some(tbl.to_owned()),
+        (None, Some(tbl)) => Some(cx.resolve_table_arn(&tbl)),
+        (None, None) => None,
+    };
     match target_table {
         Some(tbl) => {
             debug!("describing the table: {}", tbl);
-            let tbl = tbl.clone();
             let desc: TableDescription = control::describe_table_api(cx, tbl.clone()).await;
             save_using_target(cx, desc).await?;
             println!("Now you're using the table '{}' ({}).", tbl, cx.effective_region().await.as_ref());
@@ -544,6 +1126,70 @@ pub async fn use_table(
     Ok(())
 }
 
+/// Executed when you call `$ dy whoami`. Shows the identity, region, endpoint, table and config files that dynein resolves,
+/// so that you can find out why a command targets an unexpected account or region.
+/// Exits with non-zero status if the identity can't be resolved, e.g. due to missing or expired credentials.
+pub async fn whoami(cx: &Context) -> Result<(), DyneinConfigError> {
+    let region = cx.effective_region().await;
+    let config = cx.effective_sdk_config().await;
+
+    let mut failed = false;
+    let (identity, account) = if cx.is_local().await {
+        (
+            String::from("- (not checked for DynamoDB Local)"),
+            String::from("-"),
+        )
+    } else {
+        match StsSdkClient::new(&config)
+            .get_caller_identity()
+            .send()
+            .await
+        {
+            Ok(res) => {
+                debug!("GetCallerIdentity API call got a response -- {:#?}", res);
+                (res.arn.unwrap_or_default(), res.account.unwrap_or_default())
+            }
+            Err(e) => {
+                debug!("GetCallerIdentity API call got an error -- {:#?}", e);
+                failed = true;
+                (
+                    format!("ERROR: {}", DisplayErrorContext(&e)),
+                    String::from("-"),
+                )
+            }
+        }
+    };
+    let endpoint = cx.effective_endpoint_url().await.unwrap_or_else(|| {
+        format!(
+            "https://dynamodb.{}.amazonaws.com (default)",
+            region.as_ref()
+        )
+    });
+
+    println!("identity: {}", identity);
+    println!("account: {}", account);
+    println!("region: {}", region.as_ref());
+    println!("endpoint: {}", endpoint);
+    println!(
+        "table: {}",
+        cx.table_name_if_specified()
+            .unwrap_or_else(|| String::from("- (not specified)"))
+    );
+    println!(
+        "config: {}",
+        retrieve_dynein_file_path(DyneinFileType::ConfigFile)?
+    );
+    println!(
+        "cache: {}",
+        retrieve_dynein_file_path(DyneinFileType::CacheFile)?
+    );
+
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 /// Inserts specified table description into cache file.
 pub async fn insert_to_table_cache(
     cx: &Context,
@@ -560,28 +1206,41 @@ pub async fn insert_to_table_cache(
         table_name
     );
 
-    // retrieve current cache from Context and update target table desc.
-    // key to save the table desc is "<RegionName>/<TableName>" -- e.g. "us-west-2/app_data"
-    let mut cache: Cache = cx.cache.clone().expect("cx should have cache");
-    let cache_key = format!("{}/{}", region.as_ref(), table_name);
+    let schema = TableSchema {
+        region: String::from(region.as_ref()),
+        name: table_name,
+        pk: key::typed_key("HASH", desc).expect("pk should exist"),
+        sk: key::typed_key("RANGE", desc),
+        indexes: index_schemas(desc),
+        mode: table::extract_mode(&desc.billing_mode_summary),
+        ttl: None,
+    };
+    save_schema_to_cache(cx, schema, chrono::Utc::now().timestamp())
+}
 
+/// Writes the schema into the cache file, keyed by "<RegionName>/<TableName>" of the schema.
+/// `cached_at` is when the schema was retrieved by DescribeTable, which decides when it gets stale.
+fn save_schema_to_cache(
+    cx: &Context,
+    schema: TableSchema,
+    cached_at: i64,
+) -> Result<(), DyneinConfigError> {
+    let mut cache: Cache = cx.cache.clone().expect("cx should have cache");
+    let cache_key = format!("{}/{}", schema.region, schema.name);
     let mut table_schema_hashmap: HashMap<String, TableSchema> = cache.tables.unwrap_or_default();
     debug!(
         "table schema cache before insert: {:#?}",
         table_schema_hashmap
     );
 
-    table_schema_hashmap.insert(
-        cache_key,
-        TableSchema {
-            region: String::from(region.as_ref()),
-            name: table_name,
-            pk: key::typed_key("HASH", desc).expect("pk should exist"),
-            sk: key::typed_key("RANGE", desc),
-            indexes: index_schemas(desc),
-            mode: table::extract_mode(&desc.billing_mode_summary),
-        },
-    );
+    // The cache of the context isn't updated, so schemas cached earlier in this process are merged as well.
+    let mut refreshed = REFRESHED_SCHEMAS.lock().unwrap();
+    refreshed.insert(cache_key, (schema, cached_at));
+    for (key, (schema, cached_at)) in refreshed.iter() {
+        table_schema_hashmap.insert(key.to_owned(), schema.to_owned());
+        cache.cached_at.insert(key.to_owned(), *cached_at);
+    }
+    drop(refreshed);
     cache.tables = Some(table_schema_hashmap);
 
     // write to cache file
@@ -595,6 +1254,59 @@ pub async fn insert_to_table_cache(
     Ok(())
 }
 
+/// Executed when you call `$ dy config set <KEY> <VALUE>`. Validates the value and saves it into config.yml.
+/// Durations are given in seconds, and `null` (or `~`) unsets an optional value.
+pub fn set_config(cx: &mut Context, key: &str, value: &str) -> Result<(), DyneinConfigError> {
+    let mut config = cx.config.clone().unwrap_or_default();
+    if let Err(e) = update_config(&mut config, key, value) {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+
+    debug!("config file will be updated with: {:?}", config);
+    write_dynein_file(DyneinFileType::ConfigFile, serde_yaml::to_string(&config)?)?;
+    cx.config = Some(config);
+    Ok(())
+}
+
+/// Executed when you call `$ dy config get [KEY]`. Shows effective values and where they come from,
+/// i.e. `flag` (command line options), `env` (environment variables), `config` (config.yml) or `default`.
+pub async fn get_config(cx: &Context, key: Option<String>) -> Result<(), DyneinConfigError> {
+    let keys: Vec<&str> = match &key {
+        Some(key) if CONFIG_KEYS.contains(&key.as_str()) => vec![key],
+        Some(key) => {
+            error!("{}", DyneinConfigContentError::UnknownKey(key.to_owned()));
+            std::process::exit(1);
+        }
+        None => CONFIG_KEYS.to_vec(),
+    };
+
+    let mut tw = TabWriter::new(std::io::stdout());
+    tw.write_all(b"Key\tValue\tSource\n")?;
+    for key in keys {
+        let (value, source) = effective_config_value(cx, key).await;
+        tw.write_all(format!("{}\t{}\t{}\n", key, value, source).as_bytes())?;
+    }
+    tw.flush()?;
+    Ok(())
+}
+
+/// Parses a positive number of seconds, e.g. "3" or "0.5", given to timeout options or `dy config set`.
+pub fn parse_seconds(s: &str) -> Result<Duration, String> {
+    match s.parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(String::from("should be a positive number of seconds")),
+    }
+}
+
+/// Validates a number given by a command line option, e.g. "-1" or "0.5", and returns it as is.
+pub fn parse_number_literal(s: &str) -> Result<String, String> {
+    match s.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(s.to_owned()),
+        _ => Err(String::from("should be a number")),
+    }
+}
+
 /// Physicall remove config and cache file.
 pub fn remove_dynein_files() -> Result<(), DyneinConfigError> {
     fs::remove_file(retrieve_dynein_file_path(DyneinFileType::ConfigFile)?)?;
@@ -610,37 +1322,124 @@ pub async fn table_schema(cx: &Context) -> TableSchema {
         // It's possible that users pass --table without calling `dy use` for any table. Thus collect all data from DescribeTable results.
         Some(table_name) => {
             // TODO: reduce # of DescribeTable API calls. table_schema function is called every time you do something.
-            let desc: TableDescription = control::describe_table_api(
+            table_schema_by_name(
                 cx, table_name, /* should be equal to 'cx.effective_table_name()' */
             )
-            .await;
-
-            TableSchema {
-                region: String::from(cx.effective_region().await.as_ref()),
-                name: desc.table_name.to_owned().unwrap(),
-                pk: key::typed_key("HASH", &desc).expect("pk should exist"),
-                sk: key::typed_key("RANGE", &desc),
-                indexes: index_schemas(&desc),
-                mode: table::extract_mode(&desc.billing_mode_summary),
-            }
+            .await
         }
         None => {
             // simply maps config data into TableSchema struct.
             debug!("current context {:#?}", cx);
+            let cache_key = cx.effective_cache_key().await;
+            let refreshed: Option<TableSchema> = REFRESHED_SCHEMAS
+                .lock()
+                .unwrap()
+                .get(&cache_key)
+                .map(|(schema, _)| schema.to_owned());
+            if let Some(schema) = refreshed {
+                return schema;
+            }
             let cache = cx.cache.as_ref().expect("Cache should exist in context"); // can refactor here using and_then
             let cached_tables = cache.tables.as_ref().unwrap_or_else(|| {
                 error!("{}", Messages::NoEffectiveTable);
                 std::process::exit(1)
             });
-            let schema_from_cache: Option<TableSchema> = cached_tables
-                .get(&cx.effective_cache_key().await)
-                .map(|x| x.to_owned());
-            schema_from_cache.unwrap_or_else(|| {
-                error!("{}", Messages::NoEffectiveTable);
-                std::process::exit(1)
+            let schema_from_cache: TableSchema = cached_tables
+                .get(&cache_key)
+                .map(|x| x.to_owned())
+                .unwrap_or_else(|| {
+                    error!("{}", Messages::NoEffectiveTable);
+                    std::process::exit(1)
+                });
+            // Schemas cached by older versions of dynein don't have timestamps, so they're refreshed as well.
+            let max_age = cx.schema_max_age().as_secs() as i64;
+            match cache.cached_at.get(&cache_key) {
+                Some(cached_at) if chrono::Utc::now().timestamp() - cached_at < max_age => {
+                    schema_from_cache
+                }
+                _ => {
+                    debug!(
+                        "The cached schema of '{}' is stale, refreshing it.",
+                        cache_key
+                    );
+                    refresh_table_schema(cx, &schema_from_cache).await
+                }
+            }
+        }
+    }
+}
+
+/// Refreshes the cached schema of the target table if a request failed as its primary key didn't match the key schema of the table.
+/// Returns true if the key schema has changed since it was cached, so that the caller can retry the request once with the refreshed schema.
+/// Schemas of tables given by --table are retrieved by DescribeTable every time, so they're never refreshed.
+pub async fn refresh_schema_on_key_mismatch<E, R>(
+    cx: &Context,
+    ts: &TableSchema,
+    error: &SdkError<E, R>,
+) -> bool
+where
+    E: ProvideErrorMetadata,
+{
+    let mismatch = error.as_service_error().map_or(false, |se| {
+        se.code() == Some("ValidationException")
+            && se.message().map_or(false, |msg| {
+                KEY_SCHEMA_MISMATCH_MESSAGES.iter().any(|m| msg.contains(m))
             })
+    });
+    if !mismatch || cx.overwritten_table_name.is_some() {
+        return false;
+    }
+    let refreshed = refresh_table_schema(cx, ts).await;
+    refreshed.pk != ts.pk || refreshed.sk != ts.sk
+}
+
+/// Returns the TTL attribute of the table, or None if TTL is not enabled. DescribeTimeToLive is called only if `ts` hasn't looked it up,
+/// and the result is saved with the cached schema of the table, until the schema is refreshed.
+pub async fn ttl_attribute(cx: &Context, ts: &mut TableSchema) -> Option<String> {
+    if let Some(ttl) = &ts.ttl {
+        return ttl.attr.to_owned();
+    }
+    let attr = control::ttl_attribute_api(cx, &ts.name).await;
+    ts.ttl = Some(TtlSchema {
+        attr: attr.to_owned(),
+    });
+
+    // Schemas of tables given by --table are not cached.
+    if cx.overwritten_table_name.is_none() {
+        let cache_key = format!("{}/{}", ts.region, ts.name);
+        let refreshed_at = REFRESHED_SCHEMAS
+            .lock()
+            .unwrap()
+            .get(&cache_key)
+            .map(|(_, cached_at)| *cached_at);
+        let cached_at = refreshed_at
+            .or_else(|| {
+                cx.cache
+                    .as_ref()
+                    .and_then(|cache| cache.cached_at.get(&cache_key).copied())
+            })
+            .unwrap_or_else(|| chrono::Utc::now().timestamp());
+        match save_schema_to_cache(cx, ts.to_owned(), cached_at) {
+            Ok(_) => debug!("TTL setting of the table was written to the cache file."),
+            Err(e) => debug!("Failed to write TTL setting to the cache file: {:?}", e),
         }
     }
+    attr
+}
+
+/// Retrieves schema of the given table in the effective region by DescribeTable, regardless of the target table of the context.
+pub async fn table_schema_by_name(cx: &Context, table_name: String) -> TableSchema {
+    let desc: TableDescription = control::describe_table_api(cx, table_name).await;
+
+    TableSchema {
+        region: String::from(cx.effective_region().await.as_ref()),
+        name: desc.table_name.to_owned().unwrap(),
+        pk: key::typed_key("HASH", &desc).expect("pk should exist"),
+        sk: key::typed_key("RANGE", &desc),
+        indexes: index_schemas(&desc),
+        mode: table::extract_mode(&desc.billing_mode_summary),
+        ttl: None,
+    }
 }
 
 pub fn index_schemas(desc: &TableDescription) -> Option<Vec<IndexSchema>> {
@@ -679,6 +1478,35 @@ pub fn index_schemas(desc: &TableDescription) -> Option<Vec<IndexSchema>> {
     }
 }
 
+/// Asks for confirmation before a destructive operation if `safety.require_confirmation` contains it,
+/// and exits when it's declined. In non-interactive mode, the operation requires `--yes` option instead.
+/// `prompt` is called only when the confirmation is actually asked.
+pub async fn confirm_operation(
+    cx: &Context,
+    operation: SafetyOperation,
+    yes: bool,
+    prompt: impl FnOnce() -> String,
+) {
+    if yes || !cx.requires_confirmation(operation).await {
+        return;
+    }
+    if !std::io::stdin().is_terminal() {
+        error!(
+            "'{}' is listed in safety.require_confirmation, so this operation requires --yes option in non-interactive mode.",
+            operation.as_str()
+        );
+        std::process::exit(1);
+    }
+    let prompt = format!(
+        "{} in the region '{}'. Are you OK?",
+        prompt(),
+        cx.effective_region().await.as_ref()
+    );
+    if !Confirm::new().with_prompt(prompt).interact().unwrap() {
+        bye(0, "Operation has been cancelled.");
+    }
+}
+
 pub fn bye(code: i32, msg: &str) -> ! {
     println!("{}", msg);
     std::process::exit(code);
@@ -700,10 +1528,406 @@ where
 Private functions
 ================================================= */
 
-fn retrieve_dynein_file_path(file_type: DyneinFileType) -> Result<String, DyneinConfigError> {
+/// Retrieves the schema of the table by DescribeTable and saves it into the cache.
+/// Shows a warning if its key schema has changed since it was cached, e.g. because the table has been recreated.
+async fn refresh_table_schema(cx: &Context, cached: &TableSchema) -> TableSchema {
+    let desc: TableDescription = control::describe_table_api(cx, cached.name.to_owned()).await;
+    if let Err(e) = insert_to_table_cache(cx, &desc).await {
+        eprintln!(
+            "WARN: Failed to write the refreshed table schema to the cache: {}",
+            e
+        );
+    }
+    let cache_key = cx.effective_cache_key().await;
+    let refreshed: TableSchema = REFRESHED_SCHEMAS
+        .lock()
+        .unwrap()
+        .get(&cache_key)
+        .map(|(schema, _)| schema.to_owned())
+        .expect("refreshed schema should be saved");
+    if refreshed.pk != cached.pk || refreshed.sk != cached.sk {
+        eprintln!(
+            "WARN: The key schema of the table '{}' has changed since it was cached, from {} to {}. The cache has been refreshed.",
+            refreshed.name,
+            display_key_schema(cached),
+            display_key_schema(&refreshed)
+        );
+    }
+    refreshed
+}
+
+/// Returns primary keys of the table, e.g. "pk (S), sk (N)".
+fn display_key_schema(ts: &TableSchema) -> String {
+    std::iter::once(&ts.pk)
+        .chain(ts.sk.as_ref())
+        .map(key::Key::display)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns true for "local" and named local endpoints such as "local:lstack".
+fn is_local_region(region_name: &str) -> bool {
+    region_name == LOCAL_REGION || region_name.starts_with(LOCAL_REGION_PREFIX)
+}
+
+/// Updates the config with the value of the key given to `dy config set`.
+fn update_config(
+    config: &mut Config,
+    key: &str,
+    value: &str,
+) -> Result<(), DyneinConfigContentError> {
+    let retry = RetrySettingGlobal::default;
+    match key {
+        "using_region" => config.using_region = parse_config_value(key, value, parse_string)?,
+        "using_table" => config.using_table = parse_config_value(key, value, parse_string)?,
+        "using_port" => config.using_port = parse_config_value(key, value, parse_number)?,
+        "query.strict_mode" => {
+            config.query.strict_mode =
+                parse_config_value(key, value, parse_bool)?.unwrap_or_default()
+        }
+        "query.scan_warning_threshold" => {
+            config.query.scan_warning_threshold = parse_config_value(key, value, parse_number)?
+        }
+        "retry.default.max_attempts" => {
+            config.retry.get_or_insert_with(retry).default.max_attempts =
+                parse_config_value(key, value, parse_number)?
+        }
+        "retry.default.initial_backoff" => {
+            config
+                .retry
+                .get_or_insert_with(retry)
+                .default
+                .initial_backoff = parse_config_value(key, value, parse_seconds)?
+        }
+        "retry.default.max_backoff" => {
+            config.retry.get_or_insert_with(retry).default.max_backoff =
+                parse_config_value(key, value, parse_seconds)?
+        }
+        "retry.batch_write_item.max_attempts" => {
+            config
+                .retry
+                .get_or_insert_with(retry)
+                .batch_write_item
+                .get_or_insert_with(RetrySetting::default)
+                .max_attempts = parse_config_value(key, value, parse_number)?
+        }
+        "retry.batch_write_item.initial_backoff" => {
+            config
+                .retry
+                .get_or_insert_with(retry)
+                .batch_write_item
+                .get_or_insert_with(RetrySetting::default)
+                .initial_backoff = parse_config_value(key, value, parse_seconds)?
+        }
+        "retry.batch_write_item.max_backoff" => {
+            config
+                .retry
+                .get_or_insert_with(retry)
+                .batch_write_item
+                .get_or_insert_with(RetrySetting::default)
+                .max_backoff = parse_config_value(key, value, parse_seconds)?
+        }
+        "timeout.connect_timeout" => {
+            config
+                .timeout
+                .get_or_insert_with(TimeoutSetting::default)
+                .connect_timeout = parse_config_value(key, value, parse_seconds)?
+        }
+        "timeout.read_timeout" => {
+            config
+                .timeout
+                .get_or_insert_with(TimeoutSetting::default)
+                .read_timeout = parse_config_value(key, value, parse_seconds)?
+        }
+        "history.enabled" => {
+            config
+                .history
+                .get_or_insert_with(HistorySetting::default)
+                .enabled = parse_config_value(key, value, parse_bool)?.unwrap_or_default()
+        }
+        "history.max_entries" => {
+            config
+                .history
+                .get_or_insert_with(HistorySetting::default)
+                .max_entries = parse_config_value(key, value, parse_number)?
+        }
+        "safety.require_confirmation" => {
+            config
+                .safety
+                .get_or_insert_with(SafetySetting::default)
+                .require_confirmation =
+                parse_config_value(key, value, parse_safety_operations)?.unwrap_or_default()
+        }
+        "display.ellipsis" => {
+            config
+                .display
+                .get_or_insert_with(DisplaySetting::default)
+                .ellipsis = parse_config_value(key, value, parse_string)?
+        }
+        "display.redact" => {
+            config
+                .display
+                .get_or_insert_with(DisplaySetting::default)
+                .redact = parse_config_value(key, value, parse_attribute_names)?.unwrap_or_default()
+        }
+        "cache.schema_max_age" => {
+            config
+                .cache
+                .get_or_insert_with(CacheSetting::default)
+                .schema_max_age = parse_config_value(key, value, parse_seconds)?
+        }
+        _ => return Err(DyneinConfigContentError::UnknownKey(key.to_owned())),
+    }
+
+    // Validate combinations of values as well, e.g. max_attempts of the retry config.
+    if let Some(retry) = &config.retry {
+        Retry::try_from(retry.clone())?;
+    }
+    Ok(())
+}
+
+/// Returns the effective value of the config key and its source. `~` means the value isn't set.
+async fn effective_config_value(cx: &Context, key: &str) -> (String, ConfigSource) {
+    let config = cx.config.clone().unwrap_or_default();
+    let from_config = |value: Option<String>| match value {
+        Some(value) => (value, ConfigSource::Config),
+        None => (String::from("~"), ConfigSource::Default),
+    };
+    // Retry configs are changed by --max-retries option, which is detected by comparing with the one built from config.yml.
+    let configured_retry = config.retry.clone().and_then(|r| Retry::try_from(r).ok());
+    let max_attempts =
+        |effective: Option<&RetryConfig>, configured: Option<&RetryConfig>, value: Option<u32>| {
+            match effective.map(|r| r.max_attempts()) {
+                Some(attempts) if configured.map(|r| r.max_attempts()) != Some(attempts) => {
+                    (attempts.to_string(), ConfigSource::Flag)
+                }
+                _ => from_config(value.map(|v| v.to_string())),
+            }
+        };
+    let timeout = |effective: Option<Duration>, configured: Option<Duration>| match effective {
+        Some(d) if effective != configured => (display_seconds(d), ConfigSource::Flag),
+        _ => from_config(configured.map(display_seconds)),
+    };
+    let bwi_setting = config
+        .retry
+        .as_ref()
+        .and_then(|r| r.batch_write_item.as_ref());
+    let default_setting = config.retry.as_ref().map(|r| &r.default);
+
+    match key {
+        "using_region" => {
+            let region = cx.effective_region().await.as_ref().to_owned();
+            if cx.overwritten_region.is_some() {
+                (region, ConfigSource::Flag)
+            } else if config.using_region.is_some() {
+                (region, ConfigSource::Config)
+            } else if env::var("AWS_REGION").is_ok() || env::var("AWS_DEFAULT_REGION").is_ok() {
+                (region, ConfigSource::Env)
+            } else {
+                (region, ConfigSource::Default)
+            }
+        }
+        "using_table" => match &cx.overwritten_table_name {
+            Some(table) => (table.to_owned(), ConfigSource::Flag),
+            None => from_config(config.using_table),
+        },
+        "using_port" => match cx.overwritten_port {
+            Some(port) => (port.to_string(), ConfigSource::Flag),
+            None => match config.using_port {
+                Some(port) => (port.to_string(), ConfigSource::Config),
+                None => (DEFAULT_PORT.to_string(), ConfigSource::Default),
+            },
+        },
+        "query.strict_mode" => match (cx.should_strict, strict_mode_from_env()) {
+            (Some(strict), _) => (strict.to_string(), ConfigSource::Flag),
+            (None, Some(strict)) => (strict.to_string(), ConfigSource::Env),
+            (None, None) if config.query.strict_mode => {
+                (String::from("true"), ConfigSource::Config)
+            }
+            (None, None) => (String::from("false"), ConfigSource::Default),
+        },
+        "query.scan_warning_threshold" => match config.query.scan_warning_threshold {
+            Some(threshold) => (threshold.to_string(), ConfigSource::Config),
+            None => (
+                DEFAULT_SCAN_WARNING_THRESHOLD.to_string(),
+                ConfigSource::Default,
+            ),
+        },
+        "retry.default.max_attempts" => max_attempts(
+            cx.retry.as_ref().map(|r| &r.default),
+            configured_retry.as_ref().map(|r| &r.default),
+            default_setting.and_then(|r| r.max_attempts),
+        ),
+        "retry.default.initial_backoff" => from_config(
+            default_setting
+                .and_then(|r| r.initial_backoff)
+                .map(display_seconds),
+        ),
+        "retry.default.max_backoff" => from_config(
+            default_setting
+                .and_then(|r| r.max_backoff)
+                .map(display_seconds),
+        ),
+        "retry.batch_write_item.max_attempts" => max_attempts(
+            cx.retry.as_ref().and_then(|r| r.batch_write_item.as_ref()),
+            configured_retry
+                .as_ref()
+                .and_then(|r| r.batch_write_item.as_ref()),
+            bwi_setting.and_then(|r| r.max_attempts),
+        ),
+        "retry.batch_write_item.initial_backoff" => from_config(
+            bwi_setting
+                .and_then(|r| r.initial_backoff)
+                .map(display_seconds),
+        ),
+        "retry.batch_write_item.max_backoff" => {
+            from_config(bwi_setting.and_then(|r| r.max_backoff).map(display_seconds))
+        }
+        "timeout.connect_timeout" => timeout(
+            cx.timeout.as_ref().and_then(|t| t.connect_timeout),
+            config.timeout.as_ref().and_then(|t| t.connect_timeout),
+        ),
+        "timeout.read_timeout" => timeout(
+            cx.timeout.as_ref().and_then(|t| t.read_timeout),
+            config.timeout.as_ref().and_then(|t| t.read_timeout),
+        ),
+        "history.enabled" => match &config.history {
+            Some(history) if history.enabled => (String::from("true"), ConfigSource::Config),
+            _ => (String::from("false"), ConfigSource::Default),
+        },
+        "history.max_entries" => match config.history.as_ref().and_then(|h| h.max_entries) {
+            Some(max) => (max.to_string(), ConfigSource::Config),
+            None => (
+                DEFAULT_HISTORY_MAX_ENTRIES.to_string(),
+                ConfigSource::Default,
+            ),
+        },
+        "safety.require_confirmation" => from_config(
+            config
+                .safety
+                .map(|s| s.require_confirmation)
+                .filter(|ops| !ops.is_empty())
+                .map(|ops| {
+                    ops.iter()
+                        .map(|op| op.as_str())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }),
+        ),
+        "display.ellipsis" => match config.display.and_then(|d| d.ellipsis) {
+            Some(ellipsis) => (ellipsis, ConfigSource::Config),
+            None => (String::from(DEFAULT_ELLIPSIS), ConfigSource::Default),
+        },
+        "display.redact" => from_config(
+            config
+                .display
+                .map(|d| d.redact)
+                .filter(|names| !names.is_empty())
+                .map(|names| names.join(",")),
+        ),
+        "cache.schema_max_age" => match config.cache.and_then(|c| c.schema_max_age) {
+            Some(max_age) => (display_seconds(max_age), ConfigSource::Config),
+            None => (
+                display_seconds(DEFAULT_SCHEMA_MAX_AGE),
+                ConfigSource::Default,
+            ),
+        },
+        _ => unreachable!("config keys should be validated by the caller"),
+    }
+}
+
+/// Parses a value given to `dy config set`. Returns None for `~` or `null`, which unsets the value.
+fn parse_config_value<T>(
+    key: &str,
+    value: &str,
+    parse: fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, DyneinConfigContentError> {
+    if UNSET_VALUES.contains(&value) {
+        return Ok(None);
+    }
+    parse(value)
+        .map(Some)
+        .map_err(|reason| DyneinConfigContentError::InvalidValue {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            reason,
+        })
+}
+
+fn parse_string(s: &str) -> Result<String, String> {
+    Ok(s.to_owned())
+}
+
+fn parse_bool(s: &str) -> Result<bool, String> {
+    s.parse::<bool>()
+        .map_err(|_| String::from("should be true or false"))
+}
+
+fn parse_number<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse::<T>()
+        .map_err(|_| String::from("should be a non-negative integer"))
+}
+
+/// Parses operations separated by commas, e.g. "delete,import".
+fn parse_safety_operations(s: &str) -> Result<Vec<SafetyOperation>, String> {
+    s.split(',')
+        .map(|name| {
+            SafetyOperation::ALL
+                .iter()
+                .find(|op| op.as_str() == name.trim())
+                .copied()
+                .ok_or_else(|| {
+                    format!(
+                        "should be operations separated by commas. Available operations are: {}",
+                        SafetyOperation::ALL.map(|op| op.as_str()).join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Parses attribute names separated by commas, e.g. "password,ssn".
+fn parse_attribute_names(s: &str) -> Result<Vec<String>, String> {
+    let names: Vec<String> = s.split(',').map(|name| name.trim().to_owned()).collect();
+    if names.iter().any(|name| name.is_empty()) {
+        return Err(String::from(
+            "should be attribute names separated by commas",
+        ));
+    }
+    Ok(names)
+}
+
+/// Formats a duration in seconds, e.g. "0.5s".
+fn display_seconds(d: Duration) -> String {
+    format!("{}s", d.as_secs_f64())
+}
+
+/// Returns the endpoint URL of DynamoDB Local listening on the port, e.g. "http://localhost:8000".
+fn local_endpoint_url(port: u32) -> String {
+    format!("http://localhost:{}", port)
+}
+
+/// Reads DYNEIN_STRICT env var. Accepts "true"/"1" and "false"/"0", and ignores other values with a warning.
+fn strict_mode_from_env() -> Option<bool> {
+    let value = env::var(STRICT_MODE_ENV_VAR_NAME).ok()?;
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => {
+            eprintln!(
+                "WARN: ignoring invalid value for {}: '{}'. Valid values are true, false, 1, or 0.",
+                STRICT_MODE_ENV_VAR_NAME, value
+            );
+            None
+        }
+    }
+}
+
+pub fn retrieve_dynein_file_path(file_type: DyneinFileType) -> Result<String, DyneinConfigError> {
     let filename = match file_type {
         DyneinFileType::ConfigFile => CONFIG_FILE_NAME,
         DyneinFileType::CacheFile => CACHE_FILE_NAME,
+        DyneinFileType::HistoryFile => HISTORY_FILE_NAME,
     };
 
     Ok(format!("{}/{}", retrieve_or_create_dynein_dir()?, filename))
@@ -759,7 +1983,10 @@ async fn save_using_target(
     Ok(())
 }
 
-fn write_dynein_file(file_type: DyneinFileType, content: String) -> Result<(), DyneinConfigError> {
+pub fn write_dynein_file(
+    file_type: DyneinFileType,
+    content: String,
+) -> Result<(), DyneinConfigError> {
     let temp_file = NamedTempFile::new_in(retrieve_or_create_dynein_dir()?)?;
     let temp_path = temp_file.path();
 
@@ -788,8 +2015,11 @@ mod tests {
             overwritten_table_name: None,
             overwritten_port: None,
             output: None,
-            should_strict_for_query: None,
+            shell_output: None,
+            should_strict: None,
             retry: None,
+            ca_bundle: None,
+            timeout: None,
         };
         assert_eq!(
             &cx1.effective_region().await,
@@ -805,16 +2035,31 @@ mod tests {
                 using_region: Some(String::from("ap-northeast-1")),
                 using_table: Some(String::from("cfgtbl")),
                 using_port: Some(8000),
-                query: QueryConfig { strict_mode: false },
+                query: QueryConfig {
+                    strict_mode: false,
+                    scan_warning_threshold: None,
+                },
                 retry: Some(RetrySettingGlobal::default()),
+                timeout: None,
+                history: None,
+                safety: None,
+                display: None,
+                cache: None,
+                table_defaults: BTreeMap::new(),
+                locals: BTreeMap::new(),
+                hooks: Vec::new(),
+                entities: BTreeMap::new(),
             }),
             cache: None,
             overwritten_region: None,
             overwritten_table_name: None,
             overwritten_port: None,
             output: None,
-            should_strict_for_query: None,
+            shell_output: None,
+            should_strict: None,
             retry: Some(RetrySettingGlobal::default().try_into()?),
+            ca_bundle: None,
+            timeout: None,
         };
         assert_eq!(
             cx2.effective_region().await,
@@ -853,9 +2098,158 @@ mod tests {
         );
         assert_eq!(cx5.effective_table_name(), String::from("argtbl"));
 
+        // --table arn:aws:dynamodb:eu-west-1:123456789012:table/arntbl
+        let cx6 = cx4
+            .clone()
+            .with_table("arn:aws:dynamodb:eu-west-1:123456789012:table/arntbl");
+        assert_eq!(
+            cx6.effective_region().await,
+            Region::from_static("eu-west-1")
+        );
+        assert_eq!(cx6.effective_table_name(), String::from("arntbl"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_table_arn() {
+        assert_eq!(
+            parse_table_arn("arn:aws:dynamodb:eu-west-1:123456789012:table/Foo"),
+            Some(("eu-west-1", "Foo"))
+        );
+        assert_eq!(
+            parse_table_arn("arn:aws-cn:dynamodb:cn-north-1:123456789012:table/my.table-1"),
+            Some(("cn-north-1", "my.table-1"))
+        );
+        assert_eq!(parse_table_arn("Foo"), None);
+        assert_eq!(
+            parse_table_arn("arn:aws:dynamodb:eu-west-1:123456789012:table/Foo/index/bar"),
+            None
+        );
+        assert_eq!(
+            parse_table_arn("arn:aws:s3:eu-west-1:123456789012:table/Foo"),
+            None
+        );
+        assert_eq!(
+            parse_table_arn("arn:aws:dynamodb::123456789012:table/Foo"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_locals_config() -> Result<(), Box<dyn Error>> {
+        let config: Config = serde_yaml::from_str(
+            r#"
+            using_region: local:lstack
+            locals:
+              ddb: http://localhost:8001
+              lstack:
+                endpoint: http://localhost:4566
+                region: us-east-1
+            "#,
+        )?;
+        let cx = Context {
+            config: Some(config),
+            cache: None,
+            overwritten_region: None,
+            overwritten_table_name: None,
+            overwritten_port: None,
+            output: None,
+            shell_output: None,
+            should_strict: None,
+            retry: None,
+            ca_bundle: None,
+            timeout: None,
+        };
+        assert_eq!(
+            cx.local_target("local:ddb"),
+            Some((String::from("http://localhost:8001"), String::from("local")))
+        );
+        assert_eq!(
+            cx.local_target("local:lstack"),
+            Some((
+                String::from("http://localhost:4566"),
+                String::from("us-east-1")
+            ))
+        );
+        assert_eq!(
+            cx.local_target("local"),
+            Some((String::from("http://localhost:8000"), String::from("local")))
+        );
+        assert_eq!(cx.local_target("us-east-1"), None);
+        assert!(cx.is_local().await);
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_defaults() -> Result<(), Box<dyn Error>> {
+        let config: Config = serde_yaml::from_str(
+            r#"
+            using_table: Movie
+            table_defaults:
+              Movie:
+                output: json
+              Music:
+                consistent_read: true
+            "#,
+        )?;
+        let mut cx = Context {
+            config: Some(config),
+            cache: None,
+            overwritten_region: None,
+            overwritten_table_name: None,
+            overwritten_port: None,
+            output: None,
+            shell_output: None,
+            should_strict: None,
+            retry: None,
+            ca_bundle: None,
+            timeout: None,
+        };
+        assert_eq!(cx.output_or_table_default(None).as_deref(), Some("json"));
+        assert_eq!(
+            cx.output_or_table_default(Some(String::from("yaml")))
+                .as_deref(),
+            Some("yaml")
+        );
+        assert!(!cx.consistent_read_or_table_default(false));
+
+        cx.overwritten_table_name = Some(String::from("Music"));
+        assert_eq!(cx.output_or_table_default(None), None);
+        assert!(cx.consistent_read_or_table_default(false));
         Ok(())
     }
 
+    #[test]
+    fn test_apply_connection_options() {
+        let mut cx = Context {
+            config: None,
+            cache: None,
+            overwritten_region: None,
+            overwritten_table_name: None,
+            overwritten_port: None,
+            output: None,
+            shell_output: None,
+            should_strict: None,
+            retry: None,
+            ca_bundle: None,
+            timeout: Some(TimeoutSetting {
+                connect_timeout: Some(Duration::from_secs(10)),
+                read_timeout: None,
+            }),
+        };
+        cx.apply_connection_options(None, Some(Duration::from_secs(5)), Some(0));
+
+        let timeout = TimeoutConfig::from(cx.timeout.as_ref().unwrap());
+        assert_eq!(timeout.connect_timeout(), Some(Duration::from_secs(10)));
+        assert_eq!(timeout.read_timeout(), Some(Duration::from_secs(5)));
+        assert_eq!(cx.retry.unwrap().default.max_attempts(), 1);
+
+        let timeout = TimeoutConfig::from(&TimeoutSetting::default());
+        assert_eq!(timeout.connect_timeout(), Some(DEFAULT_CONNECT_TIMEOUT));
+        assert_eq!(timeout.read_timeout(), None);
+    }
+
     #[test]
     fn test_retry_setting_success() {
         let config1 = RetrySetting::default();
@@ -0,0 +1,676 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module converts items from/to Avro records for `dy export --format avro` and `dy import --format avro`.
+//
+// Avro needs a record schema before writing any item. It's either given by `--avro-schema`, or derived from
+// primary keys of the table and types of attributes in sampled items. Schemas are kept as JSON as well as parsed,
+// so that attribute values are converted by walking the JSON schema field by field.
+// In derived schemas, lists, maps and attributes of mixed types are written as JSON encoded strings.
+// Original attribute names and DynamoDB types of such fields are recorded in the file metadata,
+// so that `dy import` can restore them.
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{Read, Write};
+
+use apache_avro::types::Value as AvroValue;
+use aws_sdk_dynamodb::types::AttributeValue;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use thiserror::Error;
+
+use super::app;
+use super::data;
+use super::ddb::key;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Key of the file metadata which maps field names to original attribute names and DynamoDB types.
+const ATTRIBUTES_METADATA_KEY: &str = "dynein.attributes";
+
+/// Custom property of a field, holding the name of the attribute when it's not a valid Avro name.
+/// It can be used in schemas given by `--avro-schema` as well.
+const ATTRIBUTE_PROPERTY: &str = "ddbAttribute";
+
+/// Custom property of a field in derived schemas, holding the DynamoDB type of the attribute, e.g. "S" or "NS".
+const TYPE_PROPERTY: &str = "ddbType";
+
+/// DynamoDB type of fields whose values are written as JSON encoded strings, i.e. lists, maps, or mixed types.
+const JSON_TYPE: &str = "JSON";
+
+#[derive(Error, Debug)]
+pub enum AvroError {
+    #[error("avro error: {0}")]
+    Avro(#[from] apache_avro::Error),
+    #[error("invalid Avro schema: {0}")]
+    InvalidSchema(String),
+    #[error("the attribute '{0}' can't be written as Avro type {1}")]
+    Conversion(String, String),
+    #[error("unsupported Avro value: {0}")]
+    UnsupportedValue(String),
+}
+
+/// Record schema of exported items, with the JSON it's parsed from.
+#[derive(Debug)]
+pub struct ItemSchema {
+    json: JsonValue,
+    schema: apache_avro::Schema,
+}
+
+/// Entry of the file metadata for a field.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct FieldMetadata {
+    attribute: String,
+    #[serde(rename = "type")]
+    ddb_type: Option<String>,
+}
+
+impl ItemSchema {
+    /// Parses a schema given by `--avro-schema`, which should be a record schema.
+    pub fn parse(s: &str) -> Result<Self, AvroError> {
+        let json: JsonValue =
+            serde_json::from_str(s).map_err(|e| AvroError::InvalidSchema(e.to_string()))?;
+        Self::from_json(json)
+    }
+
+    /// Derives a record schema named after the table. Primary keys come first and are required,
+    /// followed by other attributes found in the sampled items in alphabetical order, which are nullable.
+    pub fn derive(
+        ts: &app::TableSchema,
+        items: &[HashMap<String, AttributeValue>],
+    ) -> Result<Self, AvroError> {
+        let mut attributes: BTreeMap<&str, Vec<&AttributeValue>> = BTreeMap::new();
+        for item in items {
+            for (name, value) in item {
+                attributes.entry(name).or_default().push(value);
+            }
+        }
+
+        let mut used_names: HashSet<String> = HashSet::new();
+        let mut fields: Vec<JsonValue> = vec![];
+        for key in std::iter::once(&ts.pk).chain(ts.sk.as_ref()) {
+            let values = attributes.remove(key.name.as_str()).unwrap_or_default();
+            let (avro_type, ddb_type) = if values.is_empty() {
+                key_type(&key.kind)
+            } else {
+                derive_type(&values)
+            };
+            fields.push(derive_field(
+                &key.name,
+                avro_type,
+                ddb_type,
+                false,
+                &mut used_names,
+            ));
+        }
+        for (name, values) in attributes {
+            let (avro_type, ddb_type) = derive_type(&values);
+            fields.push(derive_field(
+                name,
+                avro_type,
+                ddb_type,
+                true,
+                &mut used_names,
+            ));
+        }
+
+        let mut json = JsonMap::new();
+        json.insert(String::from("type"), JsonValue::from("record"));
+        json.insert(String::from("name"), JsonValue::from(avro_name(&ts.name)));
+        json.insert(String::from("fields"), JsonValue::Array(fields));
+        Self::from_json(JsonValue::Object(json))
+    }
+
+    /// Returns the schema in JSON, e.g. to be logged.
+    pub fn json(&self) -> &JsonValue {
+        &self.json
+    }
+
+    /// Creates a writer of an Avro object container file, with metadata to restore attributes on import.
+    pub fn writer<W: Write>(&self, output: W) -> Result<apache_avro::Writer<'_, W>, AvroError> {
+        let mut writer = apache_avro::Writer::new(&self.schema, output);
+        let metadata: BTreeMap<&str, FieldMetadata> = self
+            .fields()
+            .filter(|field| {
+                field.get(ATTRIBUTE_PROPERTY).is_some() || field.get(TYPE_PROPERTY).is_some()
+            })
+            .map(|field| {
+                (
+                    field_name(field),
+                    FieldMetadata {
+                        attribute: field_attribute(field).to_owned(),
+                        ddb_type: field
+                            .get(TYPE_PROPERTY)
+                            .and_then(JsonValue::as_str)
+                            .map(str::to_owned),
+                    },
+                )
+            })
+            .collect();
+        if !metadata.is_empty() {
+            let metadata = serde_json::to_string(&metadata)
+                .map_err(|e| AvroError::InvalidSchema(e.to_string()))?;
+            writer.add_user_metadata(ATTRIBUTES_METADATA_KEY.to_owned(), metadata)?;
+        }
+        Ok(writer)
+    }
+
+    /// Converts an item to an Avro record. Attributes which are not in the schema are dropped,
+    /// and their names are returned so that they can be reported.
+    pub fn to_record(
+        &self,
+        item: &HashMap<String, AttributeValue>,
+    ) -> Result<(AvroValue, Vec<String>), AvroError> {
+        let fields: Vec<&JsonValue> = self.fields().collect();
+        let record = record_to_avro(item, &fields)?;
+        let attributes: HashSet<&str> = fields.iter().map(|f| field_attribute(f)).collect();
+        let mut dropped: Vec<String> = item
+            .keys()
+            .filter(|name| !attributes.contains(name.as_str()))
+            .cloned()
+            .collect();
+        dropped.sort();
+        Ok((record, dropped))
+    }
+
+    fn from_json(json: JsonValue) -> Result<Self, AvroError> {
+        if json.get("type").and_then(JsonValue::as_str) != Some("record")
+            || !json.get("fields").is_some_and(JsonValue::is_array)
+        {
+            return Err(AvroError::InvalidSchema(String::from(
+                "the top level should be a record schema with fields",
+            )));
+        }
+        let schema = apache_avro::Schema::parse(&json)?;
+        Ok(Self { json, schema })
+    }
+
+    fn fields(&self) -> impl Iterator<Item = &JsonValue> {
+        self.json["fields"].as_array().into_iter().flatten()
+    }
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Reads records of an Avro object container file as JSON objects, which are imported in the same way as JSON items.
+/// Null fields are omitted, binary values are base64 encoded strings, and JSON encoded fields of derived schemas are decoded.
+pub fn read_items<R: Read>(input: R) -> Result<Vec<JsonValue>, AvroError> {
+    let reader = apache_avro::Reader::new(input)?;
+    let metadata: HashMap<String, FieldMetadata> =
+        match reader.user_metadata().get(ATTRIBUTES_METADATA_KEY) {
+            Some(v) => serde_json::from_slice(v).map_err(|e| {
+                AvroError::InvalidSchema(format!("invalid {}: {}", ATTRIBUTES_METADATA_KEY, e))
+            })?,
+            None => HashMap::new(),
+        };
+
+    let mut items: Vec<JsonValue> = vec![];
+    for value in reader {
+        let fields = match value? {
+            AvroValue::Record(fields) => fields,
+            other => {
+                return Err(AvroError::UnsupportedValue(format!(
+                    "{:?} at the top level, which should be a record",
+                    other
+                )))
+            }
+        };
+        let mut item = JsonMap::new();
+        for (name, value) in fields {
+            let field = metadata.get(&name);
+            let value = match (avro_to_json(value)?, field) {
+                (JsonValue::Null, _) => continue,
+                (JsonValue::String(s), Some(field))
+                    if field.ddb_type.as_deref() == Some(JSON_TYPE) =>
+                {
+                    serde_json::from_str(&s).unwrap_or(JsonValue::String(s))
+                }
+                (value, _) => value,
+            };
+            let attribute = field.map_or(name, |f| f.attribute.to_owned());
+            item.insert(attribute, value);
+        }
+        items.push(JsonValue::Object(item));
+    }
+    Ok(items)
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+fn derive_field(
+    attribute: &str,
+    avro_type: JsonValue,
+    ddb_type: &str,
+    nullable: bool,
+    used_names: &mut HashSet<String>,
+) -> JsonValue {
+    // Names are unique in a record, even if different attribute names are sanitized to the same one.
+    let base = avro_name(attribute);
+    let mut name = base.clone();
+    let mut n = 1;
+    while used_names.contains(&name) {
+        n += 1;
+        name = format!("{}_{}", base, n);
+    }
+    used_names.insert(name.clone());
+
+    let mut field = JsonMap::new();
+    field.insert(String::from("name"), JsonValue::from(name.as_str()));
+    if nullable {
+        field.insert(
+            String::from("type"),
+            JsonValue::Array(vec![JsonValue::from("null"), avro_type]),
+        );
+        field.insert(String::from("default"), JsonValue::Null);
+    } else {
+        field.insert(String::from("type"), avro_type);
+    }
+    if name != attribute {
+        field.insert(String::from(ATTRIBUTE_PROPERTY), JsonValue::from(attribute));
+    }
+    field.insert(String::from(TYPE_PROPERTY), JsonValue::from(ddb_type));
+    JsonValue::Object(field)
+}
+
+/// Avro names start with [A-Za-z_] and contain only [A-Za-z0-9_]. Other characters are replaced with '_'.
+fn avro_name(s: &str) -> String {
+    let name: String = s
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.chars().next() {
+        Some(c) if !c.is_ascii_digit() => name,
+        _ => format!("_{}", name),
+    }
+}
+
+/// Avro type and DynamoDB type of a primary key which isn't found in sampled items.
+fn key_type(kind: &key::KeyType) -> (JsonValue, &'static str) {
+    match kind {
+        key::KeyType::S => (JsonValue::from("string"), "S"),
+        key::KeyType::N => (JsonValue::from("long"), "N"),
+        key::KeyType::B => (JsonValue::from("bytes"), "B"),
+    }
+}
+
+/// Returns Avro type and DynamoDB type of an attribute, from its values in sampled items.
+/// Numbers are long if all sampled values are integers, otherwise double.
+fn derive_type(values: &[&AttributeValue]) -> (JsonValue, &'static str) {
+    let values: Vec<&AttributeValue> = values
+        .iter()
+        .copied()
+        .filter(|v| !matches!(v, AttributeValue::Null(_)))
+        .collect();
+    let ddb_types: BTreeSet<&str> = values.iter().map(|v| ddb_type(v)).collect();
+    let ddb_type = match ddb_types.len() {
+        0 => return (JsonValue::from("string"), "S"),
+        1 => ddb_types.into_iter().next().unwrap(),
+        _ => JSON_TYPE,
+    };
+    let array = |items: JsonValue| {
+        let mut array = JsonMap::new();
+        array.insert(String::from("type"), JsonValue::from("array"));
+        array.insert(String::from("items"), items);
+        JsonValue::Object(array)
+    };
+    match ddb_type {
+        "S" => (JsonValue::from("string"), "S"),
+        "B" => (JsonValue::from("bytes"), "B"),
+        "BOOL" => (JsonValue::from("boolean"), "BOOL"),
+        "N" => (
+            number_type(values.iter().filter_map(|v| v.as_n().ok())),
+            "N",
+        ),
+        "SS" => (array(JsonValue::from("string")), "SS"),
+        "NS" => (
+            array(number_type(
+                values.iter().filter_map(|v| v.as_ns().ok()).flatten(),
+            )),
+            "NS",
+        ),
+        "BS" => (array(JsonValue::from("bytes")), "BS"),
+        _ => (JsonValue::from("string"), JSON_TYPE),
+    }
+}
+
+fn number_type<'a>(mut numbers: impl Iterator<Item = &'a String>) -> JsonValue {
+    if numbers.all(|n| n.parse::<i64>().is_ok()) {
+        JsonValue::from("long")
+    } else {
+        JsonValue::from("double")
+    }
+}
+
+fn ddb_type(attrval: &AttributeValue) -> &'static str {
+    match attrval {
+        AttributeValue::S(_) => "S",
+        AttributeValue::N(_) => "N",
+        AttributeValue::B(_) => "B",
+        AttributeValue::Bool(_) => "BOOL",
+        AttributeValue::Null(_) => "NULL",
+        AttributeValue::Ss(_) => "SS",
+        AttributeValue::Ns(_) => "NS",
+        AttributeValue::Bs(_) => "BS",
+        AttributeValue::L(_) => "L",
+        AttributeValue::M(_) => "M",
+        _ => JSON_TYPE,
+    }
+}
+
+fn field_name(field: &JsonValue) -> &str {
+    field["name"].as_str().unwrap_or_default()
+}
+
+fn field_attribute(field: &JsonValue) -> &str {
+    field
+        .get(ATTRIBUTE_PROPERTY)
+        .and_then(JsonValue::as_str)
+        .unwrap_or_else(|| field_name(field))
+}
+
+fn record_to_avro(
+    item: &HashMap<String, AttributeValue>,
+    fields: &[&JsonValue],
+) -> Result<AvroValue, AvroError> {
+    let mut record: Vec<(String, AvroValue)> = Vec::with_capacity(fields.len());
+    for field in fields {
+        let attribute = field_attribute(field);
+        let value: Option<AttributeValue> = match item.get(attribute) {
+            Some(AttributeValue::Null(_)) | None => None,
+            Some(v) if field.get(TYPE_PROPERTY).and_then(JsonValue::as_str) == Some(JSON_TYPE) => {
+                Some(AttributeValue::S(data::attrval_to_jsonval(v).to_string()))
+            }
+            Some(v) => Some(v.clone()),
+        };
+        record.push((
+            field_name(field).to_owned(),
+            to_avro(attribute, value.as_ref(), &field["type"])?,
+        ));
+    }
+    Ok(AvroValue::Record(record))
+}
+
+/// Converts an attribute value (None if missing or NULL) to a value of the given Avro type.
+/// The first variant of a union which the value can be converted to is used.
+fn to_avro(
+    attribute: &str,
+    value: Option<&AttributeValue>,
+    schema: &JsonValue,
+) -> Result<AvroValue, AvroError> {
+    let mismatch = || AvroError::Conversion(attribute.to_owned(), schema.to_string());
+    match schema {
+        JsonValue::Array(variants) => variants
+            .iter()
+            .enumerate()
+            .find_map(|(i, variant)| {
+                to_avro(attribute, value, variant)
+                    .ok()
+                    .map(|v| AvroValue::Union(i as u32, Box::new(v)))
+            })
+            .ok_or_else(mismatch),
+        JsonValue::Object(o) => match (o.get("type").and_then(JsonValue::as_str), value) {
+            (Some("record"), Some(AttributeValue::M(m))) => {
+                let fields: Vec<&JsonValue> = o
+                    .get("fields")
+                    .and_then(JsonValue::as_array)
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                record_to_avro(m, &fields)
+            }
+            (Some("array"), Some(v)) => {
+                let elements: Vec<AttributeValue> = match v {
+                    AttributeValue::Ss(ss) => ss.iter().cloned().map(AttributeValue::S).collect(),
+                    AttributeValue::Ns(ns) => ns.iter().cloned().map(AttributeValue::N).collect(),
+                    AttributeValue::Bs(bs) => bs.iter().cloned().map(AttributeValue::B).collect(),
+                    AttributeValue::L(l) => l.clone(),
+                    _ => return Err(mismatch()),
+                };
+                elements
+                    .iter()
+                    .map(|e| to_avro(attribute, Some(e), &o["items"]))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(AvroValue::Array)
+            }
+            (Some("map"), Some(AttributeValue::M(m))) => m
+                .iter()
+                .map(|(k, v)| {
+                    Ok::<_, AvroError>((k.to_owned(), to_avro(attribute, Some(v), &o["values"])?))
+                })
+                .collect::<Result<HashMap<_, _>, _>>()
+                .map(AvroValue::Map),
+            (Some("enum"), Some(AttributeValue::S(s))) => o
+                .get("symbols")
+                .and_then(JsonValue::as_array)
+                .and_then(|symbols| symbols.iter().position(|symbol| symbol == s))
+                .map(|i| AvroValue::Enum(i as u32, s.to_owned()))
+                .ok_or_else(mismatch),
+            // A primitive type in the object form, e.g. {"type": "string"}
+            _ => match o.get("type") {
+                Some(t @ JsonValue::String(_)) => to_avro(attribute, value, t),
+                _ => Err(mismatch()),
+            },
+        },
+        JsonValue::String(t) => match (t.as_str(), value) {
+            ("null", None) => Ok(AvroValue::Null),
+            ("string", Some(AttributeValue::S(s) | AttributeValue::N(s))) => {
+                Ok(AvroValue::String(s.to_owned()))
+            }
+            ("string", Some(AttributeValue::Bool(b))) => Ok(AvroValue::String(b.to_string())),
+            ("string", Some(v)) if !matches!(v, AttributeValue::B(_)) => {
+                Ok(AvroValue::String(data::attrval_to_jsonval(v).to_string()))
+            }
+            ("long", Some(AttributeValue::N(n))) => {
+                n.parse().map(AvroValue::Long).map_err(|_| mismatch())
+            }
+            ("int", Some(AttributeValue::N(n))) => {
+                n.parse().map(AvroValue::Int).map_err(|_| mismatch())
+            }
+            ("double", Some(AttributeValue::N(n))) => {
+                n.parse().map(AvroValue::Double).map_err(|_| mismatch())
+            }
+            ("float", Some(AttributeValue::N(n))) => {
+                n.parse().map(AvroValue::Float).map_err(|_| mismatch())
+            }
+            ("boolean", Some(AttributeValue::Bool(b))) => Ok(AvroValue::Boolean(*b)),
+            ("bytes", Some(AttributeValue::B(b))) => Ok(AvroValue::Bytes(b.as_ref().to_vec())),
+            _ => Err(mismatch()),
+        },
+        _ => Err(mismatch()),
+    }
+}
+
+fn avro_to_json(value: AvroValue) -> Result<JsonValue, AvroError> {
+    let float = |f: f64| {
+        serde_json::Number::from_f64(f)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null)
+    };
+    Ok(match value {
+        AvroValue::Null => JsonValue::Null,
+        AvroValue::Boolean(b) => JsonValue::Bool(b),
+        AvroValue::Int(n) | AvroValue::Date(n) | AvroValue::TimeMillis(n) => JsonValue::from(n),
+        AvroValue::Long(n)
+        | AvroValue::TimeMicros(n)
+        | AvroValue::TimestampMillis(n)
+        | AvroValue::TimestampMicros(n) => JsonValue::from(n),
+        AvroValue::Float(f) => float(f as f64),
+        AvroValue::Double(f) => float(f),
+        AvroValue::Bytes(b) | AvroValue::Fixed(_, b) => {
+            JsonValue::from(aws_smithy_types::base64::encode(b))
+        }
+        AvroValue::String(s) | AvroValue::Enum(_, s) => JsonValue::String(s),
+        AvroValue::Uuid(u) => JsonValue::String(u.to_string()),
+        AvroValue::Union(_, v) => avro_to_json(*v)?,
+        AvroValue::Array(values) => JsonValue::Array(
+            values
+                .into_iter()
+                .map(avro_to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        AvroValue::Map(map) => JsonValue::Object(
+            map.into_iter()
+                .map(|(k, v)| Ok((k, avro_to_json(v)?)))
+                .collect::<Result<_, AvroError>>()?,
+        ),
+        AvroValue::Record(fields) => JsonValue::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| Ok((k, avro_to_json(v)?)))
+                .collect::<Result<_, AvroError>>()?,
+        ),
+        other => return Err(AvroError::UnsupportedValue(format!("{:?}", other))),
+    })
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ddb::table;
+    use aws_sdk_dynamodb::primitives::Blob;
+    use serde_json::json;
+
+    fn table_schema() -> app::TableSchema {
+        app::TableSchema {
+            region: String::from("us-east-1"),
+            name: String::from("my-table"),
+            pk: key::Key {
+                name: String::from("id"),
+                kind: key::KeyType::N,
+            },
+            sk: None,
+            indexes: None,
+            mode: table::Mode::OnDemand,
+            ttl: None,
+        }
+    }
+
+    fn items() -> Vec<HashMap<String, AttributeValue>> {
+        vec![
+            HashMap::from([
+                (String::from("id"), AttributeValue::N(String::from("1"))),
+                (String::from("name"), AttributeValue::S(String::from("a"))),
+                (
+                    String::from("price"),
+                    AttributeValue::N(String::from("1.5")),
+                ),
+                (
+                    String::from("tags"),
+                    AttributeValue::Ss(vec![String::from("x"), String::from("y")]),
+                ),
+                (
+                    String::from("data"),
+                    AttributeValue::B(Blob::new(b"\x00\x01".to_vec())),
+                ),
+            ]),
+            HashMap::from([
+                (String::from("id"), AttributeValue::N(String::from("2"))),
+                (String::from("name"), AttributeValue::Null(true)),
+                (
+                    String::from("price"),
+                    AttributeValue::N(String::from("2.5")),
+                ),
+                (
+                    String::from("first-seen"),
+                    AttributeValue::M(HashMap::from([(
+                        String::from("by"),
+                        AttributeValue::S(String::from("me")),
+                    )])),
+                ),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn test_derive_schema() {
+        let schema = ItemSchema::derive(&table_schema(), &items()).unwrap();
+        assert_eq!(
+            schema.json(),
+            &json!({
+                "type": "record",
+                "name": "my_table",
+                "fields": [
+                    {"name": "id", "type": "long", "ddbType": "N"},
+                    {"name": "data", "type": ["null", "bytes"], "default": null, "ddbType": "B"},
+                    {"name": "first_seen", "type": ["null", "string"], "default": null, "ddbAttribute": "first-seen", "ddbType": "JSON"},
+                    {"name": "name", "type": ["null", "string"], "default": null, "ddbType": "S"},
+                    {"name": "price", "type": ["null", "double"], "default": null, "ddbType": "N"},
+                    {"name": "tags", "type": ["null", {"type": "array", "items": "string"}], "default": null, "ddbType": "SS"},
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_and_read_items() {
+        let items = items();
+        let schema = ItemSchema::derive(&table_schema(), &items[1..]).unwrap();
+        let mut writer = schema.writer(Vec::new()).unwrap();
+        let mut dropped: Vec<String> = vec![];
+        for item in &items {
+            let (record, mut d) = schema.to_record(item).unwrap();
+            writer.append(record).unwrap();
+            dropped.append(&mut d);
+        }
+        assert_eq!(dropped, vec!["data", "tags"]);
+
+        let read = read_items(&writer.into_inner().unwrap()[..]).unwrap();
+        assert_eq!(
+            read,
+            vec![
+                json!({"id": 1, "name": "a", "price": 1.5}),
+                json!({"id": 2, "price": 2.5, "first-seen": {"by": "me"}}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_given_schema() {
+        let schema = ItemSchema::parse(
+            r#"{"type": "record", "name": "Item", "fields": [
+                {"name": "id", "type": "string"},
+                {"name": "price", "type": ["null", "long", "double"], "default": null}
+            ]}"#,
+        )
+        .unwrap();
+        let (record, dropped) = schema.to_record(&items()[0]).unwrap();
+        assert_eq!(
+            record,
+            AvroValue::Record(vec![
+                (String::from("id"), AvroValue::String(String::from("1"))),
+                (
+                    String::from("price"),
+                    AvroValue::Union(2, Box::new(AvroValue::Double(1.5)))
+                ),
+            ])
+        );
+        assert_eq!(dropped, vec!["data", "name", "tags"]);
+
+        assert!(matches!(
+            ItemSchema::parse(r#"{"type": "array", "items": "string"}"#),
+            Err(AvroError::InvalidSchema(_))
+        ));
+    }
+}
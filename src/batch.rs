@@ -16,19 +16,25 @@
 
 use crate::parser::DyneinParser;
 use aws_sdk_dynamodb::{
-    operation::batch_write_item::BatchWriteItemError,
-    types::{AttributeValue, DeleteRequest, PutRequest, WriteRequest},
+    operation::{
+        batch_get_item::BatchGetItemError, batch_write_item::BatchWriteItemError,
+        update_item::UpdateItemError,
+    },
+    primitives::Blob,
+    types::{AttributeValue, DeleteRequest, KeysAndAttributes, PutRequest, WriteRequest},
     Client as DynamoDbSdkClient,
 };
 use base64::{engine::general_purpose, Engine as _};
 use bytes::Bytes;
 use log::{debug, error};
+use serde::Deserialize;
 use serde_json::Value as JsonValue;
-use std::{collections::HashMap, error, fmt, fs, io::Error as IOError};
+use std::{collections::HashMap, error, fmt, fs, io::Error as IOError, time::Duration};
 
 use super::app;
 use super::data;
-use super::ddb::key;
+use super::ddb::{key, size};
+use super::metrics;
 
 /* =================================================
 struct / enum / const
@@ -39,8 +45,12 @@ pub enum DyneinBatchError {
     LoadData(IOError),
     PraseJSON(serde_json::Error),
     BatchWriteError(aws_sdk_dynamodb::error::SdkError<BatchWriteItemError>),
+    UpdateItemError(aws_sdk_dynamodb::error::SdkError<UpdateItemError>),
     InvalidInput(String),
     ParseError(crate::parser::ParseError),
+    TransformError(crate::transform::TransformError),
+    SqlDumpError(crate::sqldump::SqlDumpError),
+    AvroError(crate::avro::AvroError),
 }
 impl fmt::Display for DyneinBatchError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -48,8 +58,12 @@ impl fmt::Display for DyneinBatchError {
             DyneinBatchError::LoadData(ref e) => e.fmt(f),
             DyneinBatchError::PraseJSON(ref e) => e.fmt(f),
             DyneinBatchError::BatchWriteError(ref e) => e.fmt(f),
+            DyneinBatchError::UpdateItemError(ref e) => e.fmt(f),
             DyneinBatchError::InvalidInput(ref msg) => write!(f, "{}", msg),
             DyneinBatchError::ParseError(ref e) => e.fmt(f),
+            DyneinBatchError::TransformError(ref e) => e.fmt(f),
+            DyneinBatchError::SqlDumpError(ref e) => e.fmt(f),
+            DyneinBatchError::AvroError(ref e) => e.fmt(f),
         }
     }
 }
@@ -59,8 +73,12 @@ impl error::Error for DyneinBatchError {
             DyneinBatchError::LoadData(ref e) => Some(e),
             DyneinBatchError::PraseJSON(ref e) => Some(e),
             DyneinBatchError::BatchWriteError(ref e) => Some(e),
+            DyneinBatchError::UpdateItemError(ref e) => Some(e),
             DyneinBatchError::InvalidInput(_) => None,
             DyneinBatchError::ParseError(_) => None,
+            DyneinBatchError::TransformError(ref e) => Some(e),
+            DyneinBatchError::SqlDumpError(ref e) => Some(e),
+            DyneinBatchError::AvroError(ref e) => Some(e),
         }
     }
 }
@@ -79,6 +97,11 @@ impl From<aws_sdk_dynamodb::error::SdkError<BatchWriteItemError>> for DyneinBatc
         Self::BatchWriteError(e)
     }
 }
+impl From<aws_sdk_dynamodb::error::SdkError<UpdateItemError>> for DyneinBatchError {
+    fn from(e: aws_sdk_dynamodb::error::SdkError<UpdateItemError>) -> Self {
+        Self::UpdateItemError(e)
+    }
+}
 
 impl From<crate::parser::ParseError> for DyneinBatchError {
     fn from(e: crate::parser::ParseError) -> Self {
@@ -86,6 +109,36 @@ impl From<crate::parser::ParseError> for DyneinBatchError {
     }
 }
 
+impl From<crate::transform::TransformError> for DyneinBatchError {
+    fn from(e: crate::transform::TransformError) -> Self {
+        Self::TransformError(e)
+    }
+}
+
+impl From<crate::sqldump::SqlDumpError> for DyneinBatchError {
+    fn from(e: crate::sqldump::SqlDumpError) -> Self {
+        Self::SqlDumpError(e)
+    }
+}
+
+impl From<crate::avro::AvroError> for DyneinBatchError {
+    fn from(e: crate::avro::AvroError) -> Self {
+        Self::AvroError(e)
+    }
+}
+
+impl From<key::InvalidKeyError> for DyneinBatchError {
+    fn from(e: key::InvalidKeyError) -> Self {
+        Self::InvalidInput(e.to_string())
+    }
+}
+
+impl From<size::ItemTooLargeError> for DyneinBatchError {
+    fn from(e: size::ItemTooLargeError) -> Self {
+        Self::InvalidInput(e.to_string())
+    }
+}
+
 impl From<dialoguer::Error> for DyneinBatchError {
     fn from(e: dialoguer::Error) -> Self {
         match e {
@@ -94,6 +147,109 @@ impl From<dialoguer::Error> for DyneinBatchError {
     }
 }
 
+/// Items to put/delete for a table in `dy bwrite --table-map`.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct TableMapEntry {
+    #[serde(default)]
+    put: Vec<JsonValue>,
+    #[serde(default)]
+    del: Vec<JsonValue>,
+}
+
+/// Maximum number of retries for unprocessed items of a BatchWriteItem request, e.g. due to throttling.
+const MAX_UNPROCESSED_RETRIES: u32 = 10;
+/// Unprocessed items are retried with exponential backoff, starting from this interval.
+const UNPROCESSED_RETRY_BASE_INTERVAL: Duration = Duration::from_millis(50);
+const UNPROCESSED_RETRY_MAX_INTERVAL: Duration = Duration::from_secs(5);
+/// Maximum number of put and delete requests in a BatchWriteItem request.
+const BATCH_WRITE_ITEM_MAX_REQUESTS: usize = 25;
+/// Maximum number of keys in a BatchGetItem request.
+const BATCH_GET_ITEM_MAX_KEYS: usize = 100;
+
+/// Metrics of batch writes, shown as a summary after `dy bwrite` and `dy import`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BatchWriteStats {
+    /// Number of BatchWriteItem calls, including retries.
+    pub batches: usize,
+    pub written: usize,
+    pub failed: usize,
+    /// Number of retries for unprocessed items.
+    pub retries: usize,
+    /// Total time waited before retrying unprocessed items.
+    pub throttle_time: Duration,
+}
+
+impl BatchWriteStats {
+    pub fn summary(&self) -> String {
+        format!(
+            "Batches sent: {}, items written: {}, items failed: {}, retries: {}, throttle time: {:.2}s",
+            self.batches,
+            self.written,
+            self.failed,
+            self.retries,
+            self.throttle_time.as_secs_f64()
+        )
+    }
+}
+
+/// How to handle write requests for the same primary key in a table, given by `dy bwrite --on-duplicate`.
+/// BatchWriteItem rejects the whole request if it contains such requests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicatePolicy {
+    /// Abort without writing any item (default).
+    Error,
+    /// Keep only the last request for each key.
+    Dedupe,
+    /// Write requests for the same key in separate batches, in the given order.
+    Split,
+}
+
+impl DuplicatePolicy {
+    pub fn new(policy: Option<&str>) -> DuplicatePolicy {
+        match policy {
+            None | Some("error") => DuplicatePolicy::Error,
+            Some("dedupe") => DuplicatePolicy::Dedupe,
+            Some("split") => DuplicatePolicy::Split,
+            Some(o) => panic!("Invalid duplicate policy is given: {}", o),
+        }
+    }
+}
+
+/// Whether arrays in top-level attributes are inferred as sets (SS/NS) on import.
+/// Attributes in list_attrs are always imported as lists, and ones in set_attrs are inferred regardless of `enabled`.
+#[derive(Debug, Default)]
+pub struct SetInference {
+    pub enabled: bool,
+    pub set_attrs: Vec<String>,
+    pub list_attrs: Vec<String>,
+}
+
+impl SetInference {
+    pub fn is_enabled_for(&self, attr_name: &str) -> bool {
+        if self.list_attrs.iter().any(|a| a == attr_name) {
+            false
+        } else {
+            self.enabled || self.set_attrs.iter().any(|a| a == attr_name)
+        }
+    }
+
+    /// Returns true if any attribute can be inferred as a set.
+    pub fn is_active(&self) -> bool {
+        self.enabled || !self.set_attrs.is_empty()
+    }
+}
+
+/// Write requests given to `$ dy bwrite` (or keys to delete by `$ dy del --keys-file`), see `batch_write_item`.
+#[derive(Default)]
+pub struct BatchWriteSources {
+    pub puts: Option<Vec<String>>,
+    pub dels: Option<Vec<String>>,
+    pub keys_file: Option<String>,
+    pub table_map: Option<String>,
+    pub input_file: Option<String>,
+}
+
 /* =================================================
 Public functions
 ================================================= */
@@ -239,95 +395,214 @@ async fn batch_write_item_api(
         .await;
     let ddb = DynamoDbSdkClient::new(&config);
 
-    match ddb
+    let call = ddb
         .batch_write_item()
         .set_request_items(Some(request_items))
-        .send()
-        .await
-    {
+        .send();
+    match metrics::timed("BatchWriteItem", call).await {
         Ok(res) => Ok(res.unprocessed_items),
         Err(e) => Err(e),
     }
 }
 
-// Basically this function is intended to be defined as `pub async fn`.
-// However, to recursively use async function, you have to return a future wrapped by pinned box. For more details: `rustc --explain E0733`.
+/// Writes items until all of them are processed, retrying unprocessed items without limit.
 pub async fn batch_write_until_processed(
     cx: &app::Context,
     mut request_items: HashMap<String, Vec<WriteRequest>>,
 ) -> Result<(), aws_sdk_dynamodb::error::SdkError<BatchWriteItemError>> {
-    loop {
-        request_items = match batch_write_item_api(cx, request_items).await {
-            Ok(result) => {
-                let unprocessed_items: HashMap<String, Vec<WriteRequest>> =
-                    result.expect("alwasy wrapped by Some");
-                if !unprocessed_items.is_empty() {
-                    // if there are any unprocessed items, retry rest items
-                    debug!("UnprocessedItems: {:?}", &unprocessed_items);
-                    unprocessed_items
-                } else {
-                    return Ok(());
-                }
+    let mut stats = BatchWriteStats::default();
+    while !request_items.is_empty() {
+        request_items = batch_write_with_stats(cx, request_items, &mut stats).await?;
+    }
+    Ok(())
+}
+
+/// Writes items with BatchWriteItem, retrying unprocessed items with exponential backoff up to MAX_UNPROCESSED_RETRIES times.
+/// Returns items which are still unprocessed after retries, which is empty if all items are written.
+/// Calls, written items and retries are added to `stats`, while failed items are left for the caller to count.
+pub async fn batch_write_with_stats(
+    cx: &app::Context,
+    mut request_items: HashMap<String, Vec<WriteRequest>>,
+    stats: &mut BatchWriteStats,
+) -> Result<
+    HashMap<String, Vec<WriteRequest>>,
+    aws_sdk_dynamodb::error::SdkError<BatchWriteItemError>,
+> {
+    let mut interval = UNPROCESSED_RETRY_BASE_INTERVAL;
+    for retry in 0..=MAX_UNPROCESSED_RETRIES {
+        if retry > 0 {
+            // UnprocessedItems are mostly caused by throttling, so wait a moment before retrying them.
+            debug!("Retrying UnprocessedItems in {:?}", interval);
+            tokio::time::sleep(interval).await;
+            stats.retries += 1;
+            stats.throttle_time += interval;
+            interval = (interval * 2).min(UNPROCESSED_RETRY_MAX_INTERVAL);
+        }
+        let requested = count_write_requests(&request_items);
+        stats.batches += 1;
+        let unprocessed_items: HashMap<String, Vec<WriteRequest>> =
+            batch_write_item_api(cx, request_items)
+                .await?
+                .expect("alwasy wrapped by Some");
+        stats.written += requested - count_write_requests(&unprocessed_items);
+        if unprocessed_items.is_empty() {
+            return Ok(unprocessed_items);
+        }
+        debug!("UnprocessedItems: {:?}", &unprocessed_items);
+        request_items = unprocessed_items;
+    }
+    Ok(request_items)
+}
+
+pub fn count_write_requests(request_items: &HashMap<String, Vec<WriteRequest>>) -> usize {
+    request_items.values().map(|requests| requests.len()).sum()
+}
+
+/// Reads items of a table by their keys with BatchGetItem, sending up to 100 keys per request.
+/// Unprocessed keys are retried in the same way as unprocessed items of BatchWriteItem.
+/// Returns found items in no particular order, and keys which are still unprocessed after retries.
+pub async fn batch_get_items(
+    cx: &app::Context,
+    table_name: &str,
+    keys: Vec<HashMap<String, AttributeValue>>,
+    consistent_read: bool,
+) -> Result<
+    (
+        Vec<HashMap<String, AttributeValue>>,
+        Vec<HashMap<String, AttributeValue>>,
+    ),
+    aws_sdk_dynamodb::error::SdkError<BatchGetItemError>,
+> {
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    let mut items = vec![];
+    let mut unprocessed = vec![];
+    for chunk in keys.chunks(BATCH_GET_ITEM_MAX_KEYS) {
+        let mut keys = chunk.to_vec();
+        let mut interval = UNPROCESSED_RETRY_BASE_INTERVAL;
+        for retry in 0..=MAX_UNPROCESSED_RETRIES {
+            if retry > 0 {
+                debug!("Retrying UnprocessedKeys in {:?}", interval);
+                tokio::time::sleep(interval).await;
+                interval = (interval * 2).min(UNPROCESSED_RETRY_MAX_INTERVAL);
+            }
+            let request = KeysAndAttributes::builder()
+                .set_keys(Some(keys))
+                .consistent_read(consistent_read)
+                .build()
+                .unwrap();
+            let res = ddb
+                .batch_get_item()
+                .request_items(table_name, request)
+                .send()
+                .await?;
+            items.extend(
+                res.responses
+                    .and_then(|mut responses| responses.remove(table_name))
+                    .unwrap_or_default(),
+            );
+            keys = res
+                .unprocessed_keys
+                .and_then(|mut unprocessed_keys| unprocessed_keys.remove(table_name))
+                .map(|k| k.keys)
+                .unwrap_or_default();
+            if keys.is_empty() {
+                break;
             }
-            Err(e) => return Err(e),
+            debug!("UnprocessedKeys: {:?}", &keys);
         }
+        unprocessed.extend(keys);
     }
+    Ok((items, unprocessed))
 }
 
 /// This function is intended to be called from main.rs, as a destination of bwrite command.
-/// It executes batch write operations based on the provided `puts`, `dels`, and `input_file` arguments.
-/// At least one argument `puts`, `dels` or `input_file` is required, and all arguments can be specified simultaneously.
+/// It executes batch write operations based on the provided `puts`, `dels`, `keys_file`, `table_map` and `input_file` sources.
+/// At least one argument `puts`, `dels`, `keys_file`, `table_map` or `input_file` is required, and all arguments can be specified simultaneously.
+/// `puts`, `dels` and keys listed in `keys_file` target the effective table, while `table_map` is a JSON object which maps table names to items to put/delete,
+/// e.g. `{"TableA": {"put": [{"pk": "1"}], "del": [{"pk": "2"}]}, "TableB": {"del": ["{pk: \"3\"}"]}}`.
+/// Deleting items asks for confirmation if `safety.require_confirmation` contains "delete", unless `yes` is true.
+/// Requests for the same primary key in a table are handled according to `on_duplicate`.
 pub async fn batch_write_item(
     cx: &app::Context,
-    puts: Option<Vec<String>>,
-    dels: Option<Vec<String>>,
-    input_file: Option<String>,
+    sources: BatchWriteSources,
+    on_duplicate: DuplicatePolicy,
+    yes: bool,
 ) -> Result<(), DyneinBatchError> {
+    let BatchWriteSources {
+        puts,
+        dels,
+        keys_file,
+        table_map,
+        input_file,
+    } = sources;
     // validate the input arguments
-    if puts.is_none() && dels.is_none() && input_file.is_none() {
+    if puts.is_none()
+        && dels.is_none()
+        && keys_file.is_none()
+        && table_map.is_none()
+        && input_file.is_none()
+    {
         return Err(DyneinBatchError::InvalidInput(String::from(
             "must provide at least one argument for 'bwrite' command",
         )));
     }
 
     let mut bwrite_items = HashMap::<String, Vec<WriteRequest>>::new();
+    let mut schemas = HashMap::<String, app::TableSchema>::new();
+    let parser = DyneinParser::new();
+    let strict = cx.should_strict();
 
-    // Only use write_requests, parser and ts if `--puts` or `--dels` option is provided.
-    if puts.is_some() || dels.is_some() {
-        let mut write_requests = Vec::<WriteRequest>::new();
-        let parser = DyneinParser::new();
+    // Only retrieve schema of the effective table if `--puts`, `--dels` or `--keys-file` option is provided.
+    if puts.is_some() || dels.is_some() || keys_file.is_some() {
         let ts: app::TableSchema = app::table_schema(cx).await;
-
-        if let Some(items) = puts {
-            for item in items.iter() {
-                let attrs = parser.parse_dynein_format(None, item)?;
-                validate_item_keys(&attrs, &ts)?;
-                write_requests.push(
-                    WriteRequest::builder()
-                        .put_request(PutRequest::builder().set_item(Some(attrs)).build().unwrap())
-                        .build(),
-                );
-            }
+        let mut write_requests = build_write_requests(
+            &parser,
+            strict,
+            &ts,
+            &puts.unwrap_or_default(),
+            &dels.unwrap_or_default(),
+        )?;
+        if let Some(path) = keys_file {
+            let keys = data::read_keys_file(&ts, &path).map_err(DyneinBatchError::InvalidInput)?;
+            write_requests.extend(keys.into_iter().map(|key| {
+                WriteRequest::builder()
+                    .delete_request(DeleteRequest::builder().set_key(Some(key)).build().unwrap())
+                    .build()
+            }));
         }
+        bwrite_items.insert(ts.name.to_owned(), write_requests);
+        schemas.insert(ts.name.to_owned(), ts);
+    }
 
-        if let Some(keys) = dels {
-            for key in keys.iter() {
-                let attrs = parser.parse_dynein_format(None, key)?;
-                validate_item_keys(&attrs, &ts)?;
-                write_requests.push(
-                    WriteRequest::builder()
-                        .delete_request(
-                            DeleteRequest::builder()
-                                .set_key(Some(attrs))
-                                .build()
-                                .unwrap(),
-                        )
-                        .build(),
-                );
+    if let Some(table_map) = table_map {
+        let table_map: HashMap<String, TableMapEntry> = serde_json::from_str(&table_map)?;
+        for (tbl, entry) in table_map {
+            // A table ARN is accepted as long as the table is in the same region, as BatchWriteItem is sent to one region.
+            let table_cx = cx.clone().with_table(&tbl);
+            if table_cx.effective_region().await != cx.effective_region().await {
+                return Err(DyneinBatchError::InvalidInput(format!(
+                    "the table '{}' is not in the region '{}'. Tables in --table-map should be in the same region",
+                    tbl,
+                    cx.effective_region().await
+                )));
             }
+            let ts: app::TableSchema =
+                app::table_schema_by_name(cx, table_cx.effective_table_name()).await;
+            let mut ops = build_write_requests(
+                &parser,
+                strict,
+                &ts,
+                &entry.put.iter().map(dynein_format_of).collect::<Vec<_>>(),
+                &entry.del.iter().map(dynein_format_of).collect::<Vec<_>>(),
+            )?;
+            bwrite_items
+                .entry(ts.name.to_owned())
+                .and_modify(|e| e.append(&mut ops))
+                .or_insert(ops);
+            schemas.insert(ts.name.to_owned(), ts);
         }
-
-        bwrite_items.insert(ts.name, write_requests);
     }
 
     if let Some(file_path) = input_file {
@@ -346,104 +621,123 @@ pub async fn batch_write_item(
     }
 
     debug!("built items for batch: {:?}", bwrite_items);
-    batch_write_item_api(cx, bwrite_items).await?;
-    Ok(())
-}
+    let deletes = bwrite_items
+        .values()
+        .flatten()
+        .filter(|req| req.delete_request.is_some())
+        .count();
+    let tables: Vec<String> = bwrite_items.keys().cloned().collect();
 
-/// This function takes cx (just for table name) and Vec<JsonValue>, where this JsonValue consists of multiple items as a standard JSON format,
-///   then returns a HashMap from table name to Vec<WriteRequest>.
-///   The returned HashMap can be used for a value of "RequestItems" parameter in BatchWriteItem API. https://docs.aws.amazon.com/amazondynamodb/latest/APIReference/API_BatchWriteItem.html
-/// Note that this function assumes that target table is only one table.
-pub async fn convert_jsonvals_to_request_items(
-    cx: &app::Context,
-    items_jsonval: Vec<JsonValue>,
-    enable_set_inference: bool,
-) -> Result<HashMap<String, Vec<WriteRequest>>, DyneinBatchError> {
-    let mut results = HashMap::<String, Vec<WriteRequest>>::new();
-    let mut write_requests = Vec::<WriteRequest>::new();
-
-    for item_jsonval in items_jsonval {
-        // Focusing on an item - iterate over attributes in an item.
-        let mut item = HashMap::<String, AttributeValue>::new();
-        for (attr_name, body) in item_jsonval
-            .as_object()
-            .expect("should be valid JSON object")
-            .iter()
+    // Requests are sent in one or more rounds of BatchWriteItem. The n-th round contains the n-th batch of each table.
+    let mut rounds: Vec<HashMap<String, Vec<WriteRequest>>> = vec![];
+    for (tbl, requests) in bwrite_items {
+        let ts: app::TableSchema = match schemas.remove(&tbl) {
+            Some(ts) => ts,
+            None => app::table_schema_by_name(cx, tbl.to_owned()).await,
+        };
+        for (i, batch) in resolve_duplicate_keys(&ts, requests, on_duplicate)?
+            .into_iter()
+            .enumerate()
         {
-            item.insert(
-                attr_name.to_string(),
-                data::dispatch_jsonvalue_to_attrval(body, enable_set_inference),
-            );
+            if rounds.len() <= i {
+                rounds.push(HashMap::new());
+            }
+            rounds[i].insert(tbl.to_owned(), batch);
         }
+    }
 
-        // Fill meaningful put_request here, then push it to the write_requests. Then go to the next item.
-        write_requests.push(
-            WriteRequest::builder()
-                .put_request(PutRequest::builder().set_item(Some(item)).build().unwrap())
-                .build(),
+    if deletes > 0 {
+        app::confirm_operation(cx, app::SafetyOperation::Delete, yes, || {
+            format!(
+                "You're trying to delete {} items from the table(s) '{}'",
+                deletes,
+                tables.join("', '")
+            )
+        })
+        .await;
+    }
+    let mut stats = BatchWriteStats::default();
+    for request_items in rounds.into_iter().flat_map(chunk_round) {
+        let unprocessed_items = batch_write_with_stats(cx, request_items, &mut stats).await?;
+        stats.failed += count_write_requests(&unprocessed_items);
+    }
+    println!("{}", stats.summary());
+    if stats.failed > 0 {
+        error!(
+            "{} items were not written as they remained unprocessed after {} retries.",
+            stats.failed, MAX_UNPROCESSED_RETRIES
         );
+        std::process::exit(1);
     }
+    Ok(())
+}
 
-    // A single table name as a key, and insert all (up to 25) write_requests under the single table.
-    results.insert(cx.effective_table_name(), write_requests);
+/// This function takes a JsonValue which represents an item as a standard JSON format (e.g. a line of JSONL file),
+///   then returns a WriteRequest to put the item, which can be used in "RequestItems" parameter of BatchWriteItem API.
+///   https://docs.aws.amazon.com/amazondynamodb/latest/APIReference/API_BatchWriteItem.html
+pub fn jsonval_to_put_request(
+    item_jsonval: &JsonValue,
+    set_inference: &SetInference,
+    binary_attributes: &[String],
+) -> Result<WriteRequest, DyneinBatchError> {
+    let attributes = item_jsonval.as_object().ok_or_else(|| {
+        DyneinBatchError::InvalidInput(format!("an item should be a JSON object: {}", item_jsonval))
+    })?;
 
-    Ok(results)
+    // Focusing on an item - iterate over attributes in an item.
+    let mut item = HashMap::<String, AttributeValue>::new();
+    for (attr_name, body) in attributes {
+        let attrval = if binary_attributes.contains(attr_name) {
+            base64_jsonval_to_attrval(attr_name, body)?
+        } else {
+            data::dispatch_jsonvalue_to_attrval(body, set_inference.is_enabled_for(attr_name))
+        };
+        item.insert(attr_name.to_string(), attrval);
+    }
+
+    Ok(WriteRequest::builder()
+        .put_request(PutRequest::builder().set_item(Some(item)).build().unwrap())
+        .build())
 }
 
-/// "matrix" is a vector of vectors. These internal vectors has strs, each of them is an attribute for an item.
+/// "cells" are strs in a line of CSV file, each of them is an attribute for an item. "headers" are attribute names for each cell.
 ///
 /// e.g.
 ///    name, age, fruit ... headers
-/// [[John, 12, Apple],
-///  [Ami, 23, Orange],
-///  [Shu, 42, Banana]] ... matrix
-pub async fn csv_matrix_to_request_items(
-    cx: &app::Context,
-    matrix: &[Vec<&str>],
+///  [John, 12, Apple]  ... cells
+pub fn csv_cells_to_put_request(
+    cells: &[&str],
     headers: &[&str],
-    enable_set_inference: bool,
-) -> Result<HashMap<String, Vec<WriteRequest>>, DyneinBatchError> {
-    let total_elements_in_matrix: usize = matrix
-        .iter()
-        .map(|x| x.len())
-        .collect::<Vec<usize>>()
-        .iter()
-        .sum::<usize>();
-    if (headers.len() * matrix.len()) != total_elements_in_matrix {
-        error!("cells in the 'matrix' should have exact the same number of elements of 'headers'");
-        std::process::exit(1);
+    set_inference: &SetInference,
+    binary_attributes: &[String],
+) -> Result<WriteRequest, DyneinBatchError> {
+    if headers.len() != cells.len() {
+        return Err(DyneinBatchError::InvalidInput(format!(
+            "a line should have exact the same number of cells as the header ({}), but got {}",
+            headers.len(),
+            cells.len()
+        )));
     }
 
-    let mut results = HashMap::<String, Vec<WriteRequest>>::new();
-    let mut write_requests = Vec::<WriteRequest>::new();
-
-    for cells in matrix {
-        // Build an item. Note that DynamoDB data type of attributes are left to how serde_json::from_str parse the value in the cell.
-        let mut item = HashMap::<String, AttributeValue>::new();
-        for i in 0..headers.len() {
-            let jsonval = serde_json::from_str(cells[i])?;
-            debug!(
-                "CSV cell '{:?}' --serde_json::from_str--> JsonValue: {:?}",
-                cells[i], jsonval
-            );
-            item.insert(
-                headers[i].to_string(),
-                data::dispatch_jsonvalue_to_attrval(&jsonval, enable_set_inference),
-            );
-        }
-
-        // Fill meaningful put_request here, then push it to the write_requests. Then go to the next item.
-        write_requests.push(
-            WriteRequest::builder()
-                .put_request(PutRequest::builder().set_item(Some(item)).build().unwrap())
-                .build(),
+    // Build an item. Note that DynamoDB data type of attributes are left to how serde_json::from_str parse the value in the cell.
+    let mut item = HashMap::<String, AttributeValue>::new();
+    for (header, cell) in headers.iter().zip(cells) {
+        let jsonval = serde_json::from_str(cell)?;
+        debug!(
+            "CSV cell '{:?}' --serde_json::from_str--> JsonValue: {:?}",
+            cell, jsonval
         );
+        let attrval = if binary_attributes.iter().any(|a| a == header) {
+            base64_jsonval_to_attrval(header, &jsonval)?
+        } else {
+            data::dispatch_jsonvalue_to_attrval(&jsonval, set_inference.is_enabled_for(header))
+        };
+        item.insert(header.to_string(), attrval);
     }
 
-    // A single table name as a key, and insert all (up to 25) write_requests under the single table.
-    results.insert(cx.effective_table_name(), write_requests);
-
-    Ok(results)
+    Ok(WriteRequest::builder()
+        .put_request(PutRequest::builder().set_item(Some(item)).build().unwrap())
+        .build())
 }
 
 /* =================================================
@@ -460,7 +754,7 @@ Private functions
 ///         "Threads": Object( { "N": String( "2",), },),
 ///         "Views": Object( { "N": String( "1000",), },),
 ///     },)
-fn ddbjson_attributes_to_attrvals(
+pub fn ddbjson_attributes_to_attrvals(
     ddbjson_attributes: &JsonValue,
 ) -> HashMap<String, AttributeValue> {
     let mut built_attributes = HashMap::<String, AttributeValue>::new();
@@ -560,6 +854,95 @@ fn ddbjson_val_to_attrval(ddb_jsonval: &JsonValue) -> Option<AttributeValue> {
     }
 }
 
+/// Same as `ddbjson_attributes_to_attrvals`, but fails on any attribute which isn't valid DynamoDB JSON instead of skipping it.
+/// Use this when a dropped attribute would be lost, e.g. an item edited by users and written back by PutItem.
+pub fn try_ddbjson_attributes_to_attrvals(
+    ddbjson_attributes: &JsonValue,
+) -> Result<HashMap<String, AttributeValue>, DyneinBatchError> {
+    let attributes = ddbjson_attributes.as_object().ok_or_else(|| {
+        DyneinBatchError::InvalidInput(format!(
+            "attributes should be a JSON object, but got: {}",
+            ddbjson_attributes
+        ))
+    })?;
+    attributes
+        .iter()
+        .map(|(name, body)| {
+            try_ddbjson_val_to_attrval(body)
+                .map(|v| (name.to_owned(), v))
+                .map_err(|msg| {
+                    DyneinBatchError::InvalidInput(format!(
+                        "invalid DynamoDB JSON in attribute '{}': {}",
+                        name, msg
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Fallible version of `ddbjson_val_to_attrval`. A value should be an object with exactly one type descriptor, e.g. {"N": "30"}.
+fn try_ddbjson_val_to_attrval(ddb_jsonval: &JsonValue) -> Result<AttributeValue, String> {
+    let (descriptor, x) = match ddb_jsonval.as_object() {
+        Some(obj) if obj.len() == 1 => obj.iter().next().unwrap(),
+        _ => {
+            return Err(format!(
+                "expected an object with a type descriptor, e.g. {{\"S\": \"...\"}}, but got: {}",
+                ddb_jsonval
+            ))
+        }
+    };
+    let string = |v: &JsonValue| -> Result<String, String> {
+        v.as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| format!("{} value should be a string, but got: {}", descriptor, v))
+    };
+    let array = |v: &JsonValue| -> Result<Vec<JsonValue>, String> {
+        v.as_array()
+            .cloned()
+            .ok_or_else(|| format!("{} value should be an array, but got: {}", descriptor, v))
+    };
+    let binary = |v: &JsonValue| -> Result<Blob, String> {
+        general_purpose::STANDARD
+            .decode(string(v)?)
+            .map(Blob::new)
+            .map_err(|e| format!("failed to decode {} value as base64: {}", descriptor, e))
+    };
+    match descriptor.as_str() {
+        "S" => string(x).map(AttributeValue::S),
+        "N" => string(x).map(AttributeValue::N),
+        "B" => binary(x).map(AttributeValue::B),
+        "BOOL" => x
+            .as_bool()
+            .map(AttributeValue::Bool)
+            .ok_or_else(|| format!("BOOL value should be true or false, but got: {}", x)),
+        "NULL" => Ok(AttributeValue::Null(true)),
+        "SS" => array(x)?
+            .iter()
+            .map(string)
+            .collect::<Result<_, _>>()
+            .map(AttributeValue::Ss),
+        "NS" => array(x)?
+            .iter()
+            .map(string)
+            .collect::<Result<_, _>>()
+            .map(AttributeValue::Ns),
+        "BS" => array(x)?
+            .iter()
+            .map(binary)
+            .collect::<Result<_, _>>()
+            .map(AttributeValue::Bs),
+        "L" => array(x)?
+            .iter()
+            .map(try_ddbjson_val_to_attrval)
+            .collect::<Result<_, _>>()
+            .map(AttributeValue::L),
+        "M" => try_ddbjson_attributes_to_attrvals(x)
+            .map(AttributeValue::M)
+            .map_err(|e| e.to_string()),
+        _ => Err(format!("unsupported type descriptor '{}'", descriptor)),
+    }
+}
+
 //  Decodes a base64 encoded binary value to Bytes.
 fn json_binary_val_to_bytes(v: &JsonValue) -> Bytes {
     Bytes::from(
@@ -569,67 +952,183 @@ fn json_binary_val_to_bytes(v: &JsonValue) -> Bytes {
     )
 }
 
-// Check if the item has a partition key and sort key.
-fn validate_item_keys(
-    attrs: &HashMap<String, AttributeValue>,
-    ts: &app::TableSchema,
-) -> Result<(), DyneinBatchError> {
-    if !attrs.contains_key(&ts.pk.name) {
-        return Err(DyneinBatchError::InvalidInput(format!(
-            "must provide the partition key attribute {}",
-            ts.pk.name
-        )));
+/// Converts a base64 encoded string into Binary, or a list of base64 encoded strings into Binary Set.
+/// This is the inverse of how binary attributes are represented in JSON output of dynein.
+fn base64_jsonval_to_attrval(
+    attr_name: &str,
+    jsonval: &JsonValue,
+) -> Result<AttributeValue, DyneinBatchError> {
+    let decode = |v: &JsonValue| -> Result<Blob, DyneinBatchError> {
+        let s = v.as_str().ok_or_else(|| {
+            DyneinBatchError::InvalidInput(format!(
+                "binary attribute '{}' should be a base64 encoded string, but got: {}",
+                attr_name, v
+            ))
+        })?;
+        general_purpose::STANDARD
+            .decode(s)
+            .map(Blob::new)
+            .map_err(|e| {
+                DyneinBatchError::InvalidInput(format!(
+                    "failed to decode binary attribute '{}' as base64: {}",
+                    attr_name, e
+                ))
+            })
+    };
+
+    match jsonval {
+        JsonValue::Array(vals) => Ok(AttributeValue::Bs(
+            vals.iter().map(decode).collect::<Result<Vec<Blob>, _>>()?,
+        )),
+        v => Ok(AttributeValue::B(decode(v)?)),
     }
-    validate_key_type(&ts.pk.name, &ts.pk.kind, attrs)?;
+}
 
-    if let Some(sk) = &ts.sk {
-        if !attrs.contains_key(&sk.name) {
-            return Err(DyneinBatchError::InvalidInput(format!(
-                "must provide the sort key attribute {}",
-                sk.name
-            )));
+/// Groups write requests for a table into batches without duplicate primary keys.
+/// With Dedupe, only the last request for each key is kept in a single batch. With Split, the n-th request for a key goes into the n-th batch,
+/// so that requests for the same key are applied in the given order when batches are written one by one.
+fn resolve_duplicate_keys(
+    ts: &app::TableSchema,
+    requests: Vec<WriteRequest>,
+    policy: DuplicatePolicy,
+) -> Result<Vec<Vec<WriteRequest>>, DyneinBatchError> {
+    let keys: Vec<String> = requests.iter().map(|r| request_key(ts, r)).collect();
+    let mut counts = HashMap::<&str, usize>::new();
+    let mut duplicates = Vec::<&str>::new();
+    for key in &keys {
+        let count = counts.entry(key).or_default();
+        *count += 1;
+        if *count == 2 {
+            duplicates.push(key);
         }
-        validate_key_type(&sk.name, &sk.kind, attrs)?;
+    }
+    if duplicates.is_empty() {
+        return Ok(vec![requests]);
     }
 
-    Ok(())
-}
-
-fn validate_key_type(
-    key_name: &str,
-    expected_key_type: &key::KeyType,
-    attrs: &HashMap<String, AttributeValue>,
-) -> Result<(), DyneinBatchError> {
-    match expected_key_type {
-        key::KeyType::S => {
-            if attrs[key_name].as_s().is_err() {
-                return Err(DyneinBatchError::InvalidInput(
-                    generate_type_mismatch_error_message(key_name, "String"),
-                ));
+    match policy {
+        DuplicatePolicy::Error => Err(DyneinBatchError::InvalidInput(format!(
+            "multiple requests for the same key in the table '{}': {}. Use --on-duplicate dedupe or split to write them",
+            ts.name,
+            duplicates.join(", ")
+        ))),
+        DuplicatePolicy::Dedupe => {
+            eprintln!(
+                "WARN: Multiple requests for the same key in the table '{}' were deduplicated, keeping the last one: {}",
+                ts.name,
+                duplicates.join(", ")
+            );
+            let mut batch = Vec::<WriteRequest>::new();
+            for (request, key) in requests.into_iter().zip(&keys) {
+                let remaining = counts.get_mut(key.as_str()).expect("all keys are counted");
+                *remaining -= 1;
+                if *remaining == 0 {
+                    batch.push(request);
+                }
             }
+            Ok(vec![batch])
         }
-        key::KeyType::N => {
-            if attrs[key_name].as_n().is_err() {
-                return Err(DyneinBatchError::InvalidInput(
-                    generate_type_mismatch_error_message(key_name, "Number"),
-                ));
+        DuplicatePolicy::Split => {
+            let mut batches = Vec::<Vec<WriteRequest>>::new();
+            let mut occurrences = HashMap::<&str, usize>::new();
+            for (request, key) in requests.into_iter().zip(&keys) {
+                let occurrence = occurrences.entry(key).or_default();
+                if batches.len() <= *occurrence {
+                    batches.push(vec![]);
+                }
+                batches[*occurrence].push(request);
+                *occurrence += 1;
             }
+            Ok(batches)
         }
-        key::KeyType::B => {
-            if attrs[key_name].as_b().is_err() {
-                return Err(DyneinBatchError::InvalidInput(
-                    generate_type_mismatch_error_message(key_name, "Binary"),
-                ));
+    }
+}
+
+/// Splits a round of requests into chunks of up to 25 requests in total, which is the limit of a BatchWriteItem request.
+/// A round has at most one request for each key, so the chunks can be written one by one without changing the result.
+fn chunk_round(
+    round: HashMap<String, Vec<WriteRequest>>,
+) -> Vec<HashMap<String, Vec<WriteRequest>>> {
+    let mut chunks = Vec::<HashMap<String, Vec<WriteRequest>>>::new();
+    let mut chunk = HashMap::<String, Vec<WriteRequest>>::new();
+    let mut size = 0;
+    for (tbl, requests) in round {
+        for request in requests {
+            if size == BATCH_WRITE_ITEM_MAX_REQUESTS {
+                chunks.push(std::mem::take(&mut chunk));
+                size = 0;
             }
+            chunk.entry(tbl.to_owned()).or_default().push(request);
+            size += 1;
         }
     }
+    if size > 0 {
+        chunks.push(chunk);
+    }
+    chunks
+}
 
-    Ok(())
+/// Returns the primary key of the item to put or delete as a JSON string, e.g. `{"pk":"a","sk":1}`.
+fn request_key(ts: &app::TableSchema, request: &WriteRequest) -> String {
+    let attrs = match (&request.put_request, &request.delete_request) {
+        (Some(put), _) => &put.item,
+        (None, Some(del)) => &del.key,
+        (None, None) => panic!("WriteRequest should have either PutRequest or DeleteRequest"),
+    };
+    let key = data::primary_key_of(ts, attrs);
+    serde_json::to_string(&data::convert_to_ordered_json(&key, ts)).unwrap()
 }
 
-fn generate_type_mismatch_error_message(attr_name: &str, expected_type: &str) -> String {
-    format!(
-        "type mismatch for the key {}, expected: {}",
-        attr_name, expected_type
-    )
+// Check if the item has a partition key and sort key.
+/// Builds put and delete requests for the table from items in Dynein format, validating their primary keys.
+fn build_write_requests(
+    parser: &DyneinParser,
+    strict: bool,
+    ts: &app::TableSchema,
+    puts: &[String],
+    dels: &[String],
+) -> Result<Vec<WriteRequest>, DyneinBatchError> {
+    let parse_item = |exp: &str| {
+        if strict {
+            parser.parse_dynein_format_strict(None, exp)
+        } else {
+            parser.parse_dynein_format(None, exp)
+        }
+    };
+    let mut write_requests = Vec::<WriteRequest>::new();
+
+    for item in puts {
+        let attrs = parse_item(item)?;
+        key::validate_item_keys(&attrs, &ts.pk, ts.sk.as_ref())?;
+        write_requests.push(
+            WriteRequest::builder()
+                .put_request(PutRequest::builder().set_item(Some(attrs)).build().unwrap())
+                .build(),
+        );
+    }
+
+    for key in dels {
+        let attrs = parse_item(key)?;
+        key::validate_item_keys(&attrs, &ts.pk, ts.sk.as_ref())?;
+        write_requests.push(
+            WriteRequest::builder()
+                .delete_request(
+                    DeleteRequest::builder()
+                        .set_key(Some(attrs))
+                        .build()
+                        .unwrap(),
+                )
+                .build(),
+        );
+    }
+
+    Ok(write_requests)
+}
+
+/// Items in `--table-map` are either strings in Dynein format or JSON objects, which are valid Dynein format as they are.
+fn dynein_format_of(item: &JsonValue) -> String {
+    match item {
+        JsonValue::String(s) => s.to_owned(),
+        _ => item.to_string(),
+    }
 }
@@ -0,0 +1,676 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements `dy browse`, a terminal UI to page through items of a table.
+// UI state and key handling live in `Browser`, which doesn't call any API, so that they can be tested without a terminal.
+// API calls are made in `run` according to the `Action` returned by `Browser::handle_key`.
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    io::{self, IsTerminal, Write},
+    process::Command,
+};
+
+use aws_sdk_dynamodb::{types::AttributeValue, Client as DynamoDbSdkClient};
+use aws_smithy_types::error::display::DisplayErrorContext;
+use log::{debug, error};
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+        execute,
+        terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
+};
+use serde_json::Value as JsonValue;
+
+use super::app;
+use super::batch;
+use super::data;
+use super::output::Item;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+const HELP_LINE: &str =
+    "↑/↓ move  PgUp/PgDn scroll  n next page  / search  e edit  d delete  q quit";
+
+type DefaultTerminal = Terminal<CrosstermBackend<io::Stdout>>;
+
+pub struct BrowseParams {
+    pub limit: i32,
+}
+
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Normal,
+    Search,
+    ConfirmDelete,
+}
+
+/// What `run` should do after a key is handled. Indices point to `Browser::items`.
+#[derive(Debug, PartialEq)]
+enum Action {
+    None,
+    Quit,
+    LoadMore,
+    Edit(usize),
+    Delete(usize),
+}
+
+struct Browser {
+    items: Vec<Item>,
+    /// One-line JSON of each item, which is shown in the list and matched by search.
+    lines: Vec<String>,
+    /// Indices of items which match the filter, in the order of `items`.
+    visible: Vec<usize>,
+    filter: String,
+    input: String,
+    state: ListState,
+    mode: Mode,
+    has_more: bool,
+    detail_scroll: u16,
+    status: Option<String>,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Executed when you call `$ dy browse`. Loads items page by page with Scan API as the user scrolls,
+/// and puts or deletes the selected item on request.
+pub async fn browse(cx: &app::Context, params: BrowseParams) {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        error!("`dy browse` requires an interactive terminal. Use `dy scan` instead.");
+        std::process::exit(1);
+    }
+    let ts: app::TableSchema = app::table_schema(cx).await;
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    let mut terminal = init_terminal().unwrap_or_else(|e| {
+        error!("Failed to initialize the terminal: {}", e);
+        std::process::exit(1);
+    });
+    let result = run(&mut terminal, &ddb, &ts, &params).await;
+    restore_terminal();
+    if let Err(e) = result {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+fn init_terminal() -> io::Result<DefaultTerminal> {
+    terminal::enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(io::stdout()))
+}
+
+fn restore_terminal() {
+    if let Err(e) = terminal::disable_raw_mode() {
+        debug!("Failed to disable the raw mode: {}", e);
+    }
+    if let Err(e) = execute!(io::stdout(), LeaveAlternateScreen) {
+        debug!("Failed to leave the alternate screen: {}", e);
+    }
+}
+
+async fn run(
+    terminal: &mut DefaultTerminal,
+    ddb: &DynamoDbSdkClient,
+    ts: &app::TableSchema,
+    params: &BrowseParams,
+) -> io::Result<()> {
+    let mut browser = Browser::new();
+    let mut last_evaluated_key: Option<Item> = None;
+    let mut action = Action::LoadMore;
+    loop {
+        match action {
+            Action::None => {}
+            Action::Quit => return Ok(()),
+            Action::LoadMore => {
+                match scan_page(ddb, ts, params.limit, last_evaluated_key.clone()).await {
+                    Ok((items, lek)) => {
+                        browser.append(ts, items, lek.is_some());
+                        last_evaluated_key = lek;
+                    }
+                    Err(e) => browser.status = Some(e),
+                }
+            }
+            Action::Delete(index) => {
+                let key = data::primary_key_of(ts, &browser.items[index]);
+                browser.status = Some(match delete_item(ddb, ts, key).await {
+                    Ok(()) => {
+                        browser.remove(index);
+                        String::from("Deleted the item.")
+                    }
+                    Err(e) => e,
+                });
+            }
+            Action::Edit(index) => {
+                // The editor takes over the terminal while the item is edited.
+                restore_terminal();
+                let edited = edit_in_editor(ts, &browser.items[index]);
+                *terminal = init_terminal()?;
+                browser.status = Some(match edited {
+                    Ok(None) => String::from("The item is not changed."),
+                    Ok(Some(item)) => {
+                        match put_item(ddb, ts, item.clone(), &browser.items[index]).await {
+                            Ok(()) => {
+                                browser.replace(ts, index, item);
+                                String::from("Saved the item.")
+                            }
+                            Err(e) => e,
+                        }
+                    }
+                    Err(e) => e,
+                });
+            }
+        }
+
+        terminal.draw(|frame| browser.render(frame, ts))?;
+        action = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => browser.handle_key(key),
+            _ => Action::None,
+        };
+    }
+}
+
+async fn scan_page(
+    ddb: &DynamoDbSdkClient,
+    ts: &app::TableSchema,
+    limit: i32,
+    esk: Option<Item>,
+) -> Result<(Vec<Item>, Option<Item>), String> {
+    ddb.scan()
+        .table_name(&ts.name)
+        .limit(limit)
+        .set_exclusive_start_key(esk)
+        .send()
+        .await
+        .map(|res| (res.items.unwrap_or_default(), res.last_evaluated_key))
+        .map_err(|e| {
+            debug!("Scan API call got an error -- {:?}", e);
+            format!("Failed to scan: {}", DisplayErrorContext(&e))
+        })
+}
+
+/// Replaces the item with the edited one, only if the item still has the original attributes and values.
+async fn put_item(
+    ddb: &DynamoDbSdkClient,
+    ts: &app::TableSchema,
+    item: Item,
+    original: &Item,
+) -> Result<(), String> {
+    let (condition, names, values) = unchanged_condition(original);
+    ddb.put_item()
+        .table_name(&ts.name)
+        .set_item(Some(item))
+        .condition_expression(condition)
+        .set_expression_attribute_names(Some(names))
+        .set_expression_attribute_values(Some(values))
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| match e.as_service_error() {
+            Some(se) if se.is_conditional_check_failed_exception() => String::from(
+                "The item has been changed or deleted by someone else. The item is not saved.",
+            ),
+            _ => {
+                debug!("PutItem API call got an error -- {:?}", e);
+                format!("Failed to save the item: {}", DisplayErrorContext(&e))
+            }
+        })
+}
+
+/// Builds a condition expression which holds only if every attribute of the item is unchanged.
+fn unchanged_condition(
+    item: &Item,
+) -> (
+    String,
+    HashMap<String, String>,
+    HashMap<String, AttributeValue>,
+) {
+    let sorted: BTreeMap<&String, &AttributeValue> = item.iter().collect();
+    let mut conditions = vec![];
+    let mut names = HashMap::new();
+    let mut values = HashMap::new();
+    for (i, (name, value)) in sorted.into_iter().enumerate() {
+        conditions.push(format!("#a{i} = :a{i}"));
+        names.insert(format!("#a{i}"), name.to_owned());
+        values.insert(format!(":a{i}"), value.to_owned());
+    }
+    (conditions.join(" AND "), names, values)
+}
+
+async fn delete_item(
+    ddb: &DynamoDbSdkClient,
+    ts: &app::TableSchema,
+    key: Item,
+) -> Result<(), String> {
+    ddb.delete_item()
+        .table_name(&ts.name)
+        .set_key(Some(key))
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| {
+            debug!("DeleteItem API call got an error -- {:?}", e);
+            format!("Failed to delete the item: {}", DisplayErrorContext(&e))
+        })
+}
+
+/// Opens the item in DynamoDB JSON format with $VISUAL or $EDITOR, and returns the edited item if it's changed.
+/// DynamoDB JSON is used so that types such as sets and binaries are kept as they are.
+fn edit_in_editor(ts: &app::TableSchema, item: &Item) -> Result<Option<Item>, String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from(if cfg!(windows) { "notepad" } else { "vi" }));
+    let mut args = editor.split_whitespace();
+    let program = args.next().ok_or("Editor is not specified.")?;
+
+    let mut file = tempfile::Builder::new()
+        .suffix(".json")
+        .tempfile()
+        .map_err(|e| format!("Failed to create a temporary file: {}", e))?;
+    let sorted: BTreeMap<String, JsonValue> = data::strip_item(item).into_iter().collect();
+    serde_json::to_writer_pretty(&mut file, &sorted)
+        .map_err(io::Error::from)
+        .and_then(|_| file.flush())
+        .map_err(|e| format!("Failed to write the item: {}", e))?;
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(file.path())
+        .status()
+        .map_err(|e| format!("Failed to run the editor '{}': {}", editor, e))?;
+    if !status.success() {
+        return Err(format!("The editor '{}' exited with {}.", editor, status));
+    }
+
+    let content = fs::read_to_string(file.path())
+        .map_err(|e| format!("Failed to read the edited item: {}", e))?;
+    parse_edited_item(ts, item, &content)
+}
+
+/// Parses the edited item in DynamoDB JSON format. Any attribute which can't be converted is an error,
+/// rather than being dropped, as the whole item is replaced by PutItem.
+fn parse_edited_item(
+    ts: &app::TableSchema,
+    item: &Item,
+    content: &str,
+) -> Result<Option<Item>, String> {
+    let edited = match serde_json::from_str::<JsonValue>(content) {
+        Ok(json) if json.is_object() => batch::try_ddbjson_attributes_to_attrvals(&json)
+            .map_err(|e| format!("The item is not saved: {}", e))?,
+        Ok(_) => return Err(String::from("The edited item must be a JSON object.")),
+        Err(e) => return Err(format!("The edited item is not valid JSON: {}", e)),
+    };
+    if data::primary_key_of(ts, &edited) != data::primary_key_of(ts, item) {
+        return Err(String::from(
+            "Primary key can't be changed. Use `dy put` to create another item.",
+        ));
+    }
+    Ok(if &edited == item { None } else { Some(edited) })
+}
+
+fn summary_line(ts: &app::TableSchema, item: &Item) -> String {
+    serde_json::to_string(&data::convert_to_ordered_json(item, ts)).unwrap_or_default()
+}
+
+impl Browser {
+    fn new() -> Self {
+        Browser {
+            items: vec![],
+            lines: vec![],
+            visible: vec![],
+            filter: String::new(),
+            input: String::new(),
+            state: ListState::default(),
+            mode: Mode::Normal,
+            has_more: true,
+            detail_scroll: 0,
+            status: None,
+        }
+    }
+
+    /// Returns the index of the selected item in `items`.
+    fn selected(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|i| self.visible.get(i).copied())
+    }
+
+    fn append(&mut self, ts: &app::TableSchema, items: Vec<Item>, has_more: bool) {
+        for item in items {
+            self.lines.push(summary_line(ts, &item));
+            self.items.push(item);
+        }
+        self.has_more = has_more;
+        self.apply_filter();
+    }
+
+    fn replace(&mut self, ts: &app::TableSchema, index: usize, item: Item) {
+        self.lines[index] = summary_line(ts, &item);
+        self.items[index] = item;
+        self.apply_filter();
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.items.remove(index);
+        self.lines.remove(index);
+        self.apply_filter();
+    }
+
+    /// Updates visible items by the case-insensitive filter, keeping the selection on the same position as far as possible.
+    fn apply_filter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        self.visible = (0..self.items.len())
+            .filter(|&i| self.lines[i].to_lowercase().contains(&filter))
+            .collect();
+        let selected = match (self.state.selected(), self.visible.len()) {
+            (_, 0) => None,
+            (None, _) => Some(0),
+            (Some(i), len) => Some(i.min(len - 1)),
+        };
+        self.state.select(selected);
+    }
+
+    fn move_selection(&mut self, down: bool) -> Action {
+        let Some(current) = self.state.selected() else {
+            return Action::None;
+        };
+        self.detail_scroll = 0;
+        if !down {
+            self.state.select(Some(current.saturating_sub(1)));
+        } else if current + 1 < self.visible.len() {
+            self.state.select(Some(current + 1));
+        } else if self.has_more {
+            return Action::LoadMore;
+        }
+        Action::None
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        match self.mode {
+            Mode::Search => {
+                match key.code {
+                    KeyCode::Enter => {
+                        self.filter = std::mem::take(&mut self.input);
+                        self.mode = Mode::Normal;
+                        self.state.select(Some(0));
+                        self.apply_filter();
+                    }
+                    KeyCode::Esc => self.mode = Mode::Normal,
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(c) => self.input.push(c),
+                    _ => {}
+                }
+                Action::None
+            }
+            Mode::ConfirmDelete => {
+                self.mode = Mode::Normal;
+                match (key.code, self.selected()) {
+                    (KeyCode::Char('y'), Some(index)) => Action::Delete(index),
+                    _ => {
+                        self.status = Some(String::from("Cancelled."));
+                        Action::None
+                    }
+                }
+            }
+            Mode::Normal => {
+                self.status = None;
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+                    KeyCode::Down | KeyCode::Char('j') => self.move_selection(true),
+                    KeyCode::Up | KeyCode::Char('k') => self.move_selection(false),
+                    KeyCode::PageDown => {
+                        self.detail_scroll = self.detail_scroll.saturating_add(10);
+                        Action::None
+                    }
+                    KeyCode::PageUp => {
+                        self.detail_scroll = self.detail_scroll.saturating_sub(10);
+                        Action::None
+                    }
+                    KeyCode::Char('n') if self.has_more => Action::LoadMore,
+                    KeyCode::Char('/') => {
+                        self.input = self.filter.clone();
+                        self.mode = Mode::Search;
+                        Action::None
+                    }
+                    KeyCode::Char('e') => self.selected().map_or(Action::None, Action::Edit),
+                    KeyCode::Char('d') if self.selected().is_some() => {
+                        self.mode = Mode::ConfirmDelete;
+                        Action::None
+                    }
+                    _ => Action::None,
+                }
+            }
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame, ts: &app::TableSchema) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.size());
+        let [list_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(main);
+
+        let title = format!(
+            "{} ({} of {} items{}{})",
+            &ts.name,
+            self.visible.len(),
+            self.items.len(),
+            if self.has_more {
+                ", more in the table"
+            } else {
+                ""
+            },
+            if self.filter.is_empty() {
+                String::new()
+            } else {
+                format!(", filtered by '{}'", self.filter)
+            },
+        );
+        let lines = &self.lines;
+        let list = List::new(
+            self.visible
+                .iter()
+                .map(|&i| ListItem::new(lines[i].as_str())),
+        )
+        .block(Block::bordered().title(title))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.state);
+
+        let detail = self
+            .selected()
+            .and_then(|i| {
+                serde_json::to_string_pretty(&data::convert_to_ordered_json(&self.items[i], ts))
+                    .ok()
+            })
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(detail)
+                .block(Block::bordered().title("Item"))
+                .wrap(Wrap { trim: false })
+                .scroll((self.detail_scroll, 0)),
+            detail_area,
+        );
+
+        let footer_text = match self.mode {
+            Mode::Search => format!("/{}", self.input),
+            Mode::ConfirmDelete => String::from("Delete the selected item? (y/n)"),
+            Mode::Normal => self
+                .status
+                .clone()
+                .unwrap_or_else(|| String::from(HELP_LINE)),
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ddb::key;
+
+    fn table_schema() -> app::TableSchema {
+        app::TableSchema {
+            region: "local".to_owned(),
+            name: "tbl".to_owned(),
+            pk: key::Key {
+                name: "pk".to_owned(),
+                kind: key::KeyType::S,
+            },
+            sk: None,
+            indexes: None,
+            mode: crate::ddb::table::Mode::OnDemand,
+            ttl: None,
+        }
+    }
+
+    fn item(pk: &str) -> Item {
+        HashMap::from([("pk".to_owned(), AttributeValue::S(pk.to_owned()))])
+    }
+
+    fn press(browser: &mut Browser, code: KeyCode) -> Action {
+        browser.handle_key(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn test_browser_paging() {
+        let ts = table_schema();
+        let mut browser = Browser::new();
+        browser.append(&ts, vec![item("a"), item("b")], true);
+        assert_eq!(browser.selected(), Some(0));
+
+        assert_eq!(press(&mut browser, KeyCode::Down), Action::None);
+        assert_eq!(browser.selected(), Some(1));
+        // Moving down from the last item loads the next page.
+        assert_eq!(press(&mut browser, KeyCode::Down), Action::LoadMore);
+        browser.append(&ts, vec![item("c")], false);
+        assert_eq!(press(&mut browser, KeyCode::Down), Action::None);
+        assert_eq!(browser.selected(), Some(2));
+        assert_eq!(press(&mut browser, KeyCode::Down), Action::None);
+        assert_eq!(press(&mut browser, KeyCode::Char('n')), Action::None);
+        assert_eq!(press(&mut browser, KeyCode::Char('q')), Action::Quit);
+    }
+
+    #[test]
+    fn test_browser_search_and_delete() {
+        let ts = table_schema();
+        let mut browser = Browser::new();
+        browser.append(
+            &ts,
+            vec![item("apple"), item("banana"), item("apricot")],
+            false,
+        );
+
+        press(&mut browser, KeyCode::Char('/'));
+        for c in "AP".chars() {
+            press(&mut browser, KeyCode::Char(c));
+        }
+        press(&mut browser, KeyCode::Enter);
+        assert_eq!(browser.visible, vec![0, 2]);
+        press(&mut browser, KeyCode::Down);
+        assert_eq!(browser.selected(), Some(2));
+        assert_eq!(press(&mut browser, KeyCode::Char('e')), Action::Edit(2));
+
+        // Deletion requires confirmation.
+        press(&mut browser, KeyCode::Char('d'));
+        assert_eq!(press(&mut browser, KeyCode::Char('n')), Action::None);
+        press(&mut browser, KeyCode::Char('d'));
+        assert_eq!(press(&mut browser, KeyCode::Char('y')), Action::Delete(2));
+        browser.remove(2);
+        assert_eq!(browser.visible, vec![0]);
+        assert_eq!(browser.selected(), Some(0));
+
+        // Searching an empty string shows all items again.
+        press(&mut browser, KeyCode::Char('/'));
+        press(&mut browser, KeyCode::Backspace);
+        press(&mut browser, KeyCode::Backspace);
+        press(&mut browser, KeyCode::Enter);
+        assert_eq!(browser.visible, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_parse_edited_item() {
+        let ts = table_schema();
+        let original = HashMap::from([
+            ("pk".to_owned(), AttributeValue::S("a".to_owned())),
+            ("age".to_owned(), AttributeValue::N("30".to_owned())),
+        ]);
+        assert_eq!(
+            parse_edited_item(&ts, &original, r#"{"pk": {"S": "a"}, "age": {"N": "30"}}"#),
+            Ok(None)
+        );
+        assert_eq!(
+            parse_edited_item(
+                &ts,
+                &original,
+                r#"{"pk": {"S": "a"}, "age": {"N": "31"}, "tags": {"SS": ["x"]}}"#
+            ),
+            Ok(Some(HashMap::from([
+                ("pk".to_owned(), AttributeValue::S("a".to_owned())),
+                ("age".to_owned(), AttributeValue::N("31".to_owned())),
+                ("tags".to_owned(), AttributeValue::Ss(vec!["x".to_owned()])),
+            ])))
+        );
+        // Attributes which aren't valid DynamoDB JSON are errors, rather than being dropped from the saved item.
+        for content in [
+            r#"{"pk": {"S": "a"}, "age": 30}"#,
+            r#"{"pk": {"S": "a"}, "age": {"N": 30}}"#,
+            r#"{"pk": {"S": "a"}, "age": {"X": "30"}}"#,
+            r#"{"pk": {"S": "a"}, "info": {"M": {"age": {"N": 30}}}}"#,
+        ] {
+            assert!(parse_edited_item(&ts, &original, content)
+                .unwrap_err()
+                .starts_with("The item is not saved: invalid DynamoDB JSON in attribute"));
+        }
+        assert_eq!(
+            parse_edited_item(&ts, &original, r#"{"pk": {"S": "b"}, "age": {"N": "30"}}"#),
+            Err(String::from(
+                "Primary key can't be changed. Use `dy put` to create another item."
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unchanged_condition() {
+        let (condition, names, values) = unchanged_condition(&HashMap::from([
+            ("pk".to_owned(), AttributeValue::S("a".to_owned())),
+            ("age".to_owned(), AttributeValue::N("30".to_owned())),
+        ]));
+        assert_eq!(condition, "#a0 = :a0 AND #a1 = :a1");
+        assert_eq!(names["#a0"], "age");
+        assert_eq!(values[":a1"], AttributeValue::S("a".to_owned()));
+    }
+}
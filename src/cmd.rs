@@ -18,6 +18,12 @@ use ::serde::{Deserialize, Serialize};
 use clap::{CommandFactory, FromArgMatches, Parser};
 use std::error::Error;
 use std::ffi::OsString;
+use std::time::Duration;
+
+use super::app;
+use super::completion;
+use super::help;
+use super::output;
 
 /* =================================================
 struct / enum / const
@@ -30,7 +36,7 @@ dynein looks for config files under $HOME/.dynein/ directory.";
 // We need to specify verbatim_doc_comment to show multiple line doc comments for CLI properly.
 // See https://github.com/clap-rs/clap/issues/2389
 #[derive(Parser, Debug)]
-#[clap(name = "dynein", about = ABOUT_DYNEIN, version, verbatim_doc_comment)]
+#[clap(name = "dynein", about = ABOUT_DYNEIN, after_help = help::TOPICS_HELP, version, verbatim_doc_comment)]
 pub struct Dynein {
     #[clap(subcommand, verbatim_doc_comment)]
     pub child: Option<Sub>,
@@ -44,14 +50,55 @@ pub struct Dynein {
     #[clap(short, long, global = true, verbatim_doc_comment)]
     pub port: Option<u32>,
 
-    /// Target table of the operation. You can use --table option in both top-level and subcommand-level.
+    /// Target table of the operation, given by name or ARN. You can use --table option in both top-level and subcommand-level.
     /// You can store table schema locally by executing `$ dy use`, after that you need not to specify --table on every command.
     #[clap(short, long, global = true, verbatim_doc_comment)]
     pub table: Option<String>,
 
+    /// PEM file of CA certificates to trust in addition to the platform ones, e.g. for TLS interception in corporate networks.
+    /// Defaults to AWS_CA_BUNDLE env var. Proxy is configured by HTTPS_PROXY and NO_PROXY env vars.
+    #[clap(long, verbatim_doc_comment)]
+    pub ca_bundle: Option<String>,
+
+    /// Timeout in seconds to establish a connection, e.g. 0.5. Overrides `timeout.connect_timeout` in config.yml.
+    #[clap(long, value_name = "SECONDS", value_parser = app::parse_seconds, verbatim_doc_comment)]
+    pub connect_timeout: Option<Duration>,
+
+    /// Timeout in seconds to read a response after sending a request. Overrides `timeout.read_timeout` in config.yml.
+    #[clap(long, value_name = "SECONDS", value_parser = app::parse_seconds, verbatim_doc_comment)]
+    pub read_timeout: Option<Duration>,
+
+    /// Maximum number of retries of a failed API call. 0 disables retries. Overrides `max_attempts` of `retry` in config.yml.
+    #[clap(long, value_name = "N", verbatim_doc_comment)]
+    pub max_retries: Option<u32>,
+
+    /// Output numbers that have more significant digits than JSON numbers can hold exactly (e.g. 38 digits) as strings, instead of rounding them.
+    #[clap(long, verbatim_doc_comment)]
+    pub precise_numbers: bool,
+
+    /// Append ISO-8601 date times (UTC) to numbers which look like epoch seconds or milliseconds in output of read commands,
+    /// e.g. "1600000000 (2020-09-13T12:26:40Z)". Such numbers are output as strings.
+    #[clap(long, verbatim_doc_comment)]
+    pub humanize_epochs: bool,
+
+    /// Show values of attributes listed in `display.redact` in config.yml, which are hidden as "<redacted>" by default.
+    #[clap(long, verbatim_doc_comment)]
+    pub no_redact: bool,
+
     #[clap(long, verbatim_doc_comment)]
     pub shell: bool,
 
+    /// Run the command as the shell mode does, without reading stdin. Repeat it to run multiple commands sharing the context,
+    /// e.g. `dy -c "use users" -c "get alice" -c "query bob"`. dynein stops at the first command which fails to parse.
+    #[clap(
+        short = 'c',
+        long = "command",
+        value_name = "COMMAND",
+        conflicts_with = "shell",
+        verbatim_doc_comment
+    )]
+    pub commands: Vec<String>,
+
     /// This option displays detailed information about third-party libraries, frameworks, and other components incorporated into dynein,    
     /// as well as the full license texts under which they are distributed.
     #[clap(long)]
@@ -60,6 +107,12 @@ pub struct Dynein {
 
 // NOTE: need to be placed in the same module as Dynein struct
 pub fn initialize_from_args() -> Dynein {
+    // `dy help <topic>` shows a help topic, while `dy help <subcommand>` is handled by clap.
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    if let Some(topic) = help::find_topic(&args) {
+        help::print_topic(topic);
+        std::process::exit(0);
+    }
     Dynein::parse()
 }
 
@@ -93,6 +146,23 @@ pub enum Sub {
         /// List DynamoDB tables in all available regions
         #[clap(long, verbatim_doc_comment)]
         all_regions: bool,
+
+        /// List DynamoDB tables in the given regions, separated by commas (e.g. --regions us-east-1,eu-west-1).
+        #[clap(
+            long,
+            value_delimiter = ',',
+            conflicts_with = "all_regions",
+            verbatim_doc_comment
+        )]
+        regions: Option<Vec<String>>,
+
+        /// Show item count, size, billing mode and creation date of each table. [API: DescribeTable]
+        #[clap(long, verbatim_doc_comment)]
+        detail: bool,
+
+        /// Sort tables by name (ascending), size or items (descending). Requires --detail option.
+        #[clap(long, value_parser = ["name", "size", "items"], requires = "detail", verbatim_doc_comment)]
+        sort_by: Option<String>,
     },
 
     // NOTE: this command is defined both in top-level and sub-subcommand of table family.
@@ -102,12 +172,20 @@ pub enum Sub {
         /// Target table name. Optionally you may specify the target table by --table (-t) option.
         target_table_to_desc: Option<String>,
 
-        /// Show details of all tables in the region
+        /// Show details of all tables in the region, as a single document keyed by table names
         #[clap(long, verbatim_doc_comment)]
         all_tables: bool,
 
+        /// Show only the schema, capacity and status of the given GSI or LSI
+        #[clap(long, value_name = "NAME", conflicts_with_all = ["all_tables", "streams"], verbatim_doc_comment)]
+        index: Option<String>,
+
+        /// Show only the ARN, label and view type of the stream
+        #[clap(long, conflicts_with = "all_tables", verbatim_doc_comment)]
+        streams: bool,
+
         /// Switch output format.
-        #[clap(short, long, value_parser = ["yaml" /*, "raw" */ ], verbatim_doc_comment)]
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
         output: Option<String>,
     },
 
@@ -121,7 +199,7 @@ pub enum Sub {
         #[clap(short, long, default_value = "100", verbatim_doc_comment)]
         limit: i32,
 
-        /// Attributes to show, separated by commas, which is mapped to ProjectionExpression (e.g. --attributes name,address,age).
+        /// Attributes to show, separated by commas, which is mapped to ProjectionExpression (e.g. --attributes name,address.city,tags[0]).
         /// Note that primary key(s) are always included in results regardless of what you've passed to --attributes.
         #[clap(short, long, verbatim_doc_comment)]
         attributes: Option<String>,
@@ -139,18 +217,79 @@ pub enum Sub {
         #[clap(short, long, verbatim_doc_comment)]
         index: Option<String>,
 
+        /// Print a summary line (items returned, scanned count, pages fetched, consumed capacity and elapsed time) to stderr.
+        #[clap(long, verbatim_doc_comment)]
+        summary: bool,
+
+        /// Skip confirmation before scanning a table which has many items.
+        /// In non-interactive mode, scanning such a table fails unless this option is given.
+        #[clap(short, long, verbatim_doc_comment)]
+        yes: bool,
+
+        /// Show the value at the document path in its own column of table output, e.g. `--expand address.city`.
+        /// Maps and lists are shown as JSON as long as they fit in the cell. Specify multiple paths separated by commas.
+        #[clap(
+            long,
+            value_name = "PATH",
+            value_delimiter = ',',
+            conflicts_with_all = ["keys_only", "group_by"],
+            verbatim_doc_comment
+        )]
+        expand: Vec<String>,
+
+        /// Sort items by the attribute after they are read, e.g. `--sort-by price` or `--sort-by price:desc`.
+        /// Numbers are compared by value and strings lexicographically. Items without the attribute come last.
+        #[clap(long, value_name = "ATTR", verbatim_doc_comment)]
+        sort_by: Option<String>,
+
+        /// Print the number of items per value of the attribute instead of items, e.g. `--group-by status`.
+        /// Groups are listed in the order of --sort-by, or by values of the attribute if --sort-by isn't given.
+        #[clap(long, value_name = "ATTR", verbatim_doc_comment)]
+        group_by: Option<String>,
+
+        /// Scan all items, i.e. all pages of the table or index, and print distinct values of the attribute with their counts.
+        /// It helps you check cardinality of an attribute, e.g. before creating a GSI on it. --limit is not applied.
+        #[clap(
+            long,
+            value_name = "ATTR",
+            conflicts_with_all = ["limit", "attributes", "keys_only", "sort_by", "group_by", "expand"],
+            verbatim_doc_comment
+        )]
+        distinct: Option<String>,
+
         /// Switch output format.
-        #[clap(short, long, value_parser = ["table", "json", "raw"], verbatim_doc_comment)]
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
         output: Option<String>,
     },
 
-    /// Retrieve an item by specifying primary key(s). [API: GetItem]
+    /// Retrieve an item by specifying primary key(s), or multiple items by giving more keys. [API: GetItem, BatchGetItem]
     #[clap(aliases = &["g"], verbatim_doc_comment)]
     Get {
         /// Partition Key of the target item.
-        pval: String,
-        /// Sort Key of the target item (if any).
-        sval: Option<String>,
+        #[clap(required_unless_present = "keys_file")]
+        pval: Option<String>,
+        /// Sort Key of the target item (if any), or more Partition Keys to get multiple items from a table without sort key.
+        sval: Vec<String>,
+
+        /// Sort Keys of items to get from the partition at once, separated by commas, e.g. `dy get user1 --sort-keys a,b,c`. [API: BatchGetItem]
+        #[clap(
+            long,
+            value_name = "SVALS",
+            value_delimiter = ',',
+            conflicts_with = "sval",
+            verbatim_doc_comment
+        )]
+        sort_keys: Vec<String>,
+
+        /// Text file listing keys of items to get, one key per line: a partition key value, or "pk,sk" for a table with sort key. [API: BatchGetItem]
+        /// Values are typed based on the table schema. Blank lines and lines starting with '#' are skipped.
+        #[clap(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = ["pval", "sval", "sort_keys", "field", "replica_region"],
+            verbatim_doc_comment
+        )]
+        keys_file: Option<String>,
 
         /// Strong consistent read - to make sure retrieve the most up-to-date data. By default (false), eventual consistent reads would occur.
         /// https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/HowItWorks.ReadConsistency.html
@@ -158,8 +297,34 @@ pub enum Sub {
         consistent_read: bool,
 
         /// Switch output format.
-        #[clap(short, long, value_parser = ["json", "yaml", "raw"], verbatim_doc_comment)]
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
         output: Option<String>,
+
+        /// Print only the value at the document path, e.g. `--field attrs.profile.email` or `--field tags[0]`.
+        /// Strings, numbers and booleans are printed as raw values without quotes, and maps, lists and sets as JSON.
+        #[clap(
+            long,
+            value_name = "PATH",
+            conflicts_with = "output",
+            verbatim_doc_comment
+        )]
+        field: Option<String>,
+
+        /// Get the item in the current region and the given replica regions of the global table in parallel, and print results with latency of each region.
+        /// It helps you debug replication lag, e.g. `--replica-region us-east-1,eu-west-1`.
+        #[clap(
+            long,
+            value_name = "REGION",
+            value_delimiter = ',',
+            conflicts_with = "field",
+            verbatim_doc_comment
+        )]
+        replica_region: Vec<String>,
+
+        /// Annotate when the item expires by the TTL attribute of the table, e.g. "expires in 3d 4h". [API: DescribeTimeToLive]
+        /// The TTL setting is looked up once and cached with the table schema.
+        #[clap(long, conflicts_with_all = ["keys_file", "field", "replica_region"], verbatim_doc_comment)]
+        humanize: bool,
     },
 
     /// Retrieve items that match conditions. Partition key is required. [API: Query]
@@ -170,9 +335,80 @@ pub enum Sub {
 
         /// Additional Sort Key condition which will be converted to KeyConditionExpression.
         /// Valid syntax: ['= 12', '> 12', '>= 12', '< 12', '<= 12', 'between 10 and 99', 'begins_with myVal"]
-        #[clap(short, long = "sort-key", verbatim_doc_comment)]
+        #[clap(
+            short,
+            long = "sort-key",
+            group = "sort_key_condition",
+            verbatim_doc_comment
+        )]
         sort_key_expression: Option<String>,
 
+        /// Sort key condition equivalent to `--sort-key 'begins_with <PREFIX>'`, without quoting the value.
+        #[clap(
+            long,
+            value_name = "PREFIX",
+            group = "sort_key_condition",
+            allow_hyphen_values = true,
+            verbatim_doc_comment
+        )]
+        begins: Option<String>,
+
+        /// Sort key condition equivalent to `--sort-key 'between <FROM> and <TO>'`, without quoting values.
+        #[clap(
+            long,
+            num_args = 2,
+            value_names = ["FROM", "TO"],
+            group = "sort_key_condition",
+            allow_hyphen_values = true,
+            verbatim_doc_comment
+        )]
+        between: Option<Vec<String>>,
+
+        /// Sort key condition equivalent to `--sort-key '> <VALUE>'`, without quoting the value.
+        #[clap(
+            long,
+            value_name = "VALUE",
+            group = "sort_key_condition",
+            allow_hyphen_values = true,
+            verbatim_doc_comment
+        )]
+        sk_gt: Option<String>,
+
+        /// Sort key condition equivalent to `--sort-key '>= <VALUE>'`, without quoting the value.
+        #[clap(
+            long,
+            value_name = "VALUE",
+            group = "sort_key_condition",
+            allow_hyphen_values = true,
+            verbatim_doc_comment
+        )]
+        sk_ge: Option<String>,
+
+        /// Sort key condition equivalent to `--sort-key '< <VALUE>'`, without quoting the value.
+        #[clap(
+            long,
+            value_name = "VALUE",
+            group = "sort_key_condition",
+            allow_hyphen_values = true,
+            verbatim_doc_comment
+        )]
+        sk_lt: Option<String>,
+
+        /// Sort key condition equivalent to `--sort-key '<= <VALUE>'`, without quoting the value.
+        #[clap(
+            long,
+            value_name = "VALUE",
+            group = "sort_key_condition",
+            allow_hyphen_values = true,
+            verbatim_doc_comment
+        )]
+        sk_le: Option<String>,
+
+        /// Additional condition to filter items after they are read, which will be converted to FilterExpression.
+        /// e.g. 'age >= 20 AND attribute_exists(email)'. Note that filtered out items still consume read capacity.
+        #[clap(short, long, verbatim_doc_comment)]
+        filter: Option<String>,
+
         /// Strong consistent read - to make sure retrieve the most up-to-date data. By default (false), eventual consistent reads would occur.
         /// https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/HowItWorks.ReadConsistency.html
         #[clap(long, verbatim_doc_comment)]
@@ -201,7 +437,7 @@ pub enum Sub {
         descending: bool,
 
         /// Specify the strict mode for parsing query conditions.
-        /// By default, the non-strict mode is used unless specified on the config file.
+        /// By default, the non-strict mode is used unless specified on the config file or DYNEIN_STRICT environment variable.
         /// You cannot combine with --non-strict option.
         ///
         /// In strict mode, you will experience an error if the provided value does not match the table schema.
@@ -209,18 +445,95 @@ pub enum Sub {
         strict: bool,
 
         /// Specify the non-strict mode for parsing query conditions.
-        /// By default, the non-strict mode is used unless specified on the config file.
+        /// By default, the non-strict mode is used unless specified on the config file or DYNEIN_STRICT environment variable.
         /// You cannot combine with --strict option.
         ///
         /// In non-strict mode, dynein tries to infer the intention of the provided expression as much as possible.
         #[clap(long, conflicts_with = "strict")]
         non_strict: bool,
 
+        /// Print a summary line (items returned, scanned count, pages fetched, consumed capacity and elapsed time) to stderr.
+        #[clap(long, verbatim_doc_comment)]
+        summary: bool,
+
+        /// Run the query in the current region and the given replica regions of the global table in parallel, and print results with latency of each region.
+        /// It helps you debug replication lag, e.g. `--replica-region us-east-1,eu-west-1`.
+        #[clap(
+            long,
+            value_name = "REGION",
+            value_delimiter = ',',
+            conflicts_with = "summary",
+            verbatim_doc_comment
+        )]
+        replica_region: Vec<String>,
+
+        /// Show the value at the document path in its own column of table output, e.g. `--expand address.city`.
+        /// Maps and lists are shown as JSON as long as they fit in the cell. Specify multiple paths separated by commas.
+        #[clap(
+            long,
+            value_name = "PATH",
+            value_delimiter = ',',
+            conflicts_with_all = ["keys_only", "group_by"],
+            verbatim_doc_comment
+        )]
+        expand: Vec<String>,
+
+        /// Sort items by the attribute after they are read, e.g. `--sort-by price` or `--sort-by price:desc`.
+        /// Numbers are compared by value and strings lexicographically. Items without the attribute come last.
+        #[clap(
+            long,
+            value_name = "ATTR",
+            conflicts_with = "replica_region",
+            verbatim_doc_comment
+        )]
+        sort_by: Option<String>,
+
+        /// Print the number of items per value of the attribute instead of items, e.g. `--group-by status`.
+        /// Groups are listed in the order of --sort-by, or by values of the attribute if --sort-by isn't given.
+        #[clap(
+            long,
+            value_name = "ATTR",
+            conflicts_with = "replica_region",
+            verbatim_doc_comment
+        )]
+        group_by: Option<String>,
+
+        /// Switch output format.
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
+        output: Option<String>,
+    },
+
+    /// Find partitions which contain an item with the given sort key value. [API: Query, Scan]
+    ///
+    /// If a GSI uses the sort key of the table as its partition key, dynein queries the index.
+    /// Otherwise dynein scans the whole table, which consumes read capacity for all items.
+    #[clap(verbatim_doc_comment)]
+    FindSk {
+        /// Sort Key value to look for.
+        sval: String,
+
+        /// Skip confirmation before scanning a table which has many items.
+        /// In non-interactive mode, scanning such a table fails unless this option is given.
+        #[clap(short, long, verbatim_doc_comment)]
+        yes: bool,
+
         /// Switch output format.
-        #[clap(short, long, value_parser = ["table", "json", "raw"], verbatim_doc_comment)]
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
         output: Option<String>,
     },
 
+    /// Browse items in a table interactively, i.e. page through items, search them, and edit or delete one. [API: Scan, PutItem, DeleteItem]
+    ///
+    /// Keys: ↑/↓ (or k/j) to select an item, PgUp/PgDn to scroll the item, n to load the next page,
+    /// / to search loaded items, e to edit the item in $VISUAL or $EDITOR, d to delete the item, q to quit.
+    #[cfg(feature = "browse")]
+    #[clap(verbatim_doc_comment)]
+    Browse {
+        /// Number of items to load at a time.
+        #[clap(short, long, default_value = "100", verbatim_doc_comment)]
+        limit: i32,
+    },
+
     /// Create a new item, or replace an existing item. [API: PutItem]
     #[clap(aliases = &["p"], verbatim_doc_comment)]
     Put {
@@ -233,15 +546,42 @@ pub enum Sub {
         /// e.g. --item '{"name": "John", "age": 18, "like": ["Apple", "Banana"]}'
         #[clap(short, long, verbatim_doc_comment)]
         item: Option<String>,
+
+        /// Put the item only if no item with the same primary key exists, i.e. never replace an existing item.
+        #[clap(long, verbatim_doc_comment)]
+        if_not_exists: bool,
+
+        /// Send the item even if its estimated size exceeds the item size limit of 400 KB, showing a warning instead of an error.
+        #[clap(long, verbatim_doc_comment)]
+        force: bool,
     },
 
     /// Delete an existing item. [API: DeleteItem]
     #[clap(aliases = &["d", "delete"], verbatim_doc_comment)]
     Del {
         /// Partition Key of the target item.
-        pval: String,
+        #[clap(required_unless_present = "keys_file")]
+        pval: Option<String>,
         /// Sort Key of the target item (if any).
         sval: Option<String>,
+
+        /// Delete the item only if it matches the condition, e.g. --if 'status = "inactive"'. See `dy help expressions`.
+        #[clap(long = "if", value_name = "CONDITION", verbatim_doc_comment)]
+        condition: Option<String>,
+
+        /// Text file listing keys of items to delete, one key per line: a partition key value, or "pk,sk" for a table with sort key. [API: BatchWriteItem]
+        /// Values are typed based on the table schema. Blank lines and lines starting with '#' are skipped.
+        #[clap(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = ["pval", "sval", "condition"],
+            verbatim_doc_comment
+        )]
+        keys_file: Option<String>,
+
+        /// Skip confirmation before deleting the item(s), which is required when `safety.require_confirmation` contains "delete".
+        #[clap(short, long, verbatim_doc_comment)]
+        yes: bool,
     },
 
     /// Update an existing item. [API: UpdateItem]
@@ -253,8 +593,9 @@ pub enum Sub {
     /// https://docs.amazonaws.cn/en_us/amazondynamodb/latest/developerguide/Expressions.UpdateExpressions.html
     #[clap(aliases = &["update", "u"], verbatim_doc_comment)]
     Upd {
-        /// Partition Key of the target item.
-        pval: String,
+        /// Partition Key of the target item. With --where, only items in the partition are targeted.
+        #[clap(required_unless_present = "where_condition")]
+        pval: Option<String>,
         /// Sort Key of the target item (if any).
         sval: Option<String>,
 
@@ -271,9 +612,33 @@ pub enum Sub {
         remove: Option<String>,
 
         // TODO: ConditionExpression support --condition/-c
-        /// Increment a Number attribute by 1. e.g. `dy update <keys> --atomic-counter sitePv`.
-        #[clap(long, verbatim_doc_comment)]
-        atomic_counter: Option<String>,
+        /// Increment Number attribute(s) by 1. e.g. `dy update <keys> --atomic-counter sitePv`.
+        /// Multiple attributes are separated by commas and nested ones are given by document paths, e.g. `--atomic-counter 'pv,stats.views,daily[0]'`.
+        /// If the attribute doesn't exist yet, it's initialized with --counter-init before incremented.
+        #[clap(long, value_delimiter = ',', verbatim_doc_comment)]
+        atomic_counter: Option<Vec<String>>,
+
+        /// Step of --atomic-counter. Use a negative number to decrement, e.g. --by -1.
+        #[clap(long, value_name = "N", requires = "atomic_counter", allow_hyphen_values = true, value_parser = app::parse_number_literal, verbatim_doc_comment)]
+        by: Option<String>,
+
+        /// Initial value of the attribute used by --atomic-counter when it doesn't exist yet. Defaults to 0.
+        #[clap(long, value_name = "N", requires = "atomic_counter", allow_hyphen_values = true, value_parser = app::parse_number_literal, verbatim_doc_comment)]
+        counter_init: Option<String>,
+
+        /// Update all items that match the condition instead of a single item, e.g. --where 'createdAt < 1600000000'.
+        /// Matching items are found by Scan (or Query if PVAL is given) and updated one by one with --set or --remove.
+        /// Items which no longer match the condition when they're updated are skipped.
+        #[clap(long = "where", value_name = "CONDITION", conflicts_with_all = ["sval", "atomic_counter"], verbatim_doc_comment)]
+        where_condition: Option<String>,
+
+        /// Count items that match --where condition without updating them.
+        #[clap(long, requires = "where_condition", verbatim_doc_comment)]
+        dry_run: bool,
+
+        /// Maximum number of items updated in parallel with --where. Defaults to 8.
+        #[clap(long, value_name = "N", requires = "where_condition", value_parser = clap::value_parser!(u16).range(1..), verbatim_doc_comment)]
+        concurrency: Option<u16>,
     },
 
     /// Put or Delete multiple items at one time, up to 25 requests. [API: BatchWriteItem]
@@ -295,10 +660,32 @@ pub enum Sub {
         #[clap(long = "del")]
         dels: Option<Vec<String>>,
 
+        /// Text file listing keys of items to delete from the table, one key per line: a partition key value, or "pk,sk" for a table with sort key.
+        /// Values are typed based on the table schema. Blank lines and lines starting with '#' are skipped.
+        #[clap(long, value_name = "PATH", verbatim_doc_comment)]
+        keys_file: Option<String>,
+
+        /// Items to put or delete in multiple tables, as a JSON object which maps table names to "put" and "del" lists of items.
+        /// Items are JSON objects or strings in Dynein format. Puts and deletes of each table are validated with its primary key.
+        /// e.g. `--table-map '{"TableA": {"put": [{"pk": "1"}]}, "TableB": {"del": [{"pk": "2"}]}}'`
+        #[clap(long, value_name = "JSON", verbatim_doc_comment)]
+        table_map: Option<String>,
+
         /// Input JSON file path. This input file should be BatchWriteItem input JSON syntax. For more info:
         /// https://docs.aws.amazon.com/amazondynamodb/latest/APIReference/API_BatchWriteItem.html
         #[clap(long, short, verbatim_doc_comment)]
         input: Option<String>,
+
+        /// How to handle multiple requests for the same primary key in a table, which BatchWriteItem rejects.{n}
+        ///   error = abort without writing any item (default).{n}
+        ///   dedupe = keep only the last request for each key, showing a warning.{n}
+        ///   split = write requests for the same key in separate batches, in the given order.
+        #[clap(long, value_parser = ["error", "dedupe", "split"], verbatim_doc_comment)]
+        on_duplicate: Option<String>,
+
+        /// Skip confirmation before deleting items, which is required when `safety.require_confirmation` contains "delete".
+        #[clap(short, long, verbatim_doc_comment)]
+        yes: bool,
     },
 
     /* =================================================
@@ -321,6 +708,25 @@ pub enum Sub {
         grandchild: ConfigSub,
     },
 
+    /// Show the AWS identity, region, endpoint, table and config files that dynein resolves [API: GetCallerIdentity]
+    #[clap(verbatim_doc_comment)]
+    Whoami,
+
+    /// Generate a shell completion script. Table names are completed with the ones cached by `dy use`.
+    ///
+    /// e.g. Add `source <(dy completion bash)` to ~/.bashrc, `source <(dy completion zsh)` to ~/.zshrc (after compinit),
+    /// or `dy completion fish | source` to ~/.config/fish/config.fish.
+    #[clap(verbatim_doc_comment)]
+    Completion {
+        /// Shell to generate the completion script for.
+        #[clap(value_parser = completion::SHELL_NAMES, required_unless_present = "cached_tables", verbatim_doc_comment)]
+        shell: Option<String>,
+
+        /// Print names of tables in cache.yml, which the completion scripts call to complete table names.
+        #[clap(long, hide = true, conflicts_with = "shell")]
+        cached_tables: bool,
+    },
+
     /// Create sample tables and load test data for bootstrapping
     #[clap(verbatim_doc_comment)]
     Bootstrap {
@@ -338,7 +744,7 @@ pub enum Sub {
     /// When you export items as CSV, on the other hand, dynein has to know which attributes are to be exported as CSV format requires "column" - i.e. N th column should contain attribute ABC throughout a csv file.
     #[clap(verbatim_doc_comment)]
     Export {
-        /// Output target filename where dynein exports data into.
+        /// Output target filename where dynein exports data into. Use "-" to write to stdout (e.g. to pipe items to other commands).
         #[clap(short, long, verbatim_doc_comment)]
         output_file: String,
 
@@ -346,8 +752,10 @@ pub enum Sub {
         ///   json = JSON format with newline/indent.{n}
         ///   jsonl = JSON Lines (http://jsonlines.org). i.e. one item per line.{n}
         ///   json-compact = JSON format, all items are packed in oneline.{n}
-        ///   csv = comma-separated values with header. Use it with --keys-only or --attributes. If neither of them are given dynein will ask you target attributes interactively.
-        #[clap(short, long, value_parser = ["csv", "json", "jsonl", "json-compact"], verbatim_doc_comment)]
+        ///   csv = comma-separated values with header. Use it with --keys-only or --attributes. If neither of them are given dynein will ask you target attributes interactively.{n}
+        ///   avro = Avro object container file. The schema is derived from the table, or given by --avro-schema.{n}
+        ///   xlsx = Excel workbook with a header row of attribute names. Numbers and booleans are typed cells.
+        #[clap(short, long, value_parser = ["csv", "json", "jsonl", "json-compact", "avro", "xlsx"], verbatim_doc_comment)]
         format: Option<String>,
 
         /// [csv] Specify attributes to export, separated by commas (e.g. --attributes name,address,age). Effective only when --format is 'csv'.{n}
@@ -358,6 +766,51 @@ pub enum Sub {
         /// [csv] Export only Primary Key(s). Effective only when --format is 'csv'.
         #[clap(long, conflicts_with("attributes"), verbatim_doc_comment)]
         keys_only: bool,
+
+        /// [avro] Avro schema file of a record, whose fields are attributes to export. Effective only when --format is 'avro'.{n}
+        /// Without it, the schema is derived from the primary keys and the attributes of the first page of scanned items.
+        #[clap(long, verbatim_doc_comment)]
+        avro_schema: Option<String>,
+
+        /// jq-like transform applied to each item before writing it, with steps separated by '|'.{n}
+        ///   del(.attr), .attr = value, .new = .old, .attr |= tonumber (or tostring), select(.attr >= value), select(has(.attr)){n}
+        /// e.g. --transform 'del(.password) | select(.status == "active")'
+        #[clap(long, verbatim_doc_comment)]
+        transform: Option<String>,
+
+        /// File to record progress in after each page of Scan. If the file exists, the export resumes from the recorded position,
+        /// appending to the output file. The file is removed when the export completes. Not available with avro and xlsx formats.
+        #[clap(
+            long,
+            value_name = "FILE",
+            conflicts_with("consistent"),
+            verbatim_doc_comment
+        )]
+        checkpoint: Option<String>,
+
+        /// Export a consistent snapshot of the table as of a single point in time, instead of items scanned at different times.
+        /// The table is exported into --s3-bucket by ExportTableToPointInTime API, and the exported files are downloaded into the output file.
+        /// Point-in-time recovery (PITR) has to be enabled on the table. Not available with avro format.
+        #[clap(long, requires = "s3_bucket", verbatim_doc_comment)]
+        consistent: bool,
+
+        /// [consistent] S3 bucket to export the table into. Exported files are left in the bucket after the export.
+        #[clap(
+            long,
+            value_name = "BUCKET",
+            requires = "consistent",
+            verbatim_doc_comment
+        )]
+        s3_bucket: Option<String>,
+
+        /// [consistent] Prefix of S3 keys of the exported files.
+        #[clap(
+            long,
+            value_name = "PREFIX",
+            requires = "consistent",
+            verbatim_doc_comment
+        )]
+        s3_prefix: Option<String>,
     },
 
     /// Import items into a DynamoDB table from CSV/JSON file.
@@ -374,13 +827,115 @@ pub enum Sub {
         ///   json = JSON format with newline/indent.{n}
         ///   jsonl = JSON Lines (http://jsonlines.org). i.e. one item per line.{n}
         ///   json-compact = JSON format, all items are packed in oneline.{n}
-        ///   csv = comma-separated values with header. Header columns are considered to be DynamoDB attributes.
-        #[clap(short, long, value_parser = ["csv", "json", "jsonl", "json-compact"], verbatim_doc_comment)]
+        ///   csv = comma-separated values with header. Header columns are considered to be DynamoDB attributes.{n}
+        ///   sql = INSERT statements in a SQL dump of MySQL or PostgreSQL. Columns are considered to be DynamoDB attributes.{n}
+        ///   avro = Avro object container file. Fields of records are considered to be DynamoDB attributes.
+        #[clap(short, long, value_parser = ["csv", "json", "jsonl", "json-compact", "sql", "avro"], verbatim_doc_comment)]
         format: Option<String>,
 
         /// Enable type inference for set types. This option is provided for backward compatibility.
-        #[clap(long)]
+        /// A summary of how many values were inferred as sets or lists is shown for each attribute after the import.
+        #[clap(long, verbatim_doc_comment)]
         enable_set_inference: bool,
+
+        /// Attributes whose arrays are inferred as sets (SS/NS) even without --enable-set-inference, separated by commas.
+        /// e.g. --set-attrs tags,scores
+        #[clap(long, value_delimiter = ',', verbatim_doc_comment)]
+        set_attrs: Vec<String>,
+
+        /// Attributes whose arrays are always imported as lists (L) with --enable-set-inference, separated by commas.
+        /// e.g. --list-attrs history
+        #[clap(long, value_delimiter = ',', verbatim_doc_comment)]
+        list_attrs: Vec<String>,
+
+        /// Attributes to be imported as binary types, separated by commas. e.g. --binary-attributes image,thumbnails
+        /// Values of these attributes should be base64 encoded strings (Binary) or lists of them (Binary Set), as dynein exports binary attributes.
+        /// Primary keys of binary type are always imported as binary.
+        #[clap(long, verbatim_doc_comment)]
+        binary_attributes: Option<String>,
+
+        /// How to handle rows which are malformed or rejected by DynamoDB.{n}
+        ///   stop = abort the import at the first failed row (default).{n}
+        ///   skip = report failed rows and continue.{n}
+        ///   dlq = write failed rows with error reasons into a dead-letter file (see --dlq-file) and continue.
+        #[clap(long, value_parser = ["stop", "skip", "dlq"], verbatim_doc_comment)]
+        on_error: Option<String>,
+
+        /// Dead-letter file to write failed rows into, as JSON Lines. Used with --on-error dlq.
+        /// Default is "<INPUT_FILE>.dlq.jsonl".
+        #[clap(long, verbatim_doc_comment)]
+        dlq_file: Option<String>,
+
+        /// How to write items into the table.{n}
+        ///   replace = put items with PutItem, i.e. existing items with the same primary key are replaced (default).{n}
+        ///   merge = update items with UpdateItem, i.e. attributes absent from the input file are kept in existing items.
+        #[clap(long, value_parser = ["replace", "merge"], verbatim_doc_comment)]
+        update_mode: Option<String>,
+
+        /// jq-like transform applied to each item before writing it, with steps separated by '|'.{n}
+        ///   del(.attr), .attr = value, .new = .old, .attr |= tonumber (or tostring), select(.attr >= value), select(has(.attr)){n}
+        /// e.g. --transform '.user_name = .name | del(.name) | select(.age >= 20)'
+        #[clap(long, verbatim_doc_comment)]
+        transform: Option<String>,
+
+        /// Normalize legacy data which DynamoDB rejects before writing items, separated by commas.{n}
+        ///   drop-empty = remove empty strings from sets, and attributes which are empty strings or empty sets.{n}
+        ///   null-to-missing = remove attributes whose values are null.{n}
+        ///   empty-ss-to-null = convert empty sets into null.{n}
+        /// e.g. --normalize drop-empty,null-to-missing
+        #[clap(long, value_delimiter = ',', value_parser = ["drop-empty", "null-to-missing", "empty-ss-to-null"], verbatim_doc_comment)]
+        normalize: Vec<String>,
+
+        /// Skip confirmation before importing items, which is required when `safety.require_confirmation` contains "import".
+        #[clap(short, long, verbatim_doc_comment)]
+        yes: bool,
+
+        /// Write items even if their estimated sizes exceed the item size limit of 400 KB, showing warnings.
+        /// By default, such items are handled as failed rows (see --on-error).
+        #[clap(long, verbatim_doc_comment)]
+        force: bool,
+
+        /// Temporarily increase WCU of a Provisioned table to the given value during the import, and restore it afterwards.
+        /// Writes into a Provisioned table are always paced to its WCU.
+        #[clap(long, value_name = "N", verbatim_doc_comment)]
+        boost_wcu: Option<i64>,
+    },
+
+    /// Query items in a file exported by `dy export`, without calling any AWS APIs.
+    ///
+    /// Items are filtered with the same syntax as --filter of `dy query`, so that you can analyze a snapshot of a table offline.
+    #[clap(verbatim_doc_comment)]
+    LocalQuery {
+        /// File exported by `dy export`.
+        #[clap(long, verbatim_doc_comment)]
+        file: String,
+
+        /// Data format of the file. By default, it's guessed from the extension of the file, i.e. ".jsonl" for jsonl, ".avro" for avro, and json otherwise.
+        #[clap(long, value_parser = ["json", "jsonl", "json-compact", "avro"], verbatim_doc_comment)]
+        format: Option<String>,
+
+        /// Condition to filter items, which is evaluated locally with the same semantics as FilterExpression.
+        /// e.g. 'age >= 20 AND attribute_exists(email)'
+        #[clap(short, long, verbatim_doc_comment)]
+        filter: Option<String>,
+
+        /// Names of primary key(s) of items, separated by commas (e.g. --keys pk,sk), which are shown as the first columns.
+        /// By default, primary keys of the table in use (i.e. cached by `dy use` or given by --table) are used.
+        #[clap(
+            long,
+            value_delimiter = ',',
+            value_name = "PK[,SK]",
+            verbatim_doc_comment
+        )]
+        keys: Vec<String>,
+
+        /// Limit the number of items to show. By default, all matched items are shown.
+        #[clap(short, long, verbatim_doc_comment)]
+        limit: Option<usize>,
+
+        /// Switch output format.
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
+        output: Option<String>,
     },
 
     /// Take backup of a DynamoDB table using on-demand backup
@@ -395,6 +950,23 @@ pub enum Sub {
         /// List backups for all tables in the region
         #[clap(long, verbatim_doc_comment)]
         all_tables: bool,
+
+        /// Wait until the backup becomes AVAILABLE, showing status changes. Exits with non-zero status if it takes more than 1 hour.
+        #[clap(long, conflicts_with = "list", verbatim_doc_comment)]
+        wait: bool,
+
+        /// Name of the backup, where {table}, {date} (YYYY-MM-DD), {time} (HHMMSS) and {epoch} are replaced in UTC,
+        /// e.g. '{table}-{date}'. Defaults to '{table}--dynein-{epoch}'.
+        #[clap(long, conflicts_with = "list", verbatim_doc_comment)]
+        name_template: Option<String>,
+
+        /// Skip creating a backup if the table already has one created today (UTC), e.g. for backups run by cron.
+        #[clap(long, conflicts_with = "list", verbatim_doc_comment)]
+        if_not_exists_today: bool,
+
+        /// Switch output format. Except for table, the backup (or backups with --list) is printed with its ARN, status and size.
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
+        output: Option<String>,
     },
 
     /// Restore a DynamoDB table from backup data
@@ -409,6 +981,60 @@ pub enum Sub {
         /// Name of the newly restored table. If not specified, default naming rule "<source-table-name>-restore-<timestamp>" would be used.
         #[clap(long, verbatim_doc_comment)]
         restore_name: Option<String>,
+
+        /// Return right after the restore starts. By default, dynein waits until the restored table and its GSIs become ACTIVE,
+        /// showing status changes and GSI backfilling. Exits with non-zero status if it takes more than 1 hour.
+        #[clap(long, verbatim_doc_comment)]
+        no_wait: bool,
+    },
+
+    /// <sub> Preview expressions generated by dynein without reading or writing items
+    #[clap(verbatim_doc_comment)]
+    Expr {
+        #[clap(subcommand, verbatim_doc_comment)]
+        grandchild: ExprSub,
+    },
+
+    /// <sub> Migrate attributes of all items in a table, e.g. renaming an attribute
+    #[clap(verbatim_doc_comment)]
+    Migrate {
+        #[clap(subcommand, verbatim_doc_comment)]
+        grandchild: MigrateSub,
+    },
+
+    /// <sub> Analyze items in a table, e.g. finding items which have expired by TTL
+    #[clap(verbatim_doc_comment)]
+    Analyze {
+        #[clap(subcommand, verbatim_doc_comment)]
+        grandchild: AnalyzeSub,
+    },
+
+    /// <sub> Estimate cost of a table from its size and consumed capacity
+    #[clap(verbatim_doc_comment)]
+    Cost {
+        #[clap(subcommand, verbatim_doc_comment)]
+        grandchild: CostSub,
+    },
+
+    /// <sub> Show history of mutating commands, which is recorded when `history.enabled` is set in config.yml
+    #[clap(verbatim_doc_comment)]
+    History {
+        #[clap(subcommand, verbatim_doc_comment)]
+        grandchild: HistorySub,
+    },
+
+    /// <sub> Get or put items of entities defined in `entities` of config.yml, building their keys from templates
+    #[clap(verbatim_doc_comment)]
+    Entity {
+        #[clap(subcommand, verbatim_doc_comment)]
+        grandchild: EntitySub,
+    },
+
+    /// <sub> Build and parse keys with the templates of entities defined in `entities` of config.yml
+    #[clap(verbatim_doc_comment)]
+    Key {
+        #[clap(subcommand, verbatim_doc_comment)]
+        grandchild: KeySub,
     },
 }
 
@@ -420,6 +1046,23 @@ pub enum AdminSub {
         /// List DynamoDB tables in all available regions
         #[clap(long, verbatim_doc_comment)]
         all_regions: bool,
+
+        /// List DynamoDB tables in the given regions, separated by commas (e.g. --regions us-east-1,eu-west-1).
+        #[clap(
+            long,
+            value_delimiter = ',',
+            conflicts_with = "all_regions",
+            verbatim_doc_comment
+        )]
+        regions: Option<Vec<String>>,
+
+        /// Show item count, size, billing mode and creation date of each table. [API: DescribeTable]
+        #[clap(long, verbatim_doc_comment)]
+        detail: bool,
+
+        /// Sort tables by name (ascending), size or items (descending). Requires --detail option.
+        #[clap(long, value_parser = ["name", "size", "items"], requires = "detail", verbatim_doc_comment)]
+        sort_by: Option<String>,
     },
 
     /// Show detailed information of a table. [API: DescribeTable]
@@ -428,12 +1071,20 @@ pub enum AdminSub {
         /// Target table name. Optionally you may specify the target table by --table (-t) option.
         target_table_to_desc: Option<String>,
 
-        /// Show details of all tables in the region
+        /// Show details of all tables in the region, as a single document keyed by table names
         #[clap(long, verbatim_doc_comment)]
         all_tables: bool,
 
+        /// Show only the schema, capacity and status of the given GSI or LSI
+        #[clap(long, value_name = "NAME", conflicts_with_all = ["all_tables", "streams"], verbatim_doc_comment)]
+        index: Option<String>,
+
+        /// Show only the ARN, label and view type of the stream
+        #[clap(long, conflicts_with = "all_tables", verbatim_doc_comment)]
+        streams: bool,
+
         /// Switch output format.
-        #[clap(short, long, value_parser = ["yaml" /*, "raw" */ ], verbatim_doc_comment)]
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
         output: Option<String>,
     },
 
@@ -458,6 +1109,58 @@ pub enum AdminSub {
         target_type: DeleteSub,
     },
 
+    /// Wait until a table and its GSIs reach the given status. [API: DescribeTable]
+    #[clap(verbatim_doc_comment)]
+    Wait {
+        /// Target table name. Optionally you may specify the target table by --table (-t) option.
+        target_table_to_wait: Option<String>,
+
+        /// Status to wait for. "active" waits until the table and all GSIs become ACTIVE, and "deleted" waits until the table is deleted.
+        #[clap(long, value_parser = ["active", "deleted"], default_value = "active", verbatim_doc_comment)]
+        until: String,
+
+        /// Maximum time to wait in seconds. dynein exits with non-zero status on timeout.
+        #[clap(long, default_value = "600", verbatim_doc_comment)]
+        timeout: u64,
+    },
+
+    /// Manage tags of a table, e.g. for cost allocation. [API: ListTagsOfResource, TagResource, UntagResource]
+    #[clap(verbatim_doc_comment)]
+    Tags {
+        #[clap(subcommand, verbatim_doc_comment)]
+        action: TagsSub,
+    },
+
+    /// Save settings of all tables in the region into a YAML file, to detect drift later. [API: ListTables, DescribeTable]
+    #[clap(verbatim_doc_comment)]
+    Snapshot {
+        /// YAML file to save the snapshot into.
+        #[clap(short, long, verbatim_doc_comment)]
+        output: String,
+    },
+
+    /// Report drift of tables in the region since a snapshot saved by `dy admin snapshot`. [API: ListTables, DescribeTable]
+    #[clap(verbatim_doc_comment)]
+    DiffSnapshot {
+        /// YAML file of the snapshot.
+        snapshot_file: String,
+    },
+
+    /// Print a least-privilege IAM policy to run the given dynein operations on the table, including its indexes. [API: DescribeTable]
+    #[clap(verbatim_doc_comment)]
+    IamPolicy {
+        /// dynein operations which the policy allows, e.g. `dy admin iam-policy get put query`.
+        #[clap(required = true, value_parser = ["get", "put", "del", "upd", "bwrite", "scan", "query", "find-sk", "browse", "export", "import", "desc", "list", "backup"], verbatim_doc_comment)]
+        operations: Vec<String>,
+    },
+
+    /// Check replicas of a global table. [API: DescribeTable, PutItem, GetItem, DeleteItem]
+    #[clap(verbatim_doc_comment)]
+    Replica {
+        #[clap(subcommand, verbatim_doc_comment)]
+        action: ReplicaSub,
+    },
+
     /// [WIP] Create or update DynamoDB tables based on CloudFormation template files (.cfn.yml).
     #[clap(hide = true)]
     Apply {
@@ -498,6 +1201,10 @@ pub enum CreateSub {
         /// e.g. for Partition key only table: `--keys myPk,S`, and for Partition and Sort key table `--keys myPk,S mySk,N`
         #[clap(short, long, required = true, num_args = 1..=2, verbatim_doc_comment)]
         keys: Vec<String>,
+
+        /// Wait until the table becomes ACTIVE, showing status changes. Exits with non-zero status if it takes more than 1 hour.
+        #[clap(long, verbatim_doc_comment)]
+        wait: bool,
     },
 
     /// Create new GSI (global secondary index) for a table with given primary key(s). [API: UpdateTable]
@@ -510,6 +1217,11 @@ pub enum CreateSub {
         /// e.g. for Partition key only table: `--keys myPk,S`, and for Partition and Sort key table `--keys myPk,S mySk,N`
         #[clap(short, long, required = true, num_args = 1..=2, verbatim_doc_comment)]
         keys: Vec<String>,
+
+        /// Attributes projected into the index: `all`, `keys-only` or `include:attr1,attr2`.
+        /// Projecting fewer attributes reduces storage and write cost of the index.
+        #[clap(long, default_value = "all", verbatim_doc_comment)]
+        projection: String,
     },
 }
 
@@ -533,8 +1245,25 @@ pub enum UpdateSub {
         /// RCU (read capacity units) for the table. Acceptable only on Provisioned mode.
         #[clap(long, verbatim_doc_comment)]
         rcu: Option<i64>,
+
+        /// DynamoDB table class. Availablle values: [standard, standard-ia].
+        /// Standard-IA (Infrequent Access) reduces storage cost for tables that are rarely accessed.
+        #[clap(long, value_parser = ["standard", "standard-ia"], verbatim_doc_comment)]
+        class: Option<String>,
+
+        /// Maximum WRU (write request units) per second for the table. Acceptable only on OnDemand mode. -1 removes the limit.
+        #[clap(long, allow_hyphen_values = true, verbatim_doc_comment)]
+        max_wru: Option<i64>,
+
+        /// Maximum RRU (read request units) per second for the table. Acceptable only on OnDemand mode. -1 removes the limit.
+        #[clap(long, allow_hyphen_values = true, verbatim_doc_comment)]
+        max_rru: Option<i64>,
+
+        /// Encrypt the table at rest with the given KMS key (ARN, key ID or alias), e.g. to switch from the AWS owned key to a customer managed key.
+        /// The table remains available while it's re-encrypted, and `dy desc` shows the progress in the encryption field.
+        #[clap(long, value_name = "ARN", verbatim_doc_comment)]
+        sse_kms_key: Option<String>,
         // TODO: support following parameters
-        // - sse_enabled: bool, (default false) ... UpdateTable API
         // - stream_enabled: bool, (default false) ... UpdateTable API
         // - ttl_enabled: bool, UpdateTimeToLive API
         // - pitr_enabled: bool, UpdateContinuousBackups API (PITR)
@@ -552,12 +1281,54 @@ pub enum DeleteSub {
         /// Skip interactive confirmation before deleting a table.
         #[clap(short, long, verbatim_doc_comment)]
         yes: bool,
+
+        /// Wait until the table is deleted, showing status changes. Exits with non-zero status if it takes more than 1 hour.
+        #[clap(long, verbatim_doc_comment)]
+        wait: bool,
     },
     // #[clap(verbatim_doc_comment)]
     // Index {
     // }
 }
 
+#[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
+pub enum TagsSub {
+    /// Show tags of the target table. [API: ListTagsOfResource]
+    #[clap(aliases = &["ls"], verbatim_doc_comment)]
+    List,
+
+    /// Add tags to the target table. Existing tags with the same keys are overwritten. [API: TagResource]
+    #[clap(verbatim_doc_comment)]
+    Set {
+        /// Tags in the form of KEY=VALUE, e.g. `dy admin tags set env=prod team=payments`
+        #[clap(required = true, num_args = 1.., verbatim_doc_comment)]
+        tags: Vec<String>,
+    },
+
+    /// Remove tags from the target table. [API: UntagResource]
+    #[clap(aliases = &["rm"], verbatim_doc_comment)]
+    Remove {
+        /// Keys of tags to remove, e.g. `dy admin tags remove env team`
+        #[clap(required = true, num_args = 1.., verbatim_doc_comment)]
+        keys: Vec<String>,
+    },
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ReplicaSub {
+    /// Write a probe item in the current region and report how long it takes to be replicated to each replica region.
+    /// The probe item is deleted afterwards. [API: DescribeTable, PutItem, GetItem, DeleteItem]
+    #[clap(verbatim_doc_comment)]
+    Lag {
+        /// Target table name. Optionally you may specify the target table by --table (-t) option.
+        target_table: Option<String>,
+
+        /// Maximum time to wait for the probe item in seconds. dynein exits with non-zero status on timeout.
+        #[clap(long, default_value = "60", verbatim_doc_comment)]
+        timeout: u64,
+    },
+}
+
 #[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
 pub enum ConfigSub {
     /// Show all configuration in config (config.yml) and cache (cache.yml) files.
@@ -568,6 +1339,255 @@ pub enum ConfigSub {
     /// Reset all dynein configuration in the `~/.dynein/` directory. This command initializes dynein related files only and won't remove your data stored in DynamoDB tables.
     #[clap(verbatim_doc_comment)]
     Clear,
+
+    /// Show effective configuration and where each value comes from (flag, env, config or default).
+    #[clap(verbatim_doc_comment)]
+    Get {
+        /// Show only the key, e.g. query.strict_mode
+        #[clap(verbatim_doc_comment)]
+        key: Option<String>,
+    },
+
+    /// Set a value in config.yml, e.g. `dy config set retry.default.max_attempts 5`.
+    #[clap(verbatim_doc_comment)]
+    Set {
+        /// Key to set, e.g. query.strict_mode. Run `dy config get` to see available keys.
+        #[clap(verbatim_doc_comment)]
+        key: String,
+
+        /// Value to set. Durations are given in seconds, e.g. 0.5, and `null` unsets an optional value.
+        #[clap(verbatim_doc_comment)]
+        value: String,
+    },
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ExprSub {
+    /// Preview an update expression generated from `dy upd --set`.
+    #[clap(verbatim_doc_comment)]
+    Set {
+        /// Set expression, e.g. 'name = "John", age = age + 1'
+        #[clap(verbatim_doc_comment)]
+        expression: String,
+    },
+
+    /// Preview an update expression generated from `dy upd --remove`.
+    #[clap(verbatim_doc_comment)]
+    Remove {
+        /// Remove expression, e.g. 'name, tags[0]'
+        #[clap(verbatim_doc_comment)]
+        expression: String,
+    },
+
+    /// Preview a key condition expression generated from `dy query --sort-key`.
+    #[clap(verbatim_doc_comment)]
+    Sortkey {
+        /// Sort key expression, e.g. 'between 10 and 20'
+        #[clap(verbatim_doc_comment)]
+        expression: String,
+
+        /// Sort key name followed by comma and data type (S/N/B), e.g. `--key mySk,N`. Defaults to the sort key of the target table,
+        /// which is read from the cache or retrieved by DescribeTable API.
+        #[clap(short, long, verbatim_doc_comment)]
+        key: Option<String>,
+    },
+
+    /// Preview a condition expression, e.g. 'attribute_exists(id) AND size(tags) > 2'.
+    #[clap(verbatim_doc_comment)]
+    Condition {
+        /// Condition expression, e.g. 'age >= 20 AND NOT contains(tags, "inactive")'
+        #[clap(verbatim_doc_comment)]
+        expression: String,
+    },
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
+pub enum MigrateSub {
+    /// Rename an attribute in all items, i.e. copy the value of FROM to TO and remove FROM. [API: Scan, UpdateItem]
+    ///
+    /// Items which already have TO are left as they are and reported by the verification at the end.
+    /// Primary key attributes cannot be renamed as DynamoDB doesn't allow updating them.
+    #[clap(verbatim_doc_comment)]
+    RenameAttr {
+        /// Current name of the attribute.
+        from: String,
+
+        /// New name of the attribute.
+        to: String,
+
+        /// Maximum number of items updated in parallel. Defaults to 8.
+        #[clap(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..), verbatim_doc_comment)]
+        concurrency: Option<u16>,
+
+        /// File to record progress in. If the file exists, the migration resumes from the recorded position.
+        /// The file is removed when the migration completes.
+        #[clap(long, value_name = "FILE", verbatim_doc_comment)]
+        checkpoint: Option<String>,
+    },
+
+    /// Convert values of an attribute in all items into numbers or strings. [API: Scan, UpdateItem]
+    ///
+    /// Strings are converted into numbers with `--to N`, and numbers into strings with `--to S`.
+    /// Strings which are not numbers are left as they are and reported at the end.
+    #[clap(verbatim_doc_comment)]
+    ConvertType {
+        /// Name of the attribute to convert.
+        #[clap(long, verbatim_doc_comment)]
+        attr: String,
+
+        /// Type to convert values into.
+        #[clap(long, value_parser = ["N", "S"], verbatim_doc_comment)]
+        to: String,
+
+        /// Maximum number of items updated in parallel. Defaults to 8.
+        #[clap(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..), verbatim_doc_comment)]
+        concurrency: Option<u16>,
+    },
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
+pub enum AnalyzeSub {
+    /// Find items whose TTL has passed but which DynamoDB hasn't deleted yet. [API: DescribeTimeToLive, Scan]
+    ///
+    /// DynamoDB deletes expired items in the background, typically within a few days after they expire.
+    /// Items are listed with their TTL attribute, and the number of them is shown in stderr.
+    #[clap(verbatim_doc_comment)]
+    Expired {
+        /// Attribute which stores expiration time in epoch seconds. Defaults to the TTL attribute of the table.
+        #[clap(long, value_name = "ATTR", verbatim_doc_comment)]
+        ttl_attr: Option<String>,
+
+        /// Delete the expired items found, unless their TTL has been extended in the meantime. [API: DeleteItem]
+        #[clap(long, verbatim_doc_comment)]
+        delete: bool,
+
+        /// Switch output format.
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
+pub enum CostSub {
+    /// Estimate the monthly cost of a table in on-demand and provisioned modes. [API: DescribeTable, GetMetricStatistics]
+    ///
+    /// The estimate is based on consumed capacity of the table and its GSIs recorded in CloudWatch, and list prices of us-east-1.
+    /// Provisioned mode is estimated with auto scaling at 70% target utilization, and with the current capacity for provisioned tables.
+    #[clap(verbatim_doc_comment)]
+    Estimate {
+        /// Number of past days of consumed capacity which the estimate is based on. CloudWatch keeps hourly metrics for 63 days.
+        #[clap(long, default_value = "14", value_parser = clap::value_parser!(u16).range(1..=63), verbatim_doc_comment)]
+        days: u16,
+
+        /// Switch output format.
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
+pub enum HistorySub {
+    /// List recorded commands, oldest first.
+    #[clap(aliases = &["ls"], verbatim_doc_comment)]
+    List {
+        /// Number of latest commands to list.
+        #[clap(short, long, default_value = "20", verbatim_doc_comment)]
+        limit: usize,
+    },
+
+    /// Show a recorded command with all of its arguments in JSON.
+    #[clap(verbatim_doc_comment)]
+    Show {
+        /// ID of the command, which is shown by `dy history list`.
+        id: u64,
+    },
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
+pub enum EntitySub {
+    /// Retrieve an item of the entity, e.g. `dy entity get user --id 42`. [API: GetItem]
+    ///
+    /// Options of this command have to be given before NAME, as arguments after NAME are parameters of the key templates.
+    #[clap(verbatim_doc_comment)]
+    Get {
+        /// Name of the entity in `entities` of config.yml.
+        name: String,
+
+        /// Values of placeholders in the key templates, e.g. `--id 42` for "USER#{id}".
+        #[clap(
+            value_name = "PARAMS",
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            verbatim_doc_comment
+        )]
+        params: Vec<String>,
+
+        /// Strong consistent read - to make sure retrieve the most up-to-date data. By default (false), eventual consistent reads would occur.
+        #[clap(long, verbatim_doc_comment)]
+        consistent_read: bool,
+
+        /// Switch output format.
+        #[clap(short, long, value_parser = output::FORMAT_NAMES, verbatim_doc_comment)]
+        output: Option<String>,
+    },
+
+    /// Create a new item of the entity, or replace an existing one, e.g. `dy entity put --item '{"name": "John"}' user --id 42`. [API: PutItem]
+    ///
+    /// Options of this command have to be given before NAME, as arguments after NAME are parameters of the key templates.
+    #[clap(verbatim_doc_comment)]
+    Put {
+        /// Name of the entity in `entities` of config.yml.
+        name: String,
+
+        /// Values of placeholders in the key templates, e.g. `--id 42` for "USER#{id}".
+        #[clap(
+            value_name = "PARAMS",
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            verbatim_doc_comment
+        )]
+        params: Vec<String>,
+
+        /// Additional attributes put into the item, which should be valid JSON.
+        #[clap(short, long, verbatim_doc_comment)]
+        item: Option<String>,
+
+        /// Put the item only if no item with the same primary key exists, i.e. never replace an existing item.
+        #[clap(long, verbatim_doc_comment)]
+        if_not_exists: bool,
+    },
+
+    /// List entities defined in config.yml with their key templates.
+    #[clap(aliases = &["ls"], verbatim_doc_comment)]
+    List,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, PartialEq)]
+pub enum KeySub {
+    /// Build keys of the entity from values of placeholders, e.g. `dy key build user 42` prints {"pk": "USER#42", "sk": "PROFILE"}.
+    #[clap(verbatim_doc_comment)]
+    Build {
+        /// Name of the entity in `entities` of config.yml.
+        entity: String,
+
+        /// Values of placeholders, in the order they appear in the templates of the partition key and the sort key.
+        #[clap(allow_hyphen_values = true, verbatim_doc_comment)]
+        values: Vec<String>,
+    },
+
+    /// Print entities whose key templates match the keys, with values of placeholders, e.g. `dy key parse 'USER#42' PROFILE`.
+    #[clap(verbatim_doc_comment)]
+    Parse {
+        /// Partition key value.
+        pval: String,
+
+        /// Sort key value. Without it, only the partition key is matched.
+        sval: Option<String>,
+
+        /// Match the keys only with the templates of the entity.
+        #[clap(long, verbatim_doc_comment)]
+        entity: Option<String>,
+    },
 }
 
 #[cfg(test)]
@@ -583,12 +1603,24 @@ mod tests {
             Sub::Query {
                 pval: r#"pk\is'escaped"#.to_owned(),
                 sort_key_expression: Some("= 12".to_owned()),
+                begins: None,
+                between: None,
+                sk_gt: None,
+                sk_ge: None,
+                sk_lt: None,
+                sk_le: None,
+                filter: None,
                 consistent_read: false,
                 index: None,
                 limit: None,
                 attributes: None,
                 keys_only: false,
                 descending: false,
+                summary: false,
+                replica_region: vec![],
+                expand: vec![],
+                sort_by: None,
+                group_by: None,
                 output: None,
                 strict: false,
                 non_strict: false,
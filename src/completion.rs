@@ -0,0 +1,123 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module generates shell completion scripts for `dy completion <shell>`.
+// In addition to the static script generated by clap_complete, each script has a small helper
+// which completes table names (--table option, `dy use` and `dy desc`) with the ones cached in cache.yml.
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    io::{self, Write},
+};
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+use super::app;
+use super::cmd;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Shells which `dy completion` supports.
+pub const SHELL_NAMES: [&str; 3] = ["bash", "zsh", "fish"];
+
+const BIN_NAME: &str = "dy";
+
+const BASH_TABLE_COMPLETION: &str = r#"
+_dy_with_tables() {
+    case "$3" in
+        -t|--table|use|desc)
+            COMPREPLY=($(compgen -W "$(dy completion --cached-tables 2>/dev/null)" -- "$2"))
+            return 0
+            ;;
+    esac
+    _dy "$@"
+}
+complete -F _dy_with_tables -o bashdefault -o default dy
+"#;
+
+const ZSH_TABLE_COMPLETION: &str = r#"
+_dy_with_tables() {
+    case "${words[CURRENT-1]}" in
+        -t|--table|use|desc)
+            local -a tables
+            tables=(${(f)"$(dy completion --cached-tables 2>/dev/null)"})
+            compadd -a tables
+            return
+            ;;
+    esac
+    _dy "$@"
+}
+compdef _dy_with_tables dy
+"#;
+
+const FISH_TABLE_COMPLETION: &str = r#"
+complete -c dy -s t -l table -x -a '(dy completion --cached-tables 2>/dev/null)'
+complete -c dy -n '__fish_seen_subcommand_from use desc' -f -a '(dy completion --cached-tables 2>/dev/null)'
+"#;
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Prints the completion script for the given shell to stdout.
+pub fn print_completion_script(shell: &str) -> Result<(), Box<dyn Error>> {
+    let table_completion = match shell {
+        "bash" => BASH_TABLE_COMPLETION,
+        "zsh" => ZSH_TABLE_COMPLETION,
+        "fish" => FISH_TABLE_COMPLETION,
+        _ => return Err(format!("unsupported shell: {}", shell).into()),
+    };
+    let generator: Shell = shell.parse()?;
+
+    let mut stdout = io::stdout().lock();
+    clap_complete::generate(
+        generator,
+        &mut cmd::Dynein::command(),
+        BIN_NAME,
+        &mut stdout,
+    );
+    stdout.write_all(table_completion.as_bytes())?;
+    Ok(())
+}
+
+/// Prints names of tables in cache.yml line by line, which the completion scripts use as candidates.
+pub fn print_cached_tables(cx: &app::Context) {
+    for table_name in cached_table_names(cx) {
+        println!("{}", table_name);
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Returns table names in cache.yml, whose keys are "<Region>/<TableName>", without duplicates.
+fn cached_table_names(cx: &app::Context) -> BTreeSet<String> {
+    cx.cache
+        .as_ref()
+        .and_then(|cache| cache.tables.as_ref())
+        .map(|tables| {
+            tables
+                .keys()
+                .map(|key| key.split_once('/').map_or(key.as_str(), |(_, name)| name))
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -16,18 +16,30 @@
 
 // This module interact with DynamoDB Control Plane APIs
 use aws_sdk_dynamodb::{
+    operation::{describe_table::DescribeTableError, list_tables::ListTablesError},
+    primitives::DateTime as SdkDateTime,
     types::{
         BackupStatus, BackupSummary, BillingMode, CreateGlobalSecondaryIndexAction,
-        GlobalSecondaryIndexUpdate, Projection, ProjectionType, ProvisionedThroughput,
-        TableDescription,
+        ExportDescription, ExportStatus, GlobalSecondaryIndexUpdate, IndexStatus,
+        OnDemandThroughput, ProvisionedThroughput, SseSpecification, SseType, TableClass,
+        TableDescription, TableStatus, Tag, TimeToLiveStatus,
     },
     Client as DynamoDbSdkClient,
 };
 use aws_sdk_ec2::Client as Ec2SdkClient;
-use futures::future::join_all;
+use aws_smithy_runtime_api::client::{orchestrator::HttpResponse, result::SdkError};
+use aws_smithy_types::error::display::DisplayErrorContext;
+use chrono::{DateTime, Utc};
+use futures::{
+    future::join_all,
+    stream::{self, StreamExt},
+};
 use log::{debug, error};
+use serde::Serialize;
 use std::borrow::Cow::{Borrowed, Owned};
+use std::cell::RefCell;
 use std::{
+    fmt,
     io::{self, Error as IOError, Write},
     time,
 };
@@ -37,74 +49,303 @@ use tabwriter::TabWriter;
 
 use super::app;
 use super::ddb::table;
+use super::output;
 
 /* =================================================
-Public functions
+struct / enum / const
 ================================================= */
 
-pub async fn list_tables_all_regions(cx: &app::Context) {
-    // get all regions from us-east-1 regardless specified region
-    let config = cx
-        .clone()
-        .with_region("us-east-1")
-        .effective_sdk_config()
-        .await;
-    let ec2 = Ec2SdkClient::new(&config);
-    match ec2.describe_regions().send().await {
-        Err(e) => {
-            app::bye_with_sdk_error(1, e);
+/// Maximum number of regions to list tables concurrently on `$ dy list --all-regions`.
+const LIST_TABLES_CONCURRENCY: usize = 8;
+
+/// Maximum number of tables to describe concurrently on `$ dy desc --all-tables`.
+const DESCRIBE_TABLES_CONCURRENCY: usize = 8;
+
+/// Waiters poll DescribeTable with exponential backoff, starting from WAIT_MIN_INTERVAL up to WAIT_MAX_INTERVAL.
+const WAIT_MIN_INTERVAL: time::Duration = time::Duration::from_secs(1);
+const WAIT_MAX_INTERVAL: time::Duration = time::Duration::from_secs(20);
+
+/// Status of a table that waiters wait for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableWaitTarget {
+    /// The table and all of its GSIs are ACTIVE.
+    Active,
+    /// The table doesn't exist anymore.
+    Deleted,
+}
+
+impl fmt::Display for TableWaitTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableWaitTarget::Active => write!(f, "active"),
+            TableWaitTarget::Deleted => write!(f, "deleted"),
         }
-        Ok(res) => {
-            join_all(
-                res.regions
-                    .expect("regions should exist") // Vec<Region>
-                    .iter()
-                    .map(|r| list_tables(cx, Some(r.region_name.as_ref().unwrap()))),
-            )
-            .await;
+    }
+}
+
+/// Operations with `--wait` option give up waiting after this duration.
+const OPERATION_WAIT_TIMEOUT: time::Duration = time::Duration::from_secs(60 * 60);
+
+/// Name of backups taken by `$ dy backup` without --name-template option.
+const DEFAULT_BACKUP_NAME_TEMPLATE: &str = "{table}--dynein-{epoch}";
+
+/// Options of `$ dy admin update table`. Options that are not given leave the current settings as they are.
+pub struct UpdateTableParams {
+    pub mode: Option<String>,
+    pub wcu: Option<i64>,
+    pub rcu: Option<i64>,
+    pub class: Option<String>,
+    pub max_wru: Option<i64>,
+    pub max_rru: Option<i64>,
+    pub sse_kms_key: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct WaitTimeoutError {
+    /// e.g. "table 'Movie'" or "backup 'Movie--dynein-1600000000'"
+    resource: String,
+    /// e.g. "active", "deleted" or "available"
+    desired_status: String,
+    timeout: time::Duration,
+}
+
+impl fmt::Display for WaitTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Timed out after {} seconds waiting for the {} to be {}.",
+            self.timeout.as_secs(),
+            self.resource,
+            self.desired_status
+        )
+    }
+}
 
-            if cx.is_local().await {
-                list_tables(cx, None).await;
+/// A backup printed by `dy backup --output` with a format other than table, e.g. json, either the one just created or ones listed by `--list`.
+/// The ARN can be given to restore steps of pipelines as is.
+#[derive(Serialize, Debug)]
+struct PrintBackup {
+    table: String,
+    name: String,
+    arn: String,
+    status: String,
+    size_bytes: Option<i64>,
+    created_at: String,
+}
+
+impl From<BackupSummary> for PrintBackup {
+    fn from(backup: BackupSummary) -> Self {
+        PrintBackup {
+            table: backup.table_name.expect("table name should exist"),
+            name: backup.backup_name.expect("backup name should exist"),
+            arn: backup.backup_arn.expect("backup arn should exist"),
+            status: backup
+                .backup_status
+                .expect("status should exist")
+                .as_str()
+                .to_owned(),
+            size_bytes: backup.backup_size_bytes,
+            created_at: table::epoch_to_rfc3339(
+                backup
+                    .backup_creation_date_time
+                    .expect("creation date should exist")
+                    .as_secs_f64(),
+            ),
+        }
+    }
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Executed when you call `$ dy list --all-regions` or `$ dy list --regions`.
+/// Regions are processed concurrently (up to LIST_TABLES_CONCURRENCY at a time) and results are printed in order.
+/// A region which fails, e.g. because it's not enabled for the account or access is denied, is skipped with a warning.
+pub async fn list_tables_all_regions(
+    cx: &app::Context,
+    regions: Option<Vec<String>>,
+    detail: bool,
+    sort_by: Option<&str>,
+) {
+    // DynamoDB Local is listed in addition to AWS regions, unless regions are explicitly given.
+    let list_local = regions.is_none() && cx.is_local().await;
+    let regions: Vec<String> = match regions {
+        Some(regions) => regions,
+        None => {
+            // get all regions from us-east-1 regardless specified region
+            let config = cx
+                .clone()
+                .with_region("us-east-1")
+                .effective_sdk_config()
+                .await;
+            let ec2 = Ec2SdkClient::new(&config);
+            match ec2.describe_regions().send().await {
+                Err(e) => {
+                    app::bye_with_sdk_error(1, e);
+                }
+                Ok(res) => res
+                    .regions
+                    .expect("regions should exist") // Vec<Region>
+                    .into_iter()
+                    .filter_map(|r| r.region_name)
+                    .collect(),
             }
         }
     };
+
+    let mut results = stream::iter(regions.iter())
+        .map(|r| async move {
+            (
+                r,
+                render_tables(cx, Some(r.as_str()), detail, sort_by).await,
+            )
+        })
+        .buffered(LIST_TABLES_CONCURRENCY);
+    while let Some((region, result)) = results.next().await {
+        match result {
+            Ok(s) => print!("{}", s),
+            Err(e) => {
+                debug!("ListTables API call got an error -- {:#?}", e);
+                eprintln!(
+                    "WARN: Skipped listing tables in region {}: {}",
+                    region,
+                    DisplayErrorContext(&e)
+                );
+            }
+        }
+    }
+
+    if list_local {
+        list_tables(cx, None, detail, sort_by).await;
+    }
 }
 
-pub async fn list_tables(cx: &app::Context, override_region: Option<&str>) {
-    let table_names = list_tables_api(cx, override_region).await;
-    let region = cx.effective_region().await.to_string();
+/// Executed when you call `$ dy list`. With `detail` flag, DescribeTable API is called for each table concurrently
+/// to show item count, size, billing mode and creation date of tables.
+pub async fn list_tables(
+    cx: &app::Context,
+    override_region: Option<&str>,
+    detail: bool,
+    sort_by: Option<&str>,
+) {
+    match render_tables(cx, override_region, detail, sort_by).await {
+        Ok(s) => print!("{}", s),
+        Err(e) => {
+            debug!("ListTables API call got an error -- {:#?}", e);
+            app::bye_with_sdk_error(1, e);
+        }
+    }
+}
+
+/// Builds the output of `$ dy list` for a region as a string,
+/// so that outputs for multiple regions retrieved concurrently are not interleaved.
+async fn render_tables(
+    cx: &app::Context,
+    override_region: Option<&str>,
+    detail: bool,
+    sort_by: Option<&str>,
+) -> Result<String, SdkError<ListTablesError, HttpResponse>> {
+    let table_names = try_list_tables_api(cx, override_region).await?;
+    let region = match override_region {
+        Some(r) => r.to_owned(),
+        None => cx.effective_region().await.to_string(),
+    };
 
-    println!("DynamoDB tables in region: {}", region);
+    let mut out = format!("DynamoDB tables in region: {}\n", region);
     if table_names.is_empty() {
-        return println!("  No table in this region.");
+        out.push_str("  No table in this region.\n");
+        return Ok(out);
     }
 
-    if let Some(table_in_config) = cx.cached_using_table_schema().await {
-        for table_name in table_names {
-            if region == table_in_config.region && table_name == table_in_config.name {
-                println!("* {}", table_name);
-            } else {
-                println!("  {}", table_name);
-            }
+    let using_table: Option<String> = match cx.cached_using_table_schema().await {
+        Some(table_in_config) if table_in_config.region == region => Some(table_in_config.name),
+        Some(_) => None,
+        None => {
+            debug!("No table information (currently using table) is found on config file");
+            None
         }
-    } else {
-        debug!("No table information (currently using table) is found on config file");
+    };
+    let marker = |table_name: &str| {
+        if using_table.as_deref() == Some(table_name) {
+            "*"
+        } else {
+            " "
+        }
+    };
+
+    if !detail {
         for table_name in table_names {
-            println!("  {}", table_name)
+            out.push_str(&format!("{} {}\n", marker(table_name.as_str()), table_name));
         }
+        return Ok(out);
+    }
+
+    let regional_cx = match override_region {
+        Some(r) => Owned(cx.clone().with_region(r)),
+        None => Borrowed(cx),
+    };
+    let mut descs: Vec<TableDescription> = join_all(
+        table_names
+            .into_iter()
+            .map(|t| describe_table_api(regional_cx.as_ref(), t)),
+    )
+    .await;
+    sort_table_descriptions(&mut descs, sort_by);
+
+    let mut tw = TabWriter::new(vec![]);
+    let mut lines = vec![String::from("  name\titems\tsize_bytes\tmode\tcreated_at")];
+    for desc in descs {
+        let name = desc.table_name.unwrap_or_default();
+        lines.push(format!(
+            "{} {}\t{}\t{}\t{:?}\t{}",
+            marker(name.as_str()),
+            name,
+            desc.item_count.unwrap_or(0),
+            desc.table_size_bytes.unwrap_or(0),
+            table::extract_mode(&desc.billing_mode_summary),
+            desc.creation_date_time
+                .map(|t| table::epoch_to_rfc3339(t.as_secs_f64()))
+                .unwrap_or_default(),
+        ));
+    }
+    tw.write_all((lines.join("\n") + "\n").as_bytes()).unwrap();
+    out.push_str(&String::from_utf8(tw.into_inner().unwrap()).unwrap());
+    Ok(out)
+}
+
+/// Sorts tables by the key given by `--sort-by` option: "name" (ascending), "size" or "items" (descending).
+fn sort_table_descriptions(descs: &mut [TableDescription], sort_by: Option<&str>) {
+    match sort_by {
+        Some("size") => descs.sort_by_key(|d| std::cmp::Reverse(d.table_size_bytes.unwrap_or(0))),
+        Some("items") => descs.sort_by_key(|d| std::cmp::Reverse(d.item_count.unwrap_or(0))),
+        // ListTables API returns table names in ascending order, but sort them again just in case.
+        _ => descs.sort_by(|a, b| a.table_name.cmp(&b.table_name)),
     }
 }
 
 /// Executed when you call `$ dy desc --all-tables`.
-/// Note that `describe_table` function calls are executed in parallel (async + join_all).
+/// DescribeTable calls are executed concurrently up to DESCRIBE_TABLES_CONCURRENCY, and descriptions are printed
+/// as a single YAML (or JSON) document which maps table names to descriptions, so that it can be saved as an inventory.
 pub async fn describe_all_tables(cx: &app::Context) {
-    let table_names = list_tables_api(cx, None).await;
-    join_all(table_names.into_iter().map(|t| describe_table(cx, Some(t)))).await;
+    let descs: Vec<TableDescription> = describe_all_tables_api(cx).await;
+    let region = cx.effective_region().await;
+    let doc = output::Document {
+        value: table::table_descriptions(region.as_ref(), &descs),
+        text: None,
+    };
+    output::print_document(cx.output.as_deref(), "yaml", &doc);
 }
 
 /// Executed when you call `$ dy desc (table)`. Retrieve TableDescription via describe_table_api function,
 /// then print them in convenient way using table::print_table_description function (default/yaml).
-pub async fn describe_table(cx: &app::Context, target_table_to_desc: Option<String>) {
+/// With `index` or `streams`, only the index or the stream of the table is printed.
+pub async fn describe_table(
+    cx: &app::Context,
+    target_table_to_desc: Option<String>,
+    index: Option<String>,
+    streams: bool,
+) {
     debug!("context: {:#?}", &cx);
     debug!("positional arg table name: {:?}", &target_table_to_desc);
     let new_context = if let Some(t) = target_table_to_desc {
@@ -122,7 +363,6 @@ pub async fn describe_table(cx: &app::Context, target_table_to_desc: Option<Stri
     );
 
     // save described table info into cache for future use.
-    // Note that when this functiono is called from describe_all_tables, not all tables would be cached as calls are parallel.
     match app::insert_to_table_cache(new_context.as_ref(), &desc).await {
         Ok(_) => debug!("Described table schema was written to the cache file."),
         Err(e) => println!(
@@ -131,53 +371,149 @@ pub async fn describe_table(cx: &app::Context, target_table_to_desc: Option<Stri
         ),
     };
 
-    match new_context.output.as_deref() {
-        None | Some("yaml") => {
-            table::print_table_description(new_context.effective_region().await.as_ref(), &desc)
+    if let Some(index_name) = index {
+        match table::index_description(&desc, &index_name) {
+            Some(value) => print_description(new_context.output.as_deref(), value),
+            None => {
+                let names = table::index_names(&desc);
+                error!(
+                    "No index named '{}' is found in the table '{}'. Available indexes: {}",
+                    index_name,
+                    new_context.effective_table_name(),
+                    if names.is_empty() {
+                        String::from("(none)")
+                    } else {
+                        names.join(", ")
+                    }
+                );
+                std::process::exit(1);
+            }
         }
-        // Some("raw") => println!("{:#?}", desc),
-        Some(_) => {
-            println!("ERROR: unsupported output type.");
-            std::process::exit(1);
+        return;
+    }
+    if streams {
+        print_description(
+            new_context.output.as_deref(),
+            table::stream_description(&desc),
+        );
+        return;
+    }
+
+    print_description(
+        new_context.output.as_deref(),
+        table::table_description(new_context.effective_region().await.as_ref(), &desc),
+    );
+}
+
+/// Describes all tables in the region concurrently up to DESCRIBE_TABLES_CONCURRENCY, and returns them sorted by table names.
+/// Tables deleted after ListTables are skipped with a warning.
+pub async fn describe_all_tables_api(cx: &app::Context) -> Vec<TableDescription> {
+    let table_names = list_tables_api(cx, None).await;
+    let results: Vec<_> = stream::iter(table_names)
+        .map(|t| async move { (t.to_owned(), try_describe_table_api(cx, t).await) })
+        .buffer_unordered(DESCRIBE_TABLES_CONCURRENCY)
+        .collect()
+        .await;
+    let mut descs: Vec<TableDescription> = vec![];
+    for (table_name, result) in results {
+        match result {
+            Ok(desc) => descs.push(desc),
+            Err(e)
+                if e.as_service_error()
+                    .map_or(false, |se| se.is_resource_not_found_exception()) =>
+            {
+                eprintln!(
+                    "WARN: Skipped the table '{}', which has been deleted while describing tables.",
+                    table_name
+                );
+            }
+            Err(e) => {
+                debug!("DescribeTable API call got an error -- {:#?}", e);
+                app::bye_with_sdk_error(1, e);
+            }
         }
     }
+    descs.sort_by(|a, b| a.table_name.cmp(&b.table_name));
+    descs
+}
+
+/// Prints a table description, or a part of it such as an index, in YAML unless another format is given by --output option.
+fn print_description(format: Option<&str>, value: serde_yaml::Value) {
+    output::print_document(format, "yaml", &output::Document { value, text: None });
 }
 
 /// Originally intended to be called by describe_table function, which is called from `$ dy desc`,
 /// however it turned out that DescribeTable API result is useful in various logic, separated API into this standalone function.
 pub async fn describe_table_api(cx: &app::Context, table_name: String) -> TableDescription {
+    try_describe_table_api(cx, table_name)
+        .await
+        .unwrap_or_else(|e| {
+            debug!("DescribeTable API call got an error -- {:#?}", e);
+            app::bye_with_sdk_error(1, e);
+        })
+}
+
+/// Same as describe_table_api, but returns an error instead of exiting, e.g. to skip a table which has been deleted.
+async fn try_describe_table_api(
+    cx: &app::Context,
+    table_name: String,
+) -> Result<TableDescription, SdkError<DescribeTableError, HttpResponse>> {
     let region = cx.effective_region().await;
     let config = cx.effective_sdk_config_with_region(region.as_ref()).await;
     let ddb = DynamoDbSdkClient::new(&config);
 
-    match ddb.describe_table().table_name(table_name).send().await {
+    let res = ddb.describe_table().table_name(table_name).send().await?;
+    let desc: TableDescription = res.table.expect("This message should not be shown.");
+    debug!("Received DescribeTable Result: {:?}\n", desc);
+    Ok(desc)
+}
+
+/// Returns the attribute which TTL of the table is enabled on by DescribeTimeToLive, or None if TTL is not enabled.
+pub async fn ttl_attribute_api(cx: &app::Context, table_name: &str) -> Option<String> {
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    match ddb
+        .describe_time_to_live()
+        .table_name(table_name)
+        .send()
+        .await
+    {
         Err(e) => {
-            debug!("DescribeTable API call got an error -- {:#?}", e);
+            debug!("DescribeTimeToLive API call got an error -- {:#?}", e);
             app::bye_with_sdk_error(1, e);
         }
-        Ok(res) => {
-            let desc: TableDescription = res.table.expect("This message should not be shown.");
-            debug!("Received DescribeTable Result: {:?}\n", desc);
-            desc
-        }
+        Ok(res) => res
+            .time_to_live_description
+            .filter(|desc| desc.time_to_live_status == Some(TimeToLiveStatus::Enabled))
+            .and_then(|desc| desc.attribute_name),
     }
 }
 
 /// This function is designed to be called from dynein command, mapped in main.rs.
 /// Note that it simply ignores --table option if specified. Newly created table name should be given by the 1st argument "name".
-pub async fn create_table(cx: &app::Context, name: String, given_keys: Vec<String>) {
+pub async fn create_table(cx: &app::Context, name: String, given_keys: Vec<String>, wait: bool) {
+    // A table ARN creates the table in the region of the ARN.
+    let cx = &cx.clone().with_table(&name);
+    let name = cx.effective_table_name();
     if given_keys.is_empty() || given_keys.len() >= 3 {
         error!("You should pass one or two key definitions with --keys option");
         std::process::exit(1);
     };
 
-    match create_table_api(cx, name, given_keys).await {
+    match create_table_api(cx, name.clone(), given_keys).await {
         Ok(desc) => table::print_table_description(cx.effective_region().await.as_ref(), &desc),
         Err(e) => {
             debug!("CreateTable API call got an error -- {:#?}", e);
             app::bye_with_sdk_error(1, e);
         }
     }
+
+    if wait {
+        exit_on_wait_timeout(
+            wait_for_table(cx, &name, TableWaitTarget::Active, OPERATION_WAIT_TIMEOUT).await,
+        );
+    }
 }
 
 pub async fn create_table_api(
@@ -212,11 +548,23 @@ pub async fn create_table_api(
         })
 }
 
-pub async fn create_index(cx: &app::Context, index_name: String, given_keys: Vec<String>) {
+pub async fn create_index(
+    cx: &app::Context,
+    index_name: String,
+    given_keys: Vec<String>,
+    given_projection: &str,
+) {
     if given_keys.is_empty() || given_keys.len() >= 3 {
         error!("You should pass one or two key definitions with --keys option");
         std::process::exit(1);
     };
+    let projection = match table::generate_projection(given_projection) {
+        Ok(p) => p,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
     debug!(
         "Trying to create an index '{}' with keys '{:?}', on table '{}' ",
         &index_name,
@@ -233,11 +581,7 @@ pub async fn create_index(cx: &app::Context, index_name: String, given_keys: Vec
     let create_gsi_action = CreateGlobalSecondaryIndexAction::builder()
         .index_name(index_name)
         .set_key_schema(Some(key_schema))
-        .projection(
-            Projection::builder()
-                .projection_type(ProjectionType::All)
-                .build(),
-        )
+        .projection(projection)
         .set_provisioned_throughput(None) // TODO: assign default rcu/wcu if base table is Provisioned mode. currently it works only for OnDemand talbe.
         .build()
         .unwrap();
@@ -271,13 +615,41 @@ pub async fn create_index(cx: &app::Context, index_name: String, given_keys: Vec
 pub async fn update_table(
     cx: &app::Context,
     table_name_to_update: String,
-    mode_string: Option<String>,
-    wcu: Option<i64>,
-    rcu: Option<i64>,
+    params: UpdateTableParams,
 ) {
+    let UpdateTableParams {
+        mode: mode_string,
+        wcu,
+        rcu,
+        class: class_string,
+        max_wru,
+        max_rru,
+        sse_kms_key,
+    } = params;
+    // A table ARN targets the table in the region of the ARN.
+    let cx = &cx.clone().with_table(&table_name_to_update);
+    let table_name_to_update = cx.effective_table_name();
+
     // Retrieve TableDescription of the table to update, current (before update) status.
     let desc: TableDescription = describe_table_api(cx, table_name_to_update.clone()).await;
 
+    // Map given string into "TableClass" enum. Note that in cmd.rs clap already limits acceptable values.
+    let switching_to_class: Option<TableClass> = match class_string.as_deref() {
+        None => None,
+        Some("standard") => Some(TableClass::Standard),
+        Some("standard-ia") => Some(TableClass::StandardInfrequentAccess),
+        Some(_) => panic!(
+            "You shouldn't see this message as --class can takes only 'standard' or 'standard-ia'."
+        ),
+    };
+    let capacity_not_given = (switching_to_class.is_some()
+        || max_wru.is_some()
+        || max_rru.is_some()
+        || sse_kms_key.is_some())
+        && mode_string.is_none()
+        && wcu.is_none()
+        && rcu.is_none();
+
     // Map given string into "Mode" enum. Note that in cmd.rs clap already limits acceptable values.
     let switching_to_mode: Option<table::Mode> = match mode_string {
         None => None,
@@ -290,6 +662,8 @@ pub async fn update_table(
 
     // Configure ProvisionedThroughput struct based on argumsnts (mode/wcu/rcu).
     let provisioned_throughput: Option<ProvisionedThroughput> = match &switching_to_mode {
+        // When only --class, --max-wru/--max-rru or --sse-kms-key is given, capacity is not updated. Passing the current capacity as-is makes UpdateTable fail.
+        None if capacity_not_given => None,
         // when --mode is not given, no mode switch happens. Check the table's current mode.
         None => {
             match table::extract_mode(&desc.billing_mode_summary) {
@@ -343,6 +717,35 @@ pub async fn update_table(
         },
     };
 
+    // Configure OnDemandThroughput struct based on arguments (max_wru/max_rru). They take effect only on OnDemand mode.
+    let will_be_ondemand = match &switching_to_mode {
+        Some(target_mode) => target_mode == &table::Mode::OnDemand,
+        None => table::extract_mode(&desc.billing_mode_summary) == table::Mode::OnDemand,
+    };
+    let on_demand_throughput: Option<OnDemandThroughput> = if max_wru.is_none() && max_rru.is_none()
+    {
+        None
+    } else if will_be_ondemand {
+        Some(
+            OnDemandThroughput::builder()
+                .set_max_write_request_units(max_wru)
+                .set_max_read_request_units(max_rru)
+                .build(),
+        )
+    } else {
+        println!("Ignoring --max-wru/--max-rru options as the table mode is Provisioned.");
+        None
+    };
+
+    // Switch the key for encryption at rest to the given KMS key, e.g. from the AWS owned key to a customer managed key.
+    let sse_specification: Option<SseSpecification> = sse_kms_key.map(|key| {
+        SseSpecification::builder()
+            .enabled(true)
+            .sse_type(SseType::Kms)
+            .kms_master_key_id(key)
+            .build()
+    });
+
     // TODO: support updating CU of the table with GSI. If the table has GSIs, you must specify CU for them at the same time.
     // error message: One or more parameter values were invalid: ProvisionedThroughput must be specified for index: xyz_index,abc_index2
     //   if table has gsi
@@ -353,6 +756,9 @@ pub async fn update_table(
         table_name_to_update,
         switching_to_mode,
         provisioned_throughput,
+        on_demand_throughput,
+        switching_to_class,
+        sse_specification,
     )
     .await
     {
@@ -371,7 +777,7 @@ pub async fn update_table(
 ///   * [-] AttributeDefinitions > array of AttributeDefinition obj
 ///   * [-] GlobalSecondaryIndexUpdates > Create/Update/Delete and details of the update on GSIs
 ///   * [-] ReplicaUpdates > Create/Update/Delete and details of the update on Global Tbles replicas
-///   * [] SSESpecification > obj
+///   * [x] SSESpecification > obj
 ///   * [] StreamSpecification > obj
 ///
 /// [+] = supported, [-] = implemented (or plan to so) in another location, [] = not yet supported
@@ -381,6 +787,9 @@ async fn update_table_api(
     table_name_to_update: String,
     switching_to_mode: Option<table::Mode>,
     provisioned_throughput: Option<ProvisionedThroughput>,
+    on_demand_throughput: Option<OnDemandThroughput>,
+    table_class: Option<TableClass>,
+    sse_specification: Option<SseSpecification>,
 ) -> Result<
     TableDescription,
     aws_sdk_dynamodb::error::SdkError<aws_sdk_dynamodb::operation::update_table::UpdateTableError>,
@@ -394,6 +803,9 @@ async fn update_table_api(
         .table_name(table_name_to_update)
         .set_billing_mode(switching_to_mode.map(|v| v.into()))
         .set_provisioned_throughput(provisioned_throughput)
+        .set_on_demand_throughput(on_demand_throughput)
+        .set_table_class(table_class)
+        .set_sse_specification(sse_specification)
         .send()
         .await
         .map(|res| {
@@ -402,7 +814,61 @@ async fn update_table_api(
         })
 }
 
-pub async fn delete_table(cx: &app::Context, name: String, skip_confirmation: bool) {
+/// Returns the provisioned WCU of the table, or None if the table is OnDemand mode.
+pub fn write_capacity_units(desc: &TableDescription) -> Option<i64> {
+    match table::extract_mode(&desc.billing_mode_summary) {
+        table::Mode::OnDemand => None,
+        table::Mode::Provisioned => desc
+            .provisioned_throughput
+            .as_ref()
+            .and_then(|pt| pt.write_capacity_units),
+    }
+}
+
+/// Updates the provisioned WCU of the table keeping its RCU, and waits until the table becomes ACTIVE again.
+/// Used by `$ dy import --boost-wcu` to increase WCU during an import, and to restore it afterwards.
+pub async fn update_write_capacity(
+    cx: &app::Context,
+    table_name: &str,
+    wcu: i64,
+) -> Result<(), String> {
+    let desc: TableDescription = describe_table_api(cx, table_name.to_owned()).await;
+    let rcu = desc
+        .provisioned_throughput
+        .as_ref()
+        .and_then(|pt| pt.read_capacity_units)
+        .expect("Provisioned table should have RCU");
+    let provisioned_throughput = ProvisionedThroughput::builder()
+        .read_capacity_units(rcu)
+        .write_capacity_units(wcu)
+        .build()
+        .unwrap();
+
+    update_table_api(
+        cx,
+        table_name.to_owned(),
+        None,
+        Some(provisioned_throughput),
+        None,
+        None,
+        None,
+    )
+    .await
+    .map_err(|e| DisplayErrorContext(e).to_string())?;
+    wait_for_table(
+        cx,
+        table_name,
+        TableWaitTarget::Active,
+        OPERATION_WAIT_TIMEOUT,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+pub async fn delete_table(cx: &app::Context, name: String, skip_confirmation: bool, wait: bool) {
+    // A table ARN targets the table in the region of the ARN.
+    let cx = &cx.clone().with_table(&name);
+    let name = cx.effective_table_name();
     debug!("Trying to delete a table '{}'", &name);
 
     let msg = format!("You're trying to delete a table '{}'. Are you OK?", &name);
@@ -414,7 +880,7 @@ pub async fn delete_table(cx: &app::Context, name: String, skip_confirmation: bo
     let config = cx.effective_sdk_config().await;
     let ddb = DynamoDbSdkClient::new(&config);
 
-    match ddb.delete_table().table_name(name).send().await {
+    match ddb.delete_table().table_name(&name).send().await {
         Err(e) => {
             debug!("DeleteTable API call got an error -- {:#?}", e);
             app::bye_with_sdk_error(1, e);
@@ -427,24 +893,360 @@ pub async fn delete_table(cx: &app::Context, name: String, skip_confirmation: bo
             );
         }
     }
+
+    if wait {
+        exit_on_wait_timeout(
+            wait_for_table(cx, &name, TableWaitTarget::Deleted, OPERATION_WAIT_TIMEOUT).await,
+        );
+    }
+}
+
+/// Executed when you call `$ dy admin wait`. Exits with non-zero status on timeout.
+pub async fn wait_table(
+    cx: &app::Context,
+    target_table_to_wait: Option<String>,
+    until: &str,
+    timeout_secs: u64,
+) {
+    let new_context = if let Some(t) = target_table_to_wait {
+        Owned(cx.clone().with_table(&t))
+    } else {
+        Borrowed(cx)
+    };
+    let cx = new_context.as_ref();
+    let table_name = cx.effective_table_name();
+    let target = match until {
+        "deleted" => TableWaitTarget::Deleted,
+        _ => TableWaitTarget::Active,
+    };
+    exit_on_wait_timeout(
+        wait_for_table(
+            cx,
+            &table_name,
+            target,
+            time::Duration::from_secs(timeout_secs),
+        )
+        .await,
+    );
+}
+
+/// Polls DescribeTable API until the table reaches the target status, printing status whenever it changes.
+/// This is the waiter used by `$ dy admin wait` and `--wait` option of commands which create or delete tables.
+pub async fn wait_for_table(
+    cx: &app::Context,
+    table_name: &str,
+    target: TableWaitTarget,
+    timeout: time::Duration,
+) -> Result<(), WaitTimeoutError> {
+    let config = cx.effective_sdk_config().await;
+    let ddb = &DynamoDbSdkClient::new(&config);
+
+    let reached = poll_until(
+        &format!("Table '{}'", table_name),
+        timeout,
+        false,
+        move || async move {
+            match ddb.describe_table().table_name(table_name).send().await {
+                Ok(res) => {
+                    let desc = res.table.expect("This message should not be shown.");
+                    let (all_active, status) = table_status_summary(&desc);
+                    (target == TableWaitTarget::Active && all_active, status)
+                }
+                Err(e)
+                    if e.as_service_error()
+                        .map_or(false, |se| se.is_resource_not_found_exception()) =>
+                {
+                    let status = match target {
+                        TableWaitTarget::Active => String::from("NOT_FOUND"),
+                        TableWaitTarget::Deleted => String::from("DELETED"),
+                    };
+                    (target == TableWaitTarget::Deleted, status)
+                }
+                Err(e) => {
+                    debug!("DescribeTable API call got an error -- {:#?}", e);
+                    app::bye_with_sdk_error(1, e);
+                }
+            }
+        },
+    )
+    .await;
+
+    if reached {
+        Ok(())
+    } else {
+        Err(WaitTimeoutError {
+            resource: format!("table '{}'", table_name),
+            desired_status: target.to_string(),
+            timeout,
+        })
+    }
+}
+
+/// Polls DescribeBackup API until the backup becomes AVAILABLE, printing status whenever it changes.
+/// With `--output` other than table, e.g. json, the status is printed to stderr instead.
+pub async fn wait_for_backup(
+    cx: &app::Context,
+    backup_name: &str,
+    backup_arn: &str,
+    timeout: time::Duration,
+) -> Result<(), WaitTimeoutError> {
+    let config = cx.effective_sdk_config().await;
+    let ddb = &DynamoDbSdkClient::new(&config);
+
+    let reached = poll_until(
+        &format!("Backup '{}'", backup_name),
+        timeout,
+        !output::is_table_format(cx.output.as_deref(), "table"),
+        move || async move {
+            match ddb.describe_backup().backup_arn(backup_arn).send().await {
+                Ok(res) => {
+                    let status = res
+                        .backup_description
+                        .and_then(|d| d.backup_details)
+                        .map(|d| d.backup_status);
+                    (
+                        status == Some(BackupStatus::Available),
+                        status.map_or(String::from("UNKNOWN"), |s| s.as_str().to_owned()),
+                    )
+                }
+                Err(e) => {
+                    debug!("DescribeBackup API call got an error -- {:#?}", e);
+                    app::bye_with_sdk_error(1, e);
+                }
+            }
+        },
+    )
+    .await;
+
+    if reached {
+        Ok(())
+    } else {
+        Err(WaitTimeoutError {
+            resource: format!("backup '{}'", backup_name),
+            desired_status: String::from("available"),
+            timeout,
+        })
+    }
+}
+
+/// Polls DescribeExport API until the export to S3 completes, printing status whenever it changes.
+/// Returns the description of the completed export. Exits if the export fails.
+pub async fn wait_for_export(
+    cx: &app::Context,
+    export_arn: &str,
+    timeout: time::Duration,
+    progress_to_stderr: bool,
+) -> Result<ExportDescription, WaitTimeoutError> {
+    let config = cx.effective_sdk_config().await;
+    let ddb = &DynamoDbSdkClient::new(&config);
+    let completed: &RefCell<Option<ExportDescription>> = &RefCell::new(None);
+
+    let reached = poll_until("Export", timeout, progress_to_stderr, move || async move {
+        match ddb.describe_export().export_arn(export_arn).send().await {
+            Ok(res) => {
+                let desc = res
+                    .export_description
+                    .expect("DescribeExport should return the export description");
+                let status = desc.export_status.clone();
+                if status == Some(ExportStatus::Failed) {
+                    error!(
+                        "The export has failed: {} {}",
+                        desc.failure_code.as_deref().unwrap_or_default(),
+                        desc.failure_message.as_deref().unwrap_or_default()
+                    );
+                    std::process::exit(1);
+                }
+                let reached = status == Some(ExportStatus::Completed);
+                if reached {
+                    completed.replace(Some(desc));
+                }
+                (
+                    reached,
+                    status.map_or(String::from("UNKNOWN"), |s| s.as_str().to_owned()),
+                )
+            }
+            Err(e) => {
+                debug!("DescribeExport API call got an error -- {:#?}", e);
+                app::bye_with_sdk_error(1, e);
+            }
+        }
+    })
+    .await;
+
+    match completed.take() {
+        Some(desc) if reached => Ok(desc),
+        _ => Err(WaitTimeoutError {
+            resource: format!("export '{}'", export_arn),
+            desired_status: String::from("completed"),
+            timeout,
+        }),
+    }
+}
+
+/// Executed when you call `$ dy admin tags list`. Shows tags of the target table sorted by key.
+pub async fn list_tags(cx: &app::Context) -> Result<(), IOError> {
+    let table_name = cx.effective_table_name();
+    let arn = table_arn(cx, &table_name).await;
+
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    let mut tags: Vec<Tag> = vec![];
+    let mut next_token: Option<String> = None;
+    loop {
+        match ddb
+            .list_tags_of_resource()
+            .resource_arn(&arn)
+            .set_next_token(next_token)
+            .send()
+            .await
+        {
+            Err(e) => {
+                debug!("ListTagsOfResource API call got an error -- {:#?}", e);
+                app::bye_with_sdk_error(1, e);
+            }
+            Ok(res) => {
+                debug!("ListTagsOfResource API call got a response -- {:#?}", res);
+                tags.extend(res.tags.unwrap_or_default());
+                next_token = res.next_token;
+            }
+        }
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    if tags.is_empty() {
+        println!("No tags found on the table '{}'.", table_name);
+        return Ok(());
+    }
+
+    tags.sort_by(|a, b| a.key.cmp(&b.key));
+    let mut tw = TabWriter::new(io::stdout());
+    tw.write_all(b"Key\tValue\n")?;
+    for tag in tags {
+        tw.write_all(format!("{}\t{}\n", tag.key, tag.value).as_bytes())?;
+    }
+    tw.flush()?;
+    Ok(())
+}
+
+/// Executed when you call `$ dy admin tags set`. Tags are given as "KEY=VALUE" and overwrite existing tags with the same key.
+pub async fn set_tags(cx: &app::Context, given_tags: Vec<String>) {
+    let tags: Vec<Tag> = match parse_tags(&given_tags) {
+        Ok(tags) => tags,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let table_name = cx.effective_table_name();
+    let arn = table_arn(cx, &table_name).await;
+
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    match ddb
+        .tag_resource()
+        .resource_arn(&arn)
+        .set_tags(Some(tags))
+        .send()
+        .await
+    {
+        Err(e) => {
+            debug!("TagResource API call got an error -- {:#?}", e);
+            app::bye_with_sdk_error(1, e);
+        }
+        Ok(_) => println!(
+            "Set {} tag(s) on the table '{}'.",
+            given_tags.len(),
+            table_name
+        ),
+    }
+}
+
+/// Executed when you call `$ dy admin tags remove`. Removing keys that don't exist is not an error.
+pub async fn remove_tags(cx: &app::Context, keys: Vec<String>) {
+    let table_name = cx.effective_table_name();
+    let arn = table_arn(cx, &table_name).await;
+
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    let count = keys.len();
+    match ddb
+        .untag_resource()
+        .resource_arn(&arn)
+        .set_tag_keys(Some(keys))
+        .send()
+        .await
+    {
+        Err(e) => {
+            debug!("UntagResource API call got an error -- {:#?}", e);
+            app::bye_with_sdk_error(1, e);
+        }
+        Ok(_) => println!("Removed {} tag(s) from the table '{}'.", count, table_name),
+    }
 }
 
 /// Takes on-demand Backup for the table. It takes --all-tables option but it doesn't take any effect.
 ///
 /// OnDemand backup is a type of backups that can be manually created. Another type is called PITR (Point-In-Time-Restore) but dynein doesn't support it for now.
 /// For more information about DynamoDB on-demand backup: https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/BackupRestore.html
-pub async fn backup(cx: &app::Context, all_tables: bool) {
+pub async fn backup(
+    cx: &app::Context,
+    all_tables: bool,
+    wait: bool,
+    name_template: Option<String>,
+    if_not_exists_today: bool,
+) {
     // this "backup" function is called only when --list is NOT given. So, --all-tables would be ignored.
     if all_tables {
-        println!("NOTE: --all-tables option is ignored without --list option. Just trying to create a backup for the target table...")
+        eprintln!("NOTE: --all-tables option is ignored without --list option. Just trying to create a backup for the target table...")
     };
 
     let table_name = cx.effective_table_name();
     debug!("Taking a backof of the table '{}'", table_name);
-    let epoch: u64 = time::SystemTime::now()
-        .duration_since(time::SystemTime::UNIX_EPOCH)
-        .expect("should be able to generate UNIX EPOCH")
-        .as_secs();
+    let now = Utc::now();
+    let backup_name = match render_backup_name(
+        name_template
+            .as_deref()
+            .unwrap_or(DEFAULT_BACKUP_NAME_TEMPLATE),
+        &table_name,
+        now,
+    ) {
+        Ok(name) => name,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if if_not_exists_today {
+        let midnight = now
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight should be a valid time")
+            .and_utc();
+        let existing = list_backups_since(cx, &table_name, midnight)
+            .await
+            .into_iter()
+            .find(|b| b.backup_status != Some(BackupStatus::Deleted));
+        if let Some(existing) = existing {
+            let existing = PrintBackup::from(existing);
+            let text = format!(
+                "Skipped creating a backup, as the table '{}' already has a backup created today (UTC): {} (status: {})\n  Backup ARN: {}\n",
+                table_name, existing.name, existing.status, existing.arn
+            );
+            output::print_document(
+                cx.output.as_deref(),
+                "table",
+                &output::Document::new(&existing).with_text(text),
+            );
+            return;
+        }
+    }
 
     let config = cx.effective_sdk_config().await;
     let ddb = DynamoDbSdkClient::new(&config);
@@ -452,7 +1254,7 @@ pub async fn backup(cx: &app::Context, all_tables: bool) {
     let req = ddb
         .create_backup()
         .table_name(&table_name)
-        .backup_name(format!("{}--dynein-{}", table_name, epoch));
+        .backup_name(backup_name);
     debug!("backup req: {:?}", req);
 
     match req.send().await {
@@ -463,59 +1265,102 @@ pub async fn backup(cx: &app::Context, all_tables: bool) {
         Ok(res) => {
             debug!("Returned result: {:#?}", res);
             let details = res.backup_details.expect("should have some details");
-            println!("Backup creation has been started:");
-            println!(
-                "  Backup Name: {} (status: {})",
-                details.backup_name, details.backup_status
-            );
-            println!("  Backup ARN: {}", details.backup_arn);
-            println!(
-                "  Backup Size: {} bytes",
-                details.backup_size_bytes.expect("should have table size")
-            );
+            // Progress is shown only with table format, and other formats print the backup after it's created.
+            let table_format = output::is_table_format(cx.output.as_deref(), "table");
+            if table_format {
+                println!("Backup creation has been started:");
+                println!(
+                    "  Backup Name: {} (status: {})",
+                    details.backup_name, details.backup_status
+                );
+                println!("  Backup ARN: {}", details.backup_arn);
+                println!(
+                    "  Backup Size: {} bytes",
+                    details.backup_size_bytes.expect("should have table size")
+                );
+            }
+
+            let mut status = details.backup_status.as_str().to_owned();
+            if wait {
+                exit_on_wait_timeout(
+                    wait_for_backup(
+                        cx,
+                        &details.backup_name,
+                        &details.backup_arn,
+                        OPERATION_WAIT_TIMEOUT,
+                    )
+                    .await,
+                );
+                status = BackupStatus::Available.as_str().to_owned();
+            }
+
+            if !table_format {
+                let backup = PrintBackup {
+                    table: table_name,
+                    name: details.backup_name,
+                    arn: details.backup_arn,
+                    status,
+                    size_bytes: details.backup_size_bytes,
+                    created_at: table::epoch_to_rfc3339(
+                        details.backup_creation_date_time.as_secs_f64(),
+                    ),
+                };
+                output::print_document(
+                    cx.output.as_deref(),
+                    "table",
+                    &output::Document::new(&backup),
+                );
+            }
         }
     }
 }
 
 /// List backups for a specified table. With --all-tables option all backups for all tables in the region are shown.
+/// With `--output` other than table, e.g. json, they are printed as an array of objects including ARNs.
 pub async fn list_backups(cx: &app::Context, all_tables: bool) -> Result<(), IOError> {
-    let backups = list_backups_api(cx, all_tables).await;
-    let mut tw = TabWriter::new(io::stdout());
+    let backups: Vec<PrintBackup> = list_backups_api(cx, all_tables)
+        .await
+        .into_iter()
+        .map(PrintBackup::from)
+        .collect();
+    let mut tw = TabWriter::new(vec![]);
     // First defining header
     tw.write_all(
         ((["Table", "Status", "CreatedAt", "BackupName (size)"].join("\t")) + "\n").as_bytes(),
     )?;
-    for backup in backups {
+    for backup in &backups {
         let line = [
-            backup.table_name.expect("table name should exist"),
-            backup
-                .backup_status
-                .expect("status should exist")
-                .as_str()
-                .to_string(),
-            table::epoch_to_rfc3339(
-                backup
-                    .backup_creation_date_time
-                    .expect("creation date should exist")
-                    .as_secs_f64(),
+            backup.table.to_owned(),
+            backup.status.to_owned(),
+            backup.created_at.to_owned(),
+            format!(
+                "{} ({} bytes)",
+                backup.name,
+                backup.size_bytes.expect("size should exist")
             ),
-            backup.backup_name.expect("backup name should exist")
-                + &format!(
-                    " ({} bytes)",
-                    backup.backup_size_bytes.expect("size should exist")
-                ),
             String::from("\n"),
         ];
         tw.write_all(line.join("\t").as_bytes())?;
     }
-    tw.flush()?;
+    let text = String::from_utf8(tw.into_inner().unwrap()).unwrap();
+    output::print_document(
+        cx.output.as_deref(),
+        "table",
+        &output::Document::new(&backups).with_text(text),
+    );
     Ok(())
 }
 
 /// This function restores DynamoDB table from specified backup data.
 /// If you don't specify backup data (name) explicitly, dynein will list backups and you can select out of them.
 /// Currently overwriting properties during rstore is not supported.
-pub async fn restore(cx: &app::Context, backup_name: Option<String>, restore_name: Option<String>) {
+/// With `wait`, it polls the restored table until the table and its GSIs become ACTIVE, and reports when the table gets ready.
+pub async fn restore(
+    cx: &app::Context,
+    backup_name: Option<String>,
+    restore_name: Option<String>,
+    wait: bool,
+) {
     // let backups = list_backups_api(&cx, false).await;
     let available_backups: Vec<BackupSummary> = list_backups_api(cx, false)
         .await
@@ -572,7 +1417,7 @@ pub async fn restore(cx: &app::Context, backup_name: Option<String>, restore_nam
     match ddb
         .restore_table_from_backup()
         .backup_arn(backup_arn.clone())
-        .target_table_name(target_table_name)
+        .target_table_name(&target_table_name)
         .send()
         .await
     {
@@ -587,15 +1432,158 @@ pub async fn restore(cx: &app::Context, backup_name: Option<String>, restore_nam
             table::print_table_description(cx.effective_region().await.as_ref(), &desc);
         }
     }
+
+    if wait {
+        let started_at = time::Instant::now();
+        exit_on_wait_timeout(
+            wait_for_table(
+                cx,
+                &target_table_name,
+                TableWaitTarget::Active,
+                OPERATION_WAIT_TIMEOUT,
+            )
+            .await,
+        );
+        println!(
+            "Table '{}' has been restored and is ready to use. It took {} seconds.",
+            target_table_name,
+            started_at.elapsed().as_secs()
+        );
+    } else {
+        println!(
+            "The restored table gets ready when it and its GSIs become ACTIVE. You can wait for it by `dy admin wait {}`.",
+            target_table_name
+        );
+    }
 }
 
 /* =================================================
 Private functions
 ================================================= */
 
+/// Calls `check` with exponential backoff until it returns true or `timeout` passes. Returns whether it succeeded.
+/// `check` returns a pair of "reached the desired status or not" and the current status, which is printed when it changes.
+/// The status goes to stderr with `progress_to_stderr`, so that it doesn't mix into machine-readable output.
+async fn poll_until<F, Fut>(
+    label: &str,
+    timeout: time::Duration,
+    progress_to_stderr: bool,
+    mut check: F,
+) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = (bool, String)>,
+{
+    let started_at = time::Instant::now();
+    let mut interval = WAIT_MIN_INTERVAL;
+    let mut last_status: Option<String> = None;
+    loop {
+        let (reached, status) = check().await;
+        if last_status.as_ref() != Some(&status) {
+            if progress_to_stderr {
+                eprintln!("{}: {}", label, status);
+            } else {
+                println!("{}: {}", label, status);
+            }
+            last_status = Some(status);
+        }
+        if reached {
+            return true;
+        }
+
+        let elapsed = started_at.elapsed();
+        if elapsed >= timeout {
+            return false;
+        }
+        tokio::time::sleep(interval.min(timeout - elapsed)).await;
+        interval = (interval * 2).min(WAIT_MAX_INTERVAL);
+    }
+}
+
+fn exit_on_wait_timeout(result: Result<(), WaitTimeoutError>) {
+    if let Err(e) = result {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Returns whether the table and all of its GSIs are ACTIVE, and a short description of their status.
+/// e.g. "ACTIVE", "UPDATING" or "ACTIVE (GSI myIndex: CREATING (backfilling))"
+fn table_status_summary(desc: &TableDescription) -> (bool, String) {
+    let table_status = desc
+        .table_status
+        .as_ref()
+        .map_or("UNKNOWN", |s| s.as_str())
+        .to_owned();
+    let pending_indexes: Vec<String> = desc
+        .global_secondary_indexes
+        .iter()
+        .flatten()
+        .filter(|idx| idx.index_status != Some(IndexStatus::Active))
+        .map(|idx| {
+            format!(
+                "GSI {}: {}{}",
+                idx.index_name.as_deref().unwrap_or_default(),
+                idx.index_status.as_ref().map_or("UNKNOWN", |s| s.as_str()),
+                if idx.backfilling == Some(true) {
+                    " (backfilling)"
+                } else {
+                    ""
+                }
+            )
+        })
+        .collect();
+
+    let all_active = desc.table_status == Some(TableStatus::Active) && pending_indexes.is_empty();
+    if pending_indexes.is_empty() {
+        (all_active, table_status)
+    } else {
+        (
+            all_active,
+            format!("{} ({})", table_status, pending_indexes.join(", ")),
+        )
+    }
+}
+
 /// Basically called by list_tables function, which is called from `$ dy list`.
 /// To make ListTables API result reusable, separated API logic into this standalone function.
+/// Tagging APIs identify a table by its ARN, so retrieve it via DescribeTable.
+async fn table_arn(cx: &app::Context, table_name: &str) -> String {
+    describe_table_api(cx, table_name.to_owned())
+        .await
+        .table_arn
+        .expect("table should have ARN")
+}
+
+/// Converts "KEY=VALUE" strings into tags. Only the first '=' separates a key and a value, so values may contain '='.
+fn parse_tags(given_tags: &[String]) -> Result<Vec<Tag>, String> {
+    given_tags
+        .iter()
+        .map(|t| match t.split_once('=') {
+            Some((k, v)) if !k.is_empty() => Ok(Tag::builder().key(k).value(v).build().unwrap()),
+            _ => Err(format!(
+                "Invalid tag '{}'. Tags should be given in the form of KEY=VALUE, e.g. env=prod",
+                t
+            )),
+        })
+        .collect()
+}
+
 async fn list_tables_api(cx: &app::Context, override_region: Option<&str>) -> Vec<String> {
+    match try_list_tables_api(cx, override_region).await {
+        Err(e) => {
+            debug!("ListTables API call got an error -- {:#?}", e);
+            app::bye_with_sdk_error(1, e);
+        }
+        Ok(table_names) => table_names,
+    }
+}
+
+/// Same as list_tables_api, but returns an error instead of exiting so that callers can continue with other regions.
+async fn try_list_tables_api(
+    cx: &app::Context,
+    override_region: Option<&str>,
+) -> Result<Vec<String>, SdkError<ListTablesError, HttpResponse>> {
     let config = if let Some(override_region) = override_region {
         cx.effective_sdk_config_with_region(override_region).await
     } else {
@@ -603,14 +1591,9 @@ async fn list_tables_api(cx: &app::Context, override_region: Option<&str>) -> Ve
     };
     let ddb = DynamoDbSdkClient::new(&config);
 
-    match ddb.list_tables().send().await {
-        Err(e) => {
-            debug!("ListTables API call got an error -- {:#?}", e);
-            app::bye_with_sdk_error(1, e);
-        }
-        // ListTables API returns blank array even if no table exists in a region.
-        Ok(res) => res.table_names.expect("This message should not be shown"),
-    }
+    let res = ddb.list_tables().send().await?;
+    // ListTables API returns blank array even if no table exists in a region.
+    Ok(res.table_names.expect("This message should not be shown"))
 }
 
 /// This function is a private function that simply calls ListBackups API and return results
@@ -634,6 +1617,78 @@ async fn list_backups_api(cx: &app::Context, all_tables: bool) -> Vec<BackupSumm
     }
 }
 
+/// Lists backups of the table created at or after `since`, e.g. to find one already taken today.
+async fn list_backups_since(
+    cx: &app::Context,
+    table_name: &str,
+    since: DateTime<Utc>,
+) -> Vec<BackupSummary> {
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    match ddb
+        .list_backups()
+        .table_name(table_name)
+        .time_range_lower_bound(SdkDateTime::from_secs(since.timestamp()))
+        .send()
+        .await
+    {
+        Err(e) => {
+            debug!("ListBackups API call got an error -- {:#?}", e);
+            app::bye_with_sdk_error(1, e);
+        }
+        Ok(res) => res.backup_summaries.unwrap_or_default(),
+    }
+}
+
+/// Expands placeholders in a template of backup names: {table}, {date} (YYYY-MM-DD), {time} (HHMMSS) and {epoch},
+/// where date and time are in UTC. Returns an error if the result is not a valid backup name.
+fn render_backup_name(
+    template: &str,
+    table_name: &str,
+    now: DateTime<Utc>,
+) -> Result<String, String> {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(len) => start + len,
+            None => {
+                return Err(format!(
+                    "Unclosed placeholder in the backup name template '{}'",
+                    template
+                ))
+            }
+        };
+        match &rest[start + 1..end] {
+            "table" => name.push_str(table_name),
+            "date" => name.push_str(&now.format("%Y-%m-%d").to_string()),
+            "time" => name.push_str(&now.format("%H%M%S").to_string()),
+            "epoch" => name.push_str(&now.timestamp().to_string()),
+            unknown => {
+                return Err(format!(
+                    "Unknown placeholder '{{{}}}' in the backup name template. Available placeholders are {{table}}, {{date}}, {{time}} and {{epoch}}",
+                    unknown
+                ))
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    name.push_str(rest);
+
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+    if !valid_chars || !(3..=255).contains(&name.len()) {
+        return Err(format!(
+            "Invalid backup name '{}'. A backup name must be 3 to 255 characters of a-z, A-Z, 0-9, '_', '-' and '.'",
+            name
+        ));
+    }
+    Ok(name)
+}
+
 fn fetch_arn_from_backup_name(
     backup_name: String,
     available_backups: Vec<BackupSummary>,
@@ -645,3 +1700,38 @@ fn fetch_arn_from_backup_name(
         .backup_arn /* Option<String> */
         .unwrap()
 }
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_backup_name() {
+        let now = DateTime::from_timestamp(1_600_000_000, 0).unwrap();
+        assert_eq!(
+            render_backup_name(DEFAULT_BACKUP_NAME_TEMPLATE, "Movie", now),
+            Ok(String::from("Movie--dynein-1600000000"))
+        );
+        assert_eq!(
+            render_backup_name("{table}-{date}", "Movie", now),
+            Ok(String::from("Movie-2020-09-13"))
+        );
+        assert_eq!(
+            render_backup_name("daily.{table}_{date}T{time}", "Movie", now),
+            Ok(String::from("daily.Movie_2020-09-13T122640"))
+        );
+        assert!(render_backup_name("{table}-{month}", "Movie", now)
+            .unwrap_err()
+            .contains("Unknown placeholder '{month}'"));
+        assert!(render_backup_name("{table", "Movie", now)
+            .unwrap_err()
+            .contains("Unclosed placeholder"));
+        assert!(render_backup_name("{table} {date}", "Movie", now)
+            .unwrap_err()
+            .contains("Invalid backup name 'Movie 2020-09-13'"));
+    }
+}
@@ -0,0 +1,355 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements `dy cost estimate`, which estimates the monthly cost of a table
+// from its size and capacity settings (DescribeTable) and its consumed capacity recorded in CloudWatch.
+use std::io::{self, Write};
+
+use aws_sdk_cloudwatch::{
+    primitives::DateTime,
+    types::{Dimension, Statistic},
+    Client as CloudWatchSdkClient,
+};
+use aws_sdk_dynamodb::types::{TableClass, TableDescription};
+use log::{debug, error};
+use serde::Serialize;
+use tabwriter::TabWriter;
+
+use super::app;
+use super::control;
+use super::ddb::table;
+use super::output;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// List prices in US East (N. Virginia) for the Standard table class, in USD.
+/// https://aws.amazon.com/dynamodb/pricing/
+const ON_DEMAND_PRICE_PER_WRU: f64 = 0.625 / 1_000_000.0;
+const ON_DEMAND_PRICE_PER_RRU: f64 = 0.125 / 1_000_000.0;
+const PROVISIONED_PRICE_PER_WCU_HOUR: f64 = 0.00065;
+const PROVISIONED_PRICE_PER_RCU_HOUR: f64 = 0.00013;
+const STORAGE_PRICE_PER_GB_MONTH: f64 = 0.25;
+
+const HOURS_PER_MONTH: f64 = 730.0;
+const SECONDS_PER_HOUR: i64 = 3600;
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Default target utilization of DynamoDB auto scaling, used to estimate capacity that provisioned mode would need.
+const TARGET_UTILIZATION: f64 = 0.7;
+
+const CLOUDWATCH_NAMESPACE: &str = "AWS/DynamoDB";
+
+pub struct EstimateParams {
+    pub days: u16,
+}
+
+#[derive(Serialize, Debug)]
+struct CostEstimate {
+    table: String,
+    region: String,
+    mode: table::Mode,
+    class: String,
+    count: i64,
+    size_bytes: i64,
+    /// Number of days of consumed capacity which the estimate is based on.
+    days: u16,
+    consumed_rcu: f64,
+    consumed_wcu: f64,
+    /// Estimated monthly costs in USD.
+    monthly_usd: MonthlyCost,
+}
+
+#[derive(Serialize, Debug)]
+struct MonthlyCost {
+    storage: f64,
+    on_demand: f64,
+    provisioned_auto_scaling: f64,
+    /// Only for provisioned tables, i.e. the cost of the capacity currently provisioned.
+    provisioned_current: Option<f64>,
+}
+
+/// Consumed capacity of the table or a GSI, which is provisioned separately in provisioned mode.
+struct Consumption {
+    read: Vec<f64>,
+    write: Vec<f64>,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Executed when you call `$ dy cost estimate`. Compares the monthly cost of the table in on-demand and provisioned modes,
+/// based on consumed capacity of the table and its GSIs over the past days.
+/// Note that the estimate uses list prices of a single region, and doesn't take free tier or reserved capacity into account.
+pub async fn estimate(cx: &app::Context, params: EstimateParams) {
+    if cx.is_local().await {
+        error!("DynamoDB Local doesn't publish metrics to CloudWatch, so `dy cost estimate` is not available.");
+        std::process::exit(1);
+    }
+    let region = cx.effective_region().await;
+    let desc: TableDescription = control::describe_table_api(cx, cx.effective_table_name()).await;
+    let table_name = desc.table_name.clone().unwrap_or_default();
+    let mode = table::extract_mode(&desc.billing_mode_summary);
+    let class = table::extract_table_class(&desc.table_class_summary);
+    if class != TableClass::Standard {
+        eprintln!(
+            "WARN: The table class is {}, but prices of the STANDARD class are used.",
+            class.as_str()
+        );
+    }
+
+    let config = cx.effective_sdk_config().await;
+    let cw = CloudWatchSdkClient::new(&config);
+    // Align the period to hours so that each datapoint covers a whole hour.
+    let end = chrono::Utc::now().timestamp() / SECONDS_PER_HOUR * SECONDS_PER_HOUR;
+    let start = end - i64::from(params.days) * 24 * SECONDS_PER_HOUR;
+    let hours = f64::from(params.days) * 24.0;
+
+    let mut consumptions = vec![consumption(&cw, &table_name, None, start, end).await];
+    for gsi in desc.global_secondary_indexes.iter().flatten() {
+        if let Some(index_name) = &gsi.index_name {
+            consumptions.push(consumption(&cw, &table_name, Some(index_name), start, end).await);
+        }
+    }
+
+    let consumed_rcu: f64 = consumptions.iter().flat_map(|c| &c.read).sum();
+    let consumed_wcu: f64 = consumptions.iter().flat_map(|c| &c.write).sum();
+    let scale = HOURS_PER_MONTH / hours;
+    let size_bytes = desc.table_size_bytes.unwrap_or(0);
+
+    let provisioned_auto_scaling: f64 = consumptions
+        .iter()
+        .map(|c| {
+            capacity_hours(&c.read, hours) * PROVISIONED_PRICE_PER_RCU_HOUR
+                + capacity_hours(&c.write, hours) * PROVISIONED_PRICE_PER_WCU_HOUR
+        })
+        .sum::<f64>()
+        * scale;
+    let provisioned_current = match mode {
+        table::Mode::OnDemand => None,
+        table::Mode::Provisioned => {
+            let throughputs = std::iter::once(desc.provisioned_throughput.as_ref()).chain(
+                desc.global_secondary_indexes
+                    .iter()
+                    .flatten()
+                    .map(|gsi| gsi.provisioned_throughput.as_ref()),
+            );
+            Some(
+                throughputs
+                    .flatten()
+                    .map(|pt| {
+                        pt.read_capacity_units.unwrap_or(0) as f64 * PROVISIONED_PRICE_PER_RCU_HOUR
+                            + pt.write_capacity_units.unwrap_or(0) as f64
+                                * PROVISIONED_PRICE_PER_WCU_HOUR
+                    })
+                    .sum::<f64>()
+                    * HOURS_PER_MONTH,
+            )
+        }
+    };
+
+    let estimate = CostEstimate {
+        table: table_name,
+        region: String::from(region.as_ref()),
+        mode,
+        class: String::from(class.as_str()),
+        count: desc.item_count.unwrap_or(0),
+        size_bytes,
+        days: params.days,
+        consumed_rcu,
+        consumed_wcu,
+        monthly_usd: MonthlyCost {
+            storage: size_bytes as f64 / BYTES_PER_GB * STORAGE_PRICE_PER_GB_MONTH,
+            on_demand: (consumed_rcu * ON_DEMAND_PRICE_PER_RRU
+                + consumed_wcu * ON_DEMAND_PRICE_PER_WRU)
+                * scale,
+            provisioned_auto_scaling,
+            provisioned_current,
+        },
+    };
+    debug!("estimated cost: {:#?}", estimate);
+
+    let text = format_estimate(&estimate).unwrap_or_else(|e| {
+        error!("Failed to format the estimate: {}", e);
+        std::process::exit(1);
+    });
+    output::print_document(
+        cx.output.as_deref(),
+        "table",
+        &output::Document::new(&estimate).with_text(text),
+    );
+    eprintln!("Estimated with list prices of us-east-1, excluding free tier, reserved capacity, backups and streams. Actual prices vary by region.");
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Retrieves hourly consumed capacity of the table, or of the GSI if `index_name` is given.
+async fn consumption(
+    cw: &CloudWatchSdkClient,
+    table_name: &str,
+    index_name: Option<&str>,
+    start: i64,
+    end: i64,
+) -> Consumption {
+    Consumption {
+        read: hourly_sums(
+            cw,
+            "ConsumedReadCapacityUnits",
+            table_name,
+            index_name,
+            start,
+            end,
+        )
+        .await,
+        write: hourly_sums(
+            cw,
+            "ConsumedWriteCapacityUnits",
+            table_name,
+            index_name,
+            start,
+            end,
+        )
+        .await,
+    }
+}
+
+/// Calls GetMetricStatistics API and returns the sum of the metric in each hour. Hours without any datapoint are omitted.
+async fn hourly_sums(
+    cw: &CloudWatchSdkClient,
+    metric_name: &str,
+    table_name: &str,
+    index_name: Option<&str>,
+    start: i64,
+    end: i64,
+) -> Vec<f64> {
+    let mut dimensions = vec![Dimension::builder()
+        .name("TableName")
+        .value(table_name)
+        .build()];
+    if let Some(index_name) = index_name {
+        dimensions.push(
+            Dimension::builder()
+                .name("GlobalSecondaryIndexName")
+                .value(index_name)
+                .build(),
+        );
+    }
+
+    let res = cw
+        .get_metric_statistics()
+        .namespace(CLOUDWATCH_NAMESPACE)
+        .metric_name(metric_name)
+        .set_dimensions(Some(dimensions))
+        .start_time(DateTime::from_secs(start))
+        .end_time(DateTime::from_secs(end))
+        .period(SECONDS_PER_HOUR as i32)
+        .statistics(Statistic::Sum)
+        .send()
+        .await
+        .unwrap_or_else(|e| {
+            debug!("GetMetricStatistics API call got an error -- {:?}", e);
+            app::bye_with_sdk_error(1, e);
+        });
+    res.datapoints
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|dp| dp.sum)
+        .collect()
+}
+
+/// Returns capacity unit hours which auto scaling would provision to serve the hourly consumption at the target utilization.
+/// At least one capacity unit is provisioned even in hours without any request.
+fn capacity_hours(hourly_sums: &[f64], hours: f64) -> f64 {
+    let busy: f64 = hourly_sums
+        .iter()
+        .map(|sum| {
+            (sum / SECONDS_PER_HOUR as f64 / TARGET_UTILIZATION)
+                .ceil()
+                .max(1.0)
+        })
+        .sum();
+    let idle = (hours - hourly_sums.len() as f64).max(0.0);
+    busy + idle
+}
+
+/// Formats the estimate for `--output table`, the default of `dy cost estimate`.
+fn format_estimate(estimate: &CostEstimate) -> io::Result<String> {
+    let mut tw = TabWriter::new(vec![]);
+    writeln!(
+        tw,
+        "table: {}\nregion: {}\nmode: {:?}\nclass: {}\ncount: {}\nsize_bytes: {}",
+        estimate.table,
+        estimate.region,
+        estimate.mode,
+        estimate.class,
+        estimate.count,
+        estimate.size_bytes
+    )?;
+    writeln!(
+        tw,
+        "consumed in {} days: {:.1} RCU, {:.1} WCU\n",
+        estimate.days, estimate.consumed_rcu, estimate.consumed_wcu
+    )?;
+
+    let cost = &estimate.monthly_usd;
+    writeln!(tw, "monthly cost (USD)\tcapacity\tstorage\ttotal")?;
+    let mut rows = vec![
+        ("on-demand", Some(cost.on_demand)),
+        (
+            "provisioned (auto scaling)",
+            Some(cost.provisioned_auto_scaling),
+        ),
+    ];
+    if estimate.mode == table::Mode::Provisioned {
+        rows.push(("provisioned (current capacity)", cost.provisioned_current));
+    }
+    for (name, capacity) in rows {
+        if let Some(capacity) = capacity {
+            writeln!(
+                tw,
+                "{}\t{:.2}\t{:.2}\t{:.2}",
+                name,
+                capacity,
+                cost.storage,
+                capacity + cost.storage
+            )?;
+        }
+    }
+    Ok(String::from_utf8(tw.into_inner().unwrap()).unwrap())
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity_hours() {
+        // 3 hours without datapoints are provisioned with the minimum capacity.
+        assert_eq!(capacity_hours(&[], 3.0), 3.0);
+        // 1800 units in an hour are 0.5 units per second, which 1 unit serves at 70% utilization.
+        assert_eq!(capacity_hours(&[1800.0, 0.0], 3.0), 3.0);
+        // 36000 units in an hour are 10 units per second, which needs 15 units at 70% utilization.
+        assert_eq!(capacity_hours(&[36000.0], 2.0), 16.0);
+    }
+}
@@ -16,32 +16,79 @@
 
 // This module interact with DynamoDB Data Plane APIs
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     error::Error,
     fmt,
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
     vec::Vec,
 };
 
-use crate::parser::{AttributeDefinition, AttributeType, DyneinParser, ParseError};
+use crate::parser::{
+    AttributeDefinition, AttributeType, DocumentPath, DyneinParser, ParseError, SortKeyOperator,
+};
 use aws_sdk_dynamodb::{
     operation::scan::ScanOutput,
-    types::{AttributeValue, ReturnValue},
+    types::{AttributeValue, ConsumedCapacity, ReturnConsumedCapacity, ReturnValue},
     Client as DynamoDbSdkClient,
 };
+use aws_smithy_types::error::display::DisplayErrorContext;
+use dialoguer::Confirm;
+use futures::{
+    future::join_all,
+    stream::{self, StreamExt},
+};
 use log::{debug, error};
-use serde::{ser::SerializeStruct, Serialize, Serializer};
+use serde::{
+    ser::{SerializeMap, SerializeStruct},
+    Serialize, Serializer,
+};
 use serde_json::Value as JsonValue;
 use tabwriter::TabWriter;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 // use bytes::Bytes;
 
+use super::analyze;
 use super::app;
-use super::ddb::key;
+use super::batch;
+use super::control;
+use super::ddb::{key, size};
+use super::interrupt;
+use super::metrics;
+use super::output;
+use super::postprocess::{Distinct, PostProcess};
 
 /* =================================================
 struct / enum / const
 ================================================= */
 
+/// Whether numbers which can't be represented exactly as JSON numbers are converted to JSON strings.
+/// It's enabled by --precise-numbers option for the whole process.
+static PRECISE_NUMBERS: AtomicBool = AtomicBool::new(false);
+
+/// Whether an attribute value of a type unknown to this version of dynein has been rendered.
+/// The SDK returns such values as `AttributeValue::Unknown` when DynamoDB introduces a new data type.
+static UNKNOWN_ATTRVAL_FOUND: AtomicBool = AtomicBool::new(false);
+
+/// Appended to values truncated in table output. It's set from `display.ellipsis` config for the whole process.
+static ELLIPSIS: OnceLock<String> = OnceLock::new();
+
+/// Display width which the aggregated "attributes" column of table output is truncated to.
+const ATTRIBUTES_COLUMN_WIDTH: usize = 50;
+
+/// Display width which maps and lists in other columns of table output are truncated to.
+const NESTED_CELL_WIDTH: usize = 30;
+
+/// Rendered in place of attribute values of unknown types.
+const UNKNOWN_ATTRVAL_PLACEHOLDER: &str = "<unknown type>";
+
+/// Default number of UpdateItem requests in flight on `dy upd --where`.
+const BULK_UPDATE_CONCURRENCY: usize = 8;
+
 #[derive(Debug)]
 struct GeneratedQueryParams {
     exp: Option<String>,
@@ -67,6 +114,14 @@ enum UpdateActionType {
     Remove,
 }
 
+/// Kind of expression to be previewed by `dy expr`.
+pub enum ExpressionPreviewType {
+    Set,
+    Remove,
+    SortKey(Option<String> /* "<sort key name>,<S/N/B>" */),
+    Condition,
+}
+
 #[derive(Debug)]
 pub enum DyneinQueryParamsError {
     NoSuchIndex(String /* index name */, String /* table name */),
@@ -99,6 +154,10 @@ impl fmt::Display for DyneinQueryParamsError {
                     Please execute 'dy desc' command to see key schema."
                 )
             }
+            // The error already contains actionable guidance.
+            DyneinQueryParamsError::InvalidSortKeyOption(
+                err @ ParseError::UnsupportedSortKeyCondition(_),
+            ) => write!(f, "{}", err),
             DyneinQueryParamsError::InvalidSortKeyOption(err) => {
                 write!(
                     f,
@@ -116,49 +175,305 @@ impl fmt::Display for DyneinQueryParamsError {
 }
 impl Error for DyneinQueryParamsError {}
 
+/// Statistics of a Scan or Query operation, printed by `--summary` option.
+/// It goes to stderr so that stdout can be piped to other commands as usual.
+struct ReadSummary {
+    items: i32,
+    scanned_count: i32,
+    pages: usize,
+    consumed_capacity: Option<f64>,
+    elapsed: Duration,
+}
+
+impl ReadSummary {
+    fn new(
+        items: i32,
+        scanned_count: i32,
+        consumed_capacity: Option<&ConsumedCapacity>,
+        started_at: Instant,
+    ) -> Self {
+        Self {
+            items,
+            scanned_count,
+            pages: 1,
+            consumed_capacity: consumed_capacity.and_then(|c| c.capacity_units),
+            elapsed: started_at.elapsed(),
+        }
+    }
+
+    /// Adds statistics of another page, for operations which read all pages.
+    fn add_page(
+        &mut self,
+        items: i32,
+        scanned_count: i32,
+        consumed_capacity: Option<&ConsumedCapacity>,
+    ) {
+        self.items += items;
+        self.scanned_count += scanned_count;
+        self.pages += 1;
+        self.consumed_capacity = self
+            .consumed_capacity
+            .zip(consumed_capacity.and_then(|c| c.capacity_units))
+            .map(|(total, units)| total + units);
+    }
+}
+
+impl fmt::Display for ReadSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let capacity = match self.consumed_capacity {
+            Some(c) => format!("{} RCU", c),
+            None => String::from("unknown RCU"),
+        };
+        write!(
+            f,
+            "Summary: {} item(s) returned, {} scanned, {} page(s) fetched, {} consumed, {:.3}s elapsed",
+            self.items,
+            self.scanned_count,
+            self.pages,
+            capacity,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
 /* =================================================
 Public functions
 ================================================= */
 
+/// Converts numbers to JSON strings instead of lossy JSON numbers when they have more significant digits than f64 can hold.
+pub fn enable_precise_numbers() {
+    PRECISE_NUMBERS.store(true, Ordering::Relaxed);
+}
+
+/// Replaces the ellipsis appended to values truncated in table output. Only the first call takes effect.
+pub fn set_ellipsis(ellipsis: String) {
+    let _ = ELLIPSIS.set(ellipsis);
+}
+
+/// Returns true if any attribute value of an unknown type has been replaced with a placeholder in output so far.
+pub fn unknown_attrval_found() -> bool {
+    UNKNOWN_ATTRVAL_FOUND.load(Ordering::Relaxed)
+}
+
+/// Returns the placeholder for an attribute value of an unknown type, warning about it only the first time
+/// so that a long-running export doesn't flood stderr.
+pub fn unknown_attrval_placeholder(attrval: &AttributeValue) -> &'static str {
+    debug!("Unknown DynamoDB AttributeValue: {:?}", attrval);
+    if !UNKNOWN_ATTRVAL_FOUND.swap(true, Ordering::Relaxed) {
+        eprintln!(
+            "WARN: Found an attribute of a data type which this version of dynein doesn't support. It's shown as \"{}\". Consider upgrading dynein.",
+            UNKNOWN_ATTRVAL_PLACEHOLDER
+        );
+    }
+    UNKNOWN_ATTRVAL_PLACEHOLDER
+}
+
+pub struct ScanParams {
+    pub index: Option<String>,
+    /// Whether --consistent-read is given. `table_defaults` in config.yml is applied by `scan`, as it's ignored on GSIs.
+    pub consistent_read: bool,
+    pub attributes: Option<String>,
+    pub keys_only: bool,
+    pub limit: i32,
+    pub summary: bool,
+    pub yes: bool,
+    pub sort_by: Option<String>,
+    pub group_by: Option<String>,
+    pub distinct: Option<String>,
+    pub expand: Vec<String>,
+}
+
 /// This function calls Scan API and return mutiple items. By default it uses 'table' output format.
 /// Scan API retrieves all items in a given table, something like `SELECT * FROM mytable` in SQL world.
-pub async fn scan(
-    cx: &app::Context,
-    index: Option<String>,
-    consistent_read: bool,
-    attributes: &Option<String>,
-    keys_only: bool,
-    limit: i32,
-) {
+pub async fn scan(cx: &app::Context, mut params: ScanParams) {
     let ts: app::TableSchema = app::table_schema(cx).await;
+    params.consistent_read =
+        consistent_read_for(cx, &ts, params.index.as_deref(), params.consistent_read);
+    let post_process = PostProcess::new_or_exit(
+        params.sort_by.as_deref(),
+        params.group_by.as_deref(),
+        cx.output.as_deref(),
+    );
+    let distinct = params
+        .distinct
+        .as_deref()
+        .map(|attr| Distinct::new_or_exit(attr, cx.output.as_deref()));
+    let expand = output::expanded_columns_or_exit(&params.expand, cx.output.as_deref());
+    if distinct.is_some() {
+        // --distinct reads all items, which the confirmation below should estimate.
+        params.limit = i32::MAX;
+    }
 
-    let items = scan_api(
+    if !params.yes && !cx.is_local().await {
+        confirm_large_scan(cx, &ts, &params).await;
+    }
+    if let Some(distinct) = distinct {
+        return scan_distinct(cx, &ts, &params, distinct).await;
+    }
+
+    let started_at = Instant::now();
+    let res = scan_api(
         cx,
-        index,
-        consistent_read,
-        attributes,
-        keys_only,
-        Some(limit),
+        params.index,
+        params.consistent_read,
+        &params.attributes,
+        params.keys_only,
+        Some(params.limit),
         None,
     )
-    .await
-    .items
-    .expect("items should be 'Some' even if there's no item in the table.");
-    match cx.output.as_deref() {
-        None | Some("table") => display_items_table(items, &ts, attributes, keys_only),
-        Some("json") => println!(
-            "{}",
-            serde_json::to_string_pretty(&convert_to_json_vec(&items)).unwrap()
-        ),
-        Some("raw") => println!(
+    .await;
+    let items = res
+        .items
+        .as_deref()
+        .expect("items should be 'Some' even if there's no item in the table.");
+    let view = output::ItemsView {
+        ts: &ts,
+        attributes: &params.attributes,
+        keys_only: params.keys_only,
+        expand: &expand,
+    };
+    post_process.print_items(cx.output.as_deref(), items, &view);
+
+    if params.summary {
+        eprintln!(
             "{}",
-            serde_json::to_string_pretty(&strip_items(&items)).unwrap()
-        ),
-        Some(o) => {
-            println!("ERROR: unsupported output type '{}'.", o);
-            std::process::exit(1);
+            ReadSummary::new(
+                res.count,
+                res.scanned_count,
+                res.consumed_capacity.as_ref(),
+                started_at
+            )
+        );
+    }
+}
+
+/// Scans all items page by page for `dy scan --distinct`, reading only primary key(s) and the attribute.
+/// Only counts of values are kept in memory, so that tables larger than memory can be scanned.
+async fn scan_distinct(
+    cx: &app::Context,
+    ts: &app::TableSchema,
+    params: &ScanParams,
+    mut distinct: Distinct,
+) {
+    let scan_params = generate_scan_expressions(ts, &Some(distinct.attribute().to_owned()), false);
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    let started_at = Instant::now();
+    let mut summary = ReadSummary {
+        items: 0,
+        scanned_count: 0,
+        pages: 0,
+        consumed_capacity: Some(0.0),
+        elapsed: Duration::ZERO,
+    };
+    let watch = interrupt::watch();
+    let mut esk = None;
+    loop {
+        let req = ddb
+            .scan()
+            .table_name(&ts.name)
+            .set_index_name(params.index.clone())
+            .set_projection_expression(scan_params.exp.clone())
+            .set_expression_attribute_names(scan_params.names.clone())
+            .consistent_read(params.consistent_read)
+            .set_exclusive_start_key(esk)
+            .return_consumed_capacity(ReturnConsumedCapacity::Total)
+            .send();
+        let res = match watch.page(req).await {
+            None => break,
+            Some(res) => res.unwrap_or_else(|e| {
+                debug!("Scan API call got an error -- {:?}", e);
+                app::bye_with_sdk_error(1, e);
+            }),
+        };
+        distinct.add_items(res.items.as_deref().unwrap_or_default());
+        summary.add_page(res.count, res.scanned_count, res.consumed_capacity.as_ref());
+        esk = res.last_evaluated_key;
+        if esk.is_none() || watch.interrupted() {
+            break;
         }
     }
+    summary.elapsed = started_at.elapsed();
+
+    distinct.print(cx.output.as_deref());
+    if params.summary {
+        eprintln!("{}", summary);
+    }
+    if watch.interrupted() {
+        eprintln!(
+            "Interrupted after scanning {} page(s). The counts above are partial.",
+            summary.pages
+        );
+        std::process::exit(interrupt::EXIT_INTERRUPTED);
+    }
+}
+
+/// Warns before scanning a table (or an index) which has more items than the configured threshold, with estimated RCUs.
+/// Item count and size come from DescribeTable, which DynamoDB updates approximately every six hours.
+/// In non-interactive mode, it exits unless --yes is given so that scripts don't run expensive scans by accident.
+async fn confirm_large_scan(cx: &app::Context, ts: &app::TableSchema, params: &ScanParams) {
+    let desc = control::describe_table_api(cx, ts.name.to_owned()).await;
+    let (item_count, size_bytes) = match &params.index {
+        None => (desc.item_count, desc.table_size_bytes),
+        Some(idx) => desc
+            .global_secondary_indexes
+            .iter()
+            .flatten()
+            .find(|i| i.index_name.as_deref() == Some(idx))
+            .map(|i| (i.item_count, i.index_size_bytes))
+            .or_else(|| {
+                desc.local_secondary_indexes
+                    .iter()
+                    .flatten()
+                    .find(|i| i.index_name.as_deref() == Some(idx))
+                    .map(|i| (i.item_count, i.index_size_bytes))
+            })
+            .unwrap_or((desc.item_count, desc.table_size_bytes)),
+    };
+    let item_count = item_count.unwrap_or(0);
+    // Bounded scans, e.g. with the default --limit, are cheap however large the table is.
+    let items_to_read = item_count.min(i64::from(params.limit));
+    if items_to_read <= 0 || items_to_read as u64 <= cx.scan_warning_threshold() {
+        return;
+    }
+
+    let size_bytes = size_bytes.unwrap_or(0);
+    eprintln!(
+        "WARN: '{}' has approximately {} items ({} bytes). This scan reads up to {} items and is estimated to consume {} RCU, while a full scan would consume {} RCU.",
+        params.index.as_deref().unwrap_or(&ts.name),
+        item_count,
+        size_bytes,
+        items_to_read,
+        estimate_read_capacity(
+            size_bytes.checked_div(item_count).unwrap_or(0) * items_to_read,
+            params.consistent_read
+        ),
+        estimate_read_capacity(size_bytes, params.consistent_read),
+    );
+
+    if !io::stdin().is_terminal() {
+        error!("Scanning a large table requires --yes option in non-interactive mode.");
+        std::process::exit(1);
+    }
+    if !Confirm::new()
+        .with_prompt("Proceed anyway?")
+        .interact()
+        .unwrap()
+    {
+        app::bye(0, "Operation has been cancelled.");
+    }
+}
+
+/// Estimates read capacity units to read the given size of data. Scan rounds up the total size to the next 4 KB,
+/// and an eventually consistent read consumes half of a strongly consistent read.
+fn estimate_read_capacity(size_bytes: i64, consistent_read: bool) -> f64 {
+    let units = (size_bytes as f64 / 4096.0).ceil();
+    if consistent_read {
+        units
+    } else {
+        units / 2.0
+    }
 }
 
 pub async fn scan_api(
@@ -178,7 +493,8 @@ pub async fn scan_api(
     let config = cx.effective_sdk_config().await;
     let ddb = DynamoDbSdkClient::new(&config);
 
-    ddb.scan()
+    let call = ddb
+        .scan()
         .table_name(ts.name)
         .set_index_name(index)
         .set_limit(limit)
@@ -186,23 +502,78 @@ pub async fn scan_api(
         .set_expression_attribute_names(scan_params.names)
         .consistent_read(consistent_read)
         .set_exclusive_start_key(esk)
-        .send()
-        .await
-        .unwrap_or_else(|e| {
-            debug!("Scan API call got an error -- {:?}", e);
-            app::bye_with_sdk_error(1, e);
-        })
+        .return_consumed_capacity(ReturnConsumedCapacity::Total)
+        .send();
+    metrics::timed("Scan", call).await.unwrap_or_else(|e| {
+        debug!("Scan API call got an error -- {:?}", e);
+        app::bye_with_sdk_error(1, e);
+    })
 }
 
+/// Sort key condition of a query, given by either `--sort-key` or dedicated options such as `--begins`.
+#[derive(Debug, Clone)]
+pub enum SortKeyInput {
+    Expression(String),
+    Values(SortKeyOperator, Vec<String>),
+}
+
+impl SortKeyInput {
+    /// Build from query options. clap guarantees that at most one of them is given.
+    pub fn from_options(
+        expression: Option<String>,
+        begins: Option<String>,
+        between: Option<Vec<String>>,
+        gt: Option<String>,
+        ge: Option<String>,
+        lt: Option<String>,
+        le: Option<String>,
+    ) -> Option<SortKeyInput> {
+        if let Some(expression) = expression {
+            return Some(SortKeyInput::Expression(expression));
+        }
+        let (operator, values) = if let Some(prefix) = begins {
+            (SortKeyOperator::BeginsWith, vec![prefix])
+        } else if let Some(range) = between {
+            (SortKeyOperator::Between, range)
+        } else if let Some(v) = gt {
+            (SortKeyOperator::Gt, vec![v])
+        } else if let Some(v) = ge {
+            (SortKeyOperator::Ge, vec![v])
+        } else if let Some(v) = lt {
+            (SortKeyOperator::Lt, vec![v])
+        } else if let Some(v) = le {
+            (SortKeyOperator::Le, vec![v])
+        } else {
+            return None;
+        };
+        Some(SortKeyInput::Values(operator, values))
+    }
+}
+
+#[derive(Clone)]
 pub struct QueryParams {
     pub pval: String,
-    pub sort_key_expression: Option<String>,
+    pub sort_key: Option<SortKeyInput>,
+    pub filter: Option<String>,
     pub index: Option<String>,
     pub limit: Option<i32>,
+    /// Whether --consistent-read is given. `table_defaults` in config.yml is applied by `query`, as it's ignored on GSIs.
     pub consistent_read: bool,
     pub descending: bool,
     pub attributes: Option<String>,
     pub keys_only: bool,
+    pub summary: bool,
+    pub replica_regions: Vec<String>,
+    pub sort_by: Option<String>,
+    pub group_by: Option<String>,
+    pub expand: Vec<String>,
+}
+
+/// Result of the same read in a region, compared by `--replica-region` option.
+struct ReplicaReadResult {
+    region: String,
+    elapsed: Duration,
+    items: Result<Vec<output::Item>, String>,
 }
 
 /// This function calls Query API and return mutiple items. By default it uses 'table' output format.
@@ -210,17 +581,27 @@ pub struct QueryParams {
 /// References:
 /// - https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Query.html#Query.KeyConditionExpressions
 /// - https://aws.amazon.com/blogs/database/using-sort-keys-to-organize-data-in-amazon-dynamodb/
-pub async fn query(cx: &app::Context, params: QueryParams) {
+pub async fn query(cx: &app::Context, mut params: QueryParams) {
     debug!("context: {:#?}", &cx);
     let ts: app::TableSchema = app::table_schema(cx).await;
+    params.consistent_read =
+        consistent_read_for(cx, &ts, params.index.as_deref(), params.consistent_read);
+    // Kept to retry the query in case the cached key schema of the table is stale.
+    let retry_params = params.clone();
+    let post_process = PostProcess::new_or_exit(
+        params.sort_by.as_deref(),
+        params.group_by.as_deref(),
+        cx.output.as_deref(),
+    );
+    let expand = output::expanded_columns_or_exit(&params.expand, cx.output.as_deref());
 
-    debug!("For table '{}' (index '{:?}'), generating KeyConditionExpression using sort_key_expression: '{:?}'", &ts.name, &params.index, &params.sort_key_expression);
-    let query_params: GeneratedQueryParams = match generate_query_expressions(
+    debug!("For table '{}' (index '{:?}'), generating KeyConditionExpression using sort key condition: '{:?}'", &ts.name, &params.index, &params.sort_key);
+    let mut query_params: GeneratedQueryParams = match generate_query_expressions(
         &ts,
         &params.pval,
-        &params.sort_key_expression,
+        &params.sort_key,
         &params.index,
-        cx.should_strict_for_query(),
+        cx.should_strict(),
     ) {
         Ok(qp) => qp,
         Err(e) => {
@@ -233,6 +614,49 @@ pub async fn query(cx: &app::Context, params: QueryParams) {
         &ts.name, &query_params
     );
 
+    // FilterExpression shares ExpressionAttributeNames and ExpressionAttributeValues with KeyConditionExpression.
+    let filter_expression = params.filter.as_ref().map(|filter| {
+        let mut parser = DyneinParser::with_placeholders(
+            query_params.names.take().unwrap_or_default(),
+            query_params.vals.take().unwrap_or_default(),
+        );
+        match parser.parse_condition(filter) {
+            Ok(result) => {
+                query_params.names = Some(result.get_names());
+                query_params.vals = Some(result.get_values());
+                result.get_expression()
+            }
+            Err(e) => {
+                error!("{}\n--filter syntax is invalid. For more information, please visit https://github.com/awslabs/dynein/blob/main/docs/query.md.", e);
+                std::process::exit(1);
+            }
+        }
+    });
+    debug!("Generated FilterExpression: {:?}", &filter_expression);
+
+    if !params.replica_regions.is_empty() {
+        let regions = regions_to_compare(cx, &params.replica_regions).await;
+        let results: Vec<ReplicaReadResult> = join_all(regions.iter().map(|region| {
+            query_in_region(
+                cx,
+                region,
+                &ts,
+                &params,
+                &query_params,
+                filter_expression.as_deref(),
+            )
+        }))
+        .await;
+        let view = output::ItemsView {
+            ts: &ts,
+            attributes: &params.attributes,
+            keys_only: params.keys_only,
+            expand: &expand,
+        };
+        print_replica_results(cx, &results, &view, false);
+        return;
+    }
+
     let config = cx.effective_sdk_config().await;
     let ddb = DynamoDbSdkClient::new(&config);
 
@@ -242,90 +666,315 @@ pub async fn query(cx: &app::Context, params: QueryParams) {
         .set_index_name(params.index)
         .set_limit(params.limit)
         .set_key_condition_expression(query_params.exp)
+        .set_filter_expression(filter_expression)
         .set_expression_attribute_names(query_params.names)
         .set_expression_attribute_values(query_params.vals)
         .consistent_read(params.consistent_read)
-        .set_scan_index_forward(params.descending.then_some(false));
+        .set_scan_index_forward(params.descending.then_some(false))
+        .return_consumed_capacity(ReturnConsumedCapacity::Total);
     debug!("Request: {:#?}", req);
 
+    let started_at = Instant::now();
     match req.send().await {
         Ok(res) => {
-            match res.items {
+            match &res.items {
                 None => panic!("This message should not be shown"), // as Query returns 'Some([])' if there's no item to return.
-                Some(items) => match cx.output.as_deref() {
-                    None | Some("table") => {
-                        display_items_table(items, &ts, &params.attributes, params.keys_only)
-                    }
-                    Some("json") => println!(
-                        "{}",
-                        serde_json::to_string_pretty(&convert_to_json_vec(&items)).unwrap()
-                    ),
-                    Some("raw") => println!(
-                        "{}",
-                        serde_json::to_string_pretty(&strip_items(&items)).unwrap()
-                    ),
-                    Some(o) => {
-                        println!("ERROR: unsupported output type '{}'.", o);
-                        std::process::exit(1);
-                    }
-                },
+                Some(items) => {
+                    let view = output::ItemsView {
+                        ts: &ts,
+                        attributes: &params.attributes,
+                        keys_only: params.keys_only,
+                        expand: &expand,
+                    };
+                    post_process.print_items(cx.output.as_deref(), items, &view);
+                }
+            }
+            if params.summary {
+                eprintln!(
+                    "{}",
+                    ReadSummary::new(
+                        res.count,
+                        res.scanned_count,
+                        res.consumed_capacity.as_ref(),
+                        started_at
+                    )
+                );
             }
         }
         Err(e) => {
             debug!("Query API call got an error -- {:?}", e);
+            if app::refresh_schema_on_key_mismatch(cx, &ts, &e).await {
+                return Box::pin(query(cx, retry_params)).await;
+            }
             app::bye_with_sdk_error(1, e);
         }
     }
 }
 
+pub struct FindSortKeyParams {
+    pub sval: String,
+    pub yes: bool,
+}
+
+/// Lists primary keys of items which have the given sort key value, i.e. finds partitions which contain the sort key.
+/// As a primary key is unique, each partition has at most one such item.
+/// If a GSI uses the sort key as its partition key, this function queries the index. Otherwise it scans the whole table.
+pub async fn find_sort_key(cx: &app::Context, params: FindSortKeyParams) {
+    debug!("context: {:#?}", &cx);
+    let ts: app::TableSchema = app::table_schema(cx).await;
+    let sk = match &ts.sk {
+        Some(sk) => sk,
+        None => {
+            error!(
+                "The table '{}' has no sort key. Use `dy get` to look up an item by partition key.",
+                &ts.name
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let index = find_gsi_keyed_on(&ts, sk);
+    match index {
+        Some(idx) => eprintln!(
+            "Querying the index '{}' whose partition key is '{}'.",
+            idx, &sk.name
+        ),
+        None => {
+            eprintln!(
+                "WARN: No GSI uses '{}' as its partition key, so the whole table is scanned.",
+                &sk.name
+            );
+            if !params.yes && !cx.is_local().await {
+                let scan_params = ScanParams {
+                    index: None,
+                    consistent_read: false,
+                    attributes: None,
+                    keys_only: true,
+                    limit: i32::MAX,
+                    summary: false,
+                    yes: false,
+                    sort_by: None,
+                    group_by: None,
+                    distinct: None,
+                    expand: vec![],
+                };
+                confirm_large_scan(cx, &ts, &scan_params).await;
+            }
+        }
+    }
+
+    let condition = "#DYNEIN_SKNAME = :DYNEIN_SKVAL";
+    let projection = "#DYNEIN_PKNAME,#DYNEIN_SKNAME";
+    let names = HashMap::from([
+        (String::from("#DYNEIN_PKNAME"), ts.pk.name.to_owned()),
+        (String::from("#DYNEIN_SKNAME"), sk.name.to_owned()),
+    ]);
+    let vals = HashMap::from([(
+        String::from(":DYNEIN_SKVAL"),
+        build_attrval_scalar(&sk.kind.to_string(), &params.sval),
+    )]);
+
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+
+    let watch = interrupt::watch();
+    let mut items = vec![];
+    let mut last_evaluated_key: Option<HashMap<String, AttributeValue>> = None;
+    loop {
+        let page = match index {
+            Some(idx) => {
+                let req = ddb
+                    .query()
+                    .table_name(&ts.name)
+                    .index_name(idx)
+                    .key_condition_expression(condition)
+                    .projection_expression(projection)
+                    .set_expression_attribute_names(Some(names.clone()))
+                    .set_expression_attribute_values(Some(vals.clone()))
+                    .set_exclusive_start_key(last_evaluated_key)
+                    .send();
+                watch.page(req).await.map(|res| {
+                    res.map(|res| (res.items, res.last_evaluated_key))
+                        .unwrap_or_else(|e| {
+                            debug!("Query API call got an error -- {:?}", e);
+                            app::bye_with_sdk_error(1, e);
+                        })
+                })
+            }
+            None => {
+                let req = ddb
+                    .scan()
+                    .table_name(&ts.name)
+                    .filter_expression(condition)
+                    .projection_expression(projection)
+                    .set_expression_attribute_names(Some(names.clone()))
+                    .set_expression_attribute_values(Some(vals.clone()))
+                    .set_exclusive_start_key(last_evaluated_key)
+                    .send();
+                watch.page(req).await.map(|res| {
+                    res.map(|res| (res.items, res.last_evaluated_key))
+                        .unwrap_or_else(|e| {
+                            debug!("Scan API call got an error -- {:?}", e);
+                            app::bye_with_sdk_error(1, e);
+                        })
+                })
+            }
+        };
+        let (page, lek) = match page {
+            None => break,
+            Some(page) => page,
+        };
+        items.extend(page.unwrap_or_default());
+        match lek {
+            Some(_) if watch.interrupted() => break,
+            None => break,
+            lek => last_evaluated_key = lek,
+        }
+    }
+
+    let view = output::ItemsView {
+        ts: &ts,
+        attributes: &None,
+        keys_only: true,
+        expand: &[],
+    };
+    output::print_items(cx.output.as_deref(), "table", &items, &view);
+    eprintln!(
+        "{} partitions in the table '{}' have the sort key value '{}'.",
+        items.len(),
+        &ts.name,
+        &params.sval
+    );
+    if watch.interrupted() {
+        eprintln!("Interrupted before scanning all partitions. Other partitions may also have the sort key value.");
+        std::process::exit(interrupt::EXIT_INTERRUPTED);
+    }
+}
+
+pub struct GetItemParams {
+    pub pval: Option<String>,
+    pub svals: Vec<String>,
+    pub sort_keys: Vec<String>,
+    pub keys_file: Option<String>,
+    pub consistent_read: bool,
+    pub field: Option<String>,
+    pub replica_regions: Vec<String>,
+    pub humanize: bool,
+}
+
 /// This function calls GetItem API - get an item with given primary key(s). By default it uses 'json' output format.
-pub async fn get_item(
-    cx: &app::Context,
-    pval: String,
-    sval: Option<String>,
-    consistent_read: bool,
-) {
+/// With `humanize`, the expiration time in the TTL attribute of the item is annotated after the item, e.g. "expires in 3d 4h".
+/// Multiple items are read by BatchGetItem instead, when more partition keys (for a table without sort key) or `sort_keys` are given.
+pub async fn get_item(cx: &app::Context, params: GetItemParams) {
+    let GetItemParams {
+        pval,
+        svals,
+        sort_keys,
+        keys_file,
+        consistent_read,
+        field,
+        replica_regions,
+        humanize,
+    } = params;
     debug!("context: {:#?}", &cx);
+    // Validate --field before calling API so that a typo doesn't cost a read.
+    let field = field.map(|f| match DocumentPath::parse(&f) {
+        Ok(path) => (f, path),
+        Err(e) => {
+            error!("Invalid document path for --field '{}': {}", f, e);
+            std::process::exit(1);
+        }
+    });
     // Use table if explicitly specified by `--table/-t` option. Otherwise, load table name from config file.
-    let ts: app::TableSchema = app::table_schema(cx).await;
-    let primary_keys = identify_target(&ts, &pval, sval.as_deref());
+    let mut ts: app::TableSchema = app::table_schema(cx).await;
+    if let Some(path) = keys_file {
+        let keys = read_keys_file(&ts, &path).unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(1);
+        });
+        return get_items(cx, &ts, keys, consistent_read).await;
+    }
+    let pval = pval.expect("pval is required unless --keys-file is given");
+    let mut targets = identify_targets(&ts, &pval, &svals, &sort_keys);
+    if targets.len() > 1 {
+        if field.is_some() || !replica_regions.is_empty() || humanize {
+            error!("--field, --replica-region and --humanize are available only when getting a single item.");
+            std::process::exit(1);
+        }
+        return get_items(cx, &ts, targets, consistent_read).await;
+    }
+    let primary_keys = targets.remove(0);
 
     debug!(
         "Calling GetItem API for the table '{}' with key(s): {:?}",
         &ts.name, &primary_keys
     );
 
+    if !replica_regions.is_empty() {
+        let regions = regions_to_compare(cx, &replica_regions).await;
+        let results: Vec<ReplicaReadResult> = join_all(regions.iter().map(|region| {
+            get_item_in_region(cx, region, &ts.name, &primary_keys, consistent_read)
+        }))
+        .await;
+        let view = output::ItemsView {
+            ts: &ts,
+            attributes: &None,
+            keys_only: false,
+            expand: &[],
+        };
+        print_replica_results(cx, &results, &view, true);
+        return;
+    }
+
+    let ttl_attr = if humanize {
+        app::ttl_attribute(cx, &mut ts).await
+    } else {
+        None
+    };
+
     let config = cx.effective_sdk_config().await;
     let ddb = DynamoDbSdkClient::new(&config);
 
     match ddb
         .get_item()
-        .table_name(ts.name)
+        .table_name(&ts.name)
         .set_key(Some(primary_keys))
         .consistent_read(consistent_read)
         .send()
         .await
     {
-        Ok(res) => match res.item {
-            None => println!("No item found."),
-            Some(item) => match cx.output.as_deref() {
-                None | Some("json") => println!(
-                    "{}",
-                    serde_json::to_string_pretty(&convert_to_json(&item)).unwrap()
-                ),
-                Some("yaml") => println!(
-                    "{}",
-                    serde_yaml::to_string(&convert_to_json(&item)).unwrap()
-                ),
-                Some("raw") => println!(
-                    "{}",
-                    serde_json::to_string_pretty(&strip_item(&item)).unwrap()
-                ),
-                Some(o) => {
-                    println!("ERROR: unsupported output type '{}'.", o);
+        Ok(res) => match (res.item, field) {
+            // Scripts reading the field shouldn't take "No item found." as the value, so it's an error.
+            (None, Some(_)) => {
+                error!("No item found.");
+                std::process::exit(1);
+            }
+            (None, None) => println!("No item found."),
+            // The field is picked from the redacted item, so that --field doesn't reveal attributes hidden by `display.redact`.
+            (Some(item), Some((f, path))) => match path.extract(&output::redact_item(&item)) {
+                Some(attrval) => println!("{}", attrval_to_raw_string(attrval)),
+                None => {
+                    error!("The item doesn't have the field '{}'.", f);
                     std::process::exit(1);
                 }
             },
+            (Some(item), None) => {
+                let view = output::ItemsView {
+                    ts: &ts,
+                    attributes: &None,
+                    keys_only: false,
+                    expand: &[],
+                };
+                output::print_item(cx.output.as_deref(), "json", &item, &view);
+                if let Some(attr) = ttl_attr {
+                    println!(
+                        "{}",
+                        ttl_annotation(&attr, &item, chrono::Utc::now().timestamp())
+                    );
+                } else if humanize {
+                    eprintln!("NOTE: TTL is not enabled on the table '{}'.", &ts.name);
+                }
+            }
         },
         Err(e) => {
             debug!("GetItem API call got an error -- {:?}", e);
@@ -336,7 +985,16 @@ pub async fn get_item(
 
 // put_item function saves an item with given primary key(s). You can pass other attributes with --item/-i option in JSON format.
 // As per DynamoDB PutItem API behavior, if the item already exists it'd be replaced.
-pub async fn put_item(cx: &app::Context, pval: String, sval: Option<String>, item: Option<String>) {
+/// With `if_not_exists`, the item is put only if no item with the same primary key exists.
+/// Items over 400 KB are rejected before calling the API, unless `force` is given.
+pub async fn put_item(
+    cx: &app::Context,
+    pval: String,
+    sval: Option<String>,
+    item: Option<String>,
+    if_not_exists: bool,
+    force: bool,
+) {
     debug!("context: {:#?}", &cx);
     let ts: app::TableSchema = app::table_schema(cx).await;
     let mut full_item_image = identify_target(&ts, &pval, sval.as_deref()); // Firstly, ideitify primary key(s) to ideitnfy an item to put.
@@ -347,11 +1005,15 @@ pub async fn put_item(cx: &app::Context, pval: String, sval: Option<String>, ite
     );
 
     // merge additional items passed by `--item/-i` option.
-    match item {
+    match &item {
         None => (),
         Some(_i) => {
             let parser = DyneinParser::new();
-            let result = parser.parse_dynein_format(Some(full_item_image), &_i);
+            let result = if cx.should_strict() {
+                parser.parse_dynein_format_strict(Some(full_item_image), _i)
+            } else {
+                parser.parse_dynein_format(Some(full_item_image), _i)
+            };
             match result {
                 Ok(attrs) => {
                     full_item_image = attrs;
@@ -364,30 +1026,71 @@ pub async fn put_item(cx: &app::Context, pval: String, sval: Option<String>, ite
         }
     };
 
+    // --item may overwrite primary keys, e.g. with a value of another type.
+    if let Err(e) = key::validate_item_keys(&full_item_image, &ts.pk, ts.sk.as_ref()) {
+        error!("Failed to put the item: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = size::check_item_size(&full_item_image) {
+        if !force {
+            error!(
+                "Failed to put the item: {}. Use --force to send it anyway.",
+                e
+            );
+            std::process::exit(1);
+        }
+        eprintln!("WARN: {}", e);
+    }
+
     debug!("Calling PutItem API to insert: {:?}", &full_item_image);
 
     let config = cx.effective_sdk_config().await;
     let ddb = DynamoDbSdkClient::new(&config);
 
-    match ddb
+    let mut req = ddb
         .put_item()
         .table_name(ts.name.to_string())
-        .set_item(Some(full_item_image))
-        .send()
-        .await
-    {
+        .set_item(Some(full_item_image));
+    if if_not_exists {
+        let (exp, names) = generate_key_not_exists_condition(&ts);
+        debug!("Generated ConditionExpression: {}, names: {:?}", exp, names);
+        req = req
+            .condition_expression(exp)
+            .set_expression_attribute_names(Some(names));
+    }
+
+    match req.send().await {
         Ok(_) => {
             println!("Successfully put an item to the table '{}'.", &ts.name);
         }
+        Err(e)
+            if e.as_service_error()
+                .map_or(false, |se| se.is_conditional_check_failed_exception()) =>
+        {
+            error!(
+                "An item with the same primary key already exists in the table '{}'. It's not replaced as --if-not-exists is given.",
+                &ts.name
+            );
+            std::process::exit(1);
+        }
         Err(e) => {
             debug!("PutItem API call got an error -- {:?}", e);
+            if app::refresh_schema_on_key_mismatch(cx, &ts, &e).await {
+                return Box::pin(put_item(cx, pval, sval, item, if_not_exists, force)).await;
+            }
             app::bye_with_sdk_error(1, e);
         }
     }
 }
 
 // delete_item functions calls DeleteItem API - delete an item with given primary key(s).
-pub async fn delete_item(cx: &app::Context, pval: String, sval: Option<String>) {
+// With a condition given by `--if`, the item is deleted only if it matches the condition, i.e. ConditionExpression.
+pub async fn delete_item(
+    cx: &app::Context,
+    pval: String,
+    sval: Option<String>,
+    condition: Option<String>,
+) {
     debug!("context: {:#?}", &cx);
     let ts: app::TableSchema = app::table_schema(cx).await;
     let primary_keys = identify_target(&ts, &pval, sval.as_deref());
@@ -400,13 +1103,30 @@ pub async fn delete_item(cx: &app::Context, pval: String, sval: Option<String>)
     let config = cx.effective_sdk_config().await;
     let ddb = DynamoDbSdkClient::new(&config);
 
-    match ddb
+    let mut req = ddb
         .delete_item()
         .table_name(ts.name.to_string())
-        .set_key(Some(primary_keys))
-        .send()
-        .await
-    {
+        .set_key(Some(primary_keys));
+    if let Some(condition) = condition {
+        let result = match DyneinParser::new().parse_condition(&condition) {
+            Ok(result) => result,
+            Err(e) => {
+                error!(
+                    "{}\n--if syntax is invalid. See `dy help expressions` for more information.",
+                    e
+                );
+                std::process::exit(1);
+            }
+        };
+        debug!("Generated ConditionExpression: {}", result.get_expression());
+        req = req
+            .condition_expression(result.get_expression())
+            .set_expression_attribute_names(Some(result.get_names()))
+            // DynamoDB rejects empty ExpressionAttributeValues, e.g. for 'attribute_exists(x)'.
+            .set_expression_attribute_values(Some(result.get_values()).filter(|v| !v.is_empty()));
+    }
+
+    match req.send().await {
         // NOTE: DynamoDB DeleteItem API is idempotent and returns "OK" even if an item trying to delete doesn't exist.
         Ok(_) => {
             println!(
@@ -414,6 +1134,16 @@ pub async fn delete_item(cx: &app::Context, pval: String, sval: Option<String>)
                 &ts.name
             );
         }
+        Err(e)
+            if e.as_service_error()
+                .map_or(false, |se| se.is_conditional_check_failed_exception()) =>
+        {
+            error!(
+                "The item doesn't exist or doesn't match the condition given by --if, so it's not deleted from the table '{}'.",
+                &ts.name
+            );
+            std::process::exit(1);
+        }
         Err(e) => {
             debug!("Deletetem API call got an error -- {:?}", e);
             app::bye_with_sdk_error(1, e);
@@ -481,28 +1211,588 @@ pub async fn update_item(
     }
 }
 
-// https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/WorkingWithItems.html#WorkingWithItems.AtomicCounters
-pub async fn atomic_counter(
-    cx: &app::Context,
-    pval: String,
-    sval: Option<String>,
-    set_expression: Option<String>,
-    remove_expression: Option<String>,
-    target_attr: String,
-) {
-    debug!("context: {:#?}", &cx);
-    if set_expression.is_some() || remove_expression.is_some() {
-        error!("--atomic-counter option cannot be used with --set or --remove.");
-        std::process::exit(1);
+pub struct CounterParams {
+    /// Attribute names or document paths of counters, e.g. "stats.views".
+    pub target_attrs: Vec<String>,
+    /// Step to add to the counter, which can be negative. Defaults to 1.
+    pub step: Option<String>,
+    /// Value of the counter when the attribute doesn't exist. Defaults to 0.
+    pub init: Option<String>,
+}
+
+// https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/WorkingWithItems.html#WorkingWithItems.AtomicCounters
+pub async fn atomic_counter(
+    cx: &app::Context,
+    pval: String,
+    sval: Option<String>,
+    set_expression: Option<String>,
+    remove_expression: Option<String>,
+    counter: CounterParams,
+) {
+    debug!("context: {:#?}", &cx);
+    if set_expression.is_some() || remove_expression.is_some() {
+        error!("--atomic-counter option cannot be used with --set or --remove.");
+        std::process::exit(1);
+    };
+    let atomic_counter_expression = build_atomic_counter_expression(&counter);
+    // Validate targets here to show a friendly message, as update_item panics on a parse error.
+    if let Err(e) = DyneinParser::new().parse_set_action(&atomic_counter_expression) {
+        error!("{}\n--atomic-counter should be attribute name(s) or document path(s) separated by commas, e.g. 'pv,stats.views'.", e);
+        std::process::exit(1);
+    }
+    update_item(cx, pval, sval, Some(atomic_counter_expression), None).await;
+}
+
+pub struct BulkUpdateParams {
+    /// Partition key to query target items with. Without it, the whole table is scanned.
+    pub pval: Option<String>,
+    pub condition: String,
+    pub set: Option<String>,
+    pub remove: Option<String>,
+    pub dry_run: bool,
+    pub concurrency: Option<usize>,
+}
+
+/// Executed when you call `$ dy upd --where <condition> --set/--remove <action>`.
+/// Items matching the condition are found by Scan (or Query with a partition key) page by page, and each page is updated
+/// by UpdateItem with bounded concurrency. The condition is given to UpdateItem as ConditionExpression as well,
+/// so that items which have been changed or deleted since they're read are skipped rather than updated (or re-created).
+pub async fn bulk_update(cx: &app::Context, params: BulkUpdateParams) {
+    debug!("context: {:#?}", &cx);
+    let ts: app::TableSchema = app::table_schema(cx).await;
+
+    // UpdateExpression and ConditionExpression share ExpressionAttributeNames and ExpressionAttributeValues.
+    let mut parser = DyneinParser::new();
+    let action = match (&params.set, &params.remove) {
+        (Some(set), _) => parser
+            .parse_set_action(set)
+            .map(|r| format!("SET {}", r.get_expression())),
+        (None, Some(remove)) => parser
+            .parse_remove_action(remove)
+            .map(|r| format!("REMOVE {}", r.get_expression())),
+        (None, None) => {
+            error!("One of --set or --remove option is required with --where.");
+            std::process::exit(1);
+        }
+    };
+    let update_condition = format!(
+        "({}) AND attribute_exists({})",
+        params.condition,
+        quote_attr_name(&ts.pk.name)
+    );
+    let update = action.and_then(|action| {
+        let condition = parser.parse_condition(&update_condition)?;
+        Ok((action, condition))
+    });
+    let (action, update_condition) = match update {
+        Ok(update) => update,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    debug!("generated UpdateExpression: {:?}", action);
+    debug!("generated ConditionExpression: {:?}", update_condition);
+
+    let (key_condition, mut parser) = match &params.pval {
+        None => (None, DyneinParser::new()),
+        Some(pval) => {
+            match generate_query_expressions(&ts, pval, &None, &None, cx.should_strict()) {
+                Ok(qp) => (
+                    qp.exp,
+                    DyneinParser::with_placeholders(
+                        qp.names.unwrap_or_default(),
+                        qp.vals.unwrap_or_default(),
+                    ),
+                ),
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    };
+    let filter = parser
+        .parse_condition(&params.condition)
+        .unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(1);
+        });
+    debug!("generated FilterExpression: {:?}", filter);
+
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+    let concurrency = params.concurrency.unwrap_or(BULK_UPDATE_CONCURRENCY);
+    let show_progress = !params.dry_run && io::stderr().is_terminal();
+
+    let (mut matched, mut updated, mut skipped) = (0, 0, 0);
+    let watch = interrupt::watch();
+    let mut last_evaluated_key: Option<HashMap<String, AttributeValue>> = None;
+    loop {
+        let page = match &key_condition {
+            None => {
+                let req = ddb
+                    .scan()
+                    .table_name(&ts.name)
+                    .filter_expression(filter.get_expression())
+                    .set_expression_attribute_names(non_empty(filter.get_names()))
+                    .set_expression_attribute_values(non_empty(filter.get_values()))
+                    .set_exclusive_start_key(last_evaluated_key)
+                    .send();
+                watch.page(req).await.map(|res| {
+                    res.map(|res| (res.items, res.last_evaluated_key))
+                        .unwrap_or_else(|e| {
+                            debug!("Scan API call got an error -- {:?}", e);
+                            app::bye_with_sdk_error(1, e);
+                        })
+                })
+            }
+            Some(key_condition) => {
+                let req = ddb
+                    .query()
+                    .table_name(&ts.name)
+                    .key_condition_expression(key_condition)
+                    .filter_expression(filter.get_expression())
+                    .set_expression_attribute_names(non_empty(filter.get_names()))
+                    .set_expression_attribute_values(non_empty(filter.get_values()))
+                    .set_exclusive_start_key(last_evaluated_key)
+                    .send();
+                watch.page(req).await.map(|res| {
+                    res.map(|res| (res.items, res.last_evaluated_key))
+                        .unwrap_or_else(|e| {
+                            debug!("Query API call got an error -- {:?}", e);
+                            app::bye_with_sdk_error(1, e);
+                        })
+                })
+            }
+        };
+        // Items of the page in flight are not updated, while updates of the previous page have completed.
+        let (items, lek) = match page {
+            None => break,
+            Some(page) => page,
+        };
+        let keys: Vec<HashMap<String, AttributeValue>> = items
+            .unwrap_or_default()
+            .iter()
+            .map(|item| primary_key_of(&ts, item))
+            .collect();
+        matched += keys.len();
+
+        if !params.dry_run {
+            let mut results = stream::iter(keys)
+                .map(|key| {
+                    ddb.update_item()
+                        .table_name(&ts.name)
+                        .set_key(Some(key))
+                        .update_expression(&action)
+                        .condition_expression(update_condition.get_expression())
+                        .set_expression_attribute_names(non_empty(update_condition.get_names()))
+                        .set_expression_attribute_values(non_empty(update_condition.get_values()))
+                        .send()
+                })
+                .buffer_unordered(concurrency);
+            while let Some(result) = results.next().await {
+                match result {
+                    Ok(_) => updated += 1,
+                    Err(e)
+                        if e.as_service_error()
+                            .map_or(false, |se| se.is_conditional_check_failed_exception()) =>
+                    {
+                        skipped += 1
+                    }
+                    Err(e) => {
+                        debug!("UpdateItem API call got an error -- {:?}", e);
+                        eprintln!(
+                            "\n{} of {} matched items have been updated before the error.",
+                            updated, matched
+                        );
+                        app::bye_with_sdk_error(1, e);
+                    }
+                }
+            }
+        }
+        if show_progress {
+            eprint!("\r{} items matched, {} updated", matched, updated);
+        }
+
+        match lek {
+            Some(_) if watch.interrupted() => break,
+            None => break,
+            Some(lek) => last_evaluated_key = Some(lek),
+        }
+    }
+    if show_progress {
+        eprintln!();
+    }
+
+    if watch.interrupted() {
+        if params.dry_run {
+            eprintln!(
+                "Interrupted. {} items in the table '{}' matched the condition before the interrupt.",
+                matched, &ts.name
+            );
+        } else {
+            eprintln!(
+                "Interrupted. {} of {} matched items in the table '{}' have been updated, and {} were skipped.",
+                updated, matched, &ts.name, skipped
+            );
+        }
+        std::process::exit(interrupt::EXIT_INTERRUPTED);
+    }
+
+    if params.dry_run {
+        println!(
+            "{} items in the table '{}' match the condition. Run without --dry-run to update them.",
+            matched, &ts.name
+        );
+        return;
+    }
+    println!(
+        "Successfully updated {} items in the table '{}'.",
+        updated, &ts.name
+    );
+    if skipped > 0 {
+        println!(
+            "{} items were skipped as they no longer matched the condition when updated.",
+            skipped
+        );
+    }
+}
+
+/// Prints the expression, ExpressionAttributeNames, and ExpressionAttributeValues that dynein generates
+/// from the given dynein-style expression, without calling any DynamoDB APIs.
+/// Values are printed in DynamoDB JSON format so that the output can be reused with other tools such as AWS CLI.
+pub async fn preview_expression(
+    cx: &app::Context,
+    preview_type: ExpressionPreviewType,
+    expression: String,
+) {
+    debug!("context: {:#?}", &cx);
+    let mut parser = DyneinParser::new();
+    let (prefix, result) = match preview_type {
+        ExpressionPreviewType::Set => ("SET ", parser.parse_set_action(&expression)),
+        ExpressionPreviewType::Remove => ("REMOVE ", parser.parse_remove_action(&expression)),
+        ExpressionPreviewType::Condition => ("", parser.parse_condition(&expression)),
+        ExpressionPreviewType::SortKey(key) => {
+            let sort_attr = match key {
+                Some(key) => parse_sort_key_definition(&key),
+                // Without --key option, the sort key of the target table is used.
+                None => match app::table_schema(cx).await.sk {
+                    Some(sk) => AttributeDefinition::from(sk),
+                    None => {
+                        error!("{}", DyneinQueryParamsError::NoSortKeyDefined);
+                        std::process::exit(1);
+                    }
+                },
+            };
+            let result = if cx.should_strict() {
+                parser.parse_sort_key_with_suggest(&expression, &sort_attr)
+            } else {
+                parser.parse_sort_key_with_fallback(&expression, &sort_attr)
+            };
+            ("", result)
+        }
+    };
+
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut names = serde_json::Map::new();
+    for (placeholder, name) in result.get_names() {
+        names.insert(placeholder, JsonValue::String(name));
+    }
+    let mut values = serde_json::Map::new();
+    for (placeholder, value) in result.get_values() {
+        values.insert(
+            placeholder,
+            serde_json::to_value(AttributeValueWrapper(value)).unwrap(),
+        );
+    }
+    let preview = serde_json::json!({
+        "expression": format!("{}{}", prefix, result.get_expression()),
+        "names": names,
+        "values": values,
+    });
+    println!("{}", serde_json::to_string_pretty(&preview).unwrap());
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Returns the current region followed by replica regions given by `--replica-region` option, without duplicates.
+async fn regions_to_compare(cx: &app::Context, replica_regions: &[String]) -> Vec<String> {
+    let mut regions = vec![cx.effective_region().await.as_ref().to_owned()];
+    for region in replica_regions {
+        if !regions.contains(region) {
+            regions.push(region.to_owned());
+        }
+    }
+    regions
+}
+
+/// Calls Query API in the region with the same expressions as the current region.
+async fn query_in_region(
+    cx: &app::Context,
+    region: &str,
+    ts: &app::TableSchema,
+    params: &QueryParams,
+    query_params: &GeneratedQueryParams,
+    filter_expression: Option<&str>,
+) -> ReplicaReadResult {
+    let config = cx.effective_sdk_config_with_region(region).await;
+    let started_at = Instant::now();
+    let res = DynamoDbSdkClient::new(&config)
+        .query()
+        .table_name(ts.name.to_string())
+        .set_index_name(params.index.clone())
+        .set_limit(params.limit)
+        .set_key_condition_expression(query_params.exp.clone())
+        .set_filter_expression(filter_expression.map(str::to_owned))
+        .set_expression_attribute_names(query_params.names.clone())
+        .set_expression_attribute_values(query_params.vals.clone())
+        .consistent_read(params.consistent_read)
+        .set_scan_index_forward(params.descending.then_some(false))
+        .send()
+        .await;
+    ReplicaReadResult {
+        region: region.to_owned(),
+        elapsed: started_at.elapsed(),
+        items: res
+            .map(|res| res.items.unwrap_or_default())
+            .map_err(|e| DisplayErrorContext(&e).to_string()),
+    }
+}
+
+/// Gets items with BatchGetItem and prints them in the order of the given keys. By default it uses 'json' output format.
+/// Keys of items which don't exist are shown as warnings, and it exits with non-zero status if any key remains unprocessed.
+async fn get_items(
+    cx: &app::Context,
+    ts: &app::TableSchema,
+    keys: Vec<HashMap<String, AttributeValue>>,
+    consistent_read: bool,
+) {
+    debug!(
+        "Calling BatchGetItem API for the table '{}' with {} keys",
+        &ts.name,
+        keys.len()
+    );
+    let (found, unprocessed) = batch::batch_get_items(cx, &ts.name, keys.clone(), consistent_read)
+        .await
+        .unwrap_or_else(|e| {
+            debug!("BatchGetItem API call got an error -- {:?}", e);
+            app::bye_with_sdk_error(1, e);
+        });
+
+    let mut items = vec![];
+    let mut not_found = vec![];
+    for key in &keys {
+        match found
+            .iter()
+            .find(|item| key.iter().all(|(name, val)| item.get(name) == Some(val)))
+        {
+            Some(item) => items.push(item.to_owned()),
+            None if unprocessed.contains(key) => (),
+            None => not_found.push(key),
+        }
+    }
+
+    let view = output::ItemsView {
+        ts,
+        attributes: &None,
+        keys_only: false,
+        expand: &[],
+    };
+    output::print_items(cx.output.as_deref(), "json", &items, &view);
+    for key in not_found {
+        eprintln!(
+            "WARN: No item found for the key {}.",
+            serde_json::to_string(&convert_to_json(key)).unwrap()
+        );
+    }
+    if !unprocessed.is_empty() {
+        error!(
+            "{} key(s) remain unprocessed after retries, e.g. due to throttling. Try again later.",
+            unprocessed.len()
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Calls GetItem API in the region with the same primary key(s) as the current region.
+async fn get_item_in_region(
+    cx: &app::Context,
+    region: &str,
+    table_name: &str,
+    primary_keys: &HashMap<String, AttributeValue>,
+    consistent_read: bool,
+) -> ReplicaReadResult {
+    let config = cx.effective_sdk_config_with_region(region).await;
+    let started_at = Instant::now();
+    let res = DynamoDbSdkClient::new(&config)
+        .get_item()
+        .table_name(table_name)
+        .set_key(Some(primary_keys.clone()))
+        .consistent_read(consistent_read)
+        .send()
+        .await;
+    ReplicaReadResult {
+        region: region.to_owned(),
+        elapsed: started_at.elapsed(),
+        items: res
+            .map(|res| res.item.into_iter().collect())
+            .map_err(|e| DisplayErrorContext(&e).to_string()),
+    }
+}
+
+/// Prints results of the same read in each region with its latency, so that you can tell replication lag of global tables.
+/// Exits with 1 if the read failed in any region.
+fn print_replica_results(
+    cx: &app::Context,
+    results: &[ReplicaReadResult],
+    view: &output::ItemsView,
+    single_item: bool,
+) {
+    for result in results {
+        let elapsed_ms = result.elapsed.as_millis();
+        match &result.items {
+            Err(e) => println!("# {}: failed in {} ms -- {}", result.region, elapsed_ms, e),
+            Ok(items) => {
+                println!(
+                    "# {}: {} item(s) in {} ms",
+                    result.region,
+                    items.len(),
+                    elapsed_ms
+                );
+                if single_item {
+                    match items.first() {
+                        Some(item) => output::print_item(cx.output.as_deref(), "json", item, view),
+                        None => println!("No item found."),
+                    }
+                } else {
+                    output::print_items(cx.output.as_deref(), "table", items, view);
+                }
+            }
+        }
+    }
+
+    let succeeded: Vec<&Vec<output::Item>> = results
+        .iter()
+        .filter_map(|result| result.items.as_ref().ok())
+        .collect();
+    if succeeded.windows(2).any(|pair| pair[0] != pair[1]) {
+        eprintln!(
+            "WARN: Results differ among regions. Recent writes may not have been replicated yet."
+        );
+    }
+    if succeeded.len() < results.len() {
+        std::process::exit(1);
+    }
+}
+
+/// Describes when the item expires by the TTL attribute, e.g. "# TTL (expires_at): expires in 3d 4h (2020-09-16T16:26:40+00:00)".
+/// DynamoDB TTL ignores items whose TTL attribute is missing or not a number, so they never expire.
+fn ttl_annotation(attr: &str, item: &HashMap<String, AttributeValue>, now: i64) -> String {
+    let expiry = match item
+        .get(attr)
+        .map(|attrval| attrval.as_n().map(|n| n.parse::<f64>()))
+    {
+        None => String::from("not set, the item never expires"),
+        Some(Ok(Ok(expires_at))) => {
+            let expires_at = expires_at as i64;
+            let at = chrono::DateTime::from_timestamp(expires_at, 0)
+                .map_or(String::from("out of range"), |t| t.to_rfc3339());
+            if expires_at > now {
+                format!(
+                    "expires in {} ({})",
+                    humanize_duration(expires_at - now),
+                    at
+                )
+            } else if now - expires_at > analyze::TTL_MAX_AGE_SECONDS {
+                format!(
+                    "expired {} ago ({}), more than five years ago so TTL never deletes it",
+                    humanize_duration(now - expires_at),
+                    at
+                )
+            } else {
+                format!(
+                    "expired {} ago ({}), waiting for deletion by TTL",
+                    humanize_duration(now - expires_at),
+                    at
+                )
+            }
+        }
+        Some(_) => String::from("not a number, the item never expires"),
+    };
+    format!("# TTL ({}): {}", attr, expiry)
+}
+
+/// Shows seconds in the two largest units, e.g. "3d 4h", "12m 5s" or "42s".
+fn humanize_duration(secs: i64) -> String {
+    let units = [("d", 24 * 60 * 60), ("h", 60 * 60), ("m", 60), ("s", 1)];
+    let mut rest = secs.max(0);
+    let mut parts: Vec<String> = vec![];
+    for (suffix, unit) in units {
+        let n = rest / unit;
+        rest %= unit;
+        if n > 0 || (parts.is_empty() && unit == 1) {
+            parts.push(format!("{}{}", n, suffix));
+        } else if !parts.is_empty() {
+            break;
+        }
+        if parts.len() == 2 {
+            break;
+        }
+    }
+    parts.join(" ")
+}
+
+/// Builds a ConditionExpression which is satisfied only if there's no item with the same primary key.
+/// attribute_not_exists of the partition key is enough for that, but the sort key is checked as well for clarity.
+fn generate_key_not_exists_condition(ts: &app::TableSchema) -> (String, HashMap<String, String>) {
+    let mut keys = vec![&ts.pk];
+    keys.extend(ts.sk.as_ref());
+    let mut conditions = vec![];
+    let mut names = HashMap::new();
+    for (i, key) in keys.into_iter().enumerate() {
+        let placeholder = format!("#DYNEIN_KEYNAME{}", i);
+        conditions.push(format!("attribute_not_exists({})", placeholder));
+        names.insert(placeholder, key.name.to_owned());
+    }
+    (conditions.join(" AND "), names)
+}
+
+/// Builds a SET expression of atomic counters, e.g. "visits = if_not_exists(visits, 0) + 1".
+/// if_not_exists lets the first increment succeed on an item which doesn't have the attribute yet.
+/// Note that the parent of a nested counter (e.g. `stats` of `stats.views`) has to exist.
+fn build_atomic_counter_expression(counter: &CounterParams) -> String {
+    let step = counter.step.as_deref().unwrap_or("1");
+    let (operator, magnitude) = match step.strip_prefix('-') {
+        Some(magnitude) => ("-", magnitude),
+        None => ("+", step.trim_start_matches('+')),
     };
-    let atomic_counter_expression = format!("{} = {} + 1", target_attr, target_attr);
-    update_item(cx, pval, sval, Some(atomic_counter_expression), None).await;
+    let init = counter.init.as_deref().unwrap_or("0");
+    counter
+        .target_attrs
+        .iter()
+        .map(|attr| {
+            format!(
+                "{0} = if_not_exists({0}, {1}) {2} {3}",
+                attr.trim(),
+                init,
+                operator,
+                magnitude
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
-/* =================================================
-Private functions
-================================================= */
-
 /*
 Basically what this function does is to replace attribute names and values into DynamoDB style placeholders, i.e. "#ATTRNAME" and ":VALUE".
 And return UpdateExpression [1] string and supplementary names/values that are saved as HashMaps.
@@ -531,6 +1821,7 @@ Support status of various examples ([x] = not available for now, [o] = supported
 - [o] "SET #ri = list_append(#ri, :vals)" => --set 'RelatedItems = list_append(RelatedItems, ["item2"])'
 - [o] "SET #ri = list_append(:vals, #ri)" => --set 'RelatedItems = list_append(["item2"], RelatedItems)'
 - [o] "SET Price = if_not_exists(Price, :p)" => --set 'Price = if_not_exists(Price, 123)'
+- [o] "SET #ri = list_append(if_not_exists(#ri, :empty), :vals)" => --set 'RelatedItems = list_append(if_not_exists(RelatedItems, []), ["item2"])'
 - [o] "REMOVE Brand, InStock, QuantityOnHand" => in dynein: `$ dy update <keys> --remove 'Brand, InStock, QuantityOnHand'`.
 - [o] "REMOVE RelatedItems[1], RelatedItems[2]" => --remove 'RelatedItems[1], RelatedItems[2]'
 */
@@ -580,6 +1871,137 @@ fn generate_update_expressions(
     }
 }
 
+/// Extracts the primary key attributes of an item, e.g. to call UpdateItem for an item returned by Scan.
+pub fn primary_key_of(
+    ts: &app::TableSchema,
+    item: &HashMap<String, AttributeValue>,
+) -> HashMap<String, AttributeValue> {
+    std::iter::once(&ts.pk)
+        .chain(ts.sk.as_ref())
+        .filter_map(|key| Some((key.name.to_owned(), item.get(&key.name)?.clone())))
+        .collect()
+}
+
+/// Returns the name of a GSI whose partition key is the given key. LSIs share the partition key with the table, so they're never candidates.
+fn find_gsi_keyed_on<'a>(ts: &'a app::TableSchema, key: &key::Key) -> Option<&'a str> {
+    ts.indexes
+        .iter()
+        .flatten()
+        .find(|idx| {
+            matches!(idx.kind, app::IndexType::Gsi)
+                && idx.pk.name == key.name
+                && idx.pk.kind == key.kind
+        })
+        .map(|idx| idx.name.as_str())
+}
+
+/// Returns whether to read with strongly consistent reads, given --consistent-read or `table_defaults` in config.yml.
+/// GSIs support only eventually consistent reads, and DynamoDB rejects ConsistentRead on them.
+/// The table default is silently dropped on a GSI, while --consistent-read is dropped with a warning instead of the API error,
+/// or exits in the strict mode.
+fn consistent_read_for(
+    cx: &app::Context,
+    ts: &app::TableSchema,
+    index: Option<&str>,
+    consistent_read: bool,
+) -> bool {
+    let gsi = match index {
+        Some(index) if is_gsi(ts, index) => index,
+        _ => return cx.consistent_read_or_table_default(consistent_read),
+    };
+    if !consistent_read {
+        return false;
+    }
+    if cx.should_strict() {
+        error!(
+            "GSI '{}' doesn't support strongly consistent reads. Remove --consistent-read, or read the table or an LSI instead.",
+            gsi
+        );
+        std::process::exit(1);
+    }
+    eprintln!(
+        "WARN: GSI '{}' doesn't support strongly consistent reads. --consistent-read is ignored and the index is read with eventually consistent reads.",
+        gsi
+    );
+    false
+}
+
+fn is_gsi(ts: &app::TableSchema, index: &str) -> bool {
+    ts.indexes
+        .iter()
+        .flatten()
+        .any(|idx| idx.name == index && matches!(idx.kind, app::IndexType::Gsi))
+}
+
+/// DynamoDB rejects empty ExpressionAttributeNames and ExpressionAttributeValues, so they're omitted instead.
+fn non_empty<V>(map: HashMap<String, V>) -> Option<HashMap<String, V>> {
+    if map.is_empty() {
+        None
+    } else {
+        Some(map)
+    }
+}
+
+/// Quotes an attribute name with backticks so that the parser treats it as a single attribute even if it contains special characters.
+fn quote_attr_name(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// Parses "<sort key name>,<S/N/B>" given via `dy expr sortkey --key` option.
+fn parse_sort_key_definition(key: &str) -> AttributeDefinition {
+    let parsed = key
+        .split_once(',')
+        .and_then(|(name, kind)| Some((name.trim(), kind.trim().parse::<key::KeyType>().ok()?)));
+    match parsed {
+        Some((name, kind)) if !name.is_empty() => AttributeDefinition::new(name, kind),
+        _ => {
+            error!(
+                "Invalid format for --key option: '{}'. Valid format is '--key mySk,N'",
+                key
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Returns primary keys of items to get. For a table without sort key, `svals` are taken as more partition keys,
+/// while `sort_keys` are combined with the partition key for a table with sort key. Duplicated keys are removed.
+fn identify_targets(
+    ts: &app::TableSchema,
+    pval: &str,
+    svals: &[String],
+    sort_keys: &[String],
+) -> Vec<HashMap<String, AttributeValue>> {
+    let targets: Vec<HashMap<String, AttributeValue>> = match (&ts.sk, svals, sort_keys) {
+        (_, [], []) => vec![identify_target(ts, pval, None)],
+        (Some(_), [sval], []) => vec![identify_target(ts, pval, Some(sval))],
+        (None, _, []) => std::iter::once(pval)
+            .chain(svals.iter().map(String::as_str))
+            .map(|pval| identify_target(ts, pval, None))
+            .collect(),
+        (Some(_), [], _) => sort_keys
+            .iter()
+            .map(|sval| identify_target(ts, pval, Some(sval)))
+            .collect(),
+        (Some(_), _, []) => {
+            error!("The table '{}' has a sort key, so give only one sort key value. Use --sort-keys to get multiple items of the partition.", &ts.name);
+            std::process::exit(1);
+        }
+        (None, _, _) => {
+            error!("--sort-keys is given, but table '{t}' uses Partition key only. Check `dy desc {t}`", t = &ts.name);
+            std::process::exit(1);
+        }
+        (Some(_), _, _) => unreachable!("sort key value conflicts with --sort-keys"),
+    };
+    let mut unique: Vec<HashMap<String, AttributeValue>> = Vec::with_capacity(targets.len());
+    for target in targets {
+        if !unique.contains(&target) {
+            unique.push(target);
+        }
+    }
+    unique
+}
+
 // Without `--table/-t` option, `identify_target` utilizes table info stored in config file which is saved via `dy use` command.
 // With `--table/-t` option, `identify_target` retrieves primary key(s) info by calling DescribeTable API each time which would consumre additional time.
 fn identify_target(
@@ -630,6 +2052,117 @@ fn build_attrval_scalar(_ktype: &str, _kval: &str) -> AttributeValue {
     }
 }
 
+/// Reads primary keys from a key file given by `--keys-file`, which has one key per line: a partition key value,
+/// or "pk,sk" for a table with sort key. Values are typed based on the table schema.
+/// Blank lines and lines starting with '#' are skipped, and duplicated keys are removed.
+pub fn read_keys_file(
+    ts: &app::TableSchema,
+    path: &str,
+) -> Result<Vec<HashMap<String, AttributeValue>>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read the key file '{}': {}", path, e))?;
+    let keys =
+        parse_keys(ts, &content).map_err(|e| format!("Invalid key file '{}': {}", path, e))?;
+    if keys.is_empty() {
+        return Err(format!("No key is found in the key file '{}'", path));
+    }
+    debug!("Loaded {} keys from the key file '{}'", keys.len(), path);
+    Ok(keys)
+}
+
+/// Parses lines of a key file. For a table with sort key, the first comma separates the partition key and the sort key,
+/// so only the sort key value may contain commas unless the partition key value is quoted with double quotes as in CSV.
+fn parse_keys(
+    ts: &app::TableSchema,
+    content: &str,
+) -> Result<Vec<HashMap<String, AttributeValue>>, String> {
+    let mut keys = Vec::<HashMap<String, AttributeValue>>::new();
+    let mut seen = HashSet::<(String, Option<String>)>::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (pval, sval) = match &ts.sk {
+            None => (unquote_key_value(line), None),
+            Some(_) => match split_key_line(line) {
+                Some((pval, sval)) => (pval, Some(sval)),
+                None => {
+                    return Err(format!(
+                        "Line {} has no sort key value, while the table '{}' has a sort key. Write \"pk,sk\" in each line",
+                        i + 1,
+                        ts.name
+                    ))
+                }
+            },
+        };
+        if !seen.insert((pval.clone(), sval.clone())) {
+            continue;
+        }
+        let mut key = HashMap::<String, AttributeValue>::new();
+        key.insert(
+            ts.pk.name.to_owned(),
+            typed_key_attrval(&ts.pk, pval, i + 1)?,
+        );
+        if let (Some(sk), Some(sval)) = (&ts.sk, sval) {
+            key.insert(sk.name.to_owned(), typed_key_attrval(sk, sval, i + 1)?);
+        }
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+fn split_key_line(line: &str) -> Option<(String, String)> {
+    let comma = if line.starts_with('"') {
+        // Quotes escaped by doubling them toggle twice, so they never end the quoted value.
+        let mut quoted = false;
+        line.char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                c == ',' && !quoted
+            })?
+            .0
+    } else {
+        line.find(',')?
+    };
+    Some((
+        unquote_key_value(&line[..comma]),
+        unquote_key_value(&line[comma + 1..]),
+    ))
+}
+
+fn unquote_key_value(val: &str) -> String {
+    let val = val.trim();
+    if val.len() >= 2 && val.starts_with('"') && val.ends_with('"') {
+        val[1..val.len() - 1].replace("\"\"", "\"")
+    } else {
+        val.to_owned()
+    }
+}
+
+fn typed_key_attrval(k: &key::Key, val: String, line: usize) -> Result<AttributeValue, String> {
+    if val.is_empty() {
+        return Err(format!(
+            "Line {} has an empty value for the key '{}'",
+            line, k.name
+        ));
+    }
+    match k.kind {
+        key::KeyType::S => Ok(AttributeValue::S(val)),
+        key::KeyType::N if val.parse::<f64>().is_ok() => Ok(AttributeValue::N(val)),
+        key::KeyType::N => Err(format!(
+            "Line {} has '{}' for the key '{}', which should be a number",
+            line, val, k.name
+        )),
+        key::KeyType::B => Err(format!(
+            "Line {} is for the binary key '{}', which isn't supported",
+            line, k.name
+        )),
+    }
+}
+
 // for SS and NS DynamoDB Attributes.
 // :( serde_json::value::string -- to_string() --> "\"a\""
 // :) serde_json::value::string -- as_str() --> some("a") -- unwrap() --> "a"
@@ -751,17 +2284,14 @@ impl Serialize for AttributeValueWrapper {
                     .map(|item| AttributeValueWrapper(item.clone()))
                     .collect::<Vec<_>>(),
             )?,
-            _ => panic!(
-                "DynamoDB AttributeValue is not in valid status: {:#?}",
-                &self.0
-            ),
+            _ => state.serialize_field("S", unknown_attrval_placeholder(&self.0))?,
         };
         state.end()
     }
 }
 
 /// `strip_items` calls `strip_item` for each item.
-fn strip_items(
+pub fn strip_items(
     items: &[HashMap<String, AttributeValue>],
 ) -> Vec<HashMap<String, serde_json::Value>> {
     items.iter().map(strip_item).collect()
@@ -785,7 +2315,7 @@ fn strip_items(
 /// to something like this:
 ///
 ///     { "pkA": { "S": "e0a170d9-5ce3-443b-bbce-d0d49c71d151" }
-fn strip_item(item: &HashMap<String, AttributeValue>) -> HashMap<String, serde_json::Value> {
+pub fn strip_item(item: &HashMap<String, AttributeValue>) -> HashMap<String, serde_json::Value> {
     item.iter()
         .map(|attr| {
             (
@@ -805,7 +2335,7 @@ impl From<key::Key> for AttributeDefinition {
 fn generate_query_expressions(
     ts: &app::TableSchema,
     pval: &str,
-    sort_key_expression: &Option<String>,
+    sort_key_input: &Option<SortKeyInput>,
     index: &Option<String>,
     strict: bool,
 ) -> Result<GeneratedQueryParams, DyneinQueryParamsError> {
@@ -865,7 +2395,7 @@ fn generate_query_expressions(
         "Before appending sort key expression ... exp='{}', names='{:?}', vals={:?}",
         &expression, &names, &vals
     );
-    match sort_key_expression {
+    match sort_key_input {
         None =>
         /* No --sort-key option given. proceed with partition key condition only. */
         {
@@ -875,13 +2405,13 @@ fn generate_query_expressions(
                 vals: Some(vals),
             })
         }
-        Some(ske) =>
+        Some(ski) =>
         /* As --sort-key option is given, parse it and append the built SK related condition to required PK expression. */
         {
             append_sort_key_expression(
                 sort_key_of_target_table_or_index,
                 &expression,
-                ske,
+                ski,
                 names,
                 vals,
                 strict,
@@ -905,7 +2435,7 @@ impl From<key::KeyType> for AttributeType {
 fn append_sort_key_expression(
     sort_key: Option<key::Key>,
     partition_key_expression: &str,
-    sort_key_expression: &str,
+    sort_key_input: &SortKeyInput,
     mut names: HashMap<String, String>,
     mut vals: HashMap<String, AttributeValue>,
     strict: bool,
@@ -924,17 +2454,16 @@ fn append_sort_key_expression(
         &built
     );
 
+    let sort_attr = AttributeDefinition::new(sk_name, sk_type);
     let mut parser = DyneinParser::new();
-    let result = if strict {
-        parser.parse_sort_key_with_suggest(
-            sort_key_expression,
-            &AttributeDefinition::new(sk_name, sk_type),
-        )
-    } else {
-        parser.parse_sort_key_with_fallback(
-            sort_key_expression,
-            &AttributeDefinition::new(sk_name, sk_type),
-        )
+    let result = match sort_key_input {
+        SortKeyInput::Expression(exp) if strict => {
+            parser.parse_sort_key_with_suggest(exp, &sort_attr)
+        }
+        SortKeyInput::Expression(exp) => parser.parse_sort_key_with_fallback(exp, &sort_attr),
+        SortKeyInput::Values(operator, values) => {
+            parser.parse_sort_key_from_raw_values(*operator, values, &sort_attr)
+        }
     }
     .map_err(DyneinQueryParamsError::InvalidSortKeyOption)?;
 
@@ -960,16 +2489,17 @@ fn append_sort_key_expression(
 ///   thash       1582050565
 ///   tayoyo      1582000111
 ///   osaka       1583020931
-fn display_items_table(
-    items: Vec<HashMap<String, AttributeValue>>,
+pub fn display_items_table(
+    w: &mut dyn Write,
+    items: &[HashMap<String, AttributeValue>],
     ts: &app::TableSchema,
     selected_attributes: &Option<String>,
     keys_only: bool,
-) {
+    expand: &[output::ExpandedColumn],
+) -> io::Result<()> {
     // Print no item message and return if items length is 0.
     if items.is_empty() {
-        println!("No item to show in the table '{}'", ts.name);
-        return;
+        return writeln!(w, "No item to show in the table '{}'", ts.name);
     };
 
     // build header - first, primary key(s). Even index, key(s) are always projected.
@@ -978,6 +2508,8 @@ fn display_items_table(
     if let Some(sk) = &ts.sk {
         header.push(sk.name.as_str())
     };
+    // build header - then, nested fields pulled into their own columns by --expand.
+    header.extend(expand.iter().map(|col| col.name.as_str()));
 
     // build header - next, attribute names or aggregated "attributes" header, unless --keys-only flag is set.
     if !keys_only {
@@ -989,12 +2521,13 @@ fn display_items_table(
     };
     debug!("built header elements: {:?}", header);
 
-    let mut tw = TabWriter::new(io::stdout());
-    tw.write_all((header.join("\t") + "\n").as_bytes()).unwrap();
+    let mut tw = TabWriter::new(w);
+    tw.write_all((header.join("\t") + "\n").as_bytes())?;
 
     // `cells` is sth like: ["item1-pk\titem1-attr1\titem1-attr2", "item2-pk\titem2-attr1\titem2-attr2"]
     let mut cells: Vec<String> = vec![]; // may be able to use with_capacity to initialize the vec.
-    for mut item in items {
+    for item in items {
+        let mut item = item.to_owned();
         let mut item_attributes = vec![];
         // First, take primary key(s) of each item.
         let x: Option<AttributeValue> = item.remove(&ts.pk.name);
@@ -1004,6 +2537,9 @@ fn display_items_table(
         } else {
             item_attributes.extend(vec![attrval_to_cell_print(x)]);
         };
+        for col in expand {
+            item_attributes.push(attrval_to_cell_print(col.path.extract(&item).cloned()));
+        }
 
         if !item.is_empty() {
             if let Some(_attributes) = selected_attributes {
@@ -1015,21 +2551,37 @@ fn display_items_table(
             } else if !keys_only {
                 // print rest aggreated "attributes" column in JSON format.
                 let full = serde_json::to_string(&convert_to_json(&item)).unwrap();
-                let threshold: usize = 50;
-                if full.chars().count() > threshold {
-                    // NOTE: counting bytes slice doesn't work for multi-bytes strings
-                    let st: &String = &full.chars().take(threshold).collect();
-                    item_attributes.push(String::from(st) + "...");
-                } else {
-                    item_attributes.push(full);
-                }
+                item_attributes.push(truncate_to_width(full, ATTRIBUTES_COLUMN_WIDTH, ellipsis()));
             }
         }
         cells.push(item_attributes.join("\t"));
     }
 
-    tw.write_all((cells.join("\n") + "\n").as_bytes()).unwrap();
-    tw.flush().unwrap();
+    tw.write_all((cells.join("\n") + "\n").as_bytes())?;
+    tw.flush()
+}
+
+/// Returns the ellipsis appended to values truncated in table output.
+fn ellipsis() -> &'static str {
+    ELLIPSIS.get().map_or(app::DEFAULT_ELLIPSIS, String::as_str)
+}
+
+/// Truncates the string so that it fits in `max_width` columns of display width, appending the ellipsis if truncated.
+/// Wide characters like CJK and emoji take two columns, and a character is never split in the middle.
+fn truncate_to_width(s: String, max_width: usize, ellipsis: &str) -> String {
+    if s.width() <= max_width {
+        return s;
+    }
+    let mut width = 0;
+    let mut truncated: String = s
+        .chars()
+        .take_while(|c| {
+            width += c.width().unwrap_or(0);
+            width <= max_width
+        })
+        .collect();
+    truncated.push_str(ellipsis);
+    truncated
 }
 
 /// This function takes Option<AttributeValue> and return string,
@@ -1050,7 +2602,13 @@ fn attrval_to_cell_print(optional_attrval: Option<AttributeValue>) -> String {
                 )
                 .unwrap(),
                 AttributeValue::Null(_) => String::from("null"),
-                _ => String::from("(snip)"), // B, BS, L, and M are not shown.
+                // Maps and lists are shown as JSON as long as they fit in the cell.
+                AttributeValue::M(_) | AttributeValue::L(_) => truncate_to_width(
+                    attrval_to_jsonval(&attrval).to_string(),
+                    NESTED_CELL_WIDTH,
+                    ellipsis(),
+                ),
+                _ => String::from("(snip)"), // B and BS are not shown.
             }
         }
     }
@@ -1148,21 +2706,106 @@ pub fn convert_to_json(
         .collect()
 }
 
+/// A JSON object which keeps the order of its attributes as they were inserted.
+/// HashMap doesn't guarantee any iteration order, so this struct is used where stable output is needed (e.g. export).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderedItem(Vec<(String, JsonValue)>);
+
+impl Serialize for OrderedItem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (k, v) in &self.0 {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+/// Same as `convert_to_json`, but attributes are ordered deterministically:
+/// partition key first, sort key (if any) next, then the rest of attributes in alphabetical order.
+/// Nested maps are always sorted alphabetically as serde_json::Map is backed by BTreeMap.
+pub fn convert_to_ordered_json(
+    item: &HashMap<String, AttributeValue>,
+    ts: &app::TableSchema,
+) -> OrderedItem {
+    let key_rank = |name: &str| -> usize {
+        if name == ts.pk.name {
+            0
+        } else if ts.sk.as_ref().map_or(false, |sk| sk.name == name) {
+            1
+        } else {
+            2
+        }
+    };
+
+    let mut names: Vec<&String> = item.keys().collect();
+    names.sort_by(|a, b| {
+        key_rank(a.as_str())
+            .cmp(&key_rank(b.as_str()))
+            .then_with(|| a.cmp(b))
+    });
+
+    OrderedItem(
+        names
+            .into_iter()
+            .map(|name| (name.to_owned(), attrval_to_jsonval(&item[name])))
+            .collect(),
+    )
+}
+
+/// DynamoDB numbers have up to 38 significant digits, which may not fit in u64 or f64.
+/// Such numbers are converted to the nearest f64 by default, or kept as strings with --precise-numbers.
+/// A value which is not a number at all is kept as a string as well, instead of panicking on table data.
 fn str_to_json_num(s: &str) -> JsonValue {
-    match s.parse::<u64>() {
-        Ok(i) => serde_json::to_value(i).unwrap(),
-        Err(_) => match s.parse::<f64>() {
-            Ok(f) => serde_json::to_value(f).unwrap(),
-            Err(e) => panic!(
-                "Failed to parse DynamoDB 'N' typed value: {:#?}\n{:#?}",
-                s, e
-            ),
-        },
+    if let Ok(i) = s.parse::<u64>() {
+        return JsonValue::from(i);
+    }
+    match s.parse::<f64>() {
+        Ok(f) if f.is_finite() => {
+            let exact = decimal_parts(s).is_some()
+                && decimal_parts(s) == decimal_parts(&format!("{:e}", f));
+            if !exact && PRECISE_NUMBERS.load(Ordering::Relaxed) {
+                JsonValue::String(s.to_owned())
+            } else {
+                JsonValue::from(f)
+            }
+        }
+        _ => {
+            debug!("Failed to parse DynamoDB 'N' typed value: {:?}", s);
+            JsonValue::String(s.to_owned())
+        }
+    }
+}
+
+/// Decomposes a decimal number into its sign, significant digits without leading/trailing zeros, and exponent,
+/// e.g. "-12.30e2" into (true, "123", 1), so that numbers written differently can be compared.
+fn decimal_parts(s: &str) -> Option<(bool, String, i64)> {
+    let (negative, unsigned) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (mantissa, mut exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (unsigned, 0),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if !(int_part.chars().chain(frac_part.chars())).all(|c| c.is_ascii_digit()) {
+        return None;
     }
+    exponent -= frac_part.len() as i64;
+    let digits = format!("{}{}", int_part, frac_part);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Some((false, String::from("0"), 0));
+    }
+    let significant = digits.trim_end_matches('0');
+    exponent += (digits.len() - significant.len()) as i64;
+    Some((negative, significant.to_owned(), exponent))
 }
 
-fn attrval_to_jsonval(attrval: &AttributeValue) -> JsonValue {
-    let unsupported: &str = "<<<JSON output doesn't support this type attributes>>>";
+/// Binary values are represented as base64 (with padding) encoded strings, as JSON doesn't have binary type.
+/// They can be imported again as binary by passing attribute names to `dy import --binary-attributes`.
+pub fn attrval_to_jsonval(attrval: &AttributeValue) -> JsonValue {
     //  following list of if-else statements would be return value of this function.
     match attrval {
         AttributeValue::S(v) => serde_json::to_value(v).unwrap(),
@@ -1171,13 +2814,27 @@ fn attrval_to_jsonval(attrval: &AttributeValue) -> JsonValue {
         AttributeValue::Null(_) => serde_json::to_value(()).unwrap(),
         AttributeValue::Ss(v) => serde_json::to_value(v).unwrap(),
         AttributeValue::Ns(v) => v.iter().map(|v| str_to_json_num(v)).collect(),
-        AttributeValue::B(_) | AttributeValue::Bs(_) => serde_json::to_value(unsupported).unwrap(),
+        AttributeValue::B(v) => serde_json::to_value(aws_smithy_types::base64::encode(v)).unwrap(),
+        AttributeValue::Bs(v) => v.iter().map(aws_smithy_types::base64::encode).collect(),
         AttributeValue::M(v) => attrval_to_json_map(v),
         AttributeValue::L(v) => v.iter().map(attrval_to_jsonval).collect(),
-        _ => panic!(
-            "DynamoDB AttributeValue is not in valid status: {:#?}",
-            &attrval
-        ),
+        _ => JsonValue::from(unknown_attrval_placeholder(attrval)),
+    }
+}
+
+/// Returns a value picked by `dy get --field` as is, so that shell scripts can use it without jq.
+/// Documents and sets don't have such a representation, and are printed as JSON.
+fn attrval_to_raw_string(attrval: &AttributeValue) -> String {
+    // Numbers are printed as stored, as no JSON number is involved here.
+    if let AttributeValue::N(v) = attrval {
+        return v.to_owned();
+    }
+    match attrval_to_jsonval(attrval) {
+        JsonValue::String(v) => v,
+        v @ (JsonValue::Array(_) | JsonValue::Object(_)) => {
+            serde_json::to_string_pretty(&v).unwrap()
+        }
+        v => v.to_string(),
     }
 }
 
@@ -1209,35 +2866,29 @@ fn generate_scan_expressions(
     }
 
     // dynein always shows primary key(s) i.e. pk and sk (if any).
-    let mut names = HashMap::<String, String>::new();
-    names.insert(String::from("#DYNEIN_PKNAME"), ts.pk.name.clone());
-    let mut returning_attributes: Vec<String> = vec![String::from("#DYNEIN_PKNAME")];
+    let mut returning_attributes: Vec<String> = vec![quote_attr_name(&ts.pk.name)];
     if let Some(sk) = &ts.sk {
-        returning_attributes.push(String::from("#DYNEIN_SKNAME"));
-        names.insert(String::from("#DYNEIN_SKNAME"), sk.name.clone());
+        returning_attributes.push(quote_attr_name(&sk.name));
     };
 
     // if keys_only flag is true, no more attribute would be added.
-    if keys_only {
-    } else if let Some(_attributes) = attributes {
-        let mut i: usize = 0;
-        let attrs: Vec<&str> = _attributes.split(',').map(|x| x.trim()).collect();
-        for attr in attrs {
-            // skip if attributes contain primary key(s) as they're already included in the expression.
-            if attr == ts.pk.name
-                || (ts.sk.is_some() && attr == ts.sk.as_ref().unwrap().name.clone())
-            {
-                continue;
-            }
+    // Otherwise attributes can be document paths such as `address.city` or `items[0].price`.
+    // Primary key(s) in attributes are projected only once by the parser.
+    if let (false, Some(attrs)) = (keys_only, attributes) {
+        returning_attributes.push(attrs.to_owned());
+    };
 
-            let placeholder = String::from("#DYNEIN_ATTRNAME") + &i.to_string();
-            returning_attributes.push(placeholder.clone());
-            names.insert(placeholder, String::from(attr));
-            i += 1;
+    let mut parser = DyneinParser::new();
+    let result = match parser.parse_projection(&returning_attributes.join(",")) {
+        Ok(result) => result,
+        Err(e) => {
+            error!("{}\n--attributes syntax is invalid. Specify comma separated attribute names or document paths, e.g. 'name,address.city,items[0].price'.", e);
+            std::process::exit(1);
         }
     };
+    let names = result.get_names();
 
-    let expression: String = returning_attributes.join(",");
+    let expression: String = result.get_expression();
     debug!("generated ProjectionExpression: {}", &expression);
     debug!("generated ExpressionAttributeNames: {:?}", &names);
 
@@ -1250,9 +2901,94 @@ fn generate_scan_expressions(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aws_sdk_dynamodb::primitives::Blob;
     use serde_json::Value;
     use std::collections::HashMap;
 
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width(String::from("abc"), 3, "..."), "abc");
+        assert_eq!(truncate_to_width(String::from("abcd"), 3, "..."), "abc...");
+        // Each CJK character and emoji takes two columns.
+        assert_eq!(
+            truncate_to_width(String::from("東京都港区"), 6, "..."),
+            "東京都..."
+        );
+        // A wide character which would exceed the width is left out instead of being split.
+        assert_eq!(truncate_to_width(String::from("a東京都"), 4, "…"), "a東…");
+        assert_eq!(truncate_to_width(String::from("🍣🍣"), 4, "..."), "🍣🍣");
+        assert_eq!(truncate_to_width(String::from("🍣🍺x"), 3, ""), "🍣");
+    }
+
+    #[test]
+    fn test_str_to_json_num() {
+        assert_eq!(str_to_json_num("42"), serde_json::json!(42));
+        assert_eq!(str_to_json_num("-1.5"), serde_json::json!(-1.5));
+        assert_eq!(str_to_json_num("1E+3"), serde_json::json!(1000.0));
+        assert_eq!(
+            decimal_parts("-12.30e2"),
+            Some((true, String::from("123"), 1))
+        );
+        assert_eq!(decimal_parts("0.000"), Some((false, String::from("0"), 0)));
+
+        // 38 digits can't be represented by f64.
+        let big = "12345678901234567890123456789012345678";
+        assert!(str_to_json_num(big).is_f64());
+        assert_eq!(
+            str_to_json_num("not a number"),
+            serde_json::json!("not a number")
+        );
+
+        enable_precise_numbers();
+        assert_eq!(str_to_json_num(big), serde_json::json!(big));
+        assert_eq!(
+            str_to_json_num("0.12345678901234567890"),
+            serde_json::json!("0.12345678901234567890")
+        );
+        assert_eq!(str_to_json_num("0.1"), serde_json::json!(0.1));
+        assert_eq!(str_to_json_num("1e100"), serde_json::json!(1e100));
+    }
+
+    #[test]
+    fn test_estimate_read_capacity() {
+        assert_eq!(estimate_read_capacity(0, true), 0.0);
+        assert_eq!(estimate_read_capacity(1, true), 1.0);
+        assert_eq!(estimate_read_capacity(4096, false), 0.5);
+        assert_eq!(estimate_read_capacity(4097, false), 1.0);
+        assert_eq!(estimate_read_capacity(10 * 4096, true), 10.0);
+    }
+
+    #[test]
+    fn test_build_atomic_counter_expression() {
+        let counter = |step: Option<&str>, init: Option<&str>| CounterParams {
+            target_attrs: vec!["visits".to_owned()],
+            step: step.map(String::from),
+            init: init.map(String::from),
+        };
+        assert_eq!(
+            build_atomic_counter_expression(&counter(None, None)),
+            "visits = if_not_exists(visits, 0) + 1"
+        );
+        assert_eq!(
+            build_atomic_counter_expression(&counter(Some("-2"), Some("100"))),
+            "visits = if_not_exists(visits, 100) - 2"
+        );
+        assert_eq!(
+            build_atomic_counter_expression(&counter(Some("+0.5"), Some("-1"))),
+            "visits = if_not_exists(visits, -1) + 0.5"
+        );
+
+        let counters = CounterParams {
+            target_attrs: vec!["pv".to_owned(), " stats.views".to_owned()],
+            step: None,
+            init: None,
+        };
+        assert_eq!(
+            build_atomic_counter_expression(&counters),
+            "pv = if_not_exists(pv, 0) + 1, stats.views = if_not_exists(stats.views, 0) + 1"
+        );
+    }
+
     #[test]
     fn test_generate_update_expressions_set_int() {
         let actual = generate_update_expressions(UpdateActionType::Set, "Price = 123");
@@ -1705,4 +3441,200 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_attrval_to_jsonval_binary() {
+        assert_eq!(
+            attrval_to_jsonval(&AttributeValue::B(Blob::new(vec![0x00, 0x01, 0xff]))),
+            JsonValue::String("AAH/".to_owned())
+        );
+        assert_eq!(
+            attrval_to_jsonval(&AttributeValue::Bs(vec![
+                Blob::new("abc".as_bytes()),
+                Blob::new(Vec::<u8>::new()),
+            ])),
+            serde_json::json!(["YWJj", ""])
+        );
+    }
+
+    #[test]
+    fn test_convert_to_ordered_json() {
+        let ts = app::TableSchema {
+            region: "local".to_owned(),
+            name: "tbl".to_owned(),
+            pk: key::Key {
+                name: "pk".to_owned(),
+                kind: key::KeyType::S,
+            },
+            sk: Some(key::Key {
+                name: "sk".to_owned(),
+                kind: key::KeyType::N,
+            }),
+            indexes: None,
+            mode: crate::ddb::table::Mode::OnDemand,
+            ttl: None,
+        };
+        let item = HashMap::from([
+            ("c".to_owned(), AttributeValue::Bool(true)),
+            ("sk".to_owned(), AttributeValue::N("1".to_owned())),
+            (
+                "a".to_owned(),
+                AttributeValue::M(HashMap::from([
+                    ("y".to_owned(), AttributeValue::N("2".to_owned())),
+                    ("x".to_owned(), AttributeValue::N("1".to_owned())),
+                ])),
+            ),
+            ("pk".to_owned(), AttributeValue::S("abc".to_owned())),
+            ("B".to_owned(), AttributeValue::Null(true)),
+        ]);
+        let actual = serde_json::to_string(&convert_to_ordered_json(&item, &ts)).unwrap();
+        assert_eq!(
+            actual,
+            r#"{"pk":"abc","sk":1,"B":null,"a":{"x":1,"y":2},"c":true}"#
+        );
+    }
+
+    #[test]
+    fn test_find_gsi_keyed_on() {
+        let new_key = |name: &str, kind: key::KeyType| key::Key {
+            name: name.to_owned(),
+            kind,
+        };
+        let new_index = |name: &str, kind: app::IndexType, pk: key::Key| app::IndexSchema {
+            name: name.to_owned(),
+            kind,
+            pk,
+            sk: None,
+        };
+        let mut ts = app::TableSchema {
+            region: "local".to_owned(),
+            name: "tbl".to_owned(),
+            pk: new_key("pk", key::KeyType::S),
+            sk: Some(new_key("sk", key::KeyType::S)),
+            indexes: None,
+            mode: crate::ddb::table::Mode::OnDemand,
+            ttl: None,
+        };
+        let sk = new_key("sk", key::KeyType::S);
+        assert_eq!(find_gsi_keyed_on(&ts, &sk), None);
+
+        ts.indexes = Some(vec![
+            new_index("lsi", app::IndexType::Lsi, new_key("pk", key::KeyType::S)),
+            new_index(
+                "numeric",
+                app::IndexType::Gsi,
+                new_key("sk", key::KeyType::N),
+            ),
+            new_index(
+                "other",
+                app::IndexType::Gsi,
+                new_key("name", key::KeyType::S),
+            ),
+            new_index("by-sk", app::IndexType::Gsi, new_key("sk", key::KeyType::S)),
+        ]);
+        assert_eq!(find_gsi_keyed_on(&ts, &sk), Some("by-sk"));
+        assert!(is_gsi(&ts, "by-sk"));
+        assert!(!is_gsi(&ts, "lsi"));
+        assert!(!is_gsi(&ts, "unknown"));
+    }
+
+    #[test]
+    fn test_humanize_duration() {
+        assert_eq!(humanize_duration(0), "0s");
+        assert_eq!(humanize_duration(42), "42s");
+        assert_eq!(humanize_duration(12 * 60 + 5), "12m 5s");
+        assert_eq!(humanize_duration(3600), "1h");
+        assert_eq!(humanize_duration(3 * 86400 + 4 * 3600 + 59), "3d 4h");
+        assert_eq!(humanize_duration(3 * 86400 + 5 * 60), "3d");
+    }
+
+    #[test]
+    fn test_ttl_annotation() {
+        let now = 1_600_000_000;
+        let item = |attrval: AttributeValue| HashMap::from([(String::from("expires_at"), attrval)]);
+        assert_eq!(
+            ttl_annotation(
+                "expires_at",
+                &item(AttributeValue::N(String::from("1600273600"))),
+                now
+            ),
+            "# TTL (expires_at): expires in 3d 4h (2020-09-16T16:26:40+00:00)"
+        );
+        assert_eq!(
+            ttl_annotation(
+                "expires_at",
+                &item(AttributeValue::N(String::from("1599996400"))),
+                now
+            ),
+            "# TTL (expires_at): expired 1h ago (2020-09-13T11:26:40+00:00), waiting for deletion by TTL"
+        );
+        assert_eq!(
+            ttl_annotation("expires_at", &item(AttributeValue::N(String::from("0"))), now),
+            "# TTL (expires_at): expired 18518d 12h ago (1970-01-01T00:00:00+00:00), more than five years ago so TTL never deletes it"
+        );
+        assert_eq!(
+            ttl_annotation(
+                "expires_at",
+                &item(AttributeValue::S(String::from("tomorrow"))),
+                now
+            ),
+            "# TTL (expires_at): not a number, the item never expires"
+        );
+        assert_eq!(
+            ttl_annotation("ttl", &item(AttributeValue::Null(true)), now),
+            "# TTL (ttl): not set, the item never expires"
+        );
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        let mut ts = app::TableSchema {
+            region: "local".to_owned(),
+            name: "tbl".to_owned(),
+            pk: key::Key {
+                name: "pk".to_owned(),
+                kind: key::KeyType::S,
+            },
+            sk: Some(key::Key {
+                name: "sk".to_owned(),
+                kind: key::KeyType::N,
+            }),
+            indexes: None,
+            mode: crate::ddb::table::Mode::OnDemand,
+            ttl: None,
+        };
+        let expected = |pval: &str, sval: &str| {
+            HashMap::from([
+                ("pk".to_owned(), AttributeValue::S(pval.to_owned())),
+                ("sk".to_owned(), AttributeValue::N(sval.to_owned())),
+            ])
+        };
+        let content = "# exported from logs\nabc,1\n\n abc , 2\r\n\"a,\"\"b\"\"\",3\nabc,1\n";
+        assert_eq!(
+            parse_keys(&ts, content),
+            Ok(vec![
+                expected("abc", "1"),
+                expected("abc", "2"),
+                expected("a,\"b\"", "3")
+            ])
+        );
+        assert_eq!(
+            parse_keys(&ts, "abc,1\nabc\n"),
+            Err("Line 2 has no sort key value, while the table 'tbl' has a sort key. Write \"pk,sk\" in each line".to_owned())
+        );
+        assert_eq!(
+            parse_keys(&ts, "abc,x"),
+            Err("Line 1 has 'x' for the key 'sk', which should be a number".to_owned())
+        );
+
+        // Without sort key, the whole line is a partition key value.
+        ts.sk = None;
+        assert_eq!(
+            parse_keys(&ts, "a,b\nc\n"),
+            Ok(vec![
+                HashMap::from([("pk".to_owned(), AttributeValue::S("a,b".to_owned()))]),
+                HashMap::from([("pk".to_owned(), AttributeValue::S("c".to_owned()))]),
+            ])
+        );
+    }
 }
@@ -15,10 +15,13 @@
  */
 
 use ::serde::{Deserialize, Serialize};
-use aws_sdk_dynamodb::types::{AttributeDefinition, KeySchemaElement, TableDescription};
-use std::str::FromStr;
+use aws_sdk_dynamodb::types::{
+    AttributeDefinition, AttributeValue, KeySchemaElement, TableDescription,
+};
+use std::{collections::HashMap, str::FromStr};
+use thiserror::Error;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Key {
     pub name: String,
     /// Data type of the primary key. i.e. "S" (String), "N" (Number), or "B" (Binary).
@@ -95,6 +98,72 @@ impl FromStr for KeyType {
     }
 }
 
+/// Reasons why an item can't be written to a table, detected before calling DynamoDB APIs.
+#[derive(Error, Debug, PartialEq)]
+pub enum InvalidKeyError {
+    #[error("must provide the {role} key attribute {name}")]
+    Missing { role: &'static str, name: String },
+    #[error("type mismatch for the key {name}, expected: {expected}")]
+    TypeMismatch {
+        name: String,
+        expected: &'static str,
+    },
+    #[error("the key {name} must not be an empty {kind}")]
+    Empty { name: String, kind: &'static str },
+}
+
+/// Validates that the item has the partition key (and sort key if any) of the expected types.
+/// DynamoDB also rejects empty strings and binaries as key values, so they're reported here as well.
+/// Used by put, bwrite and import so that invalid items are reported precisely without an API round-trip.
+pub fn validate_item_keys(
+    item: &HashMap<String, AttributeValue>,
+    pk: &Key,
+    sk: Option<&Key>,
+) -> Result<(), InvalidKeyError> {
+    validate_key(item, pk, "partition")?;
+    if let Some(sk) = sk {
+        validate_key(item, sk, "sort")?;
+    }
+    Ok(())
+}
+
+fn validate_key(
+    item: &HashMap<String, AttributeValue>,
+    key: &Key,
+    role: &'static str,
+) -> Result<(), InvalidKeyError> {
+    let attrval = item
+        .get(&key.name)
+        .ok_or_else(|| InvalidKeyError::Missing {
+            role,
+            name: key.name.to_owned(),
+        })?;
+    let name = key.name.to_owned();
+    match (&key.kind, attrval) {
+        (KeyType::S, AttributeValue::S(s)) if s.is_empty() => Err(InvalidKeyError::Empty {
+            name,
+            kind: "string",
+        }),
+        (KeyType::B, AttributeValue::B(b)) if b.as_ref().is_empty() => {
+            Err(InvalidKeyError::Empty {
+                name,
+                kind: "binary",
+            })
+        }
+        (KeyType::S, AttributeValue::S(_))
+        | (KeyType::N, AttributeValue::N(_))
+        | (KeyType::B, AttributeValue::B(_)) => Ok(()),
+        (kind, _) => Err(InvalidKeyError::TypeMismatch {
+            name,
+            expected: match kind {
+                KeyType::S => "String",
+                KeyType::N => "Number",
+                KeyType::B => "Binary",
+            },
+        }),
+    }
+}
+
 /// returns Option of a tuple (attribute_name, attribute_type (S/N/B)).
 /// Used when you want to know "what is the Partition Key name and its data type of this table".
 pub fn typed_key(pk_or_sk: &str, desc: &TableDescription) -> Option<Key> {
@@ -126,3 +195,66 @@ pub fn typed_key_for_schema(
         .unwrap(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::primitives::Blob;
+
+    #[test]
+    fn test_validate_item_keys() {
+        let pk = Key {
+            name: String::from("pk"),
+            kind: KeyType::S,
+        };
+        let sk = Key {
+            name: String::from("sk"),
+            kind: KeyType::N,
+        };
+        let item = |pk: AttributeValue, sk: Option<AttributeValue>| {
+            let mut item = HashMap::from([(String::from("pk"), pk)]);
+            if let Some(sk) = sk {
+                item.insert(String::from("sk"), sk);
+            }
+            item
+        };
+        let s = |v: &str| AttributeValue::S(String::from(v));
+        let n = |v: &str| AttributeValue::N(String::from(v));
+
+        assert_eq!(
+            validate_item_keys(&item(s("a"), Some(n("1"))), &pk, Some(&sk)),
+            Ok(())
+        );
+        assert_eq!(
+            validate_item_keys(&item(s("a"), None), &pk, Some(&sk)),
+            Err(InvalidKeyError::Missing {
+                role: "sort",
+                name: String::from("sk")
+            })
+        );
+        assert_eq!(
+            validate_item_keys(&item(n("1"), None), &pk, None),
+            Err(InvalidKeyError::TypeMismatch {
+                name: String::from("pk"),
+                expected: "String"
+            })
+        );
+        assert_eq!(
+            validate_item_keys(&item(s(""), None), &pk, None),
+            Err(InvalidKeyError::Empty {
+                name: String::from("pk"),
+                kind: "string"
+            })
+        );
+        let bk = Key {
+            name: String::from("pk"),
+            kind: KeyType::B,
+        };
+        assert_eq!(
+            validate_item_keys(&item(AttributeValue::B(Blob::new(vec![])), None), &bk, None)
+                .unwrap_err()
+                .to_string(),
+            "the key pk must not be an empty binary"
+        );
+    }
+}
@@ -15,4 +15,5 @@
  */
 
 pub mod key;
+pub mod size;
 pub mod table;
@@ -0,0 +1,159 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// Client-side estimation of item sizes, following the rules in
+// https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/CapacityUnitCalculations.html
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Maximum size of an item in DynamoDB, including attribute names.
+pub const MAX_ITEM_SIZE: usize = 400 * 1024;
+
+/// A write capacity unit covers a write of an item up to 1 KB.
+const WRITE_CAPACITY_UNIT_SIZE: usize = 1024;
+
+/// Number of the largest attributes shown when an item is too large.
+const LARGEST_ATTRIBUTES_TO_SHOW: usize = 5;
+
+#[derive(Error, Debug, PartialEq)]
+#[error("the item is {size} bytes, which exceeds the limit of {limit} bytes. Largest attributes: {largest}", limit = MAX_ITEM_SIZE)]
+pub struct ItemTooLargeError {
+    pub size: usize,
+    /// e.g. "body (398012 bytes), title (120 bytes)"
+    pub largest: String,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Returns the estimated size of the item in bytes, i.e. the sum of lengths of attribute names and sizes of values.
+pub fn item_size(item: &HashMap<String, AttributeValue>) -> usize {
+    item.iter()
+        .map(|(name, attrval)| name.len() + value_size(attrval))
+        .sum()
+}
+
+/// Returns the estimated number of write capacity units consumed by writing the item, i.e. its size rounded up to the next 1 KB.
+pub fn write_capacity_units(item: &HashMap<String, AttributeValue>) -> usize {
+    item_size(item).div_ceil(WRITE_CAPACITY_UNIT_SIZE).max(1)
+}
+
+/// Returns an error with the largest attributes if the estimated size of the item exceeds 400 KB.
+pub fn check_item_size(item: &HashMap<String, AttributeValue>) -> Result<(), ItemTooLargeError> {
+    let size = item_size(item);
+    if size <= MAX_ITEM_SIZE {
+        return Ok(());
+    }
+
+    let mut sizes: Vec<(&String, usize)> = item
+        .iter()
+        .map(|(name, attrval)| (name, name.len() + value_size(attrval)))
+        .collect();
+    sizes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    let largest = sizes
+        .iter()
+        .take(LARGEST_ATTRIBUTES_TO_SHOW)
+        .map(|(name, size)| format!("{} ({} bytes)", name, size))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(ItemTooLargeError { size, largest })
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+fn value_size(attrval: &AttributeValue) -> usize {
+    match attrval {
+        AttributeValue::S(s) => s.len(),
+        AttributeValue::N(n) => number_size(n),
+        AttributeValue::B(b) => b.as_ref().len(),
+        AttributeValue::Ss(ss) => ss.iter().map(|s| s.len()).sum(),
+        AttributeValue::Ns(ns) => ns.iter().map(|n| number_size(n)).sum(),
+        AttributeValue::Bs(bs) => bs.iter().map(|b| b.as_ref().len()).sum(),
+        AttributeValue::Bool(_) | AttributeValue::Null(_) => 1,
+        // A list or map has 3 bytes of overhead, and 1 byte of overhead per element.
+        AttributeValue::L(l) => 3 + l.iter().map(|v| 1 + value_size(v)).sum::<usize>(),
+        AttributeValue::M(m) => {
+            3 + m
+                .iter()
+                .map(|(name, v)| 1 + name.len() + value_size(v))
+                .sum::<usize>()
+        }
+        _ => 0,
+    }
+}
+
+/// A number takes 1 byte per two significant digits, plus 1 byte. Leading and trailing zeros are not significant.
+fn number_size(n: &str) -> usize {
+    let mantissa = n.split(['e', 'E']).next().unwrap_or_default();
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+    let significant = digits.trim_start_matches('0').trim_end_matches('0').len();
+    (significant + 1) / 2 + 1
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_size() {
+        let item = HashMap::from([
+            (String::from("pk"), AttributeValue::S(String::from("abc"))),
+            (
+                String::from("n"),
+                AttributeValue::N(String::from("-12.300")),
+            ),
+            (
+                String::from("l"),
+                AttributeValue::L(vec![AttributeValue::Bool(true), AttributeValue::Null(true)]),
+            ),
+        ]);
+        // pk: 2 + 3, n: 1 + 3 ("123"), l: 1 + 3 + 2 * (1 + 1)
+        assert_eq!(item_size(&item), 5 + 4 + 8);
+        assert_eq!(write_capacity_units(&item), 1);
+        assert_eq!(check_item_size(&item), Ok(()));
+    }
+
+    #[test]
+    fn test_check_item_size() {
+        let item = HashMap::from([
+            (String::from("pk"), AttributeValue::S(String::from("a"))),
+            (
+                String::from("body"),
+                AttributeValue::S("x".repeat(MAX_ITEM_SIZE)),
+            ),
+        ]);
+        assert_eq!(
+            check_item_size(&item),
+            Err(ItemTooLargeError {
+                size: MAX_ITEM_SIZE + 7,
+                largest: format!("body ({} bytes), pk (3 bytes)", MAX_ITEM_SIZE + 4),
+            })
+        );
+        assert_eq!(write_capacity_units(&item), 401);
+    }
+}
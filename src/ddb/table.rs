@@ -17,11 +17,13 @@
 use ::serde::{Deserialize, Serialize};
 use aws_sdk_dynamodb::types::{
     AttributeDefinition, BillingMode, BillingModeSummary, GlobalSecondaryIndexDescription,
-    KeySchemaElement, KeyType, LocalSecondaryIndexDescription, ProvisionedThroughputDescription,
-    ScalarAttributeType, StreamSpecification, TableDescription,
+    KeySchemaElement, KeyType, LocalSecondaryIndexDescription, OnDemandThroughput, Projection,
+    ProjectionType, ProvisionedThroughputDescription, ScalarAttributeType, SseDescription,
+    SseStatus, StreamSpecification, TableClass, TableClassSummary, TableDescription,
 };
 use chrono::DateTime;
 use log::error;
+use std::collections::BTreeMap;
 
 use crate::ddb::key;
 
@@ -38,18 +40,31 @@ struct PrintDescribeTable {
     schema: PrintPrimaryKeys,
 
     mode: Mode,
+    class: String,
     capacity: Option<PrintCapacityUnits>,
+    max_request_units: Option<PrintMaxRequestUnits>,
 
     gsi: Option<Vec<PrintSecondaryIndex>>,
     lsi: Option<Vec<PrintSecondaryIndex>>,
 
     stream: Option<String>,
+    encryption: String,
 
     count: i64,
     size_bytes: i64,
     created_at: String,
 }
 
+/// Fields of `dy desc` which are left out of settings of a table, see `table_settings`.
+const VOLATILE_FIELDS: [&str; 6] = [
+    "name",
+    "region",
+    "status",
+    "count",
+    "size_bytes",
+    "created_at",
+];
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum Mode {
     Provisioned,
@@ -77,20 +92,167 @@ struct PrintCapacityUnits {
     rcu: i64,
 }
 
+/// Maximum throughput of an OnDemand table. Missing values mean no limit is configured.
+#[derive(Serialize, Deserialize, Debug)]
+struct PrintMaxRequestUnits {
+    wru: Option<i64>,
+    rru: Option<i64>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct PrintSecondaryIndex {
     name: String,
     schema: PrintPrimaryKeys,
     capacity: Option<PrintCapacityUnits>,
+    projection: String,
+}
+
+/// A secondary index printed by `dy desc --index`. LSIs don't have their own status, so it's null for them.
+#[derive(Serialize, Deserialize, Debug)]
+struct PrintIndexDescription {
+    name: String,
+    #[serde(rename = "type")]
+    index_type: String,
+    status: Option<String>,
+    schema: PrintPrimaryKeys,
+    capacity: Option<PrintCapacityUnits>,
+    projection: String,
+}
+
+/// The stream of a table printed by `dy desc --streams`. ARN and label are kept after the stream is disabled,
+/// as DynamoDB Streams still serves records written within 24 hours.
+#[derive(Serialize, Deserialize, Debug)]
+struct PrintStreamDescription {
+    enabled: bool,
+    arn: Option<String>,
+    label: Option<String>,
+    view_type: Option<String>,
 }
 
 /// Receives region (just to show in one line for reference) and TableDescription,
 /// print them in readable YAML format. NOTE: '~' representes 'null' or 'no value' in YAML syntax.
 pub fn print_table_description(region: &str, desc: &TableDescription) {
+    println!(
+        "{}",
+        serde_yaml::to_string(&build_print_table(region, desc)).unwrap()
+    );
+}
+
+/// Returns the same fields as `print_table_description`, so that they can be output in other formats.
+pub fn table_description(region: &str, desc: &TableDescription) -> serde_yaml::Value {
+    serde_yaml::to_value(build_print_table(region, desc)).unwrap()
+}
+
+/// Returns descriptions of tables as a single document, which maps table names to descriptions.
+pub fn table_descriptions(region: &str, descs: &[TableDescription]) -> serde_yaml::Value {
+    let tables: BTreeMap<String, PrintDescribeTable> = descs
+        .iter()
+        .map(|desc| {
+            let table = build_print_table(region, desc);
+            (table.name.to_owned(), table)
+        })
+        .collect();
+    serde_yaml::to_value(tables).unwrap()
+}
+
+/// Returns settings of the table recorded by `dy admin snapshot`, i.e. fields of `dy desc` except for ones which
+/// identify the table or change without any operation (e.g. item count). Stream is its view type, as ARNs differ across environments.
+pub fn table_settings(desc: &TableDescription) -> serde_yaml::Value {
+    let mut value = serde_yaml::to_value(build_print_table("", desc)).unwrap();
+    if let Some(mapping) = value.as_mapping_mut() {
+        for field in VOLATILE_FIELDS {
+            mapping.remove(field);
+        }
+        let stream: Option<String> = desc
+            .stream_specification
+            .as_ref()
+            .filter(|spec| spec.stream_enabled)
+            .and_then(|spec| spec.stream_view_type.as_ref())
+            .map(|view_type| view_type.as_str().to_owned());
+        mapping.insert(
+            serde_yaml::Value::from("stream"),
+            serde_yaml::to_value(stream).unwrap(),
+        );
+    }
+    value
+}
+
+/// Returns the schema, capacity and status of a GSI or LSI of the table, or None if the table has no index of the name.
+pub fn index_description(desc: &TableDescription, index_name: &str) -> Option<serde_yaml::Value> {
+    let mode = extract_mode(&desc.billing_mode_summary);
+    let attr_defs = desc.attribute_definitions.as_deref().unwrap_or_default();
+    let gsi = desc
+        .global_secondary_indexes
+        .iter()
+        .flatten()
+        .find(|idx| idx.index_name.as_deref() == Some(index_name))
+        .map(|idx| {
+            let status = idx.index_status.as_ref().map(|s| String::from(s.as_str()));
+            (
+                String::from("GSI"),
+                status,
+                build_print_index(&mode, attr_defs, idx),
+            )
+        });
+    let lsi = || {
+        desc.local_secondary_indexes
+            .iter()
+            .flatten()
+            .find(|idx| idx.index_name.as_deref() == Some(index_name))
+            .map(|idx| {
+                (
+                    String::from("LSI"),
+                    None,
+                    build_print_index(&mode, attr_defs, idx),
+                )
+            })
+    };
+    let (index_type, status, idx) = gsi.or_else(lsi)?;
+    let print_index = PrintIndexDescription {
+        name: idx.name,
+        index_type,
+        status,
+        schema: idx.schema,
+        capacity: idx.capacity,
+        projection: idx.projection,
+    };
+    Some(serde_yaml::to_value(print_index).unwrap())
+}
+
+/// Returns names of all GSIs and LSIs of the table, e.g. to suggest them when an unknown index is given.
+pub fn index_names(desc: &TableDescription) -> Vec<String> {
+    let gsi_names = desc
+        .global_secondary_indexes
+        .iter()
+        .flatten()
+        .filter_map(|idx| idx.index_name.clone());
+    let lsi_names = desc
+        .local_secondary_indexes
+        .iter()
+        .flatten()
+        .filter_map(|idx| idx.index_name.clone());
+    gsi_names.chain(lsi_names).collect()
+}
+
+/// Returns ARN, label and view type of the latest stream of the table.
+pub fn stream_description(desc: &TableDescription) -> serde_yaml::Value {
+    let spec = desc.stream_specification.as_ref();
+    let print_stream = PrintStreamDescription {
+        enabled: spec.map(|spec| spec.stream_enabled).unwrap_or(false),
+        arn: desc.latest_stream_arn.clone(),
+        label: desc.latest_stream_label.clone(),
+        view_type: spec
+            .and_then(|spec| spec.stream_view_type.as_ref())
+            .map(|view_type| String::from(view_type.as_str())),
+    };
+    serde_yaml::to_value(print_stream).unwrap()
+}
+
+fn build_print_table(region: &str, desc: &TableDescription) -> PrintDescribeTable {
     let attr_defs = desc.attribute_definitions.as_ref().unwrap();
     let mode = extract_mode(&desc.billing_mode_summary);
 
-    let print_table: PrintDescribeTable = PrintDescribeTable {
+    PrintDescribeTable {
         name: String::from(desc.table_name.as_ref().unwrap()),
         region: String::from(region),
         status: String::from(desc.table_status.as_ref().unwrap().as_str()),
@@ -102,17 +264,56 @@ pub fn print_table_description(region: &str, desc: &TableDescription) {
         },
 
         mode: mode.clone(),
+        class: String::from(extract_table_class(&desc.table_class_summary).as_str()),
         capacity: extract_capacity(&mode, &desc.provisioned_throughput),
+        max_request_units: extract_max_request_units(&mode, &desc.on_demand_throughput),
 
         gsi: extract_secondary_indexes(&mode, attr_defs, &desc.global_secondary_indexes),
         lsi: extract_secondary_indexes(&mode, attr_defs, &desc.local_secondary_indexes),
         stream: extract_stream(&desc.latest_stream_arn, &desc.stream_specification),
+        encryption: extract_encryption(&desc.sse_description),
 
         size_bytes: desc.table_size_bytes.unwrap(),
         count: desc.item_count.unwrap(),
         created_at: epoch_to_rfc3339(desc.creation_date_time.unwrap().as_secs_f64()),
-    };
-    println!("{}", serde_yaml::to_string(&print_table).unwrap());
+    }
+}
+
+/// Pushes scalar values in the YAML value with their paths, e.g. "gsi[0].name". Mappings keep the order of fields.
+pub fn flatten_yaml_value(
+    path: String,
+    value: &serde_yaml::Value,
+    fields: &mut Vec<(String, String)>,
+) {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            for (k, v) in mapping {
+                let key = k
+                    .as_str()
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| format!("{:?}", k));
+                let child = if path.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                flatten_yaml_value(child, v, fields);
+            }
+        }
+        serde_yaml::Value::Sequence(sequence) => {
+            for (i, v) in sequence.iter().enumerate() {
+                flatten_yaml_value(format!("{}[{}]", path, i), v, fields);
+            }
+        }
+        serde_yaml::Value::Null => fields.push((path, String::new())),
+        serde_yaml::Value::String(s) => fields.push((path, s.to_owned())),
+        other => fields.push((
+            path,
+            serde_yaml::to_string(other)
+                .map(|s| s.trim_end().to_owned())
+                .unwrap_or_default(),
+        )),
+    }
 }
 
 /// Using Vec of String which is passed via command line,
@@ -177,6 +378,14 @@ pub fn extract_mode(bs: &Option<BillingModeSummary>) -> Mode {
     }
 }
 
+/// Map "TableClassSummary" field in table description into table class.
+/// The field is omitted for tables that have never changed the class, which means STANDARD.
+pub fn extract_table_class(cs: &Option<TableClassSummary>) -> TableClass {
+    cs.as_ref()
+        .and_then(|x| x.table_class.clone())
+        .unwrap_or(TableClass::Standard)
+}
+
 // FYI: https://grammarist.com/usage/indexes-indices/
 fn extract_secondary_indexes<T: IndexDesc>(
     mode: &Mode,
@@ -186,18 +395,7 @@ fn extract_secondary_indexes<T: IndexDesc>(
     if let Some(indexes) = option_indexes {
         let mut xs = Vec::<PrintSecondaryIndex>::new();
         for idx in indexes {
-            let ks = &idx.retrieve_key_schema().as_ref().unwrap();
-            let idx = PrintSecondaryIndex {
-                name: String::from(idx.retrieve_index_name().as_ref().unwrap()),
-                schema: PrintPrimaryKeys {
-                    pk: key::typed_key_for_schema("HASH", ks, attr_defs)
-                        .expect("pk should exist")
-                        .display(),
-                    sk: key::typed_key_for_schema("RANGE", ks, attr_defs).map(|k| k.display()),
-                },
-                capacity: idx.extract_index_capacity(mode),
-            };
-            xs.push(idx);
+            xs.push(build_print_index(mode, attr_defs, idx));
         }
         Some(xs)
     } else {
@@ -205,6 +403,76 @@ fn extract_secondary_indexes<T: IndexDesc>(
     }
 }
 
+fn build_print_index<T: IndexDesc>(
+    mode: &Mode,
+    attr_defs: &[AttributeDefinition],
+    idx: &T,
+) -> PrintSecondaryIndex {
+    let ks = &idx.retrieve_key_schema().as_ref().unwrap();
+    PrintSecondaryIndex {
+        name: String::from(idx.retrieve_index_name().as_ref().unwrap()),
+        schema: PrintPrimaryKeys {
+            pk: key::typed_key_for_schema("HASH", ks, attr_defs)
+                .expect("pk should exist")
+                .display(),
+            sk: key::typed_key_for_schema("RANGE", ks, attr_defs).map(|k| k.display()),
+        },
+        capacity: idx.extract_index_capacity(mode),
+        projection: display_projection(idx.retrieve_projection()),
+    }
+}
+
+/// Shows projection of an index in one line, e.g. "ALL", "KEYS_ONLY" or "INCLUDE (attr1, attr2)".
+fn display_projection(projection: &Option<Projection>) -> String {
+    match projection {
+        None => String::from("ALL"),
+        Some(p) => match &p.projection_type {
+            Some(ProjectionType::Include) => format!(
+                "INCLUDE ({})",
+                p.non_key_attributes
+                    .as_deref()
+                    .unwrap_or_default()
+                    .join(", ")
+            ),
+            Some(t) => String::from(t.as_str()),
+            None => String::from("ALL"),
+        },
+    }
+}
+
+/// Parses the value of --projection option: "all", "keys-only" or "include:attr1,attr2".
+pub fn generate_projection(given: &str) -> Result<Projection, String> {
+    match given.split_once(':') {
+        None if given == "all" => Ok(Projection::builder()
+            .projection_type(ProjectionType::All)
+            .build()),
+        None if given == "keys-only" => Ok(Projection::builder()
+            .projection_type(ProjectionType::KeysOnly)
+            .build()),
+        Some(("include", attrs)) => {
+            let attrs: Vec<String> = attrs
+                .split(',')
+                .map(|a| a.trim())
+                .filter(|a| !a.is_empty())
+                .map(String::from)
+                .collect();
+            if attrs.is_empty() {
+                return Err(String::from(
+                    "--projection include requires attribute names, e.g. 'include:attr1,attr2'",
+                ));
+            }
+            Ok(Projection::builder()
+                .projection_type(ProjectionType::Include)
+                .set_non_key_attributes(Some(attrs))
+                .build())
+        }
+        _ => Err(format!(
+            "Invalid value for --projection option: '{}'. Valid values are 'all', 'keys-only' or 'include:attr1,attr2'",
+            given
+        )),
+    }
+}
+
 fn extract_stream(arn: &Option<String>, spec: &Option<StreamSpecification>) -> Option<String> {
     match arn {
         None => None,
@@ -221,6 +489,23 @@ fn extract_stream(arn: &Option<String>, spec: &Option<StreamSpecification>) -> O
     }
 }
 
+/// Describes the key used for encryption at rest, e.g. "AWS owned key" (default) or "KMS arn:aws:kms:...".
+/// While the table is being re-encrypted with another key, the status such as "UPDATING" follows in parentheses.
+fn extract_encryption(sse: &Option<SseDescription>) -> String {
+    let sse = match sse {
+        Some(sse) if sse.status != Some(SseStatus::Disabled) => sse,
+        _ => return String::from("AWS owned key"),
+    };
+    let key = match &sse.kms_master_key_arn {
+        Some(arn) => format!("KMS {}", arn),
+        None => String::from("KMS"),
+    };
+    match &sse.status {
+        Some(SseStatus::Enabled) | None => key,
+        Some(status) => format!("{} ({})", key, status.as_str()),
+    }
+}
+
 pub fn epoch_to_rfc3339(epoch: f64) -> String {
     let utc_datetime = DateTime::from_timestamp(epoch as i64, 0).unwrap();
     utc_datetime.to_rfc3339()
@@ -241,9 +526,27 @@ fn extract_capacity(
     }
 }
 
+fn extract_max_request_units(
+    mode: &Mode,
+    odt: &Option<OnDemandThroughput>,
+) -> Option<PrintMaxRequestUnits> {
+    match (mode, odt) {
+        (Mode::OnDemand, Some(odt))
+            if odt.max_write_request_units.is_some() || odt.max_read_request_units.is_some() =>
+        {
+            Some(PrintMaxRequestUnits {
+                wru: odt.max_write_request_units,
+                rru: odt.max_read_request_units,
+            })
+        }
+        _ => None,
+    }
+}
+
 trait IndexDesc {
     fn retrieve_index_name(&self) -> &Option<String>;
     fn retrieve_key_schema(&self) -> &Option<Vec<KeySchemaElement>>;
+    fn retrieve_projection(&self) -> &Option<Projection>;
     fn extract_index_capacity(&self, m: &Mode) -> Option<PrintCapacityUnits>;
 }
 
@@ -254,6 +557,9 @@ impl IndexDesc for GlobalSecondaryIndexDescription {
     fn retrieve_key_schema(&self) -> &Option<Vec<KeySchemaElement>> {
         &self.key_schema
     }
+    fn retrieve_projection(&self) -> &Option<Projection> {
+        &self.projection
+    }
     fn extract_index_capacity(&self, m: &Mode) -> Option<PrintCapacityUnits> {
         if m == &Mode::OnDemand {
             None
@@ -270,6 +576,9 @@ impl IndexDesc for LocalSecondaryIndexDescription {
     fn retrieve_key_schema(&self) -> &Option<Vec<KeySchemaElement>> {
         &self.key_schema
     }
+    fn retrieve_projection(&self) -> &Option<Projection> {
+        &self.projection
+    }
     fn extract_index_capacity(&self, _: &Mode) -> Option<PrintCapacityUnits> {
         None // Unlike GSI, LSI doesn't have it's own capacity.
     }
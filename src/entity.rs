@@ -0,0 +1,504 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements `dy entity`, which gets and puts items of entities defined in `entities` of config.yml.
+// In single-table designs, keys are composed of a prefix of the entity type and IDs, e.g. "USER#42", which are easy to get wrong by hand.
+// Keys are built from templates of the entity instead, e.g. pk: "USER#{id}", whose placeholders are given as options, e.g. `--id 42`.
+// `dy key build` and `dy key parse` compose and decompose keys with the same templates, e.g. for scripts.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    io::{self, Write},
+};
+
+use log::{debug, error};
+use serde_json::Value as JsonValue;
+use tabwriter::TabWriter;
+use thiserror::Error;
+
+use super::app::{self, Entity};
+use super::data;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EntityError {
+    #[error("Entity '{0}' is not defined in `entities` of config.yml. Run `dy entity list` to see defined ones.")]
+    UnknownEntity(String),
+    #[error("Invalid key template '{0}': a placeholder is not closed by '}}'.")]
+    InvalidTemplate(String),
+    #[error("Unexpected argument '{0}'. Give values of placeholders in the key templates as options, e.g. --id 42.")]
+    UnexpectedArgument(String),
+    #[error("No value is given to --{0}.")]
+    MissingValue(String),
+    #[error("--{0} is required by the key templates of the entity.")]
+    MissingParameter(String),
+    #[error("--{0} is not a placeholder in the key templates of the entity. Available ones: {1}")]
+    UnknownParameter(String, String),
+    #[error("The key templates of the entity take {0} value(s) for {1}, but {2} given.")]
+    ValueCount(usize, String, usize),
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Executed when you call `$ dy entity get`. Builds keys of the entity and gets the item as `dy get` does.
+/// Defaults of options in `table_defaults` of config.yml are looked up by the table of the entity.
+pub async fn get(
+    cx: &app::Context,
+    name: &str,
+    params: &[String],
+    consistent_read: bool,
+    output: Option<String>,
+) {
+    let (mut cx, pval, sval) = resolve_or_exit(cx, name, params);
+    cx.output = cx.output_or_table_default(output);
+    let consistent_read = cx.consistent_read_or_table_default(consistent_read);
+    data::get_item(
+        &cx,
+        data::GetItemParams {
+            pval: Some(pval),
+            svals: sval.into_iter().collect(),
+            sort_keys: vec![],
+            keys_file: None,
+            consistent_read,
+            field: None,
+            replica_regions: vec![],
+            humanize: false,
+        },
+    )
+    .await
+}
+
+/// Executed when you call `$ dy entity put`. Builds keys of the entity and puts the item as `dy put` does.
+pub async fn put(
+    cx: &app::Context,
+    name: &str,
+    params: &[String],
+    item: Option<String>,
+    if_not_exists: bool,
+) {
+    let (cx, pval, sval) = resolve_or_exit(cx, name, params);
+    data::put_item(&cx, pval, sval, item, if_not_exists, false).await
+}
+
+/// Executed when you call `$ dy entity list`. Prints entities with their tables and key templates.
+pub fn list(cx: &app::Context) -> Result<(), Box<dyn Error>> {
+    let entities = match cx.configured_entities() {
+        Some(entities) if !entities.is_empty() => entities,
+        _ => {
+            println!("No entity is defined in `entities` of config.yml.");
+            return Ok(());
+        }
+    };
+    let mut tw = TabWriter::new(io::stdout());
+    tw.write_all(b"Name\tTable\tPK\tSK\n")?;
+    for (name, entity) in entities {
+        tw.write_all(
+            format!(
+                "{}\t{}\t{}\t{}\n",
+                name,
+                entity.table.as_deref().unwrap_or("-"),
+                entity.pk,
+                entity.sk.as_deref().unwrap_or("-")
+            )
+            .as_bytes(),
+        )?;
+    }
+    tw.flush()?;
+    Ok(())
+}
+
+/// Executed when you call `$ dy key build`. Prints keys of the entity built from values of placeholders,
+/// which are given in the order they appear in the templates of the partition key and the sort key.
+pub fn build_key(cx: &app::Context, name: &str, values: &[String]) -> Result<(), Box<dyn Error>> {
+    let entity = entity_or_exit(cx, name);
+    let result = ordered_placeholders(entity).and_then(|names| {
+        if names.len() != values.len() {
+            return Err(EntityError::ValueCount(
+                names.len(),
+                names.join(", "),
+                values.len(),
+            ));
+        }
+        let params: BTreeMap<String, String> = names
+            .into_iter()
+            .map(String::from)
+            .zip(values.iter().cloned())
+            .collect();
+        let keys = build_keys(entity, &params)?;
+        Ok((params, keys))
+    });
+    let (params, (pval, sval)) = result.unwrap_or_else(|e| {
+        error!("{}", e);
+        std::process::exit(1);
+    });
+
+    // A value which contains a literal of the template, e.g. "#", makes the key ambiguous to parse.
+    if match_entity(entity, &pval, sval.as_deref())? != Some(params) {
+        eprintln!(
+            "WARN: The key can't be parsed back into the same values, as a value contains a literal part of the key templates."
+        );
+    }
+    let mut keys = serde_json::Map::new();
+    keys.insert(String::from("pk"), JsonValue::String(pval));
+    if let Some(sval) = sval {
+        keys.insert(String::from("sk"), JsonValue::String(sval));
+    }
+    println!("{}", serde_json::to_string_pretty(&keys)?);
+    Ok(())
+}
+
+/// Executed when you call `$ dy key parse`. Prints entities whose key templates match the keys, with values of placeholders.
+/// Only the partition key is matched if the sort key is not given.
+pub fn parse_key(
+    cx: &app::Context,
+    pval: &str,
+    sval: Option<&str>,
+    name: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(name) = name {
+        entity_or_exit(cx, name);
+    }
+    let candidates = cx
+        .configured_entities()
+        .into_iter()
+        .flatten()
+        .filter(|(n, _)| name.map_or(true, |name| n.as_str() == name));
+    let mut matches = vec![];
+    for (name, entity) in candidates {
+        if let Some(params) = match_entity(entity, pval, sval)? {
+            matches.push(serde_json::json!({ "entity": name, "params": params }));
+        }
+    }
+    if matches.is_empty() {
+        error!("The key doesn't match key templates of any entity in `entities` of config.yml.");
+        std::process::exit(1);
+    }
+    println!("{}", serde_json::to_string_pretty(&matches)?);
+    Ok(())
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+fn entity_or_exit<'a>(cx: &'a app::Context, name: &str) -> &'a Entity {
+    match cx
+        .configured_entities()
+        .and_then(|entities| entities.get(name))
+    {
+        Some(entity) => entity,
+        None => {
+            error!("{}", EntityError::UnknownEntity(name.to_owned()));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Returns the context targeting the table of the entity, and the partition key and sort key values built from the templates.
+/// The table of the entity is used unless --table is given.
+fn resolve_or_exit(
+    cx: &app::Context,
+    name: &str,
+    params: &[String],
+) -> (app::Context, String, Option<String>) {
+    let entity = entity_or_exit(cx, name);
+    match parse_params(params).and_then(|params| build_keys(entity, &params)) {
+        Ok((pval, sval)) => {
+            debug!("keys of the entity '{}': {} {:?}", name, pval, sval);
+            let cx = match &entity.table {
+                Some(table) if cx.overwritten_table_name.is_none() => cx.clone().with_table(table),
+                _ => cx.clone(),
+            };
+            (cx, pval, sval)
+        }
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses options given after the entity name, i.e. `--name value` or `--name=value`.
+fn parse_params(args: &[String]) -> Result<BTreeMap<String, String>, EntityError> {
+    let mut params = BTreeMap::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let param = arg
+            .strip_prefix("--")
+            .ok_or_else(|| EntityError::UnexpectedArgument(arg.to_owned()))?;
+        let (name, value) = match param.split_once('=') {
+            Some((name, value)) => (name, value.to_owned()),
+            None => match args.next() {
+                Some(value) => (param, value.to_owned()),
+                None => return Err(EntityError::MissingValue(param.to_owned())),
+            },
+        };
+        params.insert(name.to_owned(), value);
+    }
+    Ok(params)
+}
+
+/// Builds the partition key and sort key values of the entity, rejecting parameters which no template uses as they're likely typos.
+fn build_keys(
+    entity: &Entity,
+    params: &BTreeMap<String, String>,
+) -> Result<(String, Option<String>), EntityError> {
+    let mut names: BTreeSet<&str> = placeholders(&entity.pk)?.into_iter().collect();
+    if let Some(sk) = &entity.sk {
+        names.extend(placeholders(sk)?);
+    }
+    if let Some(unknown) = params.keys().find(|p| !names.contains(p.as_str())) {
+        let available: Vec<String> = names.iter().map(|p| format!("--{}", p)).collect();
+        return Err(EntityError::UnknownParameter(
+            unknown.to_owned(),
+            available.join(", "),
+        ));
+    }
+    let pval = render(&entity.pk, params)?;
+    let sval = entity
+        .sk
+        .as_deref()
+        .map(|sk| render(sk, params))
+        .transpose()?;
+    Ok((pval, sval))
+}
+
+/// Returns names of placeholders in the template, e.g. ["org", "id"] for "ORG#{org}#USER#{id}".
+fn placeholders(template: &str) -> Result<Vec<&str>, EntityError> {
+    Ok(split_template(template)?
+        .into_iter()
+        .filter_map(|(_, name)| name)
+        .collect())
+}
+
+/// Returns names of placeholders in the templates of the partition key and the sort key, in the order they first appear.
+fn ordered_placeholders(entity: &Entity) -> Result<Vec<&str>, EntityError> {
+    let mut names = placeholders(&entity.pk)?;
+    if let Some(sk) = &entity.sk {
+        names.extend(placeholders(sk)?);
+    }
+    let mut seen = BTreeSet::new();
+    names.retain(|name| seen.insert(*name));
+    Ok(names)
+}
+
+/// Returns values of placeholders if the keys match the templates of the entity.
+/// Only the partition key is matched if the sort key is not given.
+fn match_entity(
+    entity: &Entity,
+    pval: &str,
+    sval: Option<&str>,
+) -> Result<Option<BTreeMap<String, String>>, EntityError> {
+    let captured = match match_template(&split_template(&entity.pk)?, pval, BTreeMap::new()) {
+        Some(captured) => captured,
+        None => return Ok(None),
+    };
+    match (&entity.sk, sval) {
+        (Some(sk), Some(sval)) => Ok(match_template(&split_template(sk)?, sval, captured)),
+        (None, Some(_)) => Ok(None),
+        (_, None) => Ok(Some(captured)),
+    }
+}
+
+/// Matches the value with parts of a template, trying shorter values for each placeholder first.
+/// A placeholder matches a non-empty value, and the same value if it appears more than once.
+fn match_template(
+    parts: &[(&str, Option<&str>)],
+    value: &str,
+    captured: BTreeMap<String, String>,
+) -> Option<BTreeMap<String, String>> {
+    let ((literal, name), rest) = match parts.split_first() {
+        None => return value.is_empty().then_some(captured),
+        Some(first) => first,
+    };
+    let value = value.strip_prefix(literal)?;
+    let name = match name {
+        None => return match_template(rest, value, captured),
+        Some(name) => *name,
+    };
+    value
+        .char_indices()
+        .map(|(i, _)| i)
+        .skip(1)
+        .chain(std::iter::once(value.len()))
+        .filter(|end| *end > 0)
+        .find_map(|end| {
+            let v = &value[..end];
+            if captured.get(name).map_or(false, |c| c != v) {
+                return None;
+            }
+            let mut captured = captured.clone();
+            captured.insert(name.to_owned(), v.to_owned());
+            match_template(rest, &value[end..], captured)
+        })
+}
+
+/// Replaces placeholders in the template with values of the parameters.
+fn render(template: &str, params: &BTreeMap<String, String>) -> Result<String, EntityError> {
+    let mut rendered = String::new();
+    for (literal, name) in split_template(template)? {
+        rendered.push_str(literal);
+        if let Some(name) = name {
+            let value = params
+                .get(name)
+                .ok_or_else(|| EntityError::MissingParameter(name.to_owned()))?;
+            rendered.push_str(value);
+        }
+    }
+    Ok(rendered)
+}
+
+/// Splits the template into pairs of a literal and the name of the placeholder following it, if any.
+fn split_template(template: &str) -> Result<Vec<(&str, Option<&str>)>, EntityError> {
+    let mut parts = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| EntityError::InvalidTemplate(template.to_owned()))?
+            + start;
+        parts.push((&rest[..start], Some(&rest[start + 1..end])));
+        rest = &rest[end + 1..];
+    }
+    parts.push((rest, None));
+    Ok(parts)
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let args: Vec<String> = ["--id", "42", "--org=acme", "--empty="]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            parse_params(&args),
+            Ok(params(&[("id", "42"), ("org", "acme"), ("empty", "")]))
+        );
+        assert_eq!(
+            parse_params(&[String::from("42")]),
+            Err(EntityError::UnexpectedArgument(String::from("42")))
+        );
+        assert_eq!(
+            parse_params(&[String::from("--id")]),
+            Err(EntityError::MissingValue(String::from("id")))
+        );
+    }
+
+    #[test]
+    fn test_build_keys() {
+        let entity = Entity {
+            table: None,
+            pk: String::from("ORG#{org}"),
+            sk: Some(String::from("USER#{id}#{org}")),
+        };
+        assert_eq!(
+            build_keys(&entity, &params(&[("org", "acme"), ("id", "42")])),
+            Ok((String::from("ORG#acme"), Some(String::from("USER#42#acme"))))
+        );
+        assert_eq!(
+            build_keys(&entity, &params(&[("org", "acme")])),
+            Err(EntityError::MissingParameter(String::from("id")))
+        );
+        assert_eq!(
+            build_keys(&entity, &params(&[("org", "acme"), ("idd", "42")])),
+            Err(EntityError::UnknownParameter(
+                String::from("idd"),
+                String::from("--id, --org")
+            ))
+        );
+
+        let fixed = Entity {
+            table: None,
+            pk: String::from("CONFIG"),
+            sk: None,
+        };
+        assert_eq!(
+            build_keys(&fixed, &params(&[])),
+            Ok((String::from("CONFIG"), None))
+        );
+        assert_eq!(
+            placeholders("USER#{id"),
+            Err(EntityError::InvalidTemplate(String::from("USER#{id")))
+        );
+    }
+
+    #[test]
+    fn test_match_entity() {
+        let entity = Entity {
+            table: None,
+            pk: String::from("ORG#{org}"),
+            sk: Some(String::from("USER#{id}#{org}")),
+        };
+        assert_eq!(
+            match_entity(&entity, "ORG#acme", Some("USER#42#acme")),
+            Ok(Some(params(&[("org", "acme"), ("id", "42")])))
+        );
+        // Only the partition key is matched without the sort key.
+        assert_eq!(
+            match_entity(&entity, "ORG#acme", None),
+            Ok(Some(params(&[("org", "acme")])))
+        );
+        // The same placeholder has to match the same value.
+        assert_eq!(
+            match_entity(&entity, "ORG#acme", Some("USER#42#other")),
+            Ok(None)
+        );
+        assert_eq!(match_entity(&entity, "ORG#", None), Ok(None));
+        assert_eq!(match_entity(&entity, "USER#42", None), Ok(None));
+
+        // A value which contains a literal of the template is matched as short as possible.
+        let user = Entity {
+            table: None,
+            pk: String::from("USER#{id}#{name}"),
+            sk: None,
+        };
+        assert_eq!(
+            match_entity(&user, "USER#a#b#c", None),
+            Ok(Some(params(&[("id", "a"), ("name", "b#c")])))
+        );
+        assert_eq!(match_entity(&user, "USER#a#b", Some("x")), Ok(None));
+    }
+
+    #[test]
+    fn test_ordered_placeholders() {
+        let entity = Entity {
+            table: None,
+            pk: String::from("ORG#{org}"),
+            sk: Some(String::from("USER#{id}#{org}")),
+        };
+        assert_eq!(ordered_placeholders(&entity), Ok(vec!["org", "id"]));
+    }
+}
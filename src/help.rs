@@ -0,0 +1,96 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module provides topical help, e.g. `dy help expressions`, which goes beyond help of each subcommand.
+// Documents of topics are markdown files under resources/help/, embedded into the binary at build time.
+// Note that `dy help <subcommand>` is still handled by clap.
+use std::ffi::OsStr;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+pub struct Topic {
+    pub name: &'static str,
+    content: &'static str,
+}
+
+static TOPICS: [Topic; 3] = [
+    Topic {
+        name: "expressions",
+        content: include_str!("./resources/help/expressions.md"),
+    },
+    Topic {
+        name: "formats",
+        content: include_str!("./resources/help/formats.md"),
+    },
+    Topic {
+        name: "configuration",
+        content: include_str!("./resources/help/config.md"),
+    },
+];
+
+/// Shown at the bottom of `dy --help`. Keep it in sync with TOPICS.
+pub const TOPICS_HELP: &str = "\
+Help topics (dy help <TOPIC>):
+  expressions    Syntax of expressions used by upd, query and expr
+  formats        Output formats of read commands, and formats of export/import
+  configuration  Files, keys and environment variables to configure dynein";
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Returns the topic if the arguments (excluding the binary name) are `help <topic>`.
+pub fn find_topic<S: AsRef<OsStr>>(args: &[S]) -> Option<&'static Topic> {
+    match args {
+        [command, name] if command.as_ref() == "help" => {
+            TOPICS.iter().find(|topic| name.as_ref() == topic.name)
+        }
+        _ => None,
+    }
+}
+
+pub fn print_topic(topic: &Topic) {
+    print!("{}", topic.content);
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_topic() {
+        assert_eq!(
+            find_topic(&["help", "formats"]).map(|t| t.name),
+            Some("formats")
+        );
+        // Subcommands are not topics, so that clap shows their help.
+        assert!(find_topic(&["help", "scan"]).is_none());
+        assert!(find_topic(&["help", "config"]).is_none());
+        assert!(find_topic(&["help"]).is_none());
+        assert!(find_topic(&["scan", "formats"]).is_none());
+
+        for topic in TOPICS.iter() {
+            assert!(TOPICS_HELP.contains(&format!("  {} ", topic.name)));
+            assert!(topic.content.starts_with("# "));
+        }
+    }
+}
@@ -0,0 +1,214 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module records mutating commands, e.g. put, del and admin, into ~/.dynein/history.jsonl,
+// and shows them by `dy history list|show`. Recording is opt-in by `dy config set history.enabled true`.
+// Each line of the file is an Entry in JSON. Commands are recorded before they're executed,
+// as most of them exit the process on errors.
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+};
+
+use ::serde::{Deserialize, Serialize};
+use log::{debug, error};
+use serde_json::Value as JsonValue;
+use tabwriter::TabWriter;
+
+use super::app;
+use super::cmd;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Entry {
+    id: u64,
+    timestamp: String,
+    /// Subcommand, e.g. "put" or "admin create".
+    command: String,
+    region: String,
+    table: Option<String>,
+    /// All arguments of the subcommand, as parsed by dynein.
+    args: JsonValue,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Appends the subcommand to the history if it's a mutating one and history is enabled.
+/// Failures to record are reported as warnings, and don't stop the command.
+pub async fn record(cx: &app::Context, sub: &cmd::Sub) {
+    let (max_entries, command) = match (cx.history_max_entries(), mutating_command_name(sub)) {
+        (Some(max_entries), Some(command)) => (max_entries, command),
+        _ => return,
+    };
+    let region = cx.effective_region().await.as_ref().to_owned();
+    let result = load_entries().and_then(|mut entries| {
+        let entry = Entry {
+            id: entries.last().map_or(1, |e| e.id + 1),
+            timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            command: command.to_owned(),
+            region,
+            table: cx.table_name_if_specified(),
+            args: serde_json::to_value(sub)?,
+        };
+        debug!("recording history: {:?}", entry);
+        entries.push(entry);
+        let skip = entries.len().saturating_sub(max_entries);
+        save_entries(&entries[skip..])
+    });
+    if let Err(e) = result {
+        eprintln!("WARN: Failed to record the command in history: {}", e);
+    }
+}
+
+/// Executed when you call `$ dy history list`. Shows the latest entries, oldest first.
+pub fn list(cx: &app::Context, limit: usize) -> Result<(), Box<dyn Error>> {
+    if cx.history_max_entries().is_none() {
+        eprintln!("WARN: History is not enabled. Run `dy config set history.enabled true` to record mutating commands.");
+    }
+    let entries = load_entries()?;
+    let skip = entries.len().saturating_sub(limit);
+
+    let mut tw = TabWriter::new(io::stdout());
+    tw.write_all(b"ID\tTimestamp\tCommand\tRegion\tTable\n")?;
+    for entry in &entries[skip..] {
+        tw.write_all(
+            format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                entry.id,
+                entry.timestamp,
+                entry.command,
+                entry.region,
+                entry.table.as_deref().unwrap_or("-")
+            )
+            .as_bytes(),
+        )?;
+    }
+    tw.flush()?;
+    Ok(())
+}
+
+/// Executed when you call `$ dy history show <ID>`. Prints the entry including all arguments in JSON.
+pub fn show(id: u64) -> Result<(), Box<dyn Error>> {
+    match load_entries()?.into_iter().find(|e| e.id == id) {
+        Some(entry) => println!("{}", serde_json::to_string_pretty(&entry)?),
+        None => {
+            error!("No history entry with ID {}.", id);
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name of the subcommand if it may modify tables or data, otherwise None.
+/// The name is used to match commands in `hooks` of config.yml as well.
+pub fn mutating_command_name(sub: &cmd::Sub) -> Option<&'static str> {
+    match sub {
+        cmd::Sub::Put { .. } => Some("put"),
+        cmd::Sub::Del { .. } => Some("del"),
+        cmd::Sub::Upd { .. } => Some("upd"),
+        cmd::Sub::Bwrite { .. } => Some("bwrite"),
+        cmd::Sub::Import { .. } => Some("import"),
+        cmd::Sub::Bootstrap { .. } => Some("bootstrap"),
+        cmd::Sub::Backup { .. } => Some("backup"),
+        cmd::Sub::Restore { .. } => Some("restore"),
+        cmd::Sub::Migrate { .. } => Some("migrate"),
+        cmd::Sub::Entity {
+            grandchild: cmd::EntitySub::Put { .. },
+        } => Some("entity put"),
+        cmd::Sub::Analyze {
+            grandchild: cmd::AnalyzeSub::Expired { delete: true, .. },
+        } => Some("analyze expired"),
+        cmd::Sub::Admin { grandchild } => match grandchild {
+            cmd::AdminSub::Create { .. } => Some("admin create"),
+            cmd::AdminSub::Update { .. } => Some("admin update"),
+            cmd::AdminSub::Delete { .. } => Some("admin delete"),
+            cmd::AdminSub::Tags {
+                action: cmd::TagsSub::Set { .. } | cmd::TagsSub::Remove { .. },
+            } => Some("admin tags"),
+            cmd::AdminSub::Apply { .. } => Some("admin apply"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+fn load_entries() -> Result<Vec<Entry>, Box<dyn Error>> {
+    let path = app::retrieve_dynein_file_path(app::DyneinFileType::HistoryFile)?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+fn save_entries(entries: &[Entry]) -> Result<(), Box<dyn Error>> {
+    let mut content = String::new();
+    for entry in entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    app::write_dynein_file(app::DyneinFileType::HistoryFile, content)?;
+    Ok(())
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::parse_args;
+
+    #[test]
+    fn test_mutating_command_name() {
+        let name = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            mutating_command_name(&parse_args(args).unwrap())
+        };
+        assert_eq!(name(&["put", "1"]), Some("put"));
+        assert_eq!(
+            name(&["admin", "create", "table", "t", "--keys", "pk"]),
+            Some("admin create")
+        );
+        assert_eq!(
+            name(&["admin", "tags", "set", "env=prod"]),
+            Some("admin tags")
+        );
+        assert_eq!(
+            name(&["analyze", "expired", "--delete"]),
+            Some("analyze expired")
+        );
+        assert_eq!(name(&["analyze", "expired"]), None);
+        assert_eq!(name(&["admin", "tags", "list"]), None);
+        assert_eq!(name(&["scan"]), None);
+    }
+}
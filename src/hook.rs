@@ -0,0 +1,160 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module runs shell commands defined in `hooks` of config.yml before and after mutating commands,
+// e.g. to block writes during freeze windows or to post to Slack after an import completes.
+// Mutating commands are the same as the ones recorded in history. The operation is passed to hooks as env vars:
+// DYNEIN_HOOK (before or after), DYNEIN_COMMAND, DYNEIN_REGION, DYNEIN_TABLE and DYNEIN_ARGS (arguments in JSON).
+use std::{
+    io,
+    process::{Command, ExitStatus},
+};
+
+use log::{debug, error};
+
+use super::app::{self, Hook, HookTiming};
+use super::cmd;
+use super::history;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// A mutating command being executed, which is passed to hooks.
+pub struct Invocation {
+    command: &'static str,
+    region: String,
+    table: Option<String>,
+    args: String,
+    after_hooks: Vec<Hook>,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Runs before hooks of the subcommand if it's a mutating one, and returns the invocation to run after hooks later.
+/// Exits the process if any of the before hooks fails, so that the command is not executed.
+pub async fn run_before(cx: &app::Context, sub: &cmd::Sub) -> Option<Invocation> {
+    let command = history::mutating_command_name(sub)?;
+    let hooks = cx.configured_hooks();
+    if !hooks.iter().any(|hook| applies(hook, command)) {
+        return None;
+    }
+    let invocation = Invocation {
+        command,
+        region: cx.effective_region().await.as_ref().to_owned(),
+        table: cx.table_name_if_specified(),
+        args: serde_json::to_string(sub).unwrap_or_default(),
+        after_hooks: hooks
+            .iter()
+            .filter(|hook| hook.when == HookTiming::After && applies(hook, command))
+            .cloned()
+            .collect(),
+    };
+    for hook in hooks
+        .iter()
+        .filter(|hook| hook.when == HookTiming::Before && applies(hook, command))
+    {
+        match invocation.run(hook) {
+            Ok(status) if status.success() => (),
+            Ok(status) => {
+                error!(
+                    "The before hook '{}' exited with {}. `dy {}` was not executed.",
+                    hook.run, status, command
+                );
+                std::process::exit(1);
+            }
+            Err(e) => {
+                error!("Failed to run the before hook '{}': {}", hook.run, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    Some(invocation)
+}
+
+impl Invocation {
+    /// Runs after hooks once the command has succeeded. Failures of after hooks are reported as warnings,
+    /// as the command has been executed already.
+    pub fn run_after(&self) {
+        for hook in &self.after_hooks {
+            match self.run(hook) {
+                Ok(status) if status.success() => (),
+                Ok(status) => eprintln!(
+                    "WARN: The after hook '{}' exited with {}.",
+                    hook.run, status
+                ),
+                Err(e) => eprintln!("WARN: Failed to run the after hook '{}': {}", hook.run, e),
+            }
+        }
+    }
+
+    /// Runs the hook in the shell. Its stdout goes to stderr, so that it doesn't mix into output of the command.
+    fn run(&self, hook: &Hook) -> io::Result<ExitStatus> {
+        debug!("running the {:?} hook: {}", hook.when, hook.run);
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        Command::new(shell)
+            .args([flag, &hook.run])
+            .env(
+                "DYNEIN_HOOK",
+                match hook.when {
+                    HookTiming::Before => "before",
+                    HookTiming::After => "after",
+                },
+            )
+            .env("DYNEIN_COMMAND", self.command)
+            .env("DYNEIN_REGION", &self.region)
+            .env("DYNEIN_TABLE", self.table.as_deref().unwrap_or_default())
+            .env("DYNEIN_ARGS", &self.args)
+            .stdout(io::stderr())
+            .status()
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Whether the hook applies to the command. Hooks without `commands` apply to all mutating commands.
+fn applies(hook: &Hook, command: &str) -> bool {
+    hook.commands.is_empty() || hook.commands.iter().any(|c| c == command)
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applies() {
+        let hook = |commands: &[&str]| Hook {
+            when: HookTiming::Before,
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+            run: String::from("true"),
+        };
+        assert!(applies(&hook(&[]), "put"));
+        assert!(applies(&hook(&["put", "admin delete"]), "admin delete"));
+        assert!(!applies(&hook(&["put"]), "import"));
+    }
+}
@@ -0,0 +1,213 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements `dy admin iam-policy`, which generates a least-privilege IAM policy
+// to run the given dynein operations on the target table. ARNs of the table and its indexes are taken from DescribeTable.
+use std::collections::BTreeSet;
+
+use aws_sdk_dynamodb::types::TableDescription;
+use serde_json::{json, Value as JsonValue};
+
+use super::app;
+use super::control;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Operations which `dy admin iam-policy` accepts, i.e. names of dynein commands.
+const OPERATION_NAMES: [&str; 14] = [
+    "get", "put", "del", "upd", "bwrite", "scan", "query", "find-sk", "browse", "export", "import",
+    "desc", "list", "backup",
+];
+
+/// Kinds of resources which actions are allowed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Resource {
+    Table,
+    Index,
+    Backup,
+    /// Actions which don't support resource-level permissions, e.g. ListTables.
+    Any,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Executed when you call `$ dy admin iam-policy <OPERATIONS>...`. Prints the policy to run the operations on the effective table.
+pub async fn iam_policy(cx: &app::Context, operations: Vec<String>) {
+    let desc: TableDescription = control::describe_table_api(cx, cx.effective_table_name()).await;
+    let table_arn = desc.table_arn.clone().expect("table should have ARN");
+    let index_arns: Vec<String> = desc
+        .global_secondary_indexes
+        .iter()
+        .flatten()
+        .filter_map(|i| i.index_arn.clone())
+        .chain(
+            desc.local_secondary_indexes
+                .iter()
+                .flatten()
+                .filter_map(|i| i.index_arn.clone()),
+        )
+        .collect();
+    let policy = build_policy(&operations, &table_arn, &index_arns);
+    println!("{}", serde_json::to_string_pretty(&policy).unwrap());
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Returns actions which the operation calls. DescribeTable is always required to resolve the table schema.
+fn actions_of(operation: &str) -> Vec<(&'static str, Resource)> {
+    let mut actions = vec![("DescribeTable", Resource::Table)];
+    actions.extend(match operation {
+        "get" => vec![("GetItem", Resource::Table)],
+        "put" => vec![("PutItem", Resource::Table)],
+        "del" => vec![("DeleteItem", Resource::Table)],
+        "upd" => vec![("UpdateItem", Resource::Table)],
+        "bwrite" => vec![("BatchWriteItem", Resource::Table)],
+        "scan" | "export" => vec![("Scan", Resource::Table), ("Scan", Resource::Index)],
+        "query" => vec![("Query", Resource::Table), ("Query", Resource::Index)],
+        "find-sk" => vec![
+            ("Query", Resource::Table),
+            ("Query", Resource::Index),
+            ("Scan", Resource::Table),
+        ],
+        "browse" => vec![
+            ("Scan", Resource::Table),
+            ("PutItem", Resource::Table),
+            ("DeleteItem", Resource::Table),
+        ],
+        // UpdateItem is called with --update-mode merge.
+        "import" => vec![
+            ("BatchWriteItem", Resource::Table),
+            ("UpdateItem", Resource::Table),
+        ],
+        "desc" => vec![],
+        "list" => vec![("ListTables", Resource::Any)],
+        // DescribeBackup is called to wait for the backup with --wait.
+        "backup" => vec![
+            ("CreateBackup", Resource::Table),
+            ("DescribeBackup", Resource::Backup),
+            ("ListBackups", Resource::Any),
+        ],
+        _ => panic!(
+            "You shouldn't see this message as operations are limited to {:?}.",
+            OPERATION_NAMES
+        ),
+    });
+    actions
+}
+
+/// Builds a policy with one statement per kind of resources. Actions on indexes are omitted if the table has no index.
+fn build_policy(operations: &[String], table_arn: &str, index_arns: &[String]) -> JsonValue {
+    let actions: BTreeSet<(Resource, &str)> = operations
+        .iter()
+        .flat_map(|op| actions_of(op))
+        .map(|(action, resource)| (resource, action))
+        .collect();
+
+    let mut statements: Vec<JsonValue> = vec![];
+    for (resource, sid) in [
+        (Resource::Table, "DyneinTable"),
+        (Resource::Index, "DyneinIndexes"),
+        (Resource::Backup, "DyneinBackups"),
+        (Resource::Any, "DyneinAccount"),
+    ] {
+        let resources: JsonValue = match resource {
+            Resource::Table => json!(table_arn),
+            Resource::Index if index_arns.is_empty() => continue,
+            Resource::Index => json!(index_arns),
+            Resource::Backup => json!(format!("{}/backup/*", table_arn)),
+            Resource::Any => json!("*"),
+        };
+        let actions: Vec<String> = actions
+            .iter()
+            .filter(|(r, _)| r == &resource)
+            .map(|(_, action)| format!("dynamodb:{}", action))
+            .collect();
+        if actions.is_empty() {
+            continue;
+        }
+        statements.push(json!({
+            "Sid": sid,
+            "Effect": "Allow",
+            "Action": actions,
+            "Resource": resources,
+        }));
+    }
+
+    json!({
+        "Version": "2012-10-17",
+        "Statement": statements,
+    })
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_policy() {
+        let table_arn = "arn:aws:dynamodb:us-east-1:123456789012:table/users";
+        let index_arns = vec![format!("{}/index/email", table_arn)];
+        let operations = vec![
+            String::from("query"),
+            String::from("put"),
+            String::from("list"),
+        ];
+        assert_eq!(
+            build_policy(&operations, table_arn, &index_arns),
+            json!({
+                "Version": "2012-10-17",
+                "Statement": [
+                    {
+                        "Sid": "DyneinTable",
+                        "Effect": "Allow",
+                        "Action": ["dynamodb:DescribeTable", "dynamodb:PutItem", "dynamodb:Query"],
+                        "Resource": table_arn,
+                    },
+                    {
+                        "Sid": "DyneinIndexes",
+                        "Effect": "Allow",
+                        "Action": ["dynamodb:Query"],
+                        "Resource": [format!("{}/index/email", table_arn)],
+                    },
+                    {
+                        "Sid": "DyneinAccount",
+                        "Effect": "Allow",
+                        "Action": ["dynamodb:ListTables"],
+                        "Resource": "*",
+                    },
+                ],
+            })
+        );
+
+        // No statement for indexes if the table has no index.
+        let policy = build_policy(&[String::from("scan")], table_arn, &[]);
+        assert_eq!(policy["Statement"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            policy["Statement"][0]["Action"],
+            json!(["dynamodb:DescribeTable", "dynamodb:Scan"])
+        );
+    }
+}
@@ -0,0 +1,129 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module lets scans which read all pages of a table stop gracefully by Ctrl-C, e.g. `dy export` and `dy scan --distinct`.
+// Imports which boost WCU of a table are watched in the same way, so that the original WCU is restored before exiting.
+// While such a scan is in progress, the first Ctrl-C marks it as interrupted. The scan stops waiting for the page in flight,
+// and prints a summary of what it has read so far instead of being killed in the middle of writing output.
+// Ctrl-C outside of scans, or the second one, terminates the process immediately with the usual exit status.
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Once,
+    },
+    time::Duration,
+};
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Exit status of a process terminated by SIGINT, i.e. 128 + 2.
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Interval to check whether the scan has been interrupted while a page is in flight.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static HANDLER: Once = Once::new();
+static SCANS_IN_PROGRESS: AtomicUsize = AtomicUsize::new(0);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Watches Ctrl-C during a scan. The scan can be interrupted while the watch is alive.
+pub struct Watch {
+    _private: (),
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Starts watching Ctrl-C for a scan. The handler of Ctrl-C is installed at the first call.
+pub fn watch() -> Watch {
+    HANDLER.call_once(|| {
+        tokio::spawn(handle_ctrl_c());
+    });
+    SCANS_IN_PROGRESS.fetch_add(1, Ordering::SeqCst);
+    Watch { _private: () }
+}
+
+impl Watch {
+    /// Whether Ctrl-C has been pressed, which scans check between pages.
+    pub fn interrupted(&self) -> bool {
+        INTERRUPTED.load(Ordering::SeqCst)
+    }
+
+    /// Awaits the request of a page, or returns None once the scan is interrupted, dropping the request in flight.
+    pub async fn page<F: Future>(&self, request: F) -> Option<F::Output> {
+        tokio::select! {
+            output = request => Some(output),
+            _ = wait_for_interrupt() => None,
+        }
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        if SCANS_IN_PROGRESS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            // The next command in the shell mode should not be interrupted by the previous Ctrl-C.
+            INTERRUPTED.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+async fn handle_ctrl_c() {
+    while tokio::signal::ctrl_c().await.is_ok() {
+        if SCANS_IN_PROGRESS.load(Ordering::SeqCst) == 0 || INTERRUPTED.swap(true, Ordering::SeqCst)
+        {
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        eprintln!("\nInterrupted. Stopping gracefully... Press Ctrl-C again to quit immediately.");
+    }
+}
+
+async fn wait_for_interrupt() {
+    while !INTERRUPTED.load(Ordering::SeqCst) {
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_watch() {
+        let w = watch();
+        assert!(!w.interrupted());
+        assert_eq!(w.page(async { 1 }).await, Some(1));
+
+        // Simulates the first Ctrl-C, which drops the page in flight.
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        assert!(w.interrupted());
+        assert_eq!(w.page(std::future::pending::<()>()).await, None);
+
+        drop(w);
+        assert!(!watch().interrupted());
+    }
+}
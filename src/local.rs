@@ -0,0 +1,169 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements `dy local-query`, which reads items from a file exported by `dy export`
+// and filters them with conditions evaluated on the client side, i.e. without calling any AWS APIs.
+use std::{error::Error, fs, path::Path};
+
+use aws_sdk_dynamodb::types::AttributeValue;
+use log::{debug, error};
+use serde_json::Value as JsonValue;
+
+use super::app;
+use super::avro;
+use super::data;
+use super::ddb::{key, table};
+use super::output::{self, Item};
+use super::parser::LocalCondition;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+pub struct LocalQueryParams {
+    pub file: String,
+    pub format: Option<String>,
+    pub filter: Option<String>,
+    pub keys: Vec<String>,
+    pub limit: Option<usize>,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+pub async fn local_query(
+    cx: &app::Context,
+    params: LocalQueryParams,
+) -> Result<(), Box<dyn Error>> {
+    let condition: Option<LocalCondition> = params.filter.as_deref().map(|filter| {
+        LocalCondition::parse(filter).unwrap_or_else(|e| {
+            error!("{}\n--filter syntax is invalid. For more information, please visit https://github.com/awslabs/dynein/blob/main/docs/query.md.", e);
+            std::process::exit(1);
+        })
+    });
+    if params.keys.len() > 2 {
+        error!(
+            "--keys takes a partition key and an optional sort key, but {} names are given.",
+            params.keys.len()
+        );
+        std::process::exit(1);
+    }
+    if !Path::new(&params.file).exists() {
+        error!("Couldn't find the input file '{}'.", &params.file);
+        std::process::exit(1);
+    }
+
+    let items: Vec<Item> = read_items(&params.file, params.format.as_deref())?;
+    let read_count = items.len();
+    let items: Vec<Item> = items
+        .into_iter()
+        .filter(|item| condition.as_ref().map_or(true, |c| c.matches(item)))
+        .take(params.limit.unwrap_or(usize::MAX))
+        .collect();
+    debug!("{} of {} items matched", items.len(), read_count);
+
+    let ts = local_table_schema(cx, &params.file, &params.keys, &items).await;
+    let view = output::ItemsView {
+        ts: &ts,
+        attributes: &None,
+        keys_only: false,
+        expand: &[],
+    };
+    output::print_items(cx.output.as_deref(), "table", &items, &view);
+    Ok(())
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Reads all items in the file. The format is guessed from the extension of the file unless it's given.
+fn read_items(file: &str, format: Option<&str>) -> Result<Vec<Item>, Box<dyn Error>> {
+    let format = match (
+        format,
+        Path::new(file).extension().and_then(|ext| ext.to_str()),
+    ) {
+        (Some(format), _) => format,
+        (None, Some("jsonl")) => "jsonl",
+        (None, Some("avro")) => "avro",
+        (None, _) => "json",
+    };
+    debug!("reading items from '{}' as {}", file, format);
+
+    let values: Vec<JsonValue> = match format {
+        "jsonl" => fs::read_to_string(file)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?,
+        "avro" => avro::read_items(fs::File::open(file)?)?,
+        _ => match serde_json::from_str::<JsonValue>(&fs::read_to_string(file)?)? {
+            JsonValue::Array(values) => values,
+            _ => return Err(format!("'{}' should be a JSON array of items", file).into()),
+        },
+    };
+
+    values
+        .iter()
+        .map(|value| -> Result<Item, Box<dyn Error>> {
+            match data::dispatch_jsonvalue_to_attrval(value, false) {
+                AttributeValue::M(item) => Ok(item),
+                _ => Err(format!(
+                    "'{}' contains a value which is not an item: {}",
+                    file, value
+                )
+                .into()),
+            }
+        })
+        .collect()
+}
+
+/// Builds a schema to show items, whose primary keys are given by --keys or cached by `dy use`.
+/// Unlike `app::table_schema`, it never calls DescribeTable.
+async fn local_table_schema(
+    cx: &app::Context,
+    file: &str,
+    keys: &[String],
+    items: &[Item],
+) -> app::TableSchema {
+    if keys.is_empty() {
+        if let Some(ts) = cx.cached_using_table_schema().await {
+            return ts;
+        }
+        error!("Primary keys of items in '{}' are unknown. Specify them by --keys option, e.g. --keys pk,sk", file);
+        std::process::exit(1);
+    }
+
+    // Types of primary keys are taken from items, as an exported file doesn't have the table definition.
+    let typed_key = |name: &String| key::Key {
+        name: name.to_owned(),
+        kind: match items.iter().find_map(|item| item.get(name)) {
+            Some(AttributeValue::N(_)) => key::KeyType::N,
+            Some(AttributeValue::B(_)) => key::KeyType::B,
+            _ => key::KeyType::S,
+        },
+    };
+    app::TableSchema {
+        region: String::new(),
+        name: file.to_owned(),
+        pk: typed_key(&keys[0]),
+        sk: keys.get(1).map(typed_key),
+        indexes: None,
+        mode: table::Mode::OnDemand,
+        ttl: None,
+    }
+}
@@ -14,11 +14,11 @@
  * limitations under the License.
  */
 
-use crate::data::QueryParams;
+use crate::data::{GetItemParams, QueryParams, ScanParams, SortKeyInput};
 use brotli::Decompressor;
 use std::io::{stdout, Cursor};
 
-use log::debug;
+use log::{debug, error};
 use std::error::Error;
 
 extern crate pest;
@@ -26,51 +26,102 @@ extern crate pest;
 #[macro_use]
 extern crate pest_derive;
 
+mod analyze;
 mod app;
+mod avro;
 mod batch;
 mod bootstrap;
+#[cfg(feature = "browse")]
+mod browse;
 mod cmd;
+mod completion;
 mod control;
+mod cost;
 mod data;
 mod ddb;
+mod entity;
+mod help;
+mod history;
+mod hook;
+mod iam;
+mod interrupt;
+mod local;
+mod metrics;
+mod migrate;
+mod network;
+mod output;
 mod parser;
+mod pitr;
+mod postprocess;
+mod recorder;
+mod replica;
 mod shell;
+mod snapshot;
+mod sqldump;
 mod transfer;
+mod transform;
+mod xlsx;
 
 /* =================================================
    helper functions
    =================================================
 */
 async fn dispatch(context: &mut app::Context, subcommand: cmd::Sub) -> Result<(), Box<dyn Error>> {
+    history::record(context, &subcommand).await;
+    let invocation = hook::run_before(context, &subcommand).await;
+    let job = metrics::start(context, &subcommand);
+    let result = execute(context, subcommand).await;
+    if let Some(job) = job {
+        job.finish(result.is_ok()).await;
+    }
+    result?;
+    if let Some(invocation) = invocation {
+        invocation.run_after();
+    }
+    Ok(())
+}
+
+async fn execute(context: &mut app::Context, subcommand: cmd::Sub) -> Result<(), Box<dyn Error>> {
     match subcommand {
         cmd::Sub::Admin { grandchild } => match grandchild {
-            cmd::AdminSub::List { all_regions } => {
-                if all_regions {
-                    control::list_tables_all_regions(context).await
+            cmd::AdminSub::List {
+                all_regions,
+                regions,
+                detail,
+                sort_by,
+            } => {
+                if all_regions || regions.is_some() {
+                    control::list_tables_all_regions(context, regions, detail, sort_by.as_deref())
+                        .await
                 } else {
-                    control::list_tables(context, None).await
+                    control::list_tables(context, None, detail, sort_by.as_deref()).await
                 }
             }
             cmd::AdminSub::Desc {
                 target_table_to_desc,
                 all_tables,
+                index,
+                streams,
                 output,
             } => {
                 context.output = output;
                 if all_tables {
                     control::describe_all_tables(context).await
                 } else {
-                    control::describe_table(context, target_table_to_desc).await
+                    control::describe_table(context, target_table_to_desc, index, streams).await
                 }
             }
             cmd::AdminSub::Create { target_type } => match target_type {
                 cmd::CreateSub::Table {
                     new_table_name,
                     keys,
-                } => control::create_table(context, new_table_name, keys).await,
-                cmd::CreateSub::Index { index_name, keys } => {
-                    control::create_index(context, index_name, keys).await
-                }
+                    wait,
+                } => control::create_table(context, new_table_name, keys, wait).await,
+                cmd::CreateSub::Index {
+                    index_name,
+                    keys,
+                    projection,
+                } => control::create_index(context, index_name, keys, &projection).await,
             },
             cmd::AdminSub::Update { target_type } => match target_type {
                 cmd::UpdateSub::Table {
@@ -78,13 +129,54 @@ async fn dispatch(context: &mut app::Context, subcommand: cmd::Sub) -> Result<()
                     mode,
                     wcu,
                     rcu,
-                } => control::update_table(context, table_name_to_update, mode, wcu, rcu).await,
+                    class,
+                    max_wru,
+                    max_rru,
+                    sse_kms_key,
+                } => {
+                    control::update_table(
+                        context,
+                        table_name_to_update,
+                        control::UpdateTableParams {
+                            mode,
+                            wcu,
+                            rcu,
+                            class,
+                            max_wru,
+                            max_rru,
+                            sse_kms_key,
+                        },
+                    )
+                    .await
+                }
             },
             cmd::AdminSub::Delete { target_type } => match target_type {
                 cmd::DeleteSub::Table {
                     table_name_to_delete,
                     yes,
-                } => control::delete_table(context, table_name_to_delete, yes).await,
+                    wait,
+                } => control::delete_table(context, table_name_to_delete, yes, wait).await,
+            },
+            cmd::AdminSub::Wait {
+                target_table_to_wait,
+                until,
+                timeout,
+            } => control::wait_table(context, target_table_to_wait, &until, timeout).await,
+            cmd::AdminSub::Tags { action } => match action {
+                cmd::TagsSub::List => control::list_tags(context).await?,
+                cmd::TagsSub::Set { tags } => control::set_tags(context, tags).await,
+                cmd::TagsSub::Remove { keys } => control::remove_tags(context, keys).await,
+            },
+            cmd::AdminSub::Snapshot { output } => snapshot::snapshot(context, output).await?,
+            cmd::AdminSub::DiffSnapshot { snapshot_file } => {
+                snapshot::diff_snapshot(context, snapshot_file).await?
+            }
+            cmd::AdminSub::IamPolicy { operations } => iam::iam_policy(context, operations).await,
+            cmd::AdminSub::Replica { action } => match action {
+                cmd::ReplicaSub::Lag {
+                    target_table,
+                    timeout,
+                } => replica::replica_lag(context, target_table, timeout).await,
             },
             cmd::AdminSub::Apply { dev } => {
                 if dev {
@@ -101,22 +193,43 @@ async fn dispatch(context: &mut app::Context, subcommand: cmd::Sub) -> Result<()
             attributes,
             keys_only,
             limit,
+            summary,
+            yes,
+            expand,
+            sort_by,
+            group_by,
+            distinct,
             output,
         } => {
-            context.output = output;
+            context.output = context.output_or_table_default(output);
             data::scan(
                 context,
-                index,
-                consistent_read,
-                &attributes,
-                keys_only,
-                limit,
+                ScanParams {
+                    index,
+                    consistent_read,
+                    attributes,
+                    keys_only,
+                    limit,
+                    summary,
+                    yes,
+                    sort_by,
+                    group_by,
+                    distinct,
+                    expand,
+                },
             )
             .await
         }
         cmd::Sub::Query {
             pval,
             sort_key_expression,
+            begins,
+            between,
+            sk_gt,
+            sk_ge,
+            sk_lt,
+            sk_le,
+            filter,
             index,
             limit,
             attributes,
@@ -125,71 +238,204 @@ async fn dispatch(context: &mut app::Context, subcommand: cmd::Sub) -> Result<()
             descending,
             strict,
             non_strict,
+            summary,
+            replica_region,
+            expand,
+            sort_by,
+            group_by,
             output,
         } => {
-            context.output = output;
+            context.output = context.output_or_table_default(output);
             if strict || non_strict {
-                context.should_strict_for_query = Some(strict || !non_strict)
+                context.should_strict = Some(strict || !non_strict)
             }
             data::query(
                 context,
                 QueryParams {
                     pval,
-                    sort_key_expression,
+                    sort_key: SortKeyInput::from_options(
+                        sort_key_expression,
+                        begins,
+                        between,
+                        sk_gt,
+                        sk_ge,
+                        sk_lt,
+                        sk_le,
+                    ),
+                    filter,
                     index,
                     limit,
                     consistent_read,
                     descending,
                     attributes,
                     keys_only,
+                    summary,
+                    replica_regions: replica_region,
+                    sort_by,
+                    group_by,
+                    expand,
                 },
             )
             .await
         }
+        cmd::Sub::FindSk { sval, yes, output } => {
+            context.output = context.output_or_table_default(output);
+            data::find_sort_key(context, data::FindSortKeyParams { sval, yes }).await
+        }
+        #[cfg(feature = "browse")]
+        cmd::Sub::Browse { limit } => browse::browse(context, browse::BrowseParams { limit }).await,
         cmd::Sub::Get {
             pval,
             sval,
+            sort_keys,
+            keys_file,
             consistent_read,
             output,
+            field,
+            replica_region,
+            humanize,
         } => {
-            context.output = output;
-            data::get_item(context, pval, sval, consistent_read).await
+            context.output = context.output_or_table_default(output);
+            let consistent_read = context.consistent_read_or_table_default(consistent_read);
+            data::get_item(
+                context,
+                GetItemParams {
+                    pval,
+                    svals: sval,
+                    sort_keys,
+                    keys_file,
+                    consistent_read,
+                    field,
+                    replica_regions: replica_region,
+                    humanize,
+                },
+            )
+            .await
+        }
+        cmd::Sub::Put {
+            pval,
+            sval,
+            item,
+            if_not_exists,
+            force,
+        } => data::put_item(context, pval, sval, item, if_not_exists, force).await,
+        cmd::Sub::Del {
+            keys_file: Some(keys_file),
+            yes,
+            ..
+        } => {
+            // Keys in the file are deleted in the same way as `dy bwrite --keys-file`, which asks for confirmation as well.
+            batch::batch_write_item(
+                context,
+                batch::BatchWriteSources {
+                    keys_file: Some(keys_file),
+                    ..Default::default()
+                },
+                batch::DuplicatePolicy::Error,
+                yes,
+            )
+            .await?
+        }
+        cmd::Sub::Del {
+            pval,
+            sval,
+            condition,
+            keys_file: None,
+            yes,
+        } => {
+            app::confirm_operation(context, app::SafetyOperation::Delete, yes, || {
+                format!(
+                    "You're trying to delete an item from the table '{}'",
+                    context.effective_table_name()
+                )
+            })
+            .await;
+            let pval = pval.expect("pval is required unless --keys-file is given");
+            data::delete_item(context, pval, sval, condition).await
         }
-        cmd::Sub::Put { pval, sval, item } => data::put_item(context, pval, sval, item).await,
-        cmd::Sub::Del { pval, sval } => data::delete_item(context, pval, sval).await,
         cmd::Sub::Upd {
             pval,
             sval,
             set,
             remove,
             atomic_counter,
+            by,
+            counter_init,
+            where_condition,
+            dry_run,
+            concurrency,
         } => {
-            if let Some(target) = atomic_counter {
-                data::atomic_counter(context, pval, sval, set, remove, target).await;
+            if let Some(condition) = where_condition {
+                let params = data::BulkUpdateParams {
+                    pval,
+                    condition,
+                    set,
+                    remove,
+                    dry_run,
+                    concurrency: concurrency.map(usize::from),
+                };
+                data::bulk_update(context, params).await;
             } else {
-                data::update_item(context, pval, sval, set, remove).await;
+                let pval = pval.expect("PVAL is required unless --where is given");
+                if let Some(target_attrs) = atomic_counter {
+                    let counter = data::CounterParams {
+                        target_attrs,
+                        step: by,
+                        init: counter_init,
+                    };
+                    data::atomic_counter(context, pval, sval, set, remove, counter).await;
+                } else {
+                    data::update_item(context, pval, sval, set, remove).await;
+                }
             }
         }
-        cmd::Sub::Bwrite { puts, dels, input } => {
-            batch::batch_write_item(context, puts, dels, input).await?
+        cmd::Sub::Bwrite {
+            puts,
+            dels,
+            keys_file,
+            table_map,
+            input,
+            on_duplicate,
+            yes,
+        } => {
+            batch::batch_write_item(
+                context,
+                batch::BatchWriteSources {
+                    puts,
+                    dels,
+                    keys_file,
+                    table_map,
+                    input_file: input,
+                },
+                batch::DuplicatePolicy::new(on_duplicate.as_deref()),
+                yes,
+            )
+            .await?
         }
-        cmd::Sub::List { all_regions } => {
-            if all_regions {
-                control::list_tables_all_regions(context).await
+        cmd::Sub::List {
+            all_regions,
+            regions,
+            detail,
+            sort_by,
+        } => {
+            if all_regions || regions.is_some() {
+                control::list_tables_all_regions(context, regions, detail, sort_by.as_deref()).await
             } else {
-                control::list_tables(context, None).await
+                control::list_tables(context, None, detail, sort_by.as_deref()).await
             }
         }
         cmd::Sub::Desc {
             target_table_to_desc,
             all_tables,
+            index,
+            streams,
             output,
         } => {
             context.output = output;
             if all_tables {
                 control::describe_all_tables(context).await
             } else {
-                control::describe_table(context, target_table_to_desc).await
+                control::describe_table(context, target_table_to_desc, index, streams).await
             }
         }
         cmd::Sub::Use {
@@ -207,8 +453,23 @@ async fn dispatch(context: &mut app::Context, subcommand: cmd::Sub) -> Result<()
                 );
             }
             cmd::ConfigSub::Clear => app::remove_dynein_files()?,
+            cmd::ConfigSub::Get { key } => app::get_config(context, key).await?,
+            cmd::ConfigSub::Set { key, value } => app::set_config(context, &key, &value)?,
         },
 
+        cmd::Sub::Whoami => app::whoami(context).await?,
+
+        cmd::Sub::Completion {
+            shell,
+            cached_tables,
+        } => {
+            if cached_tables {
+                completion::print_cached_tables(context)
+            } else if let Some(shell) = shell {
+                completion::print_completion_script(&shell)?
+            }
+        }
+
         cmd::Sub::Bootstrap { list, sample } => {
             if list {
                 bootstrap::list_samples()
@@ -220,20 +481,121 @@ async fn dispatch(context: &mut app::Context, subcommand: cmd::Sub) -> Result<()
         cmd::Sub::Export {
             attributes,
             keys_only,
+            avro_schema,
             output_file,
             format,
-        } => transfer::export(context, attributes, keys_only, output_file, format).await?,
+            transform,
+            checkpoint,
+            consistent,
+            s3_bucket,
+            s3_prefix,
+        } => {
+            transfer::export(
+                context,
+                output_file,
+                transfer::ExportParams {
+                    attributes,
+                    keys_only,
+                    avro_schema,
+                    format,
+                    transform,
+                    checkpoint,
+                    consistent,
+                    s3_bucket,
+                    s3_prefix,
+                },
+            )
+            .await?
+        }
         cmd::Sub::Import {
             input_file,
             format,
             enable_set_inference,
-        } => transfer::import(context, input_file, format, enable_set_inference).await?,
-        cmd::Sub::Backup { list, all_tables } => {
+            set_attrs,
+            list_attrs,
+            binary_attributes,
+            on_error,
+            dlq_file,
+            update_mode,
+            transform,
+            normalize,
+            yes,
+            force,
+            boost_wcu,
+        } => {
+            app::confirm_operation(context, app::SafetyOperation::Import, yes, || {
+                // Without a target table, items are imported into tables named in the input, e.g. a multi-table JSON.
+                match context.table_name_if_specified() {
+                    Some(table_name) => format!(
+                        "You're trying to import items from '{}' into the table '{}'",
+                        input_file, table_name
+                    ),
+                    None => format!(
+                        "You're trying to import items from '{}' into the tables named in it",
+                        input_file
+                    ),
+                }
+            })
+            .await;
+            transfer::import(
+                context,
+                input_file,
+                transfer::ImportParams {
+                    format,
+                    enable_set_inference,
+                    set_attrs,
+                    list_attrs,
+                    binary_attributes,
+                    on_error,
+                    dlq_file,
+                    update_mode,
+                    transform,
+                    force,
+                    normalize,
+                    boost_wcu,
+                },
+            )
+            .await?
+        }
+        cmd::Sub::LocalQuery {
+            file,
+            format,
+            filter,
+            keys,
+            limit,
+            output,
+        } => {
+            context.output = context.output_or_table_default(output);
+            local::local_query(
+                context,
+                local::LocalQueryParams {
+                    file,
+                    format,
+                    filter,
+                    keys,
+                    limit,
+                },
+            )
+            .await?
+        }
+        cmd::Sub::Backup {
+            list,
+            all_tables,
+            wait,
+            name_template,
+            if_not_exists_today,
+            output,
+        } => {
+            context.output = output;
             if list {
                 control::list_backups(context, all_tables).await?
             } else {
                 control::backup(
-                    context, all_tables, /* all_tables is simply ignored for "backup" */
+                    context,
+                    all_tables, /* all_tables is simply ignored for "backup" */
+                    wait,
+                    name_template,
+                    if_not_exists_today,
                 )
                 .await
             }
@@ -241,11 +603,144 @@ async fn dispatch(context: &mut app::Context, subcommand: cmd::Sub) -> Result<()
         cmd::Sub::Restore {
             backup_name,
             restore_name,
-        } => control::restore(context, backup_name, restore_name).await,
+            no_wait,
+        } => control::restore(context, backup_name, restore_name, !no_wait).await,
+        cmd::Sub::Expr { grandchild } => match grandchild {
+            cmd::ExprSub::Set { expression } => {
+                data::preview_expression(context, data::ExpressionPreviewType::Set, expression)
+                    .await
+            }
+            cmd::ExprSub::Remove { expression } => {
+                data::preview_expression(context, data::ExpressionPreviewType::Remove, expression)
+                    .await
+            }
+            cmd::ExprSub::Sortkey { expression, key } => {
+                data::preview_expression(
+                    context,
+                    data::ExpressionPreviewType::SortKey(key),
+                    expression,
+                )
+                .await
+            }
+            cmd::ExprSub::Condition { expression } => {
+                data::preview_expression(
+                    context,
+                    data::ExpressionPreviewType::Condition,
+                    expression,
+                )
+                .await
+            }
+        },
+
+        cmd::Sub::Migrate { grandchild } => match grandchild {
+            cmd::MigrateSub::RenameAttr {
+                from,
+                to,
+                concurrency,
+                checkpoint,
+            } => {
+                let params = migrate::RenameAttrParams {
+                    from,
+                    to,
+                    concurrency: concurrency.map(usize::from),
+                    checkpoint,
+                };
+                migrate::rename_attr(context, params).await?
+            }
+            cmd::MigrateSub::ConvertType {
+                attr,
+                to,
+                concurrency,
+            } => {
+                let params = migrate::ConvertTypeParams {
+                    attr,
+                    to,
+                    concurrency: concurrency.map(usize::from),
+                };
+                migrate::convert_type(context, params).await
+            }
+        },
+
+        cmd::Sub::Analyze { grandchild } => match grandchild {
+            cmd::AnalyzeSub::Expired {
+                ttl_attr,
+                delete,
+                output,
+            } => {
+                context.output = output;
+                analyze::expired(context, analyze::ExpiredParams { ttl_attr, delete }).await
+            }
+        },
+
+        cmd::Sub::Cost { grandchild } => match grandchild {
+            cmd::CostSub::Estimate { days, output } => {
+                context.output = output;
+                cost::estimate(context, cost::EstimateParams { days }).await
+            }
+        },
+
+        cmd::Sub::History { grandchild } => match grandchild {
+            cmd::HistorySub::List { limit } => history::list(context, limit)?,
+            cmd::HistorySub::Show { id } => history::show(id)?,
+        },
+
+        cmd::Sub::Entity { grandchild } => match grandchild {
+            cmd::EntitySub::Get {
+                name,
+                params,
+                consistent_read,
+                output,
+            } => entity::get(context, &name, &params, consistent_read, output).await,
+            cmd::EntitySub::Put {
+                name,
+                params,
+                item,
+                if_not_exists,
+            } => entity::put(context, &name, &params, item, if_not_exists).await,
+            cmd::EntitySub::List => entity::list(context)?,
+        },
+
+        cmd::Sub::Key { grandchild } => match grandchild {
+            cmd::KeySub::Build { entity, values } => entity::build_key(context, &entity, &values)?,
+            cmd::KeySub::Parse { pval, sval, entity } => {
+                entity::parse_key(context, &pval, sval.as_deref(), entity.as_deref())?
+            }
+        },
     }
     Ok(())
 }
 
+/// Runs a builtin of the shell mode, which is available for --command option as well.
+async fn run_builtin(context: &mut app::Context, builtin: shell::BuiltinCommands) {
+    use shell::BuiltinCommands;
+
+    match builtin {
+        // `exit` is handled by the caller as it ends the loop of commands.
+        BuiltinCommands::Exit => (),
+        BuiltinCommands::Help(topic) => help::print_topic(topic),
+        BuiltinCommands::Usage => shell::print_usage(),
+        BuiltinCommands::Use(table) => {
+            // `dy use` prefers --table, which would make it impossible to switch tables in the session.
+            context.overwritten_table_name = None;
+            if let Err(e) = app::use_table(context, Some(table)).await {
+                eprintln!("{}", e)
+            }
+        }
+        BuiltinCommands::Region(region) => {
+            *context = context.clone().with_region(&region);
+            println!("Now you're using the region '{}' in this session.", region);
+        }
+        BuiltinCommands::Output(format) => {
+            println!(
+                "Now the default output format is '{}' in this session.",
+                format
+            );
+            context.shell_output = Some(format);
+        }
+        BuiltinCommands::Status => shell::print_status(context).await,
+    }
+}
+
 /* =================================================
    main() function
    =================================================
@@ -260,11 +755,32 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // when --region <region-name e.g. ap-northeast-1>, use the region. when --region local, use DynamoDB local.
     // --region/--table option can be passed as a top-level or subcommand-level (i.e. global).
     let mut context = app::Context::new(c.region, c.port, c.table)?;
+    context.ca_bundle = c.ca_bundle;
+    context.apply_connection_options(c.connect_timeout, c.read_timeout, c.max_retries);
+    if c.precise_numbers {
+        data::enable_precise_numbers();
+    }
+    if c.humanize_epochs {
+        output::enable_humanize_epochs();
+    }
+    if let Some(ellipsis) = context.configured_ellipsis() {
+        data::set_ellipsis(ellipsis);
+    }
+    if !c.no_redact {
+        if let Some(names) = context.configured_redaction() {
+            output::set_redacted_attributes(names);
+        }
+    }
     debug!("Initial command context: {:?}", &context);
 
     if let Some(child) = c.child {
         // subcommand
-        dispatch(&mut context, child).await?
+        dispatch(&mut context, child).await?;
+        // Unknown data types are rendered as placeholders to keep output going, but it's an error in the strict mode.
+        if data::unknown_attrval_found() && context.should_strict() {
+            error!("Some attributes were not output correctly because their data types are unknown to this version of dynein.");
+            std::process::exit(1);
+        }
     } else if c.shell {
         // shell mode
         use shell::BuiltinCommands;
@@ -277,10 +793,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
             let child = reader.read_line()?;
             match child {
                 Builtin(BuiltinCommands::Exit) => break,
+                Builtin(builtin) => run_builtin(&mut context, builtin).await,
                 Eof => break,
                 Command(child) => {
                     debug!("context before execution of shell command: {:#?}", context);
-                    if let Err(e) = dispatch(&mut context, child).await {
+                    if let Err(e) = dispatch(&mut context, *child).await {
                         eprintln!("{}", e)
                     }
                     debug!("context after execution of shell command: {:#?}", context)
@@ -290,6 +807,26 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 }
             }
         }
+    } else if !c.commands.is_empty() {
+        // batch mode: commands given by -c/--command run in order, sharing the context as the shell mode does.
+        use shell::BuiltinCommands;
+        use shell::ShellInput::*;
+
+        for command in c.commands {
+            match shell::parse_command(&command) {
+                Builtin(BuiltinCommands::Exit) | Eof => break,
+                Builtin(builtin) => run_builtin(&mut context, builtin).await,
+                Command(child) => dispatch(&mut context, *child).await?,
+                ParseError(e) => {
+                    debug!("parse error occur: {:#?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        if data::unknown_attrval_found() && context.should_strict() {
+            error!("Some attributes were not output correctly because their data types are unknown to this version of dynein.");
+            std::process::exit(1);
+        }
     } else if c.third_party_attribution {
         // Load 3rd party attribution file
         let compressed_data = include_bytes!("./resources/attribution/ThirdPartyAttribution.br");
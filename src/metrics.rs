@@ -0,0 +1,513 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module emits metrics and a trace span of long-running jobs, i.e. export, import and migrate,
+// to an OpenTelemetry collector with OTLP/HTTP in JSON encoding, so that jobs running in containers can be observed
+// by existing monitoring stacks such as Prometheus via the collector. It's enabled only when the standard env var
+// OTEL_EXPORTER_OTLP_ENDPOINT (or OTEL_EXPORTER_OTLP_METRICS_ENDPOINT) is set, and recording is a no-op otherwise.
+use std::{
+    collections::BTreeMap,
+    env,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use hyper::{client::HttpConnector, header::CONTENT_TYPE, Body, Client, Request};
+use hyper_rustls::HttpsConnector;
+use log::debug;
+use serde_json::{json, Value as JsonValue};
+
+use super::app;
+use super::cmd;
+use super::network;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+const ENDPOINT_ENV_VAR_NAME: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+const METRICS_ENDPOINT_ENV_VAR_NAME: &str = "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT";
+const TRACES_ENDPOINT_ENV_VAR_NAME: &str = "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT";
+/// Headers sent to the collector, e.g. "api-key=secret,team=data".
+const HEADERS_ENV_VAR_NAME: &str = "OTEL_EXPORTER_OTLP_HEADERS";
+const SERVICE_NAME_ENV_VAR_NAME: &str = "OTEL_SERVICE_NAME";
+/// Interval of exporting metrics in milliseconds while a job is running.
+const EXPORT_INTERVAL_ENV_VAR_NAME: &str = "OTEL_METRIC_EXPORT_INTERVAL";
+const DEFAULT_SERVICE_NAME: &str = "dynein";
+const DEFAULT_EXPORT_INTERVAL: Duration = Duration::from_secs(60);
+/// Upper bounds of buckets of the latency histogram in seconds.
+const LATENCY_BOUNDS_SECONDS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+/// AGGREGATION_TEMPORALITY_CUMULATIVE of OTLP. Counters hold totals since the job started.
+const CUMULATIVE: i32 = 2;
+/// SPAN_KIND_INTERNAL of OTLP.
+const SPAN_KIND_INTERNAL: i32 = 1;
+/// STATUS_CODE_OK and STATUS_CODE_ERROR of OTLP.
+const STATUS_CODE_OK: i32 = 1;
+const STATUS_CODE_ERROR: i32 = 2;
+
+/// The recorder of the running job, which is set only when metrics are enabled.
+/// It's replaced by every job, so that jobs in the shell mode or --command don't share metrics.
+static CURRENT: Mutex<Option<Arc<Recorder>>> = Mutex::new(None);
+
+struct Recorder {
+    client: Client<HttpsConnector<HttpConnector>>,
+    metrics_url: String,
+    traces_url: Option<String>,
+    headers: Vec<(String, String)>,
+    service_name: String,
+    /// Job name, e.g. "import" or "migrate rename-attr".
+    job: &'static str,
+    table: Option<String>,
+    started_at: SystemTime,
+    items: AtomicU64,
+    retries: AtomicU64,
+    /// Latency histograms per API operation, e.g. "Scan" or "BatchWriteItem".
+    latencies: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Histogram {
+    /// Counts per bucket, which has one more bucket than LATENCY_BOUNDS_SECONDS for values above the last bound.
+    bucket_counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+/// A running job whose metrics are exported periodically until it finishes.
+pub struct Job {
+    recorder: Arc<Recorder>,
+    periodic_export: tokio::task::JoinHandle<()>,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Starts recording metrics if the subcommand is a long-running job and an OTLP endpoint is configured by env vars.
+pub fn start(cx: &app::Context, sub: &cmd::Sub) -> Option<Job> {
+    let job = job_name(sub)?;
+    let (metrics_url, traces_url) = endpoints()?;
+    let client = match network::https_connector() {
+        Ok(connector) => Client::builder().build(connector),
+        Err(e) => {
+            eprintln!("WARN: Metrics are not exported: {}", e);
+            return None;
+        }
+    };
+    debug!(
+        "exporting metrics of '{}' to {} and the span to {:?}",
+        job, metrics_url, traces_url
+    );
+    let recorder = Arc::new(Recorder {
+        client,
+        metrics_url,
+        traces_url,
+        headers: env::var(HEADERS_ENV_VAR_NAME)
+            .map(|headers| parse_headers(&headers))
+            .unwrap_or_default(),
+        service_name: env::var(SERVICE_NAME_ENV_VAR_NAME)
+            .unwrap_or_else(|_| String::from(DEFAULT_SERVICE_NAME)),
+        job,
+        table: cx.table_name_if_specified(),
+        started_at: SystemTime::now(),
+        items: AtomicU64::new(0),
+        retries: AtomicU64::new(0),
+        latencies: Mutex::new(BTreeMap::new()),
+    });
+    *CURRENT.lock().unwrap() = Some(recorder.clone());
+    let interval = env::var(EXPORT_INTERVAL_ENV_VAR_NAME)
+        .ok()
+        .and_then(|ms| ms.parse().ok())
+        .map_or(DEFAULT_EXPORT_INTERVAL, Duration::from_millis);
+    let periodic_recorder = recorder.clone();
+    let periodic_export = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await; // The first tick completes immediately.
+        loop {
+            ticker.tick().await;
+            periodic_recorder.export_metrics().await;
+        }
+    });
+    Some(Job {
+        recorder,
+        periodic_export,
+    })
+}
+
+impl Job {
+    /// Exports the final metrics and the span of the job, whose status is an error unless it has succeeded.
+    pub async fn finish(self, succeeded: bool) {
+        self.periodic_export.abort();
+        {
+            let mut current = CURRENT.lock().unwrap();
+            if current
+                .as_ref()
+                .is_some_and(|recorder| Arc::ptr_eq(recorder, &self.recorder))
+            {
+                *current = None;
+            }
+        }
+        self.recorder.flush(succeeded).await;
+    }
+}
+
+/// Exits the process after exporting the final metrics and the span of the running job as failed, if any.
+/// Use this instead of `std::process::exit` in jobs, which never return to the caller to finish them.
+pub fn exit(code: i32) -> ! {
+    let recorder = CURRENT.lock().unwrap().take();
+    if let Some(recorder) = recorder {
+        // Blocking in place is possible only on the multi-thread runtime, which is the one of `dy`.
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| handle.block_on(recorder.flush(false)));
+            }
+            _ => debug!("metrics of the failed job are not exported outside of the runtime"),
+        }
+    }
+    std::process::exit(code)
+}
+
+/// Adds the number of items exported, imported or migrated.
+pub fn add_items(n: usize) {
+    if let Some(recorder) = current() {
+        recorder.items.fetch_add(n as u64, Ordering::Relaxed);
+    }
+}
+
+/// Adds the number of retries, e.g. of unprocessed items due to throttling.
+pub fn add_retries(n: usize) {
+    if let Some(recorder) = current() {
+        recorder.retries.fetch_add(n as u64, Ordering::Relaxed);
+    }
+}
+
+/// Awaits an API call, recording its latency for the operation.
+pub async fn timed<F: Future>(operation: &'static str, call: F) -> F::Output {
+    let started_at = Instant::now();
+    let output = call.await;
+    if let Some(recorder) = current() {
+        recorder
+            .latencies
+            .lock()
+            .unwrap()
+            .entry(operation)
+            .or_insert_with(Histogram::new)
+            .record(started_at.elapsed());
+    }
+    output
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+fn current() -> Option<Arc<Recorder>> {
+    CURRENT.lock().unwrap().clone()
+}
+
+fn job_name(sub: &cmd::Sub) -> Option<&'static str> {
+    match sub {
+        cmd::Sub::Export { .. } => Some("export"),
+        cmd::Sub::Import { .. } => Some("import"),
+        cmd::Sub::Migrate { grandchild } => Some(match grandchild {
+            cmd::MigrateSub::RenameAttr { .. } => "migrate rename-attr",
+            cmd::MigrateSub::ConvertType { .. } => "migrate convert-type",
+        }),
+        _ => None,
+    }
+}
+
+/// Returns URLs to send metrics and spans to. Spans are sent only if the base endpoint or the traces endpoint is set.
+fn endpoints() -> Option<(String, Option<String>)> {
+    let base = env::var(ENDPOINT_ENV_VAR_NAME)
+        .ok()
+        .map(|endpoint| endpoint.trim_end_matches('/').to_owned());
+    let metrics = env::var(METRICS_ENDPOINT_ENV_VAR_NAME)
+        .ok()
+        .or_else(|| base.as_ref().map(|base| format!("{}/v1/metrics", base)))?;
+    let traces = env::var(TRACES_ENDPOINT_ENV_VAR_NAME)
+        .ok()
+        .or_else(|| base.as_ref().map(|base| format!("{}/v1/traces", base)));
+    Some((metrics, traces))
+}
+
+/// Parses headers in the format of OTEL_EXPORTER_OTLP_HEADERS, i.e. "key1=value1,key2=value2".
+fn parse_headers(s: &str) -> Vec<(String, String)> {
+    s.split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.trim().to_owned(), v.trim().to_owned()))
+        .filter(|(k, _)| !k.is_empty())
+        .collect()
+}
+
+impl Histogram {
+    fn new() -> Histogram {
+        Histogram {
+            bucket_counts: vec![0; LATENCY_BOUNDS_SECONDS.len() + 1],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    fn record(&mut self, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        let bucket = LATENCY_BOUNDS_SECONDS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(LATENCY_BOUNDS_SECONDS.len());
+        self.bucket_counts[bucket] += 1;
+        self.count += 1;
+        self.sum += secs;
+    }
+}
+
+impl Recorder {
+    async fn flush(&self, succeeded: bool) {
+        self.export_metrics().await;
+        self.export_span(succeeded).await;
+    }
+
+    async fn export_metrics(&self) {
+        let body = self.metrics_json(SystemTime::now());
+        if let Err(e) = self.post(&self.metrics_url, body).await {
+            eprintln!(
+                "WARN: Failed to export metrics to {}: {}",
+                self.metrics_url, e
+            );
+        }
+    }
+
+    async fn export_span(&self, succeeded: bool) {
+        let url = match &self.traces_url {
+            Some(url) => url,
+            None => return,
+        };
+        let body = self.span_json(SystemTime::now(), succeeded);
+        if let Err(e) = self.post(url, body).await {
+            eprintln!("WARN: Failed to export the span to {}: {}", url, e);
+        }
+    }
+
+    async fn post(&self, url: &str, body: JsonValue) -> Result<(), BoxError> {
+        let mut request = Request::post(url).header(CONTENT_TYPE, "application/json");
+        for (k, v) in &self.headers {
+            request = request.header(k, v);
+        }
+        let res = self
+            .client
+            .request(request.body(Body::from(body.to_string()))?)
+            .await?;
+        if !res.status().is_success() {
+            return Err(format!("the collector responded with {}", res.status()).into());
+        }
+        Ok(())
+    }
+
+    /// Attributes attached to all data points and the span.
+    fn attributes(&self) -> Vec<JsonValue> {
+        let mut attributes = vec![string_attribute("dynein.job", self.job)];
+        if let Some(table) = &self.table {
+            attributes.push(string_attribute("dynein.table", table));
+        }
+        attributes
+    }
+
+    fn resource(&self) -> JsonValue {
+        json!({ "attributes": [string_attribute("service.name", &self.service_name)] })
+    }
+
+    /// Builds ExportMetricsServiceRequest of OTLP in JSON.
+    fn metrics_json(&self, now: SystemTime) -> JsonValue {
+        let (start, time) = (unix_nanos(self.started_at), unix_nanos(now));
+        let items = self.items.load(Ordering::Relaxed);
+        let elapsed = now
+            .duration_since(self.started_at)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            items as f64 / elapsed
+        } else {
+            0.0
+        };
+        let counter = |name: &str, unit: &str, description: &str, value: u64| {
+            json!({
+                "name": name,
+                "unit": unit,
+                "description": description,
+                "sum": {
+                    "aggregationTemporality": CUMULATIVE,
+                    "isMonotonic": true,
+                    "dataPoints": [{
+                        "attributes": self.attributes(),
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": time,
+                        "asInt": value.to_string(),
+                    }],
+                },
+            })
+        };
+        let mut metrics = vec![
+            counter(
+                "dynein.items",
+                "{item}",
+                "Items exported, imported or migrated",
+                items,
+            ),
+            counter(
+                "dynein.retries",
+                "{retry}",
+                "Retries of unprocessed items, e.g. due to throttling",
+                self.retries.load(Ordering::Relaxed),
+            ),
+            json!({
+                "name": "dynein.items.rate",
+                "unit": "{item}/s",
+                "description": "Average items per second since the job started",
+                "gauge": {
+                    "dataPoints": [{
+                        "attributes": self.attributes(),
+                        "timeUnixNano": time,
+                        "asDouble": rate,
+                    }],
+                },
+            }),
+        ];
+        let latency_points: Vec<JsonValue> = self
+            .latencies
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(operation, histogram)| {
+                let mut attributes = self.attributes();
+                attributes.push(string_attribute("dynein.operation", operation));
+                let bucket_counts: Vec<String> =
+                    histogram.bucket_counts.iter().map(u64::to_string).collect();
+                json!({
+                    "attributes": attributes,
+                    "startTimeUnixNano": start,
+                    "timeUnixNano": time,
+                    "count": histogram.count.to_string(),
+                    "sum": histogram.sum,
+                    "bucketCounts": bucket_counts,
+                    "explicitBounds": LATENCY_BOUNDS_SECONDS,
+                })
+            })
+            .collect();
+        if !latency_points.is_empty() {
+            metrics.push(json!({
+                "name": "dynein.request.duration",
+                "unit": "s",
+                "description": "Latency of DynamoDB API calls",
+                "histogram": {
+                    "aggregationTemporality": CUMULATIVE,
+                    "dataPoints": latency_points,
+                },
+            }));
+        }
+        json!({
+            "resourceMetrics": [{
+                "resource": self.resource(),
+                "scopeMetrics": [{
+                    "scope": { "name": DEFAULT_SERVICE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                    "metrics": metrics,
+                }],
+            }],
+        })
+    }
+
+    /// Builds ExportTraceServiceRequest of OTLP in JSON, which has a span of the whole job.
+    fn span_json(&self, now: SystemTime, succeeded: bool) -> JsonValue {
+        let mut attributes = self.attributes();
+        attributes.push(json!({
+            "key": "dynein.items",
+            "value": { "intValue": self.items.load(Ordering::Relaxed).to_string() },
+        }));
+        json!({
+            "resourceSpans": [{
+                "resource": self.resource(),
+                "scopeSpans": [{
+                    "scope": { "name": DEFAULT_SERVICE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                    "spans": [{
+                        "traceId": format!("{:032x}", rand::random::<u128>()),
+                        "spanId": format!("{:016x}", rand::random::<u64>()),
+                        "name": format!("dy {}", self.job),
+                        "kind": SPAN_KIND_INTERNAL,
+                        "startTimeUnixNano": unix_nanos(self.started_at),
+                        "endTimeUnixNano": unix_nanos(now),
+                        "attributes": attributes,
+                        "status": {
+                            "code": if succeeded { STATUS_CODE_OK } else { STATUS_CODE_ERROR },
+                        },
+                    }],
+                }],
+            }],
+        })
+    }
+}
+
+fn string_attribute(key: &str, value: &str) -> JsonValue {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// 64-bit integers are encoded as strings in OTLP JSON.
+fn unix_nanos(t: SystemTime) -> String {
+    t.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::new();
+        histogram.record(Duration::from_millis(3));
+        histogram.record(Duration::from_millis(40));
+        histogram.record(Duration::from_secs(10));
+        let mut bucket_counts = vec![0; 11];
+        bucket_counts[0] = 1;
+        bucket_counts[3] = 1;
+        bucket_counts[10] = 1;
+        assert_eq!(histogram.bucket_counts, bucket_counts);
+        assert_eq!(histogram.count, 3);
+        assert!((histogram.sum - 10.043).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        assert_eq!(
+            parse_headers("api-key=secret, team = data,broken"),
+            vec![
+                (String::from("api-key"), String::from("secret")),
+                (String::from("team"), String::from("data")),
+            ]
+        );
+        assert_eq!(parse_headers(""), vec![]);
+    }
+}
@@ -0,0 +1,491 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements `dy migrate` subcommands, which apply a schema change to all items in a table.
+// A migration scans the table page by page and updates matching items with UpdateItem. Progress can be recorded
+// in a checkpoint file after each page, so that an interrupted migration resumes where it stopped.
+// Each update is guarded by a ConditionExpression, thus processing the same page twice is harmless.
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    io::{self, IsTerminal},
+    path::Path,
+};
+
+use aws_sdk_dynamodb::{
+    types::{AttributeValue, Select},
+    Client as DynamoDbSdkClient,
+};
+use futures::stream::{self, StreamExt};
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use super::app;
+use super::batch;
+use super::data;
+use super::metrics;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Default number of UpdateItem requests in flight during a migration.
+const MIGRATION_CONCURRENCY: usize = 8;
+
+const FROM_NAME_REF: &str = "#DYNEIN_FROM";
+const TO_NAME_REF: &str = "#DYNEIN_TO";
+const ATTR_NAME_REF: &str = "#DYNEIN_ATTR";
+const OLD_VALUE_REF: &str = ":DYNEIN_OLD";
+const NEW_VALUE_REF: &str = ":DYNEIN_NEW";
+const TYPE_VALUE_REF: &str = ":DYNEIN_TYPE";
+
+pub struct RenameAttrParams {
+    pub from: String,
+    pub to: String,
+    pub concurrency: Option<usize>,
+    pub checkpoint: Option<String>,
+}
+
+pub struct ConvertTypeParams {
+    pub attr: String,
+    /// "N" or "S".
+    pub to: String,
+    pub concurrency: Option<usize>,
+}
+
+/// Contents of the checkpoint file of `dy migrate rename-attr`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct RenameAttrCheckpoint {
+    table: String,
+    from: String,
+    to: String,
+    renamed: usize,
+    skipped: usize,
+    /// LastEvaluatedKey of the last completed page in DynamoDB JSON.
+    last_evaluated_key: HashMap<String, JsonValue>,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Executed when you call `$ dy migrate rename-attr <FROM> <TO>`.
+/// Items which have FROM are updated by `SET TO = FROM REMOVE FROM`, only if they still have FROM and don't have TO yet.
+/// After all items are processed, the table is scanned again to verify that no item has FROM any more.
+pub async fn rename_attr(
+    cx: &app::Context,
+    params: RenameAttrParams,
+) -> Result<(), Box<dyn Error>> {
+    let ts: app::TableSchema = app::table_schema(cx).await;
+    if params.from == params.to {
+        error!("The new attribute name is the same as the current one.");
+        metrics::exit(1);
+    }
+    ensure_not_primary_key(&ts, &[params.from.as_str(), params.to.as_str()]);
+
+    let mut checkpoint = match &params.checkpoint {
+        Some(path) if Path::new(path).exists() => {
+            let checkpoint = load_checkpoint(path)?;
+            if (&checkpoint.table, &checkpoint.from, &checkpoint.to)
+                != (&ts.name, &params.from, &params.to)
+            {
+                error!(
+                    "The checkpoint file '{}' is for renaming '{}' to '{}' in the table '{}'. Remove it or give another file.",
+                    path, checkpoint.from, checkpoint.to, checkpoint.table
+                );
+                metrics::exit(1);
+            }
+            println!(
+                "Resuming the migration from the checkpoint file '{}' ({} items renamed so far).",
+                path, checkpoint.renamed
+            );
+            checkpoint
+        }
+        _ => RenameAttrCheckpoint {
+            table: ts.name.to_owned(),
+            from: params.from.to_owned(),
+            to: params.to.to_owned(),
+            renamed: 0,
+            skipped: 0,
+            last_evaluated_key: HashMap::new(),
+        },
+    };
+
+    let names = HashMap::from([
+        (FROM_NAME_REF.to_owned(), params.from.to_owned()),
+        (TO_NAME_REF.to_owned(), params.to.to_owned()),
+    ]);
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+    let concurrency = params.concurrency.unwrap_or(MIGRATION_CONCURRENCY);
+    let show_progress = io::stderr().is_terminal();
+
+    let mut last_evaluated_key = if checkpoint.last_evaluated_key.is_empty() {
+        None
+    } else {
+        let key = serde_json::to_value(&checkpoint.last_evaluated_key)?;
+        Some(batch::ddbjson_attributes_to_attrvals(&key))
+    };
+    loop {
+        let call = ddb
+            .scan()
+            .table_name(&ts.name)
+            .filter_expression(format!("attribute_exists({})", FROM_NAME_REF))
+            .expression_attribute_names(FROM_NAME_REF, &params.from)
+            .set_exclusive_start_key(last_evaluated_key)
+            .send();
+        let res = metrics::timed("Scan", call).await.unwrap_or_else(|e| {
+            debug!("Scan API call got an error -- {:?}", e);
+            app::bye_with_sdk_error(1, e);
+        });
+        let keys: Vec<HashMap<String, AttributeValue>> = res
+            .items
+            .unwrap_or_default()
+            .iter()
+            .map(|item| data::primary_key_of(&ts, item))
+            .collect();
+
+        let mut results = stream::iter(keys)
+            .map(|key| {
+                let call = ddb
+                    .update_item()
+                    .table_name(&ts.name)
+                    .set_key(Some(key))
+                    .update_expression(format!(
+                        "SET {to} = {from} REMOVE {from}",
+                        to = TO_NAME_REF,
+                        from = FROM_NAME_REF
+                    ))
+                    .condition_expression(format!(
+                        "attribute_exists({}) AND attribute_not_exists({})",
+                        FROM_NAME_REF, TO_NAME_REF
+                    ))
+                    .set_expression_attribute_names(Some(names.clone()))
+                    .send();
+                metrics::timed("UpdateItem", call)
+            })
+            .buffer_unordered(concurrency);
+        while let Some(result) = results.next().await {
+            match result {
+                Ok(_) => {
+                    checkpoint.renamed += 1;
+                    metrics::add_items(1);
+                }
+                // The item already has the new attribute, or has been renamed by someone else in the meantime.
+                Err(e)
+                    if e.as_service_error()
+                        .map_or(false, |se| se.is_conditional_check_failed_exception()) =>
+                {
+                    checkpoint.skipped += 1
+                }
+                Err(e) => {
+                    debug!("UpdateItem API call got an error -- {:?}", e);
+                    if let Some(path) = &params.checkpoint {
+                        eprintln!(
+                            "\nRun the same command again to resume the migration from the checkpoint file '{}'.",
+                            path
+                        );
+                    }
+                    app::bye_with_sdk_error(1, e);
+                }
+            }
+        }
+        if show_progress {
+            eprint!(
+                "\r{} items renamed, {} skipped",
+                checkpoint.renamed, checkpoint.skipped
+            );
+        }
+
+        match res.last_evaluated_key {
+            None => break,
+            Some(lek) => {
+                if let Some(path) = &params.checkpoint {
+                    checkpoint.last_evaluated_key = data::strip_item(&lek);
+                    save_checkpoint(path, &checkpoint)?;
+                }
+                last_evaluated_key = Some(lek);
+            }
+        }
+    }
+    if show_progress {
+        eprintln!();
+    }
+    if let Some(path) = &params.checkpoint {
+        if Path::new(path).exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    println!(
+        "Renamed '{}' to '{}' in {} items in the table '{}'.",
+        &params.from, &params.to, checkpoint.renamed, &ts.name
+    );
+
+    let remaining = count_items_with_attr(&ddb, &ts.name, &params.from).await;
+    if remaining > 0 {
+        error!(
+            "Verification failed: {} items still have '{}', e.g. because they already have '{}' as well. Check them with `dy scan`.",
+            remaining, &params.from, &params.to
+        );
+        metrics::exit(1);
+    }
+    println!("Verified that no item has '{}' any more.", &params.from);
+    Ok(())
+}
+
+/// Executed when you call `$ dy migrate convert-type --attr <ATTR> --to <N|S>`.
+/// Values of the attribute in the other type are converted, i.e. strings to numbers or numbers to strings.
+/// Each item is updated only if the value hasn't been changed since it's read. Strings which are not numbers are reported.
+pub async fn convert_type(cx: &app::Context, params: ConvertTypeParams) {
+    let ts: app::TableSchema = app::table_schema(cx).await;
+    ensure_not_primary_key(&ts, &[params.attr.as_str()]);
+    let from = if params.to == "N" { "S" } else { "N" };
+
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+    let concurrency = params.concurrency.unwrap_or(MIGRATION_CONCURRENCY);
+    let show_progress = io::stderr().is_terminal();
+
+    let (mut converted, mut skipped) = (0, 0);
+    let mut unconvertible: Vec<(HashMap<String, AttributeValue>, String)> = vec![];
+    let mut last_evaluated_key = None;
+    loop {
+        let call = ddb
+            .scan()
+            .table_name(&ts.name)
+            .filter_expression(format!(
+                "attribute_type({}, {})",
+                ATTR_NAME_REF, TYPE_VALUE_REF
+            ))
+            .expression_attribute_names(ATTR_NAME_REF, &params.attr)
+            .expression_attribute_values(TYPE_VALUE_REF, AttributeValue::S(from.to_owned()))
+            .set_exclusive_start_key(last_evaluated_key)
+            .send();
+        let res = metrics::timed("Scan", call).await.unwrap_or_else(|e| {
+            debug!("Scan API call got an error -- {:?}", e);
+            app::bye_with_sdk_error(1, e);
+        });
+
+        let mut updates = vec![];
+        for item in res.items.unwrap_or_default() {
+            let key = data::primary_key_of(&ts, &item);
+            match item.get(&params.attr).map(convert_value) {
+                Some(Ok((old, new))) => updates.push((key, old, new)),
+                Some(Err(value)) => unconvertible.push((key, value)),
+                None => (),
+            }
+        }
+
+        let mut results = stream::iter(updates)
+            .map(|(key, old, new)| {
+                let call = ddb
+                    .update_item()
+                    .table_name(&ts.name)
+                    .set_key(Some(key))
+                    .update_expression(format!("SET {} = {}", ATTR_NAME_REF, NEW_VALUE_REF))
+                    .condition_expression(format!("{} = {}", ATTR_NAME_REF, OLD_VALUE_REF))
+                    .expression_attribute_names(ATTR_NAME_REF, &params.attr)
+                    .expression_attribute_values(OLD_VALUE_REF, old)
+                    .expression_attribute_values(NEW_VALUE_REF, new)
+                    .send();
+                metrics::timed("UpdateItem", call)
+            })
+            .buffer_unordered(concurrency);
+        while let Some(result) = results.next().await {
+            match result {
+                Ok(_) => {
+                    converted += 1;
+                    metrics::add_items(1);
+                }
+                // The value has been changed by someone else after it's read.
+                Err(e)
+                    if e.as_service_error()
+                        .map_or(false, |se| se.is_conditional_check_failed_exception()) =>
+                {
+                    skipped += 1
+                }
+                Err(e) => {
+                    debug!("UpdateItem API call got an error -- {:?}", e);
+                    app::bye_with_sdk_error(1, e);
+                }
+            }
+        }
+        if show_progress {
+            eprint!(
+                "\r{} items converted, {} skipped, {} unconvertible",
+                converted,
+                skipped,
+                unconvertible.len()
+            );
+        }
+
+        match res.last_evaluated_key {
+            None => break,
+            lek => last_evaluated_key = lek,
+        }
+    }
+    if show_progress {
+        eprintln!();
+    }
+
+    println!(
+        "Converted '{}' from {} to {} in {} items in the table '{}'.",
+        &params.attr, from, &params.to, converted, &ts.name
+    );
+    if skipped > 0 {
+        println!(
+            "{} items were skipped as they were changed during the conversion. Run the command again to convert them.",
+            skipped
+        );
+    }
+    if !unconvertible.is_empty() {
+        for (key, value) in &unconvertible {
+            eprintln!(
+                "{} {:?} is not a number.",
+                serde_json::to_string(&data::convert_to_json(key)).unwrap(),
+                value
+            );
+        }
+        error!(
+            "{} values of '{}' couldn't be converted to numbers, as listed above.",
+            unconvertible.len(),
+            &params.attr
+        );
+        metrics::exit(1);
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Primary key attributes cannot be changed by UpdateItem, so migrations of them are rejected upfront.
+fn ensure_not_primary_key(ts: &app::TableSchema, attrs: &[&str]) {
+    if let Some(key) = std::iter::once(&ts.pk)
+        .chain(ts.sk.as_ref())
+        .find(|key| attrs.contains(&key.name.as_str()))
+    {
+        error!(
+            "'{}' is a primary key attribute of the table '{}', which cannot be migrated.",
+            key.name, &ts.name
+        );
+        metrics::exit(1);
+    }
+}
+
+/// Converts a string into a number or a number into a string, returning the old and new values.
+/// A string which is not a number is returned as an error.
+fn convert_value(value: &AttributeValue) -> Result<(AttributeValue, AttributeValue), String> {
+    let new = match value {
+        AttributeValue::S(s) => match app::parse_number_literal(s.trim()) {
+            Ok(n) => AttributeValue::N(n),
+            Err(_) => return Err(s.to_owned()),
+        },
+        AttributeValue::N(n) => AttributeValue::S(n.to_owned()),
+        // Scan returns only strings and numbers by the filter.
+        other => return Err(format!("{:?}", other)),
+    };
+    Ok((value.clone(), new))
+}
+
+/// Counts items which have the attribute, with Scan API whose Select is COUNT.
+async fn count_items_with_attr(ddb: &DynamoDbSdkClient, table: &str, attr: &str) -> i64 {
+    let mut count = 0;
+    let mut last_evaluated_key = None;
+    loop {
+        let call = ddb
+            .scan()
+            .table_name(table)
+            .select(Select::Count)
+            .filter_expression(format!("attribute_exists({})", FROM_NAME_REF))
+            .expression_attribute_names(FROM_NAME_REF, attr)
+            .set_exclusive_start_key(last_evaluated_key)
+            .send();
+        let res = metrics::timed("Scan", call).await.unwrap_or_else(|e| {
+            debug!("Scan API call got an error -- {:?}", e);
+            app::bye_with_sdk_error(1, e);
+        });
+        count += i64::from(res.count);
+        match res.last_evaluated_key {
+            None => return count,
+            lek => last_evaluated_key = lek,
+        }
+    }
+}
+
+fn load_checkpoint(path: &str) -> Result<RenameAttrCheckpoint, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn save_checkpoint(path: &str, checkpoint: &RenameAttrCheckpoint) -> Result<(), Box<dyn Error>> {
+    // Write to a temporary file first so that the checkpoint is never left half-written.
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, serde_json::to_string_pretty(checkpoint)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_value() {
+        let s = |v: &str| AttributeValue::S(v.to_owned());
+        let n = |v: &str| AttributeValue::N(v.to_owned());
+        assert_eq!(convert_value(&s(" 12.5 ")), Ok((s(" 12.5 "), n("12.5"))));
+        assert_eq!(convert_value(&s("-1e3")), Ok((s("-1e3"), n("-1e3"))));
+        assert_eq!(convert_value(&n("42")), Ok((n("42"), s("42"))));
+        assert_eq!(convert_value(&s("12 yen")), Err(String::from("12 yen")));
+        assert_eq!(convert_value(&s("NaN")), Err(String::from("NaN")));
+        assert_eq!(convert_value(&s("")), Err(String::from("")));
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("checkpoint.json");
+        let path = path.to_str().unwrap();
+        let checkpoint = RenameAttrCheckpoint {
+            table: String::from("users"),
+            from: String::from("mail"),
+            to: String::from("email"),
+            renamed: 10,
+            skipped: 1,
+            last_evaluated_key: data::strip_item(&HashMap::from([(
+                String::from("id"),
+                AttributeValue::N(String::from("42")),
+            )])),
+        };
+        save_checkpoint(path, &checkpoint)?;
+        let loaded = load_checkpoint(path)?;
+        assert_eq!(loaded, checkpoint);
+        assert_eq!(
+            batch::ddbjson_attributes_to_attrvals(&serde_json::to_value(
+                &loaded.last_evaluated_key
+            )?),
+            HashMap::from([(String::from("id"), AttributeValue::N(String::from("42")))])
+        );
+        Ok(())
+    }
+}
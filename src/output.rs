@@ -0,0 +1,955 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module renders items retrieved by read commands (scan, query and get) in the format given by --output option.
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    io::{self, Write},
+    ops::RangeInclusive,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+
+use aws_sdk_dynamodb::types::AttributeValue;
+use chrono::{DateTime, SecondsFormat};
+use log::error;
+
+use super::app;
+use super::data;
+use super::ddb::table;
+use super::parser::DocumentPath;
+
+pub type Item = HashMap<String, AttributeValue>;
+
+/// Whether numbers which look like epoch seconds or milliseconds are followed by ISO-8601 date times.
+/// It's enabled by --humanize-epochs option for the whole process.
+static HUMANIZE_EPOCHS: AtomicBool = AtomicBool::new(false);
+
+/// Names of attributes whose values are hidden in output, set by `display.redact` in config.yml unless --no-redact is given.
+static REDACTED_ATTRIBUTES: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Shown instead of values of redacted attributes.
+const REDACTED_PLACEHOLDER: &str = "<redacted>";
+
+/// Numbers are regarded as epoch seconds by --humanize-epochs if they're in this range, i.e. from 2000-01-01 to 2100-01-01,
+/// and as epoch milliseconds if they're in this range multiplied by 1000. Other numbers such as counts are rarely in these ranges.
+const PLAUSIBLE_EPOCH_SECONDS: RangeInclusive<i64> = 946_684_800..=4_102_444_800;
+
+/// Names of all registered output formats, in the order shown in help messages.
+/// Use this list for `value_parser` of --output options so that every read command accepts the same formats.
+pub const FORMAT_NAMES: [&str; 8] = [
+    "table", "json", "yaml", "raw", "csv", "jsonl", "markdown", "html",
+];
+
+/// Information about how items have been retrieved, which some formats need to decide columns to show.
+pub struct ItemsView<'a> {
+    pub ts: &'a app::TableSchema,
+    pub attributes: &'a Option<String>,
+    pub keys_only: bool,
+    /// Nested fields shown in their own columns of table output.
+    pub expand: &'a [ExpandedColumn],
+}
+
+/// Output of commands other than read commands, e.g. a table description of `dy desc` or backups of `dy backup --list`.
+/// json, yaml, raw and jsonl serialize the value as it is. csv, markdown and html render it as rows:
+/// one row per element for a list of mappings, otherwise one row per field.
+pub struct Document {
+    pub value: serde_yaml::Value,
+    /// Text for people shown by table format, e.g. aligned columns. Table format shows YAML of the value without it.
+    pub text: Option<String>,
+}
+
+/// A nested field pulled into its own column of table output by --expand option, e.g. `--expand address.city`.
+pub struct ExpandedColumn {
+    pub name: String,
+    pub path: DocumentPath,
+}
+
+pub trait OutputFormatter: Sync {
+    /// Name of the format, which is the value users pass to --output option.
+    fn name(&self) -> &'static str;
+
+    /// Writes a list of items, e.g. results of Scan or Query.
+    fn write_items(&self, w: &mut dyn Write, items: &[Item], view: &ItemsView) -> io::Result<()>;
+
+    /// Writes a single item, e.g. a result of GetItem. By default it's written as a list of one item.
+    fn write_item(&self, w: &mut dyn Write, item: &Item, view: &ItemsView) -> io::Result<()> {
+        self.write_items(w, std::slice::from_ref(item), view)
+    }
+
+    /// Writes a document which isn't made of items, e.g. a table description.
+    fn write_document(&self, w: &mut dyn Write, doc: &Document) -> io::Result<()>;
+}
+
+struct TableFormatter;
+struct JsonFormatter;
+struct YamlFormatter;
+struct RawFormatter;
+struct CsvFormatter;
+struct JsonlFormatter;
+struct MarkdownFormatter;
+struct HtmlFormatter;
+
+/// The central registry of output formats. A new format only needs to be added here and to FORMAT_NAMES.
+static FORMATTERS: [&dyn OutputFormatter; 8] = [
+    &TableFormatter,
+    &JsonFormatter,
+    &YamlFormatter,
+    &RawFormatter,
+    &CsvFormatter,
+    &JsonlFormatter,
+    &MarkdownFormatter,
+    &HtmlFormatter,
+];
+
+/// Returns the formatter registered with the given name, if any.
+pub fn find_formatter(name: &str) -> Option<&'static dyn OutputFormatter> {
+    FORMATTERS.iter().copied().find(|f| f.name() == name)
+}
+
+impl Document {
+    pub fn new<T: serde::Serialize>(value: &T) -> Document {
+        Document {
+            value: serde_yaml::to_value(value).expect("output should be serializable"),
+            text: None,
+        }
+    }
+
+    pub fn with_text(mut self, text: String) -> Document {
+        self.text = Some(text);
+        self
+    }
+}
+
+/// Parses document paths given to --expand option. Exits if a path is invalid or the output format isn't table,
+/// as the other formats show nested fields as they are.
+pub fn expanded_columns_or_exit(paths: &[String], output: Option<&str>) -> Vec<ExpandedColumn> {
+    if !paths.is_empty() && output.unwrap_or("table") != "table" {
+        error!("--expand is available only for table output.");
+        std::process::exit(1);
+    }
+    paths
+        .iter()
+        .map(|p| match DocumentPath::parse(p.trim()) {
+            Ok(path) => ExpandedColumn {
+                name: p.trim().to_owned(),
+                path,
+            },
+            Err(e) => {
+                error!("Invalid document path for --expand '{}': {}", p, e);
+                std::process::exit(1);
+            }
+        })
+        .collect()
+}
+
+/// Hides values of the attributes in output of read commands, e.g. ["password", "ssn"]. Only the first call takes effect.
+pub fn set_redacted_attributes(names: Vec<String>) {
+    let _ = REDACTED_ATTRIBUTES.set(names);
+}
+
+/// Returns the item with values of attributes listed in `display.redact` hidden, e.g. to print a field of it.
+pub fn redact_item(item: &Item) -> Item {
+    redact_map(item, redacted_attributes())
+}
+
+/// Appends ISO-8601 date times to numbers which look like epoch seconds or milliseconds in output of read commands.
+pub fn enable_humanize_epochs() {
+    HUMANIZE_EPOCHS.store(true, Ordering::Relaxed);
+}
+
+/// Prints items to stdout using the format given by --output option, or `default` format if it's not given.
+pub fn print_items(output: Option<&str>, default: &str, items: &[Item], view: &ItemsView) {
+    let formatter = formatter_or_exit(output.unwrap_or(default));
+    let items = prepare_items(formatter, items);
+    let mut stdout = io::stdout().lock();
+    if let Err(e) = formatter.write_items(&mut stdout, &items, view) {
+        error!("Failed to write items: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Prints a single item to stdout using the format given by --output option, or `default` format if it's not given.
+pub fn print_item(output: Option<&str>, default: &str, item: &Item, view: &ItemsView) {
+    let formatter = formatter_or_exit(output.unwrap_or(default));
+    let items = prepare_items(formatter, std::slice::from_ref(item));
+    let mut stdout = io::stdout().lock();
+    if let Err(e) = formatter.write_item(&mut stdout, &items[0], view) {
+        error!("Failed to write an item: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Prints a document to stdout using the format given by --output option, or `default` format if it's not given.
+pub fn print_document(output: Option<&str>, default: &str, doc: &Document) {
+    let formatter = formatter_or_exit(output.unwrap_or(default));
+    let mut stdout = io::stdout().lock();
+    if let Err(e) = formatter.write_document(&mut stdout, doc) {
+        error!("Failed to write output: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Returns true if the format given by --output option (or `default`) is table, which is meant for people rather than programs.
+/// Commands print progress to stdout only with table format, so that it doesn't mix into machine-readable output.
+pub fn is_table_format(output: Option<&str>, default: &str) -> bool {
+    output.unwrap_or(default) == "table"
+}
+
+/// Returns items as they're shown: values of attributes listed in `display.redact` are hidden, and epoch numbers are humanized
+/// if --humanize-epochs is given. Raw output keeps numbers as DynamoDB returned them, while redaction applies to all formats.
+fn prepare_items<'a>(formatter: &dyn OutputFormatter, items: &'a [Item]) -> Cow<'a, [Item]> {
+    let humanize = HUMANIZE_EPOCHS.load(Ordering::Relaxed) && formatter.name() != "raw";
+    let redacted = redacted_attributes();
+    if !humanize && redacted.is_empty() {
+        return Cow::Borrowed(items);
+    }
+    Cow::Owned(
+        items
+            .iter()
+            .map(|item| {
+                let item = redact_map(item, redacted);
+                if humanize {
+                    item.iter()
+                        .map(|(name, attrval)| (name.to_owned(), humanize_epoch_attrval(attrval)))
+                        .collect()
+                } else {
+                    item
+                }
+            })
+            .collect(),
+    )
+}
+
+fn redacted_attributes() -> &'static [String] {
+    REDACTED_ATTRIBUTES
+        .get()
+        .map_or(&[], |names| names.as_slice())
+}
+
+/// Replaces values of the attributes with the placeholder, including ones nested in maps and lists.
+fn redact_map(
+    map: &HashMap<String, AttributeValue>,
+    names: &[String],
+) -> HashMap<String, AttributeValue> {
+    map.iter()
+        .map(|(name, attrval)| {
+            let attrval = if names.contains(name) {
+                AttributeValue::S(String::from(REDACTED_PLACEHOLDER))
+            } else {
+                redact_attrval(attrval, names)
+            };
+            (name.to_owned(), attrval)
+        })
+        .collect()
+}
+
+fn redact_attrval(attrval: &AttributeValue, names: &[String]) -> AttributeValue {
+    match attrval {
+        AttributeValue::M(map) => AttributeValue::M(redact_map(map, names)),
+        AttributeValue::L(list) => {
+            AttributeValue::L(list.iter().map(|v| redact_attrval(v, names)).collect())
+        }
+        _ => attrval.clone(),
+    }
+}
+
+/// Replaces numbers which look like epoch seconds or milliseconds with strings followed by ISO-8601 date times in UTC,
+/// e.g. "1600000000 (2020-09-13T12:26:40Z)". Numbers in maps and lists are replaced as well.
+fn humanize_epoch_attrval(attrval: &AttributeValue) -> AttributeValue {
+    match attrval {
+        AttributeValue::N(n) => match epoch_to_iso8601(n) {
+            Some(datetime) => AttributeValue::S(format!("{} ({})", n, datetime)),
+            None => attrval.clone(),
+        },
+        AttributeValue::M(map) => AttributeValue::M(
+            map.iter()
+                .map(|(k, v)| (k.to_owned(), humanize_epoch_attrval(v)))
+                .collect(),
+        ),
+        AttributeValue::L(list) => {
+            AttributeValue::L(list.iter().map(humanize_epoch_attrval).collect())
+        }
+        _ => attrval.clone(),
+    }
+}
+
+fn epoch_to_iso8601(n: &str) -> Option<String> {
+    let n: i64 = n.parse().ok()?;
+    if PLAUSIBLE_EPOCH_SECONDS.contains(&n) {
+        DateTime::from_timestamp(n, 0).map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+    } else if PLAUSIBLE_EPOCH_SECONDS.contains(&(n / 1000)) {
+        DateTime::from_timestamp_millis(n).map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, true))
+    } else {
+        None
+    }
+}
+
+fn formatter_or_exit(name: &str) -> &'static dyn OutputFormatter {
+    match find_formatter(name) {
+        Some(f) => f,
+        None => {
+            println!("ERROR: unsupported output type '{}'.", name);
+            std::process::exit(1);
+        }
+    }
+}
+
+impl OutputFormatter for TableFormatter {
+    fn name(&self) -> &'static str {
+        "table"
+    }
+
+    fn write_items(&self, w: &mut dyn Write, items: &[Item], view: &ItemsView) -> io::Result<()> {
+        data::display_items_table(
+            w,
+            items,
+            view.ts,
+            view.attributes,
+            view.keys_only,
+            view.expand,
+        )
+    }
+
+    fn write_document(&self, w: &mut dyn Write, doc: &Document) -> io::Result<()> {
+        match &doc.text {
+            Some(text) => write!(w, "{}", text),
+            None => YamlFormatter.write_document(w, doc),
+        }
+    }
+}
+
+impl OutputFormatter for JsonFormatter {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn write_items(&self, w: &mut dyn Write, items: &[Item], _: &ItemsView) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *w, &data::convert_to_json_vec(items))?;
+        writeln!(w)
+    }
+
+    fn write_item(&self, w: &mut dyn Write, item: &Item, _: &ItemsView) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *w, &data::convert_to_json(item))?;
+        writeln!(w)
+    }
+
+    fn write_document(&self, w: &mut dyn Write, doc: &Document) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *w, &doc.value)?;
+        writeln!(w)
+    }
+}
+
+impl OutputFormatter for YamlFormatter {
+    fn name(&self) -> &'static str {
+        "yaml"
+    }
+
+    /// Writes a sequence of items. Attributes of each item are sorted so that results are stable across runs.
+    fn write_items(&self, w: &mut dyn Write, items: &[Item], view: &ItemsView) -> io::Result<()> {
+        let ordered: Vec<data::OrderedItem> = items
+            .iter()
+            .map(|item| data::convert_to_ordered_json(item, view.ts))
+            .collect();
+        let s = serde_yaml::to_string(&ordered).map_err(io::Error::other)?;
+        writeln!(w, "{}", s)
+    }
+
+    fn write_item(&self, w: &mut dyn Write, item: &Item, view: &ItemsView) -> io::Result<()> {
+        let s = serde_yaml::to_string(&data::convert_to_ordered_json(item, view.ts))
+            .map_err(io::Error::other)?;
+        writeln!(w, "{}", s)
+    }
+
+    fn write_document(&self, w: &mut dyn Write, doc: &Document) -> io::Result<()> {
+        let s = serde_yaml::to_string(&doc.value).map_err(io::Error::other)?;
+        writeln!(w, "{}", s)
+    }
+}
+
+impl OutputFormatter for RawFormatter {
+    fn name(&self) -> &'static str {
+        "raw"
+    }
+
+    fn write_items(&self, w: &mut dyn Write, items: &[Item], _: &ItemsView) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *w, &data::strip_items(items))?;
+        writeln!(w)
+    }
+
+    fn write_item(&self, w: &mut dyn Write, item: &Item, _: &ItemsView) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *w, &data::strip_item(item))?;
+        writeln!(w)
+    }
+
+    /// Documents have no DynamoDB types, so they're written in the same way as json format.
+    fn write_document(&self, w: &mut dyn Write, doc: &Document) -> io::Result<()> {
+        JsonFormatter.write_document(w, doc)
+    }
+}
+
+impl OutputFormatter for CsvFormatter {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    /// Writes a header line and one line per item. Primary key(s) always come first.
+    /// Unless --attributes is given, columns are all attributes found in the items, sorted by name.
+    fn write_items(&self, w: &mut dyn Write, items: &[Item], view: &ItemsView) -> io::Result<()> {
+        let attributes: Option<Vec<String>> = selected_attributes(items, view);
+
+        let mut header: Vec<&str> = vec![view.ts.pk.name.as_str()];
+        if let Some(sk) = &view.ts.sk {
+            header.push(sk.name.as_str());
+        }
+        if let Some(attrs) = &attributes {
+            header.extend(attrs.iter().map(|a| a.as_str()));
+        }
+        writeln!(w, "{}", header.join(","))?;
+
+        if !items.is_empty() {
+            let lines =
+                data::convert_items_to_csv_lines(items, view.ts, &attributes, view.keys_only);
+            writeln!(w, "{}", lines)?;
+        }
+        Ok(())
+    }
+
+    /// Cells are quoted as RFC 4180 describes, as nested values rendered in JSON contain commas and quotes.
+    fn write_document(&self, w: &mut dyn Write, doc: &Document) -> io::Result<()> {
+        let (header, rows) = document_rows(&doc.value);
+        if header.is_empty() {
+            return Ok(());
+        }
+        let line = |cells: &[String]| -> String {
+            cells
+                .iter()
+                .map(|c| escape_csv(c))
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        writeln!(w, "{}", line(&header))?;
+        for row in rows {
+            writeln!(w, "{}", line(&row))?;
+        }
+        Ok(())
+    }
+}
+
+impl OutputFormatter for JsonlFormatter {
+    fn name(&self) -> &'static str {
+        "jsonl"
+    }
+
+    /// Writes one compact JSON object per line, in the same format as `dy export --format jsonl`.
+    fn write_items(&self, w: &mut dyn Write, items: &[Item], view: &ItemsView) -> io::Result<()> {
+        for item in items {
+            serde_json::to_writer(&mut *w, &data::convert_to_ordered_json(item, view.ts))?;
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+
+    /// Writes each element of a list in its own line, or the whole document in one line.
+    fn write_document(&self, w: &mut dyn Write, doc: &Document) -> io::Result<()> {
+        match &doc.value {
+            serde_yaml::Value::Sequence(elements) => {
+                for element in elements {
+                    serde_json::to_writer(&mut *w, element)?;
+                    writeln!(w)?;
+                }
+            }
+            value => {
+                serde_json::to_writer(&mut *w, value)?;
+                writeln!(w)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OutputFormatter for MarkdownFormatter {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    /// Writes a GitHub Flavored Markdown table, with the same columns as CSV.
+    fn write_items(&self, w: &mut dyn Write, items: &[Item], view: &ItemsView) -> io::Result<()> {
+        let (header, rows) = item_rows(items, view);
+        write_markdown_table(w, &header, &rows)
+    }
+
+    fn write_document(&self, w: &mut dyn Write, doc: &Document) -> io::Result<()> {
+        let (header, rows) = document_rows(&doc.value);
+        if header.is_empty() {
+            return Ok(());
+        }
+        write_markdown_table(w, &header, &rows)
+    }
+}
+
+impl OutputFormatter for HtmlFormatter {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    /// Writes an HTML table, with the same columns as CSV.
+    fn write_items(&self, w: &mut dyn Write, items: &[Item], view: &ItemsView) -> io::Result<()> {
+        let (header, rows) = item_rows(items, view);
+        write_html_table(w, &header, &rows)
+    }
+
+    fn write_document(&self, w: &mut dyn Write, doc: &Document) -> io::Result<()> {
+        let (header, rows) = document_rows(&doc.value);
+        if header.is_empty() {
+            return Ok(());
+        }
+        write_html_table(w, &header, &rows)
+    }
+}
+
+/// Returns attributes to show other than primary key(s). None with --keys-only, attributes given by --attributes,
+/// or all attributes found in the items sorted by name.
+fn selected_attributes(items: &[Item], view: &ItemsView) -> Option<Vec<String>> {
+    if view.keys_only {
+        None
+    } else if let Some(attrs) = view.attributes {
+        Some(attrs.split(',').map(|a| a.trim().to_owned()).collect())
+    } else {
+        Some(non_key_attribute_names(items, view.ts))
+    }
+}
+
+/// Returns the header and cells of items, where primary key(s) come first.
+/// Strings and numbers are shown as they are, other types as JSON, and missing attributes as empty cells.
+fn item_rows(items: &[Item], view: &ItemsView) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut columns: Vec<String> = vec![view.ts.pk.name.to_owned()];
+    if let Some(sk) = &view.ts.sk {
+        columns.push(sk.name.to_owned());
+    }
+    columns.extend(selected_attributes(items, view).unwrap_or_default());
+
+    let rows: Vec<Vec<String>> = items
+        .iter()
+        .map(|item| {
+            columns
+                .iter()
+                .map(|column| match item.get(column) {
+                    None => String::new(),
+                    Some(AttributeValue::S(v) | AttributeValue::N(v)) => v.to_owned(),
+                    Some(v) => data::attrval_to_jsonval(v).to_string(),
+                })
+                .collect()
+        })
+        .collect();
+    (columns, rows)
+}
+
+/// Returns the header and rows of a document. A list of mappings has a row per element with columns of their keys,
+/// e.g. backups, and any other value has a row per field with its path, e.g. ("schema.pk", "id (S)").
+fn document_rows(value: &serde_yaml::Value) -> (Vec<String>, Vec<Vec<String>>) {
+    match value {
+        serde_yaml::Value::Sequence(elements) if elements.iter().all(|e| e.is_mapping()) => {
+            let mut columns: Vec<String> = vec![];
+            for key in elements
+                .iter()
+                .filter_map(|e| e.as_mapping())
+                .flat_map(|m| m.keys())
+            {
+                let column = document_cell(key);
+                if !columns.contains(&column) {
+                    columns.push(column);
+                }
+            }
+            let rows: Vec<Vec<String>> = elements
+                .iter()
+                .map(|element| {
+                    columns
+                        .iter()
+                        .map(|column| {
+                            element
+                                .get(column.as_str())
+                                .map_or(String::new(), document_cell)
+                        })
+                        .collect()
+                })
+                .collect();
+            (columns, rows)
+        }
+        _ => {
+            let mut fields: Vec<(String, String)> = vec![];
+            table::flatten_yaml_value(String::new(), value, &mut fields);
+            let rows: Vec<Vec<String>> = fields
+                .into_iter()
+                .map(|(field, value)| vec![field, value])
+                .collect();
+            (vec![String::from("Field"), String::from("Value")], rows)
+        }
+    }
+}
+
+/// Returns a value in a cell of a document row. Nested values are shown in compact JSON.
+fn document_cell(value: &serde_yaml::Value) -> String {
+    match value {
+        serde_yaml::Value::Null => String::new(),
+        serde_yaml::Value::String(s) => s.to_owned(),
+        serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_) => {
+            serde_json::to_string(value).unwrap_or_default()
+        }
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim_end().to_owned())
+            .unwrap_or_default(),
+    }
+}
+
+/// Writes a GitHub Flavored Markdown table. Cells are escaped as HTML, as renderers interpret HTML tags in Markdown,
+/// and then pipes are escaped and line breaks are replaced with <br>.
+fn write_markdown_table(
+    w: &mut dyn Write,
+    header: &[String],
+    rows: &[Vec<String>],
+) -> io::Result<()> {
+    let line = |cells: &[String]| {
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|c| escape_html(c).replace('|', "\\|").replace('\n', "<br>"))
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+    writeln!(w, "{}", line(header))?;
+    writeln!(w, "|{}", " --- |".repeat(header.len()))?;
+    for row in rows {
+        writeln!(w, "{}", line(row))?;
+    }
+    Ok(())
+}
+
+/// Writes an HTML table, which can be pasted into wikis or embedded in other pages.
+fn write_html_table(w: &mut dyn Write, header: &[String], rows: &[Vec<String>]) -> io::Result<()> {
+    let line = |tag: &str, cells: &[String]| {
+        let cells: String = cells
+            .iter()
+            .map(|c| format!("<{tag}>{}</{tag}>", escape_html(c), tag = tag))
+            .collect::<Vec<_>>()
+            .concat();
+        format!("<tr>{}</tr>", cells)
+    };
+    writeln!(w, "<table>")?;
+    writeln!(w, "  <thead>")?;
+    writeln!(w, "    {}", line("th", header))?;
+    writeln!(w, "  </thead>")?;
+    writeln!(w, "  <tbody>")?;
+    for row in rows {
+        writeln!(w, "    {}", line("td", row))?;
+    }
+    writeln!(w, "  </tbody>")?;
+    writeln!(w, "</table>")
+}
+
+/// Surrounds a CSV cell with double quotes if it contains a comma, a double quote or a line break, doubling embedded double quotes.
+fn escape_csv(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Collects names of all attributes other than primary key(s) in the given items.
+fn non_key_attribute_names(items: &[Item], ts: &app::TableSchema) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    for name in items.iter().flat_map(|item| item.keys()) {
+        if name != &ts.pk.name && ts.sk.as_ref().map_or(true, |sk| &sk.name != name) {
+            names.insert(name.to_owned());
+        }
+    }
+    names.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ddb::key;
+
+    fn table_schema() -> app::TableSchema {
+        app::TableSchema {
+            region: "local".to_owned(),
+            name: "tbl".to_owned(),
+            pk: key::Key {
+                name: "pk".to_owned(),
+                kind: key::KeyType::S,
+            },
+            sk: Some(key::Key {
+                name: "sk".to_owned(),
+                kind: key::KeyType::N,
+            }),
+            indexes: None,
+            mode: crate::ddb::table::Mode::OnDemand,
+            ttl: None,
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            HashMap::from([
+                ("pk".to_owned(), AttributeValue::S("a".to_owned())),
+                ("sk".to_owned(), AttributeValue::N("1".to_owned())),
+                ("name".to_owned(), AttributeValue::S("x".to_owned())),
+            ]),
+            HashMap::from([
+                ("pk".to_owned(), AttributeValue::S("b".to_owned())),
+                ("sk".to_owned(), AttributeValue::N("2".to_owned())),
+                ("flag".to_owned(), AttributeValue::Bool(true)),
+            ]),
+        ]
+    }
+
+    fn render(name: &str, attributes: &Option<String>, keys_only: bool) -> String {
+        let ts = table_schema();
+        let view = ItemsView {
+            ts: &ts,
+            attributes,
+            keys_only,
+            expand: &[],
+        };
+        let mut buf: Vec<u8> = vec![];
+        find_formatter(name)
+            .unwrap()
+            .write_items(&mut buf, &items(), &view)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_redact_map() {
+        let s = |s: &str| AttributeValue::S(String::from(s));
+        let names = vec![String::from("password"), String::from("ssn")];
+        let item: Item = HashMap::from([
+            (String::from("id"), s("u1")),
+            (String::from("password"), s("hunter2")),
+            (
+                String::from("profiles"),
+                AttributeValue::L(vec![AttributeValue::M(HashMap::from([
+                    (String::from("ssn"), s("123-45-6789")),
+                    (String::from("name"), s("alice")),
+                ]))]),
+            ),
+        ]);
+        let expected: Item = HashMap::from([
+            (String::from("id"), s("u1")),
+            (String::from("password"), s(REDACTED_PLACEHOLDER)),
+            (
+                String::from("profiles"),
+                AttributeValue::L(vec![AttributeValue::M(HashMap::from([
+                    (String::from("ssn"), s(REDACTED_PLACEHOLDER)),
+                    (String::from("name"), s("alice")),
+                ]))]),
+            ),
+        ]);
+        assert_eq!(redact_map(&item, &names), expected);
+        assert_eq!(redact_map(&item, &[]), item);
+    }
+
+    #[test]
+    fn test_humanize_epoch_attrval() {
+        let n = |s: &str| AttributeValue::N(String::from(s));
+        let s = |s: &str| AttributeValue::S(String::from(s));
+        assert_eq!(
+            humanize_epoch_attrval(&n("1600000000")),
+            s("1600000000 (2020-09-13T12:26:40Z)")
+        );
+        assert_eq!(
+            humanize_epoch_attrval(&n("1600000000123")),
+            s("1600000000123 (2020-09-13T12:26:40.123Z)")
+        );
+        // Small numbers, decimals and strings are left as they are.
+        assert_eq!(humanize_epoch_attrval(&n("42")), n("42"));
+        assert_eq!(
+            humanize_epoch_attrval(&n("1600000000.5")),
+            n("1600000000.5")
+        );
+        assert_eq!(humanize_epoch_attrval(&s("1600000000")), s("1600000000"));
+        assert_eq!(
+            humanize_epoch_attrval(&AttributeValue::L(vec![AttributeValue::M(HashMap::from(
+                [(String::from("at"), n("1600000000"))]
+            ))])),
+            AttributeValue::L(vec![AttributeValue::M(HashMap::from([(
+                String::from("at"),
+                s("1600000000 (2020-09-13T12:26:40Z)")
+            )]))])
+        );
+    }
+
+    #[test]
+    fn test_registry() {
+        for name in FORMAT_NAMES {
+            assert_eq!(find_formatter(name).map(|f| f.name()), Some(name));
+        }
+        assert!(find_formatter("xml").is_none());
+    }
+
+    #[test]
+    fn test_csv_formatter() {
+        assert_eq!(
+            render("csv", &None, false),
+            "pk,sk,flag,name\n\"a\",1,null,\"x\"\n\"b\",2,true,null\n"
+        );
+        assert_eq!(
+            render("csv", &Some("name".to_owned()), false),
+            "pk,sk,name\n\"a\",1,\"x\"\n\"b\",2,null\n"
+        );
+        assert_eq!(render("csv", &None, true), "pk,sk\n\"a\",1\n\"b\",2\n");
+    }
+
+    #[test]
+    fn test_yaml_formatter() {
+        assert_eq!(
+            render("yaml", &None, false),
+            "- pk: a\n  sk: 1\n  name: x\n- pk: b\n  sk: 2\n  flag: true\n\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_formatter() {
+        assert_eq!(
+            render("markdown", &None, false),
+            "| pk | sk | flag | name |\n| --- | --- | --- | --- |\n| a | 1 |  | x |\n| b | 2 | true |  |\n"
+        );
+        let mut buf: Vec<u8> = vec![];
+        write_markdown_table(
+            &mut buf,
+            &["a".to_owned()],
+            &[
+                vec!["x|y\nz".to_owned()],
+                vec!["<script>x & y</script>".to_owned()],
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "| a |\n| --- |\n| x\\|y<br>z |\n| &lt;script&gt;x &amp; y&lt;/script&gt; |\n"
+        );
+    }
+
+    #[test]
+    fn test_html_formatter() {
+        assert_eq!(
+            render("html", &Some("name".to_owned()), false),
+            "<table>\n  <thead>\n    <tr><th>pk</th><th>sk</th><th>name</th></tr>\n  </thead>\n  <tbody>\n    <tr><td>a</td><td>1</td><td>x</td></tr>\n    <tr><td>b</td><td>2</td><td></td></tr>\n  </tbody>\n</table>\n"
+        );
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_table_formatter_expand() {
+        let ts = table_schema();
+        let expand = expanded_columns_or_exit(&["info.city".to_owned(), "info".to_owned()], None);
+        let view = ItemsView {
+            ts: &ts,
+            attributes: &None,
+            keys_only: false,
+            expand: &expand,
+        };
+        let item = HashMap::from([
+            ("pk".to_owned(), AttributeValue::S("a".to_owned())),
+            ("sk".to_owned(), AttributeValue::N("1".to_owned())),
+            (
+                "info".to_owned(),
+                AttributeValue::M(HashMap::from([
+                    ("city".to_owned(), AttributeValue::S("Tokyo".to_owned())),
+                    (
+                        "tags".to_owned(),
+                        AttributeValue::L(vec![AttributeValue::S("x".repeat(30))]),
+                    ),
+                ])),
+            ),
+        ]);
+        let mut buf: Vec<u8> = vec![];
+        find_formatter("table")
+            .unwrap()
+            .write_items(&mut buf, &[item], &view)
+            .unwrap();
+        // Maps and lists in the expanded columns are truncated to 30 columns.
+        assert_eq!(
+            String::from_utf8(buf).unwrap().lines().nth(1).unwrap(),
+            format!(
+                "a   1   Tokyo      {{\"city\":\"Tokyo\",\"tags\":[\"xxxxx...  {{\"info\":{{\"city\":\"Tokyo\",\"tags\":[\"{}...",
+                "x".repeat(17)
+            )
+        );
+    }
+
+    #[test]
+    fn test_jsonl_formatter() {
+        assert_eq!(
+            render("jsonl", &None, false),
+            "{\"pk\":\"a\",\"sk\":1,\"name\":\"x\"}\n{\"pk\":\"b\",\"sk\":2,\"flag\":true}\n"
+        );
+    }
+
+    #[test]
+    fn test_write_document() {
+        let write = |format: &str, doc: &Document| {
+            let mut buf: Vec<u8> = vec![];
+            find_formatter(format)
+                .unwrap()
+                .write_document(&mut buf, doc)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let backups: serde_yaml::Value =
+            serde_yaml::from_str("[{name: a, size: 1}, {name: b, status: AVAILABLE}]").unwrap();
+        let doc = Document {
+            value: backups,
+            text: Some(String::from("text\n")),
+        };
+        assert_eq!(write("table", &doc), "text\n");
+        // A list of objects is rendered as rows whose columns are all keys of them.
+        assert_eq!(write("csv", &doc), "name,size,status\na,1,\nb,,AVAILABLE\n");
+        let nested =
+            Document::new(&serde_json::json!([{"name": "t", "keys": {"pk": "S", "sk": "N"}}]));
+        assert_eq!(
+            write("csv", &nested),
+            "keys,name\n\"{\"\"pk\"\":\"\"S\"\",\"\"sk\"\":\"\"N\"\"}\",t\n"
+        );
+        assert_eq!(
+            write("jsonl", &doc),
+            "{\"name\":\"a\",\"size\":1}\n{\"name\":\"b\",\"status\":\"AVAILABLE\"}\n"
+        );
+
+        // Other values are rendered as fields and their values, and formatted as yaml without text.
+        let desc = Document::new(&serde_json::json!({"name": "t", "pk": {"name": "id"}}));
+        assert_eq!(
+            write("markdown", &desc),
+            "| Field | Value |\n| --- | --- |\n| name | t |\n| pk.name | id |\n"
+        );
+        assert_eq!(write("table", &desc), "name: t\npk:\n  name: id\n\n");
+    }
+}
@@ -22,7 +22,7 @@ use bytes::Bytes;
 use itertools::Itertools;
 use pest::iterators::Pair;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter, Write};
 use std::iter::Enumerate;
 use std::str::Chars;
@@ -149,6 +149,17 @@ impl Display for SortKeyCondition {
     }
 }
 
+/// The operator of a sort key condition which is given by dedicated options such as `--sk-gt`.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum SortKeyOperator {
+    Le,
+    Lt,
+    Ge,
+    Gt,
+    Between,
+    BeginsWith,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 struct AtomicSet {
     path: Path,
@@ -209,10 +220,33 @@ enum Function {
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 enum ListAppendParameter {
+    Function(Box<Function>),
     Path(Path),
     ListLiteral(AttrVal),
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Condition {
+    Or(Vec<Condition>),
+    And(Vec<Condition>),
+    Not(Box<Condition>),
+    Comparison(ConditionOperand, String, ConditionOperand),
+    Between(ConditionOperand, ConditionOperand, ConditionOperand),
+    In(ConditionOperand, Vec<ConditionOperand>),
+    AttributeExists(Path),
+    AttributeNotExists(Path),
+    AttributeType(Path, ConditionOperand),
+    BeginsWith(Path, ConditionOperand),
+    Contains(Path, ConditionOperand),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum ConditionOperand {
+    Size(Path),
+    Literal(AttrVal),
+    Path(Path),
+}
+
 /// The result of parsing expression
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExpressionResult {
@@ -235,6 +269,55 @@ impl ExpressionResult {
     }
 }
 
+/// A document path like `address.city` or `items[0].price`, which is resolved against an item on the client side.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DocumentPath {
+    path: Path,
+}
+
+impl DocumentPath {
+    pub fn parse(exp: &str) -> Result<DocumentPath, ParseError> {
+        match GeneratedParser::parse(Rule::document_path, exp) {
+            Ok(mut pair) => Ok(DocumentPath {
+                path: parse_path(pair.next().unwrap().into_inner().next().unwrap()),
+            }),
+            Err(err) => Err(ParseError::ParsingError(Box::new(err))),
+        }
+    }
+
+    /// Returns the value at the path, or None if the item doesn't have it.
+    pub fn extract<'a>(
+        &self,
+        item: &'a HashMap<String, AttributeValue>,
+    ) -> Option<&'a AttributeValue> {
+        resolve_path(&self.path, item)
+    }
+}
+
+/// A condition like `price < 100 AND attribute_exists(email)`, which is evaluated against items on the client side,
+/// e.g. `dy local-query --filter`. The syntax is the same as [`DyneinParser::parse_condition`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LocalCondition {
+    condition: Condition,
+}
+
+impl LocalCondition {
+    pub fn parse(exp: &str) -> Result<LocalCondition, ParseError> {
+        match GeneratedParser::parse(Rule::condition, exp) {
+            Ok(mut pair) => Ok(LocalCondition {
+                condition: parse_condition_pair(pair.next().unwrap())?,
+            }),
+            Err(err) => Err(ParseError::ParsingError(Box::new(err))),
+        }
+    }
+
+    /// Returns true if the item satisfies the condition, following the semantics of FilterExpression of DynamoDB.
+    /// e.g. comparisons between different types, or with a missing attribute, are false except for `<>`.
+    pub fn matches(&self, item: &HashMap<String, AttributeValue>) -> bool {
+        evaluate_condition(&self.condition, item)
+    }
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct ParsingErrorWithSuggestError {
     pub parse_error: Box<pest::error::Error<Rule>>,
@@ -358,6 +441,24 @@ impl Display for InvalidTypesWithSuggestError {
         )
     }
 }
+/// The error context of a sort key condition which KeyConditionExpression does not support
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct UnsupportedSortKeyConditionError {
+    pub condition: String,
+    pub suggest: String,
+}
+
+impl Display for UnsupportedSortKeyConditionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} cannot be used for a sort key condition because KeyConditionExpression does not support it.\n\
+            Please use --filter option instead, e.g. --filter '{}'. Note that filtered out items still consume read capacity.",
+            self.condition, self.suggest
+        )
+    }
+}
+
 /// The error context of a parsing error
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ParseError {
@@ -371,6 +472,10 @@ pub enum ParseError {
     InvalidTypes(InvalidTypesError),
     InvalidTypesWithSuggest(InvalidTypesWithSuggestError),
     Base64DecodeError(DecodeError),
+    DuplicateMapKey(String),
+    UnsupportedSortKeyCondition(UnsupportedSortKeyConditionError),
+    InvalidTimeLiteral(String),
+    ChainedArithmetic(String),
 }
 
 impl Display for ParseError {
@@ -406,6 +511,26 @@ impl Display for ParseError {
             ParseError::Base64DecodeError(err) => {
                 write!(f, "failed to decode base64 string: {}", err)
             }
+            ParseError::DuplicateMapKey(key) => {
+                write!(f, "the key '{}' is duplicated in the map literal", key)
+            }
+            ParseError::UnsupportedSortKeyCondition(err) => {
+                write!(f, "{}", err)
+            }
+            ParseError::InvalidTimeLiteral(input) => {
+                write!(
+                    f,
+                    "the time literal is invalid: '{}'. epoch() requires an RFC 3339 timestamp, e.g. epoch(\"2025-01-01T00:00:00Z\")",
+                    input
+                )
+            }
+            ParseError::ChainedArithmetic(input) => {
+                write!(
+                    f,
+                    "a value of a set action can have only one '+' or '-' operator, e.g. 'a = b + 1', because DynamoDB does not support more: '{}'",
+                    input
+                )
+            }
         }
     }
 }
@@ -907,13 +1032,39 @@ fn parse_binary_string_literal(str: &str) -> Result<Bytes, ParseError> {
 /// Parse b64 literal.
 static B64_ENGINE: OnceLock<GeneralPurpose> = OnceLock::new();
 fn parse_b64_literal(str: &str) -> Result<Bytes, ParseError> {
+    decode_b64(&str[4..str.len() - 1])
+}
+
+fn decode_b64(str: &str) -> Result<Bytes, ParseError> {
     let engine = B64_ENGINE.get_or_init(|| {
         GeneralPurpose::new(
             &base64::alphabet::STANDARD,
             GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
         )
     });
-    Ok(engine.decode(&str[4..str.len() - 1]).map(Bytes::from)?)
+    Ok(engine.decode(str).map(Bytes::from)?)
+}
+
+/// Convert a raw value given from command line to a value of the type without any quotes.
+/// Binary values are expected to be encoded in base64.
+fn raw_value_to_attr_val(raw: &str, attr_type: AttributeType) -> Result<AttrVal, ParseError> {
+    match attr_type {
+        AttributeType::N => {
+            let is_number = GeneratedParser::parse(Rule::number_literal, raw)
+                .map(|mut pair| pair.next().unwrap().as_str().len() == raw.len())
+                .unwrap_or(false);
+            if is_number {
+                Ok(AttrVal::N(raw.to_owned()))
+            } else {
+                Err(ParseError::InvalidTypes(InvalidTypesError {
+                    expected_type: AttributeType::N,
+                    actual_type: AttributeType::S,
+                }))
+            }
+        }
+        AttributeType::B => Ok(AttrVal::B(decode_b64(raw)?)),
+        _ => Ok(AttrVal::S(raw.to_owned())),
+    }
 }
 
 fn parse_string_literal(pair: Pair<Rule>) -> Result<String, ParseError> {
@@ -927,6 +1078,99 @@ fn parse_string_literal(pair: Pair<Rule>) -> Result<String, ParseError> {
     }
 }
 
+/// Evaluate a time literal, i.e. `now()`, `now_plus(<amount> <unit>)` or `epoch("<RFC 3339 timestamp>")`, to epoch seconds.
+/// `now` is given by the caller so that the result is testable.
+fn parse_time_literal(pair: Pair<Rule>, now: i64) -> Result<i64, ParseError> {
+    let invalid = || ParseError::InvalidTimeLiteral(pair.as_str().to_owned());
+    match pair.as_rule() {
+        Rule::now_literal => Ok(now),
+        Rule::now_plus_literal => {
+            // this unwrap is safe because now_plus_literal has always an amount and a unit
+            let (amount, unit) = pair.clone().into_inner().collect_tuple().unwrap();
+            let amount: i64 = amount.as_str().parse().map_err(|_| invalid())?;
+            let unit_secs = match unit.as_str().to_ascii_lowercase().trim_end_matches('s') {
+                "second" => 1,
+                "minute" => 60,
+                "hour" => 60 * 60,
+                "day" => 24 * 60 * 60,
+                "week" => 7 * 24 * 60 * 60,
+                _ => unreachable!("Unexpected time unit"),
+            };
+            amount
+                .checked_mul(unit_secs)
+                .and_then(|secs| now.checked_add(secs))
+                .ok_or_else(invalid)
+        }
+        Rule::epoch_literal => {
+            // this unwrap is safe because epoch_literal has always one string literal
+            let timestamp = parse_string_literal(pair.clone().into_inner().next().unwrap())?;
+            chrono::DateTime::parse_from_rfc3339(&timestamp)
+                .map(|t| t.timestamp())
+                .map_err(|_| invalid())
+        }
+        _ => {
+            // this must not happen
+            unreachable!("Expect time literal, but another token found");
+        }
+    }
+}
+
+/// Encode a ULID (https://github.com/ulid/spec) from 48 bits of Unix time in milliseconds and 80 bits of randomness.
+/// The result is 26 characters of Crockford's Base32, which sorts lexicographically by time.
+fn encode_ulid(timestamp_ms: u64, randomness: u128) -> String {
+    const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+    let value =
+        (u128::from(timestamp_ms & 0xFFFF_FFFF_FFFF) << 80) | (randomness & ((1 << 80) - 1));
+    (0..26)
+        .map(|i| ALPHABET[((value >> (125 - 5 * i)) & 0x1F) as usize] as char)
+        .collect()
+}
+
+/// Merge the parsed map literal into the initial item.
+fn merge_map_literal(
+    initial_item: Option<HashMap<String, AttributeValue>>,
+    pair: Pair<Rule>,
+) -> Result<HashMap<String, AttributeValue>, ParseError> {
+    assert_eq!(pair.as_rule(), Rule::map_literal);
+    // pair is parsed through Rule::map_literal so we expect that pair should be HashMap
+    let item = parse_literal(pair)?
+        .convert_attribute_value()
+        .as_m()
+        .unwrap()
+        .to_owned();
+    let mut image = initial_item.unwrap_or_default();
+    image.extend(item);
+    Ok(image)
+}
+
+/// Check that every map literal under the pair has no duplicate keys.
+fn check_duplicate_map_keys(pair: &Pair<Rule>) -> Result<(), ParseError> {
+    for map in pair
+        .clone()
+        .into_inner()
+        .flatten()
+        .filter(|p| p.as_rule() == Rule::map_literal)
+    {
+        let mut keys = HashSet::new();
+        for map_pair in map.into_inner() {
+            // this unwrap is safe because map_pair always starts with map_key, which has one string literal
+            let key = parse_string_literal(
+                map_pair
+                    .into_inner()
+                    .next()
+                    .unwrap()
+                    .into_inner()
+                    .next()
+                    .unwrap(),
+            )?;
+            if !keys.insert(key.clone()) {
+                return Err(ParseError::DuplicateMapKey(key));
+            }
+        }
+    }
+    Ok(())
+}
+
 fn parse_list_literal(pair: Pair<Rule>) -> Result<Vec<AttrVal>, ParseError> {
     assert_eq!(pair.as_rule(), Rule::list_literal);
     pair.into_inner().map(parse_literal).collect()
@@ -942,6 +1186,14 @@ fn parse_literal(pair: Pair<Rule>) -> Result<AttrVal, ParseError> {
             Ok(AttrVal::S(parse_string_literal(pair)?))
         }
         Rule::number_literal => Ok(AttrVal::N(pair.as_str().to_owned())),
+        Rule::now_literal | Rule::now_plus_literal | Rule::epoch_literal => Ok(AttrVal::N(
+            parse_time_literal(pair, chrono::Utc::now().timestamp())?.to_string(),
+        )),
+        Rule::uuid_literal => Ok(AttrVal::S(uuid::Uuid::new_v4().to_string())),
+        Rule::ulid_literal => Ok(AttrVal::S(encode_ulid(
+            chrono::Utc::now().timestamp_millis() as u64,
+            rand::random(),
+        ))),
         Rule::binary_literal => Ok(AttrVal::B(parse_binary_literal(pair.as_str())?)),
         Rule::binary_string_literal => Ok(AttrVal::B(parse_binary_string_literal(pair.as_str())?)),
         Rule::b64_literal => Ok(AttrVal::B(parse_b64_literal(pair.as_str())?)),
@@ -1050,6 +1302,9 @@ fn parse_list_append_parameter(pair: Pair<Rule>) -> Result<ListAppendParameter,
     assert_eq!(pair.as_rule(), Rule::list_append_parameter);
     let pair = pair.into_inner().next().unwrap();
     match pair.as_rule() {
+        Rule::function => Ok(ListAppendParameter::Function(Box::new(parse_function(
+            pair,
+        )?))),
         Rule::path => Ok(ListAppendParameter::Path(parse_path(pair))),
         Rule::list_literal => Ok(ListAppendParameter::ListLiteral(AttrVal::L(
             parse_list_literal(pair)?,
@@ -1105,37 +1360,32 @@ fn parse_operand(pair: Pair<Rule>) -> Result<Operand, ParseError> {
 
 fn parse_value(pair: Pair<Rule>) -> Result<Value, ParseError> {
     assert_eq!(pair.as_rule(), Rule::value);
-    // this unwrap is safe because value has exactly one children
-    let pair = pair.into_inner().next().unwrap();
-    match pair.as_rule() {
-        Rule::plus_expression => {
-            if let Some((lhs, rhs)) = pair.into_inner().collect_tuple() {
-                let lhs = parse_operand(lhs)?;
-                let rhs = parse_operand(rhs)?;
-                Ok(Value::PlusExpression(lhs, rhs))
-            } else {
-                // this must not happen
-                unreachable!("Invalid plus expression is detected");
-            }
-        }
-        Rule::minus_expression => {
-            if let Some((lhs, rhs)) = pair.into_inner().collect_tuple() {
-                let lhs = parse_operand(lhs)?;
-                let rhs = parse_operand(rhs)?;
-                Ok(Value::MinusExpression(lhs, rhs))
-            } else {
-                // this must not happen
-                unreachable!("Invalid plus expression is detected");
+    let mut pairs = pair.into_inner();
+    // this unwrap is safe because value always starts with an operand
+    let lhs = parse_operand(pairs.next().unwrap())?;
+    match pairs.next_tuple() {
+        None => Ok(Value::Operand(lhs)),
+        Some((operator, rhs)) => {
+            assert_eq!(operator.as_rule(), Rule::arithmetic_operator);
+            let rhs = parse_operand(rhs)?;
+            match operator.as_str() {
+                "+" => Ok(Value::PlusExpression(lhs, rhs)),
+                "-" => Ok(Value::MinusExpression(lhs, rhs)),
+                _ => {
+                    // this must not happen
+                    unreachable!("Unexpected arithmetic operator is detected");
+                }
             }
         }
-        Rule::operand => Ok(Value::Operand(parse_operand(pair)?)),
-        _ => {
-            // this must not happen
-            unreachable!("Unexpected expression is detected");
-        }
     }
 }
 
+/// Returns an error to explain that DynamoDB does not support the set action if it has chained arithmetic operators.
+fn detect_chained_arithmetic(exp: &str) -> Option<ParseError> {
+    GeneratedParser::parse(Rule::chained_arithmetic, exp).ok()?;
+    Some(ParseError::ChainedArithmetic(exp.to_owned()))
+}
+
 fn parse_sort_key_condition(pair: Pair<Rule>) -> Result<SortKeyCondition, ParseError> {
     assert_eq!(pair.as_rule(), Rule::sort_key);
     // this unwrap is safe because sort_key exactly one children
@@ -1199,6 +1449,37 @@ fn parse_sort_key_condition(pair: Pair<Rule>) -> Result<SortKeyCondition, ParseE
     }
 }
 
+/// Detect conditions which users commonly try but KeyConditionExpression does not support,
+/// and return an error which suggests an equivalent filter.
+fn detect_unsupported_sort_key_condition(
+    exp: &str,
+    sort_attr: &AttributeDefinition,
+) -> Option<ParseError> {
+    let mut pair = GeneratedParser::parse(Rule::sort_key_unsupported, exp).ok()?;
+    // this unwrap is safe because sort_key_unsupported has exactly one children
+    let pair = pair.next().unwrap().into_inner().next().unwrap();
+    let rest = exp[pair.as_span().end()..].trim();
+    let name = &sort_attr.attribute_name;
+    let (condition, suggest) = match pair.as_rule() {
+        Rule::sort_not_equal => (
+            format!("'{}'", pair.as_str()),
+            format!("{} <> {}", name, rest),
+        ),
+        Rule::sort_in => ("IN".to_owned(), format!("{} IN {}", name, rest)),
+        Rule::sort_function => (
+            format!("{}()", pair.as_str().trim().to_lowercase()),
+            exp.trim().to_owned(),
+        ),
+        _ => {
+            // this must not happen
+            unreachable!("Unexpected unsupported sort key condition")
+        }
+    };
+    Some(ParseError::UnsupportedSortKeyCondition(
+        UnsupportedSortKeyConditionError { condition, suggest },
+    ))
+}
+
 fn parse_sort_key_str_pair(pair: Pair<Rule>) -> Result<SortKeyCondition, ParseError> {
     assert_eq!(pair.as_rule(), Rule::sort_key_str);
     // this unwrap is safe because sort_key exactly one children
@@ -1351,10 +1632,338 @@ fn parse_set_action_pair(pair: Pair<Rule>) -> Result<SetAction, ParseError> {
     Ok(set_actions)
 }
 
+fn parse_condition_pair(pair: Pair<Rule>) -> Result<Condition, ParseError> {
+    assert_eq!(pair.as_rule(), Rule::condition);
+    // this unwrap is safe because condition has exactly one children
+    parse_or_condition(pair.into_inner().next().unwrap())
+}
+
+fn parse_or_condition(pair: Pair<Rule>) -> Result<Condition, ParseError> {
+    assert_eq!(pair.as_rule(), Rule::or_condition);
+    let mut conditions = pair
+        .into_inner()
+        .filter(|p| p.as_rule() != Rule::or_keyword)
+        .map(parse_and_condition)
+        .collect::<Result<Vec<Condition>, ParseError>>()?;
+    if conditions.len() == 1 {
+        Ok(conditions.remove(0))
+    } else {
+        Ok(Condition::Or(conditions))
+    }
+}
+
+fn parse_and_condition(pair: Pair<Rule>) -> Result<Condition, ParseError> {
+    assert_eq!(pair.as_rule(), Rule::and_condition);
+    let mut conditions = pair
+        .into_inner()
+        .filter(|p| p.as_rule() != Rule::and_keyword)
+        .map(parse_not_condition)
+        .collect::<Result<Vec<Condition>, ParseError>>()?;
+    if conditions.len() == 1 {
+        Ok(conditions.remove(0))
+    } else {
+        Ok(Condition::And(conditions))
+    }
+}
+
+fn parse_not_condition(pair: Pair<Rule>) -> Result<Condition, ParseError> {
+    assert_eq!(pair.as_rule(), Rule::not_condition);
+    let mut inner = pair.into_inner();
+    // this unwrap is safe because not_condition has at least one children
+    let pair = inner.next().unwrap();
+    match pair.as_rule() {
+        Rule::not_keyword => Ok(Condition::Not(Box::new(parse_not_condition(
+            inner.next().unwrap(),
+        )?))),
+        Rule::or_condition => parse_or_condition(pair),
+        Rule::condition_function => parse_condition_function(pair),
+        Rule::comparison_condition => {
+            if let Some((lhs, comparator, rhs)) = pair.into_inner().collect_tuple() {
+                let comparator = match comparator.as_str() {
+                    "==" => "=".to_owned(),
+                    c => c.to_owned(),
+                };
+                Ok(Condition::Comparison(
+                    parse_condition_operand(lhs)?,
+                    comparator,
+                    parse_condition_operand(rhs)?,
+                ))
+            } else {
+                // this must not happen
+                unreachable!("Invalid comparison is detected")
+            }
+        }
+        Rule::between_condition => {
+            let operands = pair
+                .into_inner()
+                .filter(|p| p.as_rule() == Rule::condition_operand);
+            if let Some((target, start, end)) = operands.collect_tuple() {
+                Ok(Condition::Between(
+                    parse_condition_operand(target)?,
+                    parse_condition_operand(start)?,
+                    parse_condition_operand(end)?,
+                ))
+            } else {
+                // this must not happen
+                unreachable!("Invalid between condition is detected")
+            }
+        }
+        Rule::in_condition => {
+            let mut operands = pair
+                .into_inner()
+                .filter(|p| p.as_rule() == Rule::condition_operand)
+                .map(parse_condition_operand)
+                .collect::<Result<Vec<ConditionOperand>, ParseError>>()?;
+            let target = operands.remove(0);
+            Ok(Condition::In(target, operands))
+        }
+        _ => {
+            // this must not happen
+            unreachable!("Unexpected condition is detected")
+        }
+    }
+}
+
+fn parse_condition_function(pair: Pair<Rule>) -> Result<Condition, ParseError> {
+    assert_eq!(pair.as_rule(), Rule::condition_function);
+    // this unwrap is safe because condition_function has exactly one children
+    let pair = pair.into_inner().next().unwrap();
+    let rule = pair.as_rule();
+    let mut inner = pair.into_inner();
+    // every condition function takes a path as the first argument
+    let path = parse_path(inner.next().unwrap());
+    match rule {
+        Rule::attribute_exists_function => Ok(Condition::AttributeExists(path)),
+        Rule::attribute_not_exists_function => Ok(Condition::AttributeNotExists(path)),
+        Rule::attribute_type_function => Ok(Condition::AttributeType(
+            path,
+            parse_condition_operand(inner.next().unwrap())?,
+        )),
+        Rule::begins_with_function => Ok(Condition::BeginsWith(
+            path,
+            parse_condition_operand(inner.next().unwrap())?,
+        )),
+        Rule::contains_function => Ok(Condition::Contains(
+            path,
+            parse_condition_operand(inner.next().unwrap())?,
+        )),
+        _ => {
+            // this must not happen
+            unreachable!("Invalid condition function is detected")
+        }
+    }
+}
+
+fn parse_condition_operand(pair: Pair<Rule>) -> Result<ConditionOperand, ParseError> {
+    assert_eq!(pair.as_rule(), Rule::condition_operand);
+    // this unwrap is safe because condition_operand has exactly one children
+    let pair = pair.into_inner().next().unwrap();
+    match pair.as_rule() {
+        Rule::size_function => Ok(ConditionOperand::Size(parse_path(
+            pair.into_inner().next().unwrap(),
+        ))),
+        Rule::path => Ok(ConditionOperand::Path(parse_path(pair))),
+        _ => Ok(ConditionOperand::Literal(parse_literal(pair)?)),
+    }
+}
+
 fn attr_name_ref(idx: usize) -> String {
     format!("#DYNEIN_ATTRNAME{}", idx)
 }
 
+fn resolve_path<'a>(
+    path: &Path,
+    item: &'a HashMap<String, AttributeValue>,
+) -> Option<&'a AttributeValue> {
+    let mut elements = path.elements.iter();
+    let mut current = match elements.next()? {
+        PathElement::Attribute(name) => item.get(name)?,
+        PathElement::Index(_) => return None,
+    };
+    for elem in elements {
+        current = match (elem, current) {
+            (PathElement::Attribute(name), AttributeValue::M(map)) => map.get(name)?,
+            (PathElement::Index(idx), AttributeValue::L(list)) => {
+                list.get(idx.parse::<usize>().ok()?)?
+            }
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+fn evaluate_condition(condition: &Condition, item: &HashMap<String, AttributeValue>) -> bool {
+    match condition {
+        Condition::Or(conditions) => conditions.iter().any(|c| evaluate_condition(c, item)),
+        Condition::And(conditions) => conditions.iter().all(|c| evaluate_condition(c, item)),
+        Condition::Not(condition) => !evaluate_condition(condition, item),
+        Condition::Comparison(lhs, comparator, rhs) => match (
+            evaluate_condition_operand(lhs, item),
+            evaluate_condition_operand(rhs, item),
+        ) {
+            (Some(lhs), Some(rhs)) => match comparator.as_str() {
+                "=" | "==" => attr_val_eq(&lhs, &rhs),
+                "<>" => !attr_val_eq(&lhs, &rhs),
+                "<" => attr_val_cmp(&lhs, &rhs).map_or(false, |o| o.is_lt()),
+                "<=" => attr_val_cmp(&lhs, &rhs).map_or(false, |o| o.is_le()),
+                ">" => attr_val_cmp(&lhs, &rhs).map_or(false, |o| o.is_gt()),
+                ">=" => attr_val_cmp(&lhs, &rhs).map_or(false, |o| o.is_ge()),
+                _ => unreachable!("Unexpected comparator is detected"),
+            },
+            // A missing attribute is not equal to anything.
+            _ => comparator == "<>",
+        },
+        Condition::Between(target, start, end) => match (
+            evaluate_condition_operand(target, item),
+            evaluate_condition_operand(start, item),
+            evaluate_condition_operand(end, item),
+        ) {
+            (Some(target), Some(start), Some(end)) => {
+                attr_val_cmp(&target, &start).map_or(false, |o| o.is_ge())
+                    && attr_val_cmp(&target, &end).map_or(false, |o| o.is_le())
+            }
+            _ => false,
+        },
+        Condition::In(target, operands) => match evaluate_condition_operand(target, item) {
+            Some(target) => operands.iter().any(|operand| {
+                evaluate_condition_operand(operand, item)
+                    .map_or(false, |value| attr_val_eq(&target, &value))
+            }),
+            None => false,
+        },
+        Condition::AttributeExists(path) => resolve_path(path, item).is_some(),
+        Condition::AttributeNotExists(path) => resolve_path(path, item).is_none(),
+        Condition::AttributeType(path, operand) => {
+            match (
+                resolve_path(path, item),
+                evaluate_condition_operand(operand, item),
+            ) {
+                (Some(value), Some(AttributeValue::S(type_name))) => {
+                    attr_val_type_name(value) == Some(type_name.as_str())
+                }
+                _ => false,
+            }
+        }
+        Condition::BeginsWith(path, operand) => {
+            match (
+                resolve_path(path, item),
+                evaluate_condition_operand(operand, item),
+            ) {
+                (Some(AttributeValue::S(s)), Some(AttributeValue::S(prefix))) => {
+                    s.starts_with(prefix.as_str())
+                }
+                (Some(AttributeValue::B(b)), Some(AttributeValue::B(prefix))) => {
+                    b.as_ref().starts_with(prefix.as_ref())
+                }
+                _ => false,
+            }
+        }
+        Condition::Contains(path, operand) => {
+            match (
+                resolve_path(path, item),
+                evaluate_condition_operand(operand, item),
+            ) {
+                (Some(AttributeValue::S(s)), Some(AttributeValue::S(sub))) => {
+                    s.contains(sub.as_str())
+                }
+                (Some(AttributeValue::Ss(set)), Some(AttributeValue::S(s))) => set.contains(&s),
+                (Some(AttributeValue::Ns(set)), Some(AttributeValue::N(n))) => {
+                    set.iter().any(|m| number_eq(m, &n))
+                }
+                (Some(AttributeValue::Bs(set)), Some(AttributeValue::B(b))) => set.contains(&b),
+                (Some(AttributeValue::L(list)), Some(value)) => {
+                    list.iter().any(|elem| attr_val_eq(elem, &value))
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Returns the value of the operand, or None if it refers to a missing attribute.
+fn evaluate_condition_operand(
+    operand: &ConditionOperand,
+    item: &HashMap<String, AttributeValue>,
+) -> Option<AttributeValue> {
+    match operand {
+        ConditionOperand::Literal(val) => Some(val.clone().convert_attribute_value()),
+        ConditionOperand::Path(path) => resolve_path(path, item).cloned(),
+        ConditionOperand::Size(path) => {
+            let size = match resolve_path(path, item)? {
+                AttributeValue::S(s) => s.chars().count(),
+                AttributeValue::B(b) => b.as_ref().len(),
+                AttributeValue::Ss(set) => set.len(),
+                AttributeValue::Ns(set) => set.len(),
+                AttributeValue::Bs(set) => set.len(),
+                AttributeValue::L(list) => list.len(),
+                AttributeValue::M(map) => map.len(),
+                _ => return None,
+            };
+            Some(AttributeValue::N(size.to_string()))
+        }
+    }
+}
+
+/// Returns the type name used by `attribute_type` function, e.g. "S" or "BOOL".
+fn attr_val_type_name(val: &AttributeValue) -> Option<&'static str> {
+    match val {
+        AttributeValue::S(_) => Some("S"),
+        AttributeValue::N(_) => Some("N"),
+        AttributeValue::B(_) => Some("B"),
+        AttributeValue::Bool(_) => Some("BOOL"),
+        AttributeValue::Null(_) => Some("NULL"),
+        AttributeValue::L(_) => Some("L"),
+        AttributeValue::M(_) => Some("M"),
+        AttributeValue::Ss(_) => Some("SS"),
+        AttributeValue::Ns(_) => Some("NS"),
+        AttributeValue::Bs(_) => Some("BS"),
+        _ => None,
+    }
+}
+
+fn number_eq(a: &str, b: &str) -> bool {
+    matches!(number_cmp(a, b), Some(std::cmp::Ordering::Equal))
+}
+
+fn number_cmp(a: &str, b: &str) -> Option<std::cmp::Ordering> {
+    a.parse::<f64>().ok()?.partial_cmp(&b.parse::<f64>().ok()?)
+}
+
+/// Equality of values. Numbers are compared as numbers, and sets are compared regardless of the order of elements.
+fn attr_val_eq(a: &AttributeValue, b: &AttributeValue) -> bool {
+    match (a, b) {
+        (AttributeValue::N(a), AttributeValue::N(b)) => number_eq(a, b),
+        (AttributeValue::Ns(a), AttributeValue::Ns(b)) => {
+            a.len() == b.len() && a.iter().all(|x| b.iter().any(|y| number_eq(x, y)))
+        }
+        (AttributeValue::Ss(a), AttributeValue::Ss(b)) => {
+            a.len() == b.len() && a.iter().all(|x| b.contains(x))
+        }
+        (AttributeValue::Bs(a), AttributeValue::Bs(b)) => {
+            a.len() == b.len() && a.iter().all(|x| b.contains(x))
+        }
+        (AttributeValue::L(a), AttributeValue::L(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(x, y)| attr_val_eq(x, y))
+        }
+        (AttributeValue::M(a), AttributeValue::M(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, x)| b.get(k).map_or(false, |y| attr_val_eq(x, y)))
+        }
+        _ => a == b,
+    }
+}
+
+/// Ordering of values, which is defined only between numbers, strings (UTF-8 bytes) and binaries of the same type.
+fn attr_val_cmp(a: &AttributeValue, b: &AttributeValue) -> Option<std::cmp::Ordering> {
+    match (a, b) {
+        (AttributeValue::N(a), AttributeValue::N(b)) => number_cmp(a, b),
+        (AttributeValue::S(a), AttributeValue::S(b)) => Some(a.as_bytes().cmp(b.as_bytes())),
+        (AttributeValue::B(a), AttributeValue::B(b)) => Some(a.as_ref().cmp(b.as_ref())),
+        _ => None,
+    }
+}
+
 fn attr_val_ref(idx: usize) -> String {
     format!(":DYNEIN_ATTRVAL{}", idx)
 }
@@ -1380,6 +1989,24 @@ impl DyneinParser {
         }
     }
 
+    /// Create a new parser which continues from `ExpressionAttributeNames` and `ExpressionAttributeValues` built elsewhere.
+    ///
+    /// Placeholders generated by this parser never conflict with the given ones.
+    pub fn with_placeholders(
+        names: HashMap<String, String>,
+        values: HashMap<String, AttributeValue>,
+    ) -> DyneinParser {
+        let names_inv = names
+            .iter()
+            .map(|(placeholder, name)| (name.to_owned(), placeholder.to_owned()))
+            .collect();
+        DyneinParser {
+            names,
+            names_inv,
+            values,
+        }
+    }
+
     /// Clear the parser internal state for `ExpressionAttributeNames` and `ExpressionAttributeValues`.
     ///
     /// Currently, this function is used for testing purposes only.
@@ -1397,10 +2024,16 @@ impl DyneinParser {
         sort_attr: &AttributeDefinition,
     ) -> Result<ExpressionResult, ParseError> {
         self.parse_sort_key_without_fallback(exp, sort_attr)
-            .or_else(|err| match sort_attr.attribute_type {
-                AttributeType::S => self.parse_and_process_sort_key_for_string(exp, sort_attr),
-                AttributeType::N => self.parse_and_process_sort_key_for_number(exp, sort_attr),
-                _ => Err(err),
+            .or_else(|err| {
+                // Unsupported conditions must not be treated as a bare string.
+                if let Some(err) = detect_unsupported_sort_key_condition(exp, sort_attr) {
+                    return Err(err);
+                }
+                match sort_attr.attribute_type {
+                    AttributeType::S => self.parse_and_process_sort_key_for_string(exp, sort_attr),
+                    AttributeType::N => self.parse_and_process_sort_key_for_number(exp, sort_attr),
+                    _ => Err(err),
+                }
             })
     }
 
@@ -1411,6 +2044,9 @@ impl DyneinParser {
         sort_attr: &AttributeDefinition,
     ) -> Result<ExpressionResult, ParseError> {
         let mut pair = GeneratedParser::parse(Rule::sort_key, exp).map_err(|err| {
+            if let Some(err) = detect_unsupported_sort_key_condition(exp, sort_attr) {
+                return err;
+            }
             let fallback_result = self.try_sort_key_parse(exp, sort_attr);
             match fallback_result {
                 Some(exp) => ParseError::ParsingErrorWithSuggest(ParsingErrorWithSuggestError {
@@ -1424,6 +2060,42 @@ impl DyneinParser {
         self.process_sort_key(exp, sort_attr, condition)
     }
 
+    /// Build a sort key condition from raw values given by dedicated options such as `--sk-gt`.
+    ///
+    /// Values are interpreted as the type of the sort key so that users need not to quote them.
+    /// `values` must have two elements for [`SortKeyOperator::Between`], and one element for others.
+    pub fn parse_sort_key_from_raw_values(
+        &mut self,
+        operator: SortKeyOperator,
+        values: &[String],
+        sort_attr: &AttributeDefinition,
+    ) -> Result<ExpressionResult, ParseError> {
+        let mut values = values
+            .iter()
+            .map(|raw| raw_value_to_attr_val(raw, sort_attr.attribute_type))
+            .collect::<Result<Vec<AttrVal>, ParseError>>()?
+            .into_iter();
+        let mut next = || {
+            values
+                .next()
+                .expect("The number of values must match the operator")
+        };
+        let condition = match operator {
+            SortKeyOperator::Le => SortKeyCondition::Le(next()),
+            SortKeyOperator::Lt => SortKeyCondition::Lt(next()),
+            SortKeyOperator::Ge => SortKeyCondition::Ge(next()),
+            SortKeyOperator::Gt => SortKeyCondition::Gt(next()),
+            SortKeyOperator::Between => {
+                let start = next();
+                let end = next();
+                SortKeyCondition::Between(start, end)
+            }
+            SortKeyOperator::BeginsWith => SortKeyCondition::BeginsWith(next()),
+        };
+        let exp = condition.to_string();
+        self.process_sort_key(&exp, sort_attr, condition)
+    }
+
     /// Parse sort key condition in strict mode.
     fn parse_sort_key_without_fallback(
         &mut self,
@@ -1442,18 +2114,28 @@ impl DyneinParser {
         exp: &str,
     ) -> Result<HashMap<String, AttributeValue>, ParseError> {
         let result = GeneratedParser::parse(Rule::map_literal, exp);
+        match result {
+            Ok(mut pair) => merge_map_literal(initial_item, pair.next().unwrap()),
+            Err(err) => Err(ParseError::ParsingError(Box::new(err))),
+        }
+    }
+
+    /// Parse an item in the strict mode.
+    ///
+    /// Unlike [`DyneinParser::parse_dynein_format`], this method rejects trailing input after the map literal
+    /// and duplicate keys in any map literals instead of silently ignoring them.
+    pub fn parse_dynein_format_strict(
+        &self,
+        initial_item: Option<HashMap<String, AttributeValue>>,
+        exp: &str,
+    ) -> Result<HashMap<String, AttributeValue>, ParseError> {
+        let result = GeneratedParser::parse(Rule::item, exp);
         match result {
             Ok(mut pair) => {
-                // pair is parsed through Rule::map_literal so we expect that pair should be HashMap
-                let item = parse_literal(pair.next().unwrap())?
-                    .convert_attribute_value()
-                    .as_m()
-                    .unwrap()
-                    .to_owned();
-                // content must be map literal
-                let mut image = initial_item.unwrap_or_default();
-                image.extend(item);
-                Ok(image)
+                let pair = pair.next().unwrap();
+                check_duplicate_map_keys(&pair)?;
+                // this unwrap is safe because item has exactly one map literal
+                merge_map_literal(initial_item, pair.into_inner().next().unwrap())
             }
             Err(err) => Err(ParseError::ParsingError(Box::new(err))),
         }
@@ -1470,7 +2152,8 @@ impl DyneinParser {
                 let set_action = parse_set_action_pair(pair.next().unwrap())?;
                 self.process_set_action(set_action)
             }
-            Err(err) => Err(ParseError::ParsingError(Box::new(err))),
+            Err(err) => Err(detect_chained_arithmetic(exp)
+                .unwrap_or_else(|| ParseError::ParsingError(Box::new(err)))),
         }
     }
 
@@ -1489,6 +2172,49 @@ impl DyneinParser {
         }
     }
 
+    /// Parse a projection expression, e.g. `name, address.city, items[0].price`.
+    ///
+    /// Duplicated paths are projected only once because DynamoDB rejects them.
+    pub fn parse_projection(&mut self, exp: &str) -> Result<ExpressionResult, ParseError> {
+        let result = GeneratedParser::parse(Rule::projection, exp);
+        match result {
+            Ok(mut pair) => {
+                let mut projected: Vec<String> = Vec::new();
+                for path in pair.next().unwrap().into_inner().map(parse_path) {
+                    let path = self.process_path(path);
+                    if !projected.contains(&path) {
+                        projected.push(path);
+                    }
+                }
+                Ok(ExpressionResult {
+                    exp: projected.join(","),
+                    names: self.names.clone(),
+                    values: self.values.clone(),
+                })
+            }
+            Err(err) => Err(ParseError::ParsingError(Box::new(err))),
+        }
+    }
+
+    /// Parse a condition expression, e.g. `attribute_exists(pk) AND price < 100`.
+    ///
+    /// The result can be used for `ConditionExpression` or `FilterExpression`.
+    pub fn parse_condition(&mut self, exp: &str) -> Result<ExpressionResult, ParseError> {
+        let result = GeneratedParser::parse(Rule::condition, exp);
+        match result {
+            Ok(mut pair) => {
+                let condition = parse_condition_pair(pair.next().unwrap())?;
+                let expression = self.process_condition(condition)?;
+                Ok(ExpressionResult {
+                    exp: expression,
+                    names: self.names.clone(),
+                    values: self.values.clone(),
+                })
+            }
+            Err(err) => Err(ParseError::ParsingError(Box::new(err))),
+        }
+    }
+
     fn try_sort_key_parse(
         &self,
         exp: &str,
@@ -1589,6 +2315,7 @@ impl DyneinParser {
         input: ListAppendParameter,
     ) -> Result<String, ParseError> {
         match input {
+            ListAppendParameter::Function(function) => self.process_function(*function),
             ListAppendParameter::Path(path) => Ok(self.process_path(path)),
             ListAppendParameter::ListLiteral(literal) => self.process_literal(literal),
         }
@@ -1647,6 +2374,83 @@ impl DyneinParser {
         }
     }
 
+    fn process_condition_operand(&mut self, input: ConditionOperand) -> Result<String, ParseError> {
+        match input {
+            ConditionOperand::Size(path) => Ok(format!("size({})", self.process_path(path))),
+            ConditionOperand::Literal(literal) => self.process_literal(literal),
+            ConditionOperand::Path(path) => Ok(self.process_path(path)),
+        }
+    }
+
+    /// Generates an expression of the condition. Nested logical operators are enclosed in parentheses.
+    fn process_condition(&mut self, input: Condition) -> Result<String, ParseError> {
+        match input {
+            Condition::Or(conditions) => Ok(conditions
+                .into_iter()
+                .map(|c| self.process_nested_condition(c))
+                .collect::<Result<Vec<String>, ParseError>>()?
+                .join(" OR ")),
+            Condition::And(conditions) => Ok(conditions
+                .into_iter()
+                .map(|c| self.process_nested_condition(c))
+                .collect::<Result<Vec<String>, ParseError>>()?
+                .join(" AND ")),
+            Condition::Not(condition) => Ok(format!(
+                "NOT {}",
+                self.process_nested_condition(*condition)?
+            )),
+            Condition::Comparison(lhs, comparator, rhs) => {
+                let lhs = self.process_condition_operand(lhs)?;
+                let rhs = self.process_condition_operand(rhs)?;
+                Ok(format!("{} {} {}", lhs, comparator, rhs))
+            }
+            Condition::Between(target, start, end) => {
+                let target = self.process_condition_operand(target)?;
+                let start = self.process_condition_operand(start)?;
+                let end = self.process_condition_operand(end)?;
+                Ok(format!("{} BETWEEN {} AND {}", target, start, end))
+            }
+            Condition::In(target, candidates) => {
+                let target = self.process_condition_operand(target)?;
+                let candidates = candidates
+                    .into_iter()
+                    .map(|c| self.process_condition_operand(c))
+                    .collect::<Result<Vec<String>, ParseError>>()?;
+                Ok(format!("{} IN ({})", target, candidates.join(",")))
+            }
+            Condition::AttributeExists(path) => {
+                Ok(format!("attribute_exists({})", self.process_path(path)))
+            }
+            Condition::AttributeNotExists(path) => {
+                Ok(format!("attribute_not_exists({})", self.process_path(path)))
+            }
+            Condition::AttributeType(path, operand) => {
+                let path = self.process_path(path);
+                let operand = self.process_condition_operand(operand)?;
+                Ok(format!("attribute_type({},{})", path, operand))
+            }
+            Condition::BeginsWith(path, operand) => {
+                let path = self.process_path(path);
+                let operand = self.process_condition_operand(operand)?;
+                Ok(format!("begins_with({},{})", path, operand))
+            }
+            Condition::Contains(path, operand) => {
+                let path = self.process_path(path);
+                let operand = self.process_condition_operand(operand)?;
+                Ok(format!("contains({},{})", path, operand))
+            }
+        }
+    }
+
+    fn process_nested_condition(&mut self, input: Condition) -> Result<String, ParseError> {
+        match input {
+            Condition::Or(_) | Condition::And(_) => {
+                Ok(format!("({})", self.process_condition(input)?))
+            }
+            _ => self.process_condition(input),
+        }
+    }
+
     fn process_sort_key(
         &mut self,
         exp: &str,
@@ -2081,6 +2885,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_time_literal() {
+        let now = 1_700_000_000;
+        let parse = |input: &str| {
+            let pair = GeneratedParser::parse(Rule::literal, input)
+                .unwrap()
+                .next()
+                .unwrap();
+            parse_time_literal(pair, now)
+        };
+        assert_eq!(parse("now()"), Ok(now));
+        assert_eq!(parse("NOW( )"), Ok(now));
+        assert_eq!(parse("now_plus(7 days)"), Ok(now + 7 * 86400));
+        assert_eq!(parse("now_plus(1 hour)"), Ok(now + 3600));
+        assert_eq!(parse("now_plus(-30 minutes)"), Ok(now - 1800));
+        assert_eq!(parse("now_plus(2 weeks)"), Ok(now + 14 * 86400));
+        assert_eq!(parse(r#"epoch("2025-01-01T00:00:00Z")"#), Ok(1735689600));
+        assert_eq!(parse("epoch('2025-01-01T09:00:00+09:00')"), Ok(1735689600));
+        assert_eq!(
+            parse(r#"epoch("2025-01-01")"#),
+            Err(ParseError::InvalidTimeLiteral(
+                r#"epoch("2025-01-01")"#.to_owned()
+            ))
+        );
+        assert!(GeneratedParser::parse(Rule::literal, "now_plus(1 year)").is_err());
+    }
+
+    #[test]
+    fn test_parse_id_literal() {
+        let parse = |input: &str| {
+            let pair = GeneratedParser::parse(Rule::literal, input)
+                .unwrap()
+                .next()
+                .unwrap();
+            match parse_literal(pair).unwrap() {
+                AttrVal::S(id) => id,
+                other => panic!("Unexpected literal: {:?}", other),
+            }
+        };
+        let uuid = parse("uuid()");
+        assert_eq!(uuid::Uuid::parse_str(&uuid).unwrap().get_version_num(), 4);
+        assert_ne!(uuid, parse("UUID( )"));
+
+        let ulid = parse("ulid()");
+        assert_eq!(ulid.len(), 26);
+        assert_ne!(ulid, parse("ulid()"));
+
+        // The example in the ULID spec.
+        assert_eq!(encode_ulid(1469918176385, 0), "01ARYZ6S410000000000000000");
+        assert_eq!(
+            encode_ulid(1469918176385, u128::MAX),
+            "01ARYZ6S41ZZZZZZZZZZZZZZZZ"
+        );
+    }
+
     #[test]
     fn test_parse_literal() {
         // boolean literal
@@ -2427,6 +3286,138 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unsupported_sort_key_condition() {
+        let mut parser = DyneinParser::new();
+        let sort_attr = AttributeDefinition::new("sk", AttributeType::S);
+        let expect = |condition: &str, suggest: &str| {
+            Err(ParseError::UnsupportedSortKeyCondition(
+                UnsupportedSortKeyConditionError {
+                    condition: condition.to_owned(),
+                    suggest: suggest.to_owned(),
+                },
+            ))
+        };
+        assert_eq!(
+            parser.parse_sort_key_with_fallback("in (\"a\", \"b\")", &sort_attr),
+            expect("IN", "sk IN (\"a\", \"b\")")
+        );
+        assert_eq!(
+            parser.parse_sort_key_with_suggest("<> \"a\"", &sort_attr),
+            expect("'<>'", "sk <> \"a\"")
+        );
+        // `!=3` must not be treated as a bare string in non-strict mode
+        assert_eq!(
+            parser.parse_sort_key_with_fallback("!=3", &sort_attr),
+            expect("'!='", "sk <> 3")
+        );
+        assert_eq!(
+            parser.parse_sort_key_with_fallback("size(sk) > 3", &sort_attr),
+            expect("size()", "size(sk) > 3")
+        );
+        // a bare string which looks like a function name is still accepted
+        assert!(parser
+            .parse_sort_key_with_fallback("contains", &sort_attr)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_parse_sort_key_from_raw_values() {
+        let mut parser = DyneinParser::new();
+        assert_eq!(
+            parser
+                .parse_sort_key_from_raw_values(
+                    SortKeyOperator::BeginsWith,
+                    &["it's \"quoted\"".to_owned()],
+                    &AttributeDefinition::new("sk", AttributeType::S),
+                )
+                .unwrap(),
+            ExpressionResult {
+                exp: format!("begins_with({},{})", attr_name_ref(0), attr_val_ref(0)),
+                names: HashMap::from([(attr_name_ref(0), "sk".to_owned())]),
+                values: HashMap::from([(
+                    attr_val_ref(0),
+                    AttributeValue::S("it's \"quoted\"".to_owned())
+                )]),
+            }
+        );
+
+        parser.clear();
+        assert_eq!(
+            parser
+                .parse_sort_key_from_raw_values(
+                    SortKeyOperator::Between,
+                    &["-1".to_owned(), "1e3".to_owned()],
+                    &AttributeDefinition::new("sk", AttributeType::N),
+                )
+                .unwrap()
+                .get_values(),
+            HashMap::from([
+                (attr_val_ref(0), AttributeValue::N("-1".to_owned())),
+                (attr_val_ref(1), AttributeValue::N("1e3".to_owned())),
+            ])
+        );
+
+        parser.clear();
+        assert_eq!(
+            parser.parse_sort_key_from_raw_values(
+                SortKeyOperator::Gt,
+                &["12a".to_owned()],
+                &AttributeDefinition::new("sk", AttributeType::N),
+            ),
+            Err(ParseError::InvalidTypes(InvalidTypesError {
+                expected_type: AttributeType::N,
+                actual_type: AttributeType::S,
+            }))
+        );
+
+        parser.clear();
+        assert_eq!(
+            parser
+                .parse_sort_key_from_raw_values(
+                    SortKeyOperator::Le,
+                    &["ZmlsZQ==".to_owned()],
+                    &AttributeDefinition::new("sk", AttributeType::B),
+                )
+                .unwrap()
+                .get_expression(),
+            format!("{}<={}", attr_name_ref(0), attr_val_ref(0))
+        );
+    }
+
+    #[test]
+    fn test_with_placeholders() {
+        let mut parser = DyneinParser::new();
+        let key = parser
+            .parse_sort_key_with_fallback("= 1", &AttributeDefinition::new("sk", AttributeType::N))
+            .unwrap();
+        let mut names = key.get_names();
+        names.insert("#DYNEIN_PKNAME".to_owned(), "pk".to_owned());
+        let mut parser = DyneinParser::with_placeholders(names, key.get_values());
+        assert_eq!(
+            parser.parse_condition("sk <> 2 and name = \"a\"").unwrap(),
+            ExpressionResult {
+                exp: format!(
+                    "{} <> {} AND {} = {}",
+                    attr_name_ref(0),
+                    attr_val_ref(1),
+                    attr_name_ref(2),
+                    attr_val_ref(2)
+                ),
+                names: HashMap::from([
+                    ("#DYNEIN_PKNAME".to_owned(), "pk".to_owned()),
+                    (attr_name_ref(0), "sk".to_owned()),
+                    (attr_name_ref(2), "name".to_owned()),
+                ]),
+                values: HashMap::from([
+                    (attr_val_ref(0), AttributeValue::N("1".to_owned())),
+                    (attr_val_ref(1), AttributeValue::N("2".to_owned())),
+                    (attr_val_ref(2), AttributeValue::S("a".to_owned())),
+                ]),
+            }
+        );
+    }
+
     #[test]
     fn test_parse_sort_key() {
         let mut parser = DyneinParser::new();
@@ -2765,6 +3756,58 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_parse_dynein_format_strict() {
+        let parser = DyneinParser::new();
+        assert_eq!(
+            parser
+                .parse_dynein_format_strict(
+                    Some(HashMap::from([(
+                        "pk".to_owned(),
+                        AttributeValue::S("a".to_owned())
+                    )])),
+                    r#"{"k0": 1, "k1": {"k0": 2}}"#,
+                )
+                .unwrap(),
+            HashMap::from([
+                ("pk".to_owned(), AttributeValue::S("a".to_owned())),
+                ("k0".to_owned(), AttributeValue::N("1".to_owned())),
+                (
+                    "k1".to_owned(),
+                    AttributeValue::M(HashMap::from([(
+                        "k0".to_owned(),
+                        AttributeValue::N("2".to_owned())
+                    )]))
+                ),
+            ])
+        );
+
+        // trailing input after the map literal is ignored in the non-strict mode
+        assert!(parser
+            .parse_dynein_format(None, r#"{"k0": 1} "k1": 2}"#)
+            .is_ok());
+        assert!(matches!(
+            parser.parse_dynein_format_strict(None, r#"{"k0": 1} "k1": 2}"#),
+            Err(ParseError::ParsingError(_))
+        ));
+
+        // the last value wins in the non-strict mode
+        assert_eq!(
+            parser
+                .parse_dynein_format(None, r#"{"k0": 1, "k0": 2}"#)
+                .unwrap(),
+            HashMap::from([("k0".to_owned(), AttributeValue::N("2".to_owned()))])
+        );
+        assert_eq!(
+            parser.parse_dynein_format_strict(None, r#"{"k0": 1, 'k0': 2}"#),
+            Err(ParseError::DuplicateMapKey("k0".to_owned()))
+        );
+        assert_eq!(
+            parser.parse_dynein_format_strict(None, r#"{"k0": [{"k1": 1, "k1": 2}]}"#),
+            Err(ParseError::DuplicateMapKey("k1".to_owned()))
+        );
+    }
+
     #[test]
     fn test_parse_set_action() {
         let mut parser = DyneinParser::new();
@@ -2805,6 +3848,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_set_action_with_arithmetic() {
+        let mut parser = DyneinParser::new();
+        assert_eq!(
+            parser.parse_set_action("p0 = (p1 + p2)").unwrap(),
+            ExpressionResult {
+                exp: format!(
+                    "{}={}+{}",
+                    attr_name_ref(0),
+                    attr_name_ref(1),
+                    attr_name_ref(2)
+                ),
+                names: HashMap::from([
+                    (attr_name_ref(0), "p0".to_owned()),
+                    (attr_name_ref(1), "p1".to_owned()),
+                    (attr_name_ref(2), "p2".to_owned()),
+                ]),
+                values: HashMap::new(),
+            }
+        );
+
+        // DynamoDB rejects more than one arithmetic operator in a value.
+        for exp in [
+            "p0 = p1 + p2 - 1",
+            "p0 = (p1 + p2) - 1",
+            "p0 = 1, p1 = p1 - (p2 + 2)",
+        ] {
+            parser.clear();
+            assert_eq!(
+                parser.parse_set_action(exp),
+                Err(ParseError::ChainedArithmetic(exp.to_owned()))
+            );
+        }
+        parser.clear();
+        assert!(matches!(
+            parser.parse_set_action("p0 = (p1)"),
+            Err(ParseError::ParsingError(_))
+        ));
+
+        parser.clear();
+        assert_eq!(
+            parser
+                .parse_set_action(
+                    "p0 = list_append(if_not_exists(p0, []), [1]), p1 = if_not_exists(p1, 0) + 1"
+                )
+                .unwrap()
+                .get_expression(),
+            format!(
+                "{}=list_append(if_not_exists({},{}),{}),{}=if_not_exists({},{})+{}",
+                attr_name_ref(0),
+                attr_name_ref(0),
+                attr_val_ref(0),
+                attr_val_ref(1),
+                attr_name_ref(1),
+                attr_name_ref(1),
+                attr_val_ref(2),
+                attr_val_ref(3)
+            )
+        );
+    }
+
     #[test]
     fn test_set_and_remove_action() {
         let mut parser = DyneinParser::new();
@@ -2827,4 +3931,191 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_projection() {
+        let mut parser = DyneinParser::new();
+        assert_eq!(
+            parser
+                .parse_projection("`pk`, name, address.city, items[0].price, pk")
+                .unwrap(),
+            ExpressionResult {
+                exp: format!(
+                    "{},{},{}.{},{}[0].{}",
+                    attr_name_ref(0),
+                    attr_name_ref(1),
+                    attr_name_ref(2),
+                    attr_name_ref(3),
+                    attr_name_ref(4),
+                    attr_name_ref(5),
+                ),
+                names: HashMap::from([
+                    (attr_name_ref(0), "pk".to_owned()),
+                    (attr_name_ref(1), "name".to_owned()),
+                    (attr_name_ref(2), "address".to_owned()),
+                    (attr_name_ref(3), "city".to_owned()),
+                    (attr_name_ref(4), "items".to_owned()),
+                    (attr_name_ref(5), "price".to_owned()),
+                ]),
+                values: HashMap::new(),
+            }
+        );
+        assert!(parser.parse_projection("name,").is_err());
+    }
+
+    #[test]
+    fn test_document_path() {
+        let item = HashMap::from([
+            ("id".to_owned(), AttributeValue::S("1".to_owned())),
+            (
+                "attrs".to_owned(),
+                AttributeValue::M(HashMap::from([(
+                    "emails".to_owned(),
+                    AttributeValue::L(vec![
+                        AttributeValue::S("a@example.com".to_owned()),
+                        AttributeValue::S("b@example.com".to_owned()),
+                    ]),
+                )])),
+            ),
+        ]);
+        let extract = |exp: &str| DocumentPath::parse(exp).unwrap().extract(&item).cloned();
+
+        assert_eq!(extract("id"), Some(AttributeValue::S("1".to_owned())));
+        assert_eq!(
+            extract("attrs.emails[1]"),
+            Some(AttributeValue::S("b@example.com".to_owned()))
+        );
+        assert!(matches!(
+            extract("`attrs`.emails"),
+            Some(AttributeValue::L(_))
+        ));
+        assert_eq!(extract("attrs.emails[2]"), None);
+        assert_eq!(extract("attrs.phone"), None);
+        assert_eq!(extract("id.x"), None);
+        assert!(DocumentPath::parse("attrs.").is_err());
+        assert!(DocumentPath::parse("a, b").is_err());
+    }
+
+    #[test]
+    fn test_local_condition() {
+        let item = HashMap::from([
+            ("id".to_owned(), AttributeValue::S("user#1".to_owned())),
+            ("age".to_owned(), AttributeValue::N("20".to_owned())),
+            (
+                "tags".to_owned(),
+                AttributeValue::Ss(vec!["a".to_owned(), "b".to_owned()]),
+            ),
+            (
+                "attrs".to_owned(),
+                AttributeValue::M(HashMap::from([(
+                    "emails".to_owned(),
+                    AttributeValue::L(vec![AttributeValue::S("a@example.com".to_owned())]),
+                )])),
+            ),
+        ]);
+        let matches = |exp: &str| LocalCondition::parse(exp).unwrap().matches(&item);
+
+        assert!(matches("age >= 20 AND attribute_exists(attrs.emails[0])"));
+        assert!(matches("age = 20.0"));
+        assert!(!matches("age > \"10\""));
+        assert!(matches("age between 10 and 30"));
+        assert!(matches("id in (\"user#2\", \"user#1\")"));
+        assert!(matches(
+            "begins_with(id, \"user#\") and contains(tags, \"b\")"
+        ));
+        assert!(matches("attribute_type(tags, \"SS\")"));
+        assert!(matches("size(attrs.emails) = 1 or missing = 1"));
+        assert!(matches("missing <> 1"));
+        assert!(!matches("missing = 1"));
+        assert!(matches("nullable <> 1 and true_flag <> true"));
+        assert!(!matches("not (age < 30)"));
+        assert!(LocalCondition::parse("age >").is_err());
+    }
+
+    #[test]
+    fn test_condition() {
+        let mut parser = DyneinParser::new();
+        assert_eq!(
+            parser
+                .parse_condition("attribute_exists(p0) and p1 >= 10 or not p0 == \"x\"")
+                .unwrap(),
+            ExpressionResult {
+                exp: format!(
+                    "(attribute_exists({}) AND {} >= {}) OR NOT {} = {}",
+                    attr_name_ref(0),
+                    attr_name_ref(1),
+                    attr_val_ref(0),
+                    attr_name_ref(0),
+                    attr_val_ref(1),
+                ),
+                names: HashMap::from([
+                    (attr_name_ref(0), "p0".to_owned()),
+                    (attr_name_ref(1), "p1".to_owned()),
+                ]),
+                values: HashMap::from([
+                    (attr_val_ref(0), AttributeValue::N("10".to_owned())),
+                    (attr_val_ref(1), AttributeValue::S("x".to_owned())),
+                ]),
+            }
+        );
+
+        parser.clear();
+        assert_eq!(
+            parser
+                .parse_condition("size(p0) BETWEEN 1 AND 3 AND (p1 IN (1, 2) OR contains(p2, 'a'))")
+                .unwrap()
+                .get_expression(),
+            format!(
+                "size({}) BETWEEN {} AND {} AND ({} IN ({},{}) OR contains({},{}))",
+                attr_name_ref(0),
+                attr_val_ref(0),
+                attr_val_ref(1),
+                attr_name_ref(1),
+                attr_val_ref(2),
+                attr_val_ref(3),
+                attr_name_ref(2),
+                attr_val_ref(4),
+            )
+        );
+
+        // keywords should not be a part of identifiers
+        parser.clear();
+        assert_eq!(
+            parser
+                .parse_condition("notes <> 1")
+                .unwrap()
+                .get_expression(),
+            format!("{} <> {}", attr_name_ref(0), attr_val_ref(0))
+        );
+
+        // nor should literals such as true, false and null
+        for (exp, name, value) in [
+            (
+                "nullable = 1",
+                "nullable",
+                AttributeValue::N("1".to_owned()),
+            ),
+            ("true_flag = true", "true_flag", AttributeValue::Bool(true)),
+            ("falsey <> null", "falsey", AttributeValue::Null(true)),
+            ("trueval <> 1", "trueval", AttributeValue::N("1".to_owned())),
+        ] {
+            parser.clear();
+            assert_eq!(
+                parser.parse_condition(exp).unwrap(),
+                ExpressionResult {
+                    exp: format!(
+                        "{} {} {}",
+                        attr_name_ref(0),
+                        if exp.contains("<>") { "<>" } else { "=" },
+                        attr_val_ref(0)
+                    ),
+                    names: HashMap::from([(attr_name_ref(0), name.to_owned())]),
+                    values: HashMap::from([(attr_val_ref(0), value)]),
+                }
+            );
+        }
+
+        assert!(parser.parse_condition("p0 =").is_err());
+        assert!(parser.parse_condition("p0 = 1 and").is_err());
+    }
 }
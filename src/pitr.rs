@@ -0,0 +1,307 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module takes a consistent snapshot of a table for `dy export --consistent`.
+// Unlike Scan, which reads items at different times while the table is being written, ExportTableToPointInTime API
+// exports all items as of a single point in time. It requires point-in-time recovery (PITR) to be enabled on the table.
+// DynamoDB writes the export into S3 as gzipped DynamoDB JSON files, which are downloaded and read one by one.
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader},
+    time::{Duration, SystemTime},
+};
+
+use aws_sdk_dynamodb::{
+    primitives::DateTime as SdkDateTime,
+    types::{AttributeValue, ExportFormat, PointInTimeRecoveryStatus},
+    Client as DynamoDbSdkClient,
+};
+use aws_sdk_s3::Client as S3SdkClient;
+use chrono::{DateTime, Utc};
+use flate2::read::MultiGzDecoder;
+use log::{debug, error};
+use serde_json::Value as JsonValue;
+
+use super::app;
+use super::batch;
+use super::control;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Exports of large tables take a while, mostly depending on the table size.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Data files of a completed export, which are read as pages of items.
+pub struct ExportedFiles {
+    s3: S3SdkClient,
+    bucket: String,
+    data_file_keys: VecDeque<String>,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Exports the table into the S3 bucket as of now by ExportTableToPointInTime API, and waits for the export to complete.
+/// Exported files are left in the bucket, so that they can be reused or removed by S3 lifecycle rules.
+pub async fn export_to_s3(
+    cx: &app::Context,
+    ts: &app::TableSchema,
+    bucket: String,
+    prefix: Option<String>,
+    progress_to_stderr: bool,
+) -> ExportedFiles {
+    if cx.is_local().await {
+        app::bye(
+            1,
+            "DynamoDB Local doesn't support exporting tables to S3. --consistent is available only for tables on AWS.",
+        );
+    }
+
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+    ensure_pitr_enabled(&ddb, &ts.name).await;
+
+    let table_arn = control::describe_table_api(cx, ts.name.to_owned())
+        .await
+        .table_arn
+        .expect("DescribeTable should return the table ARN");
+    let export_time = SystemTime::now();
+    let req = ddb
+        .export_table_to_point_in_time()
+        .table_arn(table_arn)
+        .export_time(SdkDateTime::from(export_time))
+        .s3_bucket(&bucket)
+        .set_s3_prefix(prefix.to_owned())
+        .export_format(ExportFormat::DynamodbJson);
+    let export_arn = match req.send().await {
+        Err(e) => {
+            debug!("ExportTableToPointInTime API call got an error -- {:#?}", e);
+            app::bye_with_sdk_error(1, e);
+        }
+        Ok(res) => res
+            .export_description
+            .and_then(|desc| desc.export_arn)
+            .expect("ExportTableToPointInTime should return the export ARN"),
+    };
+    let message = format!(
+        "Exporting the table '{}' as of {} into s3://{}/{} ({})",
+        ts.name,
+        DateTime::<Utc>::from(export_time).to_rfc3339(),
+        bucket,
+        prefix.as_deref().unwrap_or_default(),
+        export_arn
+    );
+    if progress_to_stderr {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+
+    let desc =
+        match control::wait_for_export(cx, &export_arn, EXPORT_TIMEOUT, progress_to_stderr).await {
+            Ok(desc) => desc,
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        };
+
+    let s3 = S3SdkClient::new(&config);
+    let manifest_summary_key = desc
+        .export_manifest
+        .expect("a completed export should have the manifest");
+    let data_file_keys = match read_data_file_keys(&s3, &bucket, &manifest_summary_key).await {
+        Ok(keys) => keys,
+        Err(e) => {
+            error!("Failed to read the manifest of the export: {}", e);
+            std::process::exit(1);
+        }
+    };
+    debug!("data files of the export: {:?}", data_file_keys);
+
+    ExportedFiles {
+        s3,
+        bucket,
+        data_file_keys,
+    }
+}
+
+impl ExportedFiles {
+    /// Downloads the next data file and returns items in it, or None if all data files have been read.
+    pub async fn next_page(&mut self) -> io::Result<Option<Vec<HashMap<String, AttributeValue>>>> {
+        let key = match self.data_file_keys.pop_front() {
+            None => return Ok(None),
+            Some(key) => key,
+        };
+        let bytes = get_object(&self.s3, &self.bucket, &key).await?;
+        parse_data_file(&bytes[..]).map(Some)
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+async fn ensure_pitr_enabled(ddb: &DynamoDbSdkClient, table_name: &str) {
+    let status = match ddb
+        .describe_continuous_backups()
+        .table_name(table_name)
+        .send()
+        .await
+    {
+        Err(e) => {
+            debug!(
+                "DescribeContinuousBackups API call got an error -- {:#?}",
+                e
+            );
+            app::bye_with_sdk_error(1, e);
+        }
+        Ok(res) => res
+            .continuous_backups_description
+            .and_then(|desc| desc.point_in_time_recovery_description)
+            .and_then(|desc| desc.point_in_time_recovery_status),
+    };
+    if status != Some(PointInTimeRecoveryStatus::Enabled) {
+        app::bye(
+            1,
+            &format!(
+                "Point-in-time recovery (PITR) is not enabled on the table '{}', which --consistent requires. \
+                Enable it, e.g. by `aws dynamodb update-continuous-backups --table-name {} --point-in-time-recovery-specification PointInTimeRecoveryEnabled=true`.",
+                table_name, table_name
+            ),
+        );
+    }
+}
+
+/// Reads S3 keys of data files from the manifest of the export.
+/// manifest-summary.json points to manifest-files.json, which has a JSON line per data file.
+async fn read_data_file_keys(
+    s3: &S3SdkClient,
+    bucket: &str,
+    manifest_summary_key: &str,
+) -> io::Result<VecDeque<String>> {
+    let summary: JsonValue =
+        serde_json::from_slice(&get_object(s3, bucket, manifest_summary_key).await?)?;
+    let manifest_files_key = summary["manifestFilesS3Key"].as_str().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "manifestFilesS3Key is missing")
+    })?;
+    let manifest_files = get_object(s3, bucket, manifest_files_key).await?;
+    parse_manifest_files(&manifest_files[..])
+}
+
+fn parse_manifest_files(manifest_files: &[u8]) -> io::Result<VecDeque<String>> {
+    let mut keys = VecDeque::new();
+    for line in manifest_files.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: JsonValue = serde_json::from_str(&line)?;
+        match entry["dataFileS3Key"].as_str() {
+            Some(key) => keys.push_back(key.to_owned()),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("dataFileS3Key is missing in a manifest entry: {}", line),
+                ))
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Parses a gzipped data file of the export, which has a line of `{"Item": {...}}` in DynamoDB JSON per item.
+fn parse_data_file(gzipped: &[u8]) -> io::Result<Vec<HashMap<String, AttributeValue>>> {
+    let mut items = Vec::new();
+    for line in BufReader::new(MultiGzDecoder::new(gzipped)).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: JsonValue = serde_json::from_str(&line)?;
+        items.push(batch::ddbjson_attributes_to_attrvals(&entry["Item"]));
+    }
+    Ok(items)
+}
+
+async fn get_object(s3: &S3SdkClient, bucket: &str, key: &str) -> io::Result<bytes::Bytes> {
+    debug!("downloading s3://{}/{}", bucket, key);
+    let res = match s3.get_object().bucket(bucket).key(key).send().await {
+        Err(e) => {
+            debug!("GetObject API call got an error -- {:#?}", e);
+            app::bye_with_sdk_error(1, e);
+        }
+        Ok(res) => res,
+    };
+    let body = res
+        .body
+        .collect()
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    Ok(body.into_bytes())
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_manifest_files() {
+        let manifest = br#"{"itemCount":2,"md5Checksum":"x","etag":"y","dataFileS3Key":"AWSDynamoDB/01/data/a.json.gz"}
+{"itemCount":1,"md5Checksum":"x","etag":"y","dataFileS3Key":"AWSDynamoDB/01/data/b.json.gz"}
+"#;
+        assert_eq!(
+            parse_manifest_files(manifest).unwrap(),
+            VecDeque::from(vec![
+                String::from("AWSDynamoDB/01/data/a.json.gz"),
+                String::from("AWSDynamoDB/01/data/b.json.gz"),
+            ])
+        );
+        assert!(parse_manifest_files(br#"{"itemCount":1}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_data_file() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(
+                br#"{"Item":{"pk":{"S":"a"},"n":{"N":"1"}}}
+{"Item":{"pk":{"S":"b"}}}
+"#,
+            )
+            .unwrap();
+        let items = parse_data_file(&encoder.finish().unwrap()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].get("pk"),
+            Some(&AttributeValue::S(String::from("a")))
+        );
+        assert_eq!(
+            items[0].get("n"),
+            Some(&AttributeValue::N(String::from("1")))
+        );
+        assert_eq!(items[1].len(), 1);
+    }
+}
@@ -0,0 +1,460 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements --sort-by and --group-by options of scan and query, which sort items and count them per value
+// on the client side after they are read, so that quick analyses don't need jq or sort.
+// `dy scan --distinct` counts values over all items of a table in the same way.
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use aws_sdk_dynamodb::types::AttributeValue;
+use log::error;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use tabwriter::TabWriter;
+
+use super::data;
+use super::output::{self, Item, ItemsView};
+use super::parser::DocumentPath;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Output formats which --group-by supports.
+const GROUP_FORMAT_NAMES: [&str; 3] = ["table", "json", "jsonl"];
+
+/// An attribute given to --sort-by or --group-by, which can be a document path like `address.city`.
+struct Attribute {
+    name: String,
+    path: DocumentPath,
+}
+
+/// Options to process items after they are read, i.e. --sort-by and --group-by. Both options are optional.
+pub struct PostProcess {
+    sort_by: Option<Attribute>,
+    descending: bool,
+    group_by: Option<Attribute>,
+}
+
+/// Items which have the same value of the --group-by attribute. The value is None for items without the attribute.
+struct Group {
+    value: Option<AttributeValue>,
+    count: usize,
+}
+
+/// Counts items per value of an attribute. Groups are listed in the order their first items appear.
+#[derive(Default)]
+struct GroupCounter {
+    groups: Vec<Group>,
+    /// Values are keyed by their types and JSON representation, so that e.g. number 1 and string "1" differ.
+    indexes: HashMap<Option<(Option<String>, String)>, usize>,
+}
+
+/// Distinct values of an attribute with their counts over all items read by `dy scan --distinct`.
+pub struct Distinct {
+    attr: Attribute,
+    counter: GroupCounter,
+    items: usize,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+impl PostProcess {
+    /// Validates the options before reading items, so that a typo doesn't cost a read.
+    /// `sort_by` is an attribute optionally followed by `:asc` or `:desc`, e.g. `price:desc`.
+    pub fn new(
+        sort_by: Option<&str>,
+        group_by: Option<&str>,
+        output: Option<&str>,
+    ) -> Result<PostProcess, String> {
+        let (sort_by, descending) = match sort_by {
+            None => (None, false),
+            Some(s) => {
+                let (name, descending) = match s.rsplit_once(':') {
+                    Some((name, "desc")) => (name, true),
+                    Some((name, "asc")) => (name, false),
+                    _ => (s, false),
+                };
+                (Some(Attribute::parse(name, "--sort-by")?), descending)
+            }
+        };
+        let group_by = group_by
+            .map(|name| Attribute::parse(name, "--group-by"))
+            .transpose()?;
+        if group_by.is_some() {
+            check_group_format("--group-by", output)?;
+        }
+        Ok(PostProcess {
+            sort_by,
+            descending,
+            group_by,
+        })
+    }
+
+    /// Returns a PostProcess created by [`PostProcess::new`], or exits with an error message if the options are invalid.
+    pub fn new_or_exit(
+        sort_by: Option<&str>,
+        group_by: Option<&str>,
+        output: Option<&str>,
+    ) -> PostProcess {
+        PostProcess::new(sort_by, group_by, output).unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(1);
+        })
+    }
+
+    /// Prints items in the same way as [`output::print_items`] after sorting them,
+    /// or prints the number of items per value instead if --group-by is given.
+    pub fn print_items(&self, output: Option<&str>, items: &[Item], view: &ItemsView) {
+        if self.sort_by.is_none() && self.group_by.is_none() {
+            return output::print_items(output, "table", items, view);
+        }
+        let items = self.sort(items);
+        match &self.group_by {
+            None => output::print_items(output, "table", &items, view),
+            Some(attr) => print_groups(output, &attr.name, &group(&items, &attr.path)),
+        }
+    }
+
+    /// Sorts items by the --sort-by attribute, or by the --group-by attribute if --sort-by isn't given.
+    /// The sort is stable, i.e. items with the same value keep the order they're read in.
+    fn sort(&self, items: &[Item]) -> Vec<Item> {
+        let mut items = items.to_vec();
+        if let Some(attr) = self.sort_by.as_ref().or(self.group_by.as_ref()) {
+            items.sort_by(|a, b| {
+                compare_values(attr.path.extract(a), attr.path.extract(b), self.descending)
+            });
+        }
+        items
+    }
+}
+
+impl Distinct {
+    /// Validates the attribute and the output format before scanning, so that a typo doesn't cost a read.
+    pub fn new_or_exit(name: &str, output: Option<&str>) -> Distinct {
+        let attr = Attribute::parse(name, "--distinct")
+            .and_then(|attr| check_group_format("--distinct", output).map(|_| attr))
+            .unwrap_or_else(|e| {
+                error!("{}", e);
+                std::process::exit(1);
+            });
+        Distinct {
+            attr,
+            counter: GroupCounter::default(),
+            items: 0,
+        }
+    }
+
+    /// Name of the attribute, which can be used as a projection expression to read only the attribute.
+    pub fn attribute(&self) -> &str {
+        &self.attr.name
+    }
+
+    /// Counts values of items in a page. Only the counts are kept, so that a large table can be scanned.
+    pub fn add_items(&mut self, items: &[Item]) {
+        for item in items {
+            self.counter.add(self.attr.path.extract(item));
+        }
+        self.items += items.len();
+    }
+
+    /// Prints values with their counts, the most frequent first, followed by a line of the cardinality to stderr.
+    /// Values of the same count are sorted by value, and the count of items without the attribute comes last.
+    pub fn print(self, output: Option<&str>) {
+        let (name, items) = (self.attr.name.to_owned(), self.items);
+        let groups = self.into_sorted_groups();
+        print_groups(output, &name, &groups);
+        eprintln!(
+            "{} distinct value(s) of '{}' in {} item(s).",
+            groups.iter().filter(|g| g.value.is_some()).count(),
+            name,
+            items
+        );
+    }
+
+    fn into_sorted_groups(self) -> Vec<Group> {
+        let mut groups = self.counter.groups;
+        groups.sort_by(|a, b| {
+            (a.value.is_none(), b.count)
+                .cmp(&(b.value.is_none(), a.count))
+                .then_with(|| compare_values(a.value.as_ref(), b.value.as_ref(), false))
+        });
+        groups
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+impl Attribute {
+    fn parse(name: &str, option: &str) -> Result<Attribute, String> {
+        let name = name.trim();
+        match DocumentPath::parse(name) {
+            Ok(path) => Ok(Attribute {
+                name: name.to_owned(),
+                path,
+            }),
+            Err(e) => Err(format!(
+                "Invalid attribute for {} '{}': {}",
+                option, name, e
+            )),
+        }
+    }
+}
+
+/// Compares values of the attribute. Numbers are compared by value, and strings and binaries lexicographically.
+/// Items without the attribute always come last, even in descending order.
+fn compare_values(
+    left: Option<&AttributeValue>,
+    right: Option<&AttributeValue>,
+    descending: bool,
+) -> Ordering {
+    match (left, right) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(l), Some(r)) if descending => compare_attrvals(r, l),
+        (Some(l), Some(r)) => compare_attrvals(l, r),
+    }
+}
+
+/// Total order of attribute values. Values of different types are ordered by type, i.e. numbers, strings, binaries,
+/// booleans, nulls and others, where others (e.g. maps, lists and sets) are compared by their JSON representation.
+fn compare_attrvals(left: &AttributeValue, right: &AttributeValue) -> Ordering {
+    match (left, right) {
+        (AttributeValue::N(l), AttributeValue::N(r)) => {
+            match (l.parse::<f64>(), r.parse::<f64>()) {
+                (Ok(l), Ok(r)) => l.total_cmp(&r),
+                _ => l.cmp(r),
+            }
+        }
+        (AttributeValue::S(l), AttributeValue::S(r)) => l.cmp(r),
+        (AttributeValue::B(l), AttributeValue::B(r)) => l.as_ref().cmp(r.as_ref()),
+        (AttributeValue::Bool(l), AttributeValue::Bool(r)) => l.cmp(r),
+        (l, r) => type_rank(l).cmp(&type_rank(r)).then_with(|| {
+            data::attrval_to_jsonval(l)
+                .to_string()
+                .cmp(&data::attrval_to_jsonval(r).to_string())
+        }),
+    }
+}
+
+fn type_rank(attrval: &AttributeValue) -> u8 {
+    match attrval {
+        AttributeValue::N(_) => 0,
+        AttributeValue::S(_) => 1,
+        AttributeValue::B(_) => 2,
+        AttributeValue::Bool(_) => 3,
+        AttributeValue::Null(_) => 4,
+        _ => 5,
+    }
+}
+
+/// Counts items per value of the attribute. Groups are listed in the order their first items appear.
+fn group(items: &[Item], path: &DocumentPath) -> Vec<Group> {
+    let mut counter = GroupCounter::default();
+    for item in items {
+        counter.add(path.extract(item));
+    }
+    counter.groups
+}
+
+impl GroupCounter {
+    fn add(&mut self, value: Option<&AttributeValue>) {
+        let key = value.map(|v| {
+            (
+                data::attrval_to_type(v),
+                data::attrval_to_jsonval(v).to_string(),
+            )
+        });
+        match self.indexes.get(&key) {
+            Some(&i) => self.groups[i].count += 1,
+            None => {
+                self.indexes.insert(key, self.groups.len());
+                self.groups.push(Group {
+                    value: value.cloned(),
+                    count: 1,
+                });
+            }
+        }
+    }
+}
+
+fn check_group_format(option: &str, output: Option<&str>) -> Result<(), String> {
+    let format = output.unwrap_or("table");
+    if GROUP_FORMAT_NAMES.contains(&format) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} doesn't support the output format '{}'. Available formats are: {}",
+            option,
+            format,
+            GROUP_FORMAT_NAMES.join(", ")
+        ))
+    }
+}
+
+fn print_groups(output: Option<&str>, name: &str, groups: &[Group]) {
+    let mut stdout = io::stdout().lock();
+    if let Err(e) = write_groups(&mut stdout, output.unwrap_or("table"), name, groups) {
+        error!("Failed to write groups: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Writes groups in the output format. The table format shows strings and numbers as they are, other types as JSON,
+/// and an empty cell for items without the attribute. JSON formats omit the attribute for such items.
+fn write_groups(w: &mut dyn Write, format: &str, name: &str, groups: &[Group]) -> io::Result<()> {
+    match format {
+        "json" | "jsonl" => {
+            let objects: Vec<JsonValue> = groups
+                .iter()
+                .map(|g| {
+                    let mut object = JsonMap::new();
+                    if let Some(v) = &g.value {
+                        object.insert(name.to_owned(), data::attrval_to_jsonval(v));
+                    }
+                    object.insert(String::from("count"), JsonValue::from(g.count));
+                    JsonValue::Object(object)
+                })
+                .collect();
+            if format == "json" {
+                serde_json::to_writer_pretty(&mut *w, &objects)?;
+                writeln!(w)
+            } else {
+                for object in objects {
+                    serde_json::to_writer(&mut *w, &object)?;
+                    writeln!(w)?;
+                }
+                Ok(())
+            }
+        }
+        _ => {
+            let mut tw = TabWriter::new(vec![]);
+            writeln!(tw, "{}\tcount", name)?;
+            for g in groups {
+                let value = match &g.value {
+                    None => String::new(),
+                    Some(AttributeValue::S(v) | AttributeValue::N(v)) => v.to_owned(),
+                    Some(v) => data::attrval_to_jsonval(v).to_string(),
+                };
+                writeln!(tw, "{}\t{}", value, g.count)?;
+            }
+            w.write_all(&tw.into_inner().map_err(io::Error::other)?)
+        }
+    }
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(pk: &str, price: Option<AttributeValue>) -> Item {
+        let mut item = HashMap::from([(String::from("pk"), AttributeValue::S(pk.to_owned()))]);
+        if let Some(price) = price {
+            item.insert(String::from("price"), price);
+        }
+        item
+    }
+
+    fn pks(items: &[Item]) -> Vec<&str> {
+        items
+            .iter()
+            .map(|i| i.get("pk").unwrap().as_s().unwrap().as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort() {
+        let n = |v: &str| Some(AttributeValue::N(v.to_owned()));
+        let items = vec![
+            item("a", n("10")),
+            item("b", None),
+            item("c", n("9.5")),
+            item("d", Some(AttributeValue::S(String::from("free")))),
+            item("e", n("-1")),
+        ];
+
+        // Numbers are compared by value rather than as strings, and missing values come last.
+        let pp = PostProcess::new(Some("price"), None, None).unwrap();
+        assert_eq!(pks(&pp.sort(&items)), vec!["e", "c", "a", "d", "b"]);
+
+        let pp = PostProcess::new(Some("price:desc"), None, None).unwrap();
+        assert_eq!(pks(&pp.sort(&items)), vec!["d", "a", "c", "e", "b"]);
+    }
+
+    #[test]
+    fn test_group() {
+        let s = |v: &str| Some(AttributeValue::S(v.to_owned()));
+        let items = vec![
+            item("a", s("x")),
+            item("b", None),
+            item("c", s("y")),
+            item("d", s("x")),
+            item("e", Some(AttributeValue::N(String::from("1")))),
+            item("f", s("1")),
+        ];
+        let pp = PostProcess::new(None, Some("price"), None).unwrap();
+        let groups = group(&pp.sort(&items), &pp.group_by.as_ref().unwrap().path);
+        let mut w = vec![];
+        write_groups(&mut w, "table", "price", &groups).unwrap();
+        assert_eq!(
+            String::from_utf8(w).unwrap(),
+            "price  count\n1      1\n1      1\nx      2\ny      1\n       1\n"
+        );
+
+        let mut w = vec![];
+        write_groups(&mut w, "jsonl", "price", &groups).unwrap();
+        assert_eq!(
+            String::from_utf8(w).unwrap(),
+            "{\"count\":1,\"price\":1}\n{\"count\":1,\"price\":\"1\"}\n{\"count\":2,\"price\":\"x\"}\n{\"count\":1,\"price\":\"y\"}\n{\"count\":1}\n"
+        );
+    }
+
+    #[test]
+    fn test_distinct() {
+        let s = |v: &str| Some(AttributeValue::S(v.to_owned()));
+        let mut distinct = Distinct::new_or_exit("price", None);
+        distinct.add_items(&[item("a", s("y")), item("b", None), item("c", None)]);
+        distinct.add_items(&[item("d", s("x")), item("e", s("y"))]);
+        assert_eq!(distinct.items, 5);
+
+        // The most frequent first, and the count of items without the attribute last.
+        let mut w = vec![];
+        write_groups(&mut w, "table", "price", &distinct.into_sorted_groups()).unwrap();
+        assert_eq!(
+            String::from_utf8(w).unwrap(),
+            "price  count\ny      2\nx      1\n       2\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        assert!(PostProcess::new(Some("tags["), None, None).is_err());
+        assert!(PostProcess::new(None, Some("status"), Some("csv")).is_err());
+        assert!(PostProcess::new(None, Some("status"), Some("json")).is_ok());
+    }
+}
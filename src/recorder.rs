@@ -0,0 +1,326 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module records HTTP interactions with AWS into files (DYNEIN_RECORD=dir) and replays them without network (DYNEIN_REPLAY=dir).
+// It's implemented as an HTTP client of the SDK, so that every API call made through Context is recorded or replayed.
+//
+// Each interaction is stored as "<Operation>-<hash of the request>-<n>.json", where n counts identical requests in a process.
+// Bodies are compared after normalizing JSON, as the SDK serializes maps in arbitrary order.
+// If the same request is recorded by multiple dy invocations, the last one wins.
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use ::serde::{Deserialize, Serialize};
+use aws_smithy_runtime_api::{
+    client::{
+        http::{
+            HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings,
+            SharedHttpClient, SharedHttpConnector,
+        },
+        orchestrator::{HttpRequest, HttpResponse},
+        result::ConnectorError,
+        runtime_components::RuntimeComponents,
+    },
+    http::StatusCode,
+};
+use aws_smithy_types::{body::SdkBody, byte_stream::ByteStream};
+use log::{debug, error};
+use serde_json::Value as JsonValue;
+
+const RECORD_ENV_VAR_NAME: &str = "DYNEIN_RECORD";
+const REPLAY_ENV_VAR_NAME: &str = "DYNEIN_REPLAY";
+
+/// Number of requests sent so far in this process, per request key.
+static OCCURRENCES: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+pub enum Mode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl Mode {
+    /// Returns the mode given by DYNEIN_RECORD or DYNEIN_REPLAY environment variable. DYNEIN_REPLAY takes precedence.
+    pub fn from_env() -> Option<Mode> {
+        if let Ok(dir) = env::var(REPLAY_ENV_VAR_NAME) {
+            return Some(Mode::Replay(PathBuf::from(dir)));
+        }
+        if let Ok(dir) = env::var(RECORD_ENV_VAR_NAME) {
+            return Some(Mode::Record(PathBuf::from(dir)));
+        }
+        None
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedRequest {
+    method: String,
+    uri: String,
+    target: Option<String>,
+    body: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+/// HTTP client which sends requests with the inner client and writes interactions into the directory.
+#[derive(Debug)]
+pub struct RecordingClient {
+    dir: PathBuf,
+    inner: SharedHttpClient,
+}
+
+impl RecordingClient {
+    pub fn new(dir: PathBuf, inner: SharedHttpClient) -> RecordingClient {
+        if let Err(e) = fs::create_dir_all(&dir) {
+            error!("Failed to create the directory '{}': {}", dir.display(), e);
+            std::process::exit(1);
+        }
+        RecordingClient { dir, inner }
+    }
+}
+
+impl HttpClient for RecordingClient {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(RecordingConnector {
+            dir: self.dir.clone(),
+            inner: self.inner.http_connector(settings, components),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct RecordingConnector {
+    dir: PathBuf,
+    inner: SharedHttpConnector,
+}
+
+impl HttpConnector for RecordingConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let recorded_request = RecordedRequest::from(&request);
+        let path = self.dir.join(file_name(
+            &recorded_request,
+            next_occurrence(&recorded_request),
+        ));
+        let inner = self.inner.clone();
+
+        HttpConnectorFuture::new(async move {
+            let response = inner.call(request).await?;
+            let status = response.status().as_u16();
+            let headers: Vec<(String, String)> = response
+                .headers()
+                .iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect();
+            let body = ByteStream::new(response.into_body())
+                .collect()
+                .await
+                .map_err(|e| ConnectorError::other(e.into(), None))?
+                .into_bytes();
+
+            let interaction = Interaction {
+                request: recorded_request,
+                response: RecordedResponse {
+                    status,
+                    headers,
+                    body: String::from_utf8_lossy(&body).into_owned(),
+                },
+            };
+            // Failing to record shouldn't break the command itself.
+            match serde_json::to_string_pretty(&interaction)
+                .map_err(|e| e.to_string())
+                .and_then(|s| fs::write(&path, s).map_err(|e| e.to_string()))
+            {
+                Ok(_) => debug!("Recorded an interaction into '{}'", path.display()),
+                Err(e) => error!("Failed to record into '{}': {}", path.display(), e),
+            }
+
+            Ok(interaction.response.into_http_response())
+        })
+    }
+}
+
+/// HTTP client which serves recorded responses from the directory without network.
+#[derive(Debug)]
+pub struct ReplayingClient {
+    dir: PathBuf,
+}
+
+impl ReplayingClient {
+    pub fn new(dir: PathBuf) -> ReplayingClient {
+        ReplayingClient { dir }
+    }
+}
+
+impl HttpClient for ReplayingClient {
+    fn http_connector(
+        &self,
+        _: &HttpConnectorSettings,
+        _: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(ReplayingConnector {
+            dir: self.dir.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ReplayingConnector {
+    dir: PathBuf,
+}
+
+impl HttpConnector for ReplayingConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let recorded_request = RecordedRequest::from(&request);
+        let occurrence = next_occurrence(&recorded_request);
+        HttpConnectorFuture::ready(
+            find_recording(&self.dir, &recorded_request, occurrence)
+                .map(|interaction| interaction.response.into_http_response())
+                .ok_or_else(|| {
+                    ConnectorError::other(
+                        format!(
+                            "No recorded response for the request {} (target: {}) in '{}'",
+                            recorded_request.uri,
+                            recorded_request.target.as_deref().unwrap_or("-"),
+                            self.dir.display()
+                        )
+                        .into(),
+                        None,
+                    )
+                }),
+        )
+    }
+}
+
+/// Finds the n-th recording of the request. Falls back to the last one when the request is repeated more times than recorded,
+/// e.g. a waiter polling DescribeTable.
+fn find_recording(dir: &Path, request: &RecordedRequest, occurrence: usize) -> Option<Interaction> {
+    (0..=occurrence).rev().find_map(|n| {
+        let path = dir.join(file_name(request, n));
+        let s = fs::read_to_string(&path).ok()?;
+        debug!("Replaying an interaction from '{}'", path.display());
+        serde_json::from_str(&s).ok()
+    })
+}
+
+impl From<&HttpRequest> for RecordedRequest {
+    fn from(request: &HttpRequest) -> Self {
+        RecordedRequest {
+            method: request.method().to_owned(),
+            uri: request.uri().to_owned(),
+            target: request.headers().get("x-amz-target").map(String::from),
+            body: String::from_utf8_lossy(request.body().bytes().unwrap_or_default()).into_owned(),
+        }
+    }
+}
+
+impl RecordedResponse {
+    fn into_http_response(self) -> HttpResponse {
+        let mut response = HttpResponse::new(
+            StatusCode::try_from(self.status).expect("recorded status code should be valid"),
+            SdkBody::from(self.body),
+        );
+        for (k, v) in self.headers {
+            response.headers_mut().insert(k, v);
+        }
+        response
+    }
+}
+
+fn next_occurrence(request: &RecordedRequest) -> usize {
+    let mut occurrences = OCCURRENCES.lock().expect("lock should not be poisoned");
+    let n = occurrences.entry(request_key(request)).or_insert(0);
+    *n += 1;
+    *n - 1
+}
+
+fn file_name(request: &RecordedRequest, occurrence: usize) -> String {
+    format!("{}-{}.json", request_key(request), occurrence)
+}
+
+/// Builds a key identifying the request, e.g. "Scan-8c1b2a3f4d5e6f70".
+fn request_key(request: &RecordedRequest) -> String {
+    let operation = request
+        .target
+        .as_deref()
+        .and_then(|t| t.rsplit('.').next())
+        .unwrap_or("Request");
+    // serde_json::Value sorts object keys, which normalizes the body.
+    let body = serde_json::from_str::<JsonValue>(&request.body)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|_| request.body.to_owned());
+    let source = format!(
+        "{} {} {} {}",
+        request.method,
+        request.uri,
+        request.target.as_deref().unwrap_or_default(),
+        body
+    );
+    format!("{}-{:016x}", operation, fnv1a(source.as_bytes()))
+}
+
+/// 64-bit FNV-1a hash. Unlike DefaultHasher, the result is stable across Rust versions, so recordings can be shared.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: &str) -> RecordedRequest {
+        RecordedRequest {
+            method: "POST".to_owned(),
+            uri: "http://localhost:8000/".to_owned(),
+            target: Some("DynamoDB_20120810.Scan".to_owned()),
+            body: body.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_request_key() {
+        let key = request_key(&request(r#"{"TableName":"t","Limit":1}"#));
+        assert!(key.starts_with("Scan-"));
+        assert_eq!(key, request_key(&request(r#"{"Limit":1,"TableName":"t"}"#)));
+        assert_ne!(key, request_key(&request(r#"{"TableName":"t","Limit":2}"#)));
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
@@ -0,0 +1,207 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements `dy admin replica lag`, which measures replication lag of a global table.
+// It writes a probe item in the current region, polls every replica region until the item appears there,
+// and deletes the probe item at the end.
+use std::{
+    borrow::Cow::{Borrowed, Owned},
+    collections::HashMap,
+    io::Write,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use aws_sdk_dynamodb::{
+    primitives::Blob,
+    types::{AttributeValue, TableDescription},
+    Client as DynamoDbSdkClient,
+};
+use aws_smithy_types::error::display::DisplayErrorContext;
+use futures::future::join_all;
+use log::{debug, error};
+use tabwriter::TabWriter;
+
+use super::app;
+use super::control;
+use super::ddb::key;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Interval to read the probe item in replica regions. It's the resolution of measured lag as well.
+const PROBE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Prefix of string and binary primary keys of probe items, followed by the current time in nanoseconds.
+const PROBE_KEY_PREFIX: &str = "dynein-replica-lag-probe-";
+
+/// How the probe item was replicated to a replica region.
+enum Propagation {
+    /// The probe item appeared in the region after the duration since it was written.
+    Arrived(Duration),
+    TimedOut,
+    Failed(String),
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Executed when you call `$ dy admin replica lag`. Prints how long it took for a probe item to be replicated to each replica region.
+/// Exits with non-zero status if the probe item didn't appear in any of the regions within the timeout.
+pub async fn replica_lag(cx: &app::Context, target_table: Option<String>, timeout_secs: u64) {
+    let new_context = match target_table {
+        Some(t) => Owned(cx.clone().with_table(&t)),
+        None => Borrowed(cx),
+    };
+    let cx = new_context.as_ref();
+    let table_name = cx.effective_table_name();
+    let current_region = cx.effective_region().await.as_ref().to_owned();
+    let desc: TableDescription = control::describe_table_api(cx, table_name.clone()).await;
+    let replica_regions: Vec<String> = desc
+        .replicas
+        .iter()
+        .flatten()
+        .filter_map(|replica| replica.region_name.clone())
+        .filter(|region| region != &current_region)
+        .collect();
+    if replica_regions.is_empty() {
+        error!(
+            "The table '{}' in {} has no replica in other regions. Replication lag can be checked only for global tables.",
+            table_name, current_region
+        );
+        std::process::exit(1);
+    }
+
+    // Primary key values are unique to each run, so that the probe item never overwrites existing items.
+    let probe_id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("current time should be after the epoch")
+        .as_nanos()
+        .to_string();
+    let pk = key::typed_key("HASH", &desc).expect("pk should exist");
+    let probe_key: HashMap<String, AttributeValue> = std::iter::once(&pk)
+        .chain(key::typed_key("RANGE", &desc).as_ref())
+        .map(|k| (k.name.to_owned(), probe_attrval(&k.kind, &probe_id)))
+        .collect();
+    debug!("probe item: {:?}", &probe_key);
+
+    let config = cx.effective_sdk_config().await;
+    let ddb = DynamoDbSdkClient::new(&config);
+    if let Err(e) = ddb
+        .put_item()
+        .table_name(&table_name)
+        .set_item(Some(probe_key.clone()))
+        .condition_expression("attribute_not_exists(#pk)")
+        .expression_attribute_names("#pk", &pk.name)
+        .send()
+        .await
+    {
+        debug!("PutItem API call got an error -- {:?}", e);
+        app::bye_with_sdk_error(1, e);
+    }
+    let written_at = Instant::now();
+    println!(
+        "Wrote a probe item to the table '{}' in {}. Waiting for it to be replicated...",
+        table_name, current_region
+    );
+
+    let timeout = Duration::from_secs(timeout_secs);
+    let propagations: Vec<Propagation> =
+        join_all(replica_regions.iter().map(|region| {
+            wait_for_probe(cx, region, &table_name, &probe_key, written_at, timeout)
+        }))
+        .await;
+
+    if let Err(e) = ddb
+        .delete_item()
+        .table_name(&table_name)
+        .set_key(Some(probe_key))
+        .send()
+        .await
+    {
+        error!(
+            "Failed to delete the probe item from the table '{}': {}",
+            table_name,
+            DisplayErrorContext(&e)
+        );
+    }
+
+    let mut tw = TabWriter::new(vec![]);
+    let mut lines = vec![String::from("region\tlag")];
+    for (region, propagation) in replica_regions.iter().zip(&propagations) {
+        lines.push(match propagation {
+            Propagation::Arrived(lag) => format!("{}\t{:.2} s", region, lag.as_secs_f64()),
+            Propagation::TimedOut => format!("{}\tnot replicated in {} s", region, timeout_secs),
+            Propagation::Failed(e) => format!("{}\tfailed -- {}", region, e),
+        });
+    }
+    tw.write_all((lines.join("\n") + "\n").as_bytes()).unwrap();
+    print!("{}", String::from_utf8(tw.into_inner().unwrap()).unwrap());
+
+    if propagations
+        .iter()
+        .any(|p| !matches!(p, Propagation::Arrived(_)))
+    {
+        std::process::exit(1);
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Reads the probe item in the region until it appears or `timeout` passes since it was written.
+/// Strongly consistent reads are used so that the lag isn't inflated by stale reads within the region.
+async fn wait_for_probe(
+    cx: &app::Context,
+    region: &str,
+    table_name: &str,
+    probe_key: &HashMap<String, AttributeValue>,
+    written_at: Instant,
+    timeout: Duration,
+) -> Propagation {
+    let config = cx.effective_sdk_config_with_region(region).await;
+    let ddb = DynamoDbSdkClient::new(&config);
+    loop {
+        match ddb
+            .get_item()
+            .table_name(table_name)
+            .set_key(Some(probe_key.clone()))
+            .consistent_read(true)
+            .send()
+            .await
+        {
+            Ok(res) if res.item.is_some() => return Propagation::Arrived(written_at.elapsed()),
+            Ok(_) => debug!("the probe item hasn't been replicated to {} yet", region),
+            Err(e) => return Propagation::Failed(DisplayErrorContext(&e).to_string()),
+        }
+        if written_at.elapsed() >= timeout {
+            return Propagation::TimedOut;
+        }
+        tokio::time::sleep(PROBE_POLL_INTERVAL).await;
+    }
+}
+
+fn probe_attrval(kind: &key::KeyType, probe_id: &str) -> AttributeValue {
+    match kind {
+        key::KeyType::S => AttributeValue::S(format!("{}{}", PROBE_KEY_PREFIX, probe_id)),
+        key::KeyType::N => AttributeValue::N(probe_id.to_owned()),
+        key::KeyType::B => {
+            AttributeValue::B(Blob::new(format!("{}{}", PROBE_KEY_PREFIX, probe_id)))
+        }
+    }
+}
@@ -14,10 +14,14 @@
  * limitations under the License.
  */
 
+use crate::app;
 use crate::cmd;
+use crate::help;
+use crate::output;
+use clap::CommandFactory;
 use log::debug;
-use std::io::{stdout, BufRead, IsTerminal, Stdin, StdinLock, Write};
-use std::{error::Error, io};
+use std::io::{stdout, BufRead, BufReader, IsTerminal, Stdin, StdinLock, Write};
+use std::{error::Error, fs::File, io};
 
 /* =================================================
 struct / enum / const
@@ -25,18 +29,62 @@ struct / enum / const
 
 pub enum ShellInput {
     Builtin(BuiltinCommands),
-    Command(cmd::Sub),
+    Command(Box<cmd::Sub>),
     Eof,
     ParseError(Box<dyn Error>),
 }
 
 pub enum BuiltinCommands {
     Exit,
+    Help(&'static help::Topic),
+    /// `help` without a topic, which shows shell builtins as well as dynein commands.
+    Usage,
+    /// `use <TABLE>`, same as `dy use` but it takes precedence over --table given when dynein started.
+    Use(String),
+    /// `region <REGION>`, which switches the region for the rest of the session.
+    Region(String),
+    /// `output <FORMAT>`, which sets the default output format for the rest of the session.
+    Output(String),
+    /// `status`, which shows the current table, region and output format.
+    Status,
 }
 
+/// Shell builtins shown by `help`.
+const BUILTIN_USAGES: [(&str, &str); 7] = [
+    ("use <TABLE>", "Switch the target table, same as `dy use`"),
+    (
+        "region <REGION>",
+        "Switch the region for the rest of the session",
+    ),
+    (
+        "output <FORMAT>",
+        "Set the default output format for the rest of the session",
+    ),
+    ("status", "Show the current table, region and output format"),
+    ("source <FILE>", "Run commands in the file"),
+    (
+        "help [TOPIC]",
+        "Print this message or the help of the given topic",
+    ),
+    ("exit", "Exit the shell"),
+];
+
 pub struct ShellReader<'a> {
     line: String,
     input: StdinLock<'a>,
+    /// Files being read by `source` builtin. The last one is read first, and stdin is read again after all of them reach EOF.
+    sources: Vec<Box<dyn BufRead>>,
+}
+
+/// Why the input continues to the next line.
+#[derive(Debug, PartialEq)]
+enum Continuation {
+    /// A trailing backslash outside quotes, which is removed with the newline like POSIX shells.
+    Backslash,
+    /// A quote which isn't closed yet. The newline is kept in the quoted string.
+    Quote,
+    /// Braces or brackets which aren't closed yet outside quotes, e.g. a JSON literal. The next line is joined without indentation.
+    Bracket,
 }
 
 /* =================================================
@@ -48,33 +96,27 @@ impl<'a> ShellReader<'a> {
         Self {
             line: String::new(),
             input: input.lock(),
+            sources: vec![],
         }
     }
 
     pub fn read_line(&mut self) -> Result<ShellInput, Box<dyn Error>> {
-        if io::stdin().is_terminal() {
-            print!("> ");
-            stdout().flush().expect("failed to flush output");
-        }
-        self.line.clear();
-        match self.input.read_line(&mut self.line) {
-            Ok(0) => {
-                return Ok(ShellInput::Eof);
-            }
-            Ok(_) => (),
-            Err(e) => return Err(Box::new(e)),
-        }
+        loop {
+            let line = match self.read_command()? {
+                Some(line) => line,
+                None => return Ok(ShellInput::Eof),
+            };
+            let line = line.trim();
 
-        let line = self.line.trim_end();
+            debug!("Line read: {:?}", line);
 
-        debug!("Line read: {:?}", line);
+            // Blank lines and comments are allowed in script files.
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
 
-        match line {
-            // build-in shell command(s)
-            "exit" => Ok(ShellInput::Builtin(BuiltinCommands::Exit)),
-            // dy commands
-            line => {
-                // TODO: better handling of whitespaces
+            // `source` is handled by the reader itself, as it switches the input.
+            if line == "source" || line.starts_with("source ") {
                 let args = match parse(line) {
                     Ok(args) => args,
                     Err(e) => {
@@ -82,17 +124,193 @@ impl<'a> ShellReader<'a> {
                         return Ok(ShellInput::ParseError(e));
                     }
                 };
-                debug!("Args: {:?}", args);
-                let child = match cmd::parse_args(args) {
-                    Ok(child) => child,
-                    Err(e) => {
-                        eprintln!("Invalid argument: {}", e);
-                        return Ok(ShellInput::ParseError(e));
-                    }
-                };
-                Ok(ShellInput::Command(child))
+                if let Err(e) = self.source(&args[1..]) {
+                    eprintln!("Error while reading a script file: {}", e);
+                    return Ok(ShellInput::ParseError(e));
+                }
+                continue;
+            }
+
+            return Ok(parse_command(line));
+        }
+    }
+
+    /// Reads lines until a command is complete, joining lines continued by a trailing backslash, an unclosed quote or unclosed braces.
+    /// Returns None at the end of stdin.
+    fn read_command(&mut self) -> io::Result<Option<String>> {
+        let mut command = String::new();
+        let mut continuation: Option<Continuation> = None;
+        loop {
+            let from_stdin = self.sources.is_empty();
+            if from_stdin && io::stdin().is_terminal() {
+                print!("{}", if continuation.is_some() { "... " } else { "> " });
+                stdout().flush().expect("failed to flush output");
+            }
+            self.line.clear();
+            let read = match self.sources.last_mut() {
+                Some(source) => source.read_line(&mut self.line)?,
+                None => self.input.read_line(&mut self.line)?,
+            };
+            if read == 0 {
+                if !from_stdin {
+                    self.sources.pop();
+                }
+                // An incomplete command is returned as is, so that it's reported as a parse error.
+                match continuation {
+                    Some(_) => return Ok(Some(command)),
+                    None if from_stdin => return Ok(None),
+                    None => continue,
+                }
+            }
+
+            let line = self.line.trim_end_matches(&['\n', '\r'][..]);
+            match continuation {
+                Some(Continuation::Bracket) => command.push_str(line.trim_start()),
+                _ => command.push_str(line),
+            }
+            continuation = continuation_of(&command);
+            match continuation {
+                None => return Ok(Some(command)),
+                Some(Continuation::Backslash) => {
+                    command.pop();
+                }
+                Some(Continuation::Quote) => command.push('\n'),
+                Some(Continuation::Bracket) => (),
+            }
+        }
+    }
+
+    /// `source <FILE>` builtin, which reads commands from the file before reading stdin again.
+    fn source(&mut self, args: &[String]) -> Result<(), Box<dyn Error>> {
+        match args {
+            [file] => {
+                let file =
+                    File::open(file).map_err(|e| format!("failed to open '{}': {}", file, e))?;
+                self.sources.push(Box::new(BufReader::new(file)));
+                Ok(())
+            }
+            _ => Err("usage: source <FILE>".into()),
+        }
+    }
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Parses a line into a builtin or a dynein command. Errors are printed to stderr.
+/// Used for lines read by the shell and commands given by --command option.
+pub fn parse_command(line: &str) -> ShellInput {
+    if line == "exit" {
+        return ShellInput::Builtin(BuiltinCommands::Exit);
+    }
+
+    // TODO: better handling of whitespaces
+    let args = match parse(line) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error while parsing input: {}", e);
+            return ShellInput::ParseError(e);
+        }
+    };
+    debug!("Args: {:?}", args);
+    if let Some(topic) = help::find_topic(&args) {
+        return ShellInput::Builtin(BuiltinCommands::Help(topic));
+    }
+    let builtin = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["help"] => Some(BuiltinCommands::Usage),
+        ["use", table] => Some(BuiltinCommands::Use(table.to_owned())),
+        ["region", region] => Some(BuiltinCommands::Region(region.to_owned())),
+        ["output", format] if output::FORMAT_NAMES.contains(&format) => {
+            Some(BuiltinCommands::Output(format.to_owned()))
+        }
+        ["output", ..] => {
+            let e = format!(
+                "usage: output <FORMAT>, where <FORMAT> is one of {}",
+                output::FORMAT_NAMES.join(", ")
+            );
+            eprintln!("Invalid argument: {}", e);
+            return ShellInput::ParseError(e.into());
+        }
+        ["status"] => Some(BuiltinCommands::Status),
+        _ => None,
+    };
+    if let Some(builtin) = builtin {
+        return ShellInput::Builtin(builtin);
+    }
+    match cmd::parse_args(args) {
+        Ok(child) => ShellInput::Command(Box::new(child)),
+        Err(e) => {
+            eprintln!("Invalid argument: {}", e);
+            ShellInput::ParseError(e)
+        }
+    }
+}
+
+/// Prints help of dynein commands followed by shell builtins.
+pub fn print_usage() {
+    print!("{}", cmd::Sub::command().no_binary_name(true).render_help());
+    println!("\nShell builtins:");
+    let width = BUILTIN_USAGES
+        .iter()
+        .map(|(u, _)| u.len())
+        .max()
+        .unwrap_or(0);
+    for (usage, about) in BUILTIN_USAGES {
+        println!("  {:width$}  {}", usage, about, width = width);
+    }
+}
+
+/// Prints the current state of the session, i.e. `status` builtin.
+pub async fn print_status(cx: &app::Context) {
+    println!(
+        "table: {}",
+        cx.table_name_if_specified()
+            .unwrap_or_else(|| String::from("- (not specified)"))
+    );
+    println!("region: {}", cx.effective_region().await.as_ref());
+    println!(
+        "output: {}",
+        cx.shell_output
+            .as_deref()
+            .unwrap_or("- (default of each command)")
+    );
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Returns why the input needs the next line, or None if it's a complete command.
+fn continuation_of(input: &str) -> Option<Continuation> {
+    let mut in_quote = false;
+    let mut depth = 0;
+    // Same as `parse`, a quote starts a string only at the beginning of a token, e.g. "it's" is not quoted.
+    let mut token_start = true;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match (in_quote, c) {
+            (true, '\\') => {
+                chars.next();
             }
+            (true, '\'') => in_quote = false,
+            (true, _) => (),
+            (false, '\'') if token_start => in_quote = true,
+            (false, '{') | (false, '[') => depth += 1,
+            (false, '}') | (false, ']') => depth -= 1,
+            (false, _) => (),
         }
+        token_start = !in_quote && c == ' ';
+    }
+
+    if in_quote {
+        Some(Continuation::Quote)
+    } else if input.ends_with('\\') {
+        Some(Continuation::Backslash)
+    } else if depth > 0 {
+        Some(Continuation::Bracket)
+    } else {
+        None
     }
 }
 
@@ -166,4 +384,23 @@ mod tests {
         let result = parse(input);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_continuation_of() {
+        assert_eq!(continuation_of("put 1 --item '{}'"), None);
+        assert_eq!(continuation_of("get 1 --field tags[0]"), None);
+        assert_eq!(continuation_of("put 1 \\"), Some(Continuation::Backslash));
+        assert_eq!(
+            continuation_of("put 1 --item '{\"a\": 1,"),
+            Some(Continuation::Quote)
+        );
+        assert_eq!(continuation_of(r#"put 1 --item 'it\'s'"#), None);
+        assert_eq!(
+            continuation_of(r#"put 1 --item {"a":[1,"#),
+            Some(Continuation::Bracket)
+        );
+        // Braces in quotes don't matter.
+        assert_eq!(continuation_of("put 1 --item '{'"), None);
+        assert_eq!(continuation_of("put 1 --item it's"), None);
+    }
 }
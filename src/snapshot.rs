@@ -0,0 +1,243 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module records settings of all tables in a region into a YAML file by `dy admin snapshot`,
+// and reports drift between the file and the current tables by `dy admin diff-snapshot`.
+// Settings are fields of `dy desc` except for volatile ones, see `table::table_settings`.
+// Settings are compared field by field after flattening, e.g. "capacity.wcu" or "gsi[0].name".
+use std::{collections::BTreeMap, error::Error, fs};
+
+use ::serde::{Deserialize, Serialize};
+use log::debug;
+use serde_yaml::Value as YamlValue;
+
+use super::app;
+use super::control;
+use super::ddb::table;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Snapshot {
+    region: String,
+    created_at: String,
+    /// Settings of tables keyed by table names.
+    tables: BTreeMap<String, YamlValue>,
+}
+
+/// A difference between the snapshot and the current tables.
+#[derive(Debug, PartialEq)]
+enum Drift {
+    /// A table which didn't exist when the snapshot was taken.
+    Added(String),
+    /// A table in the snapshot which doesn't exist anymore.
+    Removed(String),
+    /// A field whose value has changed. (table, path of the field, value in the snapshot, current value)
+    Changed(String, String, String, String),
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let or_none = |v: &str| {
+            if v.is_empty() {
+                "~".to_owned()
+            } else {
+                v.to_owned()
+            }
+        };
+        match self {
+            Drift::Added(t) => write!(f, "+ {}: created after the snapshot", t),
+            Drift::Removed(t) => write!(f, "- {}: deleted after the snapshot", t),
+            Drift::Changed(t, path, old, new) => {
+                write!(f, "~ {}.{}: {} -> {}", t, path, or_none(old), or_none(new))
+            }
+        }
+    }
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Executed when you call `$ dy admin snapshot --output <file>`. Saves settings of all tables in the region as YAML.
+pub async fn snapshot(cx: &app::Context, output_file: String) -> Result<(), Box<dyn Error>> {
+    let region = cx.effective_region().await.as_ref().to_owned();
+    let snapshot = Snapshot {
+        region,
+        created_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        tables: current_settings(cx).await,
+    };
+    fs::write(&output_file, serde_yaml::to_string(&snapshot)?)?;
+    println!(
+        "Saved settings of {} table(s) in '{}' to '{}'.",
+        snapshot.tables.len(),
+        snapshot.region,
+        output_file
+    );
+    Ok(())
+}
+
+/// Executed when you call `$ dy admin diff-snapshot <file>`. Compares the snapshot with the current tables in the region,
+/// which may differ from the region of the snapshot to compare environments. Exits with 1 if any drift is found.
+pub async fn diff_snapshot(cx: &app::Context, snapshot_file: String) -> Result<(), Box<dyn Error>> {
+    let snapshot: Snapshot = serde_yaml::from_str(&fs::read_to_string(&snapshot_file)?)?;
+    let region = cx.effective_region().await.as_ref().to_owned();
+    println!(
+        "Comparing the snapshot of '{}' taken at {} with tables in '{}'.",
+        snapshot.region, snapshot.created_at, region
+    );
+
+    let drifts = compare(&snapshot.tables, &current_settings(cx).await);
+    if drifts.is_empty() {
+        println!("No drift detected.");
+        return Ok(());
+    }
+    for drift in &drifts {
+        println!("{}", drift);
+    }
+    println!("{} drift(s) detected.", drifts.len());
+    std::process::exit(1);
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+async fn current_settings(cx: &app::Context) -> BTreeMap<String, YamlValue> {
+    control::describe_all_tables_api(cx)
+        .await
+        .iter()
+        .map(|desc| {
+            let name = desc
+                .table_name
+                .clone()
+                .expect("desc should have table name");
+            (name, table::table_settings(desc))
+        })
+        .collect()
+}
+
+/// Returns drifts from the snapshot to the current settings, ordered by table names.
+fn compare(
+    snapshot: &BTreeMap<String, YamlValue>,
+    current: &BTreeMap<String, YamlValue>,
+) -> Vec<Drift> {
+    let mut table_names: Vec<&String> = snapshot.keys().chain(current.keys()).collect();
+    table_names.sort();
+    table_names.dedup();
+
+    let mut drifts: Vec<Drift> = vec![];
+    for name in table_names {
+        let (old, new) = match (snapshot.get(name), current.get(name)) {
+            (Some(old), Some(new)) => (flatten(old), flatten(new)),
+            (None, _) => {
+                drifts.push(Drift::Added(name.to_owned()));
+                continue;
+            }
+            (_, None) => {
+                drifts.push(Drift::Removed(name.to_owned()));
+                continue;
+            }
+        };
+        debug!("comparing settings of '{}': {:?} -> {:?}", name, old, new);
+        // Keep the order of fields in the current settings, followed by fields only in the snapshot.
+        let paths = new.iter().map(|(path, _)| path).chain(
+            old.iter()
+                .map(|(path, _)| path)
+                .filter(|path| !new.iter().any(|(p, _)| &p == path)),
+        );
+        for path in paths {
+            let value_of = |fields: &[(String, String)]| {
+                fields
+                    .iter()
+                    .find(|(p, _)| p == path)
+                    .map(|(_, v)| v.to_owned())
+                    .unwrap_or_default()
+            };
+            let (old_value, new_value) = (value_of(&old), value_of(&new));
+            if old_value != new_value {
+                drifts.push(Drift::Changed(
+                    name.to_owned(),
+                    path.to_owned(),
+                    old_value,
+                    new_value,
+                ));
+            }
+        }
+    }
+    drifts
+}
+
+fn flatten(settings: &YamlValue) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = vec![];
+    table::flatten_yaml_value(String::new(), settings, &mut fields);
+    fields
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(s: &str) -> YamlValue {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_compare() {
+        let snapshot = BTreeMap::from([
+            (
+                String::from("a"),
+                settings("mode: Provisioned\ncapacity:\n  wcu: 5\n  rcu: 5\ngsi: ~"),
+            ),
+            (String::from("b"), settings("mode: OnDemand")),
+        ]);
+        let current = BTreeMap::from([
+            (
+                String::from("a"),
+                settings("mode: Provisioned\ncapacity:\n  wcu: 10\n  rcu: 5\ngsi:\n- name: idx"),
+            ),
+            (String::from("c"), settings("mode: OnDemand")),
+        ]);
+        let drifts = compare(&snapshot, &current);
+        assert_eq!(
+            drifts,
+            vec![
+                Drift::Changed(
+                    String::from("a"),
+                    String::from("capacity.wcu"),
+                    String::from("5"),
+                    String::from("10")
+                ),
+                Drift::Changed(
+                    String::from("a"),
+                    String::from("gsi[0].name"),
+                    String::new(),
+                    String::from("idx")
+                ),
+                Drift::Removed(String::from("b")),
+                Drift::Added(String::from("c")),
+            ]
+        );
+        assert_eq!(drifts[0].to_string(), "~ a.capacity.wcu: 5 -> 10");
+        assert!(compare(&snapshot, &snapshot).is_empty());
+    }
+}
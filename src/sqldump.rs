@@ -0,0 +1,625 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module reads rows from INSERT statements in SQL dumps of MySQL (mysqldump) or PostgreSQL (pg_dump --inserts),
+// for `dy import --format sql`. Each row is converted into a JSON object which maps column names to values.
+//
+// Column names are taken from the column list of INSERT statements, or from CREATE TABLE statements in the dump.
+// Column types in CREATE TABLE statements are used to type values, e.g. '42' in an INT column is a number,
+// 1 in a BOOLEAN (or MySQL TINYINT(1)) column is a boolean, and '{"a": 1}' in a JSON column is a map.
+// Other statements (SET, LOCK TABLES, COPY, ...) are ignored.
+use std::collections::HashMap;
+
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SqlDumpError {
+    #[error("Invalid SQL at line {0}: {1}")]
+    Syntax(usize, String),
+    #[error("Column names of the table '{0}' are unknown. INSERT statements should have column lists (e.g. mysqldump --complete-insert, pg_dump --column-inserts), or the dump should contain CREATE TABLE statements")]
+    UnknownColumns(String),
+    #[error("A row of the table '{0}' has {1} values, but {2} columns are given")]
+    ColumnCount(String, usize, usize),
+}
+
+/// Rows of a table in a SQL dump.
+#[derive(Debug, PartialEq)]
+pub struct SqlTable {
+    pub name: String,
+    pub rows: Vec<JsonValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Keywords and unquoted identifiers.
+    Word(String),
+    /// Identifiers quoted with backticks or double quotes.
+    Quoted(String),
+    Str(String),
+    Number(String),
+    /// `::` of PostgreSQL casts.
+    Cast,
+    Symbol(char),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SqlValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    Str(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Column {
+    name: String,
+    /// Upper-cased type with its arguments, e.g. "VARCHAR(255)". Empty if unknown.
+    kind: String,
+}
+
+/// Tokens of a statement, with line numbers to report errors.
+struct Statement {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+/* =================================================
+Public functions
+================================================= */
+
+/// Parses a SQL dump and returns rows of INSERT statements grouped by tables, in the order of their first appearance.
+pub fn parse_dump(input: &str) -> Result<Vec<SqlTable>, SqlDumpError> {
+    // pg_dump writes backslashes in strings as they are, while mysqldump escapes them.
+    let backslash_escapes = !input
+        .to_lowercase()
+        .contains("standard_conforming_strings = on");
+    let tokens = tokenize(input, backslash_escapes)?;
+
+    let mut columns = HashMap::<String, Vec<Column>>::new();
+    let mut tables = Vec::<SqlTable>::new();
+    for tokens in tokens.split(|(_, t)| *t == Token::Symbol(';')) {
+        let mut stmt = Statement {
+            tokens: tokens.to_vec(),
+            pos: 0,
+        };
+        if stmt.eat_words(&["CREATE", "TABLE"]) {
+            stmt.eat_words(&["IF", "NOT", "EXISTS"]);
+            let name = stmt.name()?;
+            columns.insert(name, stmt.column_definitions()?);
+        } else if stmt.eat_word("INSERT") || stmt.eat_word("REPLACE") {
+            stmt.eat_word("IGNORE");
+            stmt.expect_word("INTO")?;
+            let name = stmt.name()?;
+            let given = if stmt.peek() == Some(&Token::Symbol('(')) {
+                Some(stmt.column_list()?)
+            } else {
+                None
+            };
+            let cols: Vec<Column> = match (given, columns.get(&name)) {
+                // Types are still taken from CREATE TABLE if any.
+                (Some(given), defined) => given
+                    .into_iter()
+                    .map(|name| Column {
+                        kind: defined
+                            .and_then(|d| d.iter().find(|c| c.name == name))
+                            .map(|c| c.kind.to_owned())
+                            .unwrap_or_default(),
+                        name,
+                    })
+                    .collect(),
+                (None, Some(defined)) => defined.to_owned(),
+                (None, None) => return Err(SqlDumpError::UnknownColumns(name)),
+            };
+            stmt.expect_word("VALUES")?;
+
+            let mut rows = vec![];
+            loop {
+                let values = stmt.tuple()?;
+                if values.len() != cols.len() {
+                    return Err(SqlDumpError::ColumnCount(name, values.len(), cols.len()));
+                }
+                let row: serde_json::Map<String, JsonValue> = cols
+                    .iter()
+                    .zip(values)
+                    .map(|(col, value)| (col.name.to_owned(), to_jsonval(value, &col.kind)))
+                    .collect();
+                rows.push(JsonValue::Object(row));
+                if !stmt.eat_symbol(',') {
+                    break;
+                }
+            }
+            // e.g. ON DUPLICATE KEY UPDATE ..., or ON CONFLICT DO NOTHING, are ignored.
+            match tables.iter_mut().find(|t| t.name == name) {
+                Some(table) => table.rows.append(&mut rows),
+                None => tables.push(SqlTable { name, rows }),
+            }
+        }
+    }
+    Ok(tables)
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+impl Statement {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, t)| t.to_owned());
+        self.pos += 1;
+        token
+    }
+
+    fn error(&self, message: &str) -> SqlDumpError {
+        let line = self
+            .tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map_or(0, |(line, _)| *line);
+        SqlDumpError::Syntax(line, message.to_owned())
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(word) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Consumes the words only if all of them follow.
+    fn eat_words(&mut self, words: &[&str]) -> bool {
+        let start = self.pos;
+        if words.iter().all(|w| self.eat_word(w)) {
+            true
+        } else {
+            self.pos = start;
+            false
+        }
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), SqlDumpError> {
+        if self.eat_word(word) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", word)))
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), SqlDumpError> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", symbol)))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, SqlDumpError> {
+        match self.next_token() {
+            Some(Token::Word(w)) | Some(Token::Quoted(w)) => Ok(w),
+            _ => {
+                self.pos -= 1;
+                Err(self.error("expected an identifier"))
+            }
+        }
+    }
+
+    /// Table names may be qualified by a schema (e.g. public.users), which is dropped.
+    fn name(&mut self) -> Result<String, SqlDumpError> {
+        let mut name = self.identifier()?;
+        while self.eat_symbol('.') {
+            name = self.identifier()?;
+        }
+        Ok(name)
+    }
+
+    /// e.g. (`id`, `name`)
+    fn column_list(&mut self) -> Result<Vec<String>, SqlDumpError> {
+        self.expect_symbol('(')?;
+        let mut names = vec![self.identifier()?];
+        while self.eat_symbol(',') {
+            names.push(self.identifier()?);
+        }
+        self.expect_symbol(')')?;
+        Ok(names)
+    }
+
+    /// e.g. (id INT NOT NULL, name VARCHAR(255), PRIMARY KEY (id))
+    fn column_definitions(&mut self) -> Result<Vec<Column>, SqlDumpError> {
+        const CONSTRAINTS: [&str; 9] = [
+            "PRIMARY",
+            "KEY",
+            "UNIQUE",
+            "CONSTRAINT",
+            "INDEX",
+            "FOREIGN",
+            "CHECK",
+            "FULLTEXT",
+            "SPATIAL",
+        ];
+        self.expect_symbol('(')?;
+        let mut columns = vec![];
+        loop {
+            let is_constraint = matches!(
+                self.peek(),
+                Some(Token::Word(w)) if CONSTRAINTS.iter().any(|c| w.eq_ignore_ascii_case(c))
+            );
+            if !is_constraint {
+                let name = self.identifier()?;
+                let mut kind = match self.peek() {
+                    Some(Token::Word(w)) => w.to_uppercase(),
+                    _ => String::new(),
+                };
+                if !kind.is_empty() {
+                    self.pos += 1;
+                    if self.peek() == Some(&Token::Symbol('(')) {
+                        kind.push('(');
+                        kind.push_str(&self.skip_until_closed().join(","));
+                        kind.push(')');
+                    }
+                }
+                columns.push(Column { name, kind });
+            }
+            // Skip the rest of the definition, e.g. NOT NULL DEFAULT '0'.
+            let mut depth = 0;
+            loop {
+                match self.next_token() {
+                    None => return Err(self.error("unterminated CREATE TABLE")),
+                    Some(Token::Symbol('(')) => depth += 1,
+                    Some(Token::Symbol(')')) if depth == 0 => return Ok(columns),
+                    Some(Token::Symbol(')')) => depth -= 1,
+                    Some(Token::Symbol(',')) if depth == 0 => break,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Consumes a parenthesized list of simple tokens, e.g. (10,2), and returns them as strings.
+    fn skip_until_closed(&mut self) -> Vec<String> {
+        let mut args = vec![];
+        self.pos += 1; // '('
+        while let Some(token) = self.next_token() {
+            match token {
+                Token::Symbol(')') => break,
+                Token::Symbol(',') => (),
+                Token::Word(s) | Token::Quoted(s) | Token::Str(s) | Token::Number(s) => {
+                    args.push(s)
+                }
+                _ => (),
+            }
+        }
+        args
+    }
+
+    /// e.g. (1, 'Alice', NULL)
+    fn tuple(&mut self) -> Result<Vec<SqlValue>, SqlDumpError> {
+        self.expect_symbol('(')?;
+        let mut values = vec![self.value()?];
+        while self.eat_symbol(',') {
+            values.push(self.value()?);
+        }
+        self.expect_symbol(')')?;
+        Ok(values)
+    }
+
+    fn value(&mut self) -> Result<SqlValue, SqlDumpError> {
+        let value = match self.next_token() {
+            Some(Token::Number(n)) => SqlValue::Number(n),
+            Some(Token::Str(s)) => SqlValue::Str(s),
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("NULL") => SqlValue::Null,
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("TRUE") => SqlValue::Bool(true),
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("FALSE") => SqlValue::Bool(false),
+            // Typed literals, e.g. DATE '2024-01-01' or E'line\n', are read as strings.
+            Some(Token::Word(_)) if matches!(self.peek(), Some(Token::Str(_))) => {
+                match self.next_token() {
+                    Some(Token::Str(s)) => SqlValue::Str(s),
+                    _ => unreachable!(),
+                }
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("only literals are supported as values"));
+            }
+        };
+        // Casts, e.g. '2024-01-01'::timestamp without time zone, are ignored.
+        if self.peek() == Some(&Token::Cast) {
+            while !matches!(
+                self.peek(),
+                None | Some(Token::Symbol(',')) | Some(Token::Symbol(')'))
+            ) {
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+}
+
+fn tokenize(input: &str, backslash_escapes: bool) -> Result<Vec<(usize, Token)>, SqlDumpError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let start_line = line;
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+            }
+            c if c.is_whitespace() => i += 1,
+            // Comments, including MySQL specific ones like /*!40101 SET ... */
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                i += 2;
+            }
+            '\'' | '`' | '"' => {
+                let mut s = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(SqlDumpError::Syntax(
+                                start_line,
+                                String::from("unterminated quote"),
+                            ))
+                        }
+                        // A doubled quote is an escaped quote.
+                        Some(&q) if q == c && chars.get(i + 1) == Some(&c) => {
+                            s.push(c);
+                            i += 2;
+                        }
+                        Some(&q) if q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some('\\') if c == '\'' && backslash_escapes => {
+                            s.push(match chars.get(i + 1) {
+                                Some('n') => '\n',
+                                Some('r') => '\r',
+                                Some('t') => '\t',
+                                Some('0') => '\0',
+                                Some('Z') => '\x1a',
+                                Some(&e) => e,
+                                None => '\\',
+                            });
+                            i += 2;
+                        }
+                        Some(&ch) => {
+                            if ch == '\n' {
+                                line += 1;
+                            }
+                            s.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push((
+                    start_line,
+                    if c == '\'' {
+                        Token::Str(s)
+                    } else {
+                        Token::Quoted(s)
+                    },
+                ));
+            }
+            ':' if next == Some(':') => {
+                tokens.push((line, Token::Cast));
+                i += 2;
+            }
+            c if c.is_ascii_digit()
+                || ((c == '-' || c == '+' || c == '.')
+                    && next.map_or(false, |n| n.is_ascii_digit())) =>
+            {
+                let start = i;
+                i += 1;
+                while i < chars.len() {
+                    let ch = chars[i];
+                    let exponent_sign =
+                        (ch == '-' || ch == '+') && matches!(chars[i - 1], 'e' | 'E');
+                    if ch.is_ascii_digit() || ch == '.' || ch == 'e' || ch == 'E' || exponent_sign {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((line, Token::Number(chars[start..i].iter().collect())));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+                {
+                    i += 1;
+                }
+                tokens.push((line, Token::Word(chars[start..i].iter().collect())));
+            }
+            c => {
+                tokens.push((line, Token::Symbol(c)));
+                i += 1;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Converts a SQL value into a JSON value, using the type of the column if known.
+fn to_jsonval(value: SqlValue, kind: &str) -> JsonValue {
+    let base = kind.split('(').next().unwrap_or_default();
+    let is_bool = matches!(base, "BOOL" | "BOOLEAN" | "BIT") || kind == "TINYINT(1)";
+    // e.g. INT, BIGINT, INTEGER, INT8, BIGSERIAL, DECIMAL(10,2), DOUBLE PRECISION
+    let is_number = !is_bool
+        && (base.ends_with("INT")
+            || base.ends_with("SERIAL")
+            || matches!(
+                base,
+                "INTEGER"
+                    | "INT2"
+                    | "INT4"
+                    | "INT8"
+                    | "DECIMAL"
+                    | "NUMERIC"
+                    | "FLOAT"
+                    | "FLOAT4"
+                    | "FLOAT8"
+                    | "DOUBLE"
+                    | "REAL"
+                    | "NUMBER"
+            ));
+    // e.g. VARCHAR(255), CHARACTER VARYING, LONGTEXT
+    let is_string =
+        base.contains("CHAR") || base.contains("TEXT") || matches!(base, "CLOB" | "UUID" | "ENUM");
+
+    match value {
+        SqlValue::Null => JsonValue::Null,
+        SqlValue::Bool(b) => JsonValue::Bool(b),
+        SqlValue::Number(n) if is_bool && (n == "0" || n == "1") => JsonValue::Bool(n == "1"),
+        SqlValue::Number(n) if is_string => JsonValue::String(n),
+        SqlValue::Number(n) => number_or_string(n),
+        SqlValue::Str(s) if is_bool => match s.to_lowercase().as_str() {
+            "t" | "true" | "1" | "y" | "yes" => JsonValue::Bool(true),
+            "f" | "false" | "0" | "n" | "no" => JsonValue::Bool(false),
+            _ => JsonValue::String(s),
+        },
+        SqlValue::Str(s) if is_number => number_or_string(s),
+        SqlValue::Str(s) if base == "JSON" || base == "JSONB" => {
+            match serde_json::from_str::<JsonValue>(&s) {
+                Ok(v @ (JsonValue::Object(_) | JsonValue::Array(_))) => v,
+                _ => JsonValue::String(s),
+            }
+        }
+        SqlValue::Str(s) => JsonValue::String(s),
+    }
+}
+
+fn number_or_string(s: String) -> JsonValue {
+    match serde_json::from_str::<JsonValue>(&s) {
+        Ok(n @ JsonValue::Number(_)) => n,
+        _ => JsonValue::String(s),
+    }
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_mysql_dump() {
+        let dump = r#"
+-- MySQL dump 10.13
+/*!40101 SET NAMES utf8mb4 */;
+DROP TABLE IF EXISTS `users`;
+CREATE TABLE `users` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `name` varchar(255) DEFAULT NULL,
+  `active` tinyint(1) NOT NULL DEFAULT '1',
+  `price` decimal(10,2) DEFAULT NULL,
+  `profile` json DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `idx_name` (`name`)
+) ENGINE=InnoDB;
+LOCK TABLES `users` WRITE;
+INSERT INTO `users` VALUES (1,'O\'Reilly',1,'9.50','{\"tags\": [\"a\"]}'),(2,'semi;colon',0,NULL,NULL);
+UNLOCK TABLES;
+"#;
+        assert_eq!(
+            parse_dump(dump),
+            Ok(vec![SqlTable {
+                name: String::from("users"),
+                rows: vec![
+                    json!({"id": 1, "name": "O'Reilly", "active": true, "price": 9.5, "profile": {"tags": ["a"]}}),
+                    json!({"id": 2, "name": "semi;colon", "active": false, "price": null, "profile": null}),
+                ],
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_postgres_dump() {
+        let dump = r#"
+SET standard_conforming_strings = on;
+INSERT INTO public.orders (order_id, "user", note, paid, created_at) VALUES (-1, 'u1', 'C:\tmp ''quoted''', true, '2024-01-01 00:00:00'::timestamp without time zone);
+INSERT INTO public.orders (order_id, "user", note, paid, created_at) VALUES (2.5e3, 'u2', E'x', false, NULL) ON CONFLICT DO NOTHING;
+"#;
+        assert_eq!(
+            parse_dump(dump),
+            Ok(vec![SqlTable {
+                name: String::from("orders"),
+                rows: vec![
+                    json!({"order_id": -1, "user": "u1", "note": "C:\\tmp 'quoted'", "paid": true, "created_at": "2024-01-01 00:00:00"}),
+                    json!({"order_id": 2500.0, "user": "u2", "note": "x", "paid": false, "created_at": null}),
+                ],
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_dump_errors() {
+        assert_eq!(
+            parse_dump("INSERT INTO t VALUES (1);"),
+            Err(SqlDumpError::UnknownColumns(String::from("t")))
+        );
+        assert_eq!(
+            parse_dump("INSERT INTO t (a, b) VALUES (1);"),
+            Err(SqlDumpError::ColumnCount(String::from("t"), 1, 2))
+        );
+        assert_eq!(
+            parse_dump("\nINSERT INTO t (a) VALUES (now());"),
+            Err(SqlDumpError::Syntax(
+                2,
+                String::from("only literals are supported as values")
+            ))
+        );
+    }
+}
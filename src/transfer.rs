@@ -15,29 +15,41 @@
  */
 
 use console::Term;
-use std::collections::VecDeque;
-use std::time::Instant;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::time::{Duration, Instant};
 use std::{
     collections::HashMap,
     fs,
-    io::{Error as IOError, Write},
+    io::{self, BufWriter, Write},
     path::Path,
 };
 
 use dialoguer::Confirm;
 use log::{debug, error};
-use serde_json::{de::StrRead, Deserializer, StreamDeserializer, Value as JsonValue};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 
 use aws_sdk_dynamodb::{
+    error::DisplayErrorContext,
     operation::scan::ScanOutput,
-    types::{AttributeValue, WriteRequest},
+    types::{AttributeValue, PutRequest, WriteRequest},
+    Client as DynamoDbSdkClient,
 };
 use thiserror::Error;
 
 use super::app;
+use super::avro;
 use super::batch;
+use super::control;
 use super::data;
-use super::ddb::table;
+use super::ddb::{key, size, table};
+use super::interrupt;
+use super::metrics;
+use super::pitr;
+use super::sqldump;
+use super::transform::Transform;
+use super::xlsx;
 
 #[derive(Error, Debug)]
 pub enum DyneinExportError {
@@ -45,6 +57,12 @@ pub enum DyneinExportError {
     IO(#[from] std::io::Error),
     #[error("serde error")]
     SerdeError(#[from] serde_json::Error),
+    #[error("{0}")]
+    TransformError(#[from] crate::transform::TransformError),
+    #[error("{0}")]
+    AvroError(#[from] crate::avro::AvroError),
+    #[error("xlsx error: {0}")]
+    XlsxError(#[from] rust_xlsxwriter::XlsxError),
 }
 
 impl From<dialoguer::Error> for DyneinExportError {
@@ -55,12 +73,358 @@ impl From<dialoguer::Error> for DyneinExportError {
     }
 }
 
+/// Options of `$ dy export`.
+pub struct ExportParams {
+    pub attributes: Option<String>,
+    pub keys_only: bool,
+    pub avro_schema: Option<String>,
+    pub format: Option<String>,
+    pub transform: Option<String>,
+    /// File to record progress in, from which the export resumes if it exists.
+    pub checkpoint: Option<String>,
+    /// Whether to export a consistent snapshot by PITR export into s3_bucket, instead of Scan.
+    pub consistent: bool,
+    pub s3_bucket: Option<String>,
+    pub s3_prefix: Option<String>,
+}
+
+/// Contents of the checkpoint file of `dy export --checkpoint`, which is updated after each page of Scan is written.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct ExportCheckpoint {
+    table: String,
+    output_file: String,
+    format: String,
+    /// Attributes of CSV columns, which have to be the same on resume as the header has been written.
+    attributes: Option<String>,
+    /// Number of items written so far.
+    exported: usize,
+    /// Size of the output file when the last page was written. The file is truncated to this size on resume,
+    /// so that items written after the checkpoint, e.g. before a crash, are not duplicated.
+    bytes: u64,
+    /// LastEvaluatedKey of the last completed page in DynamoDB JSON.
+    last_evaluated_key: HashMap<String, JsonValue>,
+}
+
+/// Options of `$ dy import`.
+pub struct ImportParams {
+    pub format: Option<String>,
+    pub enable_set_inference: bool,
+    /// Attributes which are always inferred as sets, given by `--set-attrs`.
+    pub set_attrs: Vec<String>,
+    /// Attributes which are always imported as lists, given by `--list-attrs`.
+    pub list_attrs: Vec<String>,
+    pub binary_attributes: Option<String>,
+    pub on_error: Option<String>,
+    pub dlq_file: Option<String>,
+    pub update_mode: Option<String>,
+    pub transform: Option<String>,
+    /// Write items over 400 KB with warnings, instead of handling them as failed rows.
+    pub force: bool,
+    /// Names of normalizations, i.e. "drop-empty", "null-to-missing" and "empty-ss-to-null".
+    pub normalize: Vec<String>,
+    /// WCU to set on a Provisioned table during the import, which is restored afterwards.
+    pub boost_wcu: Option<i64>,
+}
+
 #[derive(Debug)]
 struct SuggestedAttribute {
     name: String,
     type_str: String,
 }
 
+/// A row in an import file. The original record is kept so that it can be reported when the row is failed to be imported.
+#[derive(Debug)]
+struct ImportRow {
+    /// Line number in the input file (JSONL, CSV) or index of the item in the JSON array, starting from 1.
+    number: usize,
+    record: JsonValue,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ImportErrorPolicy {
+    /// Abort the import at the first failed row.
+    Stop,
+    /// Report failed rows and continue.
+    Skip,
+    /// Write failed rows with error reasons into a dead-letter file (JSON Lines) and continue.
+    Dlq,
+}
+
+/// Normalizations of legacy data applied to items on import, given by `--normalize`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Normalization {
+    /// Remove empty strings/binaries from sets, and attributes which are empty strings or empty sets.
+    drop_empty: bool,
+    /// Remove attributes whose values are NULL.
+    null_to_missing: bool,
+    /// Convert empty sets into NULL.
+    empty_set_to_null: bool,
+}
+
+impl Normalization {
+    fn new(names: &[String]) -> Normalization {
+        let mut n = Normalization::default();
+        for name in names {
+            match name.as_str() {
+                "drop-empty" => n.drop_empty = true,
+                "null-to-missing" => n.null_to_missing = true,
+                "empty-ss-to-null" => n.empty_set_to_null = true,
+                o => panic!("Invalid normalization is given: {}", o),
+            }
+        }
+        n
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Normalization::default()
+    }
+
+    fn apply(&self, item: HashMap<String, AttributeValue>) -> HashMap<String, AttributeValue> {
+        item.into_iter()
+            .filter_map(|(name, attrval)| self.apply_value(attrval).map(|v| (name, v)))
+            .collect()
+    }
+
+    /// Returns the normalized value, or None if the attribute (or the element of a list) should be removed.
+    fn apply_value(&self, attrval: AttributeValue) -> Option<AttributeValue> {
+        let normalized = match attrval {
+            AttributeValue::S(s) if self.drop_empty && s.is_empty() => return None,
+            AttributeValue::Ss(mut ss) => {
+                if self.drop_empty {
+                    ss.retain(|s| !s.is_empty());
+                }
+                self.empty_set(ss.is_empty())
+                    .unwrap_or(AttributeValue::Ss(ss))
+            }
+            AttributeValue::Ns(ns) => self
+                .empty_set(ns.is_empty())
+                .unwrap_or(AttributeValue::Ns(ns)),
+            AttributeValue::Bs(mut bs) => {
+                if self.drop_empty {
+                    bs.retain(|b| !b.as_ref().is_empty());
+                }
+                self.empty_set(bs.is_empty())
+                    .unwrap_or(AttributeValue::Bs(bs))
+            }
+            AttributeValue::L(l) => {
+                AttributeValue::L(l.into_iter().filter_map(|v| self.apply_value(v)).collect())
+            }
+            AttributeValue::M(m) => AttributeValue::M(self.apply(m)),
+            other => other,
+        };
+        match normalized {
+            AttributeValue::Null(_) if self.null_to_missing => None,
+            AttributeValue::Ss(_) | AttributeValue::Ns(_) | AttributeValue::Bs(_)
+                if self.drop_empty && is_empty_set(&normalized) =>
+            {
+                None
+            }
+            v => Some(v),
+        }
+    }
+
+    /// Returns NULL for an empty set if empty-ss-to-null is given.
+    fn empty_set(&self, is_empty: bool) -> Option<AttributeValue> {
+        (is_empty && self.empty_set_to_null).then_some(AttributeValue::Null(true))
+    }
+}
+
+/// How rows are written into the table by write_rows_with_chunked_25.
+struct WriteMode {
+    /// Update items with UpdateItem instead of putting them with BatchWriteItem.
+    merge: bool,
+    /// Paces writes into a Provisioned table, if any.
+    pacer: Option<WritePacer>,
+}
+
+/// Paces writes into a Provisioned table, so that consumed write capacity stays within its WCU on average.
+#[derive(Debug)]
+struct WritePacer {
+    wcu: i64,
+    started: Instant,
+    /// Estimated write capacity units consumed since `started`.
+    consumed: usize,
+}
+
+impl WritePacer {
+    fn new(wcu: i64) -> WritePacer {
+        WritePacer {
+            wcu: wcu.max(1),
+            started: Instant::now(),
+            consumed: 0,
+        }
+    }
+
+    /// Number of items to write at once, so that a single batch doesn't exceed WCU of small tables.
+    fn batch_size(&self) -> usize {
+        (self.wcu as usize).min(25)
+    }
+
+    /// Returns how long to wait before the next batch, after writing items which consumed the given capacity units.
+    fn delay_after(&mut self, consumed: usize, now: Instant) -> Duration {
+        self.consumed += consumed;
+        let expected = Duration::from_secs_f64(self.consumed as f64 / self.wcu as f64);
+        expected.saturating_sub(now.duration_since(self.started))
+    }
+}
+
+/// What happened during an import, shown after all rows are processed.
+#[derive(Debug, Default)]
+struct ImportReport {
+    set_inference: SetInferenceSummary,
+    writes: batch::BatchWriteStats,
+}
+
+/// Counts per top-level attribute of how arrays were imported, to show what set inference did after the import.
+/// Kept in a RefCell as items are converted in a Fn closure.
+#[derive(Debug, Default)]
+struct SetInferenceSummary {
+    /// attribute name => (number of values inferred as sets, number of values kept as lists)
+    counts: RefCell<BTreeMap<String, (usize, usize)>>,
+}
+
+impl SetInferenceSummary {
+    fn record(&self, item: &HashMap<String, AttributeValue>) {
+        let mut counts = self.counts.borrow_mut();
+        for (name, attrval) in item {
+            match attrval {
+                AttributeValue::Ss(_) | AttributeValue::Ns(_) => {
+                    counts.entry(name.to_owned()).or_default().0 += 1
+                }
+                AttributeValue::L(_) => counts.entry(name.to_owned()).or_default().1 += 1,
+                _ => (),
+            }
+        }
+    }
+
+    /// Returns warning lines for attributes which have any values inferred as sets, or nothing if no set was inferred.
+    fn warnings(&self) -> Vec<String> {
+        let counts = self.counts.borrow();
+        if counts.values().all(|(sets, _)| *sets == 0) {
+            return vec![];
+        }
+        let mut lines = vec![String::from(
+            "WARN: Set inference imported arrays as sets (SS/NS) for the following attributes. Use --set-attrs or --list-attrs to pin their types.",
+        )];
+        for (name, (sets, lists)) in counts.iter() {
+            let mixed = if *sets > 0 && *lists > 0 {
+                " (mixed types)"
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "  {}: {} as sets, {} as lists{}",
+                name, sets, lists, mixed
+            ));
+        }
+        lines
+    }
+}
+
+fn is_empty_set(attrval: &AttributeValue) -> bool {
+    match attrval {
+        AttributeValue::Ss(ss) => ss.is_empty(),
+        AttributeValue::Ns(ns) => ns.is_empty(),
+        AttributeValue::Bs(bs) => bs.is_empty(),
+        _ => false,
+    }
+}
+
+struct ImportErrorHandler {
+    policy: ImportErrorPolicy,
+    /// Name of the table which rows currently being handled are imported into.
+    table: String,
+    dlq_file: String,
+    /// Opened on the first failed row, so that a clean import leaves no dead-letter file behind.
+    dlq_writer: Option<BufWriter<fs::File>>,
+    failed_rows: usize,
+}
+
+impl ImportErrorHandler {
+    fn new(on_error: Option<&str>, dlq_file: String) -> ImportErrorHandler {
+        let policy = match on_error {
+            None | Some("stop") => ImportErrorPolicy::Stop,
+            Some("skip") => ImportErrorPolicy::Skip,
+            Some("dlq") => ImportErrorPolicy::Dlq,
+            Some(o) => panic!("Invalid error handling policy is given: {}", o),
+        };
+        ImportErrorHandler {
+            policy,
+            table: String::new(),
+            dlq_file,
+            dlq_writer: None,
+            failed_rows: 0,
+        }
+    }
+
+    /// Returns Err with the given error if the import should be stopped, otherwise records the failed row and returns Ok.
+    fn handle(
+        &mut self,
+        row: &ImportRow,
+        e: batch::DyneinBatchError,
+    ) -> Result<(), batch::DyneinBatchError> {
+        let reason = match &e {
+            batch::DyneinBatchError::BatchWriteError(sdk_error) => {
+                DisplayErrorContext(sdk_error).to_string()
+            }
+            batch::DyneinBatchError::UpdateItemError(sdk_error) => {
+                DisplayErrorContext(sdk_error).to_string()
+            }
+            e => e.to_string(),
+        };
+        self.failed_rows += 1;
+        match self.policy {
+            ImportErrorPolicy::Stop => {
+                error!(
+                    "failed to import row {} into the table '{}': {}",
+                    row.number, self.table, reason
+                );
+                return Err(e);
+            }
+            ImportErrorPolicy::Dlq => {
+                let w = match &mut self.dlq_writer {
+                    Some(w) => w,
+                    None => self
+                        .dlq_writer
+                        .insert(BufWriter::new(fs::File::create(&self.dlq_file)?)),
+                };
+                let line = serde_json::json!({
+                    "table": self.table,
+                    "row": row.number,
+                    "record": row.record,
+                    "error": reason,
+                });
+                writeln!(w, "{}", line)?;
+            }
+            ImportErrorPolicy::Skip => eprintln!(
+                "WARN: skipped row {} for the table '{}': {}",
+                row.number, self.table, reason
+            ),
+        }
+        Ok(())
+    }
+
+    /// Flushes the dead-letter file and shows a summary of failed rows.
+    fn finish(mut self) -> Result<(), io::Error> {
+        if let Some(w) = &mut self.dlq_writer {
+            w.flush()?;
+        }
+        if self.failed_rows > 0 {
+            match self.policy {
+                ImportErrorPolicy::Dlq => eprintln!(
+                    "WARN: {} rows failed to be imported. They have been written to '{}' with error reasons.",
+                    self.failed_rows, self.dlq_file
+                ),
+                _ => eprintln!(
+                    "WARN: {} rows failed to be imported and have been skipped.",
+                    self.failed_rows
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Hash, PartialOrd, PartialEq)]
 struct ProgressState {
     processed_items: usize,
@@ -131,6 +495,7 @@ impl ProgressState {
 }
 
 const MAX_NUMBER_OF_OBSERVES: usize = 10;
+const STDOUT_FILENAME: &str = "-";
 
 /* =================================================
 Public functions
@@ -139,19 +504,37 @@ Public functions
 /// Export items in a DynamoDB table into specified format (JSON, JSONL, JSON compact, or CSV. default is JSON).
 /// As CSV is a kind of "structured" format, you cannot export DynamoDB's NoSQL-ish "unstructured" data into CSV without any instruction from users.
 /// Thus as an "instruction" this function takes --attributes or --keys-only options. If neither of them are given, dynein "guesses" attributes to export from the first item.
+/// With --checkpoint, the progress is recorded after each page, and the export resumes from it when the same command is run again.
+/// With --consistent, items are read from files exported into S3 by PITR export, which are a snapshot at a single point in time.
 pub async fn export(
     cx: &app::Context,
-    given_attributes: Option<String>,
-    keys_only: bool,
     output_file: String,
-    format: Option<String>,
+    params: ExportParams,
 ) -> Result<(), DyneinExportError> {
+    let ExportParams {
+        attributes: given_attributes,
+        keys_only,
+        avro_schema,
+        format,
+        transform,
+        checkpoint: checkpoint_file,
+        consistent,
+        s3_bucket,
+        s3_prefix,
+    } = params;
+    let transform: Option<Transform> = transform.as_deref().map(Transform::parse).transpose()?;
     // TODO: Parallel scan to make it faster https://docs.aws.amazon.com/amazondynamodb/latest/developerguide/Scan.html#Scan.ParallelScan
     // TODO: Show rough progress bar (sum(scan_output.scanned_item)/item_size_of_the_table(6hr)) to track progress.
     let ts: app::TableSchema = app::table_schema(cx).await;
     let format_str: Option<&str> = format.as_deref();
+    let to_stdout = output_file == STDOUT_FILENAME;
+    let resumed: Option<ExportCheckpoint> = match &checkpoint_file {
+        Some(path) => load_export_checkpoint(path, &ts, &output_file, format_str, to_stdout)?,
+        None => None,
+    };
 
-    if ts.mode == table::Mode::Provisioned {
+    // PITR export doesn't consume read capacity of the table.
+    if ts.mode == table::Mode::Provisioned && !consistent {
         let msg = "WARN: For the best performance on import/export, dynein recommends OnDemand mode. However the target table is Provisioned mode now. Proceed anyway?";
         if !Confirm::new().with_prompt(msg).interact()? {
             app::bye(0, "Operation has been cancelled.");
@@ -160,6 +543,8 @@ pub async fn export(
 
     // Basically given_attributes would be used, but on CSV format, it can be overwritten by suggested attributes
     let attributes: Option<String> = match format_str {
+        // Columns have been decided when the export started.
+        Some("csv") if resumed.is_some() => resumed.as_ref().and_then(|c| c.attributes.clone()),
         Some("csv") => {
             if !keys_only && given_attributes.is_none() {
                 overwrite_attributes_or_exit(cx, &ts)
@@ -179,113 +564,237 @@ pub async fn export(
             given_attributes
         }
     };
+    if avro_schema.is_some() && format_str != Some("avro") {
+        app::bye(1, "You can use --avro-schema only with Avro format.")
+    }
+    if consistent && format_str == Some("avro") {
+        app::bye(1, "You can't use --consistent with Avro format.")
+    }
+    // Data files exported into S3, each of which is read as a page instead of Scan.
+    let mut exported_files: Option<pitr::ExportedFiles> = match s3_bucket {
+        Some(bucket) if consistent => {
+            Some(pitr::export_to_s3(cx, &ts, bucket, s3_prefix, to_stdout).await)
+        }
+        _ => None,
+    };
 
-    // Create output file. If target file already exists, ask users if it's ok to delete contents of the file.
-    // Though final output file is created here, it would be blank until scan all items. You can see progress in temporary output file.
-    let f: fs::File = if Path::new(&output_file).exists() {
+    // Open output target. "-" means stdout so that exported items can be piped to other commands.
+    // If target file already exists, ask users if it's ok to delete contents of the file, unless the export resumes.
+    let mut writer: Box<dyn Write> = if to_stdout {
+        Box::new(BufWriter::new(io::stdout()))
+    } else if let Some(checkpoint) = &resumed {
+        let f = fs::OpenOptions::new().append(true).open(&output_file)?;
+        f.set_len(checkpoint.bytes)?;
+        Box::new(BufWriter::new(f))
+    } else if Path::new(&output_file).exists() {
         let msg = "Specified output file already exists. Is it OK to truncate contents?";
         if !Confirm::new().with_prompt(msg).interact()? {
             app::bye(0, "Operation has been cancelled.");
         }
         debug!("truncating existing output file.");
-        let _f = fs::OpenOptions::new().append(true).open(&output_file)?;
-        _f.set_len(0)?;
-        _f
+        let f = fs::OpenOptions::new().write(true).open(&output_file)?;
+        f.set_len(0)?;
+        Box::new(BufWriter::new(f))
     } else {
-        fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&output_file)?
+        Box::new(BufWriter::new(
+            fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&output_file)?,
+        ))
     };
 
-    // These temporary file is used to store data "body" and finally merged into output file.
-    let tmp_output_filename: &str = &format!("{}_tmp", output_file);
-    let mut tmp_output_file: fs::File = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(tmp_output_filename)?;
-    tmp_output_file.set_len(0)?;
-
-    let mut last_evaluated_key: Option<HashMap<String, AttributeValue>> = None;
-    let mut progress_status = ProgressState::new(MAX_NUMBER_OF_OBSERVES);
-    loop {
-        // Invoke Scan API here. At the 1st iteration exclusive_start_key would be "None" as defined above, outside of the loop.
-        // On 2nd iteration and later, passing last_evaluated_key from the previous loop as an exclusive_start_key.
-        let scan_output: ScanOutput = data::scan_api(
+    if format_str == Some("avro") {
+        return export_avro(
             cx,
-            None,  /* index */
-            false, /* consistent_read */
-            &attributes,
-            keys_only,
-            None,               /* limit */
-            last_evaluated_key, /* exclusive_start_key */
+            &ts,
+            &mut writer,
+            transform.as_ref(),
+            avro_schema.as_deref(),
+            to_stdout,
         )
         .await;
+    }
 
-        let items = scan_output
-            .items
-            .expect("Scan result items should be 'Some' even if no item returned.");
+    // Items are written page by page as soon as they're scanned, so that memory usage doesn't depend on the size of the table.
+    // Only the "frame" of each format (e.g. "[" and "]" of JSON array, CSV header) is written before/after the scan loop.
+    let attributes_to_append: Option<Vec<String>> = attrs_to_append(&ts, &attributes);
+    match format_str {
+        _ if resumed.is_some() => (),
+        None | Some("json") | Some("json-compact") => writer.write_all(b"[")?,
+        Some("csv") => writer
+            .write_all(build_csv_header(&ts, attributes_to_append.clone(), keys_only).as_bytes())?,
+        _ => (),
+    }
 
-        progress_status.add_observation(items.len());
-        match format_str {
-            None | Some("json") => {
-                let s = serde_json::to_string_pretty(&data::convert_to_json_vec(&items))?;
-                tmp_output_file.write_all(connectable_json(s, false).as_bytes())?;
-            }
-            Some("jsonl") => {
-                let mut s: String = String::new();
-                for item in &items {
-                    s.push_str(&serde_json::to_string(&data::convert_to_json(item))?);
-                    s.push('\n');
-                }
-                tmp_output_file.write_all(s.as_bytes())?;
-            }
-            Some("json-compact") => {
-                let s = serde_json::to_string(&data::convert_to_json_vec(&items))?;
-                tmp_output_file.write_all(connectable_json(s, true).as_bytes())?;
-            }
-            Some("csv") => {
-                let s = data::convert_items_to_csv_lines(
-                    &items,
-                    &ts,
-                    &attrs_to_append(&ts, &attributes),
+    // A workbook has to be built in memory, and it's written after all items are scanned.
+    let mut sheet: Option<xlsx::ItemSheet> =
+        (format_str == Some("xlsx")).then(|| xlsx::ItemSheet::new(&ts));
+    let mut exported: usize = resumed.as_ref().map_or(0, |c| c.exported);
+    let mut is_first_item = exported == 0;
+    let mut last_evaluated_key: Option<HashMap<String, AttributeValue>> = match &resumed {
+        Some(checkpoint) => {
+            println!(
+                "Resuming the export from the checkpoint file '{}' ({} items exported so far).",
+                checkpoint_file.as_deref().unwrap_or_default(),
+                checkpoint.exported
+            );
+            let key = serde_json::to_value(&checkpoint.last_evaluated_key)?;
+            Some(batch::ddbjson_attributes_to_attrvals(&key))
+        }
+        None => None,
+    };
+    let mut progress_status = ProgressState::new(MAX_NUMBER_OF_OBSERVES);
+    // On Ctrl-C, the loop stops before the next page, and the frame is closed so that the output is still readable.
+    let watch = interrupt::watch();
+    loop {
+        let (items, scanned_last_evaluated_key) = match exported_files.as_mut() {
+            Some(files) => match watch.page(files.next_page()).await.transpose()? {
+                None => break,
+                Some(Some(items)) => (items, None),
+                Some(None) => break,
+            },
+            None => {
+                // Invoke Scan API here. At the 1st iteration exclusive_start_key would be "None" as defined above, outside of the loop.
+                // On 2nd iteration and later, passing last_evaluated_key from the previous loop as an exclusive_start_key.
+                let req = data::scan_api(
+                    cx,
+                    None,  /* index */
+                    false, /* consistent_read */
+                    &attributes,
                     keys_only,
+                    None,                      /* limit */
+                    last_evaluated_key.take(), /* exclusive_start_key */
                 );
-                tmp_output_file.write_all(s.as_bytes())?;
+                let scan_output: ScanOutput = match watch.page(req).await {
+                    None => break,
+                    Some(scan_output) => scan_output,
+                };
+                let items = scan_output
+                    .items
+                    .expect("Scan result items should be 'Some' even if no item returned.");
+                (items, scan_output.last_evaluated_key)
+            }
+        };
+
+        progress_status.add_observation(items.len());
+        metrics::add_items(items.len());
+        for item in items {
+            let item = match &transform {
+                None => item,
+                Some(t) => match t.apply(item)? {
+                    Some(item) => item,
+                    None => continue, // filtered out by select()
+                },
+            };
+            match format_str {
+                None | Some("json") => {
+                    let separator: &[u8] = if is_first_item { b"\n" } else { b",\n" };
+                    writer.write_all(separator)?;
+                    let s =
+                        serde_json::to_string_pretty(&data::convert_to_ordered_json(&item, &ts))?;
+                    writer.write_all(indent_json(&s).as_bytes())?;
+                }
+                Some("json-compact") => {
+                    if !is_first_item {
+                        writer.write_all(b",")?;
+                    }
+                    serde_json::to_writer(&mut writer, &data::convert_to_ordered_json(&item, &ts))?;
+                }
+                Some("jsonl") => {
+                    serde_json::to_writer(&mut writer, &data::convert_to_ordered_json(&item, &ts))?;
+                    writer.write_all(b"\n")?;
+                }
+                Some("csv") => {
+                    let line = data::convert_items_to_csv_lines(
+                        std::slice::from_ref(&item),
+                        &ts,
+                        &attributes_to_append,
+                        keys_only,
+                    );
+                    writer.write_all(line.as_bytes())?;
+                    writer.write_all(b"\n")?;
+                }
+                Some("xlsx") => {
+                    if let Some(sheet) = sheet.as_mut() {
+                        sheet.append(&item)?;
+                    }
+                }
+                Some(o) => panic!("Invalid output format is given: {}", o),
             }
-            Some(o) => panic!("Invalid output format is given: {}", o),
+            is_first_item = false;
+            exported += 1;
+        }
+        writer.flush()?;
+        if !to_stdout {
+            progress_status.show();
         }
-        progress_status.show();
 
         // update last_evaluated_key for the next iteration.
         // If there's no more item in the table, last_evaluated_key would be "None" and it means it's ok to break the loop.
+        // Exported files have no key, and the loop ends when all of them have been read.
         debug!(
             "scan_output.last_evaluated_key is: {:?}",
-            &scan_output.last_evaluated_key
+            &scanned_last_evaluated_key
         );
-        match scan_output.last_evaluated_key {
+        match scanned_last_evaluated_key {
+            None if exported_files.is_some() => (),
             None => break,
-            Some(lek) => last_evaluated_key = Some(lek),
+            Some(lek) => {
+                if let Some(path) = &checkpoint_file {
+                    let checkpoint = ExportCheckpoint {
+                        table: ts.name.to_owned(),
+                        output_file: output_file.to_owned(),
+                        format: String::from(format_str.unwrap_or("json")),
+                        attributes: attributes.clone(),
+                        exported,
+                        bytes: fs::metadata(&output_file)?.len(),
+                        last_evaluated_key: data::strip_item(&lek),
+                    };
+                    save_export_checkpoint(path, &checkpoint)?;
+                }
+                last_evaluated_key = Some(lek);
+            }
+        }
+        if watch.interrupted() {
+            break;
         }
     }
 
     match format_str {
-        None | Some("json") => json_finish(f, tmp_output_filename)?.write_all(b"\n]")?,
-        Some("json-compact") => json_finish(f, tmp_output_filename)?.write_all(b"]")?,
-        Some("jsonl") => jsonl_finish(f, tmp_output_filename)?,
-        Some("csv") => csv_finish(
-            f,
-            tmp_output_filename,
-            &ts,
-            attrs_to_append(&ts, &attributes),
-            keys_only,
-        )?
-        .write_all(b"\n")?,
-        Some(o) => panic!("Invalid output format is given: {}", o),
+        None | Some("json") => writer.write_all(b"\n]")?,
+        Some("json-compact") => writer.write_all(b"]")?,
+        _ => (),
     };
-
-    // As mentioned earlier, deleting temporary file here in all formats.
-    fs::remove_file(tmp_output_filename)?;
+    if let Some(sheet) = sheet {
+        writer.write_all(&sheet.save()?)?;
+    }
+    writer.flush()?;
+    if watch.interrupted() {
+        eprintln!(
+            "Interrupted. {} items have been exported into '{}' before the interrupt.",
+            exported,
+            if to_stdout {
+                "stdout"
+            } else {
+                output_file.as_str()
+            }
+        );
+        // The checkpoint points to the end of the last page, so the closing frame written above is truncated on resume.
+        if let Some(path) = checkpoint_file.as_deref().filter(|p| Path::new(p).exists()) {
+            eprintln!(
+                "Run the same command to resume the export from the checkpoint file '{}'.",
+                path
+            );
+        }
+        metrics::exit(interrupt::EXIT_INTERRUPTED);
+    }
+    if let Some(path) = &checkpoint_file {
+        if Path::new(path).exists() {
+            fs::remove_file(path)?;
+        }
+    }
 
     Ok(())
 }
@@ -293,74 +802,213 @@ pub async fn export(
 pub async fn import(
     cx: &app::Context,
     input_file: String,
-    format: Option<String>,
-    enable_set_inference: bool,
+    params: ImportParams,
 ) -> Result<(), batch::DyneinBatchError> {
-    let format_str: Option<&str> = format.as_deref();
-
-    let ts: app::TableSchema = app::table_schema(cx).await;
-    if ts.mode == table::Mode::Provisioned {
-        let msg = "WARN: For the best performance on import/export, dynein recommends OnDemand mode. However the target table is Provisioned mode now. Proceed anyway?";
-        if !Confirm::new().with_prompt(msg).interact()? {
-            println!("Operation has been cancelled.");
-            return Ok(());
-        }
+    let format_str: Option<&str> = params.format.as_deref();
+    if let Some(attr) = params
+        .set_attrs
+        .iter()
+        .find(|a| params.list_attrs.contains(a))
+    {
+        error!(
+            "The attribute '{}' is given to both --set-attrs and --list-attrs.",
+            attr
+        );
+        metrics::exit(1);
     }
+    let mut report = ImportReport::default();
+    let transform: Option<Transform> = params
+        .transform
+        .as_deref()
+        .map(Transform::parse)
+        .transpose()?;
 
-    let input_string: String = if Path::new(&input_file).exists() {
-        fs::read_to_string(&input_file)?
-    } else {
+    if !Path::new(&input_file).exists() {
         error!("Couldn't find the input file '{}'.", &input_file);
-        std::process::exit(1);
+        metrics::exit(1);
+    }
+    let input_string: String = match format_str {
+        // Avro is a binary format, which is read in its own branch below.
+        Some("avro") => String::new(),
+        _ => fs::read_to_string(&input_file)?,
     };
 
+    let dlq_file: String = params
+        .dlq_file
+        .clone()
+        .unwrap_or_else(|| format!("{}.dlq.jsonl", input_file));
+    let mut error_handler = ImportErrorHandler::new(params.on_error.as_deref(), dlq_file);
+
     match format_str {
         None | Some("json") | Some("json-compact") => {
-            let array_of_json_obj: Vec<JsonValue> = serde_json::from_str(&input_string)?;
-            write_array_of_jsons_with_chunked_25(cx, array_of_json_obj, enable_set_inference)
+            // A JSON file is either an array of items for the target table, or a map from table names to arrays of items
+            // (like "RequestItems" of BatchWriteItem) so that a single file can seed multiple tables.
+            let tables: Vec<(Option<String>, Vec<JsonValue>)> =
+                match serde_json::from_str::<JsonValue>(&input_string)? {
+                    JsonValue::Array(items) => vec![(None, items)],
+                    JsonValue::Object(map) => map
+                        .into_iter()
+                        .map(|(table_name, items)| match items {
+                            JsonValue::Array(items) => Ok((Some(table_name), items)),
+                            _ => Err(batch::DyneinBatchError::InvalidInput(format!(
+                                "items for the table '{}' should be a JSON array",
+                                table_name
+                            ))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                    _ => {
+                        return Err(batch::DyneinBatchError::InvalidInput(String::from(
+                            "JSON should be an array of items, or a map from table names to arrays of items",
+                        )))
+                    }
+                };
+            for (table_name, items) in tables {
+                let table_cx: app::Context = match &table_name {
+                    Some(table_name) => cx.clone().with_table(table_name),
+                    None => cx.clone(),
+                };
+                let rows: Vec<ImportRow> = items
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, record)| ImportRow {
+                        number: i + 1,
+                        record,
+                    })
+                    .collect();
+                import_rows(
+                    &table_cx,
+                    rows,
+                    None, /* csv_headers */
+                    &params,
+                    transform.as_ref(),
+                    &mut report,
+                    &mut error_handler,
+                )
                 .await?;
+            }
         }
-        Some("jsonl") => {
-            // JSON Lines can be deserialized with into_iter() as below.
-            let array_of_json_obj: StreamDeserializer<'_, StrRead<'_>, JsonValue> =
-                Deserializer::from_str(&input_string).into_iter::<JsonValue>();
-            // list_of_jsons contains deserialize results. Filter them and get only valid items.
-            let array_of_valid_json_obj: Vec<JsonValue> =
-                array_of_json_obj.filter_map(Result::ok).collect();
-            write_array_of_jsons_with_chunked_25(cx, array_of_valid_json_obj, enable_set_inference)
+        Some("sql") => {
+            // Rows of a single table in the dump are imported into the target table,
+            // while rows of multiple tables are imported into the tables of the same names.
+            let tables: Vec<sqldump::SqlTable> = sqldump::parse_dump(&input_string)?;
+            let multiple_tables = tables.len() > 1;
+            for table in tables {
+                let table_cx: app::Context = if multiple_tables {
+                    cx.clone().with_table(&table.name)
+                } else {
+                    cx.clone()
+                };
+                let rows: Vec<ImportRow> = table
+                    .rows
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, record)| ImportRow {
+                        number: i + 1,
+                        record,
+                    })
+                    .collect();
+                import_rows(
+                    &table_cx,
+                    rows,
+                    None, /* csv_headers */
+                    &params,
+                    transform.as_ref(),
+                    &mut report,
+                    &mut error_handler,
+                )
                 .await?;
+            }
         }
-        Some("csv") => {
-            let lines: Vec<&str> = input_string
-                .split('\n')
-                .collect::<Vec<&str>>() // split by "\n" and get lines
+        Some("avro") => {
+            let items: Vec<JsonValue> =
+                avro::read_items(io::BufReader::new(fs::File::open(&input_file)?))?;
+            let rows: Vec<ImportRow> = items
                 .into_iter()
-                .filter(|&x| !x.is_empty())
-                .collect::<Vec<&str>>(); // remove blank line (e.g. last line)
-            let headers: Vec<&str> = lines[0].split(',').collect::<Vec<&str>>();
-            let mut matrix: Vec<Vec<&str>> = vec![];
-            // Iterate over lines (from index = 1, as index = 0 is the header line)
-            let mut progress_status = ProgressState::new(MAX_NUMBER_OF_OBSERVES);
-            for (i, line) in lines.iter().enumerate().skip(1) {
-                let cells: Vec<&str> = line.split(',').collect::<Vec<&str>>();
-                debug!("splitted line => {:?}", cells);
-                matrix.push(cells);
-                if i % 25 == 0 {
-                    write_csv_matrix(cx, &matrix, &headers, enable_set_inference).await?;
-                    progress_status.add_observation(25);
-                    progress_status.show();
-                    matrix.clear();
+                .enumerate()
+                .map(|(i, record)| ImportRow {
+                    number: i + 1,
+                    record,
+                })
+                .collect();
+            import_rows(
+                cx,
+                rows,
+                None, /* csv_headers */
+                &params,
+                transform.as_ref(),
+                &mut report,
+                &mut error_handler,
+            )
+            .await?;
+        }
+        Some("jsonl") => {
+            // JSON Lines has one item per line. Malformed lines are handled as failed rows.
+            error_handler.table = cx.effective_table_name();
+            let mut rows: Vec<ImportRow> = vec![];
+            for (i, line) in input_string.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let row = ImportRow {
+                    number: i + 1,
+                    record: JsonValue::String(line.to_owned()),
+                };
+                match serde_json::from_str::<JsonValue>(line) {
+                    Ok(record) => rows.push(ImportRow { record, ..row }),
+                    Err(e) => error_handler.handle(&row, e.into())?,
                 }
             }
-            debug!("rest of matrix => {:?}", matrix);
-            if !matrix.is_empty() {
-                write_csv_matrix(cx, &matrix, &headers, enable_set_inference).await?;
-                progress_status.add_observation(matrix.len());
-                progress_status.show();
-            }
+            import_rows(
+                cx,
+                rows,
+                None, /* csv_headers */
+                &params,
+                transform.as_ref(),
+                &mut report,
+                &mut error_handler,
+            )
+            .await?;
+        }
+        Some("csv") => {
+            // Keep line numbers in the input file to report failed rows, ignoring blank lines (e.g. last line).
+            let mut lines = input_string
+                .split('\n')
+                .enumerate()
+                .filter(|(_, x)| !x.is_empty());
+            let headers: Vec<&str> = match lines.next() {
+                Some((_, header_line)) => header_line.split(',').collect::<Vec<&str>>(),
+                None => return Ok(()),
+            };
+            let rows: Vec<ImportRow> = lines
+                .map(|(i, line)| ImportRow {
+                    number: i + 1,
+                    record: JsonValue::String(line.to_owned()),
+                })
+                .collect();
+            import_rows(
+                cx,
+                rows,
+                Some(&headers),
+                &params,
+                transform.as_ref(),
+                &mut report,
+                &mut error_handler,
+            )
+            .await?;
         }
         Some(o) => panic!("Invalid input format is given: {}", o),
     }
+
+    for line in report.set_inference.warnings() {
+        eprintln!("{}", line);
+    }
+    report.writes.failed = error_handler.failed_rows;
+    error_handler.finish()?;
+    // Terminate the line of the progress first.
+    println!("\n{}", report.writes.summary());
+    if report.writes.failed > 0 {
+        metrics::exit(1);
+    }
     Ok(())
 }
 
@@ -368,17 +1016,176 @@ pub async fn import(
 Private functions
 ================================================= */
 
+/// Loads the checkpoint of `dy export --checkpoint` if the file exists, exiting if it's for another export.
+/// Formats which can't be appended to, and stdout which can't be truncated, are rejected before scanning.
+fn load_export_checkpoint(
+    path: &str,
+    ts: &app::TableSchema,
+    output_file: &str,
+    format: Option<&str>,
+    to_stdout: bool,
+) -> Result<Option<ExportCheckpoint>, DyneinExportError> {
+    if to_stdout {
+        app::bye(1, "--checkpoint is not available when exporting to stdout.");
+    }
+    if matches!(format, Some("avro") | Some("xlsx")) {
+        app::bye(
+            1,
+            "--checkpoint is not available with avro and xlsx formats, as the output file can't be appended to.",
+        );
+    }
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+    let checkpoint: ExportCheckpoint = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let format = format.unwrap_or("json");
+    if (
+        checkpoint.table.as_str(),
+        checkpoint.output_file.as_str(),
+        checkpoint.format.as_str(),
+    ) != (ts.name.as_str(), output_file, format)
+    {
+        error!(
+            "The checkpoint file '{}' is for exporting the table '{}' into '{}' as {}. Remove it or give another file.",
+            path, checkpoint.table, checkpoint.output_file, checkpoint.format
+        );
+        metrics::exit(1);
+    }
+    if !Path::new(output_file).exists() {
+        error!(
+            "The output file '{}' recorded in the checkpoint file '{}' doesn't exist. Remove the checkpoint file to start over.",
+            output_file, path
+        );
+        metrics::exit(1);
+    }
+    Ok(Some(checkpoint))
+}
+
+fn save_export_checkpoint(path: &str, checkpoint: &ExportCheckpoint) -> io::Result<()> {
+    // Write to a temporary file first so that the checkpoint is never left half-written.
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, serde_json::to_string_pretty(checkpoint)?)?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Exports items into an Avro object container file. Unlike other formats, the schema has to be fixed before writing items,
+/// so it's read from the `--avro-schema` file, or derived from the first page of scanned items.
+async fn export_avro(
+    cx: &app::Context,
+    ts: &app::TableSchema,
+    writer: &mut dyn Write,
+    transform: Option<&Transform>,
+    avro_schema: Option<&str>,
+    to_stdout: bool,
+) -> Result<(), DyneinExportError> {
+    let given_schema: Option<avro::ItemSchema> = match avro_schema {
+        Some(path) => Some(avro::ItemSchema::parse(&fs::read_to_string(path)?)?),
+        None => None,
+    };
+
+    let mut progress_status = ProgressState::new(MAX_NUMBER_OF_OBSERVES);
+    // Scan without projection, so that all attributes are exported.
+    let scan = |exclusive_start_key| {
+        data::scan_api(cx, None, false, &None, false, None, exclusive_start_key)
+    };
+    let mut scan_output: ScanOutput = scan(None).await;
+    let page = scan_output
+        .items
+        .take()
+        .expect("Scan result items should be 'Some' even if no item returned.");
+    progress_status.add_observation(page.len());
+    metrics::add_items(page.len());
+    let mut items = transform_items(page, transform)?;
+
+    let schema: avro::ItemSchema = match given_schema {
+        Some(schema) => schema,
+        None => avro::ItemSchema::derive(ts, &items)?,
+    };
+    debug!("Avro schema: {}", schema.json());
+    let mut avro_writer = schema.writer(writer)?;
+    let mut dropped_attributes: BTreeSet<String> = BTreeSet::new();
+    let watch = interrupt::watch();
+    let mut exported: usize = 0;
+    loop {
+        for item in &items {
+            let (record, dropped) = schema.to_record(item)?;
+            avro_writer.append(record).map_err(avro::AvroError::from)?;
+            dropped_attributes.extend(dropped);
+            exported += 1;
+        }
+        avro_writer.flush().map_err(avro::AvroError::from)?;
+        if !to_stdout {
+            progress_status.show();
+        }
+
+        match scan_output.last_evaluated_key.take() {
+            None => break,
+            Some(_) if watch.interrupted() => break,
+            Some(lek) => {
+                scan_output = match watch.page(scan(Some(lek))).await {
+                    None => break,
+                    Some(scan_output) => scan_output,
+                };
+                let page = scan_output
+                    .items
+                    .take()
+                    .expect("Scan result items should be 'Some' even if no item returned.");
+                progress_status.add_observation(page.len());
+                metrics::add_items(page.len());
+                items = transform_items(page, transform)?;
+            }
+        }
+    }
+    avro_writer
+        .into_inner()
+        .map_err(avro::AvroError::from)?
+        .flush()?;
+
+    if !dropped_attributes.is_empty() {
+        eprintln!(
+            "WARN: The following attributes are not in the Avro schema, and were not exported: {}. Use --avro-schema to export them.",
+            dropped_attributes.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+    if watch.interrupted() {
+        eprintln!(
+            "Interrupted. {} items have been exported before the interrupt.",
+            exported
+        );
+        metrics::exit(interrupt::EXIT_INTERRUPTED);
+    }
+    Ok(())
+}
+
+/// Applies the transform to scanned items, leaving out items filtered out by select().
+fn transform_items(
+    items: Vec<HashMap<String, AttributeValue>>,
+    transform: Option<&Transform>,
+) -> Result<Vec<HashMap<String, AttributeValue>>, crate::transform::TransformError> {
+    let transform = match transform {
+        None => return Ok(items),
+        Some(t) => t,
+    };
+    let mut transformed = Vec::with_capacity(items.len());
+    for item in items {
+        if let Some(item) = transform.apply(item)? {
+            transformed.push(item);
+        }
+    }
+    Ok(transformed)
+}
+
 async fn overwrite_attributes_or_exit(
     cx: &app::Context,
     ts: &app::TableSchema,
 ) -> Result<Option<String>, dialoguer::Error> {
-    println!("As neither --keys-only nor --attributes options are given, fetching an item to understand attributes to export...");
+    eprintln!("As neither --keys-only nor --attributes options are given, fetching an item to understand attributes to export...");
     let suggested_attributes: Vec<SuggestedAttribute> = suggest_attributes(cx, ts).await;
 
     // if at least one attribute found
-    println!("Found following attributes in the first item in the table:");
+    eprintln!("Found following attributes in the first item in the table:");
     for preview_attribute in &suggested_attributes {
-        println!(
+        eprintln!(
             "  - {} ({})",
             preview_attribute.name, preview_attribute.type_str
         );
@@ -425,7 +1232,7 @@ async fn suggest_attributes(cx: &app::Context, ts: &app::TableSchema) -> Vec<Sug
         Some(ts.pk.name.to_owned()),
         ts.sk.to_owned().map(|x| x.name),
     ];
-    let non_key_attributes = items[0]
+    let mut non_key_attributes = items[0]
         .iter()
         .filter(
             |(attr, _)| {
@@ -435,11 +1242,14 @@ async fn suggest_attributes(cx: &app::Context, ts: &app::TableSchema) -> Vec<Sug
             }, // ).map(|(k, _)| k).collect::<Vec<&String>>();
         )
         .collect::<Vec<(&String, &AttributeValue)>>();
+    // HashMap doesn't keep the order of attributes. Sort them so that CSV header is stable across exports.
+    non_key_attributes.sort_by(|a, b| a.0.cmp(b.0));
 
     for (attr, attrval) in non_key_attributes {
         attributes_suggestion.push(SuggestedAttribute {
             name: attr.to_owned(),
-            type_str: data::attrval_to_type(attrval).expect("attrval should be mapped"),
+            type_str: data::attrval_to_type(attrval)
+                .unwrap_or_else(|| data::unknown_attrval_placeholder(attrval).to_owned()),
         });
     }
 
@@ -469,53 +1279,10 @@ fn filter_attributes_to_append(ts: &app::TableSchema, ats: &str) -> Vec<String>
     attributes_to_append
 }
 
-/// This function tweaks scan output items.
-/// Each scan iteration, converted string would be a single JSON array: e.g. [ {a:1}, {a:2} ]
-/// When multiple scan is needed (i.e. when last_evaluated_key is Some), connected string would be: e.g. [ {a:1}, {a:2} ][ {a:3}, {a:4} ]
-/// To avoid this invalid JSON from written to output file, this method remove the first "[" and the last "]", then add "," after the last item.
-fn connectable_json(mut s: String, compact: bool) -> String {
-    s.remove(0); // remove first char "["
-    let len = s.len();
-    if compact || len == 1 {
-        // empty array even if not compact is on one line
-        s.truncate(len - 1); // remove last char "]"
-    } else {
-        s.truncate(len - 2); // remove last char "]" and newline
-    }
-    s.push(','); // add last "," so that continue to next iteration
-    s
-}
-
-/// This function takes final output file and temporary filename which has incomplete JSON body, and write final output JSON file.
-/// last "]" is not added in this function, as it depends on json or json-compact.
-fn json_finish(mut f: fs::File, tmp_output_filename: &str) -> Result<fs::File, IOError> {
-    f.write_all(b"[")?; // write initial "[" as the first letter of JSON array.
-    let mut contents = fs::read_to_string(tmp_output_filename)?;
-    let len = contents.len();
-    contents.truncate(len - 1); // remove last ","
-    f.write_all(contents.as_bytes())?;
-    Ok(f)
-}
-
-/// This function takes final output file and temporary filename. For JSON"L", copying whole content is enough.
-fn jsonl_finish(mut f: fs::File, tmp_output_filename: &str) -> Result<(), IOError> {
-    let contents = fs::read_to_string(tmp_output_filename)?;
-    f.write_all(contents.as_bytes())?;
-    Ok(())
-}
-
-/// This function takes final output file and temporary filename, writing CSV header and then copying contents to the output file.
-fn csv_finish(
-    mut f: fs::File,
-    tmp_output_filename: &str,
-    ts: &app::TableSchema,
-    attributes_to_append: Option<Vec<String>>,
-    keys_only: bool,
-) -> Result<fs::File, IOError> {
-    f.write_all(build_csv_header(ts, attributes_to_append, keys_only).as_bytes())?;
-    let contents = fs::read_to_string(tmp_output_filename)?;
-    f.write_all(contents.as_bytes())?;
-    Ok(f)
+/// Indents pretty-printed JSON of an item so that it can be placed as an element of a pretty-printed JSON array.
+/// Newlines in string values are always escaped by serde_json, so it's safe to indent line by line.
+fn indent_json(s: &str) -> String {
+    format!("  {}", s.replace('\n', "\n  "))
 }
 
 /// This function generate CSV headers for the output file to export.
@@ -541,40 +1308,349 @@ fn build_csv_header(
     header_str
 }
 
-async fn write_array_of_jsons_with_chunked_25(
+/// Returns names of attributes whose values should be decoded from base64 strings into binary on import.
+/// Primary key(s) of binary type are always included, in addition to attributes specified by users.
+fn binary_attributes_to_decode(
+    ts: &app::TableSchema,
+    binary_attributes: &Option<String>,
+) -> Vec<String> {
+    let mut results: Vec<String> = vec![];
+    for key in std::iter::once(&ts.pk).chain(ts.sk.iter()) {
+        if key.kind == key::KeyType::B {
+            results.push(key.name.to_owned());
+        }
+    }
+    if let Some(attrs) = binary_attributes {
+        for attr in attrs.split(',').map(|a| a.trim()).filter(|a| !a.is_empty()) {
+            if !results.iter().any(|r| r == attr) {
+                results.push(attr.to_owned());
+            }
+        }
+    }
+    results
+}
+
+/// Imports rows into the target table of the given context.
+/// Rows are JSON objects, or lines of a CSV file (kept as JSON strings) when csv_headers is given.
+/// Items are passed to the transform, if any, and rows filtered out by the transform are skipped.
+async fn import_rows(
     cx: &app::Context,
-    array_of_json_obj: Vec<JsonValue>,
-    enable_set_inference: bool,
+    rows: Vec<ImportRow>,
+    csv_headers: Option<&[&str]>,
+    params: &ImportParams,
+    transform: Option<&Transform>,
+    report: &mut ImportReport,
+    error_handler: &mut ImportErrorHandler,
 ) -> Result<(), batch::DyneinBatchError> {
+    let ts: app::TableSchema = app::table_schema(cx).await;
+    let set_inference_summary = &report.set_inference;
+    let set_inference = batch::SetInference {
+        enabled: params.enable_set_inference,
+        set_attrs: params.set_attrs.clone(),
+        list_attrs: params.list_attrs.clone(),
+    };
+    let merge: bool = params.update_mode.as_deref() == Some("merge");
+    let force = params.force;
+    let normalization = Normalization::new(&params.normalize);
+    let binary_attributes: Vec<String> =
+        binary_attributes_to_decode(&ts, &params.binary_attributes);
+    // Writes into a Provisioned table are paced to its WCU, which is optionally boosted during the import.
+    let mut wcu_to_restore: Option<i64> = None;
+    let pacer: Option<WritePacer> = if ts.mode == table::Mode::Provisioned
+        || params.boost_wcu.is_some()
+    {
+        let desc = control::describe_table_api(cx, ts.name.to_owned()).await;
+        match (control::write_capacity_units(&desc), params.boost_wcu) {
+            (None, boost) => {
+                if boost.is_some() {
+                    println!(
+                        "Ignoring --boost-wcu option as the table '{}' is OnDemand mode.",
+                        ts.name
+                    );
+                }
+                None
+            }
+            (Some(wcu), Some(boost)) if boost > wcu => {
+                println!(
+                    "Increasing WCU of the table '{}' from {} to {} during the import.",
+                    ts.name, wcu, boost
+                );
+                // WCU can't be restored if dynein is killed or exits by an unexpected error in the middle of the import.
+                println!(
+                        "If the import doesn't complete, run `dy admin update table {} --wcu {}` to restore it.",
+                        ts.name, wcu
+                    );
+                if let Err(e) = control::update_write_capacity(cx, &ts.name, boost).await {
+                    error!("Failed to update WCU of the table '{}': {}", ts.name, e);
+                    metrics::exit(1);
+                }
+                wcu_to_restore = Some(wcu);
+                Some(WritePacer::new(boost))
+            }
+            (Some(wcu), boost) => {
+                if boost.is_some() {
+                    println!(
+                        "Ignoring --boost-wcu option as the table '{}' already has {} WCU.",
+                        ts.name, wcu
+                    );
+                }
+                println!(
+                    "The table '{}' is Provisioned mode. Writes are paced to {} WCU.",
+                    ts.name, wcu
+                );
+                Some(WritePacer::new(wcu))
+            }
+        }
+    } else {
+        None
+    };
+
+    error_handler.table = ts.name.to_owned();
+    let to_request = |record: &JsonValue| match csv_headers {
+        None => batch::jsonval_to_put_request(record, &set_inference, &binary_attributes),
+        Some(headers) => {
+            let cells: Vec<&str> = record
+                .as_str()
+                .expect("CSV rows should be kept as String")
+                .split(',')
+                .collect::<Vec<&str>>();
+            debug!("splitted line => {:?}", cells);
+            batch::csv_cells_to_put_request(&cells, headers, &set_inference, &binary_attributes)
+        }
+    };
+    let to_request = |record: &JsonValue| -> Result<Option<WriteRequest>, batch::DyneinBatchError> {
+        let mut request = to_request(record)?;
+        if set_inference.is_active() {
+            if let Some(put_request) = request.put_request.as_ref() {
+                set_inference_summary.record(&put_request.item);
+            }
+        }
+        if !normalization.is_empty() {
+            let item = request
+                .put_request
+                .expect("import should generate PutRequest")
+                .item;
+            request = WriteRequest::builder()
+                .put_request(
+                    PutRequest::builder()
+                        .set_item(Some(normalization.apply(item)))
+                        .build()
+                        .unwrap(),
+                )
+                .build();
+        }
+        let request = match transform {
+            None => Some(request),
+            Some(t) => {
+                let item = request
+                    .put_request
+                    .expect("import should generate PutRequest")
+                    .item;
+                t.apply(item)?.map(|item| {
+                    WriteRequest::builder()
+                        .put_request(PutRequest::builder().set_item(Some(item)).build().unwrap())
+                        .build()
+                })
+            }
+        };
+        // Rows with invalid keys or too large items are handled as failed rows, without sending the whole batch.
+        if let Some(put_request) = request.as_ref().and_then(|r| r.put_request.as_ref()) {
+            key::validate_item_keys(&put_request.item, &ts.pk, ts.sk.as_ref())?;
+            if let Err(e) = size::check_item_size(&put_request.item) {
+                if !force {
+                    return Err(e.into());
+                }
+                eprintln!("WARN: {}", e);
+            }
+        }
+        Ok(request)
+    };
+    let writes = write_rows_with_chunked_25(
+        cx,
+        &ts,
+        &rows,
+        to_request,
+        WriteMode { merge, pacer },
+        &mut report.writes,
+        error_handler,
+    );
+    // Ctrl-C during a boosted import stops the writes instead of terminating dynein, so that WCU is restored before exiting.
+    let watch = wcu_to_restore.map(|_| interrupt::watch());
+    let result = match &watch {
+        Some(watch) => watch.page(writes).await,
+        None => Some(writes.await),
+    };
+
+    // Restore WCU even if the import has been stopped by a failed row or Ctrl-C.
+    if let Some(wcu) = wcu_to_restore {
+        println!("\nRestoring WCU of the table '{}' to {}.", ts.name, wcu);
+        if let Err(e) = control::update_write_capacity(cx, &ts.name, wcu).await {
+            error!(
+                "Failed to restore WCU of the table '{}': {}. Run `dy admin update table {} --wcu {}` to restore it.",
+                ts.name, e, ts.name, wcu
+            );
+        }
+    }
+    match result {
+        Some(result) => result,
+        None => {
+            eprintln!(
+                "Interrupted. The import into the table '{}' has been stopped.",
+                ts.name
+            );
+            metrics::exit(interrupt::EXIT_INTERRUPTED);
+        }
+    }
+}
+
+/// This function converts rows into WriteRequests and writes them with BatchWriteItem, up to 25 items at once.
+/// Rows which fail to be converted or written are passed to the error handler, which decides whether to continue the import.
+async fn write_rows_with_chunked_25<F>(
+    cx: &app::Context,
+    ts: &app::TableSchema,
+    rows: &[ImportRow],
+    to_request: F,
+    mut mode: WriteMode,
+    stats: &mut batch::BatchWriteStats,
+    error_handler: &mut ImportErrorHandler,
+) -> Result<(), batch::DyneinBatchError>
+where
+    F: Fn(&JsonValue) -> Result<Option<WriteRequest>, batch::DyneinBatchError>,
+{
+    let ddb = DynamoDbSdkClient::new(&cx.effective_sdk_config().await);
     let mut progress_status = ProgressState::new(MAX_NUMBER_OF_OBSERVES);
-    for chunk /* Vec<JsonValue> */ in array_of_json_obj.chunks(25) { // As BatchWriteItem request can have up to 25 items.
-        let items = chunk.to_vec();
-        let count = items.len();
-        let request_items: HashMap<String, Vec<WriteRequest>> = batch::convert_jsonvals_to_request_items(cx, items, enable_set_inference).await?;
-        batch::batch_write_until_processed(cx, request_items).await?;
-        progress_status.add_observation(count);
+    // As BatchWriteItem request can have up to 25 items. Smaller batches are used for tables with less WCU.
+    let batch_size: usize = mode.pacer.as_ref().map_or(25, |p| p.batch_size());
+    for chunk /* &[ImportRow] */ in rows.chunks(batch_size) {
+        let (written, retries) = (stats.written, stats.retries);
+        let mut requests: Vec<(&ImportRow, WriteRequest)> = vec![];
+        for row in chunk {
+            match to_request(&row.record) {
+                Ok(Some(request)) => requests.push((row, request)),
+                Ok(None) => debug!("row {} was filtered out by the transform", row.number),
+                Err(e) => error_handler.handle(row, e)?,
+            }
+        }
+        let consumed: usize = requests
+            .iter()
+            .filter_map(|(_, request)| request.put_request.as_ref())
+            .map(|put_request| size::write_capacity_units(&put_request.item))
+            .sum();
+
+        if mode.merge {
+            // There's no batch API for UpdateItem, so items are merged one by one.
+            for (row, request) in requests {
+                match merge_item(&ddb, ts, request).await {
+                    Ok(()) => stats.written += 1,
+                    Err(e) => error_handler.handle(row, e)?,
+                }
+            }
+        } else if !requests.is_empty() {
+            let request_items: HashMap<String, Vec<WriteRequest>> = HashMap::from([(
+                cx.effective_table_name(),
+                requests.iter().map(|(_, request)| request.clone()).collect(),
+            )]);
+            match batch::batch_write_with_stats(cx, request_items, stats).await {
+                Ok(unprocessed_items) => {
+                    let unprocessed: Vec<&WriteRequest> =
+                        unprocessed_items.values().flatten().collect();
+                    for (row, request) in &requests {
+                        if unprocessed.contains(&request) {
+                            error_handler.handle(row, unprocessed_error())?;
+                        }
+                    }
+                }
+                Err(e) => {
+                    if error_handler.policy == ImportErrorPolicy::Stop {
+                        return Err(e.into());
+                    }
+                    // BatchWriteItem rejects the whole request even if only one of items is invalid.
+                    // Retry items one by one to identify which rows are failed.
+                    debug!("BatchWriteItem failed, retrying items one by one: {:?}", e);
+                    for (row, request) in requests {
+                        let request_items =
+                            HashMap::from([(cx.effective_table_name(), vec![request])]);
+                        match batch::batch_write_with_stats(cx, request_items, stats).await {
+                            Ok(unprocessed_items) if unprocessed_items.is_empty() => (),
+                            Ok(_) => error_handler.handle(row, unprocessed_error())?,
+                            Err(e) => error_handler.handle(row, e.into())?,
+                        }
+                    }
+                }
+            }
+        }
+
+        metrics::add_items(stats.written - written);
+        metrics::add_retries(stats.retries - retries);
+        progress_status.add_observation(chunk.len());
         progress_status.show();
+        if let Some(pacer) = mode.pacer.as_mut() {
+            tokio::time::sleep(pacer.delay_after(consumed, Instant::now())).await;
+        }
     }
     Ok(())
 }
 
-/// This function takes "matrix" with "headers", builds a parameter for BatchWriteItem, then write it untill they've been processed all.
-/// The "matrix" is a data built from CSV file and each "cell/column" is an attribute of a item.
-///
-/// e.g.
-///    name, age, fruit ... headers
-/// [[John, 12, Apple],
-///  [Ami, 23, Orange],
-///  [Shu, 42, Banana]] ... matrix
-async fn write_csv_matrix(
-    cx: &app::Context,
-    matrix: &[Vec<&str>],
-    headers: &[&str],
-    enable_set_inference: bool,
+fn unprocessed_error() -> batch::DyneinBatchError {
+    batch::DyneinBatchError::InvalidInput(String::from(
+        "the item remained unprocessed after retries, e.g. due to throttling",
+    ))
+}
+
+/// Writes an item of the given PutRequest with UpdateItem API, setting each non-key attribute with SET action.
+/// Unlike PutItem, attributes of the existing item which don't exist in the given item are kept as they are.
+async fn merge_item(
+    ddb: &DynamoDbSdkClient,
+    ts: &app::TableSchema,
+    request: WriteRequest,
 ) -> Result<(), batch::DyneinBatchError> {
-    let request_items: HashMap<String, Vec<WriteRequest>> =
-        batch::csv_matrix_to_request_items(cx, matrix, headers, enable_set_inference).await?;
-    batch::batch_write_until_processed(cx, request_items).await?;
+    let mut item: HashMap<String, AttributeValue> = request
+        .put_request
+        .expect("rows to import should be converted into PutRequest")
+        .item;
+
+    let mut primary_keys = HashMap::<String, AttributeValue>::new();
+    for key in std::iter::once(&ts.pk).chain(ts.sk.iter()) {
+        match item.remove(&key.name) {
+            Some(attrval) => primary_keys.insert(key.name.to_owned(), attrval),
+            None => {
+                return Err(batch::DyneinBatchError::InvalidInput(format!(
+                    "must provide the primary key attribute {}",
+                    key.name
+                )))
+            }
+        };
+    }
+
+    // Sort attributes so that the generated expression is stable.
+    let mut attributes: Vec<(String, AttributeValue)> = item.into_iter().collect();
+    attributes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut actions: Vec<String> = vec![];
+    let mut names = HashMap::<String, String>::new();
+    let mut vals = HashMap::<String, AttributeValue>::new();
+    for (i, (name, attrval)) in attributes.into_iter().enumerate() {
+        actions.push(format!("#DYNEIN_ATTRNAME{} = :DYNEIN_ATTRVAL{}", i, i));
+        names.insert(format!("#DYNEIN_ATTRNAME{}", i), name);
+        vals.insert(format!(":DYNEIN_ATTRVAL{}", i), attrval);
+    }
+
+    // If the item has only primary key(s), UpdateItem without expression creates the item unless it exists.
+    let update_expression = if actions.is_empty() {
+        None
+    } else {
+        Some(format!("SET {}", actions.join(", ")))
+    };
+
+    let call = ddb
+        .update_item()
+        .table_name(ts.name.to_string())
+        .set_key(Some(primary_keys))
+        .set_update_expression(update_expression)
+        .set_expression_attribute_names((!names.is_empty()).then_some(names))
+        .set_expression_attribute_values((!vals.is_empty()).then_some(vals))
+        .send();
+    metrics::timed("UpdateItem", call).await?;
     Ok(())
 }
 
@@ -584,6 +1660,157 @@ mod tests {
     use std::ops::Add;
     use std::time::Duration;
 
+    #[test]
+    fn test_import_error_handler() {
+        let row = ImportRow {
+            number: 3,
+            record: JsonValue::String("broken".to_owned()),
+        };
+        let err = || batch::DyneinBatchError::InvalidInput("invalid row".to_owned());
+
+        let mut stop = ImportErrorHandler::new(None, String::new());
+        assert!(stop.handle(&row, err()).is_err());
+
+        let mut skip = ImportErrorHandler::new(Some("skip"), String::new());
+        assert!(skip.handle(&row, err()).is_ok());
+        assert_eq!(skip.failed_rows, 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        let dlq_file = dir.path().join("dlq.jsonl").to_str().unwrap().to_owned();
+        let clean = ImportErrorHandler::new(Some("dlq"), dlq_file.clone());
+        clean.finish().unwrap();
+        assert!(!Path::new(&dlq_file).exists());
+
+        let mut dlq = ImportErrorHandler::new(Some("dlq"), dlq_file.clone());
+        dlq.table = "tbl".to_owned();
+        assert!(dlq.handle(&row, err()).is_ok());
+        dlq.finish().unwrap();
+        assert_eq!(
+            fs::read_to_string(dlq_file).unwrap(),
+            "{\"error\":\"invalid row\",\"record\":\"broken\",\"row\":3,\"table\":\"tbl\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_normalization() {
+        let s = |v: &str| AttributeValue::S(v.to_owned());
+        let item = || {
+            HashMap::from([
+                ("pk".to_owned(), s("a")),
+                ("empty".to_owned(), s("")),
+                ("null".to_owned(), AttributeValue::Null(true)),
+                (
+                    "ss".to_owned(),
+                    AttributeValue::Ss(vec!["".to_owned(), "x".to_owned()]),
+                ),
+                (
+                    "empty_ss".to_owned(),
+                    AttributeValue::Ss(vec!["".to_owned()]),
+                ),
+                (
+                    "m".to_owned(),
+                    AttributeValue::M(HashMap::from([(
+                        "n".to_owned(),
+                        AttributeValue::Null(true),
+                    )])),
+                ),
+            ])
+        };
+        let normalize = |names: &[&str]| {
+            let names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+            Normalization::new(&names).apply(item())
+        };
+
+        assert_eq!(normalize(&[]), item());
+        assert_eq!(
+            normalize(&["drop-empty"]),
+            HashMap::from([
+                ("pk".to_owned(), s("a")),
+                ("null".to_owned(), AttributeValue::Null(true)),
+                ("ss".to_owned(), AttributeValue::Ss(vec!["x".to_owned()])),
+                (
+                    "m".to_owned(),
+                    AttributeValue::M(HashMap::from([(
+                        "n".to_owned(),
+                        AttributeValue::Null(true)
+                    )])),
+                ),
+            ])
+        );
+        assert_eq!(
+            normalize(&["drop-empty", "empty-ss-to-null", "null-to-missing"]),
+            HashMap::from([
+                ("pk".to_owned(), s("a")),
+                ("ss".to_owned(), AttributeValue::Ss(vec!["x".to_owned()])),
+                ("m".to_owned(), AttributeValue::M(HashMap::new())),
+            ])
+        );
+        assert_eq!(
+            normalize(&["drop-empty", "empty-ss-to-null"])["empty_ss"],
+            AttributeValue::Null(true)
+        );
+    }
+
+    #[test]
+    fn test_set_inference_summary() {
+        let set_inference = batch::SetInference {
+            enabled: true,
+            set_attrs: vec![],
+            list_attrs: vec!["history".to_owned()],
+        };
+        let summary = SetInferenceSummary::default();
+        for record in [
+            serde_json::json!({"pk": 1, "tags": ["a", "b"], "history": ["x"]}),
+            serde_json::json!({"pk": 2, "tags": ["a", 1], "history": ["y"]}),
+            serde_json::json!({"pk": 3, "tags": [1, 2]}),
+        ] {
+            let request = batch::jsonval_to_put_request(&record, &set_inference, &[]).unwrap();
+            summary.record(&request.put_request.unwrap().item);
+        }
+        assert_eq!(
+            summary.warnings()[1..],
+            [
+                "  history: 0 as sets, 2 as lists",
+                "  tags: 2 as sets, 1 as lists (mixed types)"
+            ]
+        );
+
+        let without_sets = SetInferenceSummary::default();
+        let record = serde_json::json!({"pk": 1, "tags": ["a"]});
+        let set_inference = batch::SetInference::default();
+        let request = batch::jsonval_to_put_request(&record, &set_inference, &[]).unwrap();
+        without_sets.record(&request.put_request.unwrap().item);
+        assert!(without_sets.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_write_pacer() {
+        let mut pacer = WritePacer::new(10);
+        assert_eq!(pacer.batch_size(), 10);
+        let started = pacer.started;
+        // 20 WCU should take 2 seconds with 10 WCU.
+        assert_eq!(
+            pacer.delay_after(20, started + Duration::from_millis(500)),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            pacer.delay_after(5, started + Duration::from_secs(3)),
+            Duration::ZERO
+        );
+        assert_eq!(WritePacer::new(1000).batch_size(), 25);
+        assert_eq!(WritePacer::new(0).batch_size(), 1);
+    }
+
+    #[test]
+    fn test_indent_json() {
+        let item = serde_json::json!({"pk": "a\nb", "m": {"x": 1}});
+        let s = indent_json(&serde_json::to_string_pretty(&item).unwrap());
+        assert_eq!(
+            s,
+            "  {\n    \"m\": {\n      \"x\": 1\n    },\n    \"pk\": \"a\\nb\"\n  }"
+        );
+    }
+
     #[test]
     fn test_progress_status() {
         let mut progress = ProgressState::new(2);
@@ -0,0 +1,435 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module implements --transform option of import/export, a small jq-like language applied to each item.
+//
+// A transform is a pipeline of steps separated by '|':
+//   del(.attr)            ... remove the attribute
+//   .attr = <value>       ... set the attribute to a JSON literal (string, number, boolean or null) or another attribute (.other)
+//   .attr |= tonumber     ... convert a string attribute into a number (tostring does the opposite)
+//   select(<condition>)   ... keep the item only if the condition holds, e.g. select(.age >= 20), select(has(.email))
+// e.g. `.user_name = .name | del(.name) | .age |= tonumber | select(.age >= 20)`
+use std::{cmp::Ordering, collections::HashMap};
+
+use aws_sdk_dynamodb::types::AttributeValue;
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+pub type Item = HashMap<String, AttributeValue>;
+
+#[derive(Error, Debug)]
+pub enum TransformError {
+    #[error("Invalid transform step '{0}': {1}")]
+    Parse(String, String),
+    #[error("Cannot convert the attribute '{0}' {1}")]
+    Conversion(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transform {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Delete(String),
+    Assign(String, Operand),
+    Update(String, Conversion),
+    Select(Condition),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Attribute(String),
+    Literal(AttributeValue),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Conversion {
+    ToNumber,
+    ToString,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Has(String),
+    Compare(String, CompareOperator, Operand),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOperator {
+    Eq,
+    Ne,
+    Le,
+    Ge,
+    Lt,
+    Gt,
+}
+
+// Two-character operators come first so that ">=" is not recognized as ">".
+const COMPARE_OPERATORS: [(&str, CompareOperator); 6] = [
+    ("==", CompareOperator::Eq),
+    ("!=", CompareOperator::Ne),
+    ("<=", CompareOperator::Le),
+    (">=", CompareOperator::Ge),
+    ("<", CompareOperator::Lt),
+    (">", CompareOperator::Gt),
+];
+
+impl Transform {
+    /// Parses a transform script. Steps are separated by '|', except '|' in "|=" and in string literals.
+    pub fn parse(script: &str) -> Result<Transform, TransformError> {
+        let steps = split_outside_quotes(script, |rest| {
+            rest.starts_with('|') && !rest.starts_with("|=")
+        })
+        .into_iter()
+        .map(|s| parse_step(s.trim()))
+        .collect::<Result<Vec<Step>, TransformError>>()?;
+        Ok(Transform { steps })
+    }
+
+    /// Applies all steps to the item. Returns None if the item is filtered out by select().
+    pub fn apply(&self, mut item: Item) -> Result<Option<Item>, TransformError> {
+        for step in &self.steps {
+            match step {
+                Step::Delete(attr) => {
+                    item.remove(attr);
+                }
+                Step::Assign(attr, operand) => {
+                    // Assigning a missing attribute is no-op, so that `.new = .old | del(.old)` renames only existing attributes.
+                    if let Some(v) = operand.evaluate(&item) {
+                        item.insert(attr.to_owned(), v);
+                    }
+                }
+                Step::Update(attr, conversion) => {
+                    if let Some(v) = item.remove(attr) {
+                        item.insert(attr.to_owned(), conversion.convert(attr, v)?);
+                    }
+                }
+                Step::Select(condition) => {
+                    if !condition.evaluate(&item) {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+        Ok(Some(item))
+    }
+}
+
+impl Operand {
+    fn evaluate(&self, item: &Item) -> Option<AttributeValue> {
+        match self {
+            Operand::Attribute(attr) => item.get(attr).cloned(),
+            Operand::Literal(v) => Some(v.clone()),
+        }
+    }
+}
+
+impl Conversion {
+    fn convert(&self, attr: &str, v: AttributeValue) -> Result<AttributeValue, TransformError> {
+        match (self, v) {
+            (Conversion::ToNumber, AttributeValue::S(s)) => {
+                let s = s.trim();
+                if s.parse::<f64>().is_ok() {
+                    Ok(AttributeValue::N(s.to_owned()))
+                } else {
+                    Err(TransformError::Conversion(
+                        attr.to_owned(),
+                        format!("to a number: '{}' is not numeric", s),
+                    ))
+                }
+            }
+            (Conversion::ToNumber, v @ AttributeValue::N(_)) => Ok(v),
+            (Conversion::ToString, AttributeValue::N(n)) => Ok(AttributeValue::S(n)),
+            (Conversion::ToString, AttributeValue::Bool(b)) => Ok(AttributeValue::S(b.to_string())),
+            (Conversion::ToString, v @ AttributeValue::S(_)) => Ok(v),
+            (c, _) => Err(TransformError::Conversion(
+                attr.to_owned(),
+                format!(
+                    "with {}: only strings, numbers and booleans can be converted",
+                    match c {
+                        Conversion::ToNumber => "tonumber",
+                        Conversion::ToString => "tostring",
+                    }
+                ),
+            )),
+        }
+    }
+}
+
+impl Condition {
+    /// A missing attribute is treated as null, as jq does.
+    fn evaluate(&self, item: &Item) -> bool {
+        match self {
+            Condition::Has(attr) => item.contains_key(attr),
+            Condition::Compare(attr, op, rhs) => {
+                let null = AttributeValue::Null(true);
+                let left = item.get(attr).unwrap_or(&null);
+                let right = rhs.evaluate(item).unwrap_or(AttributeValue::Null(true));
+                match compare(left, &right) {
+                    Some(ordering) => match op {
+                        CompareOperator::Eq => ordering == Ordering::Equal,
+                        CompareOperator::Ne => ordering != Ordering::Equal,
+                        CompareOperator::Le => ordering != Ordering::Greater,
+                        CompareOperator::Ge => ordering != Ordering::Less,
+                        CompareOperator::Lt => ordering == Ordering::Less,
+                        CompareOperator::Gt => ordering == Ordering::Greater,
+                    },
+                    // Values of different types are never equal nor ordered.
+                    None => *op == CompareOperator::Ne,
+                }
+            }
+        }
+    }
+}
+
+fn compare(left: &AttributeValue, right: &AttributeValue) -> Option<Ordering> {
+    match (left, right) {
+        (AttributeValue::N(l), AttributeValue::N(r)) => {
+            l.parse::<f64>().ok()?.partial_cmp(&r.parse::<f64>().ok()?)
+        }
+        (AttributeValue::S(l), AttributeValue::S(r)) => Some(l.cmp(r)),
+        (AttributeValue::Bool(l), AttributeValue::Bool(r)) => Some(l.cmp(r)),
+        (AttributeValue::Null(_), AttributeValue::Null(_)) => Some(Ordering::Equal),
+        (l, r) if l == r => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn parse_step(step: &str) -> Result<Step, TransformError> {
+    let invalid = |reason: &str| TransformError::Parse(step.to_owned(), reason.to_owned());
+
+    if let Some(inner) = strip_call(step, "del") {
+        return Ok(Step::Delete(parse_path(inner).map_err(|e| invalid(&e))?));
+    }
+    if let Some(inner) = strip_call(step, "select") {
+        return Ok(Step::Select(
+            parse_condition(inner).map_err(|e| invalid(&e))?,
+        ));
+    }
+    if let Some((lhs, rhs)) = split_once_outside_quotes(step, "|=") {
+        let attr = parse_path(lhs).map_err(|e| invalid(&e))?;
+        let conversion = match rhs.trim() {
+            "tonumber" => Conversion::ToNumber,
+            "tostring" => Conversion::ToString,
+            _ => return Err(invalid("only tonumber and tostring can be used with |=")),
+        };
+        return Ok(Step::Update(attr, conversion));
+    }
+    if let Some((lhs, rhs)) = split_once_outside_quotes(step, "=") {
+        if !rhs.starts_with('=') && !lhs.ends_with(['!', '<', '>']) {
+            let attr = parse_path(lhs).map_err(|e| invalid(&e))?;
+            return Ok(Step::Assign(
+                attr,
+                parse_operand(rhs).map_err(|e| invalid(&e))?,
+            ));
+        }
+    }
+    Err(invalid(
+        "expected del(.attr), .attr = value, .attr |= tonumber/tostring or select(condition)",
+    ))
+}
+
+fn parse_condition(s: &str) -> Result<Condition, String> {
+    if let Some(inner) = strip_call(s.trim(), "has") {
+        return Ok(Condition::Has(parse_path(inner)?));
+    }
+    for (token, op) in COMPARE_OPERATORS {
+        if let Some((lhs, rhs)) = split_once_outside_quotes(s, token) {
+            return Ok(Condition::Compare(
+                parse_path(lhs)?,
+                op,
+                parse_operand(rhs)?,
+            ));
+        }
+    }
+    Err(String::from(
+        "select() takes a comparison such as .attr == value, or has(.attr)",
+    ))
+}
+
+/// Parses ".attr" or ."attr with spaces" into an attribute name.
+fn parse_path(s: &str) -> Result<String, String> {
+    let name = s
+        .trim()
+        .strip_prefix('.')
+        .ok_or_else(|| format!("'{}' is not an attribute path like .attr", s.trim()))?;
+    if name.starts_with('"') {
+        return serde_json::from_str::<String>(name).map_err(|e| e.to_string());
+    }
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "'{}' is not a valid attribute path. Quote the name like .\"my attr\" if needed",
+            s.trim()
+        ));
+    }
+    Ok(name.to_owned())
+}
+
+fn parse_operand(s: &str) -> Result<Operand, String> {
+    let s = s.trim();
+    if s.starts_with('.') {
+        return parse_path(s).map(Operand::Attribute);
+    }
+    match serde_json::from_str::<JsonValue>(s) {
+        Ok(JsonValue::String(v)) => Ok(Operand::Literal(AttributeValue::S(v))),
+        Ok(JsonValue::Number(v)) => Ok(Operand::Literal(AttributeValue::N(v.to_string()))),
+        Ok(JsonValue::Bool(v)) => Ok(Operand::Literal(AttributeValue::Bool(v))),
+        Ok(JsonValue::Null) => Ok(Operand::Literal(AttributeValue::Null(true))),
+        Ok(_) => Err(String::from("only scalar literals are supported as values")),
+        Err(_) => Err(format!(
+            "'{}' is neither an attribute path nor a JSON literal (strings need double quotes)",
+            s
+        )),
+    }
+}
+
+/// Returns the argument of `name(...)` if the given step is a call of the function.
+fn strip_call<'a>(s: &'a str, name: &str) -> Option<&'a str> {
+    s.strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+fn split_once_outside_quotes<'a>(s: &'a str, token: &str) -> Option<(&'a str, &'a str)> {
+    let pos = find_outside_quotes(s, |rest| rest.starts_with(token))?;
+    Some((&s[..pos], &s[pos + token.len()..]))
+}
+
+fn split_outside_quotes(s: &str, is_separator: impl Fn(&str) -> bool) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut rest = s;
+    while let Some(pos) = find_outside_quotes(rest, &is_separator) {
+        parts.push(&rest[..pos]);
+        rest = &rest[pos + 1..];
+    }
+    parts.push(rest);
+    parts
+}
+
+/// Finds the first position where `matches` holds, skipping double-quoted strings.
+fn find_outside_quotes(s: &str, matches: impl Fn(&str) -> bool) -> Option<usize> {
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if in_quotes {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_quotes = false,
+                _ => (),
+            }
+        } else if c == '"' {
+            in_quotes = true;
+        } else if matches(&s[i..]) {
+            return Some(i);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item() -> Item {
+        HashMap::from([
+            ("pk".to_owned(), AttributeValue::S("a".to_owned())),
+            ("name".to_owned(), AttributeValue::S("alice".to_owned())),
+            ("age".to_owned(), AttributeValue::S(" 20 ".to_owned())),
+            ("tmp".to_owned(), AttributeValue::Bool(true)),
+        ])
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Transform::parse(
+                r#".user_name = .name | del(.name) | .age |= tonumber | select(.note == "a|b")"#
+            )
+            .unwrap()
+            .steps,
+            vec![
+                Step::Assign(
+                    "user_name".to_owned(),
+                    Operand::Attribute("name".to_owned())
+                ),
+                Step::Delete("name".to_owned()),
+                Step::Update("age".to_owned(), Conversion::ToNumber),
+                Step::Select(Condition::Compare(
+                    "note".to_owned(),
+                    CompareOperator::Eq,
+                    Operand::Literal(AttributeValue::S("a|b".to_owned()))
+                )),
+            ]
+        );
+        assert_eq!(
+            Transform::parse(r#"select(.age >= 20) | ."my attr" = 1"#)
+                .unwrap()
+                .steps,
+            vec![
+                Step::Select(Condition::Compare(
+                    "age".to_owned(),
+                    CompareOperator::Ge,
+                    Operand::Literal(AttributeValue::N("20".to_owned()))
+                )),
+                Step::Assign(
+                    "my attr".to_owned(),
+                    Operand::Literal(AttributeValue::N("1".to_owned()))
+                ),
+            ]
+        );
+        assert!(Transform::parse("del(name)").is_err());
+        assert!(Transform::parse(".a = b").is_err());
+        assert!(Transform::parse(".a |= ascii_downcase").is_err());
+        assert!(Transform::parse("select(.a)").is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let t = Transform::parse(".user_name = .name | del(.name) | del(.tmp) | .age |= tonumber")
+            .unwrap();
+        assert_eq!(
+            t.apply(item()).unwrap(),
+            Some(HashMap::from([
+                ("pk".to_owned(), AttributeValue::S("a".to_owned())),
+                (
+                    "user_name".to_owned(),
+                    AttributeValue::S("alice".to_owned())
+                ),
+                ("age".to_owned(), AttributeValue::N("20".to_owned())),
+            ]))
+        );
+
+        let t = Transform::parse(".age |= tonumber | select(.age > 20)").unwrap();
+        assert_eq!(t.apply(item()).unwrap(), None);
+        let t = Transform::parse(".age |= tonumber | select(.age <= 20)").unwrap();
+        assert!(t.apply(item()).unwrap().is_some());
+        let t = Transform::parse("select(has(.email))").unwrap();
+        assert_eq!(t.apply(item()).unwrap(), None);
+        let t = Transform::parse("select(.email == null)").unwrap();
+        assert!(t.apply(item()).unwrap().is_some());
+
+        let t = Transform::parse(".name |= tonumber").unwrap();
+        assert!(t.apply(item()).is_err());
+    }
+}
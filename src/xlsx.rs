@@ -0,0 +1,207 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+// This module writes items into an Excel workbook for `dy export --format xlsx`.
+// The workbook has a single worksheet with a header row of attribute names. Primary keys are the first columns,
+// and other attributes follow in the order they're found. As an xlsx file is a zip archive,
+// the worksheet is kept in memory until all items are added.
+use std::collections::HashMap;
+
+use aws_sdk_dynamodb::types::AttributeValue;
+use rust_xlsxwriter::{ColNum, Format, RowNum, Workbook, Worksheet, XlsxError};
+
+use super::app;
+use super::data;
+
+/* =================================================
+struct / enum / const
+================================================= */
+
+/// Excel keeps up to 15 significant digits of numbers.
+const MAX_NUMBER_DIGITS: usize = 15;
+
+/// Maximum length of a worksheet name.
+const MAX_SHEET_NAME_LENGTH: usize = 31;
+
+pub struct ItemSheet {
+    sheet_name: String,
+    worksheet: Worksheet,
+    /// Attribute names in the order of columns.
+    columns: Vec<String>,
+    column_indexes: HashMap<String, ColNum>,
+    /// Number of items written, i.e. rows except the header.
+    rows: RowNum,
+}
+
+impl ItemSheet {
+    /// Creates a worksheet named after the table, with primary keys as the first columns.
+    pub fn new(ts: &app::TableSchema) -> Self {
+        let mut sheet = Self {
+            sheet_name: sheet_name(&ts.name),
+            worksheet: Worksheet::new(),
+            columns: vec![],
+            column_indexes: HashMap::new(),
+            rows: 0,
+        };
+        for key in std::iter::once(&ts.pk).chain(ts.sk.as_ref()) {
+            sheet.column(&key.name);
+        }
+        sheet
+    }
+
+    /// Writes an item as a row. Numbers and booleans are written as typed cells, binaries as base64 encoded strings,
+    /// and sets, lists and maps as JSON. Numbers with more digits than Excel keeps are written as strings not to lose them.
+    pub fn append(&mut self, item: &HashMap<String, AttributeValue>) -> Result<(), XlsxError> {
+        let row: RowNum = self.rows + 1;
+        // Write cells in the order of attribute names, so that new columns are added in the same order for every run.
+        let mut attributes: Vec<(&String, &AttributeValue)> = item.iter().collect();
+        attributes.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in attributes {
+            let col: ColNum = self.column(name);
+            match value {
+                AttributeValue::S(s) => self.worksheet.write_string(row, col, s)?,
+                AttributeValue::N(n) => match excel_number(n) {
+                    Some(f) => self.worksheet.write_number(row, col, f)?,
+                    None => self.worksheet.write_string(row, col, n)?,
+                },
+                AttributeValue::Bool(b) => self.worksheet.write_boolean(row, col, *b)?,
+                AttributeValue::Null(_) => continue,
+                v => match data::attrval_to_jsonval(v) {
+                    serde_json::Value::String(s) => self.worksheet.write_string(row, col, s)?,
+                    json => self.worksheet.write_string(row, col, json.to_string())?,
+                },
+            };
+        }
+        self.rows = row;
+        Ok(())
+    }
+
+    /// Writes the header row, and returns the workbook as bytes of an xlsx file.
+    pub fn save(mut self) -> Result<Vec<u8>, XlsxError> {
+        let header = Format::new().set_bold();
+        for (col, name) in self.columns.iter().enumerate() {
+            self.worksheet
+                .write_string_with_format(0, col as ColNum, name, &header)?;
+        }
+        self.worksheet.set_freeze_panes(1, 0)?;
+        self.worksheet.set_name(&self.sheet_name)?;
+
+        let mut workbook = Workbook::new();
+        workbook.push_worksheet(self.worksheet);
+        workbook.save_to_buffer()
+    }
+
+    /// Returns the column of the attribute, adding a new column if it's the first time to see the attribute.
+    fn column(&mut self, name: &str) -> ColNum {
+        if let Some(col) = self.column_indexes.get(name) {
+            return *col;
+        }
+        let col = self.columns.len() as ColNum;
+        self.columns.push(name.to_owned());
+        self.column_indexes.insert(name.to_owned(), col);
+        col
+    }
+}
+
+/* =================================================
+Private functions
+================================================= */
+
+/// Returns the number as f64 if Excel can keep it as is, otherwise None.
+fn excel_number(n: &str) -> Option<f64> {
+    let mantissa = n.split(['e', 'E']).next().unwrap_or_default();
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+    if digits.trim_start_matches('0').trim_end_matches('0').len() > MAX_NUMBER_DIGITS {
+        return None;
+    }
+    n.parse::<f64>().ok().filter(|f| f.is_finite())
+}
+
+/// Worksheet names can't contain []:*?/\ and are up to 31 characters.
+fn sheet_name(table_name: &str) -> String {
+    table_name
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            c => c,
+        })
+        .take(MAX_SHEET_NAME_LENGTH)
+        .collect()
+}
+
+/* =================================================
+Unit Tests
+================================================= */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ddb::{key, table};
+
+    #[test]
+    fn test_item_sheet_columns() {
+        let ts = app::TableSchema {
+            region: String::from("us-east-1"),
+            name: String::from("table"),
+            pk: key::Key {
+                name: String::from("pk"),
+                kind: key::KeyType::S,
+            },
+            sk: Some(key::Key {
+                name: String::from("sk"),
+                kind: key::KeyType::N,
+            }),
+            indexes: None,
+            mode: table::Mode::OnDemand,
+            ttl: None,
+        };
+        let mut sheet = ItemSheet::new(&ts);
+        sheet
+            .append(&HashMap::from([
+                (String::from("pk"), AttributeValue::S(String::from("a"))),
+                (String::from("sk"), AttributeValue::N(String::from("1"))),
+                (String::from("z"), AttributeValue::Bool(true)),
+                (String::from("b"), AttributeValue::Null(true)),
+            ]))
+            .unwrap();
+        sheet
+            .append(&HashMap::from([
+                (String::from("pk"), AttributeValue::S(String::from("b"))),
+                (String::from("sk"), AttributeValue::N(String::from("2"))),
+                (
+                    String::from("a"),
+                    AttributeValue::L(vec![AttributeValue::N(String::from("1"))]),
+                ),
+            ]))
+            .unwrap();
+        assert_eq!(sheet.columns, vec!["pk", "sk", "b", "z", "a"]);
+        assert_eq!(sheet.rows, 2);
+        // An xlsx file is a zip archive.
+        assert!(sheet.save().unwrap().starts_with(b"PK"));
+    }
+
+    #[test]
+    fn test_excel_number() {
+        assert_eq!(excel_number("42"), Some(42.0));
+        assert_eq!(excel_number("-1.5e3"), Some(-1500.0));
+        assert_eq!(
+            excel_number("123456789012345000"),
+            Some(123456789012345000.0)
+        );
+        assert_eq!(excel_number("1234567890123456"), None);
+        assert_eq!(sheet_name("a/b:c"), "a_b_c");
+    }
+}
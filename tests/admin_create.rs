@@ -52,10 +52,13 @@ schema:
   pk: pk \\(S\\)
   sk: null
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi: null
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 0
 size_bytes: 0
 created_at: .*"
@@ -114,10 +117,13 @@ schema:
   pk: pk \\(S\\)
   sk: null
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi: null
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 0
 size_bytes: 0
 created_at: .*"
@@ -150,10 +156,13 @@ schema:
   pk: pk \\(N\\)
   sk: null
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi: null
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 0
 size_bytes: 0
 created_at: .*"
@@ -186,10 +195,13 @@ schema:
   pk: pk \\(S\\)
   sk: sk \\(S\\)
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi: null
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 0
 size_bytes: 0
 created_at: .*"
@@ -222,10 +234,13 @@ schema:
   pk: pk \\(B\\)
   sk: sk \\(N\\)
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi: null
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 0
 size_bytes: 0
 created_at: .*"
@@ -268,15 +283,19 @@ schema:
   pk: pk \\(S\\)
   sk: null
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi:
 - name: idx
   schema:
     pk: gsi \\(N\\)
     sk: null
   capacity: null
+  projection: ALL
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 2
 size_bytes: \\d+
 created_at: .*"
@@ -338,15 +357,19 @@ schema:
   pk: pk \\(S\\)
   sk: null
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi:
 - name: idx
   schema:
     pk: pk \\(S\\)
     sk: gsi \\(N\\)
   capacity: null
+  projection: ALL
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 2
 size_bytes: \\d+
 created_at: .*"
@@ -372,3 +395,51 @@ created_at: .*"
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_admin_create_index_with_projection() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = setup().await?;
+    let tbl = tm.create_temporary_table("pk", None).await?;
+
+    tm.command()?
+        .args([
+            "-r",
+            "local",
+            "admin",
+            "create",
+            "index",
+            "--table",
+            &tbl,
+            "idx",
+            "--keys",
+            "gsi,N",
+            "--projection",
+            "include:name,age",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("projection: INCLUDE (name, age)"));
+
+    tm.command()?
+        .args([
+            "-r",
+            "local",
+            "admin",
+            "create",
+            "index",
+            "--table",
+            &tbl,
+            "idx2",
+            "--keys",
+            "gsi2,S",
+            "--projection",
+            "keys",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid value for --projection option: 'keys'.",
+        ));
+
+    Ok(())
+}
@@ -76,3 +76,29 @@ async fn test_admin_delete_existent_table() -> Result<(), Box<dyn std::error::Er
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_admin_delete_table_with_wait() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup_with_lock().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+    let mut c = tm.command()?;
+
+    let cmd = c.args([
+        "--region",
+        "local",
+        "admin",
+        "delete",
+        "table",
+        &table_name,
+        "--yes",
+        "--wait",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::ends_with(format!(
+            "Table '{}': DELETED\n",
+            table_name
+        )));
+
+    Ok(())
+}
@@ -35,10 +35,13 @@ schema:
   pk: pk \\(S\\)
   sk: sk \\(N\\)
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi: null
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 0
 size_bytes: 0
 created_at: .*",
@@ -67,10 +70,13 @@ schema:
   pk: pk \\(S\\)
   sk: sk \\(N\\)
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi: null
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 0
 size_bytes: 0
 created_at: .*",
@@ -93,40 +99,48 @@ async fn test_admin_desc_all_tables() -> Result<(), Box<dyn std::error::Error>>
     let cmd = c.args(["--region", "local", "admin", "desc", "--all-tables"]);
     cmd.assert().success().stdout(
         predicate::str::is_match(format!(
-            "name: {}
-region: local
-status: ACTIVE
-schema:
-  pk: pk \\(S\\)
-  sk: null
-mode: OnDemand
-capacity: null
-gsi: null
-lsi: null
-stream: null
-count: 0
-size_bytes: 0
-created_at: .*",
-            table_name1
+            "{}:
+  name: {}
+  region: local
+  status: ACTIVE
+  schema:
+    pk: pk \\(S\\)
+    sk: null
+  mode: OnDemand
+  class: STANDARD
+  capacity: null
+  max_request_units: null
+  gsi: null
+  lsi: null
+  stream: null
+  encryption: AWS owned key
+  count: 0
+  size_bytes: 0
+  created_at: .*",
+            table_name1, table_name1
         ))
         .unwrap()
         .and(
             predicate::str::is_match(format!(
-                "name: {}
-region: local
-status: ACTIVE
-schema:
-  pk: pk \\(S\\)
-  sk: sk \\(N\\)
-mode: OnDemand
-capacity: null
-gsi: null
-lsi: null
-stream: null
-count: 0
-size_bytes: 0
-created_at: .*",
-                table_name2
+                "{}:
+  name: {}
+  region: local
+  status: ACTIVE
+  schema:
+    pk: pk \\(S\\)
+    sk: sk \\(N\\)
+  mode: OnDemand
+  class: STANDARD
+  capacity: null
+  max_request_units: null
+  gsi: null
+  lsi: null
+  stream: null
+  encryption: AWS owned key
+  count: 0
+  size_bytes: 0
+  created_at: .*",
+                table_name2, table_name2
             ))
             .unwrap(),
         ),
@@ -0,0 +1,42 @@
+/*
+* Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+*
+* Licensed under the Apache License, Version 2.0 (the "License").
+* You may not use this file except in compliance with the License.
+* You may obtain a copy of the License at
+*
+*     http://www.apache.org/licenses/LICENSE-2.0
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific language governing permissions and
+* limitations under the License.
+*/
+
+pub mod util;
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+
+#[tokio::test]
+async fn test_admin_tags_set_invalid_tag() -> Result<(), Box<dyn std::error::Error>> {
+    let tm = util::setup().await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "admin",
+        "tags",
+        "set",
+        "--table",
+        "dummy-table-doesnt-exist",
+        "env=prod",
+        "team",
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Invalid tag 'team'. Tags should be given in the form of KEY=VALUE, e.g. env=prod",
+    ));
+
+    Ok(())
+}
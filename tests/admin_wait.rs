@@ -0,0 +1,101 @@
+/*
+* Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+*
+* Licensed under the Apache License, Version 2.0 (the "License").
+* You may not use this file except in compliance with the License.
+* You may obtain a copy of the License at
+*
+*     http://www.apache.org/licenses/LICENSE-2.0
+*
+* Unless required by applicable law or agreed to in writing, software
+* distributed under the License is distributed on an "AS IS" BASIS,
+* WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+* See the License for the specific language governing permissions and
+* limitations under the License.
+*/
+pub mod util;
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+
+#[tokio::test]
+async fn test_admin_wait_until_active() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "admin", "wait", &table_name]);
+    cmd.assert()
+        .success()
+        .stdout(format!("Table '{}': ACTIVE\n", table_name));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_wait_until_deleted() -> Result<(), Box<dyn std::error::Error>> {
+    let tm = util::setup().await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "admin",
+        "wait",
+        "dummy-table-doesnt-exist",
+        "--until",
+        "deleted",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout("Table 'dummy-table-doesnt-exist': DELETED\n");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_wait_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "admin",
+        "wait",
+        &table_name,
+        "--until",
+        "deleted",
+        "--timeout",
+        "1",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Timed out after 1 seconds waiting for the table '{}' to be deleted.",
+            table_name
+        )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_admin_wait_table_arn() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    // The region in the ARN, which is "local" here, is used to find the table.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "admin",
+        "wait",
+        &format!("arn:aws:dynamodb:local:000000000000:table/{}", table_name),
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(format!("Table '{}': ACTIVE\n", table_name));
+
+    Ok(())
+}
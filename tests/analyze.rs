@@ -0,0 +1,117 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+pub mod util;
+
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+
+#[tokio::test]
+async fn test_analyze_expired() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [
+                util::TemporaryItem::new("a", None, Some(r#"{"expires_at": 1700000000}"#)),
+                util::TemporaryItem::new("b", None, Some(r#"{"expires_at": 32503680000}"#)),
+                util::TemporaryItem::new("c", None, Some(r#"{"expires_at": "1700000000"}"#)),
+                util::TemporaryItem::new("d", None, None),
+            ],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "analyze",
+        "expired",
+        "--ttl-attr",
+        "expires_at",
+        "-o",
+        "jsonl",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(
+            predicate::str::contains(r#""pk":"a""#)
+                .and(predicate::str::contains(r#""pk":"b""#).not())
+                .and(predicate::str::contains(r#""pk":"c""#).not())
+                .and(predicate::str::contains(r#""pk":"d""#).not()),
+        )
+        .stderr(
+            predicate::str::contains("TTL is not enabled on the table").and(
+                predicate::str::contains("1 items in the table").and(predicate::str::contains(
+                    "have expired by 'expires_at' but are not deleted yet.",
+                )),
+            ),
+        );
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "analyze",
+        "expired",
+        "--ttl-attr",
+        "expires_at",
+        "--delete",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted 1 expired items"));
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "--table", &table_name, "get", "a"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("No item found."));
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "--table", &table_name, "get", "b"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("32503680000"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_analyze_expired_without_ttl() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "analyze",
+        "expired",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("TTL is not enabled on the table"));
+
+    Ok(())
+}
@@ -31,6 +31,27 @@ async fn test_help() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_help_topics() -> Result<(), Box<dyn std::error::Error>> {
+    let mut dynein_cmd = Command::cargo_bin("dy")?;
+    dynein_cmd
+        .args(["help", "expressions"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("# Expressions"));
+
+    // Subcommands are still handled by clap.
+    let mut dynein_cmd = Command::cargo_bin("dy")?;
+    dynein_cmd
+        .args(["help", "scan"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Retrieve items in a table without any condition",
+        ));
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_custom_config_location() -> Result<(), Box<dyn std::error::Error>> {
     let tm = util::setup().await?;
@@ -54,3 +75,171 @@ async fn test_custom_config_location() -> Result<(), Box<dyn std::error::Error>>
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_record_and_replay() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new("a", None, Some(r#"{"x": 1}"#))],
+        )
+        .await?;
+    let recording_dir = tempfile::tempdir()?;
+
+    let recorded = tm
+        .command()?
+        .env("DYNEIN_RECORD", recording_dir.path())
+        .args(["--region", "local", "scan", "--table", &tbl])
+        .output()?;
+    assert!(recorded.status.success());
+    assert!(std::fs::read_dir(recording_dir.path())?.count() > 0);
+
+    // Replayed responses should be the same as recorded ones, without calling DynamoDB.
+    tm.command()?
+        .env("DYNEIN_REPLAY", recording_dir.path())
+        .args(["--region", "local", "scan", "--table", &tbl])
+        .assert()
+        .success()
+        .stdout(String::from_utf8(recorded.stdout)?);
+
+    // Requests which are not recorded should fail.
+    tm.command()?
+        .env("DYNEIN_REPLAY", recording_dir.path())
+        .args(["--region", "local", "get", "--table", &tbl, "b"])
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_whoami_local() -> Result<(), Box<dyn std::error::Error>> {
+    let tm = util::setup().await?;
+
+    tm.command()?
+        .args(["--region", "local", "--table", "whoami-table", "whoami"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("identity: - (not checked for DynamoDB Local)")
+                .and(predicate::str::contains("region: local"))
+                .and(predicate::str::contains("endpoint: http://localhost:"))
+                .and(predicate::str::contains("table: whoami-table")),
+        );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_completion() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+
+    for shell in ["bash", "zsh", "fish"] {
+        tm.command()?
+            .args(["completion", shell])
+            .assert()
+            .success()
+            .stdout(
+                predicate::str::contains("whoami")
+                    .and(predicate::str::contains("dy completion --cached-tables")),
+            );
+    }
+
+    // Table names cached by `dy use` are candidates of table name completion.
+    let tbl = tm.create_temporary_table("pk", None).await?;
+    tm.command()?
+        .args(["--region", "local", "use", &tbl])
+        .assert()
+        .success();
+    tm.command()?
+        .args(["completion", "--cached-tables"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{}\n", tbl)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_endpoint_url_env_var() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm.create_temporary_table("pk", None).await?;
+
+    // AWS_ENDPOINT_URL_DYNAMODB is honored like other AWS tools.
+    tm.command()?
+        .env("AWS_ENDPOINT_URL_DYNAMODB", "http://localhost:8000")
+        .args(["--region", "us-west-2", "admin", "list"])
+        .assert()
+        .success();
+
+    // `--region local` takes precedence over the endpoint given by environment variables.
+    tm.command()?
+        .env("AWS_ENDPOINT_URL_DYNAMODB", "http://localhost:1")
+        .env("AWS_ENDPOINT_URL", "http://localhost:1")
+        .args(["--region", "local", "scan", "--table", &tbl])
+        .assert()
+        .success();
+
+    tm.command()?
+        .env("AWS_ENDPOINT_URL_DYNAMODB", "http://localhost:1")
+        .args(["--region", "local", "whoami"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("endpoint: http://localhost:8000"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_named_local_endpoint() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm
+        .create_temporary_table_with_items("pk", None, [util::TemporaryItem::new("a", None, None)])
+        .await?;
+
+    let config = r#"
+using_region: ~
+using_table: ~
+using_port: ~
+locals:
+  ddb: http://localhost:8000
+"#;
+
+    // Requests are signed with "local" region by default, so the table created with `--region local` is visible.
+    tm.command_with_envs(config)?
+        .args(["--region", "local:ddb", "get", "--table", &tbl, "a"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""pk": "a""#));
+
+    tm.command_with_envs(config)?
+        .args(["--region", "local:unknown", "scan", "--table", &tbl])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No local endpoint named 'unknown' is defined",
+        ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ca_bundle_not_found() -> Result<(), Box<dyn std::error::Error>> {
+    let tm = util::setup().await?;
+
+    tm.command()?
+        .args([
+            "--ca-bundle",
+            "./tests/resources/no-such-ca-bundle.pem",
+            "--region",
+            "local",
+            "list",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to load the CA bundle"));
+
+    Ok(())
+}
@@ -44,3 +44,26 @@ async fn test_backup() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_backup_invalid_name_template() -> Result<(), Box<dyn std::error::Error>> {
+    let tm = setup().await?;
+
+    tm.command()?
+        .args([
+            "-r",
+            "local",
+            "backup",
+            "--table",
+            "non-existent-table",
+            "--name-template",
+            "{table}-{month}",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown placeholder '{month}' in the backup name template.",
+        ));
+
+    Ok(())
+}
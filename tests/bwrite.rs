@@ -500,6 +500,151 @@ async fn test_batch_write_all_options() -> Result<(), Box<dyn std::error::Error>
     Ok(())
 }
 
+#[tokio::test]
+async fn test_batch_write_table_map() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+    let table_name2 = tm
+        .create_temporary_table_with_items(
+            "pk",
+            Some("sk"),
+            [util::TemporaryItem::new("a", Some("1"), None)],
+        )
+        .await?;
+
+    let table_map = format!(
+        r#"{{"{}": {{"put": [{{"pk": "x"}}, "{{\"pk\": \"y\", \"set\": <<1, 2>>}}"]}}, "{}": {{"del": [{{"pk": "a", "sk": "1"}}]}}}}"#,
+        table_name, table_name2
+    );
+    let mut c = tm.command()?;
+    c.args(["--region", "local", "bwrite", "--table-map", &table_map])
+        .assert()
+        .success();
+
+    let mut c = tm.command()?;
+    let scan_cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "scan",
+        "-o",
+        "json",
+    ]);
+    scan_cmd.assert().success().stdout(
+        predicate::str::is_match(r#""pk": "x""#)?.and(predicate::str::is_match(r#""pk": "y""#)?),
+    );
+    let mut c = tm.command()?;
+    let scan_cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name2,
+        "scan",
+        "-o",
+        "json",
+    ]);
+    scan_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""pk": "a""#).not());
+
+    // Keys are validated with the schema of each table.
+    let table_map = format!(r#"{{"{}": {{"del": [{{"pk": "a"}}]}}}}"#, table_name2);
+    let mut c = tm.command()?;
+    c.args(["--region", "local", "bwrite", "--table-map", &table_map])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "must provide the sort key attribute sk",
+        ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_write_duplicate_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    // Duplicate keys are rejected by default, without writing any item.
+    let mut c = tm.command()?;
+    c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "bwrite",
+        "--put",
+        r#"{"pk": "1", "v": "old"}"#,
+        "--put",
+        r#"{"pk": "1", "v": "new"}"#,
+    ])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+        r#"multiple requests for the same key in the table"#,
+    ));
+
+    let mut c = tm.command()?;
+    c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "bwrite",
+        "--put",
+        r#"{"pk": "1", "v": "old"}"#,
+        "--put",
+        r#"{"pk": "1", "v": "new"}"#,
+        "--on-duplicate",
+        "dedupe",
+    ])
+    .assert()
+    .success()
+    .stderr(predicate::str::contains(
+        r#"keeping the last one: {"pk":"1"}"#,
+    ));
+
+    // The put and the delete of the same key are written in this order.
+    let mut c = tm.command()?;
+    c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "bwrite",
+        "--put",
+        r#"{"pk": "2"}"#,
+        "--del",
+        r#"{"pk": "2"}"#,
+        "--on-duplicate",
+        "split",
+    ])
+    .assert()
+    .success();
+
+    let mut c = tm.command()?;
+    c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "scan",
+        "-o",
+        "json",
+    ])
+    .assert()
+    .success()
+    .stdout(
+        predicate::str::contains(r#""v": "new""#)
+            .and(predicate::str::contains(r#""v": "old""#).not())
+            .and(predicate::str::contains(r#""pk": "2""#).not()),
+    );
+
+    Ok(())
+}
+
 fn create_test_json_file(
     json_path: &str,
     table_names: Vec<&String>,
@@ -17,6 +17,7 @@
 pub mod util;
 
 use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*;
 
 #[tokio::test]
 async fn test_config_dump() -> Result<(), Box<dyn std::error::Error>> {
@@ -71,6 +72,109 @@ retry: null
     Ok(())
 }
 
+#[tokio::test]
+async fn test_config_set_get() -> Result<(), Box<dyn std::error::Error>> {
+    let tm = util::setup().await?;
+
+    tm.command()?
+        .args(["config", "set", "query.strict_mode", "true"])
+        .assert()
+        .success();
+    tm.command()?
+        .args(["config", "set", "retry.default.max_attempts", "5"])
+        .assert()
+        .success();
+    tm.command()?
+        .args(["config", "set", "timeout.connect_timeout", "0.5"])
+        .assert()
+        .success();
+
+    tm.command()?
+        .args(["config", "get", "query.strict_mode"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"query.strict_mode +true +config",
+        )?);
+    tm.command()?
+        .args(["config", "get"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"retry.default.max_attempts +5 +config")?
+                .and(predicate::str::is_match(
+                    r"timeout.connect_timeout +0.5s +config",
+                )?)
+                .and(predicate::str::is_match(r"using_port +8000 +default")?),
+        );
+
+    // Command line options and environment variables take precedence over config.yml.
+    tm.command()?
+        .env("DYNEIN_STRICT", "false")
+        .args(["--max-retries", "0", "config", "get"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"query.strict_mode +false +env")?.and(
+                predicate::str::is_match(r"retry.default.max_attempts +1 +flag")?,
+            ),
+        );
+
+    // Unset an optional value.
+    tm.command()?
+        .args(["config", "set", "timeout.connect_timeout", "null"])
+        .assert()
+        .success();
+    tm.command()?
+        .args(["config", "get", "timeout.connect_timeout"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"timeout.connect_timeout +~ +default",
+        )?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_config_set_invalid() -> Result<(), Box<dyn std::error::Error>> {
+    let tm = util::setup().await?;
+
+    tm.command()?
+        .args(["config", "set", "query.strict_mode", "yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value 'yes' for query.strict_mode: should be true or false",
+        ));
+    tm.command()?
+        .args(["config", "set", "retry.default.max_attempts", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "max_attempts should be greater than zero",
+        ));
+    tm.command()?
+        .args(["config", "set", "query.scan_warning_threshold", "-1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("should be a non-negative integer"));
+    tm.command()?
+        .args(["config", "set", "safety.require_confirmation", "truncate"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Available operations are: delete, import",
+        ));
+    tm.command()?
+        .args(["config", "get", "no_such_key"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown config key 'no_such_key'"));
+
+    Ok(())
+}
+
 async fn assert_config_use_dump(
     tm: &util::TestManager<'_>,
     table_name: String,
@@ -83,8 +187,10 @@ async fn assert_config_use_dump(
 
     let mut c = tm.command()?;
     let cmd = c.args(["config", "dump"]);
-    cmd.assert().success().stdout(format!(
-        "tables:
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(format!(
+            "^tables:
   local/{table_name}:
     region: local
     name: {table_name}
@@ -94,6 +200,8 @@ async fn assert_config_use_dump(
     sk: null
     indexes: null
     mode: OnDemand
+cached_at:
+  local/{table_name}: [0-9]+
 
 using_region: local
 using_table: {table_name}
@@ -102,8 +210,8 @@ query:
   strict_mode: false
 retry: null
 
-"
-    ));
+$"
+        ))?);
 
     Ok(())
 }
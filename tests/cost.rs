@@ -0,0 +1,56 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+pub mod util;
+
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+
+#[tokio::test]
+async fn test_cost_estimate_local() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    // CloudWatch metrics are not available for DynamoDB Local.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "cost",
+        "estimate",
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "DynamoDB Local doesn't publish metrics to CloudWatch",
+    ));
+
+    // --days is limited to the retention period of hourly metrics.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "cost",
+        "estimate",
+        "--days",
+        "64",
+    ]);
+    cmd.assert().failure();
+
+    Ok(())
+}
@@ -18,6 +18,8 @@ pub mod util;
 
 use assert_cmd::prelude::*; // Add methods on commands
 use predicates::prelude::*; // Used for writing assertions
+use std::fs;
+use tempfile::tempdir;
 
 #[tokio::test]
 async fn test_del_non_existent_table() -> Result<(), Box<dyn std::error::Error>> {
@@ -125,3 +127,140 @@ async fn test_del_existent_item_with_sk() -> Result<(), Box<dyn std::error::Erro
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_del_keys_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk,S",
+            Some("sk,N"),
+            [
+                util::TemporaryItem::new("abc", Some("1"), None),
+                util::TemporaryItem::new("abc", Some("2"), None),
+                util::TemporaryItem::new("abc", Some("3"), None),
+                util::TemporaryItem::new("def", Some("1"), None),
+            ],
+        )
+        .await?;
+    let base_dir = tempdir()?;
+    let keys_path = base_dir.path().join("keys.txt");
+    fs::write(&keys_path, "# cleanup list\nabc,1\nabc,3\n\ndef,1\nabc,1\n")?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "del",
+        "--keys-file",
+        keys_path.to_str().unwrap(),
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("items written: 3,"));
+
+    let mut c = tm.command()?;
+    let scan_cmd = c.args(["--region", "local", "--table", &table_name, "scan"]);
+    scan_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("pk   sk  attributes\nabc  2\n"));
+
+    // A line without sort key value is rejected before deleting any item.
+    fs::write(&keys_path, "abc,2\ndef\n")?;
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "del",
+        "--keys-file",
+        keys_path.to_str().unwrap(),
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Line 2 has no sort key value, while the table",
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_del_with_condition() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [
+                util::TemporaryItem::new("a", None, Some(r#"{"status": "active"}"#)),
+                util::TemporaryItem::new("b", None, Some(r#"{"status": "inactive"}"#)),
+            ],
+        )
+        .await?;
+
+    // The item doesn't match the condition, so it's kept.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "del",
+        "a",
+        "--if",
+        r#"status = "inactive""#,
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("doesn't match the condition"));
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "del",
+        "b",
+        "--if",
+        r#"status = "inactive""#,
+    ]);
+    cmd.assert().success();
+
+    let mut c = tm.command()?;
+    let scan_cmd = c.args(["--region", "local", "--table", &table_name, "scan"]);
+    scan_cmd.assert().success().stdout(
+        predicate::str::contains(r#"{"status":"active"}"#)
+            .and(predicate::str::contains("inactive").not()),
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_del_require_confirmation() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items("pk", None, [util::TemporaryItem::new("a", None, None)])
+        .await?;
+
+    let config = "safety:
+  require_confirmation: [delete]
+";
+    // Non-local regions require --yes in non-interactive mode. The command fails before calling any API.
+    let mut c = tm.command_with_envs(config)?;
+    let cmd = c.args(["--region", "us-east-1", "--table", "dummy", "del", "a"]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "so this operation requires --yes option in non-interactive mode",
+    ));
+
+    // DynamoDB Local doesn't require confirmation.
+    let mut c = tm.command_with_envs(config)?;
+    let cmd = c.args(["--region", "local", "--table", &table_name, "del", "a"]);
+    cmd.assert().success();
+
+    Ok(())
+}
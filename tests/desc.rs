@@ -56,10 +56,13 @@ schema:
   pk: pk \\(S\\)
   sk: sk \\(N\\)
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi: null
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 0
 size_bytes: 0
 created_at: .*",
@@ -88,10 +91,13 @@ schema:
   pk: pk \\(S\\)
   sk: sk \\(N\\)
 mode: OnDemand
+class: STANDARD
 capacity: null
+max_request_units: null
 gsi: null
 lsi: null
 stream: null
+encryption: AWS owned key
 count: 0
 size_bytes: 0
 created_at: .*",
@@ -113,43 +119,121 @@ async fn test_desc_all_tables() -> Result<(), Box<dyn std::error::Error>> {
     let cmd = c.args(["--region", "local", "desc", "--all-tables"]);
     cmd.assert().success().stdout(
         predicate::str::is_match(format!(
-            "name: {}
-region: local
-status: ACTIVE
-schema:
-  pk: pk \\(S\\)
-  sk: null
-mode: OnDemand
-capacity: null
-gsi: null
-lsi: null
-stream: null
-count: 0
-size_bytes: 0
-created_at: .*",
-            table_name1
+            "{}:
+  name: {}
+  region: local
+  status: ACTIVE
+  schema:
+    pk: pk \\(S\\)
+    sk: null
+  mode: OnDemand
+  class: STANDARD
+  capacity: null
+  max_request_units: null
+  gsi: null
+  lsi: null
+  stream: null
+  encryption: AWS owned key
+  count: 0
+  size_bytes: 0
+  created_at: .*",
+            table_name1, table_name1
         ))
         .unwrap()
         .and(
             predicate::str::is_match(format!(
-                "name: {}
-region: local
-status: ACTIVE
-schema:
-  pk: pk \\(S\\)
-  sk: sk \\(N\\)
-mode: OnDemand
-capacity: null
-gsi: null
-lsi: null
-stream: null
-count: 0
-size_bytes: 0
-created_at: .*",
-                table_name2
+                "{}:
+  name: {}
+  region: local
+  status: ACTIVE
+  schema:
+    pk: pk \\(S\\)
+    sk: sk \\(N\\)
+  mode: OnDemand
+  class: STANDARD
+  capacity: null
+  max_request_units: null
+  gsi: null
+  lsi: null
+  stream: null
+  encryption: AWS owned key
+  count: 0
+  size_bytes: 0
+  created_at: .*",
+                table_name2, table_name2
             ))
             .unwrap(),
         ),
     );
     Ok(())
 }
+
+#[tokio::test]
+async fn test_desc_index() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk,S", None).await?;
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "admin",
+            "create",
+            "index",
+            "--table",
+            &table_name,
+            "idx",
+            "--keys",
+            "gsi,N",
+        ])
+        .assert()
+        .success();
+
+    tm.command()?
+        .args(["--region", "local", "desc", &table_name, "--index", "idx"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                "^name: idx
+type: GSI
+status: [A-Z]+
+schema:
+  pk: gsi \\(N\\)
+  sk: null
+capacity: null
+projection: ALL
+",
+            )
+            .unwrap(),
+        );
+
+    tm.command()?
+        .args(["--region", "local", "desc", &table_name, "--index", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "No index named 'nope' is found in the table '{}'. Available indexes: idx",
+            table_name
+        )));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_desc_streams() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk,S", None).await?;
+
+    tm.command()?
+        .args(["--region", "local", "desc", &table_name, "--streams"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "enabled: false
+arn: null
+label: null
+view_type: null
+
+",
+        ));
+    Ok(())
+}
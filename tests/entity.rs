@@ -0,0 +1,162 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+pub mod util;
+
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+
+#[tokio::test]
+async fn test_entity_put_and_get() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", Some("sk")).await?;
+    let config = format!(
+        r#"entities:
+  user:
+    table: {}
+    pk: "USER#{{id}}"
+    sk: PROFILE
+"#,
+        table_name
+    );
+
+    let mut c = tm.command_with_envs(&config)?;
+    c.args([
+        "--region",
+        "local",
+        "entity",
+        "put",
+        "--item",
+        r#"{"name": "John"}"#,
+        "user",
+        "--id",
+        "42",
+    ])
+    .assert()
+    .success();
+
+    // The item is stored with the keys built from the templates.
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &table_name,
+            "get",
+            "USER#42",
+            "PROFILE",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""name": "John""#));
+
+    let mut c = tm.command_with_envs(&config)?;
+    c.args(["--region", "local", "entity", "get", "user", "--id=42"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""pk": "USER#42""#));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_entity_rejects_wrong_params() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let config = r#"entities:
+  user:
+    pk: "USER#{id}"
+"#;
+
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["--region", "local", "entity", "get", "user", "--idd", "42"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--idd is not a placeholder in the key templates of the entity. Available ones: --id",
+        ));
+
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["--region", "local", "entity", "get", "user"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--id is required by the key templates of the entity.",
+        ));
+
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["--region", "local", "entity", "get", "order", "--id", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Entity 'order' is not defined in `entities` of config.yml.",
+        ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_key_build_and_parse() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let config = r#"entities:
+  user:
+    pk: "USER#{id}"
+    sk: PROFILE
+  order:
+    pk: "USER#{user}"
+    sk: "ORDER#{id}"
+"#;
+
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["key", "build", "order", "42", "2024-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            r#"{
+  "pk": "USER#42",
+  "sk": "ORDER#2024-0001"
+}
+"#,
+        ));
+
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["key", "build", "order", "42"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The key templates of the entity take 2 value(s) for user, id, but 1 given.",
+        ));
+
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["key", "parse", "USER#42", "ORDER#2024-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""entity": "order""#))
+        .stdout(predicate::str::contains(r#""entity": "user""#).not());
+
+    // Only the partition key is matched without the sort key.
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["key", "parse", "USER#42", "--entity", "user"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""id": "42""#));
+
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["key", "parse", "ORDER#1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The key doesn't match key templates of any entity",
+        ));
+    Ok(())
+}
@@ -63,6 +63,33 @@ async fn test_export_empty_table() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_export_with_unreachable_metrics_endpoint() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let base_dir = tempdir()?;
+    let temp_path = base_dir.path().join(&table_name);
+
+    // Failures to send metrics are warned, and don't fail the export.
+    let mut c = tm.command()?;
+    let cmd = c
+        .env("OTEL_EXPORTER_OTLP_ENDPOINT", "http://127.0.0.1:1")
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &table_name,
+            "export",
+            "--output-file",
+            temp_path.to_str().unwrap(),
+        ]);
+    cmd.assert().success().stderr(predicate::str::contains(
+        "WARN: Failed to export metrics to http://127.0.0.1:1/v1/metrics",
+    ));
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_export_with_items() -> Result<(), Box<dyn std::error::Error>> {
     let mut tm = util::setup().await?;
@@ -116,3 +143,240 @@ async fn test_export_with_items() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_export_with_checkpoint() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [
+                util::TemporaryItem::new("abc", None, None),
+                util::TemporaryItem::new("def", None, None),
+            ],
+        )
+        .await?;
+
+    let base_dir = tempdir()?;
+    let output_path = base_dir.path().join("out.jsonl");
+    let checkpoint_path = base_dir.path().join("export.ckpt");
+
+    // The checkpoint file is removed when the export completes.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "export",
+        "--format",
+        "jsonl",
+        "--output-file",
+        output_path.to_str().unwrap(),
+        "--checkpoint",
+        checkpoint_path.to_str().unwrap(),
+    ]);
+    cmd.assert().success();
+    assert!(!checkpoint_path.exists());
+    assert_eq!(std::fs::read_to_string(&output_path)?.lines().count(), 2);
+
+    // A checkpoint of another export is rejected.
+    std::fs::write(
+        &checkpoint_path,
+        r#"{"table": "another-table", "output_file": "another.jsonl", "format": "jsonl", "attributes": null,
+            "exported": 1, "bytes": 16, "last_evaluated_key": {"pk": {"S": "abc"}}}"#,
+    )?;
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "export",
+        "--format",
+        "jsonl",
+        "--output-file",
+        output_path.to_str().unwrap(),
+        "--checkpoint",
+        checkpoint_path.to_str().unwrap(),
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "is for exporting the table 'another-table' into 'another.jsonl' as jsonl. Remove it or give another file.",
+    ));
+
+    // Stdout can't be truncated on resume.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "export",
+        "--output-file",
+        "-",
+        "--checkpoint",
+        checkpoint_path.to_str().unwrap(),
+    ]);
+    cmd.assert().failure().stdout(predicate::str::contains(
+        "--checkpoint is not available when exporting to stdout.",
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_consistent() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new("abc", None, None)],
+        )
+        .await?;
+
+    let base_dir = tempdir()?;
+    let output_path = base_dir.path().join("out.jsonl");
+
+    // --consistent exports the table into S3, so a bucket is required.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "export",
+        "--output-file",
+        output_path.to_str().unwrap(),
+        "--consistent",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--s3-bucket <BUCKET>"));
+
+    // DynamoDB Local doesn't support exports to S3, and the output file is not created.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "export",
+        "--output-file",
+        output_path.to_str().unwrap(),
+        "--consistent",
+        "--s3-bucket",
+        "my-exports",
+    ]);
+    cmd.assert().failure().stdout(predicate::str::contains(
+        "DynamoDB Local doesn't support exporting tables to S3.",
+    ));
+    assert!(!output_path.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_avro() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            Some("sk,N"),
+            [
+                util::TemporaryItem::new("abc", Some("1"), None),
+                util::TemporaryItem::new(
+                    "abc",
+                    Some("2"),
+                    Some(r#"{"a": 1, "b-c": {"d": [true, "e"]}}"#),
+                ),
+            ],
+        )
+        .await?;
+    let import_table_name = tm.create_temporary_table("pk", Some("sk,N")).await?;
+
+    let base_dir = tempdir()?;
+    let temp_path = base_dir.path().join(&table_name);
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "export",
+        "--format",
+        "avro",
+        "--output-file",
+        temp_path.to_str().unwrap(),
+    ]);
+    cmd.assert().success();
+
+    // Exported items are imported again as they were, including the nested attribute whose name isn't valid in Avro.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &import_table_name,
+        "import",
+        "--format",
+        "avro",
+        "--input-file",
+        temp_path.to_str().unwrap(),
+    ]);
+    cmd.assert().success();
+
+    util::assert_eq_json_ignore_order(
+        tm.command()?.args([
+            "--region",
+            "local",
+            "--table",
+            &import_table_name,
+            "scan",
+            "--output",
+            "json",
+        ]),
+        r#"[{"pk":"abc","sk":1},{"pk":"abc","sk":2,"a":1,"b-c":{"d":[true,"e"]}}]"#,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_xlsx() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new(
+                "abc",
+                None,
+                Some(r#"{"a": 1, "b": true}"#),
+            )],
+        )
+        .await?;
+
+    let base_dir = tempdir()?;
+    let temp_path = base_dir.path().join(&table_name);
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "export",
+        "--format",
+        "xlsx",
+        "--output-file",
+        temp_path.to_str().unwrap(),
+    ]);
+    cmd.assert().success();
+
+    // An xlsx file is a zip archive.
+    let export_content = std::fs::read(temp_path)?;
+    assert!(export_content.starts_with(b"PK"));
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+pub mod util;
+
+use crate::util::assert_eq_json_ignore_order;
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+use std::time::Duration;
+use tokio::time::sleep;
+
+#[tokio::test]
+async fn test_find_sk() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            Some("sk"),
+            [
+                util::TemporaryItem::new("a", Some("x"), Some(r#"{"name": "a-x"}"#)),
+                util::TemporaryItem::new("a", Some("y"), None),
+                util::TemporaryItem::new("b", Some("x"), None),
+                util::TemporaryItem::new("c", Some("z"), None),
+            ],
+        )
+        .await?;
+
+    // Without a GSI on the sort key, the whole table is scanned.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "find-sk",
+        "x",
+        "--output",
+        "raw",
+    ]);
+    cmd.assert().success().stderr(
+        predicate::str::contains("No GSI uses 'sk' as its partition key")
+            .and(predicate::str::contains("2 partitions in the table")),
+    );
+    assert_eq_json_ignore_order(
+        cmd,
+        r#"[
+            {"pk":{"S":"a"}, "sk":{"S":"x"}},
+            {"pk":{"S":"b"}, "sk":{"S":"x"}}
+        ]"#,
+    );
+
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "admin",
+            "create",
+            "index",
+            "--table",
+            &table_name,
+            "by-sk",
+            "--keys",
+            "sk,S",
+        ])
+        .assert()
+        .success();
+    sleep(Duration::from_secs(1)).await;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "find-sk",
+        "x",
+        "--output",
+        "raw",
+    ]);
+    cmd.assert().success().stderr(
+        predicate::str::contains("Querying the index 'by-sk'")
+            .and(predicate::str::contains("2 partitions in the table")),
+    );
+    assert_eq_json_ignore_order(
+        cmd,
+        r#"[
+            {"pk":{"S":"a"}, "sk":{"S":"x"}},
+            {"pk":{"S":"b"}, "sk":{"S":"x"}}
+        ]"#,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_find_sk_without_sort_key() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "--table", &table_name, "find-sk", "x"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("has no sort key"));
+
+    Ok(())
+}
@@ -18,6 +18,8 @@ pub mod util;
 
 use assert_cmd::prelude::*; // Add methods on commands
 use predicates::prelude::*; // Used for writing assertions
+use std::fs;
+use tempfile::tempdir;
 
 #[tokio::test]
 async fn test_get_non_existent_table() -> Result<(), Box<dyn std::error::Error>> {
@@ -174,3 +176,405 @@ async fn prepare_table_with_item<'a>(
 
     Ok(table_name)
 }
+
+#[tokio::test]
+async fn test_get_precise_numbers() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new(
+                "42",
+                None,
+                Some(r#"{"big": 12345678901234567890123456789012345678, "small": 1.5}"#),
+            )],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--precise-numbers",
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "42",
+    ]);
+    util::assert_eq_cmd_json(
+        cmd,
+        r#"{"pk": "42", "big": "12345678901234567890123456789012345678", "small": 1.5}"#,
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_field() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new(
+                "42",
+                None,
+                Some(r#"{"attrs": {"profile": {"email": "a@example.com"}, "tags": ["x", "y"]}, "age": 30}"#),
+            )],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "42",
+        "--field",
+        "attrs.profile.email",
+    ]);
+    cmd.assert().success().stdout("a@example.com\n");
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "42",
+        "--field",
+        "age",
+    ]);
+    cmd.assert().success().stdout("30\n");
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "42",
+        "--field",
+        "attrs.tags[1]",
+    ]);
+    cmd.assert().success().stdout("y\n");
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "42",
+        "--field",
+        "attrs.phone",
+    ]);
+    cmd.assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "The item doesn't have the field 'attrs.phone'.",
+        ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_replica_region() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new("42", None, Some(r#"{"age": 30}"#))],
+        )
+        .await?;
+
+    // The current region is read only once even if it's given to --replica-region.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "42",
+        "--replica-region",
+        "local",
+        "-o",
+        "jsonl",
+    ]);
+    cmd.assert().success().stdout(predicate::str::is_match(
+        r#"^# local: 1 item\(s\) in \d+ ms\n\{"pk":"42","age":30\}\n$"#,
+    )?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_multiple_items() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [
+                util::TemporaryItem::new("a", None, None),
+                util::TemporaryItem::new("b", None, None),
+                util::TemporaryItem::new("c", None, None),
+            ],
+        )
+        .await?;
+
+    // Items are printed in the order of the given keys.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "c",
+        "a",
+        "zz",
+        "-o",
+        "jsonl",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("{\"pk\":\"c\"}\n{\"pk\":\"a\"}\n"))
+        .stderr(predicate::str::contains(
+            r#"WARN: No item found for the key {"pk":"zz"}."#,
+        ));
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "a",
+        "--sort-keys",
+        "1,2",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--sort-keys is given, but table"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_keys_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [
+                util::TemporaryItem::new("a", None, None),
+                util::TemporaryItem::new("b", None, None),
+                util::TemporaryItem::new("c", None, None),
+            ],
+        )
+        .await?;
+    let base_dir = tempdir()?;
+    let keys_path = base_dir.path().join("keys.txt");
+    fs::write(&keys_path, "c\n\n# not found\nzz\na\n")?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "--keys-file",
+        keys_path.to_str().unwrap(),
+        "-o",
+        "jsonl",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("{\"pk\":\"c\"}\n{\"pk\":\"a\"}\n"))
+        .stderr(predicate::str::contains(
+            r#"WARN: No item found for the key {"pk":"zz"}."#,
+        ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_sort_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            Some("sk,N"),
+            [
+                util::TemporaryItem::new("u1", Some("1"), None),
+                util::TemporaryItem::new("u1", Some("2"), None),
+                util::TemporaryItem::new("u1", Some("3"), None),
+            ],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "u1",
+        "--sort-keys",
+        "3,1",
+        "-o",
+        "jsonl",
+    ]);
+    cmd.assert().success().stdout(predicate::str::diff(
+        "{\"pk\":\"u1\",\"sk\":3}\n{\"pk\":\"u1\",\"sk\":1}\n",
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_humanize_without_ttl() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new(
+                "a",
+                None,
+                Some(r#"{"expires_at": 1600000000}"#),
+            )],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "a",
+        "--humanize",
+        "-o",
+        "jsonl",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "{\"pk\":\"a\",\"expires_at\":1600000000}\n",
+        ))
+        .stderr(predicate::str::contains(format!(
+            "NOTE: TTL is not enabled on the table '{}'.",
+            table_name
+        )));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_humanize_epochs() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new(
+                "a",
+                None,
+                Some(r#"{"count": 42, "created_at": 1600000000123, "updated_at": 1600000000}"#),
+            )],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "--humanize-epochs",
+        "get",
+        "a",
+        "-o",
+        "jsonl",
+    ]);
+    cmd.assert().success().stdout(predicate::str::diff(
+        "{\"pk\":\"a\",\"count\":42,\"created_at\":\"1600000000123 (2020-09-13T12:26:40.123Z)\",\"updated_at\":\"1600000000 (2020-09-13T12:26:40Z)\"}\n",
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_redact() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new(
+                "a",
+                None,
+                Some(r#"{"password": "hunter2", "profile": {"ssn": "123-45-6789", "name": "alice"}}"#),
+            )],
+        )
+        .await?;
+    tm.command()?
+        .args(["config", "set", "display.redact", "password,ssn"])
+        .assert()
+        .success();
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "a",
+        "-o",
+        "jsonl",
+    ]);
+    cmd.assert().success().stdout(predicate::str::diff(
+        "{\"pk\":\"a\",\"password\":\"<redacted>\",\"profile\":{\"name\":\"alice\",\"ssn\":\"<redacted>\"}}\n",
+    ));
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "get",
+        "a",
+        "--field",
+        "password",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("<redacted>\n"));
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "--no-redact",
+        "get",
+        "a",
+        "--field",
+        "password",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("hunter2\n"));
+    Ok(())
+}
@@ -0,0 +1,97 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+pub mod util;
+
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+
+const HISTORY_CONFIG: &str = "history:
+  enabled: true
+  max_entries: 2
+";
+
+#[tokio::test]
+async fn test_history() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command_with_envs(HISTORY_CONFIG)?;
+    let config_dir = c
+        .get_envs()
+        .find(|(k, _)| *k == "DYNEIN_CONFIG_DIR")
+        .and_then(|(_, v)| v)
+        .unwrap()
+        .to_owned();
+    for pk in ["a", "b", "c"] {
+        c = tm.command()?;
+        c.env("DYNEIN_CONFIG_DIR", &config_dir)
+            .args(["--region", "local", "--table", &table_name, "put", pk])
+            .assert()
+            .success();
+    }
+    // Read-only commands are not recorded.
+    c = tm.command()?;
+    c.env("DYNEIN_CONFIG_DIR", &config_dir)
+        .args(["--region", "local", "--table", &table_name, "get", "a"])
+        .assert()
+        .success();
+
+    // Only the latest 2 commands are kept.
+    c = tm.command()?;
+    c.env("DYNEIN_CONFIG_DIR", &config_dir)
+        .args(["history", "list"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(format!(r"\n2 +\S+ +put +local +{}\n", table_name))?
+                .and(predicate::str::is_match(format!(
+                    r"\n3 +\S+ +put +local +{}\n",
+                    table_name
+                ))?)
+                .and(predicate::str::contains("get").not())
+                .and(predicate::str::is_match(r"\n1 ")?.not()),
+        );
+
+    c = tm.command()?;
+    c.env("DYNEIN_CONFIG_DIR", &config_dir)
+        .args(["history", "show", "3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""pval": "c""#));
+
+    c = tm.command()?;
+    c.env("DYNEIN_CONFIG_DIR", &config_dir)
+        .args(["history", "show", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No history entry with ID 1."));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_history_disabled() -> Result<(), Box<dyn std::error::Error>> {
+    let tm = util::setup().await?;
+
+    let mut c = tm.command()?;
+    c.args(["history", "list"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("History is not enabled."));
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+pub mod util;
+
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+
+#[tokio::test]
+async fn test_before_hook_blocks_command() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command_with_envs(
+        "hooks:
+  - when: before
+    commands: [put]
+    run: exit 3
+",
+    )?;
+    c.args(["--region", "local", "--table", &table_name, "put", "a"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The before hook 'exit 3' exited with",
+        ));
+
+    // The item has not been put.
+    tm.command()?
+        .args(["--region", "local", "--table", &table_name, "get", "a"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("No item found.\n"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_after_hook() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let config = "hooks:
+  - when: after
+    run: echo after-hook-ran
+  - when: before
+    commands: [del]
+    run: exit 1
+";
+    // Output of hooks goes to stderr, and read-only commands don't run hooks.
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["--region", "local", "--table", &table_name, "put", "a"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("after-hook-ran").not())
+        .stderr(predicate::str::contains("after-hook-ran"));
+
+    let mut c = tm.command_with_envs(config)?;
+    c.args(["--region", "local", "--table", &table_name, "get", "a"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("after-hook-ran").not());
+    Ok(())
+}
@@ -16,7 +16,7 @@
 
 pub mod util;
 
-use crate::util::assert_eq_cmd_json;
+use crate::util::{assert_eq_cmd_json, assert_eq_json_ignore_order};
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
 use std::fs;
@@ -230,3 +230,229 @@ async fn test_import_jsonl_with_set_inference() -> Result<(), Box<dyn std::error
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_import_jsonl_with_merge_mode() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new(
+                "pk1",
+                None,
+                Some(r#"{"a": 1, "b": 2}"#),
+            )],
+        )
+        .await?;
+    let base_dir = tempdir()?;
+    let temp_path = base_dir.path().join(&tbl);
+
+    // Write the JSONL to a file. Attribute "b" of pk1 is absent.
+    let jsonl_contents = r#"{"pk":"pk1","a":10,"c":3}
+{"pk":"pk2","a":20}"#;
+    fs::write(&temp_path, jsonl_contents)?;
+
+    tm.command()?
+        .args([
+            "-r",
+            "local",
+            "import",
+            "-t",
+            &tbl,
+            "-f",
+            "jsonl",
+            "-i",
+            temp_path.to_str().unwrap(),
+            "--update-mode",
+            "merge",
+        ])
+        .assert()
+        .success();
+
+    // Existing attributes which don't appear in the file should be kept.
+    assert_eq_cmd_json(
+        tm.command()?
+            .args(["-r", "local", "get", "-t", &tbl, "pk1"]),
+        r#"{"pk":"pk1","a":10,"b":2,"c":3}"#,
+    );
+    assert_eq_cmd_json(
+        tm.command()?
+            .args(["-r", "local", "get", "-t", &tbl, "pk2"]),
+        r#"{"pk":"pk2","a":20}"#,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_json_multiple_tables() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl1 = tm.create_temporary_table("pk", None).await?;
+    let tbl2 = tm.create_temporary_table("pk", Some("sk,N")).await?;
+    let base_dir = tempdir()?;
+    let temp_path = base_dir.path().join(&tbl1);
+
+    // Write JSON which maps table names to items
+    let contents = format!(
+        r#"{{
+        "{}": [{{"pk":"pk1","a":1}}, {{"pk":"pk2"}}],
+        "{}": [{{"pk":"pk1","sk":1,"b":2}}]
+      }}"#,
+        tbl1, tbl2
+    );
+    fs::write(&temp_path, contents)?;
+
+    tm.command()?
+        .args([
+            "-r",
+            "local",
+            "import",
+            "-f",
+            "json",
+            "-i",
+            temp_path.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    assert_eq_cmd_json(
+        tm.command()?
+            .args(["-r", "local", "get", "-t", &tbl1, "pk1"]),
+        r#"{"pk":"pk1","a":1}"#,
+    );
+    assert_eq_cmd_json(
+        tm.command()?
+            .args(["-r", "local", "get", "-t", &tbl1, "pk2"]),
+        r#"{"pk":"pk2"}"#,
+    );
+    assert_eq_cmd_json(
+        tm.command()?
+            .args(["-r", "local", "get", "-t", &tbl2, "pk1", "1"]),
+        r#"{"pk":"pk1","sk":1,"b":2}"#,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_jsonl_with_transform() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm.create_temporary_table("pk", None).await?;
+    let base_dir = tempdir()?;
+    let temp_path = base_dir.path().join(&tbl);
+
+    let jsonl_contents = r#"{"pk":"pk1","name":"alice","age":"20","tmp":true}
+{"pk":"pk2","name":"bob","age":"15","tmp":true}"#;
+    fs::write(&temp_path, jsonl_contents)?;
+
+    tm.command()?
+        .args([
+            "-r",
+            "local",
+            "import",
+            "-t",
+            &tbl,
+            "-f",
+            "jsonl",
+            "-i",
+            temp_path.to_str().unwrap(),
+            "--transform",
+            ".user_name = .name | del(.name) | del(.tmp) | .age |= tonumber | select(.age >= 18)",
+        ])
+        .assert()
+        .success();
+
+    assert_eq_json_ignore_order(
+        tm.command()?
+            .args(["-r", "local", "scan", "-t", &tbl, "--output", "json"]),
+        r#"[{"pk":"pk1","user_name":"alice","age":20}]"#,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_jsonl_with_failed_rows() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm.create_temporary_table("pk", None).await?;
+    let base_dir = tempdir()?;
+    let temp_path = base_dir.path().join(&tbl);
+
+    let jsonl_contents = r#"{"pk":"pk1"}
+{"pk" 2}
+{"pk":"pk3"}"#;
+    fs::write(&temp_path, jsonl_contents)?;
+
+    // The import continues with --on-error skip, but exits with non-zero status as a row has failed.
+    tm.command()?
+        .args([
+            "-r",
+            "local",
+            "import",
+            "-t",
+            &tbl,
+            "-f",
+            "jsonl",
+            "-i",
+            temp_path.to_str().unwrap(),
+            "--on-error",
+            "skip",
+        ])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Batches sent: 1, items written: 2, items failed: 1, retries: 0",
+        ))
+        .stderr(predicate::str::contains("WARN: skipped row 2"));
+
+    assert_eq_json_ignore_order(
+        tm.command()?
+            .args(["-r", "local", "scan", "-t", &tbl, "--output", "json"]),
+        r#"[{"pk":"pk1"},{"pk":"pk3"}]"#,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_sql() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm.create_temporary_table("id", None).await?;
+    let base_dir = tempdir()?;
+    let temp_path = base_dir.path().join(&tbl);
+
+    let sql_contents = r#"
+CREATE TABLE `users` (
+  `id` varchar(16) NOT NULL,
+  `age` int DEFAULT NULL,
+  `active` tinyint(1) NOT NULL,
+  PRIMARY KEY (`id`)
+);
+INSERT INTO `users` VALUES ('u1','20',1),('u2',NULL,0);
+"#;
+    fs::write(&temp_path, sql_contents)?;
+
+    tm.command()?
+        .args([
+            "-r",
+            "local",
+            "import",
+            "-t",
+            &tbl,
+            "-f",
+            "sql",
+            "-i",
+            temp_path.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    assert_eq_json_ignore_order(
+        tm.command()?
+            .args(["-r", "local", "scan", "-t", &tbl, "--output", "json"]),
+        r#"[{"id":"u1","age":20,"active":true},{"id":"u2","age":null,"active":false}]"#,
+    );
+
+    Ok(())
+}
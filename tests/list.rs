@@ -71,3 +71,48 @@ async fn test_list_table_ls_alias() -> Result<(), Box<dyn std::error::Error>> {
         .stdout(predicate::str::contains(format!("* {table_name}")));
     Ok(())
 }
+
+#[tokio::test]
+async fn test_list_table_with_detail() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup_with_lock().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "use", &table_name]);
+    cmd.assert().success();
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "ls", "--detail", "--sort-by", "items"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("DynamoDB tables in region: local"))
+        .stdout(predicate::str::is_match("name +items +size_bytes +mode +created_at\n").unwrap())
+        .stdout(predicate::str::is_match(format!("\\* {table_name} +0 +0 +")).unwrap());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_list_table_sort_by_requires_detail() -> Result<(), Box<dyn std::error::Error>> {
+    let tm = util::setup_with_lock().await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "ls", "--sort-by", "size"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--detail"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_list_table_with_regions() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup_with_lock().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "ls", "--regions", "local"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("DynamoDB tables in region: local"))
+        .stdout(predicate::str::contains(table_name));
+    Ok(())
+}
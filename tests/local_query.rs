@@ -0,0 +1,90 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+pub mod util;
+
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn test_local_query() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            Some("sk,N"),
+            vec![
+                util::TemporaryItem::new("a", Some("1"), Some("{\"age\": 15}")),
+                util::TemporaryItem::new("a", Some("2"), Some("{\"age\": 25}")),
+                util::TemporaryItem::new(
+                    "b",
+                    Some("1"),
+                    Some("{\"age\": 35, \"email\": \"b@example.com\"}"),
+                ),
+            ],
+        )
+        .await?;
+
+    let tmpdir = tempdir()?;
+    let export_file = tmpdir.path().join("export.jsonl");
+    let export_file = export_file.to_str().unwrap();
+    let mut c = tm.command()?;
+    c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "export",
+        "--output-file",
+        export_file,
+        "--format",
+        "jsonl",
+    ])
+    .assert()
+    .success();
+
+    // No region or table is needed to query the exported file.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "local-query",
+        "--file",
+        export_file,
+        "--keys",
+        "pk,sk",
+        "--filter",
+        "age >= 20 AND NOT attribute_exists(email)",
+        "--output",
+        "jsonl",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout("{\"pk\":\"a\",\"sk\":2,\"age\":25}\n");
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["local-query", "--file", export_file, "--filter", "age >"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--filter syntax is invalid"));
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["local-query", "--file", export_file]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Specify them by --keys option"));
+
+    Ok(())
+}
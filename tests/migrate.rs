@@ -0,0 +1,188 @@
+/*
+ * Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License").
+ * You may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+pub mod util;
+
+use crate::util::assert_eq_cmd_json;
+use assert_cmd::prelude::*; // Add methods on commands
+use predicates::prelude::*; // Used for writing assertions
+
+#[tokio::test]
+async fn test_migrate_rename_attr() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [
+                util::TemporaryItem::new("a", None, Some(r#"{"mail": "a@example.com"}"#)),
+                util::TemporaryItem::new("b", None, Some(r#"{"mail": "b@example.com"}"#)),
+                util::TemporaryItem::new("c", None, None),
+            ],
+        )
+        .await?;
+    let dir = tempfile::tempdir()?;
+    let checkpoint = dir.path().join("checkpoint.json");
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "migrate",
+        "rename-attr",
+        "mail",
+        "email",
+        "--concurrency",
+        "1",
+        "--checkpoint",
+        checkpoint.to_str().unwrap(),
+    ]);
+    cmd.assert().success().stdout(
+        predicate::str::contains("Renamed 'mail' to 'email' in 2 items")
+            .and(predicate::str::contains("Verified that no item has 'mail'")),
+    );
+    assert!(!checkpoint.exists());
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "--table", &table_name, "get", "a"]);
+    assert_eq_cmd_json(cmd, r#"{"pk": "a", "email": "a@example.com"}"#);
+
+    // An item which already has the new attribute is left as it is, and the verification fails.
+    let mut c = tm.command()?;
+    c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "put",
+        "d",
+        "--item",
+        r#"{"mail": "old@example.com", "email": "new@example.com"}"#,
+    ])
+    .assert()
+    .success();
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "migrate",
+        "rename-attr",
+        "mail",
+        "email",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("1 items still have 'mail'"));
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "--table", &table_name, "get", "d"]);
+    assert_eq_cmd_json(
+        cmd,
+        r#"{"pk": "d", "mail": "old@example.com", "email": "new@example.com"}"#,
+    );
+
+    // Primary keys cannot be renamed.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "migrate",
+        "rename-attr",
+        "pk",
+        "id",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is a primary key attribute"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_migrate_convert_type() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [
+                util::TemporaryItem::new("a", None, Some(r#"{"price": "120"}"#)),
+                util::TemporaryItem::new("b", None, Some(r#"{"price": 80}"#)),
+                util::TemporaryItem::new("c", None, Some(r#"{"price": "free"}"#)),
+            ],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "migrate",
+        "convert-type",
+        "--attr",
+        "price",
+        "--to",
+        "N",
+    ]);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("in 1 items"))
+        .stderr(
+            predicate::str::contains(r#"{"pk":"c"} "free" is not a number."#).and(
+                predicate::str::contains("1 values of 'price' couldn't be converted"),
+            ),
+        );
+
+    for (pk, expected) in [
+        ("a", r#"{"pk": "a", "price": 120}"#),
+        ("b", r#"{"pk": "b", "price": 80}"#),
+        ("c", r#"{"pk": "c", "price": "free"}"#),
+    ] {
+        let mut c = tm.command()?;
+        let cmd = c.args(["--region", "local", "--table", &table_name, "get", pk]);
+        assert_eq_cmd_json(cmd, expected);
+    }
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "migrate",
+        "convert-type",
+        "--attr",
+        "price",
+        "--to",
+        "S",
+    ]);
+    cmd.assert().success();
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "--table", &table_name, "get", "b"]);
+    assert_eq_cmd_json(cmd, r#"{"pk": "b", "price": "80"}"#);
+
+    Ok(())
+}
@@ -485,3 +485,169 @@ async fn test_multiple_put_same_pk_different_sk() -> Result<(), Box<dyn std::err
     util::assert_eq_json_ignore_order(get_cmd, &expected);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_put_if_not_exists() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            Some("sk"),
+            [util::TemporaryItem::new(
+                "42",
+                Some("1"),
+                Some(r#"{"x": 1}"#),
+            )],
+        )
+        .await?;
+
+    // An existing item is not replaced.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "put",
+        "42",
+        "1",
+        "--if-not-exists",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+
+    // An item with another sort key is put.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "put",
+        "42",
+        "2",
+        "--if-not-exists",
+    ]);
+    cmd.assert().success();
+
+    let mut c = tm.command()?;
+    let get_cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "scan",
+        "-o",
+        "json",
+    ]);
+    let expected = r#"[{"pk": "42", "sk": "1", "x": 1}, {"pk": "42", "sk": "2"}]"#;
+    util::assert_eq_cmd_json(get_cmd, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_put_time_literals() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "put",
+        "42",
+        "--item",
+        r#"{"expires": epoch("2025-01-01T00:00:00Z")}"#,
+    ]);
+    cmd.assert().success();
+
+    let mut c = tm.command()?;
+    let get_cmd = c.args(["--region", "local", "--table", &table_name, "get", "42"]);
+    util::assert_eq_cmd_json(get_cmd, r#"{"pk": "42", "expires": 1735689600}"#);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_put_id_literals() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "put",
+        "42",
+        "--item",
+        r#"{"uuid": uuid(), "ulid": ulid()}"#,
+    ]);
+    cmd.assert().success();
+
+    let mut c = tm.command()?;
+    let output = c
+        .args(["--region", "local", "--table", &table_name, "get", "42"])
+        .output()?;
+    let item: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(item["uuid"].as_str().map(str::len), Some(36));
+    assert_eq!(item["ulid"].as_str().map(str::len), Some(26));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_put_invalid_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", Some("sk,N")).await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "put",
+        "a",
+        "1",
+        "--item",
+        r#"{"sk": "one"}"#,
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "type mismatch for the key sk, expected: Number",
+    ));
+
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "--table", &table_name, "put", "", "1"]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "the key pk must not be an empty string",
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_put_too_large_item() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm.create_temporary_table("pk", None).await?;
+    let item = format!(r#"{{"body": "{}"}}"#, "x".repeat(410 * 1024));
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "put",
+        "a",
+        "--item",
+        &item,
+    ]);
+    cmd.assert().failure().stderr(
+        predicate::str::contains("exceeds the limit of 409600 bytes").and(
+            predicate::str::contains("Largest attributes: body (419844 bytes)"),
+        ),
+    );
+    Ok(())
+}
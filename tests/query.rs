@@ -275,6 +275,71 @@ async fn test_query_for_index() -> Result<(), Box<dyn std::error::Error>> {
         .assert()
         .success();
 
+    // GSIs don't support strongly consistent reads, so --consistent-read is dropped with a warning.
+    let mut query_cmd = tm.command()?;
+    query_cmd
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &table_name,
+            "query",
+            "1",
+            "--index",
+            "gsi",
+            "--consistent-read",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "WARN: GSI 'gsi' doesn't support strongly consistent reads.",
+        ));
+
+    // It's an error in the strict mode.
+    let mut query_cmd = tm.command()?;
+    query_cmd
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &table_name,
+            "query",
+            "1",
+            "--index",
+            "gsi",
+            "--consistent-read",
+            "--strict",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Remove --consistent-read, or read the table or an LSI instead.",
+        ));
+
+    // consistent_read in table_defaults is silently dropped on GSIs, even in the strict mode.
+    let config = format!(
+        "table_defaults:
+  {table_name}:
+    consistent_read: true
+"
+    );
+    let mut query_cmd = tm.command_with_envs(config)?;
+    query_cmd
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &table_name,
+            "query",
+            "1",
+            "--index",
+            "gsi",
+            "--strict",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("WARN").not());
+
     Ok(())
 }
 
@@ -672,3 +737,42 @@ query:
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_query_output_yaml() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            Some("sk,N"),
+            [
+                util::TemporaryItem::new("abc", Some("1"), Some("{'opt':'A'}")),
+                util::TemporaryItem::new("abc", Some("2"), None),
+            ],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "query",
+        "abc",
+        "-o",
+        "yaml",
+    ]);
+    util::assert_eq_cmd_yaml(
+        cmd,
+        r#"
+- pk: abc
+  sk: 1
+  opt: A
+- pk: abc
+  sk: 2
+"#,
+    );
+
+    Ok(())
+}
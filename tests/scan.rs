@@ -227,3 +227,177 @@ async fn test_scan_with_limits() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_scan_output_yaml() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk,S",
+            None,
+            [TemporaryItem::new(
+                "1",
+                None,
+                Some("{'opt':'A','flag':true}"),
+            )],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "scan",
+        "-o",
+        "yaml",
+    ]);
+    util::assert_eq_cmd_yaml(
+        cmd,
+        r#"
+- pk: "1"
+  flag: true
+  opt: A
+"#,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scan_with_summary() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk,S",
+            None,
+            [
+                TemporaryItem::new("1", None, None),
+                TemporaryItem::new("2", None, None),
+            ],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "scan",
+        "--summary",
+        "-o",
+        "jsonl",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Summary").not())
+        .stderr(predicate::str::contains(
+            "Summary: 2 item(s) returned, 2 scanned, 1 page(s) fetched",
+        ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scan_sort_by_and_group_by() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk,S",
+            None,
+            [
+                TemporaryItem::new("a", None, Some("{'price':10,'status':'open'}")),
+                TemporaryItem::new("b", None, Some("{'price':9,'status':'closed'}")),
+                TemporaryItem::new("c", None, Some("{'price':100,'status':'open'}")),
+            ],
+        )
+        .await?;
+
+    // Numbers are sorted by value, not as strings.
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "scan",
+        "--sort-by",
+        "price:desc",
+        "--keys-only",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("pk\nc\na\nb\n"));
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "scan",
+        "--group-by",
+        "status",
+    ]);
+    cmd.assert().success().stdout(predicate::str::diff(
+        "status  count\nclosed  1\nopen    2\n",
+    ));
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "scan",
+        "--group-by",
+        "status",
+        "-o",
+        "csv",
+    ]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "--group-by doesn't support the output format 'csv'",
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scan_distinct() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk,S",
+            None,
+            [
+                TemporaryItem::new("a", None, Some("{'status':'open'}")),
+                TemporaryItem::new("b", None, Some("{'status':'closed'}")),
+                TemporaryItem::new("c", None, Some("{'status':'open'}")),
+                TemporaryItem::new("d", None, None),
+            ],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let cmd = c.args([
+        "--region",
+        "local",
+        "--table",
+        &table_name,
+        "scan",
+        "--distinct",
+        "status",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "status  count\nopen    2\nclosed  1\n        1\n",
+        ))
+        .stderr(predicate::str::contains(
+            "2 distinct value(s) of 'status' in 4 item(s).",
+        ));
+
+    Ok(())
+}
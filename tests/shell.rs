@@ -47,3 +47,115 @@ async fn test_shell_mode() -> Result<(), Box<dyn std::error::Error>> {
 
     tm.cleanup(vec![table_name])
 }
+
+#[tokio::test]
+async fn test_shell_mode_multi_line_and_source() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::{Seek, SeekFrom};
+
+    let table_name = "table--test_shell_mode_multi_line_and_source";
+    let tm = util::setup().await?;
+
+    let mut script = Builder::new().suffix(".dy").tempfile()?;
+    writeln!(script, "# commands in a script file")?;
+    writeln!(script, "get 2")?;
+    let script_path = script.path().to_str().unwrap().to_owned();
+
+    let mut c = tm.command()?;
+    let shell_session = c.args(["--region", "local", "--shell"]);
+    let mut tmpfile = Builder::new().tempfile()?.into_file();
+    writeln!(tmpfile, "admin create table {} --keys pk", table_name)?;
+    writeln!(tmpfile, "use {}", table_name)?;
+    writeln!(tmpfile)?;
+    // A quoted JSON literal spanning lines.
+    writeln!(tmpfile, "put 1 --item '{{")?;
+    writeln!(tmpfile, "  \"name\": \"alice\"")?;
+    writeln!(tmpfile, "}}'")?;
+    // A trailing backslash and unclosed braces.
+    writeln!(tmpfile, "put 2 \\")?;
+    writeln!(tmpfile, "  --item {{\"name\":")?;
+    writeln!(tmpfile, "    \"bob\"}}")?;
+    writeln!(tmpfile, "source {}", script_path)?;
+    writeln!(tmpfile, "get 1")?;
+    tmpfile.seek(SeekFrom::Start(0))?;
+    shell_session.stdin(tmpfile).assert().success().stdout(
+        predicate::str::contains("\"name\": \"bob\"")
+            .and(predicate::str::contains("\"name\": \"alice\"")),
+    );
+
+    tm.cleanup(vec![table_name])
+}
+
+#[tokio::test]
+async fn test_shell_mode_builtins() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            vec![util::TemporaryItem::new("a", None, Some("{\"age\": 15}"))],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let shell_session = c.args(["--region", "local", "--shell"]);
+    let mut tmpfile = Builder::new().tempfile()?.into_file();
+    writeln!(tmpfile, "use {}", table_name)?;
+    writeln!(tmpfile, "output jsonl")?;
+    writeln!(tmpfile, "status")?;
+    writeln!(tmpfile, "scan")?;
+    writeln!(tmpfile, "output unknown")?;
+    tmpfile.seek(SeekFrom::Start(0))?;
+    shell_session
+        .stdin(tmpfile)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains(format!(
+                "table: {}\nregion: local\noutput: jsonl\n",
+                table_name
+            ))
+            .and(predicate::str::contains("{\"pk\":\"a\",\"age\":15}\n")),
+        )
+        .stderr(predicate::str::contains("usage: output <FORMAT>"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_commands_option() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let table_name = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            vec![util::TemporaryItem::new("a", None, Some("{\"age\": 15}"))],
+        )
+        .await?;
+
+    let mut c = tm.command()?;
+    let use_table = format!("use {}", table_name);
+    let cmd = c.args([
+        "--region",
+        "local",
+        "-c",
+        &use_table,
+        "-c",
+        "output jsonl",
+        "--command",
+        "get a",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::ends_with("{\"pk\":\"a\",\"age\":15}\n"));
+
+    // dynein stops at the first command which fails to parse.
+    let mut c = tm.command()?;
+    let cmd = c.args(["--region", "local", "-c", "get", "-c", &use_table]);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("Now you're using").not());
+
+    Ok(())
+}
@@ -294,3 +294,218 @@ async fn test_upd_atomic_counter() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_upd_atomic_counter_multiple_targets() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new(
+                "pk1",
+                None,
+                Some(r#"{"pv": 1, "stats": {"views": 10}, "daily": [5]}"#),
+            )],
+        )
+        .await?;
+
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &tbl,
+            "upd",
+            "pk1",
+            "--atomic-counter",
+            "pv,stats.views,daily[0],likes",
+        ])
+        .assert()
+        .success();
+
+    let mut cmd = tm.command()?;
+    cmd.args(["--region", "local", "--table", &tbl, "get", "pk1"]);
+    assert_eq_cmd_json(
+        &mut cmd,
+        r#"{"pk":"pk1","pv":2,"stats":{"views":11},"daily":[6],"likes":1}"#,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upd_atomic_counter_with_step_and_init() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm.create_temporary_table("pk", None).await?;
+
+    // The counter doesn't exist yet, so it's initialized with --counter-init.
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &tbl,
+            "upd",
+            "pk1",
+            "--atomic-counter",
+            "stock",
+            "--counter-init",
+            "10",
+            "--by",
+            "-3",
+        ])
+        .assert()
+        .success();
+
+    let mut cmd = tm.command()?;
+    cmd.args(["--region", "local", "--table", &tbl, "get", "pk1"]);
+    assert_eq_cmd_json(&mut cmd, r#"{"pk":"pk1","stock":7}"#);
+
+    // --counter-init is ignored once the counter exists.
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &tbl,
+            "upd",
+            "pk1",
+            "--atomic-counter",
+            "stock",
+            "--counter-init",
+            "10",
+            "--by",
+            "5",
+        ])
+        .assert()
+        .success();
+
+    let mut cmd = tm.command()?;
+    cmd.args(["--region", "local", "--table", &tbl, "get", "pk1"]);
+    assert_eq_cmd_json(&mut cmd, r#"{"pk":"pk1","stock":12}"#);
+
+    // --by requires a number.
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &tbl,
+            "upd",
+            "pk1",
+            "--atomic-counter",
+            "stock",
+            "--by",
+            "abc",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("should be a number"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upd_where() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tm = util::setup().await?;
+    let tbl = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [
+                util::TemporaryItem::new("a", None, Some(r#"{"createdAt": 1500000000}"#)),
+                util::TemporaryItem::new("b", None, Some(r#"{"createdAt": 1550000000}"#)),
+                util::TemporaryItem::new("c", None, Some(r#"{"createdAt": 1650000000}"#)),
+            ],
+        )
+        .await?;
+
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &tbl,
+            "upd",
+            "--set",
+            "status = \"archived\"",
+            "--where",
+            "createdAt < 1600000000",
+            "--dry-run",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 items in the table"));
+
+    let mut cmd = tm.command()?;
+    cmd.args(["--region", "local", "--table", &tbl, "get", "a"]);
+    assert_eq_cmd_json(&mut cmd, r#"{"pk":"a","createdAt":1500000000}"#);
+
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &tbl,
+            "upd",
+            "--set",
+            "status = \"archived\"",
+            "--where",
+            "createdAt < 1600000000",
+            "--concurrency",
+            "2",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Successfully updated 2 items"));
+
+    for (pk, expected) in [
+        (
+            "a",
+            r#"{"pk":"a","createdAt":1500000000,"status":"archived"}"#,
+        ),
+        (
+            "b",
+            r#"{"pk":"b","createdAt":1550000000,"status":"archived"}"#,
+        ),
+        ("c", r#"{"pk":"c","createdAt":1650000000}"#),
+    ] {
+        let mut cmd = tm.command()?;
+        cmd.args(["--region", "local", "--table", &tbl, "get", pk]);
+        assert_eq_cmd_json(&mut cmd, expected);
+    }
+
+    // PVAL narrows down target items to the partition.
+    tm.command()?
+        .args([
+            "--region",
+            "local",
+            "--table",
+            &tbl,
+            "upd",
+            "c",
+            "--remove",
+            "createdAt",
+            "--where",
+            "attribute_exists(createdAt)",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Successfully updated 1 items"));
+
+    let mut cmd = tm.command()?;
+    cmd.args(["--region", "local", "--table", &tbl, "get", "c"]);
+    assert_eq_cmd_json(&mut cmd, r#"{"pk":"c"}"#);
+
+    // --where can't be combined with SVAL.
+    tm.command()?
+        .args([
+            "--region", "local", "--table", &tbl, "upd", "a", "x", "--set", "n = 1", "--where",
+            "n = 0",
+        ])
+        .assert()
+        .failure();
+
+    Ok(())
+}
@@ -115,6 +115,40 @@ async fn test_use() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_use_with_port() -> Result<(), Box<dyn std::error::Error>> {
+    const PORT: i32 = 8001;
+    let mut tm = util::setup_with_port(PORT).await?;
+
+    let tbl = tm
+        .create_temporary_table_with_items(
+            "pk",
+            None,
+            [util::TemporaryItem::new("pk1", None, None)],
+        )
+        .await?;
+    tm.command()?
+        .args(["-r", "local", "-p", &PORT.to_string(), "use", &tbl])
+        .assert()
+        .success();
+
+    let mut config_path = tm.default_config_dir();
+    config_path.push("config.yml");
+    let config_contents = std::fs::read_to_string(&config_path)?;
+    assert!(config_contents.contains(&format!("using_port: {}", PORT)));
+
+    // The port saved in the config file is used without --port option.
+    assert_eq_cmd_json(tm.command()?.args(["get", "pk1"]), r#"{"pk":"pk1"}"#);
+
+    // --port option takes precedence over the config file. The table only exists on DynamoDB Local listening on PORT.
+    tm.command()?
+        .args(["--port", "8000", "get", "pk1"])
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_use_switch() -> Result<(), Box<dyn std::error::Error>> {
     let mut tm = util::setup().await?;